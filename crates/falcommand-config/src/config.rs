@@ -1,7 +1,11 @@
-use std::path::{Path, PathBuf};
+use std::path::Path;
 use std::collections::HashMap;
+use std::ops::RangeInclusive;
 use serde::{Deserialize, Serialize};
-use log::info;
+use log::{info, warn};
+
+use crate::paths::PathResolver;
+use crate::types::Action;
 
 #[derive(Debug, thiserror::Error)]
 pub enum ConfigError {
@@ -18,6 +22,27 @@ pub enum ConfigError {
     FileSystemError(String),
 }
 
+/// `appearance.transparency` の許容範囲（validate とスキーマで共有）
+pub const TRANSPARENCY_RANGE: RangeInclusive<f32> = 0.0..=1.0;
+
+/// `search.fuzzy_threshold` の許容範囲
+pub const FUZZY_THRESHOLD_RANGE: RangeInclusive<f64> = 0.0..=1.0;
+
+/// `behavior.max_results` の許容範囲
+pub const MAX_RESULTS_RANGE: RangeInclusive<usize> = 1..=100;
+
+/// `ranking` の各重みの許容範囲
+pub const RANKING_WEIGHT_RANGE: RangeInclusive<f64> = 0.0..=1.0;
+
+/// `ranking.context_boosts` の各ボーナスの許容範囲。完全一致の結果を追い越さない程度に小さく抑える
+pub const CONTEXT_BOOST_RANGE: RangeInclusive<f64> = 0.0..=0.1;
+
+/// `ranking.usage_half_life_days` の許容範囲（日）
+pub const USAGE_HALF_LIFE_RANGE: RangeInclusive<f64> = 1.0..=365.0;
+
+/// `power.battery_threshold_percent` の許容範囲
+pub const BATTERY_THRESHOLD_RANGE: RangeInclusive<u8> = 0..=100;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     pub appearance: AppearanceConfig,
@@ -25,6 +50,19 @@ pub struct Config {
     pub search: SearchConfig,
     pub plugins: PluginConfig,
     pub sync: SyncConfig,
+    #[serde(default)]
+    pub crash_reporting: CrashReportingConfig,
+    #[serde(default)]
+    pub watchdog: WatchdogConfig,
+    #[serde(default)]
+    pub ranking: RankingConfig,
+    #[serde(default)]
+    pub power: PowerConfig,
+    #[serde(default)]
+    pub default_profile: Option<String>,
+    /// 名前で検索して実行するユーザー定義コマンド
+    #[serde(default)]
+    pub custom_commands: Vec<CustomCommand>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -35,17 +73,69 @@ pub struct AppearanceConfig {
     pub show_window: ShowWindow,
     pub show_window_display_number: u32,
     pub font_size: u32,
-    pub max_results: usize,
+    /// ウィンドウに一度に表示する結果の行数。検索結果の件数は `behavior.max_results` で決まる
+    #[serde(default = "default_visible_result_rows")]
+    pub visible_result_rows: usize,
+    /// 旧形式の `appearance.max_results`。読み込み時に `visible_result_rows` へ移行され、保存されない
+    #[serde(default, rename = "max_results", skip_serializing)]
+    pub(crate) legacy_max_results: Option<usize>,
     pub enable_system_tray: bool,
     pub start_in_tray: bool,
     pub minimize_to_tray: bool,
+    /// ウィンドウ表示時に短いフェードインを行う（入力フォーカスは待たない）
+    #[serde(default = "default_true")]
+    pub fade_in: bool,
+    /// アニメーションを無効にする（fade_in より優先）
+    #[serde(default)]
+    pub reduced_motion: bool,
+    /// 表示言語と数値・日付の書式（"en" または "ja"）。未設定なら OS のロケールに従う
+    #[serde(default)]
+    pub language: Option<String>,
+    /// 結果の行に出す項目のプリセット
+    #[serde(default)]
+    pub result_row_layout: ResultRowLayout,
+    /// プリセットに関係なく行にカテゴリのバッジを付ける
+    #[serde(default)]
+    pub show_category_badge: bool,
+    /// 行にスコアを表示する（ランキングの調整用）
+    #[serde(default)]
+    pub show_score_debug: bool,
+    /// 行から使える追加のアクションを表示する
+    #[serde(default = "default_true")]
+    pub show_shortcut_hints: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// 結果の行の構成
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ResultRowLayout {
+    /// タイトルだけ
+    TitleOnly,
+    /// タイトルの下に説明
+    #[default]
+    TitleDescription,
+    /// タイトルの下にパス（パスのない結果は説明）
+    TitlePath,
+    /// タイトルとカテゴリのバッジ、その下に説明とパス
+    Detailed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Theme {
     Light,
     Dark,
     System,
+    /// `themes/<name>.json` のテーマ。設定ファイルでは `{"Custom": "dracula"}`
+    Custom(String),
+}
+
+impl Theme {
+    /// ユーザーのテーマファイルの名前
+    pub fn custom_name(&self) -> Option<&str> {
+        match self {
+            Theme::Custom(name) => Some(name),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -69,6 +159,100 @@ pub struct BehaviorConfig {
     pub rebuild_index_on_startup: bool,
     pub save_search_history: bool,
     pub record_usage_stats: bool,
+    /// 結果に「Markdown リンクとしてコピー」「メールで共有」などのセカンダリアクションを付ける
+    #[serde(default = "default_true")]
+    pub share_actions: bool,
+    /// 目的（intent）ごとのホットキー。"toggle" 以外はプラグイン ID で、そのプラグインのプレフィックスを入力した状態で開く
+    #[serde(default)]
+    pub hotkeys: HashMap<String, String>,
+    /// 検索履歴の保持日数。0 の場合は無期限に保持する
+    #[serde(default = "default_history_retention_days")]
+    pub history_retention_days: u32,
+    /// クリップボードを書き換えたあと、元のテキストに戻せる秒数。0 の場合は戻せない
+    #[serde(default = "default_clipboard_undo_secs")]
+    pub clipboard_undo_secs: u64,
+    /// ウィンドウを開いたときにクリップボードを読み、URL・パス・計算式・テキストに応じた結果を空の検索欄の下に出す
+    #[serde(default)]
+    pub clipboard_suggestions: bool,
+}
+
+/// ウィンドウの表示切り替えに使う主ホットキーの intent 名
+pub const TOGGLE_INTENT: &str = "toggle";
+
+impl BehaviorConfig {
+    /// (intent, ホットキー) の一覧。主ホットキーが先頭で、`hotkeys` に "toggle" がなければ `hotkey` を使う
+    pub fn hotkey_bindings(&self) -> Vec<(String, String)> {
+        let toggle = self.hotkeys.get(TOGGLE_INTENT).unwrap_or(&self.hotkey).clone();
+        let mut others: Vec<(String, String)> = self.hotkeys.iter()
+            .filter(|(intent, _)| intent.as_str() != TOGGLE_INTENT)
+            .map(|(intent, chord)| (intent.clone(), chord.clone()))
+            .collect();
+        others.sort();
+
+        let mut bindings = vec![(TOGGLE_INTENT.to_string(), toggle)];
+        bindings.extend(others);
+        bindings
+    }
+}
+
+/// 比較用にホットキーを正規化する（大文字小文字・修飾キーの順序・別名を無視）
+pub fn normalize_chord(chord: &str) -> String {
+    let mut modifiers = Vec::new();
+    let mut keys = Vec::new();
+    for part in chord.split('+').map(|p| p.trim().to_lowercase()).filter(|p| !p.is_empty()) {
+        match part.as_str() {
+            "ctrl" | "control" => modifiers.push("ctrl"),
+            "alt" | "option" => modifiers.push("alt"),
+            "shift" => modifiers.push("shift"),
+            "cmd" | "command" | "super" | "meta" | "win" => modifiers.push("super"),
+            _ => keys.push(part),
+        }
+    }
+    modifiers.sort();
+    modifiers.dedup();
+
+    let mut parts: Vec<String> = modifiers.into_iter().map(str::to_string).collect();
+    parts.extend(keys);
+    parts.join("+")
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_visible_result_rows() -> usize {
+    10
+}
+
+fn default_history_retention_days() -> u32 {
+    90
+}
+
+fn default_clipboard_undo_secs() -> u64 {
+    10
+}
+
+fn default_archive_size_limit_mb() -> u64 {
+    200
+}
+
+fn default_min_results_per_source() -> usize {
+    1
+}
+
+fn default_cache_sample_size() -> usize {
+    200
+}
+
+fn default_category_priority() -> Vec<String> {
+    ["application", "custom", "system", "plugin", "bookmark", "file"]
+        .into_iter()
+        .map(String::from)
+        .collect()
+}
+
+fn default_app_exclude_patterns() -> Vec<String> {
+    vec!["uninstall*".to_string(), "*redistributable*".to_string(), "*setup*".to_string()]
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -79,6 +263,41 @@ pub struct SearchConfig {
     pub enable_file_search: bool,
     pub enable_app_search: bool,
     pub enable_web_search: bool,
+    #[serde(default)]
+    pub app_aliases: HashMap<String, String>,
+    /// 組み込みの機密ディレクトリ除外リストより優先してインデックスを許可するパス
+    #[serde(default)]
+    pub allow_sensitive_paths: Vec<String>,
+    /// バージョン違いをまとめずに個別に表示するアプリ（元の名前または正規化後の名前）
+    #[serde(default)]
+    pub ungrouped_apps: Vec<String>,
+    /// アプリ検索から除外するパターン（区切り文字を含むものは実行ファイルのパス、それ以外はアプリ名に一致）
+    #[serde(default = "default_app_exclude_patterns")]
+    pub app_exclude_patterns: Vec<String>,
+    /// zip / tar.gz アーカイブ内のファイル一覧もインデックスする
+    #[serde(default)]
+    pub index_archive_contents: bool,
+    /// 中身をインデックスするアーカイブの最大サイズ（MB）
+    #[serde(default = "default_archive_size_limit_mb")]
+    pub archive_size_limit_mb: u64,
+    /// 同じスコアの結果を並べるカテゴリの順（application, file, bookmark, plugin または plugin:<id>, system, custom）
+    #[serde(default = "default_category_priority")]
+    pub category_priority: Vec<String>,
+    /// アーカイブの一覧などファイルの解析を別プロセス（falcommand-worker）で行う。起動できなければ本体で行う
+    #[serde(default = "default_true")]
+    pub isolated_extraction: bool,
+    /// 「このアプリで開く」で拡張子ごとに最初から候補にするアプリ（拡張子 -> アプリ名の一覧）。選んだアプリは自動で覚える
+    #[serde(default)]
+    pub open_with: HashMap<String, Vec<String>>,
+    /// 閾値以上の結果を出したソース（カテゴリ）ごとに、件数上限で切り捨てられても残す最低件数
+    #[serde(default = "default_min_results_per_source")]
+    pub min_results_per_source: usize,
+    /// 起動時にインデックスのキャッシュから存在を確かめる項目数（0 で確認しない）
+    #[serde(default = "default_cache_sample_size")]
+    pub cache_sample_size: usize,
+    /// `@名前` で呼び出すクエリのひな形（名前 -> ひな形）。`{args}` は名前の後に入力した語に置き換わる
+    #[serde(default)]
+    pub query_macros: HashMap<String, String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -88,6 +307,23 @@ pub struct PluginConfig {
     pub plugin_settings: HashMap<String, serde_json::Value>,
 }
 
+impl PluginConfig {
+    /// プラグインを有効/無効に切り替え、`enabled` と `disabled` の両方に同じ名前が残らないようにする
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) {
+        self.enabled.retain(|n| n != name);
+        self.disabled.retain(|n| n != name);
+        if enabled {
+            self.enabled.push(name.to_string());
+        } else {
+            self.disabled.push(name.to_string());
+        }
+    }
+    
+    pub fn is_enabled(&self, name: &str) -> bool {
+        self.enabled.iter().any(|n| n == name) && !self.disabled.iter().any(|n| n == name)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SyncConfig {
     pub enabled: bool,
@@ -96,8 +332,154 @@ pub struct SyncConfig {
     pub encrypt_data: bool,
 }
 
-impl Config {
-    pub fn default() -> Self {
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CrashReportingConfig {
+    pub upload_enabled: bool,
+    pub upload_url: Option<String>,
+}
+
+/// UI イベントループの監視設定
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchdogConfig {
+    /// イベントループの応答がこのミリ秒数より長く途絶えたら停止とみなす
+    #[serde(default = "default_stall_threshold_ms")]
+    pub stall_threshold_ms: u64,
+    /// 停止が繰り返されたらウィンドウを作り直す（インデックスとプラグインはそのまま）
+    #[serde(default)]
+    pub auto_restart_ui: bool,
+}
+
+pub const MIN_STALL_THRESHOLD_MS: u64 = 500;
+
+fn default_stall_threshold_ms() -> u64 {
+    2000
+}
+
+impl Default for WatchdogConfig {
+    fn default() -> Self {
+        Self {
+            stall_threshold_ms: default_stall_threshold_ms(),
+            auto_restart_ui: false,
+        }
+    }
+}
+
+/// バッテリー駆動中のバックグラウンド処理
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PowerConfig {
+    /// バッテリー残量が閾値を下回っている間、定期的なバックグラウンド処理を見送る（AC 電源で再開する）
+    #[serde(default = "default_true")]
+    pub defer_on_battery: bool,
+    /// バックグラウンド処理を見送るバッテリー残量（%）。これ未満で見送る
+    #[serde(default = "default_battery_threshold_percent")]
+    pub battery_threshold_percent: u8,
+}
+
+fn default_battery_threshold_percent() -> u8 {
+    30
+}
+
+impl Default for PowerConfig {
+    fn default() -> Self {
+        Self {
+            defer_on_battery: true,
+            battery_threshold_percent: default_battery_threshold_percent(),
+        }
+    }
+}
+
+/// ユーザー定義コマンド。`action` 一つか、`actions` を順に実行するマクロ
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CustomCommand {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(flatten)]
+    pub steps: CommandSteps,
+    /// マクロの手順の間に置く待ち時間（ミリ秒）
+    #[serde(default)]
+    pub step_delay_ms: u64,
+    /// 手順が失敗したら残りを実行しない（false なら失敗しても最後まで続ける）
+    #[serde(default = "default_true")]
+    pub stop_on_failure: bool,
+}
+
+/// 単一のアクション（従来の形式）か、順に実行するアクションの並び
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum CommandSteps {
+    Single { action: Action },
+    Macro { actions: Vec<Action> },
+}
+
+impl CustomCommand {
+    /// 実行する順のアクション（`Action::RunCustomCommand` は展開しない）
+    pub fn actions(&self) -> &[Action] {
+        match self.steps {
+            CommandSteps::Single { ref action } => std::slice::from_ref(action),
+            CommandSteps::Macro { ref actions } => actions,
+        }
+    }
+
+    pub fn is_macro(&self) -> bool {
+        matches!(self.steps, CommandSteps::Macro { .. })
+    }
+}
+
+/// 検索結果のスコアの重み
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RankingConfig {
+    /// ソースのスコアにあいまい一致のスコアを混ぜる割合（0 でソースのスコアのみ）
+    #[serde(default = "default_fuzzy_weight")]
+    pub fuzzy_weight: f64,
+    /// 同じクエリで以前選んだ結果へのボーナスの倍率
+    #[serde(default = "default_selection_weight")]
+    pub selection_weight: f64,
+    /// 起動回数に応じたボーナスの上限（0 で無効）
+    #[serde(default)]
+    pub usage_weight: f64,
+    /// 起動の重みが半分になるまでの日数（古い起動ほどボーナスに効かなくなる）
+    #[serde(default = "default_usage_half_life_days")]
+    pub usage_half_life_days: f64,
+    /// 最前面のアプリごとのファイル結果へのボーナス。キーはアプリ名の一部（大文字小文字を区別しない）、
+    /// 値は拡張子ごとのボーナス。例: `"code": {"rs": 0.1, "toml": 0.1, "md": 0.1}`
+    ///
+    /// 最前面のアプリを取得できない環境では何もしない
+    #[serde(default)]
+    pub context_boosts: HashMap<String, HashMap<String, f64>>,
+    /// クエリごとに先頭へ固定する結果のタイトル。キーはクエリ（大文字小文字と前後の空白は区別しない）、
+    /// 値は固定する順に並べたタイトル。例: `"term": ["Alacritty"]`
+    #[serde(default)]
+    pub pinned: HashMap<String, Vec<String>>,
+}
+
+fn default_fuzzy_weight() -> f64 {
+    0.5
+}
+
+fn default_selection_weight() -> f64 {
+    1.0
+}
+
+fn default_usage_half_life_days() -> f64 {
+    30.0
+}
+
+impl Default for RankingConfig {
+    fn default() -> Self {
+        Self {
+            fuzzy_weight: default_fuzzy_weight(),
+            selection_weight: default_selection_weight(),
+            usage_weight: 0.0,
+            usage_half_life_days: default_usage_half_life_days(),
+            context_boosts: HashMap::new(),
+            pinned: HashMap::new(),
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
         Self {
             appearance: AppearanceConfig {
                 theme: Theme::System,
@@ -106,10 +488,18 @@ impl Config {
                 show_window: ShowWindow::Mouse,
                 show_window_display_number: 0,
                 font_size: 14,
-                max_results: 10,
+                visible_result_rows: default_visible_result_rows(),
+                legacy_max_results: None,
                 enable_system_tray: true,
                 start_in_tray: false,
                 minimize_to_tray: true,
+                fade_in: true,
+                reduced_motion: false,
+                language: None,
+                result_row_layout: ResultRowLayout::default(),
+                show_category_badge: false,
+                show_score_debug: false,
+                show_shortcut_hints: true,
             },
             behavior: BehaviorConfig {
                 hotkey: "Ctrl+Space".to_string(),
@@ -118,6 +508,11 @@ impl Config {
                 rebuild_index_on_startup: true,
                 save_search_history: true,
                 record_usage_stats: true,
+                share_actions: true,
+                hotkeys: HashMap::new(),
+                history_retention_days: default_history_retention_days(),
+                clipboard_undo_secs: default_clipboard_undo_secs(),
+                clipboard_suggestions: false,
             },
             search: SearchConfig {
                 include_paths: {
@@ -145,9 +540,21 @@ impl Config {
                 enable_file_search: true,
                 enable_app_search: true,
                 enable_web_search: false,
+                app_aliases: HashMap::new(),
+                allow_sensitive_paths: Vec::new(),
+                ungrouped_apps: Vec::new(),
+                app_exclude_patterns: default_app_exclude_patterns(),
+                index_archive_contents: false,
+                archive_size_limit_mb: default_archive_size_limit_mb(),
+                category_priority: default_category_priority(),
+                isolated_extraction: true,
+                open_with: HashMap::new(),
+                min_results_per_source: default_min_results_per_source(),
+                cache_sample_size: default_cache_sample_size(),
+                query_macros: HashMap::new(),
             },
             plugins: PluginConfig {
-                enabled: vec!["calculator".to_string(), "translator".to_string()],
+                enabled: vec!["calculator".to_string(), "translator".to_string(), "timer".to_string(), "devdocs".to_string(), "system".to_string(), "network".to_string(), "notes".to_string()],
                 disabled: vec!["weather".to_string()],
                 plugin_settings: HashMap::new(),
             },
@@ -157,14 +564,68 @@ impl Config {
                 auto_sync_interval: 3600, // 1 hour
                 encrypt_data: true,
             },
+            crash_reporting: CrashReportingConfig::default(),
+            watchdog: WatchdogConfig::default(),
+            ranking: RankingConfig::default(),
+            power: PowerConfig::default(),
+            default_profile: None,
+            custom_commands: Vec::new(),
         }
     }
-    
+}
+
+impl Config {
     pub async fn load_default() -> Result<Self, ConfigError> {
-        let config_path = Self::get_default_config_path()?;
+        let resolver = PathResolver::detect(false, None)?;
+        Self::load_profile(resolver).await.map(|(config, _)| config)
+    }
+    
+    /// 設定を読み込み、選択されたプロファイルに対応する PathResolver と共に返す
+    ///
+    /// プロファイルは引数（`--profile`）で指定された `resolver` のもの、ベース設定の `default_profile` の順で決定される
+    pub async fn load_profile(resolver: PathResolver) -> Result<(Self, PathResolver), ConfigError> {
+        let base_config = Self::load_base(&resolver).await?;
+        
+        let profile = resolver.profile()
+            .map(|p| p.to_string())
+            .or_else(|| base_config.default_profile.clone());
+        let resolver = resolver.with_profile(profile);
+        let config = Self::apply_overlays(base_config, &resolver).await?;
+        
+        Ok((config, resolver))
+    }
+    
+    /// ベース設定を読み込み、プロファイルとデバッグ設定を重ねる（プロファイル切り替え用）
+    pub async fn load_with_resolver(resolver: &PathResolver) -> Result<Self, ConfigError> {
+        let base_config = Self::load_base(resolver).await?;
+        Self::apply_overlays(base_config, resolver).await
+    }
+    
+    /// 安全モード用の設定。既定の設定に、ベース設定の外観と動作の項目だけを重ねる
+    ///
+    /// プロファイルやデバッグ設定は使わず、ベース設定が読めなければ既定の設定のままにする。ファイルには書き込まない
+    pub async fn load_safe_mode(resolver: &PathResolver) -> Self {
+        let mut config = Self::default();
+        let config_path = resolver.config_file();
+        if !config_path.exists() {
+            return config;
+        }
+        
+        match Self::load_from_file(&config_path).await {
+            Ok(base) => {
+                config.appearance = base.appearance;
+                config.behavior = base.behavior;
+            }
+            Err(e) => warn!("Ignoring the configuration in safe mode: {}", e),
+        }
+        config
+    }
+    
+    async fn load_base(resolver: &PathResolver) -> Result<Self, ConfigError> {
+        let config_path = resolver.config_file();
         
         // ベース設定の読み込み
-        let mut base_config = if config_path.exists() {
+        let base_config = if config_path.exists() {
             Self::load_from_file(&config_path).await?
         } else {
             info!("Config file not found, creating default configuration");
@@ -174,23 +635,36 @@ impl Config {
         };
 
         // プラットフォーム固有設定をマージ
-        base_config = base_config.get_platform_specific_config().await;
+        Ok(base_config.get_platform_specific_config(resolver).await)
+    }
+    
+    async fn apply_overlays(mut config: Self, resolver: &PathResolver) -> Result<Self, ConfigError> {
+        // プロファイル設定をマージ
+        if let Some(profile_path) = resolver.profile_config_file() {
+            if profile_path.exists() {
+                info!("Applying profile configuration from: {:?}", profile_path);
+                config = config.merge_with(Self::load_from_file(&profile_path).await?);
+            } else {
+                info!("Profile '{}' has no configuration overlay", resolver.profile().unwrap_or_default());
+            }
+        }
 
         // デバッグビルドの場合、デバッグ設定を最優先でマージ
         if cfg!(debug_assertions) {
-            if let Ok(Some(debug_config)) = Self::load_debug_config().await {
+            if let Ok(Some(debug_config)) = Self::load_debug_config(resolver).await {
                 info!("Applying debug configuration with highest priority");
-                base_config = base_config.merge_with(debug_config);
+                config = config.merge_with(debug_config);
             }
         }
 
-        Ok(base_config)
+        Ok(config)
     }
     
     pub async fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Self, ConfigError> {
         info!("Loading config from: {:?}", path.as_ref());
         let content = tokio::fs::read_to_string(path).await?;
-        let config: Config = serde_json::from_str(&content)?;
+        let mut config: Config = serde_json::from_str(&content)?;
+        config.migrate();
         config.validate()?;
         Ok(config)
     }
@@ -203,157 +677,346 @@ impl Config {
             tokio::fs::create_dir_all(parent).await?;
         }
         
+        // 一時ファイルに書いてから置き換え、書き込み途中の設定ファイルが残らないようにする
         let content = serde_json::to_string_pretty(self)?;
-        tokio::fs::write(path, content).await?;
+        let tmp_path = path.as_ref().with_extension("json.tmp");
+        tokio::fs::write(&tmp_path, content).await?;
+        tokio::fs::rename(&tmp_path, path).await?;
         Ok(())
     }
     
+    /// 現在のプロファイルの設定ファイル（プロファイルがなければベース設定）に保存
+    pub async fn save(&self, resolver: &PathResolver) -> Result<(), ConfigError> {
+        self.save_to_file(resolver.active_config_file()).await
+    }
+    
+    /// 旧形式の項目を現在の項目に移す。次に保存したときには旧項目は書き出されない
+    pub fn migrate(&mut self) {
+        // 以前は表示行数と検索件数が別々に設定できたが、検索件数は behavior.max_results に一本化した
+        if let Some(legacy) = self.appearance.legacy_max_results.take() {
+            let rows = legacy.min(self.behavior.max_results);
+            if legacy != self.behavior.max_results {
+                warn!(
+                    "appearance.max_results ({}) is deprecated; behavior.max_results ({}) limits results and {} rows are shown",
+                    legacy, self.behavior.max_results, rows
+                );
+            } else {
+                warn!("appearance.max_results is deprecated; it is now appearance.visible_result_rows");
+            }
+            self.appearance.visible_result_rows = rows;
+        }
+    }
+    
     pub fn validate(&self) -> Result<(), ConfigError> {
         // Validate transparency
-        if self.appearance.transparency < 0.0 || self.appearance.transparency > 1.0 {
-            return Err(ConfigError::ValidationError(
-                "Transparency must be between 0.0 and 1.0".to_string()
-            ));
+        if !TRANSPARENCY_RANGE.contains(&self.appearance.transparency) {
+            return Err(ConfigError::ValidationError(format!(
+                "Transparency must be between {:.1} and {:.1}",
+                TRANSPARENCY_RANGE.start(), TRANSPARENCY_RANGE.end()
+            )));
         }
         
         // Validate fuzzy threshold
-        if self.search.fuzzy_threshold < 0.0 || self.search.fuzzy_threshold > 1.0 {
+        if !FUZZY_THRESHOLD_RANGE.contains(&self.search.fuzzy_threshold) {
+            return Err(ConfigError::ValidationError(format!(
+                "Fuzzy threshold must be between {:.1} and {:.1}",
+                FUZZY_THRESHOLD_RANGE.start(), FUZZY_THRESHOLD_RANGE.end()
+            )));
+        }
+        
+        // Validate crash report upload target
+        if self.crash_reporting.upload_enabled && self.crash_reporting.upload_url.is_none() {
             return Err(ConfigError::ValidationError(
-                "Fuzzy threshold must be between 0.0 and 1.0".to_string()
+                "Crash report upload requires upload_url".to_string()
             ));
         }
         
-        // Validate max results
-        if self.behavior.max_results == 0 || self.behavior.max_results > 100 {
+        // Validate ranking weights
+        let weights = [
+            ("fuzzy_weight", self.ranking.fuzzy_weight),
+            ("selection_weight", self.ranking.selection_weight),
+            ("usage_weight", self.ranking.usage_weight),
+        ];
+        for (name, weight) in weights {
+            if !RANKING_WEIGHT_RANGE.contains(&weight) {
+                return Err(ConfigError::ValidationError(format!(
+                    "ranking.{} must be between {:.1} and {:.1}",
+                    name, RANKING_WEIGHT_RANGE.start(), RANKING_WEIGHT_RANGE.end()
+                )));
+            }
+        }
+        if !USAGE_HALF_LIFE_RANGE.contains(&self.ranking.usage_half_life_days) {
+            return Err(ConfigError::ValidationError(format!(
+                "ranking.usage_half_life_days must be between {:.0} and {:.0}",
+                USAGE_HALF_LIFE_RANGE.start(), USAGE_HALF_LIFE_RANGE.end()
+            )));
+        }
+        for (app, boosts) in &self.ranking.context_boosts {
+            if let Some((extension, _)) = boosts.iter().find(|(_, boost)| !CONTEXT_BOOST_RANGE.contains(*boost)) {
+                return Err(ConfigError::ValidationError(format!(
+                    "ranking.context_boosts.{}.{} must be between {:.1} and {:.1}",
+                    app, extension, CONTEXT_BOOST_RANGE.start(), CONTEXT_BOOST_RANGE.end()
+                )));
+            }
+        }
+        
+        // Validate watchdog threshold: shorter than the heartbeat interval would report stalls all the time
+        if self.watchdog.stall_threshold_ms < MIN_STALL_THRESHOLD_MS {
+            return Err(ConfigError::ValidationError(format!(
+                "Watchdog stall threshold must be at least {} ms",
+                MIN_STALL_THRESHOLD_MS
+            )));
+        }
+        
+        // Validate plugin lists
+        if let Some(name) = self.plugins.enabled.iter().find(|n| self.plugins.disabled.contains(n)) {
             return Err(ConfigError::ValidationError(
-                "Max results must be between 1 and 100".to_string()
+                format!("Plugin '{}' is listed as both enabled and disabled", name)
             ));
         }
         
-        Ok(())
-    }
-    
-    fn get_default_config_path() -> Result<PathBuf, ConfigError> {
-        if cfg!(debug_assertions) {
-            // デバッグビルド（開発中）の場合はプロジェクトルートの.falcommandフォルダを使用
-            let current_dir = std::env::current_dir()
-                .map_err(|e| ConfigError::FileSystemError(format!("Cannot determine current directory: {}", e)))?;
-            Ok(current_dir.join(".falcommand").join("config.json"))
-        } else {
-            // リリースビルドの場合は従来通りシステム設定ディレクトリを使用
-            let config_dir = dirs::config_dir()
-                .ok_or_else(|| ConfigError::FileSystemError("Cannot determine config directory".to_string()))?;
-            
-            Ok(config_dir.join("falcommand").join("config.json"))
+        // Validate hotkeys: one chord can only trigger one intent
+        let mut chords: HashMap<String, String> = HashMap::new();
+        for (intent, chord) in self.behavior.hotkey_bindings() {
+            if let Some(existing) = chords.insert(normalize_chord(&chord), intent.clone()) {
+                return Err(ConfigError::ValidationError(format!(
+                    "Hotkey '{}' is assigned to both '{}' and '{}'",
+                    chord, existing, intent
+                )));
+            }
+        }
+        
+        // Validate the battery threshold
+        if !BATTERY_THRESHOLD_RANGE.contains(&self.power.battery_threshold_percent) {
+            return Err(ConfigError::ValidationError(format!(
+                "Battery threshold must be between {}% and {}%",
+                BATTERY_THRESHOLD_RANGE.start(), BATTERY_THRESHOLD_RANGE.end()
+            )));
+        }
+        
+        // Validate custom commands: macros run by name, so names must be unique
+        let mut command_names = std::collections::HashSet::new();
+        for command in &self.custom_commands {
+            if command.name.trim().is_empty() {
+                return Err(ConfigError::ValidationError("Custom commands need a name".to_string()));
+            }
+            if !command_names.insert(command.name.to_lowercase()) {
+                return Err(ConfigError::ValidationError(format!(
+                    "Custom command '{}' is defined more than once",
+                    command.name
+                )));
+            }
+            if command.actions().is_empty() {
+                return Err(ConfigError::ValidationError(format!(
+                    "Custom command '{}' has no actions",
+                    command.name
+                )));
+            }
+        }
+        
+        // Validate max results
+        if !MAX_RESULTS_RANGE.contains(&self.behavior.max_results) {
+            return Err(ConfigError::ValidationError(format!(
+                "Max results must be between {} and {}",
+                MAX_RESULTS_RANGE.start(), MAX_RESULTS_RANGE.end()
+            )));
         }
+        
+        // Validate visible rows: the window can't show more rows than a search returns
+        if self.appearance.visible_result_rows == 0 || self.appearance.visible_result_rows > self.behavior.max_results {
+            return Err(ConfigError::ValidationError(format!(
+                "Visible result rows must be between 1 and max results ({})",
+                self.behavior.max_results
+            )));
+        }
+        
+        Ok(())
     }
     
-    pub async fn get_platform_specific_config(&self) -> Config {
+    pub async fn get_platform_specific_config(&self, resolver: &PathResolver) -> Config {
         let mut config = self.clone();
         
         // Load platform-specific overrides if they exist
-        let platform_config_path = Self::get_platform_config_path();
-        if let Ok(path) = platform_config_path {
-            if path.exists() {
-                if let Ok(platform_config) = Self::load_from_file(&path).await {
-                    // Merge platform-specific settings
-                    config = config.merge_with(platform_config);
-                    info!("Applied platform-specific configuration from: {:?}", path);
-                }
+        let path = resolver.platform_config_file();
+        if path != resolver.config_file() && path.exists() {
+            if let Ok(platform_config) = Self::load_from_file(&path).await {
+                // Merge platform-specific settings
+                config = config.merge_with(platform_config);
+                info!("Applied platform-specific configuration from: {:?}", path);
             }
         }
         
         config
     }
     
-    fn get_platform_config_path() -> Result<PathBuf, ConfigError> {
-        let config_dir = if cfg!(debug_assertions) {
-            // デバッグビルドの場合はプロジェクトルートの.falcommandフォルダを使用
-            let current_dir = std::env::current_dir()
-                .map_err(|e| ConfigError::FileSystemError(format!("Cannot determine current directory: {}", e)))?;
-            current_dir.join(".falcommand")
-        } else {
-            // リリースビルドの場合は従来通りシステム設定ディレクトリを使用
-            dirs::config_dir()
-                .ok_or_else(|| ConfigError::FileSystemError("Cannot determine config directory".to_string()))?
-                .join("falcommand")
-        };
-        
-        let filename = if cfg!(target_os = "windows") {
-            "config.windows.json"
-        } else if cfg!(target_os = "macos") {
-            "config.macos.json"
-        } else if cfg!(target_os = "linux") {
-            "config.linux.json"
-        } else {
-            "config.json"
-        };
-        
-        Ok(config_dir.join(filename))
-    }
-    
     fn merge_with(&self, other: Config) -> Config {
         // Simple merge - in real implementation, this would be more sophisticated
         other
     }
 
-    /// デバッグ設定ファイルのパスを取得
-    fn get_debug_config_path() -> Result<PathBuf, ConfigError> {
-        if cfg!(debug_assertions) {
-            let current_dir = std::env::current_dir()
-                .map_err(|e| ConfigError::FileSystemError(format!("Cannot determine current directory: {}", e)))?;
-            Ok(current_dir.join(".falcommand").join("config.debug.json"))
-        } else {
-            // リリースビルドの場合はデバッグ設定は使用しない
-            Err(ConfigError::FileSystemError("Debug config not available in release builds".to_string()))
-        }
-    }
-
-    /// プラットフォーム固有デバッグ設定ファイルのパスを取得
-    fn get_platform_debug_config_path() -> Result<PathBuf, ConfigError> {
-        if cfg!(debug_assertions) {
-            let current_dir = std::env::current_dir()
-                .map_err(|e| ConfigError::FileSystemError(format!("Cannot determine current directory: {}", e)))?;
-            
-            let filename = if cfg!(target_os = "windows") {
-                "config.debug.windows.json"
-            } else if cfg!(target_os = "macos") {
-                "config.debug.macos.json"
-            } else if cfg!(target_os = "linux") {
-                "config.debug.linux.json"
-            } else {
-                "config.debug.json"
-            };
-            
-            Ok(current_dir.join(".falcommand").join(filename))
-        } else {
-            // リリースビルドの場合はデバッグ設定は使用しない
-            Err(ConfigError::FileSystemError("Platform debug config not available in release builds".to_string()))
-        }
-    }
-
     /// デバッグ設定を読み込み（存在する場合のみ）
-    async fn load_debug_config() -> Result<Option<Config>, ConfigError> {
+    async fn load_debug_config(resolver: &PathResolver) -> Result<Option<Config>, ConfigError> {
+        // リリースビルドの場合はデバッグ設定は使用しない
         if !cfg!(debug_assertions) {
             return Ok(None);
         }
 
         // プラットフォーム固有デバッグ設定を最優先で試行
-        if let Ok(platform_debug_path) = Self::get_platform_debug_config_path() {
-            if platform_debug_path.exists() {
-                info!("Loading platform-specific debug config from: {:?}", platform_debug_path);
-                return Self::load_from_file(&platform_debug_path).await.map(Some);
-            }
+        let platform_debug_path = resolver.platform_debug_config_file();
+        if platform_debug_path != resolver.debug_config_file() && platform_debug_path.exists() {
+            info!("Loading platform-specific debug config from: {:?}", platform_debug_path);
+            return Self::load_from_file(&platform_debug_path).await.map(Some);
         }
 
         // 一般デバッグ設定を次に試行
-        if let Ok(debug_path) = Self::get_debug_config_path() {
-            if debug_path.exists() {
-                info!("Loading debug config from: {:?}", debug_path);
-                return Self::load_from_file(&debug_path).await.map(Some);
-            }
+        let debug_path = resolver.debug_config_file();
+        if debug_path.exists() {
+            info!("Loading debug config from: {:?}", debug_path);
+            return Self::load_from_file(&debug_path).await.map(Some);
         }
 
         Ok(None)
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_hotkeys(hotkeys: &[(&str, &str)]) -> Config {
+        let mut config = Config::default();
+        config.behavior.hotkey = "Ctrl+Space".to_string();
+        config.behavior.hotkeys = hotkeys.iter().map(|(intent, chord)| (intent.to_string(), chord.to_string())).collect();
+        config
+    }
+
+    #[test]
+    fn chords_normalize_modifier_order_case_and_aliases() {
+        assert_eq!(normalize_chord("Shift+Ctrl+T"), "ctrl+shift+t");
+        assert_eq!(normalize_chord("control + shift + t"), normalize_chord("Shift+Ctrl+T"));
+        assert_eq!(normalize_chord("Cmd+Option+Space"), normalize_chord("Alt+Super+space"));
+        assert_eq!(normalize_chord("Win+Meta+E"), "super+e");
+        assert_ne!(normalize_chord("Ctrl+T"), normalize_chord("Ctrl+Shift+T"));
+    }
+
+    #[test]
+    fn the_toggle_binding_comes_first_and_falls_back_to_hotkey() {
+        let config = with_hotkeys(&[("translator", "Ctrl+Alt+T"), ("timer", "Ctrl+Alt+M")]);
+        assert_eq!(config.behavior.hotkey_bindings(), [
+            ("toggle".to_string(), "Ctrl+Space".to_string()),
+            ("timer".to_string(), "Ctrl+Alt+M".to_string()),
+            ("translator".to_string(), "Ctrl+Alt+T".to_string()),
+        ]);
+
+        let config = with_hotkeys(&[("toggle", "Alt+Space")]);
+        assert_eq!(config.behavior.hotkey_bindings(), [("toggle".to_string(), "Alt+Space".to_string())]);
+    }
+
+    #[test]
+    fn a_chord_bound_to_two_intents_fails_validation() {
+        assert!(with_hotkeys(&[("timer", "Ctrl+Alt+M"), ("translator", "Ctrl+Alt+T")]).validate().is_ok());
+
+        // The same chord written differently still collides, including with the primary hotkey
+        let error = with_hotkeys(&[("timer", "space+control")]).validate().unwrap_err();
+        assert!(error.to_string().contains("'toggle' and 'timer'"), "{}", error);
+
+        let error = with_hotkeys(&[("timer", "Ctrl+Alt+M"), ("translator", "Alt+Ctrl+m")]).validate().unwrap_err();
+        assert!(error.to_string().contains("'timer' and 'translator'"), "{}", error);
+    }
+
+    // Existing configs keep their plain theme names; a custom theme is an object naming the file
+    #[test]
+    fn themes_serialize_by_name() {
+        for (theme, json) in [(Theme::Dark, r#""Dark""#), (Theme::System, r#""System""#), (Theme::Custom("dracula".to_string()), r#"{"Custom":"dracula"}"#)] {
+            assert_eq!(serde_json::to_string(&theme).unwrap(), json);
+            assert_eq!(serde_json::from_str::<Theme>(json).unwrap(), theme);
+        }
+        assert_eq!(Theme::Custom("dracula".to_string()).custom_name(), Some("dracula"));
+        assert_eq!(Theme::Light.custom_name(), None);
+
+        let mut saved = serde_json::to_value(Config::default()).unwrap();
+        saved["appearance"]["theme"] = serde_json::json!("Light");
+        assert_eq!(serde_json::from_value::<Config>(saved.clone()).unwrap().appearance.theme, Theme::Light);
+        saved["appearance"]["theme"] = serde_json::json!({ "Custom": "nord" });
+        assert_eq!(serde_json::from_value::<Config>(saved).unwrap().appearance.theme, Theme::Custom("nord".to_string()));
+    }
+
+    // Boosts are capped small enough that an exact title match always stays ahead
+    #[test]
+    fn context_boosts_over_the_cap_fail_validation() {
+        let mut config = Config::default();
+        config.ranking.context_boosts.insert("code".to_string(), HashMap::from([("rs".to_string(), 0.1)]));
+        assert!(config.validate().is_ok());
+
+        config.ranking.context_boosts.insert("mail".to_string(), HashMap::from([("pdf".to_string(), 0.5)]));
+        let error = config.validate().unwrap_err();
+        assert!(error.to_string().contains("ranking.context_boosts.mail.pdf must be between 0.0 and 0.1"), "{}", error);
+
+        config.ranking.context_boosts.insert("mail".to_string(), HashMap::from([("pdf".to_string(), -0.05)]));
+        assert!(config.validate().is_err());
+    }
+
+    // Warnings logged by any test in this binary; tests look for their own by content
+    struct CapturedLog(std::sync::Mutex<Vec<String>>);
+
+    impl log::Log for CapturedLog {
+        fn enabled(&self, metadata: &log::Metadata) -> bool {
+            metadata.level() <= log::Level::Warn
+        }
+
+        fn log(&self, record: &log::Record) {
+            if self.enabled(record.metadata()) {
+                self.0.lock().unwrap().push(record.args().to_string());
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    static CAPTURED_LOG: CapturedLog = CapturedLog(std::sync::Mutex::new(Vec::new()));
+
+    fn captured_warnings() -> Vec<String> {
+        static INSTALL: std::sync::Once = std::sync::Once::new();
+        INSTALL.call_once(|| {
+            log::set_logger(&CAPTURED_LOG).unwrap();
+            log::set_max_level(log::LevelFilter::Warn);
+        });
+        CAPTURED_LOG.0.lock().unwrap().clone()
+    }
+
+    // As Config::load_from_file reads it
+    fn load_legacy(appearance_max: usize, behavior_max: usize) -> Config {
+        let mut saved = serde_json::to_value(Config::default()).unwrap();
+        let appearance = saved["appearance"].as_object_mut().unwrap();
+        appearance.remove("visible_result_rows");
+        appearance.insert("max_results".to_string(), serde_json::json!(appearance_max));
+        saved["behavior"]["max_results"] = serde_json::json!(behavior_max);
+
+        let mut config: Config = serde_json::from_value(saved).unwrap();
+        config.migrate();
+        config.validate().unwrap();
+        config
+    }
+
+    // The two settings disagreed: behavior.max_results keeps limiting results and the rows shown
+    // never exceed it
+    #[test]
+    fn legacy_max_results_migrate_with_behavior_winning() {
+        captured_warnings();
+        let config = load_legacy(30, 15);
+        assert_eq!((config.behavior.max_results, config.appearance.visible_result_rows), (15, 15));
+        let expected = "appearance.max_results (30) is deprecated; behavior.max_results (15) limits results and 15 rows are shown";
+        assert!(captured_warnings().iter().any(|warning| warning == expected), "{:?}", captured_warnings());
+
+        // Fewer rows than results is kept as it was
+        let config = load_legacy(5, 15);
+        assert_eq!((config.behavior.max_results, config.appearance.visible_result_rows), (15, 5));
+    }
+
+    #[test]
+    fn only_visible_result_rows_is_written_back() {
+        let saved = serde_json::to_value(load_legacy(8, 10)).unwrap();
+        assert_eq!(saved["appearance"]["visible_result_rows"], 8);
+        assert!(saved["appearance"].get("max_results").is_none(), "{}", saved["appearance"]);
+        assert_eq!(saved["behavior"]["max_results"], 10);
+    }
+}
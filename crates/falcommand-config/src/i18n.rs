@@ -0,0 +1,116 @@
+use std::sync::OnceLock;
+
+/// UI表示言語
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    English,
+    Japanese,
+}
+
+impl Language {
+    /// `ja_JP.UTF-8` のようなロケール文字列から言語を判定
+    pub fn from_locale(locale: &str) -> Self {
+        if locale.to_lowercase().starts_with("ja") {
+            Language::Japanese
+        } else {
+            Language::English
+        }
+    }
+
+    /// 環境変数（LC_ALL, LC_MESSAGES, LANG の順）から言語を判定
+    pub fn detect() -> Self {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .map(|locale| Self::from_locale(&locale))
+            .unwrap_or(Language::English)
+    }
+}
+
+static LANGUAGE: OnceLock<Language> = OnceLock::new();
+
+/// 設定の `appearance.language` で表示言語を決める（未設定なら環境から判定）。起動時に一度だけ呼ぶ
+pub fn init_language(configured: Option<&str>) -> Language {
+    *LANGUAGE.get_or_init(|| configured.map(Language::from_locale).unwrap_or_else(Language::detect))
+}
+
+/// 現在の表示言語（初回呼び出し時に環境から判定）
+pub fn current_language() -> Language {
+    *LANGUAGE.get_or_init(Language::detect)
+}
+
+/// 翻訳済みの文字列を取得する。翻訳がない場合は英語の `fallback` を返す
+pub fn tr(key: &str, fallback: &str) -> String {
+    translate(current_language(), key, fallback)
+}
+
+pub fn translate(language: Language, key: &str, fallback: &str) -> String {
+    let catalog: &[(&str, &str)] = match language {
+        Language::English => return fallback.to_string(),
+        Language::Japanese => JAPANESE,
+    };
+
+    catalog.iter()
+        .find(|(k, _)| *k == key)
+        .map(|(_, text)| text.to_string())
+        .unwrap_or_else(|| fallback.to_string())
+}
+
+const JAPANESE: &[(&str, &str)] = &[
+    ("help.title", "検索構文のヘルプ"),
+    ("help.example", "例"),
+    ("window.safe_mode", "安全モード"),
+    ("hint.app_arguments", "アプリを引数付きで起動"),
+    ("hint.folder_scope", "フォルダ内のファイルだけを検索"),
+    ("hint.help", "使用できる検索構文を一覧表示"),
+    ("hint.scheduled", "予約済みのアクションを表示・キャンセル"),
+    ("hint.profile", "プロファイルを切り替え"),
+    ("hint.stats", "過去 7・30・90 日間の起動統計を表示"),
+    ("hint.history", "最近の検索を一覧表示し、個別に削除"),
+    ("hint.app_menu", "最前面のアプリのメニューコマンドを実行"),
+    ("hint.open_with", "ファイルを別のアプリで開く"),
+    ("hint.doctor", "ホットキー・設定・インデックス・権限を診断"),
+    ("hint.safe_mode", "設定ファイルや前回のクラッシュレポートを開く、通常モードで再起動する"),
+    ("hint.query_macro", "保存したクエリを実行、または新しい名前でクエリを保存"),
+    ("hint.calculator", "計算式を評価"),
+    ("hint.translator", "テキストを翻訳"),
+    ("hint.timer", "カウントダウンタイマーを開始"),
+    ("hint.note", "メモファイルに一行追記する"),
+    ("hint.notes", "最近のメモを表示"),
+    ("hint.duplicates", "内容が同じファイルを探す"),
+    ("hint.path_completion", "パスを入力するとフォルダの中身を表示する（Tab で補完）"),
+    ("hint.clipboard_undo", "コピーで上書きしたクリップボードの内容を元に戻す"),
+    ("hint.devdocs_http", "HTTPステータスコードを調べる"),
+    ("hint.devdocs_commands", "よく使うコマンドのワンライナー"),
+    ("hint.devdocs_codepoint", "文字コードを調べる"),
+    ("hint.devdocs_mime", "MIMEタイプを調べる"),
+    ("hint.system_volume", "システムの音量を設定する"),
+    ("hint.system_mute", "ミュートを切り替える"),
+    ("hint.system_brightness", "画面の明るさを設定する"),
+    ("hint.system_battery", "バッテリー残量を表示する"),
+    ("hint.network_ip", "ローカルとグローバルのIPアドレスを表示する"),
+    ("hint.network_wifi", "接続中のWi-Fiを表示する"),
+    ("hint.network_ping", "ホストにpingを送る"),
+];
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locales_map_to_languages() {
+        for locale in ["ja_JP.UTF-8", "ja", "JA-jp"] {
+            assert_eq!(Language::from_locale(locale), Language::Japanese, "{}", locale);
+        }
+        for locale in ["en_US.UTF-8", "C", "", "de_DE"] {
+            assert_eq!(Language::from_locale(locale), Language::English, "{}", locale);
+        }
+    }
+
+    #[test]
+    fn missing_translations_fall_back_to_english() {
+        assert_eq!(translate(Language::Japanese, "window.safe_mode", "Safe mode"), "安全モード");
+        assert_eq!(translate(Language::English, "window.safe_mode", "Safe mode"), "Safe mode");
+        assert_eq!(translate(Language::Japanese, "no.such.key", "Fallback"), "Fallback");
+    }
+}
@@ -0,0 +1,217 @@
+use std::path::{Path, PathBuf};
+
+/// `PATHEXT` が未設定の場合に使う Windows の既定値
+pub const DEFAULT_PATHEXT: &str = ".COM;.EXE;.BAT;.CMD";
+
+/// 実行可能として扱うスクリプトの拡張子
+pub const SCRIPT_EXTENSIONS: [&str; 3] = ["bat", "cmd", "ps1"];
+
+/// `PATHEXT` を小文字の拡張子リスト（先頭のドット付き）に変換する
+pub fn pathext_list(pathext: Option<&str>) -> Vec<String> {
+    pathext.filter(|p| !p.trim().is_empty())
+        .unwrap_or(DEFAULT_PATHEXT)
+        .split(';')
+        .map(|ext| ext.trim().to_lowercase())
+        .filter(|ext| ext.starts_with('.') && ext.len() > 1)
+        .collect()
+}
+
+fn has_listed_extension(command: &str, pathext: &[String]) -> bool {
+    let lower = command.to_lowercase();
+    pathext.iter().any(|ext| lower.ends_with(ext.as_str()))
+}
+
+/// `PATH` の各ディレクトリを順に、入力どおりの名前、`PATHEXT` の順の拡張子で検索する
+///
+/// パス区切りを含むコマンドは `PATH` を使わずそのパスだけを確認する
+pub fn resolve_in_path(command: &str, path_dirs: &[PathBuf], pathext: &[String]) -> Option<PathBuf> {
+    let candidates_for = |base: PathBuf| -> Vec<PathBuf> {
+        let mut candidates = Vec::new();
+        if has_listed_extension(command, pathext) {
+            candidates.push(base.clone());
+        }
+        for ext in pathext {
+            let mut with_ext = base.clone().into_os_string();
+            with_ext.push(ext);
+            candidates.push(PathBuf::from(with_ext));
+        }
+        candidates
+    };
+
+    if command.contains(['\\', '/']) {
+        return candidates_for(PathBuf::from(command)).into_iter().find(|c| c.is_file());
+    }
+
+    path_dirs.iter()
+        .flat_map(|dir| candidates_for(dir.join(command)))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Windows では `PATHEXT` を考慮してコマンドを解決し、見つからなければ入力のまま返す
+pub fn resolve_command(command: &str) -> PathBuf {
+    if !cfg!(target_os = "windows") {
+        return PathBuf::from(command);
+    }
+
+    let pathext = pathext_list(std::env::var("PATHEXT").ok().as_deref());
+    let path_dirs: Vec<PathBuf> = std::env::var_os("PATH")
+        .map(|paths| std::env::split_paths(&paths).collect())
+        .unwrap_or_default();
+
+    resolve_in_path(command, &path_dirs, &pathext).unwrap_or_else(|| PathBuf::from(command))
+}
+
+pub fn is_script(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| SCRIPT_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+/// Windows で `PATHEXT` に含まれる拡張子、または PowerShell スクリプトなら実行可能とみなす
+pub fn is_windows_executable(path: &Path, pathext: &[String]) -> bool {
+    let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+        return false;
+    };
+    has_listed_extension(name, pathext) || is_script(path)
+}
+
+/// 起動するプログラムと引数を決める（`.ps1` は PowerShell 経由で実行する）
+pub fn launch_command(path: &Path, args: &[String]) -> (PathBuf, Vec<String>) {
+    let is_powershell = path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.eq_ignore_ascii_case("ps1"))
+        .unwrap_or(false);
+
+    if is_powershell {
+        let mut ps_args = vec![
+            "-NoProfile".to_string(),
+            "-ExecutionPolicy".to_string(),
+            "Bypass".to_string(),
+            "-File".to_string(),
+            path.to_string_lossy().into_owned(),
+        ];
+        ps_args.extend(args.iter().cloned());
+        return (PathBuf::from("powershell"), ps_args);
+    }
+
+    (path.to_path_buf(), args.to_vec())
+}
+
+/// 正規化で付く `\\?\` 接頭辞を表示用に取り除く（実行には元のパスを使う）
+pub fn display_path(path: &Path) -> String {
+    let text = path.to_string_lossy();
+    if let Some(unc) = text.strip_prefix(r"\\?\UNC\") {
+        format!(r"\\{}", unc)
+    } else if let Some(local) = text.strip_prefix(r"\\?\") {
+        local.to_string()
+    } else {
+        text.into_owned()
+    }
+}
+
+/// ShellExecuteW で既定のアプリケーションを使ってファイルや URL を開く（`cmd /C start` を経由しない）
+#[cfg(target_os = "windows")]
+pub fn shell_open(target: impl AsRef<std::ffi::OsStr>) -> std::io::Result<()> {
+    use std::os::windows::ffi::OsStrExt;
+    use winapi::um::shellapi::ShellExecuteW;
+    use winapi::um::winuser::SW_SHOWNORMAL;
+
+    let wide = |s: &std::ffi::OsStr| -> Vec<u16> { s.encode_wide().chain(std::iter::once(0)).collect() };
+    let operation = wide(std::ffi::OsStr::new("open"));
+    let file = wide(target.as_ref());
+
+    let result = unsafe {
+        ShellExecuteW(
+            std::ptr::null_mut(),
+            operation.as_ptr(),
+            file.as_ptr(),
+            std::ptr::null(),
+            std::ptr::null(),
+            SW_SHOWNORMAL,
+        )
+    };
+
+    // 32 より大きい値が成功を表す
+    if result as isize > 32 {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error())
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn touch(dir: &Path, name: &str) -> PathBuf {
+        let path = dir.join(name);
+        std::fs::write(&path, "").unwrap();
+        path
+    }
+
+    #[test]
+    fn pathext_is_normalized() {
+        assert_eq!(pathext_list(Some(".EXE; .Cmd;;bat;.")), [".exe", ".cmd"]);
+        assert_eq!(pathext_list(Some("  ")), [".com", ".exe", ".bat", ".cmd"]);
+        assert_eq!(pathext_list(None), pathext_list(Some(DEFAULT_PATHEXT)));
+    }
+
+    // PATH order wins over PATHEXT order, and PATHEXT order over the name as typed without an extension
+    #[test]
+    fn resolution_follows_path_then_pathext_order() {
+        let first = tempfile::tempdir().unwrap();
+        let second = tempfile::tempdir().unwrap();
+        let dirs = [first.path().to_path_buf(), second.path().to_path_buf()];
+        let pathext = pathext_list(Some(".COM;.EXE;.BAT"));
+
+        let tool_bat = touch(first.path(), "tool.bat");
+        touch(second.path(), "tool.exe");
+        touch(first.path(), "tool");
+        assert_eq!(resolve_in_path("tool", &dirs, &pathext), Some(tool_bat.clone()));
+
+        let tool_com = touch(first.path(), "tool.com");
+        assert_eq!(resolve_in_path("tool", &dirs, &pathext), Some(tool_com));
+        // A typed listed extension is taken as is
+        assert_eq!(resolve_in_path("tool.bat", &dirs, &pathext), Some(tool_bat));
+        assert_eq!(resolve_in_path("missing", &dirs, &pathext), None);
+    }
+
+    #[test]
+    fn commands_with_a_directory_skip_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let elsewhere = tempfile::tempdir().unwrap();
+        let script = touch(dir.path(), "build.cmd");
+        touch(elsewhere.path(), "build.cmd");
+        let pathext = pathext_list(None);
+
+        let typed = dir.path().join("build").to_string_lossy().into_owned();
+        assert_eq!(resolve_in_path(&typed, &[elsewhere.path().to_path_buf()], &pathext), Some(script));
+        assert!(is_windows_executable(Path::new("C:/tools/deploy.PS1"), &pathext));
+        assert!(!is_windows_executable(Path::new("C:/tools/notes.txt"), &pathext));
+    }
+
+    // Paths are handed over as one argument each, never through a shell that would read & ^ ( )
+    #[test]
+    fn adversarial_paths_stay_single_arguments() {
+        let args = vec!["--flag".to_string()];
+        for path in [
+            r"C:\Program Files (x86)\Tool\tool.exe",
+            r"C:\Users\me\R&D\run^fast.bat",
+            "/home/me/My Scripts (old)/a&b.sh",
+        ] {
+            assert_eq!(launch_command(Path::new(path), &args), (PathBuf::from(path), args.clone()));
+        }
+
+        let script = r"C:\Users\me\R&D (1)\deploy ^prod.ps1";
+        let (program, ps_args) = launch_command(Path::new(script), &args);
+        assert_eq!(program, PathBuf::from("powershell"));
+        assert_eq!(ps_args, ["-NoProfile", "-ExecutionPolicy", "Bypass", "-File", script, "--flag"]);
+    }
+
+    #[test]
+    fn verbatim_prefixes_are_hidden_from_display() {
+        assert_eq!(display_path(Path::new(r"\\?\C:\Tools\a b.exe")), r"C:\Tools\a b.exe");
+        assert_eq!(display_path(Path::new(r"\\?\UNC\server\share\x.txt")), r"\\server\share\x.txt");
+        assert_eq!(display_path(Path::new("/usr/bin/env")), "/usr/bin/env");
+    }
+}
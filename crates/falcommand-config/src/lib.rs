@@ -1,5 +1,12 @@
 pub mod config;
 pub mod types;
+pub mod paths;
+pub mod i18n;
+pub mod launch;
+pub mod schema;
+pub mod theme;
 
 pub use config::*;
-pub use types::*;
\ No newline at end of file
+pub use types::*;
+pub use paths::*;
+pub use theme::*;
\ No newline at end of file
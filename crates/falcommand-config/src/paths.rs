@@ -0,0 +1,322 @@
+use std::path::PathBuf;
+
+use crate::config::ConfigError;
+
+/// 設定・データファイルの配置を一元的に解決する
+///
+/// プロファイルが選択されている場合、履歴や使用状況などのプロファイル固有データは
+/// `<config_dir>/falcommand/profiles/<name>/` 配下に配置される。
+/// ポータブルモードでは全てのパスが実行ファイルのディレクトリ配下に解決される。
+/// リリースビルドの Linux では、キャッシュは `XDG_CACHE_HOME`、実行時ソケットは `XDG_RUNTIME_DIR` に置かれる
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathResolver {
+    config_root: PathBuf,
+    data_root: PathBuf,
+    cache_root: PathBuf,
+    runtime_root: Option<PathBuf>,
+    profile: Option<String>,
+    portable: bool,
+}
+
+impl PathResolver {
+    pub const PROFILES_DIR: &'static str = "profiles";
+    pub const PORTABLE_MARKER: &'static str = "portable.marker";
+
+    /// `--portable` 指定時、または実行ファイルの隣に `portable.marker` がある場合はポータブルモードで解決する
+    pub fn detect(portable: bool, profile: Option<String>) -> Result<Self, ConfigError> {
+        let executable_dir = Self::executable_dir();
+        let marker_present = executable_dir.as_ref()
+            .map(|dir| dir.join(Self::PORTABLE_MARKER).exists())
+            .unwrap_or(false);
+
+        if portable || marker_present {
+            let root = executable_dir
+                .ok_or_else(|| ConfigError::FileSystemError("Cannot determine executable directory".to_string()))?;
+            return Ok(Self::portable(root, profile));
+        }
+
+        Self::new(profile)
+    }
+
+    pub fn portable(root: PathBuf, profile: Option<String>) -> Self {
+        Self {
+            portable: true,
+            ..Self::with_roots(root.join("config"), root.join("data"), profile)
+        }
+    }
+
+    fn executable_dir() -> Option<PathBuf> {
+        let executable = std::env::current_exe().ok()?;
+        executable.parent().map(|dir| dir.to_path_buf())
+    }
+
+    pub fn new(profile: Option<String>) -> Result<Self, ConfigError> {
+        Self::for_build(cfg!(debug_assertions), profile)
+    }
+
+    fn for_build(debug_build: bool, profile: Option<String>) -> Result<Self, ConfigError> {
+        if debug_build {
+            // デバッグビルド（開発中）の場合はプロジェクトルートの.falcommandフォルダを使用
+            let current_dir = std::env::current_dir()
+                .map_err(|e| ConfigError::FileSystemError(format!("Cannot determine current directory: {}", e)))?;
+            let root = current_dir.join(".falcommand");
+            return Ok(Self::with_roots(root.clone(), root, profile));
+        }
+
+        // リリースビルドの場合はシステムの設定・データ・キャッシュディレクトリを使用
+        let config_root = dirs::config_dir()
+            .ok_or_else(|| ConfigError::FileSystemError("Cannot determine config directory".to_string()))?
+            .join("falcommand");
+        let data_root = dirs::data_dir()
+            .ok_or_else(|| ConfigError::FileSystemError("Cannot determine data directory".to_string()))?
+            .join("falcommand");
+
+        // XDG_RUNTIME_DIR は Linux でのみ設定される
+        Ok(Self::with_roots(config_root, data_root, profile).with_system_dirs(dirs::cache_dir(), dirs::runtime_dir()))
+    }
+
+    /// システムのキャッシュ・実行時ディレクトリ（`XDG_CACHE_HOME`、`XDG_RUNTIME_DIR` など）の下に置く
+    fn with_system_dirs(mut self, cache_dir: Option<PathBuf>, runtime_dir: Option<PathBuf>) -> Self {
+        if let Some(cache_dir) = cache_dir {
+            self.cache_root = cache_dir.join("falcommand");
+        }
+        self.runtime_root = runtime_dir.map(|dir| dir.join("falcommand"));
+        self
+    }
+
+    pub fn with_roots(config_root: PathBuf, data_root: PathBuf, profile: Option<String>) -> Self {
+        Self {
+            cache_root: data_root.join("cache"),
+            runtime_root: None,
+            config_root,
+            data_root,
+            profile: profile.filter(|p| !p.is_empty()),
+            portable: false,
+        }
+    }
+
+    pub fn with_profile(&self, profile: Option<String>) -> Self {
+        Self {
+            profile: profile.filter(|p| !p.is_empty()),
+            ..self.clone()
+        }
+    }
+
+    /// ポータブルモードでは自動起動の登録やキーリングなど、ディレクトリ外への書き込みを行わない
+    pub fn is_portable(&self) -> bool {
+        self.portable
+    }
+
+    pub fn profile(&self) -> Option<&str> {
+        self.profile.as_deref()
+    }
+
+    pub fn config_dir(&self) -> PathBuf {
+        self.config_root.clone()
+    }
+
+    /// ユーザーのテーマファイル（`<name>.json`）の置き場所。プロファイル間で共有する
+    pub fn themes_dir(&self) -> PathBuf {
+        self.config_root.join("themes")
+    }
+
+    pub fn config_file(&self) -> PathBuf {
+        self.config_root.join("config.json")
+    }
+
+    pub fn platform_config_file(&self) -> PathBuf {
+        self.config_root.join(Self::platform_file_name("config", "json"))
+    }
+
+    pub fn debug_config_file(&self) -> PathBuf {
+        self.config_root.join("config.debug.json")
+    }
+
+    pub fn platform_debug_config_file(&self) -> PathBuf {
+        self.config_root.join(Self::platform_file_name("config.debug", "json"))
+    }
+
+    pub fn profiles_dir(&self) -> PathBuf {
+        self.config_root.join(Self::PROFILES_DIR)
+    }
+
+    pub fn profile_dir(&self) -> Option<PathBuf> {
+        self.profile.as_ref().map(|name| self.profiles_dir().join(name))
+    }
+
+    pub fn profile_config_file(&self) -> Option<PathBuf> {
+        self.profile_dir().map(|dir| dir.join("config.json"))
+    }
+
+    /// 設定の保存先（プロファイルがあればプロファイルの設定ファイル、なければベース設定）
+    pub fn active_config_file(&self) -> PathBuf {
+        self.profile_config_file().unwrap_or_else(|| self.config_file())
+    }
+
+    /// プロファイル固有データ（履歴、使用状況、ピン留めなど）の保存先
+    pub fn data_dir(&self) -> PathBuf {
+        self.profile_dir().unwrap_or_else(|| self.data_root.clone())
+    }
+
+    pub fn data_file(&self, name: &str) -> PathBuf {
+        self.data_dir().join(name)
+    }
+
+    /// プロファイル間で共有されるデータ（インデックス、クラッシュレポートなど）の保存先
+    pub fn shared_data_dir(&self) -> PathBuf {
+        self.data_root.clone()
+    }
+
+    pub fn shared_data_file(&self, name: &str) -> PathBuf {
+        self.data_root.join(name)
+    }
+
+    pub fn logs_dir(&self) -> PathBuf {
+        self.data_root.join("logs")
+    }
+
+    /// 削除しても再生成できるデータ（インデックスキャッシュ、アイコン、サムネイル）の保存先
+    pub fn cache_dir(&self) -> PathBuf {
+        self.cache_root.clone()
+    }
+
+    pub fn cache_file(&self, name: &str) -> PathBuf {
+        self.cache_root.join(name)
+    }
+
+    pub fn icon_cache_dir(&self) -> PathBuf {
+        self.cache_root.join("icons")
+    }
+
+    pub fn thumbnails_dir(&self) -> PathBuf {
+        self.cache_root.join("thumbnails")
+    }
+
+    /// 単一インスタンス用 IPC ソケットなど実行時ファイルの保存先（`XDG_RUNTIME_DIR` がなければキャッシュ配下）
+    pub fn runtime_dir(&self) -> PathBuf {
+        self.runtime_root.clone().unwrap_or_else(|| self.cache_root.join("run"))
+    }
+
+    pub fn plugins_dir(&self) -> PathBuf {
+        self.data_root.join("plugins")
+    }
+
+    pub fn backups_dir(&self) -> PathBuf {
+        self.data_root.join("backups")
+    }
+
+    fn platform_file_name(stem: &str, extension: &str) -> String {
+        let platform = if cfg!(target_os = "windows") {
+            Some("windows")
+        } else if cfg!(target_os = "macos") {
+            Some("macos")
+        } else if cfg!(target_os = "linux") {
+            Some("linux")
+        } else {
+            None
+        };
+
+        match platform {
+            Some(platform) => format!("{}.{}.{}", stem, platform, extension),
+            None => format!("{}.{}", stem, extension),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn debug_builds_keep_everything_in_the_working_directory() {
+        let root = std::env::current_dir().unwrap().join(".falcommand");
+
+        let base = PathResolver::for_build(true, None).unwrap();
+        assert_eq!(base.config_file(), root.join("config.json"));
+        assert_eq!(base.data_file("history.json"), root.join("history.json"));
+        assert_eq!(base.cache_dir(), root.join("cache"));
+
+        let work = PathResolver::for_build(true, Some("work".to_string())).unwrap();
+        assert_eq!(work.active_config_file(), root.join("profiles/work/config.json"));
+        assert_eq!(work.data_file("history.json"), root.join("profiles/work/history.json"));
+        assert_eq!(work.shared_data_file("index.json"), root.join("index.json"));
+    }
+
+    #[test]
+    fn release_builds_use_the_system_directories() {
+        let config_root = dirs::config_dir().unwrap().join("falcommand");
+        let data_root = dirs::data_dir().unwrap().join("falcommand");
+
+        let base = PathResolver::for_build(false, None).unwrap();
+        assert_eq!(base.config_file(), config_root.join("config.json"));
+        assert_eq!(base.data_file("history.json"), data_root.join("history.json"));
+        assert_eq!(base.cache_dir(), dirs::cache_dir().unwrap().join("falcommand"));
+
+        // Profile data sits with the profile's config, shared data stays in the data directory
+        let work = PathResolver::for_build(false, Some("work".to_string())).unwrap();
+        assert_eq!(work.active_config_file(), config_root.join("profiles/work/config.json"));
+        assert_eq!(work.data_file("history.json"), config_root.join("profiles/work/history.json"));
+        assert_eq!(work.shared_data_file("index.json"), data_root.join("index.json"));
+    }
+
+    #[test]
+    fn cache_and_runtime_files_follow_the_xdg_dirs() {
+        let resolver = PathResolver::with_roots("/c".into(), "/d".into(), Some("work".to_string()))
+            .with_system_dirs(Some("/xdg/cache".into()), Some("/run/user/1000".into()));
+        assert_eq!(resolver.cache_file("index.json"), PathBuf::from("/xdg/cache/falcommand/index.json"));
+        assert_eq!(resolver.icon_cache_dir(), PathBuf::from("/xdg/cache/falcommand/icons"));
+        assert_eq!(resolver.runtime_dir(), PathBuf::from("/run/user/1000/falcommand"));
+        // Config and data stay where they were
+        assert_eq!(resolver.data_file("history.json"), PathBuf::from("/c/profiles/work/history.json"));
+        assert_eq!(resolver.logs_dir(), PathBuf::from("/d/logs"));
+
+        // Without XDG_RUNTIME_DIR (macOS, Windows) the socket goes under the cache
+        let resolver = PathResolver::with_roots("/c".into(), "/d".into(), None).with_system_dirs(None, None);
+        assert_eq!(resolver.cache_dir(), PathBuf::from("/d/cache"));
+        assert_eq!(resolver.runtime_dir(), PathBuf::from("/d/cache/run"));
+    }
+
+    #[test]
+    fn portable_mode_keeps_everything_under_the_executable_directory() {
+        let root = PathBuf::from("/usb/falcommand");
+        let resolver = PathResolver::portable(root.clone(), Some("work".to_string()));
+        assert!(resolver.is_portable());
+
+        let paths = [
+            resolver.config_dir(),
+            resolver.config_file(),
+            resolver.active_config_file(),
+            resolver.data_dir(),
+            resolver.shared_data_dir(),
+            resolver.logs_dir(),
+            resolver.cache_dir(),
+            resolver.runtime_dir(),
+            resolver.plugins_dir(),
+            resolver.backups_dir(),
+            resolver.profiles_dir(),
+        ];
+        for path in &paths {
+            assert!(path.starts_with(&root), "{:?} is outside {:?}", path, root);
+        }
+        // None of the system directories leak in
+        let system_dirs = [dirs::config_dir(), dirs::data_dir(), dirs::cache_dir(), dirs::runtime_dir()];
+        for dir in system_dirs.iter().flatten() {
+            assert!(paths.iter().all(|path| !path.starts_with(dir)), "{:?} leaked in", dir);
+        }
+
+        assert_eq!(resolver.cache_dir(), root.join("data/cache"));
+        assert_eq!(resolver.runtime_dir(), root.join("data/cache/run"));
+        assert_eq!(resolver.data_file("history.json"), root.join("config/profiles/work/history.json"));
+    }
+
+    #[test]
+    fn an_empty_profile_is_the_base_profile() {
+        let base = PathResolver::with_roots("/c".into(), "/d".into(), Some(String::new()));
+        assert_eq!(base.profile(), None);
+        assert_eq!(base.active_config_file(), PathBuf::from("/c/config.json"));
+
+        let work = base.with_profile(Some("work".to_string()));
+        assert_eq!(work.data_dir(), PathBuf::from("/c/profiles/work"));
+        assert_eq!(work.with_profile(None), base);
+    }
+}
@@ -0,0 +1,505 @@
+use serde_json::{json, Map, Value};
+
+use crate::config::{Config, BATTERY_THRESHOLD_RANGE, CONTEXT_BOOST_RANGE, FUZZY_THRESHOLD_RANGE, MAX_RESULTS_RANGE, RANKING_WEIGHT_RANGE, TRANSPARENCY_RANGE, USAGE_HALF_LIFE_RANGE};
+
+const SCHEMA_DRAFT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+fn boolean(description: &str) -> Value {
+    json!({ "type": "boolean", "description": description })
+}
+
+fn string(description: &str) -> Value {
+    json!({ "type": "string", "description": description })
+}
+
+fn integer(description: &str) -> Value {
+    json!({ "type": "integer", "minimum": 0, "description": description })
+}
+
+fn integer_range(description: &str, min: usize, max: usize) -> Value {
+    json!({ "type": "integer", "minimum": min, "maximum": max, "description": description })
+}
+
+fn number_range(description: &str, min: f64, max: f64) -> Value {
+    json!({ "type": "number", "minimum": min, "maximum": max, "description": description })
+}
+
+fn string_enum(description: &str, values: &[&str]) -> Value {
+    json!({ "type": "string", "enum": values, "description": description })
+}
+
+fn nullable_string(description: &str) -> Value {
+    json!({ "type": ["string", "null"], "description": description })
+}
+
+fn string_list(description: &str) -> Value {
+    json!({ "type": "array", "items": { "type": "string" }, "description": description })
+}
+
+fn map_of(description: &str, values: Value) -> Value {
+    json!({ "type": "object", "additionalProperties": values, "description": description })
+}
+
+fn object(description: &str, fields: Vec<(&str, Value)>) -> Value {
+    let properties: Map<String, Value> = fields.into_iter()
+        .map(|(name, schema)| (name.to_string(), schema))
+        .collect();
+    json!({
+        "type": "object",
+        "description": description,
+        "properties": properties,
+        "additionalProperties": false,
+    })
+}
+
+fn theme() -> Value {
+    json!({
+        "description": "Color theme: \"Light\", \"Dark\", \"System\" or {\"Custom\": \"<name>\"} for themes/<name>.json",
+        "oneOf": [
+            { "type": "string", "enum": ["Light", "Dark", "System"] },
+            {
+                "type": "object",
+                "properties": { "Custom": { "type": "string" } },
+                "additionalProperties": false,
+            }
+        ]
+    })
+}
+
+fn window_position() -> Value {
+    json!({
+        "description": "Where the window opens: \"Center\", \"Cursor\" or {\"Custom\": {\"x\": .., \"y\": ..}}",
+        "oneOf": [
+            { "type": "string", "enum": ["Center", "Cursor"] },
+            {
+                "type": "object",
+                "properties": {
+                    "Custom": {
+                        "type": "object",
+                        "properties": { "x": { "type": "integer" }, "y": { "type": "integer" } },
+                        "additionalProperties": false,
+                    }
+                },
+                "additionalProperties": false,
+            }
+        ]
+    })
+}
+
+fn custom_command() -> Value {
+    json!({
+        "type": "object",
+        "description": "A command run by name: one action, or a macro of actions run in order",
+        "properties": {
+            "name": { "type": "string", "description": "Name the command is searched by" },
+            "description": { "type": "string", "description": "Shown under the name" },
+            "action": { "description": "The action of a single-action command" },
+            "actions": {
+                "type": "array",
+                "items": {},
+                "description": "The steps of a macro; {\"RunCustomCommand\": \"name\"} runs another custom command",
+            },
+            "step_delay_ms": { "type": "integer", "minimum": 0, "description": "Milliseconds between macro steps" },
+            "stop_on_failure": { "type": "boolean", "description": "Skip the remaining steps after one fails" },
+        },
+        "additionalProperties": false,
+    })
+}
+
+fn structure() -> Value {
+    let transparency = (*TRANSPARENCY_RANGE.start() as f64, *TRANSPARENCY_RANGE.end() as f64);
+    let fuzzy = (*FUZZY_THRESHOLD_RANGE.start(), *FUZZY_THRESHOLD_RANGE.end());
+    let max_results = (*MAX_RESULTS_RANGE.start(), *MAX_RESULTS_RANGE.end());
+    let weight = (*RANKING_WEIGHT_RANGE.start(), *RANKING_WEIGHT_RANGE.end());
+
+    object("FalCommand configuration", vec![
+        ("appearance", object("Window appearance", vec![
+            ("theme", theme()),
+            ("transparency", number_range("Window opacity", transparency.0, transparency.1)),
+            ("position", window_position()),
+            ("show_window", string_enum("Which display the window opens on", &["Mouse", "Display"])),
+            ("show_window_display_number", integer("Display index used when show_window is Display")),
+            ("font_size", integer("Font size in points")),
+            ("visible_result_rows", integer_range("Number of result rows the window shows; at most behavior.max_results", max_results.0, max_results.1)),
+            ("enable_system_tray", boolean("Show the tray icon")),
+            ("start_in_tray", boolean("Start hidden in the tray")),
+            ("minimize_to_tray", boolean("Hide to the tray instead of minimizing")),
+            ("fade_in", boolean("Fade the window in when it is shown; input is never delayed")),
+            ("reduced_motion", boolean("Disable animations, overriding fade_in")),
+            ("language", nullable_string("Display language and number/date format: \"en\" or \"ja\"; the OS locale when unset")),
+            ("result_row_layout", string_enum("What each result row shows", &["TitleOnly", "TitleDescription", "TitlePath", "Detailed"])),
+            ("show_category_badge", boolean("Add a category badge to every row, whatever the layout")),
+            ("show_score_debug", boolean("Show each result's score, for tuning the ranking")),
+            ("show_shortcut_hints", boolean("Show the extra actions a row offers")),
+        ])),
+        ("behavior", object("Launcher behavior", vec![
+            ("hotkey", string("Global hotkey that toggles the window")),
+            ("auto_hide", boolean("Hide the window after running a result")),
+            ("max_results", integer_range("Maximum number of results per search", max_results.0, max_results.1)),
+            ("rebuild_index_on_startup", boolean("Rebuild the application and file index at startup")),
+            ("save_search_history", boolean("Remember queries for completion and stats")),
+            ("record_usage_stats", boolean("Record launches for ranking and the stats command")),
+            ("share_actions", boolean("Add copy-as-markdown, copy-as-JSON and email secondary actions")),
+            ("hotkeys", map_of("Extra hotkeys by intent: \"toggle\" overrides hotkey, any other intent is a plugin id opened with its prefix", json!({ "type": "string" }))),
+            ("history_retention_days", integer("Days search history is kept; 0 keeps it forever")),
+            ("clipboard_undo_secs", integer("Seconds a clipboard change can be undone; 0 turns undo off")),
+            ("clipboard_suggestions", boolean("Read the clipboard when the window opens and offer to open, calculate or search what was copied")),
+        ])),
+        ("search", object("Indexing and matching", vec![
+            ("include_paths", map_of("Directories to index, keyed by OS (windows, macos, linux)", json!({ "type": "array", "items": { "type": "string" } }))),
+            ("exclude_patterns", string_list("Glob patterns and folders that are never indexed as files")),
+            ("fuzzy_threshold", number_range("Minimum fuzzy match score", fuzzy.0, fuzzy.1)),
+            ("enable_file_search", boolean("Search indexed files")),
+            ("enable_app_search", boolean("Search installed applications")),
+            ("enable_web_search", boolean("Offer web search results")),
+            ("app_aliases", map_of("Extra names for applications (alias -> application name)", json!({ "type": "string" }))),
+            ("allow_sensitive_paths", string_list("Paths indexed even though they are inside a built-in sensitive directory")),
+            ("ungrouped_apps", string_list("Apps whose versions are listed separately instead of grouped into one result")),
+            ("app_exclude_patterns", string_list("Apps hidden from search: patterns with a path separator match the executable path, others the app name")),
+            ("index_archive_contents", boolean("Also index the files listed inside .zip and .tar.gz archives")),
+            ("archive_size_limit_mb", integer("Archives larger than this many MB are indexed as plain files only")),
+            ("category_priority", string_list("Order of categories for results with equal scores: application, file, bookmark, plugin (or plugin:<id>), system, custom")),
+            ("isolated_extraction", boolean("Parse archives and other untrusted files in the falcommand-worker helper process; falls back to in-process parsing when it can't start")),
+            ("open_with", map_of("Applications offered first by Open with… for each file extension (extension -> application names); picks are learned on top", json!({ "type": "array", "items": { "type": "string" } }))),
+            ("min_results_per_source", integer("Results kept per source (category) with a match above the fuzzy threshold, even when other sources fill max_results")),
+            ("cache_sample_size", integer("Entries of the index cache checked for existence on start; too many missing triggers a rebuild. 0 skips the check")),
+            ("query_macros", map_of("Saved queries typed as @name (name -> query); {args} is replaced by what follows the name", json!({ "type": "string" }))),
+        ])),
+        ("plugins", object("Plugins", vec![
+            ("enabled", string_list("Plugin ids to load")),
+            ("disabled", string_list("Plugin ids never loaded, even if also enabled")),
+            ("plugin_settings", map_of("Settings per plugin id", json!({}))),
+        ])),
+        ("sync", object("Settings sync", vec![
+            ("enabled", boolean("Sync settings and history")),
+            ("provider", nullable_string("Sync provider name")),
+            ("auto_sync_interval", integer("Seconds between automatic syncs")),
+            ("encrypt_data", boolean("Encrypt synced data")),
+        ])),
+        ("crash_reporting", object("Crash reports", vec![
+            ("upload_enabled", boolean("Upload crash reports; requires upload_url")),
+            ("upload_url", nullable_string("Where crash reports are uploaded")),
+        ])),
+        ("watchdog", object("UI responsiveness watchdog", vec![
+            ("stall_threshold_ms", integer("Milliseconds without an event loop heartbeat before the UI counts as stalled")),
+            ("auto_restart_ui", boolean("Recreate the window after repeated stalls; indexes and plugins keep running")),
+        ])),
+        ("ranking", object("Weights of the parts that make up a result's score", vec![
+            ("fuzzy_weight", number_range("Share of the fuzzy match score mixed into the source's score", weight.0, weight.1)),
+            ("selection_weight", number_range("Multiplier for the bonus of results picked before for the same query", weight.0, weight.1)),
+            ("usage_weight", number_range("Largest bonus from how often a result was launched; 0 disables it", weight.0, weight.1)),
+            ("usage_half_life_days", number_range("Days after which a launch counts half as much for the usage bonus", *USAGE_HALF_LIFE_RANGE.start(), *USAGE_HALF_LIFE_RANGE.end())),
+            ("context_boosts", map_of(
+                "File result bonuses while an app is in front, keyed by part of its name, e.g. \"code\": {\"rs\": 0.1}",
+                map_of("Bonus per file extension", number_range("Bonus", *CONTEXT_BOOST_RANGE.start(), *CONTEXT_BOOST_RANGE.end())),
+            )),
+            ("pinned", map_of(
+                "Results kept at the top for a query, keyed by the query, e.g. \"term\": [\"Alacritty\"]",
+                string_list("Titles of the pinned results, in order"),
+            )),
+        ])),
+        ("power", object("Background work on battery power", vec![
+            ("defer_on_battery", boolean("Defer periodic background work (sync, probes, maintenance, rescans) on battery below the threshold; it resumes on AC")),
+            ("battery_threshold_percent", integer_range("Battery percentage below which background work is deferred", *BATTERY_THRESHOLD_RANGE.start() as usize, *BATTERY_THRESHOLD_RANGE.end() as usize)),
+        ])),
+        ("default_profile", nullable_string("Profile used when --profile is not given")),
+        ("custom_commands", json!({
+            "type": "array",
+            "items": custom_command(),
+            "description": "Commands and macros run by name",
+        })),
+    ])
+}
+
+// f32 fields serialize with widening noise (0.95 -> 0.949999988...)
+fn tidy_default(value: &Value) -> Value {
+    match value {
+        Value::Number(number) if number.is_f64() => json!((number.as_f64().unwrap_or_default() * 1e6).round() / 1e6),
+        Value::Array(items) => Value::Array(items.iter().map(tidy_default).collect()),
+        Value::Object(fields) => Value::Object(fields.iter().map(|(k, v)| (k.clone(), tidy_default(v))).collect()),
+        _ => value.clone(),
+    }
+}
+
+// Copies each value of `defaults` into the matching property's "default"
+fn apply_defaults(schema: &mut Value, defaults: &Value) {
+    if let Value::Object(ref mut node) = schema {
+        node.insert("default".to_string(), tidy_default(defaults));
+    }
+
+    let (Some(properties), Value::Object(values)) = (schema.get_mut("properties").and_then(Value::as_object_mut), defaults) else {
+        return;
+    };
+    for (name, value) in values {
+        match properties.get_mut(name) {
+            Some(property) => apply_defaults(property, value),
+            None => log::warn!("Config field '{}' is missing from the schema", name),
+        }
+    }
+}
+
+/// Config 型の JSON Schema を生成する（既定値は Config::default()、範囲は validate と同じ定数から）
+pub fn config_schema() -> Value {
+    let mut schema = structure();
+    let defaults = serde_json::to_value(Config::default()).unwrap_or(Value::Null);
+    apply_defaults(&mut schema, &defaults);
+
+    if let Value::Object(ref mut root) = schema {
+        root.remove("default");
+        root.insert("$schema".to_string(), json!(SCHEMA_DRAFT));
+        root.insert("title".to_string(), json!("FalCommand configuration"));
+    }
+    schema
+}
+
+fn type_matches(expected: &str, value: &Value) -> bool {
+    match expected {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        _ => true,
+    }
+}
+
+fn check(value: &Value, schema: &Value, path: &str, errors: &mut Vec<String>) {
+    if let Some(options) = schema.get("oneOf").and_then(Value::as_array) {
+        let matching = options.iter()
+            .filter(|option| {
+                let mut option_errors = Vec::new();
+                check(value, option, path, &mut option_errors);
+                option_errors.is_empty()
+            })
+            .count();
+        if matching != 1 {
+            errors.push(format!("{}: does not match exactly one allowed form", path));
+        }
+        return;
+    }
+
+    let types: Vec<&str> = match schema.get("type") {
+        Some(Value::String(t)) => vec![t.as_str()],
+        Some(Value::Array(ts)) => ts.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    };
+    if !types.is_empty() && !types.iter().any(|t| type_matches(t, value)) {
+        errors.push(format!("{}: expected {}", path, types.join(" or ")));
+        return;
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        if !allowed.contains(value) {
+            errors.push(format!("{}: must be one of {}", path, Value::Array(allowed.clone())));
+        }
+    }
+
+    if let Some(number) = value.as_f64() {
+        if let Some(min) = schema.get("minimum").and_then(Value::as_f64) {
+            if number < min {
+                errors.push(format!("{}: must be at least {}", path, min));
+            }
+        }
+        if let Some(max) = schema.get("maximum").and_then(Value::as_f64) {
+            if number > max {
+                errors.push(format!("{}: must be at most {}", path, max));
+            }
+        }
+    }
+
+    if let Some(items) = value.as_array() {
+        if let Some(item_schema) = schema.get("items") {
+            for (i, item) in items.iter().enumerate() {
+                check(item, item_schema, &format!("{}[{}]", path, i), errors);
+            }
+        }
+    }
+
+    if let Some(fields) = value.as_object() {
+        let properties = schema.get("properties").and_then(Value::as_object);
+        for (name, field) in fields {
+            let field_path = if path.is_empty() { name.clone() } else { format!("{}.{}", path, name) };
+            match (properties.and_then(|p| p.get(name)), schema.get("additionalProperties")) {
+                (Some(property), _) => check(field, property, &field_path, errors),
+                (None, Some(Value::Bool(false))) => errors.push(format!("{}: unknown field", field_path)),
+                (None, Some(extra)) if extra.is_object() => check(field, extra, &field_path, errors),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// 生成したスキーマの範囲で値を検証し、違反箇所を `path: 理由` 形式で返す
+///
+/// 設定 UI や外部ツールが使う部分集合（type, enum, minimum, maximum, properties, items, oneOf）だけを扱う
+pub fn validate_against_schema(value: &Value, schema: &Value) -> Result<(), Vec<String>> {
+    let mut errors = Vec::new();
+    check(value, schema, "", &mut errors);
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+fn type_label(schema: &Value) -> String {
+    if schema.get("oneOf").is_some() {
+        return "string or object".to_string();
+    }
+    match schema.get("type") {
+        Some(Value::String(t)) => t.clone(),
+        Some(Value::Array(ts)) => ts.iter().filter_map(Value::as_str).collect::<Vec<_>>().join(" or "),
+        _ => "any".to_string(),
+    }
+}
+
+fn constraint_label(schema: &Value) -> String {
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        return allowed.iter().filter_map(Value::as_str).collect::<Vec<_>>().join(", ");
+    }
+    match (schema.get("minimum"), schema.get("maximum")) {
+        (Some(min), Some(max)) => format!("{} – {}", min, max),
+        (Some(min), None) => format!("≥ {}", min),
+        (None, Some(max)) => format!("≤ {}", max),
+        (None, None) => String::new(),
+    }
+}
+
+fn markdown_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+fn document_fields(schema: &Value, prefix: &str, rows: &mut Vec<String>) {
+    let Some(properties) = schema.get("properties").and_then(Value::as_object) else {
+        return;
+    };
+
+    for (name, property) in properties {
+        let key = if prefix.is_empty() { name.clone() } else { format!("{}.{}", prefix, name) };
+        if property.get("properties").is_some() {
+            document_fields(property, &key, rows);
+            continue;
+        }
+
+        let default = property.get("default").map(|d| d.to_string()).unwrap_or_default();
+        let description = property.get("description").and_then(Value::as_str).unwrap_or_default();
+        rows.push(format!(
+            "| `{}` | {} | `{}` | {} | {} |",
+            key,
+            type_label(property),
+            markdown_cell(&default),
+            markdown_cell(&constraint_label(property)),
+            markdown_cell(description),
+        ));
+    }
+}
+
+/// スキーマから設定項目の Markdown 表を生成する
+pub fn config_markdown(schema: &Value) -> String {
+    let mut rows = vec![
+        "| Key | Type | Default | Allowed | Description |".to_string(),
+        "| --- | --- | --- | --- | --- |".to_string(),
+    ];
+    document_fields(schema, "", &mut rows);
+
+    let title = schema.get("title").and_then(Value::as_str).unwrap_or("Configuration");
+    format!("# {}\n\n{}\n", title, rows.join("\n"))
+}
+/// 設定項目の種類（設定を検索結果にするときの操作の決め手になる）
+#[derive(Debug, Clone, PartialEq)]
+pub enum SchemaFieldKind {
+    Boolean,
+    /// 取り得る値（スキーマに並んだ順）
+    Enum(Vec<String>),
+    Other,
+}
+
+/// スキーマの末端の設定項目（`path` は `behavior.hotkey` のようなドット区切り）
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaField {
+    pub path: String,
+    pub description: String,
+    pub kind: SchemaFieldKind,
+}
+
+fn collect_fields(schema: &Value, prefix: &str, fields: &mut Vec<SchemaField>) {
+    let Some(properties) = schema.get("properties").and_then(Value::as_object) else {
+        return;
+    };
+
+    for (name, property) in properties {
+        let path = if prefix.is_empty() { name.clone() } else { format!("{}.{}", prefix, name) };
+        if property.get("properties").is_some() {
+            collect_fields(property, &path, fields);
+            continue;
+        }
+
+        let kind = match (property.get("type").and_then(Value::as_str), property.get("enum").and_then(Value::as_array)) {
+            (_, Some(values)) => SchemaFieldKind::Enum(values.iter().filter_map(Value::as_str).map(String::from).collect()),
+            (Some("boolean"), None) => SchemaFieldKind::Boolean,
+            _ => SchemaFieldKind::Other,
+        };
+        let description = property.get("description").and_then(Value::as_str).unwrap_or_default().to_string();
+        fields.push(SchemaField { path, description, kind });
+    }
+}
+
+/// スキーマの設定項目をドキュメントと同じ順に列挙する
+pub fn schema_fields(schema: &Value) -> Vec<SchemaField> {
+    let mut fields = Vec::new();
+    collect_fields(schema, "", &mut fields);
+    fields
+}
+
+/// ドット区切りの `path` の値を `value` に置き換えた設定を返す（既存の項目だけ、検証に通ったものだけ）
+pub fn set_config_value(config: &Config, path: &str, value: Value) -> Result<Config, String> {
+    let mut root = serde_json::to_value(config).map_err(|e| e.to_string())?;
+    let mut target = &mut root;
+    for key in path.split('.') {
+        target = target.get_mut(key).ok_or_else(|| format!("Unknown setting: {}", path))?;
+    }
+    *target = value;
+
+    let updated: Config = serde_json::from_value(root).map_err(|e| format!("Invalid value for {}: {}", path, e))?;
+    updated.validate().map_err(|e| e.to_string())?;
+    Ok(updated)
+}
+
+/// ドット区切りの `path` の現在の値
+pub fn get_config_value(config: &Config, path: &str) -> Result<Value, String> {
+    let root = serde_json::to_value(config).map_err(|e| e.to_string())?;
+    path.split('.')
+        .try_fold(&root, |value, key| value.get(key))
+        .cloned()
+        .ok_or_else(|| format!("Unknown setting: {}", path))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn default_config() -> Value {
+        serde_json::to_value(Config::default()).unwrap()
+    }
+
+    #[test]
+    fn default_config_matches_the_schema() {
+        assert_eq!(validate_against_schema(&default_config(), &config_schema()), Ok(()));
+    }
+
+    #[test]
+    fn invalid_config_fails_schema_validation() {
+        let mut config = default_config();
+        config["behavior"]["max_results"] = json!("many");
+        config["search"]["fuzzy_threshold"] = json!(7.5);
+        config["appearance"]["colour"] = json!("red");
+
+        let errors = validate_against_schema(&config, &config_schema()).unwrap_err();
+        assert!(errors.contains(&"behavior.max_results: expected integer".to_string()), "{:?}", errors);
+        assert!(errors.iter().any(|e| e.starts_with("search.fuzzy_threshold: must be at most")), "{:?}", errors);
+        assert!(errors.contains(&"appearance.colour: unknown field".to_string()), "{:?}", errors);
+    }
+}
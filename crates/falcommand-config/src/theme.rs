@@ -0,0 +1,354 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Theme;
+use crate::types::Category;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ThemeError {
+    #[error("Invalid theme name '{0}': use a file name without separators")]
+    InvalidName(String),
+
+    #[error("Theme file not found: {0}")]
+    NotFound(PathBuf),
+
+    #[error("Failed to read theme file {path}: {reason}")]
+    ReadError { path: PathBuf, reason: String },
+
+    #[error("Invalid theme file {path}: {reason}")]
+    ParseError { path: PathBuf, reason: String },
+
+    #[error("Theme file {path} is missing '{key}'")]
+    MissingKey { path: PathBuf, key: &'static str },
+
+    #[error("'{0}' is not a color: use #rgb, #rrggbb or #rrggbbaa")]
+    InvalidColor(String),
+}
+
+/// `#rgb`、`#rrggbb`、`#rrggbbaa` で書かれた色
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl Color {
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b, a: 0xff }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, ThemeError> {
+        let invalid = || ThemeError::InvalidColor(text.to_string());
+        let hex = text.strip_prefix('#').filter(|hex| hex.chars().all(|c| c.is_ascii_hexdigit())).ok_or_else(invalid)?;
+        let digit = |index: usize| u8::from_str_radix(&hex[index..=index], 16).map_err(|_| invalid());
+        let byte = |index: usize| u8::from_str_radix(&hex[index..index + 2], 16).map_err(|_| invalid());
+        match hex.len() {
+            // "#abc" は "#aabbcc"
+            3 => Ok(Self::rgb(digit(0)? * 0x11, digit(1)? * 0x11, digit(2)? * 0x11)),
+            6 => Ok(Self::rgb(byte(0)?, byte(2)?, byte(4)?)),
+            8 => Ok(Self { a: byte(6)?, ..Self::rgb(byte(0)?, byte(2)?, byte(4)?) }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl TryFrom<String> for Color {
+    type Error = ThemeError;
+
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        Self::parse(&text)
+    }
+}
+
+impl From<Color> for String {
+    fn from(color: Color) -> Self {
+        color.to_string()
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.r, self.g, self.b)?;
+        if self.a != 0xff {
+            write!(f, "{:02x}", self.a)?;
+        }
+        Ok(())
+    }
+}
+
+/// カテゴリのバッジの色のキー
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BadgeCategory {
+    Application,
+    File,
+    Bookmark,
+    Plugin,
+    SystemCommand,
+    CustomCommand,
+}
+
+impl BadgeCategory {
+    /// プラグインの結果はプラグインによらず `plugin` の色
+    pub fn of(category: &Category) -> Self {
+        match category {
+            Category::Application => BadgeCategory::Application,
+            Category::File => BadgeCategory::File,
+            Category::Bookmark => BadgeCategory::Bookmark,
+            Category::Plugin(_) => BadgeCategory::Plugin,
+            Category::SystemCommand => BadgeCategory::SystemCommand,
+            Category::CustomCommand => BadgeCategory::CustomCommand,
+        }
+    }
+}
+
+/// UI が使う色の一式
+///
+/// `falcommand theme export` はこの形の JSON を出力し、そのままテーマファイルとして使える
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Palette {
+    pub background: Color,
+    pub foreground: Color,
+    /// 説明、バッジ、ヘルプのグループ名
+    pub muted: Color,
+    /// 履歴の補完候補やクエリの展開のヒント
+    pub faint: Color,
+    /// 入力欄の選択範囲
+    pub selection: Color,
+    /// スコアとモード表示
+    pub accent: Color,
+    pub border: Color,
+    pub badges: BTreeMap<BadgeCategory, Color>,
+}
+
+impl Palette {
+    pub fn dark() -> Self {
+        Self {
+            background: Color::rgb(0x20, 0x22, 0x25),
+            foreground: Color::rgb(0xdc, 0xdd, 0xde),
+            muted: Color::rgb(0x8e, 0x92, 0x97),
+            faint: Color::rgb(0x6b, 0x6f, 0x76),
+            selection: Color::rgb(0x3d, 0x5a, 0x80),
+            accent: Color::rgb(0xfa, 0xa6, 0x1a),
+            border: Color::rgb(0x2f, 0x31, 0x36),
+            badges: BTreeMap::from([
+                (BadgeCategory::Application, Color::rgb(0x7d, 0x9c, 0xf0)),
+                (BadgeCategory::File, Color::rgb(0x57, 0xc2, 0x8a)),
+                (BadgeCategory::Bookmark, Color::rgb(0xe0, 0xb0, 0x4f)),
+                (BadgeCategory::Plugin, Color::rgb(0xb5, 0x8c, 0xe6)),
+                (BadgeCategory::SystemCommand, Color::rgb(0xef, 0x6f, 0x6c)),
+                (BadgeCategory::CustomCommand, Color::rgb(0x4f, 0xc1, 0xd6)),
+            ]),
+        }
+    }
+
+    pub fn light() -> Self {
+        Self {
+            background: Color::rgb(0xf7, 0xf7, 0xf8),
+            foreground: Color::rgb(0x2e, 0x33, 0x38),
+            muted: Color::rgb(0x6a, 0x70, 0x78),
+            faint: Color::rgb(0xa0, 0xa4, 0xaa),
+            selection: Color::rgb(0xbf, 0xd4, 0xf5),
+            accent: Color::rgb(0xc2, 0x6a, 0x00),
+            border: Color::rgb(0xdc, 0xde, 0xe2),
+            badges: BTreeMap::from([
+                (BadgeCategory::Application, Color::rgb(0x2f, 0x5b, 0xc9)),
+                (BadgeCategory::File, Color::rgb(0x1f, 0x85, 0x4f)),
+                (BadgeCategory::Bookmark, Color::rgb(0x9a, 0x6a, 0x00)),
+                (BadgeCategory::Plugin, Color::rgb(0x7a, 0x4b, 0xb8)),
+                (BadgeCategory::SystemCommand, Color::rgb(0xc0, 0x39, 0x2b)),
+                (BadgeCategory::CustomCommand, Color::rgb(0x0e, 0x7c, 0x91)),
+            ]),
+        }
+    }
+
+    /// `Light`・`Dark` はその色、`System` とユーザーのテーマはシステムの明暗に合わせる
+    pub fn base(theme: &Theme, system_dark: bool) -> Self {
+        match theme {
+            Theme::Light => Self::light(),
+            Theme::Dark => Self::dark(),
+            Theme::System | Theme::Custom(_) if system_dark => Self::dark(),
+            Theme::System | Theme::Custom(_) => Self::light(),
+        }
+    }
+
+    /// 設定のテーマの色。ユーザーのテーマはファイルを読み、書かれていない色は `base` のもの
+    ///
+    /// ファイルが読めない場合はエラーとし、呼び出し側は `base` を使う
+    pub fn resolve(theme: &Theme, system_dark: bool, themes_dir: &Path) -> Result<Self, ThemeError> {
+        let base = Self::base(theme, system_dark);
+        match theme.custom_name() {
+            Some(name) => Ok(ThemeFile::load(&theme_file(themes_dir, name)?)?.over(&base)),
+            None => Ok(base),
+        }
+    }
+
+    pub fn badge(&self, category: &Category) -> Color {
+        self.badges.get(&BadgeCategory::of(category)).copied().unwrap_or(self.muted)
+    }
+}
+
+/// `<themes_dir>/<name>.json`。名前にパス区切りや `..` は使えない
+pub fn theme_file(themes_dir: &Path, name: &str) -> Result<PathBuf, ThemeError> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && !name.contains(['/', '\\', ':'])
+        && !name.contains("..");
+    if !valid {
+        return Err(ThemeError::InvalidName(name.to_string()));
+    }
+    Ok(themes_dir.join(format!("{}.json", name)))
+}
+
+/// ユーザーのテーマファイル。`background` と `foreground` は必須で、ほかの色は省略できる
+///
+/// ```json
+/// {"background": "#282a36", "foreground": "#f8f8f2", "accent": "#ff79c6", "badges": {"file": "#50fa7b"}}
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ThemeFile {
+    pub background: Option<Color>,
+    pub foreground: Option<Color>,
+    pub muted: Option<Color>,
+    pub faint: Option<Color>,
+    pub selection: Option<Color>,
+    pub accent: Option<Color>,
+    pub border: Option<Color>,
+    #[serde(default)]
+    pub badges: BTreeMap<BadgeCategory, Color>,
+}
+
+impl ThemeFile {
+    /// 省略すると基本の色が読めなくなる色
+    pub const REQUIRED_KEYS: [&'static str; 2] = ["background", "foreground"];
+
+    pub fn load(path: &Path) -> Result<Self, ThemeError> {
+        let text = std::fs::read_to_string(path).map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => ThemeError::NotFound(path.to_path_buf()),
+            _ => ThemeError::ReadError { path: path.to_path_buf(), reason: e.to_string() },
+        })?;
+        Self::parse(path, &text)
+    }
+
+    /// 色の書式、未知のキー、必須のキーを確認する。`path` はエラーの表示用
+    pub fn parse(path: &Path, text: &str) -> Result<Self, ThemeError> {
+        let file: Self = serde_json::from_str(text)
+            .map_err(|e| ThemeError::ParseError { path: path.to_path_buf(), reason: e.to_string() })?;
+        let present = [file.background.is_some(), file.foreground.is_some()];
+        if let Some((key, _)) = Self::REQUIRED_KEYS.iter().zip(present).find(|(_, present)| !present) {
+            return Err(ThemeError::MissingKey { path: path.to_path_buf(), key });
+        }
+        Ok(file)
+    }
+
+    /// 書かれていない色を `base` で補ったパレット
+    pub fn over(&self, base: &Palette) -> Palette {
+        let mut badges = base.badges.clone();
+        badges.extend(self.badges.iter().map(|(category, color)| (*category, *color)));
+        Palette {
+            background: self.background.unwrap_or(base.background),
+            foreground: self.foreground.unwrap_or(base.foreground),
+            muted: self.muted.unwrap_or(base.muted),
+            faint: self.faint.unwrap_or(base.faint),
+            selection: self.selection.unwrap_or(base.selection),
+            accent: self.accent.unwrap_or(base.accent),
+            border: self.border.unwrap_or(base.border),
+            badges,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Result<ThemeFile, ThemeError> {
+        ThemeFile::parse(Path::new("dracula.json"), text)
+    }
+
+    #[test]
+    fn colors_in_each_hex_form() {
+        assert_eq!(Color::parse("#abc").unwrap(), Color::rgb(0xaa, 0xbb, 0xcc));
+        assert_eq!(Color::parse("#A0B1C2").unwrap(), Color::rgb(0xa0, 0xb1, 0xc2));
+        assert_eq!(Color::parse("#11223380").unwrap(), Color { a: 0x80, ..Color::rgb(0x11, 0x22, 0x33) });
+        for invalid in ["abc", "#ab", "#12345", "#gggggg", "#", "", "#ééé"] {
+            assert_eq!(Color::parse(invalid), Err(ThemeError::InvalidColor(invalid.to_string())), "{}", invalid);
+        }
+
+        // Written back in the long form, with alpha only when it isn't opaque
+        assert_eq!(Color::parse("#ABC").unwrap().to_string(), "#aabbcc");
+        assert_eq!(Color::parse("#11223380").unwrap().to_string(), "#11223380");
+        assert_eq!(serde_json::to_string(&Color::rgb(1, 2, 3)).unwrap(), "\"#010203\"");
+    }
+
+    #[test]
+    fn theme_files_are_validated() {
+        let file = parse(r##"{"background": "#282a36", "foreground": "#f8f8f2", "badges": {"file": "#50fa7b"}}"##).unwrap();
+        assert_eq!(file.background, Some(Color::rgb(0x28, 0x2a, 0x36)));
+        assert_eq!(file.badges[&BadgeCategory::File], Color::rgb(0x50, 0xfa, 0x7b));
+
+        assert_eq!(
+            parse(r##"{"background": "#282a36"}"##),
+            Err(ThemeError::MissingKey { path: PathBuf::from("dracula.json"), key: "foreground" }),
+        );
+        let error = |text: &str| parse(text).unwrap_err().to_string();
+        assert!(error(r##"{"background": "#000", "foreground": "white"}"##).contains("'white' is not a color"));
+        assert!(error(r##"{"background": "#000", "foreground": "#fff", "backgroud": "#111"}"##).contains("unknown field `backgroud`"));
+        assert!(error(r##"{"background": "#000", "foreground": "#fff", "badges": {"folder": "#111"}}"##).contains("folder"));
+        assert!(error("not json").starts_with("Invalid theme file dracula.json"));
+    }
+
+    // Colors the file leaves out come from the base; badges merge key by key
+    #[test]
+    fn missing_colors_fall_back_to_the_base() {
+        let file = parse(r##"{"background": "#000", "foreground": "#fff", "accent": "#f0f", "badges": {"plugin": "#0f0"}}"##).unwrap();
+        let base = Palette::light();
+        let palette = file.over(&base);
+
+        assert_eq!((palette.background, palette.accent), (Color::rgb(0, 0, 0), Color::rgb(0xff, 0, 0xff)));
+        assert_eq!((palette.muted, palette.border), (base.muted, base.border));
+        assert_eq!(palette.badge(&Category::Plugin("Timer".to_string())), Color::rgb(0, 0xff, 0));
+        assert_eq!(palette.badge(&Category::File), base.badges[&BadgeCategory::File]);
+    }
+
+    #[test]
+    fn custom_themes_resolve_over_the_system_mode() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("dracula.json"), r##"{"background": "#282a36", "foreground": "#f8f8f2"}"##).unwrap();
+        let dracula = Theme::Custom("dracula".to_string());
+
+        let dark = Palette::resolve(&dracula, true, dir.path()).unwrap();
+        assert_eq!(dark.background, Color::rgb(0x28, 0x2a, 0x36));
+        assert_eq!(dark.selection, Palette::dark().selection);
+        assert_eq!(Palette::resolve(&dracula, false, dir.path()).unwrap().selection, Palette::light().selection);
+
+        // The fixed themes ignore the system mode and never read a file
+        assert_eq!(Palette::resolve(&Theme::Light, true, dir.path()).unwrap(), Palette::light());
+        assert_eq!(Palette::resolve(&Theme::System, false, dir.path()).unwrap(), Palette::light());
+
+        let missing = Theme::Custom("nord".to_string());
+        assert_eq!(Palette::resolve(&missing, true, dir.path()), Err(ThemeError::NotFound(dir.path().join("nord.json"))));
+    }
+
+    #[test]
+    fn theme_names_stay_in_the_themes_directory() {
+        let dir = Path::new("/themes");
+        assert_eq!(theme_file(dir, "solarized-dark").unwrap(), dir.join("solarized-dark.json"));
+        for name in ["", "../config", "a/b", "a\\b", ".hidden", "c:evil"] {
+            assert_eq!(theme_file(dir, name), Err(ThemeError::InvalidName(name.to_string())), "{}", name);
+        }
+    }
+
+    // An exported palette is a complete theme file
+    #[test]
+    fn exported_palettes_load_as_themes() {
+        let exported = serde_json::to_string_pretty(&Palette::dark()).unwrap();
+        assert_eq!(parse(&exported).unwrap().over(&Palette::light()), Palette::dark());
+    }
+}
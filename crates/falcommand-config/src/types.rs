@@ -2,8 +2,13 @@ use std::path::PathBuf;
 use serde::{Deserialize, Serialize};
 use log::info;
 
+use crate::launch;
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "StoredSearchResult")]
 pub struct SearchResult {
+    /// カテゴリとアクションの対象から求める識別子。スコアやタイトルが変わっても同じ結果なら同じ値になる
+    pub id: ResultId,
     pub title: String,
     pub description: String,
     pub path: Option<PathBuf>,
@@ -11,8 +16,294 @@ pub struct SearchResult {
     pub action: Action,
     pub score: f64,
     pub category: Category,
+    #[serde(default)]
+    pub secondary_actions: Vec<SecondaryAction>,
+    /// 表形式で見せる追加情報（項目名と値）。空なら出力しないので、古い形式の JSON と互換
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub details: Vec<(String, String)>,
+    /// クエリに一致した理由。ソースが判断できなければ None で、何も表示しない
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub match_reason: Option<MatchReason>,
+}
+
+/// 読み込む側の `SearchResult`。ID のない古い形式の JSON は、カテゴリとアクションから ID を求め直す
+#[derive(Deserialize)]
+struct StoredSearchResult {
+    #[serde(default)]
+    id: Option<ResultId>,
+    title: String,
+    description: String,
+    path: Option<PathBuf>,
+    icon: Option<PathBuf>,
+    action: Action,
+    score: f64,
+    category: Category,
+    #[serde(default)]
+    secondary_actions: Vec<SecondaryAction>,
+    #[serde(default)]
+    details: Vec<(String, String)>,
+    #[serde(default)]
+    match_reason: Option<MatchReason>,
+}
+
+impl From<StoredSearchResult> for SearchResult {
+    fn from(stored: StoredSearchResult) -> Self {
+        Self {
+            id: stored.id.unwrap_or_else(|| result_id(&stored.category, &stored.action)),
+            title: stored.title,
+            description: stored.description,
+            path: stored.path,
+            icon: stored.icon,
+            action: stored.action,
+            score: stored.score,
+            category: stored.category,
+            secondary_actions: stored.secondary_actions,
+            details: stored.details,
+            match_reason: stored.match_reason,
+        }
+    }
+}
+
+/// 結果がクエリに一致した理由。タイトルに含まれない語で出てきた結果の説明に使う
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MatchReason {
+    TitleMatch,
+    KeywordMatch(String),
+    AliasMatch(String),
+    /// 一致した内容の行
+    ContentMatch(String),
+    HistoryBoost,
+    /// 最前面だったアプリ（名前）に合わせて上げた結果
+    ContextBoost(String),
+    Pinned,
+}
+
+impl MatchReason {
+    /// 「keyword: browser」のような短い説明
+    pub fn describe(&self) -> String {
+        match self {
+            MatchReason::TitleMatch => "name".to_string(),
+            MatchReason::KeywordMatch(keyword) => format!("keyword: {}", keyword),
+            MatchReason::AliasMatch(alias) => format!("alias: {}", alias),
+            MatchReason::ContentMatch(line) => format!("content: {}", line),
+            MatchReason::HistoryBoost => "history".to_string(),
+            MatchReason::ContextBoost(app) => format!("context: {}", app),
+            MatchReason::Pinned => "pinned".to_string(),
+        }
+    }
+    
+    /// 結果の行に添える「matched keyword: browser」。タイトルで一致した結果には何も添えない
+    pub fn row_suffix(&self) -> Option<String> {
+        match self {
+            MatchReason::TitleMatch => None,
+            MatchReason::HistoryBoost => Some("boosted by history".to_string()),
+            MatchReason::ContextBoost(app) => Some(format!("boosted for {}", app)),
+            MatchReason::Pinned => Some("pinned".to_string()),
+            reason => Some(format!("matched {}", reason.describe())),
+        }
+    }
 }
 
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SecondaryAction {
+    pub title: String,
+    pub action: Action,
+    /// 取り消せない操作。UI は強調表示し、実行前に確認を求める
+    #[serde(default)]
+    pub destructive: bool,
+}
+
+impl SecondaryAction {
+    pub fn new(title: impl Into<String>, action: Action) -> Self {
+        Self {
+            title: title.into(),
+            action,
+            destructive: false,
+        }
+    }
+    
+    pub fn destructive(title: impl Into<String>, action: Action) -> Self {
+        Self {
+            destructive: true,
+            ..Self::new(title, action)
+        }
+    }
+    
+    /// 一覧に表示するタイトル（破壊的な操作には警告記号を付ける）
+    pub fn display_title(&self) -> String {
+        if self.destructive {
+            format!("⚠ {}", self.title)
+        } else {
+            self.title.clone()
+        }
+    }
+}
+
+/// 検索をまたいで「同じ結果」を識別する ID。実行や OS に依存せず安定している
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct ResultId(pub u64);
+
+impl std::fmt::Display for ResultId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+impl std::str::FromStr for ResultId {
+    type Err = std::num::ParseIntError;
+
+    /// `Display` と同じ 16 進表記を読む（コマンドライン引数やジャンプリストから渡される ID）
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        u64::from_str_radix(s.trim(), 16).map(ResultId)
+    }
+}
+
+/// 結果 ID を求める唯一の関数。カテゴリとアクションの対象（パス、URL、コマンド、プラグインアクション）だけを使う
+pub fn result_id(category: &Category, action: &Action) -> ResultId {
+    let mut key = category_key(category);
+    key.push('\u{1f}');
+    action_key(action, &mut key);
+    ResultId(fnv1a(key.as_bytes()))
+}
+
+// FNV-1a: unlike std's hashers its output is fixed, so ids can be persisted
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+
+    bytes.iter().fold(OFFSET_BASIS, |hash, byte| (hash ^ *byte as u64).wrapping_mul(PRIME))
+}
+
+fn category_key(category: &Category) -> String {
+    match category {
+        Category::Application => "app".to_string(),
+        Category::File => "file".to_string(),
+        Category::Bookmark => "bookmark".to_string(),
+        Category::Plugin(id) => format!("plugin:{}", id),
+        Category::SystemCommand => "system".to_string(),
+        Category::CustomCommand => "custom".to_string(),
+    }
+}
+
+// Forward slashes everywhere; case folded where the file system usually ignores case
+fn path_key(path: &std::path::Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    if cfg!(any(target_os = "windows", target_os = "macos")) {
+        path.to_lowercase()
+    } else {
+        path
+    }
+}
+
+// serde_json may keep objects in insertion order, so keys are sorted here
+fn json_key(value: &serde_json::Value, key: &mut String) {
+    match value {
+        serde_json::Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            key.push('{');
+            for (name, value) in entries {
+                key.push_str(&serde_json::Value::String(name.clone()).to_string());
+                key.push(':');
+                json_key(value, key);
+                key.push(',');
+            }
+            key.push('}');
+        }
+        serde_json::Value::Array(items) => {
+            key.push('[');
+            for item in items {
+                json_key(item, key);
+                key.push(',');
+            }
+            key.push(']');
+        }
+        other => key.push_str(&other.to_string()),
+    }
+}
+
+fn action_key(action: &Action, key: &mut String) {
+    let mut push = |parts: &[&str]| {
+        for part in parts {
+            key.push_str(part);
+            key.push('\u{1f}');
+        }
+    };
+
+    match action {
+        Action::ExecuteApplication { path, args } => {
+            push(&["exec", &path_key(path)]);
+            push(&args.iter().map(String::as_str).collect::<Vec<_>>());
+        }
+        Action::OpenFile(path) => push(&["open", &path_key(path)]),
+        Action::OpenUrl(url) => push(&["url", url]),
+        Action::CopyToClipboard(text) => push(&["copy", text]),
+        Action::ExecuteCommand { command, args } => {
+            push(&["command", command]);
+            push(&args.iter().map(String::as_str).collect::<Vec<_>>());
+        }
+        Action::PluginAction { plugin_id, action_data } => {
+            push(&["plugin", plugin_id]);
+            json_key(action_data, key);
+        }
+        Action::ScheduleAction { action, delay_secs, .. } => {
+            push(&["schedule", &delay_secs.to_string()]);
+            action_key(action, key);
+        }
+        Action::CancelScheduledAction(id) => push(&["cancel", id]),
+        Action::SwitchProfile(name) => push(&["profile", name]),
+        Action::ExcludeFromIndex(path) => push(&["exclude", &path_key(path)]),
+        Action::ClearUsageData => push(&["clear-usage"]),
+        Action::TrashFile(path) => push(&["trash", &path_key(path)]),
+        Action::DeleteHistoryEntry(query) => push(&["delete-history", query]),
+        Action::PressMenuItem { app, path, .. } => {
+            // The pid changes between launches; the app name and menu path don't
+            push(&["menu", app]);
+            push(&path.iter().map(String::as_str).collect::<Vec<_>>());
+        }
+        Action::OpenArchiveMember { archive, member } => push(&["archive", &path_key(archive), member]),
+        Action::SetQuery(query) => push(&["query", query]),
+        Action::RestartNormally => push(&["restart"]),
+        Action::QuitApp => push(&["quit"]),
+        Action::OpenSettings(field) => push(&["settings", field]),
+        Action::RunCustomCommand(name) => push(&["custom-command", &name.to_lowercase()]),
+        Action::SetSetting { path, value } => {
+            push(&["set-setting", path]);
+            json_key(value, key);
+        }
+        Action::SaveQueryMacro { name, template } => push(&["save-query-macro", &name.to_lowercase(), template]),
+        Action::UndoClipboardChange => push(&["undo-clipboard"]),
+        Action::ResolveSyncConflict { category, resolution } => {
+            push(&["resolve-sync-conflict", &format!("{:?}", category), &format!("{:?}", resolution)]);
+        }
+    }
+}
+
+/// 検索構文（プレフィックス、演算子、トリガーなど）のヘルプ項目
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyntaxHint {
+    pub pattern: String,
+    pub description: String,
+    pub example: String,
+}
+
+impl SyntaxHint {
+    pub fn new(pattern: impl Into<String>, description: impl Into<String>, example: impl Into<String>) -> Self {
+        Self {
+            pattern: pattern.into(),
+            description: description.into(),
+            example: example.into(),
+        }
+    }
+}
+
+// Quick choices offered by the "Open later" secondary actions
+pub const OPEN_LATER_DELAYS: [(&str, u64); 3] = [
+    ("10m", 10 * 60),
+    ("30m", 30 * 60),
+    ("1h", 60 * 60),
+];
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Action {
     ExecuteApplication { 
@@ -30,6 +321,82 @@ pub enum Action {
         plugin_id: String,
         action_data: serde_json::Value,
     },
+    ScheduleAction {
+        action: Box<Action>,
+        delay_secs: u64,
+        label: String,
+    },
+    CancelScheduledAction(String),
+    SwitchProfile(String),
+    ExcludeFromIndex(PathBuf),
+    ClearUsageData,
+    TrashFile(PathBuf),
+    DeleteHistoryEntry(String),
+    /// 最前面だったアプリのメニュー項目を押す（`path` はメニューバーからのタイトルの並び）
+    PressMenuItem {
+        pid: i32,
+        app: String,
+        path: Vec<String>,
+    },
+    /// アーカイブ内のファイルを一時ディレクトリに展開して開く（`member` はアーカイブ内のパス）
+    OpenArchiveMember {
+        archive: PathBuf,
+        member: String,
+    },
+    /// 検索欄のテキストを置き換えて検索し直す（ウィンドウ側で処理する）
+    SetQuery(String),
+    /// FalCommand を通常モードで起動し直す（安全モードから抜けるときに使う）
+    RestartNormally,
+    /// 設定を開く（`field` はドット区切りの項目名、`behavior.hotkey` など）
+    OpenSettings(String),
+    /// 設定項目を書き換えてすぐ保存する（真偽値の切り替えや列挙値の順送りに使う）
+    SetSetting {
+        path: String,
+        value: serde_json::Value,
+    },
+    /// 設定の `custom_commands` にある名前のコマンドを実行する（マクロの手順からも参照できる）
+    RunCustomCommand(String),
+    /// FalCommand を終了する（トレイが使えない環境での終了手段）
+    QuitApp,
+    /// クエリを `search.query_macros` に `@name` で呼び出せるマクロとして保存する
+    SaveQueryMacro {
+        name: String,
+        template: String,
+    },
+    /// 直前のコピーで上書きされたクリップボードの内容を戻す（元の内容は ActionExecutor だけが保持する）
+    UndoClipboardChange,
+    /// 同期の競合を、どちらを残すか（またはマージするか）を選んで解決する
+    ResolveSyncConflict {
+        category: SyncCategory,
+        resolution: Resolution,
+    },
+}
+
+/// 同期されるデータの種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SyncCategory {
+    Settings,
+    SearchHistory,
+    UsageStats,
+}
+
+impl SyncCategory {
+    /// 表示用の名前（"Search history" など）
+    pub fn label(&self) -> &'static str {
+        match self {
+            SyncCategory::Settings => "Settings",
+            SyncCategory::SearchHistory => "Search history",
+            SyncCategory::UsageStats => "Usage stats",
+        }
+    }
+}
+
+/// 同期の競合の解決方法
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Resolution {
+    KeepLocal,
+    KeepRemote,
+    Merge,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -53,19 +420,26 @@ pub enum ActionError {
 
 impl SearchResult {
     pub fn new(title: impl Into<String>, description: impl Into<String>) -> Self {
+        let action = Action::CopyToClipboard(String::new());
+        let category = Category::SystemCommand;
         Self {
+            id: result_id(&category, &action),
             title: title.into(),
             description: description.into(),
             path: None,
             icon: None,
-            action: Action::CopyToClipboard(String::new()),
+            action,
             score: 0.0,
-            category: Category::SystemCommand,
+            category,
+            secondary_actions: Vec::new(),
+            details: Vec::new(),
+            match_reason: None,
         }
     }
     
     pub fn with_action(mut self, action: Action) -> Self {
         self.action = action;
+        self.id = result_id(&self.category, &self.action);
         self
     }
     
@@ -76,6 +450,7 @@ impl SearchResult {
     
     pub fn with_category(mut self, category: Category) -> Self {
         self.category = category;
+        self.id = result_id(&self.category, &self.action);
         self
     }
     
@@ -88,14 +463,85 @@ impl SearchResult {
         self.icon = Some(icon);
         self
     }
+    
+    pub fn with_match_reason(mut self, reason: MatchReason) -> Self {
+        self.match_reason = Some(reason);
+        self
+    }
+    
+    /// 詳細表示に項目を追加する（追加した順に表示される）
+    pub fn with_detail(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.details.push((key.into(), value.into()));
+        self
+    }
+    
+    pub fn with_secondary_action(mut self, title: impl Into<String>, action: Action) -> Self {
+        self.secondary_actions.push(SecondaryAction::new(title, action));
+        self
+    }
+    
+    /// 確認が必要な破壊的セカンダリアクションを追加
+    pub fn with_destructive_action(mut self, title: impl Into<String>, action: Action) -> Self {
+        self.secondary_actions.push(SecondaryAction::destructive(title, action));
+        self
+    }
+    
+    /// 現在のアクションを後で実行する「Open later」セカンダリアクションを追加
+    pub fn with_open_later_actions(mut self) -> Self {
+        for (label, delay_secs) in OPEN_LATER_DELAYS {
+            let action = Action::ScheduleAction {
+                action: Box::new(self.action.clone()),
+                delay_secs,
+                label: self.title.clone(),
+            };
+            self = self.with_secondary_action(format!("Open later ({})", label), action);
+        }
+        self
+    }
 }
 
 impl Action {
+    /// 確認なしに実行してはいけないアクション
+    pub fn is_destructive(&self) -> bool {
+        matches!(self, Action::TrashFile(_))
+    }
+    
+    /// 引数を含まないアクションの種類名（外部に知らせる実行記録に使う）
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Action::ExecuteApplication { .. } => "exec",
+            Action::OpenFile(_) => "open",
+            Action::OpenUrl(_) => "url",
+            Action::CopyToClipboard(_) => "copy",
+            Action::ExecuteCommand { .. } => "command",
+            Action::PluginAction { .. } => "plugin",
+            Action::ScheduleAction { .. } => "schedule",
+            Action::CancelScheduledAction(_) => "cancel",
+            Action::SwitchProfile(_) => "profile",
+            Action::ExcludeFromIndex(_) => "exclude",
+            Action::ClearUsageData => "clear-usage",
+            Action::TrashFile(_) => "trash",
+            Action::DeleteHistoryEntry(_) => "delete-history",
+            Action::PressMenuItem { .. } => "menu",
+            Action::OpenArchiveMember { .. } => "archive",
+            Action::SetQuery(_) => "query",
+            Action::RestartNormally => "restart",
+            Action::QuitApp => "quit",
+            Action::OpenSettings(_) => "settings",
+            Action::RunCustomCommand(_) => "custom-command",
+            Action::SetSetting { .. } => "set-setting",
+            Action::SaveQueryMacro { .. } => "save-query-macro",
+            Action::UndoClipboardChange => "undo-clipboard",
+            Action::ResolveSyncConflict { .. } => "resolve-sync-conflict",
+        }
+    }
+    
     pub async fn execute(&self) -> Result<(), ActionError> {
         match self {
             Action::ExecuteApplication { path, args } => {
                 info!("Executing application: {:?} with args: {:?}", path, args);
-                let mut cmd = tokio::process::Command::new(path);
+                let (program, args) = launch::launch_command(path, args);
+                let mut cmd = tokio::process::Command::new(program);
                 cmd.args(args);
                 let result = cmd.spawn();
                 match result {
@@ -107,9 +553,8 @@ impl Action {
                 info!("Opening file: {:?}", path);
                 #[cfg(target_os = "windows")]
                 {
-                    let result = tokio::process::Command::new("cmd")
-                        .args(&["/C", "start", "", &path.to_string_lossy()])
-                        .spawn();
+                    // cmd /C start mangles UNC paths and paths containing & or ^
+                    let result = launch::shell_open(path);
                     match result {
                         Ok(_) => Ok(()),
                         Err(e) => Err(ActionError::PlatformError(format!("Failed to open file: {}", e))),
@@ -138,8 +583,14 @@ impl Action {
             }
             Action::OpenUrl(url) => {
                 info!("Opening URL: {}", url);
-                // This would use platform-specific URL opening
-                Ok(())
+                #[cfg(target_os = "windows")]
+                let result = launch::shell_open(url);
+                #[cfg(target_os = "macos")]
+                let result = tokio::process::Command::new("open").arg(url).spawn().map(|_| ());
+                #[cfg(target_os = "linux")]
+                let result = tokio::process::Command::new("xdg-open").arg(url).spawn().map(|_| ());
+                
+                result.map_err(|e| ActionError::PlatformError(format!("Failed to open URL: {}", e)))
             }
             Action::CopyToClipboard(text) => {
                 info!("Copying to clipboard: {}", text);
@@ -148,7 +599,8 @@ impl Action {
             }
             Action::ExecuteCommand { command, args } => {
                 info!("Executing command: {} with args: {:?}", command, args);
-                let mut cmd = tokio::process::Command::new(command);
+                let (program, args) = launch::launch_command(&launch::resolve_command(command), args);
+                let mut cmd = tokio::process::Command::new(program);
                 cmd.args(args);
                 let result = cmd.spawn();
                 match result {
@@ -161,6 +613,176 @@ impl Action {
                 // This would delegate to the plugin system
                 Ok(())
             }
+            Action::ScheduleAction { .. } | Action::CancelScheduledAction(_) => {
+                Err(ActionError::Other("Scheduled actions must be executed through the ActionExecutor".to_string()))
+            }
+            Action::SwitchProfile(_) => {
+                Err(ActionError::Other("Profile switches must be executed through the ActionExecutor".to_string()))
+            }
+            Action::ExcludeFromIndex(_) => {
+                Err(ActionError::Other("Index exclusions must be executed through the ActionExecutor".to_string()))
+            }
+            Action::ClearUsageData => {
+                Err(ActionError::Other("Clearing usage data must be executed through the ActionExecutor".to_string()))
+            }
+            Action::TrashFile(_) => {
+                Err(ActionError::Other("Moving files to the trash must be executed through the ActionExecutor".to_string()))
+            }
+            Action::DeleteHistoryEntry(_) => {
+                Err(ActionError::Other("Deleting history entries must be executed through the ActionExecutor".to_string()))
+            }
+            Action::PressMenuItem { .. } => {
+                Err(ActionError::Other("Menu items must be pressed through the ActionExecutor".to_string()))
+            }
+            Action::OpenArchiveMember { .. } => {
+                Err(ActionError::Other("Archive members must be opened through the ActionExecutor".to_string()))
+            }
+            Action::SetQuery(_) => {
+                Err(ActionError::Other("Query changes must be handled by the launcher window".to_string()))
+            }
+            Action::RestartNormally | Action::QuitApp => {
+                Err(ActionError::Other("Restarting and quitting must be executed through the ActionExecutor".to_string()))
+            }
+            Action::OpenSettings(_) | Action::SetSetting { .. } | Action::SaveQueryMacro { .. } => {
+                Err(ActionError::Other("Settings must be changed through the ActionExecutor".to_string()))
+            }
+            Action::RunCustomCommand(_) => {
+                Err(ActionError::Other("Custom commands must be run through the ActionExecutor".to_string()))
+            }
+            Action::UndoClipboardChange => {
+                Err(ActionError::Other("Clipboard changes must be undone through the ActionExecutor".to_string()))
+            }
+            Action::ResolveSyncConflict { .. } => {
+                Err(ActionError::Other("Sync conflicts must be resolved through the ActionExecutor".to_string()))
+            }
+        }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use super::*;
+
+    fn firefox() -> Action {
+        Action::ExecuteApplication { path: PathBuf::from("/usr/bin/firefox"), args: Vec::new() }
+    }
+
+    #[test]
+    fn ids_ignore_presentation_and_follow_the_target() {
+        let a = SearchResult::new("Firefox", "Web browser").with_category(Category::Application).with_action(firefox()).with_score(0.9);
+        let b = SearchResult::new("Mozilla Firefox", "").with_action(firefox()).with_category(Category::Application).with_score(0.1);
+        assert_eq!(a.id, b.id);
+        assert_eq!(a.id, result_id(&Category::Application, &firefox()));
+
+        // The builders keep the id current
+        let file = a.clone().with_category(Category::File);
+        assert_ne!(file.id, a.id);
+        let other = a.clone().with_action(Action::OpenFile(PathBuf::from("/usr/bin/firefox")));
+        assert_ne!(other.id, a.id);
+    }
+
+    // Ids are persisted (pins, usage, jump lists), so they must not change between runs or builds
+    #[test]
+    fn ids_are_the_same_in_every_run() {
+        assert_eq!(fnv1a(b""), 0xcbf29ce484222325);
+        assert_eq!(fnv1a(b"a"), 0xaf63dc4c8601ec8c);
+        assert_eq!(fnv1a(b"foobar"), 0x85944171f73967e8);
+
+        assert_eq!(result_id(&Category::Application, &firefox()).to_string(), "f8cfb3c05ef393ed");
+        let timer = Action::PluginAction { plugin_id: "Timer".to_string(), action_data: serde_json::json!({ "cancel": 3 }) };
+        assert_eq!(result_id(&Category::Plugin("Timer".to_string()), &timer).to_string(), "ec922fddc20b5a8f");
+    }
+
+    // Results saved before ids existed get the id they would have now, not 0
+    #[test]
+    fn results_without_an_id_derive_it_when_read() {
+        let result = SearchResult::new("Firefox", "").with_category(Category::Application).with_action(firefox());
+        let mut json = serde_json::to_value(&result).unwrap();
+        json.as_object_mut().unwrap().remove("id");
+        let read: SearchResult = serde_json::from_value(json).unwrap();
+        assert_eq!(read.id, result_id(&Category::Application, &firefox()));
+        assert_eq!(read, result);
+
+        // A stored id is kept as written
+        let mut json = serde_json::to_value(&result).unwrap();
+        json["id"] = serde_json::json!(7);
+        assert_eq!(serde_json::from_value::<SearchResult>(json).unwrap().id, ResultId(7));
+    }
+
+    #[test]
+    fn equivalent_targets_share_an_id() {
+        let data = |first: (&str, i32), second: (&str, i32)| {
+            let mut map = serde_json::Map::new();
+            map.insert(first.0.to_string(), first.1.into());
+            map.insert(second.0.to_string(), second.1.into());
+            Action::PluginAction { plugin_id: "notes".to_string(), action_data: serde_json::Value::Object(map) }
+        };
+        let category = Category::Plugin("notes".to_string());
+        assert_eq!(result_id(&category, &data(("a", 1), ("b", 2))), result_id(&category, &data(("b", 2), ("a", 1))));
+
+        let windows = Action::OpenFile(PathBuf::from("C:\\Users\\me\\a.txt"));
+        let forward = Action::OpenFile(PathBuf::from("C:/Users/me/a.txt"));
+        assert_eq!(result_id(&Category::File, &windows), result_id(&Category::File, &forward));
+
+        // Only the menu path identifies a menu item; the pid changes between launches
+        let menu = |pid| Action::PressMenuItem { app: "Finder".to_string(), pid, path: vec!["File".to_string(), "New Window".to_string()] };
+        assert_eq!(result_id(&Category::SystemCommand, &menu(1)), result_id(&Category::SystemCommand, &menu(2)));
+    }
+
+    #[test]
+    fn distinct_targets_do_not_collide() {
+        let command = |args: &[&str]| Action::ExecuteCommand { command: "git".to_string(), args: args.iter().map(|arg| arg.to_string()).collect() };
+        // Field boundaries are part of the key
+        assert_ne!(result_id(&Category::CustomCommand, &command(&["a b"])), result_id(&Category::CustomCommand, &command(&["a", "b"])));
+        assert_ne!(result_id(&Category::CustomCommand, &command(&["ab"])), result_id(&Category::CustomCommand, &command(&["a", "b"])));
+        assert_ne!(result_id(&Category::File, &firefox()), result_id(&Category::Application, &firefox()));
+        assert_ne!(
+            result_id(&Category::File, &Action::OpenFile(PathBuf::from("/a"))),
+            result_id(&Category::File, &Action::TrashFile(PathBuf::from("/a"))),
+        );
+
+        let mut ids = HashSet::new();
+        for n in 0..20_000 {
+            let action = Action::OpenFile(PathBuf::from(format!("/home/me/file{}.txt", n)));
+            assert!(ids.insert(result_id(&Category::File, &action)), "collision at file{}", n);
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn ids_round_trip_through_their_text_form() {
+        let id = result_id(&Category::Application, &firefox());
+        assert_eq!(id.to_string().parse::<ResultId>(), Ok(id));
+        assert_eq!(" 00000000000000ff ".parse::<ResultId>(), Ok(ResultId(255)));
+        assert!("not hex".parse::<ResultId>().is_err());
+    }
+
+    #[test]
+    fn results_without_details_keep_the_old_json() {
+        // As written by versions before details, by sync peers and by external plugins
+        let json = r#"{
+            "title": "Weather",
+            "description": "Tokyo",
+            "path": null,
+            "icon": null,
+            "action": {"CopyToClipboard": "Sunny"},
+            "score": 0.8,
+            "category": {"Plugin": "weather"}
+        }"#;
+        let result: SearchResult = serde_json::from_str(json).unwrap();
+        assert!(result.details.is_empty() && result.secondary_actions.is_empty());
+        assert_eq!(result.title, "Weather");
+
+        // Nothing new is written for a result without details
+        let written = serde_json::to_value(&result).unwrap();
+        assert!(written.get("details").is_none());
+
+        let detailed = result.with_detail("Humidity", "62%").with_detail("Wind", "3 m/s");
+        let written = serde_json::to_string(&detailed).unwrap();
+        let read: SearchResult = serde_json::from_str(&written).unwrap();
+        assert_eq!(read.details, vec![
+            ("Humidity".to_string(), "62%".to_string()),
+            ("Wind".to_string(), "3 m/s".to_string()),
+        ]);
+        assert_eq!(read, detailed);
+    }
+}
@@ -0,0 +1,289 @@
+use std::cmp::Ordering;
+use indexmap::IndexMap;
+
+use falcommand_platform::AppInfo;
+
+// Release channels and editions that follow a " - " separator ("Visual Studio Code - Insiders")
+const EDITION_SUFFIXES: &[&str] = &[
+    "insiders", "preview", "beta", "alpha", "nightly", "canary", "dev", "developer edition",
+    "community", "community edition", "professional", "enterprise", "lts", "stable",
+];
+
+const ARCHITECTURES: &[&str] = &[
+    "64-bit", "32-bit", "64bit", "32bit", "x64", "x86", "x86_64", "x86-64", "amd64", "arm64", "aarch64", "win64", "win32",
+];
+
+const TRADEMARKS: &[&str] = &["r", "tm", "c"];
+
+// Dotted numeric version, compared component by component ("3.9" < "3.12")
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct AppVersion(pub Vec<u64>);
+
+impl AppVersion {
+    // "3.12", "v2.0.1" or "1.85.0"; a bare number is not a version ("Microsoft 365", "Office 2019")
+    pub fn parse(token: &str) -> Option<Self> {
+        let (digits, prefixed) = match token.strip_prefix(['v', 'V']) {
+            Some(rest) => (rest, true),
+            None => (token, false),
+        };
+        if !prefixed && !digits.contains('.') {
+            return None;
+        }
+
+        let components: Option<Vec<u64>> = digits.split('.').map(|part| part.parse().ok()).collect();
+        components.filter(|c| !c.is_empty()).map(AppVersion)
+    }
+}
+
+impl std::fmt::Display for AppVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let parts: Vec<String> = self.0.iter().map(u64::to_string).collect();
+        write!(f, "{}", parts.join("."))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NormalizedAppName {
+    // What the result shows: trademark symbols and architecture qualifiers removed, version and edition kept
+    pub display: String,
+    // Lowercase name without version, architecture or edition, shared by all versions of an app
+    pub canonical: String,
+    pub version: Option<AppVersion>,
+    // Editions and qualifiers moved out of the name, still searchable as keywords
+    pub qualifiers: Vec<String>,
+}
+
+// "x64" or a group of them such as "64-bit x64"
+fn is_architecture(text: &str) -> bool {
+    let mut words = text.split_whitespace().peekable();
+    words.peek().is_some() && words.all(|word| ARCHITECTURES.contains(&word.to_lowercase().as_str()))
+}
+
+fn strip_trademark_symbols(name: &str) -> String {
+    name.chars().filter(|c| !matches!(c, '™' | '®' | '©')).collect()
+}
+
+// Splits "Name (a) [b]" into "Name " and ["a", "b"]
+fn split_parentheticals(name: &str) -> (String, Vec<String>) {
+    let mut rest = String::new();
+    let mut groups = Vec::new();
+    let mut current: Option<String> = None;
+
+    for c in name.chars() {
+        match (c, current.as_mut()) {
+            ('(' | '[', None) => current = Some(String::new()),
+            (')' | ']', Some(group)) => {
+                groups.push(group.trim().to_string());
+                current = None;
+            }
+            (_, Some(group)) => group.push(c),
+            (_, None) => rest.push(c),
+        }
+    }
+    // An unclosed parenthesis is part of the name
+    if let Some(group) = current {
+        rest.push('(');
+        rest.push_str(&group);
+    }
+    (rest, groups)
+}
+
+pub fn normalize_app_name(name: &str) -> NormalizedAppName {
+    let cleaned = strip_trademark_symbols(name);
+    let (mut base, groups) = split_parentheticals(&cleaned);
+    let mut qualifiers = Vec::new();
+
+    for group in groups {
+        if group.is_empty() || TRADEMARKS.contains(&group.to_lowercase().as_str()) {
+            continue;
+        }
+        qualifiers.push(group);
+    }
+
+    if let Some((head, suffix)) = base.rsplit_once(" - ") {
+        if EDITION_SUFFIXES.contains(&suffix.trim().to_lowercase().as_str()) {
+            qualifiers.push(suffix.trim().to_string());
+            base = head.to_string();
+        }
+    }
+
+    let mut version = None;
+    let mut display_words = Vec::new();
+    let mut canonical_words = Vec::new();
+    for word in base.split_whitespace() {
+        if is_architecture(word) {
+            qualifiers.push(word.to_string());
+            continue;
+        }
+        display_words.push(word);
+        match AppVersion::parse(word) {
+            Some(parsed) if version.is_none() => version = Some(parsed),
+            Some(_) => {}
+            None => canonical_words.push(word.to_lowercase()),
+        }
+    }
+
+    // A name that is nothing but a version or qualifier stays as it is
+    if canonical_words.is_empty() {
+        let trimmed = cleaned.trim().to_string();
+        return NormalizedAppName {
+            canonical: trimmed.to_lowercase(),
+            display: trimmed,
+            version: None,
+            qualifiers: Vec::new(),
+        };
+    }
+
+    // Editions stay visible so "Launch Visual Studio Code (Insiders)" is distinguishable
+    let mut display = display_words.join(" ");
+    for qualifier in qualifiers.iter().filter(|q| !is_architecture(q)) {
+        display.push_str(&format!(" ({})", qualifier));
+    }
+
+    NormalizedAppName {
+        display,
+        canonical: canonical_words.join(" "),
+        version,
+        qualifiers,
+    }
+}
+
+// Newest version first; among equal versions the unqualified build (stable over Insiders) wins
+fn compare_versions(a: &NormalizedAppName, b: &NormalizedAppName) -> Ordering {
+    b.version.cmp(&a.version)
+        .then_with(|| a.qualifiers.len().cmp(&b.qualifiers.len()))
+        .then_with(|| a.display.cmp(&b.display))
+}
+
+// One entry per canonical name: the newest version carries the others as `other_versions`.
+// Apps listed in `ungrouped` (by raw or canonical name) are kept as they are
+pub fn group_app_versions(apps: Vec<AppInfo>, ungrouped: &[String]) -> Vec<(String, AppInfo)> {
+    let is_ungrouped = |app: &AppInfo, normalized: &NormalizedAppName| {
+        ungrouped.iter().any(|name| name.eq_ignore_ascii_case(&app.name) || name.to_lowercase() == normalized.canonical)
+    };
+
+    let mut entries = Vec::new();
+    let mut groups: IndexMap<String, Vec<(NormalizedAppName, AppInfo)>> = IndexMap::new();
+    for app in apps {
+        let normalized = normalize_app_name(&app.name);
+        if is_ungrouped(&app, &normalized) {
+            entries.push((app.name.to_lowercase(), app));
+            continue;
+        }
+
+        groups.entry(normalized.canonical.clone()).or_default().push((normalized, app));
+    }
+
+    for (canonical, mut members) in groups {
+        members.sort_by(|(a, _), (b, _)| compare_versions(a, b));
+
+        let mut versions = members.into_iter().map(|(normalized, mut app)| {
+            app.name = normalized.display;
+            app.keywords.push(normalized.canonical);
+            app.keywords.extend(normalized.qualifiers);
+            app
+        });
+        let Some(mut primary) = versions.next() else {
+            continue;
+        };
+        primary.other_versions = versions.collect();
+        entries.push((canonical, primary));
+    }
+    entries
+}
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use super::*;
+
+    fn app(name: &str) -> AppInfo {
+        AppInfo::new(name, PathBuf::from(format!("/apps/{}", name)))
+    }
+
+    fn version(text: &str) -> Option<AppVersion> {
+        AppVersion::parse(text)
+    }
+
+    // Names as Windows, macOS and Linux installers register them
+    #[test]
+    fn real_world_names_normalize() {
+        let corpus = [
+            // (raw, display, canonical, version)
+            ("Python 3.12 (64-bit)", "Python 3.12", "python", Some("3.12")),
+            ("7-Zip 23.01 (x64)", "7-Zip 23.01", "7-zip", Some("23.01")),
+            ("Notepad++ (64-bit x64)", "Notepad++", "notepad++", None),
+            ("Git [arm64]", "Git", "git", None),
+            ("Steam™", "Steam", "steam", None),
+            ("Microsoft® Word 2019", "Microsoft Word 2019", "microsoft word 2019", None),
+            ("Microsoft 365", "Microsoft 365", "microsoft 365", None),
+            ("Adobe Photoshop® 2024", "Adobe Photoshop 2024", "adobe photoshop 2024", None),
+            ("Visual Studio Code - Insiders", "Visual Studio Code (Insiders)", "visual studio code", None),
+            ("JetBrains Rider - Preview", "JetBrains Rider (Preview)", "jetbrains rider", None),
+            ("LibreOffice 7.6 - LTS", "LibreOffice 7.6 (LTS)", "libreoffice", Some("7.6")),
+            ("Mozilla Firefox (x64 en-US)", "Mozilla Firefox (x64 en-US)", "mozilla firefox", None),
+            ("Node.js v20.11.0", "Node.js v20.11.0", "node.js", Some("20.11.0")),
+            ("GIMP 2.10.36", "GIMP 2.10.36", "gimp", Some("2.10.36")),
+            ("Oracle VM VirtualBox (R)", "Oracle VM VirtualBox", "oracle vm virtualbox", None),
+            // Editions without the " - " separator are part of the name
+            ("Microsoft Edge Canary", "Microsoft Edge Canary", "microsoft edge canary", None),
+            ("IntelliJ IDEA Community Edition", "IntelliJ IDEA Community Edition", "intellij idea community edition", None),
+            // Nothing but a version, or an unclosed parenthesis, is left alone
+            ("1.2.3", "1.2.3", "1.2.3", None),
+            ("Zoom (", "Zoom (", "zoom (", None),
+        ];
+
+        for (raw, display, canonical, expected_version) in corpus {
+            let normalized = normalize_app_name(raw);
+            assert_eq!(normalized.display, display, "display of {:?}", raw);
+            assert_eq!(normalized.canonical, canonical, "canonical name of {:?}", raw);
+            assert_eq!(normalized.version, expected_version.and_then(version), "version of {:?}", raw);
+        }
+        assert_eq!(normalize_app_name("Python 3.12 (64-bit)").qualifiers, ["64-bit"]);
+    }
+
+    #[test]
+    fn versions_compare_numerically() {
+        assert!(version("3.9") < version("3.12"));
+        assert!(version("1.10") > version("1.9.9"));
+        assert!(version("2.0") < version("2.0.1"));
+        assert_eq!(version("v2"), Some(AppVersion(vec![2])));
+        assert_eq!(version("23.01").unwrap().to_string(), "23.1");
+
+        // Bare numbers and non-numeric parts are not versions
+        assert_eq!(version("2019"), None);
+        assert_eq!(version("3.x"), None);
+        assert_eq!(version("v"), None);
+    }
+
+    #[test]
+    fn the_newest_version_leads_its_group() {
+        let apps = vec![
+            app("Python 3.9 (32-bit)"),
+            app("Python 3.12 (64-bit)"),
+            app("Python 3.10"),
+            app("Visual Studio Code - Insiders"),
+            app("Visual Studio Code"),
+            app("GIMP 2.10.36"),
+        ];
+        let grouped = group_app_versions(apps, &[]);
+
+        let names: Vec<(&str, &str)> = grouped.iter().map(|(key, app)| (key.as_str(), app.name.as_str())).collect();
+        assert_eq!(names, [("python", "Python 3.12"), ("visual studio code", "Visual Studio Code"), ("gimp", "GIMP 2.10.36")]);
+
+        let older: Vec<&str> = grouped[0].1.other_versions.iter().map(|app| app.name.as_str()).collect();
+        assert_eq!(older, ["Python 3.10", "Python 3.9"]);
+        assert!(grouped[0].1.keywords.contains(&"64-bit".to_string()));
+        // Stable wins over Insiders at the same version
+        assert_eq!(grouped[1].1.other_versions[0].name, "Visual Studio Code (Insiders)");
+    }
+
+    #[test]
+    fn ungrouped_apps_are_kept_apart() {
+        let apps = vec![app("Python 3.12"), app("Python 3.9")];
+        let grouped = group_app_versions(apps, &["python".to_string()]);
+
+        assert_eq!(grouped.len(), 2);
+        assert!(grouped.iter().all(|(_, app)| app.other_versions.is_empty()));
+        assert_eq!(grouped[0].1.name, "Python 3.12");
+    }
+}
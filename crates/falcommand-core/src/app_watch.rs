@@ -0,0 +1,257 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use notify::{EventKind, RecursiveMode, Watcher};
+use tokio::sync::{mpsc, Mutex};
+use log::{debug, info, warn};
+
+use falcommand_platform::{AppInfo, PlatformProvider};
+use crate::crash::TaskSupervisor;
+use crate::index::IndexManager;
+use crate::power::PowerPolicy;
+
+// Decides when changes in the install locations are rescanned: once they settle for `debounce`,
+// and never sooner than `min_interval` after the last rescan. Changes during the wait are kept
+#[derive(Debug, Clone)]
+pub struct RescanLimiter {
+    debounce: Duration,
+    min_interval: Duration,
+    last_change: Option<Instant>,
+    last_rescan: Option<Instant>,
+}
+
+impl RescanLimiter {
+    pub const DEBOUNCE: Duration = Duration::from_secs(10);
+    pub const MIN_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+    pub fn new(debounce: Duration, min_interval: Duration) -> Self {
+        Self { debounce, min_interval, last_change: None, last_rescan: None }
+    }
+
+    pub fn changed(&mut self, now: Instant) {
+        self.last_change = Some(now);
+    }
+
+    pub fn is_due(&self, now: Instant) -> bool {
+        let Some(last_change) = self.last_change else {
+            return false;
+        };
+        let settled = now.duration_since(last_change) >= self.debounce;
+        let allowed = self.last_rescan
+            .is_none_or(|last_rescan| now.duration_since(last_rescan) >= self.min_interval);
+        settled && allowed
+    }
+
+    // A rescan covers every change seen before it, whatever triggered it
+    pub fn rescanned(&mut self, now: Instant) {
+        self.last_change = None;
+        self.last_rescan = Some(now);
+    }
+}
+
+impl Default for RescanLimiter {
+    fn default() -> Self {
+        Self::new(Self::DEBOUNCE, Self::MIN_INTERVAL)
+    }
+}
+
+// Whether the program an app launches is gone: a path that no longer exists, or a bare command
+// found in no PATH directory
+pub fn launch_target_missing(path: &Path) -> bool {
+    if path.components().count() > 1 {
+        return !path.exists();
+    }
+    let Some(search_path) = std::env::var_os("PATH") else {
+        return false;
+    };
+    let candidates: Vec<PathBuf> = if cfg!(target_os = "windows") && path.extension().is_none() {
+        vec![path.to_path_buf(), path.with_extension("exe")]
+    } else {
+        vec![path.to_path_buf()]
+    };
+    !std::env::split_paths(&search_path)
+        .any(|dir| candidates.iter().any(|candidate| dir.join(candidate).is_file()))
+}
+
+// Keeps the app index current between full rebuilds: rescans apps when the install locations
+// change, and when an indexed app turns out to be uninstalled
+pub struct AppIndexRefresher {
+    index_manager: Arc<IndexManager>,
+    platform_provider: Arc<dyn PlatformProvider>,
+    limiter: Mutex<RescanLimiter>,
+    // Watched changes wait while it defers; a launch of a missing app never does
+    power_policy: Option<Arc<PowerPolicy>>,
+}
+
+impl AppIndexRefresher {
+    const TICK: Duration = Duration::from_secs(1);
+
+    pub fn new(index_manager: Arc<IndexManager>, platform_provider: Arc<dyn PlatformProvider>) -> Self {
+        Self::with_limiter(index_manager, platform_provider, RescanLimiter::default())
+    }
+
+    pub fn with_limiter(
+        index_manager: Arc<IndexManager>,
+        platform_provider: Arc<dyn PlatformProvider>,
+        limiter: RescanLimiter,
+    ) -> Self {
+        Self { index_manager, platform_provider, limiter: Mutex::new(limiter), power_policy: None }
+    }
+
+    pub fn with_power_policy(mut self, power_policy: Arc<PowerPolicy>) -> Self {
+        self.power_policy = Some(power_policy);
+        self
+    }
+
+    pub async fn changed(&self, now: Instant) {
+        self.limiter.lock().await.changed(now);
+    }
+
+    // Rescans if changes are pending and the limiter allows it; true when it did
+    pub async fn rescan_if_due(&self, now: Instant) -> bool {
+        if !self.limiter.lock().await.is_due(now) {
+            return false;
+        }
+        // The change stays pending until the policy allows it
+        if let Some(ref power_policy) = self.power_policy {
+            if !power_policy.allows("app rescans").await {
+                return false;
+            }
+        }
+        self.rescan(now).await;
+        true
+    }
+
+    async fn rescan(&self, now: Instant) {
+        self.limiter.lock().await.rescanned(now);
+        match self.index_manager.refresh_applications(self.platform_provider.clone()).await {
+            Ok(count) => info!("Application index refreshed with {} entries", count),
+            Err(e) => warn!("Failed to refresh the application index: {}", e),
+        }
+    }
+
+    // After launching `path` failed because it is gone: drops the app and rescans right away, as the
+    // user is waiting. Returns the app of the same name found by the rescan, e.g. after an update
+    // moved it, for the launch to be retried with
+    pub async fn recover_missing_app(&self, path: &Path) -> Option<AppInfo> {
+        let removed = self.index_manager.remove_app(path).await?;
+        info!("{} is no longer installed at {}, refreshing applications", removed.name, path.display());
+
+        self.rescan(Instant::now()).await;
+        self.index_manager.get_app_info(&removed.name).await
+            .filter(|app| app.executable_path != path && !launch_target_missing(&app.executable_path))
+    }
+
+    pub fn start(self: Arc<Self>) {
+        let locations = self.platform_provider.app_install_locations();
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            match event {
+                Ok(event) if matches!(event.kind, EventKind::Create(_) | EventKind::Remove(_) | EventKind::Modify(_)) => {
+                    let _ = sender.send(());
+                }
+                Ok(_) => {}
+                Err(e) => warn!("App install watcher error: {}", e),
+            }
+        });
+        let mut watcher = match watcher {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                warn!("Install location watching is unavailable, new apps appear with the next rebuild: {}", e);
+                return;
+            }
+        };
+
+        // Start Menu shortcuts sit in nested folders; bundles and .desktop files at the top level
+        let mode = if cfg!(target_os = "windows") { RecursiveMode::Recursive } else { RecursiveMode::NonRecursive };
+        for location in locations.iter().filter(|location| location.is_dir()) {
+            match watcher.watch(location, mode) {
+                Ok(()) => debug!("Watching {} for installed apps", location.display()),
+                Err(e) => warn!("Failed to watch {}: {}", location.display(), e),
+            }
+        }
+
+        let receiver = Arc::new(Mutex::new(receiver));
+        let watcher = Arc::new(std::sync::Mutex::new(watcher));
+        TaskSupervisor::default().spawn("app-install-watch", move || {
+            let refresher = self.clone();
+            let receiver = receiver.clone();
+            let watcher = watcher.clone();
+            async move {
+                // Dropping the watcher stops the events
+                let _watcher = watcher;
+                let mut receiver = receiver.lock().await;
+                let mut interval = tokio::time::interval(Self::TICK);
+                loop {
+                    tokio::select! {
+                        event = receiver.recv() => match event {
+                            Some(()) => refresher.changed(Instant::now()).await,
+                            None => return,
+                        },
+                        _ = interval.tick() => {
+                            refresher.rescan_if_due(Instant::now()).await;
+                        }
+                    }
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: Duration = Duration::from_secs(1);
+
+    fn limiter() -> RescanLimiter {
+        RescanLimiter::new(10 * SECOND, 300 * SECOND)
+    }
+
+    #[test]
+    fn nothing_is_due_without_a_change() {
+        let start = Instant::now();
+        assert!(!limiter().is_due(start + 3600 * SECOND));
+    }
+
+    // Every change restarts the debounce, so a burst of changes rescans once after the last one
+    #[test]
+    fn changes_settle_before_a_rescan() {
+        let start = Instant::now();
+        let mut limiter = limiter();
+        limiter.changed(start);
+        assert!(!limiter.is_due(start + 9 * SECOND));
+        limiter.changed(start + 8 * SECOND);
+        assert!(!limiter.is_due(start + 17 * SECOND));
+        assert!(limiter.is_due(start + 18 * SECOND));
+    }
+
+    // A change right after a rescan settles but waits out the interval, and is not lost meanwhile
+    #[test]
+    fn rescans_are_rate_limited_without_dropping_changes() {
+        let start = Instant::now();
+        let mut limiter = limiter();
+        limiter.changed(start);
+        limiter.rescanned(start + 10 * SECOND);
+        assert!(!limiter.is_due(start + 400 * SECOND));
+
+        limiter.changed(start + 20 * SECOND);
+        assert!(!limiter.is_due(start + 30 * SECOND));
+        assert!(!limiter.is_due(start + 309 * SECOND));
+        assert!(limiter.is_due(start + 310 * SECOND));
+
+        // A rescan, whatever triggered it, covers the pending change
+        limiter.rescanned(start + 310 * SECOND);
+        assert!(!limiter.is_due(start + 1000 * SECOND));
+    }
+
+    #[test]
+    fn missing_launch_targets() {
+        let dir = tempfile::tempdir().unwrap();
+        let program = dir.path().join("app");
+        std::fs::write(&program, "").unwrap();
+        assert!(!launch_target_missing(&program));
+        assert!(launch_target_missing(&dir.path().join("uninstalled")));
+        assert!(launch_target_missing(Path::new("falcommand-no-such-command")));
+    }
+}
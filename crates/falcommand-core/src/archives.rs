@@ -0,0 +1,387 @@
+use std::collections::hash_map::DefaultHasher;
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::{Component, Path, PathBuf};
+use std::time::SystemTime;
+use flate2::read::{DeflateDecoder, GzDecoder};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ArchiveError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Corrupt archive: {0}")]
+    Corrupt(String),
+
+    #[error("Unsupported archive: {0}")]
+    Unsupported(String),
+
+    #[error("No member {0} in the archive")]
+    MemberNotFound(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveKind {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+impl ArchiveKind {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_string_lossy().to_lowercase();
+        if name.ends_with(".zip") {
+            Some(Self::Zip)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else if name.ends_with(".tar") {
+            Some(Self::Tar)
+        } else {
+            None
+        }
+    }
+}
+
+// A regular file inside an archive; `path` uses forward slashes as stored in the archive
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchiveEntry {
+    pub path: String,
+    pub size: u64,
+}
+
+impl ArchiveEntry {
+    pub fn file_name(&self) -> &str {
+        self.path.rsplit('/').next().unwrap_or(&self.path)
+    }
+}
+
+// Listing of one archive as of `modified`; `entries` is None when the archive could not be read
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveListing {
+    pub modified: SystemTime,
+    pub entries: Option<Vec<ArchiveEntry>>,
+}
+
+// Huge archives would flood the index; the rest of the listing is dropped
+const MAX_ENTRIES: usize = 10_000;
+
+// Reads only the headers; member data is never decompressed for a zip and only skipped over for a tar
+pub fn list_archive(path: &Path) -> std::result::Result<Vec<ArchiveEntry>, ArchiveError> {
+    let kind = ArchiveKind::from_path(path)
+        .ok_or_else(|| ArchiveError::Unsupported(path.display().to_string()))?;
+    let mut entries = match kind {
+        ArchiveKind::Zip => zip::read_central_directory(&mut File::open(path)?)?
+            .into_iter()
+            .filter(|entry| !entry.name.ends_with('/'))
+            .map(|entry| ArchiveEntry { path: entry.name, size: entry.size })
+            .collect(),
+        ArchiveKind::Tar => list_tar(BufReader::new(File::open(path)?))?,
+        ArchiveKind::TarGz => list_tar(GzDecoder::new(BufReader::new(File::open(path)?)))?,
+    };
+    entries.truncate(MAX_ENTRIES);
+    Ok(entries)
+}
+
+fn list_tar(reader: impl Read) -> std::result::Result<Vec<ArchiveEntry>, ArchiveError> {
+    let corrupt = |e: std::io::Error| ArchiveError::Corrupt(e.to_string());
+    let mut archive = tar::Archive::new(reader);
+    let mut entries = Vec::new();
+    for entry in archive.entries().map_err(corrupt)? {
+        let entry = entry.map_err(corrupt)?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let path = entry.path().map_err(corrupt)?.to_string_lossy().replace('\\', "/");
+        entries.push(ArchiveEntry {
+            path: path.trim_start_matches("./").to_string(),
+            size: entry.header().size().unwrap_or(0),
+        });
+        if entries.len() >= MAX_ENTRIES {
+            break;
+        }
+    }
+    Ok(entries)
+}
+
+// Where a member is extracted: its path inside the archive, minus anything that could escape `dest_dir`
+pub fn member_destination(dest_dir: &Path, member: &str) -> Option<PathBuf> {
+    let relative: PathBuf = Path::new(member).components()
+        .filter_map(|component| match component {
+            Component::Normal(part) => Some(part),
+            _ => None,
+        })
+        .collect();
+    (!relative.as_os_str().is_empty()).then(|| dest_dir.join(relative))
+}
+
+// One folder per archive under the cache dir, so members with the same name don't collide and
+// clearing the cache removes them
+pub fn extraction_dir(cache_dir: &Path, archive: &Path) -> PathBuf {
+    let mut hasher = DefaultHasher::new();
+    archive.hash(&mut hasher);
+    let stem = archive.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+    cache_dir
+        .join("archives")
+        .join(format!("{}-{:08x}", stem, hasher.finish() as u32))
+}
+
+// Extracts a single member below `dest_dir` and returns the extracted file
+pub fn extract_member(archive: &Path, member: &str, dest_dir: &Path) -> std::result::Result<PathBuf, ArchiveError> {
+    let kind = ArchiveKind::from_path(archive)
+        .ok_or_else(|| ArchiveError::Unsupported(archive.display().to_string()))?;
+    let destination = member_destination(dest_dir, member)
+        .ok_or_else(|| ArchiveError::MemberNotFound(member.to_string()))?;
+    if let Some(parent) = destination.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    match kind {
+        ArchiveKind::Zip => zip::extract(archive, member, &destination)?,
+        ArchiveKind::Tar => extract_tar(BufReader::new(File::open(archive)?), member, &destination)?,
+        ArchiveKind::TarGz => extract_tar(GzDecoder::new(BufReader::new(File::open(archive)?)), member, &destination)?,
+    }
+    Ok(destination)
+}
+
+fn extract_tar(reader: impl Read, member: &str, destination: &Path) -> std::result::Result<(), ArchiveError> {
+    let corrupt = |e: std::io::Error| ArchiveError::Corrupt(e.to_string());
+    let mut archive = tar::Archive::new(reader);
+    for entry in archive.entries().map_err(corrupt)? {
+        let mut entry = entry.map_err(corrupt)?;
+        let path = entry.path().map_err(corrupt)?.to_string_lossy().replace('\\', "/");
+        if entry.header().entry_type().is_file() && path.trim_start_matches("./") == member {
+            std::io::copy(&mut entry, &mut File::create(destination)?)?;
+            return Ok(());
+        }
+    }
+    Err(ArchiveError::MemberNotFound(member.to_string()))
+}
+
+// Just enough of the zip format to list the central directory and inflate one member
+mod zip {
+    use super::*;
+
+    const END_OF_CENTRAL_DIRECTORY: u32 = 0x0605_4b50;
+    const CENTRAL_DIRECTORY_HEADER: u32 = 0x0201_4b50;
+    const LOCAL_FILE_HEADER: u32 = 0x0403_4b50;
+    const END_RECORD_LEN: usize = 22;
+    const CENTRAL_HEADER_LEN: usize = 46;
+    const LOCAL_HEADER_LEN: usize = 30;
+    const MAX_COMMENT_LEN: u64 = u16::MAX as u64;
+    const FLAG_ENCRYPTED: u16 = 1;
+    const METHOD_STORED: u16 = 0;
+    const METHOD_DEFLATED: u16 = 8;
+
+    pub(super) struct ZipEntry {
+        pub name: String,
+        pub size: u64,
+        compressed_size: u64,
+        method: u16,
+        flags: u16,
+        local_header_offset: u64,
+    }
+
+    fn u16_at(bytes: &[u8], offset: usize) -> u16 {
+        u16::from_le_bytes([bytes[offset], bytes[offset + 1]])
+    }
+
+    fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes([bytes[offset], bytes[offset + 1], bytes[offset + 2], bytes[offset + 3]])
+    }
+
+    fn corrupt(message: &str) -> ArchiveError {
+        ArchiveError::Corrupt(message.to_string())
+    }
+
+    pub(super) fn read_central_directory(file: &mut File) -> std::result::Result<Vec<ZipEntry>, ArchiveError> {
+        // The end record sits at the very end, followed only by an optional comment
+        let len = file.metadata()?.len();
+        let tail_len = len.min(END_RECORD_LEN as u64 + MAX_COMMENT_LEN);
+        file.seek(SeekFrom::Start(len - tail_len))?;
+        let mut tail = vec![0; tail_len as usize];
+        file.read_exact(&mut tail)?;
+
+        let end = (0..tail.len().saturating_sub(END_RECORD_LEN - 1))
+            .rev()
+            .find(|&i| u32_at(&tail, i) == END_OF_CENTRAL_DIRECTORY)
+            .ok_or_else(|| corrupt("no end of central directory record"))?;
+        let entry_count = u16_at(&tail, end + 10);
+        let directory_size = u32_at(&tail, end + 12);
+        let directory_offset = u32_at(&tail, end + 16);
+        if entry_count == u16::MAX || directory_size == u32::MAX || directory_offset == u32::MAX {
+            return Err(ArchiveError::Unsupported("zip64 archives".to_string()));
+        }
+        if directory_offset as u64 + directory_size as u64 > len {
+            return Err(corrupt("central directory lies past the end of the file"));
+        }
+
+        file.seek(SeekFrom::Start(directory_offset as u64))?;
+        let mut directory = vec![0; directory_size as usize];
+        file.read_exact(&mut directory)?;
+
+        let mut entries = Vec::with_capacity(entry_count as usize);
+        let mut offset = 0;
+        for _ in 0..entry_count {
+            if offset + CENTRAL_HEADER_LEN > directory.len() || u32_at(&directory, offset) != CENTRAL_DIRECTORY_HEADER {
+                return Err(corrupt("truncated central directory"));
+            }
+            let header = &directory[offset..];
+            let name_len = u16_at(header, 28) as usize;
+            let extra_len = u16_at(header, 30) as usize;
+            let comment_len = u16_at(header, 32) as usize;
+            let record_len = CENTRAL_HEADER_LEN + name_len + extra_len + comment_len;
+            if offset + record_len > directory.len() {
+                return Err(corrupt("truncated central directory"));
+            }
+
+            let name = &header[CENTRAL_HEADER_LEN..CENTRAL_HEADER_LEN + name_len];
+            entries.push(ZipEntry {
+                name: String::from_utf8_lossy(name).replace('\\', "/"),
+                size: u32_at(header, 24) as u64,
+                compressed_size: u32_at(header, 20) as u64,
+                method: u16_at(header, 10),
+                flags: u16_at(header, 8),
+                local_header_offset: u32_at(header, 42) as u64,
+            });
+            offset += record_len;
+        }
+        Ok(entries)
+    }
+
+    pub(super) fn extract(archive: &Path, member: &str, destination: &Path) -> std::result::Result<(), ArchiveError> {
+        let mut file = File::open(archive)?;
+        let entry = read_central_directory(&mut file)?
+            .into_iter()
+            .find(|entry| entry.name == member)
+            .ok_or_else(|| ArchiveError::MemberNotFound(member.to_string()))?;
+        if entry.flags & FLAG_ENCRYPTED != 0 {
+            return Err(ArchiveError::Unsupported(format!("{} is encrypted", member)));
+        }
+        if entry.method != METHOD_STORED && entry.method != METHOD_DEFLATED {
+            return Err(ArchiveError::Unsupported(format!("compression method {}", entry.method)));
+        }
+
+        // Name and extra field lengths in the local header can differ from the central directory
+        file.seek(SeekFrom::Start(entry.local_header_offset))?;
+        let mut header = [0; LOCAL_HEADER_LEN];
+        file.read_exact(&mut header)?;
+        if u32_at(&header, 0) != LOCAL_FILE_HEADER {
+            return Err(corrupt("bad local file header"));
+        }
+        let skip = u16_at(&header, 26) as i64 + u16_at(&header, 28) as i64;
+        file.seek(SeekFrom::Current(skip))?;
+
+        let mut data = BufReader::new(file).take(entry.compressed_size);
+        let mut output = File::create(destination)?;
+        if entry.method == METHOD_DEFLATED {
+            std::io::copy(&mut DeflateDecoder::new(data), &mut output)?;
+        } else {
+            std::io::copy(&mut data, &mut output)?;
+        }
+        Ok(())
+    }
+}
+#[cfg(test)]
+mod tests {
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+    use super::*;
+    use crate::testing::write_zip;
+
+    fn write_tar_gz(path: &Path, members: &[(&str, &str)]) {
+        let mut builder = tar::Builder::new(GzEncoder::new(File::create(path).unwrap(), Compression::default()));
+        for (name, contents) in members {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, name, contents.as_bytes()).unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap();
+    }
+
+    fn paths(entries: &[ArchiveEntry]) -> Vec<&str> {
+        entries.iter().map(|entry| entry.path.as_str()).collect()
+    }
+
+    fn files_below(dir: &Path) -> Vec<PathBuf> {
+        let mut files = Vec::new();
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                files.extend(files_below(&path));
+            } else {
+                files.push(path);
+            }
+        }
+        files
+    }
+
+    #[test]
+    fn zip_listings_keep_nested_paths_and_skip_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("bundle.zip");
+        write_zip(&archive, &[("docs/", ""), ("docs/guide/intro.md", "# Intro"), ("docs\\notes.txt", "notes"), ("README", "hello")], true);
+
+        let entries = list_archive(&archive).unwrap();
+        assert_eq!(paths(&entries), ["docs/guide/intro.md", "docs/notes.txt", "README"]);
+        assert_eq!(entries[0].size, 7);
+        assert_eq!(entries[0].file_name(), "intro.md");
+    }
+
+    #[test]
+    fn only_the_requested_member_is_extracted() {
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("out");
+        for (name, deflate) in [("deflated.zip", true), ("stored.zip", false)] {
+            let archive = dir.path().join(name);
+            write_zip(&archive, &[("a/b/c.txt", "nested contents"), ("other.txt", "other")], deflate);
+
+            let extracted = extract_member(&archive, "a/b/c.txt", &dest).unwrap();
+            assert_eq!(extracted, dest.join("a").join("b").join("c.txt"));
+            assert_eq!(std::fs::read_to_string(&extracted).unwrap(), "nested contents");
+            assert!(matches!(extract_member(&archive, "missing.txt", &dest), Err(ArchiveError::MemberNotFound(_))));
+        }
+        assert_eq!(files_below(&dest), [dest.join("a").join("b").join("c.txt")]);
+    }
+
+    #[test]
+    fn tar_gz_members_are_listed_and_extracted() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("backup.tar.gz");
+        write_tar_gz(&archive, &[("./src/main.rs", "fn main() {}"), ("src/lib.rs", "")]);
+
+        assert_eq!(paths(&list_archive(&archive).unwrap()), ["src/main.rs", "src/lib.rs"]);
+        let extracted = extract_member(&archive, "src/main.rs", &dir.path().join("out")).unwrap();
+        assert_eq!(std::fs::read_to_string(extracted).unwrap(), "fn main() {}");
+    }
+
+    #[test]
+    fn members_cannot_escape_the_extraction_folder() {
+        let dest = Path::new("/cache/archives/x");
+        assert_eq!(member_destination(dest, "../../etc/passwd"), Some(dest.join("etc").join("passwd")));
+        assert_eq!(member_destination(dest, "/abs/file"), Some(dest.join("abs").join("file")));
+        assert_eq!(member_destination(dest, ".."), None);
+    }
+
+    #[test]
+    fn each_archive_extracts_into_its_own_cache_folder() {
+        let cache = Path::new("/cache");
+        let first = extraction_dir(cache, Path::new("/a/backup.zip"));
+        assert!(first.starts_with("/cache/archives"));
+        assert!(first.file_name().unwrap().to_string_lossy().starts_with("backup-"));
+        assert_ne!(first, extraction_dir(cache, Path::new("/b/backup.zip")));
+    }
+
+    #[test]
+    fn unreadable_archives_are_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let broken = dir.path().join("broken.zip");
+        std::fs::write(&broken, b"not a zip at all").unwrap();
+        assert!(matches!(list_archive(&broken), Err(ArchiveError::Corrupt(_))));
+        assert!(matches!(list_archive(&dir.path().join("notes.txt")), Err(ArchiveError::Unsupported(_))));
+        assert_eq!(ArchiveKind::from_path(Path::new("a.TGZ")), Some(ArchiveKind::TarGz));
+    }
+}
@@ -0,0 +1,91 @@
+use std::path::Path;
+
+// Splits "code ~/projects/foo" into the app token and the argument remainder
+pub fn split_app_arguments(query: &str) -> Option<(&str, &str)> {
+    let (token, rest) = query.trim_start().split_once(char::is_whitespace)?;
+    let rest = rest.trim();
+    if token.is_empty() || rest.is_empty() {
+        return None;
+    }
+    Some((token, rest))
+}
+
+// Whether `query` is still the start of one of `app_names`: in "google chrome" the space belongs to
+// the name "Google Chrome", so it doesn't start an argument for "Google"
+pub fn is_app_name_prefix<'a>(query: &str, mut app_names: impl Iterator<Item = &'a str>) -> bool {
+    let normalize = |text: &str| text.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+    let query = normalize(query);
+    app_names.any(|name| normalize(name).starts_with(&query))
+}
+
+pub fn looks_like_path(arg: &str) -> bool {
+    arg.starts_with('~')
+        || arg.starts_with('/')
+        || arg.starts_with('.')
+        || arg.contains('/')
+        || arg.contains('\\')
+        || Path::new(arg).has_root()
+}
+
+// "https://example.com/a?b": a web address with nothing around it, as copied from a browser
+pub fn looks_like_url(text: &str) -> bool {
+    let Some((scheme, rest)) = text.split_once("://") else {
+        return false;
+    };
+    matches!(scheme.to_ascii_lowercase().as_str(), "http" | "https" | "ftp")
+        && !rest.is_empty()
+        && !rest.starts_with('/')
+        && !text.chars().any(char::is_whitespace)
+}
+
+// Expands a leading `~` to the home directory; other arguments are returned unchanged
+pub fn expand_home(arg: &str) -> String {
+    if arg == "~" {
+        if let Some(home) = dirs::home_dir() {
+            return home.to_string_lossy().into_owned();
+        }
+    } else if let Some(rest) = arg.strip_prefix("~/").or_else(|| arg.strip_prefix("~\\")) {
+        if let Some(home) = dirs::home_dir() {
+            return home.join(rest).to_string_lossy().into_owned();
+        }
+    }
+    arg.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multi_word_app_name_is_not_an_argument() {
+        let names = ["Google Chrome", "Code"];
+        assert!(is_app_name_prefix("google chrome", names.iter().copied()));
+        assert!(is_app_name_prefix("google  ch", names.iter().copied()));
+        assert!(!is_app_name_prefix("code ~/projects", names.iter().copied()));
+    }
+    
+    #[test]
+    fn urls_are_whole_web_addresses() {
+        assert!(looks_like_url("https://example.com/a?b=1"));
+        assert!(looks_like_url("HTTP://example.com"));
+        assert!(looks_like_url("ftp://files.example.com/pub"));
+        assert!(!looks_like_url("file:///etc/hosts"));
+        assert!(!looks_like_url("https://"));
+        assert!(!looks_like_url("https:///path"));
+        assert!(!looks_like_url("https://example.com and more"));
+        assert!(!looks_like_url("example.com"));
+    }
+    
+    #[test]
+    fn only_a_leading_tilde_is_expanded() {
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(expand_home("~"), home.to_string_lossy());
+        assert_eq!(expand_home("~/projects/foo"), home.join("projects/foo").to_string_lossy());
+        assert_eq!(expand_home("~\\projects"), home.join("projects").to_string_lossy());
+        
+        // "~user" and a tilde further in are not the home directory
+        assert_eq!(expand_home("~alice/notes"), "~alice/notes");
+        assert_eq!(expand_home("notes/~"), "notes/~");
+        assert_eq!(expand_home("--verbose"), "--verbose");
+    }
+}
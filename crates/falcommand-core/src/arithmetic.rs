@@ -0,0 +1,35 @@
+use std::sync::OnceLock;
+use regex::Regex;
+
+// A number, optionally signed and parenthesized
+const NUMBER: &str = r"\(*\s*[-+]?\s*\d+(?:\.\d+)?\s*\)*";
+
+// Numbers joined by operators, optionally parenthesized, so "file2.txt" isn't arithmetic. Unanchored;
+// the calculator's trigger and is_arithmetic both match it against the whole text
+pub fn arithmetic_pattern() -> String {
+    format!(r"\s*{NUMBER}(?:\s*[-+*/]\s*{NUMBER})*\s*")
+}
+
+// "12+30" or "(2.5 * 4) / 2"; a lone number is not a calculation
+pub fn is_arithmetic(text: &str) -> bool {
+    static ARITHMETIC: OnceLock<Regex> = OnceLock::new();
+    let arithmetic = ARITHMETIC.get_or_init(|| {
+        Regex::new(&format!("^(?:{})$", arithmetic_pattern())).expect("arithmetic pattern compiles")
+    });
+    arithmetic.is_match(text) && text.trim().parse::<f64>().is_err()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn calculations_but_not_lone_numbers_or_names() {
+        for text in ["12+30", "(2.5 * 4) / 2", " -3 - -4 ", "1/3"] {
+            assert!(is_arithmetic(text), "{}", text);
+        }
+        for text in ["42", "-7.5", "file2.txt", "2 apples", "12+", "x*2", ""] {
+            assert!(!is_arithmetic(text), "{}", text);
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use log::warn;
+
+use falcommand_config::{Action, Category, Config, SearchResult};
+use falcommand_platform::PlatformProvider;
+use crate::arguments::{expand_home, looks_like_url};
+use crate::arithmetic::is_arithmetic;
+use crate::path_completion::is_path_shaped;
+
+// Larger clipboard text is a document, not something to act on; it isn't looked at
+pub const MAX_CLIPBOARD_SUGGESTION_BYTES: usize = 2048;
+
+// Suggestions put above the empty query at most
+pub const MAX_CLIPBOARD_SUGGESTIONS: usize = 2;
+
+// What the clipboard holds, as far as the launcher can act on it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClipboardContent {
+    Url(String),
+    Path(String),
+    Expression(String),
+    Text(String),
+}
+
+impl ClipboardContent {
+    // Trimmed single-line text up to MAX_CLIPBOARD_SUGGESTION_BYTES, by the same detectors queries
+    // go through. None for anything else
+    pub fn classify(text: &str) -> Option<Self> {
+        if text.len() > MAX_CLIPBOARD_SUGGESTION_BYTES {
+            return None;
+        }
+        let text = text.trim();
+        if text.is_empty() || text.contains(['\n', '\r']) {
+            return None;
+        }
+        let text = text.to_string();
+        Some(if looks_like_url(&text) {
+            ClipboardContent::Url(text)
+        } else if is_path_shaped(&text) {
+            ClipboardContent::Path(text)
+        } else if is_arithmetic(&text) {
+            ClipboardContent::Expression(text)
+        } else {
+            ClipboardContent::Text(text)
+        })
+    }
+
+    // The action that fits the content first, then searching for it; up to MAX_CLIPBOARD_SUGGESTIONS
+    pub fn suggestions(&self) -> Vec<SearchResult> {
+        let suggestion = |title: &str, text: &str, action: Action| {
+            SearchResult::new(title, text)
+                .with_action(action)
+                .with_category(Category::SystemCommand)
+                .with_score(1.0)
+        };
+        let search = |text: &str| suggestion("Search for copied text", text, Action::SetQuery(text.to_string()));
+
+        let mut suggestions = match self {
+            ClipboardContent::Url(url) => vec![suggestion("Open copied URL", url, Action::OpenUrl(url.clone())), search(url)],
+            ClipboardContent::Path(path) => vec![
+                suggestion("Open copied path", path, Action::OpenFile(PathBuf::from(expand_home(path)))),
+                search(path),
+            ],
+            // The calculator answers once the expression is the query
+            ClipboardContent::Expression(expression) => vec![
+                suggestion("Calculate copied expression", expression, Action::SetQuery(expression.clone())),
+            ],
+            ClipboardContent::Text(text) => vec![search(text)],
+        };
+        suggestions.truncate(MAX_CLIPBOARD_SUGGESTIONS);
+        suggestions
+    }
+}
+
+// Offers to act on the clipboard when the window opens. Reads it then and only then, and only with
+// behavior.clipboard_suggestions on; nothing read is kept or logged
+pub struct ClipboardSuggestions {
+    platform: Arc<dyn PlatformProvider>,
+    config: Arc<RwLock<Config>>,
+}
+
+impl ClipboardSuggestions {
+    pub fn new(platform: Arc<dyn PlatformProvider>, config: Arc<RwLock<Config>>) -> Self {
+        Self { platform, config }
+    }
+
+    pub async fn suggestions(&self) -> Vec<SearchResult> {
+        if !self.config.read().await.behavior.clipboard_suggestions {
+            return Vec::new();
+        }
+        let platform = self.platform.clone();
+        let text = match tokio::task::spawn_blocking(move || platform.paste_from_clipboard()).await {
+            Ok(Ok(text)) => text,
+            // Images and other non-text contents don't paste as text
+            Ok(Err(_)) => return Vec::new(),
+            Err(e) => {
+                warn!("Failed to read the clipboard: {}", e);
+                return Vec::new();
+            }
+        };
+        ClipboardContent::classify(&text)
+            .map(|content| content.suggestions())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_kind_of_content_is_told_apart() {
+        let classify = |text: &str| ClipboardContent::classify(text);
+        assert_eq!(classify(" https://example.com/a?b=1 "), Some(ClipboardContent::Url("https://example.com/a?b=1".to_string())));
+        assert_eq!(classify("~/Documents/report.pdf"), Some(ClipboardContent::Path("~/Documents/report.pdf".to_string())));
+        assert_eq!(classify("C:\\Users\\me"), Some(ClipboardContent::Path("C:\\Users\\me".to_string())));
+        assert_eq!(classify("(2.5 * 4) / 2"), Some(ClipboardContent::Expression("(2.5 * 4) / 2".to_string())));
+        assert_eq!(classify("meeting notes"), Some(ClipboardContent::Text("meeting notes".to_string())));
+
+        // Near misses fall through to plain text
+        assert_eq!(classify("42"), Some(ClipboardContent::Text("42".to_string())));
+        assert_eq!(classify("file2.txt"), Some(ClipboardContent::Text("file2.txt".to_string())));
+        assert_eq!(classify("see https://example.com"), Some(ClipboardContent::Text("see https://example.com".to_string())));
+    }
+
+    #[test]
+    fn large_multi_line_or_empty_text_is_ignored() {
+        let at_limit = "a".repeat(MAX_CLIPBOARD_SUGGESTION_BYTES);
+        assert_eq!(ClipboardContent::classify(&at_limit), Some(ClipboardContent::Text(at_limit.clone())));
+        assert_eq!(ClipboardContent::classify(&format!("{}a", at_limit)), None);
+        assert_eq!(ClipboardContent::classify("first line\nsecond line"), None);
+        assert_eq!(ClipboardContent::classify("line\r\nline"), None);
+        assert_eq!(ClipboardContent::classify("  \n "), None);
+    }
+
+    #[test]
+    fn the_fitting_action_comes_before_searching() {
+        let titles = |content: ClipboardContent| -> Vec<String> {
+            content.suggestions().into_iter().map(|result| result.title).collect()
+        };
+        let url = ClipboardContent::Url("https://example.com".to_string());
+        assert_eq!(titles(url.clone()), ["Open copied URL", "Search for copied text"]);
+        assert_eq!(url.suggestions()[0].action, Action::OpenUrl("https://example.com".to_string()));
+
+        let path = ClipboardContent::Path("/tmp/report.pdf".to_string());
+        assert_eq!(titles(path.clone()), ["Open copied path", "Search for copied text"]);
+        assert_eq!(path.suggestions()[0].action, Action::OpenFile(PathBuf::from("/tmp/report.pdf")));
+
+        let expression = ClipboardContent::Expression("12+30".to_string());
+        assert_eq!(titles(expression.clone()), ["Calculate copied expression"]);
+        assert_eq!(expression.suggestions()[0].action, Action::SetQuery("12+30".to_string()));
+
+        let text = ClipboardContent::Text("meeting notes".to_string());
+        assert_eq!(text.suggestions()[0].action, Action::SetQuery("meeting notes".to_string()));
+    }
+}
@@ -0,0 +1,203 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use log::{info, warn};
+
+use falcommand_config::{Action, Category, SearchResult, SyntaxHint};
+use falcommand_config::i18n::tr;
+use falcommand_platform::{PlatformError, PlatformProvider};
+use crate::clock::Clock;
+use crate::source::SearchSource;
+
+// Clipboard text longer than this isn't held; undo is for what was just copied by hand, not for dumps
+pub const MAX_CLIPBOARD_UNDO_BYTES: usize = 256 * 1024;
+
+// The clipboard text a copy replaced. Only ever written back to the clipboard: never logged,
+// shown in a result or saved
+struct HeldClipboard {
+    previous: String,
+    // What the copy put there, so a later copy by the user isn't overwritten by the undo
+    copied: String,
+    expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClipboardRestore {
+    // Put this back on the clipboard
+    Restore(String),
+    // The clipboard no longer holds what was copied; the newer contents are left alone
+    Changed,
+    // Nothing held, or the window has passed
+    Expired,
+}
+
+// Holds what a clipboard-writing action replaced for a short while, so it can be put back
+pub struct ClipboardUndo {
+    clock: Arc<dyn Clock>,
+    held: Mutex<Option<HeldClipboard>>,
+}
+
+impl std::fmt::Debug for ClipboardUndo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClipboardUndo")
+            .field("held", &self.held.lock().unwrap().is_some())
+            .finish()
+    }
+}
+
+impl ClipboardUndo {
+    pub fn new(clock: Arc<dyn Clock>) -> Self {
+        Self { clock, held: Mutex::new(None) }
+    }
+
+    // Holds `previous`, read just before `copied` replaced it, for `window`. Non-text clipboards
+    // (which fail to paste as text), empty or oversized text and a copy of the same text aren't
+    // held; any earlier hold is dropped either way. True when there is something to undo
+    pub fn hold(&self, previous: Result<String, PlatformError>, copied: &str, window: Duration) -> bool {
+        let mut held = self.held.lock().unwrap();
+        *held = None;
+        let Ok(previous) = previous else {
+            return false;
+        };
+        if window.is_zero() || previous.is_empty() || previous.len() > MAX_CLIPBOARD_UNDO_BYTES || previous == copied {
+            return false;
+        }
+        let Ok(window) = chrono::Duration::from_std(window) else {
+            return false;
+        };
+        *held = Some(HeldClipboard {
+            previous,
+            copied: copied.to_string(),
+            expires_at: self.clock.now() + window,
+        });
+        true
+    }
+
+    // Until when the change can be undone
+    pub fn pending_until(&self) -> Option<DateTime<Utc>> {
+        let mut held = self.held.lock().unwrap();
+        let now = self.clock.now();
+        if held.as_ref().is_some_and(|held| held.expires_at <= now) {
+            *held = None;
+        }
+        held.as_ref().map(|held| held.expires_at)
+    }
+
+    // Drops the held text once its window has passed
+    pub fn expire(&self) {
+        self.pending_until();
+    }
+
+    // Takes the held text if it may go back. `current` is what the clipboard holds now
+    pub fn take(&self, current: Result<String, PlatformError>) -> ClipboardRestore {
+        let Some(held) = self.held.lock().unwrap().take() else {
+            return ClipboardRestore::Expired;
+        };
+        if held.expires_at <= self.clock.now() {
+            return ClipboardRestore::Expired;
+        }
+        match current {
+            Ok(current) if current == held.copied => ClipboardRestore::Restore(held.previous),
+            _ => ClipboardRestore::Changed,
+        }
+    }
+
+    // Puts the held text back on `platform`'s clipboard if it may go back
+    pub fn restore(&self, platform: &dyn PlatformProvider) -> Result<ClipboardRestore, PlatformError> {
+        let restore = self.take(platform.paste_from_clipboard());
+        if let ClipboardRestore::Restore(ref previous) = restore {
+            platform.copy_to_clipboard(previous)?;
+        }
+        Ok(restore)
+    }
+
+    // Says the copy can be undone, with an Undo button where notifications have one. The button
+    // restores like the "Undo clipboard change" result, so it does nothing once the window passed
+    pub fn notify(self: &Arc<Self>, platform: Arc<dyn PlatformProvider>, window: Duration) -> Result<(), PlatformError> {
+        let clipboard_undo = self.clone();
+        let restore_platform = platform.clone();
+        let undo = Box::new(move || match clipboard_undo.restore(restore_platform.as_ref()) {
+            Ok(ClipboardRestore::Restore(_)) => info!("Clipboard change undone from the notification"),
+            Ok(ClipboardRestore::Changed) => info!("The clipboard changed since the copy; it was left as it is"),
+            Ok(ClipboardRestore::Expired) => info!("The clipboard change can no longer be undone"),
+            Err(e) => warn!("Failed to undo the clipboard change: {}", e),
+        });
+
+        let message = format!("Undo within {} seconds to put back what it replaced", window.as_secs());
+        if platform.show_notification_with_action("Copied to clipboard", &message, "Undo", undo)? {
+            return Ok(());
+        }
+        // No button here: the notification points at the other ways to undo
+        let message = format!("Search 'undo' or reopen the launcher within {} seconds to put back what it replaced", window.as_secs());
+        platform.show_notification("Copied to clipboard", &message)
+    }
+
+    // The top result while a change can be undone; None once the window has passed
+    pub fn undo_result(&self) -> Option<SearchResult> {
+        let expires_at = self.pending_until()?;
+        let seconds_left = (expires_at - self.clock.now()).num_seconds().max(1);
+        Some(
+            SearchResult::new(
+                "Undo clipboard change",
+                format!("Put back what was on the clipboard before the last copy · {}s left", seconds_left),
+            )
+            .with_action(Action::UndoClipboardChange)
+            .with_category(Category::SystemCommand)
+            .with_score(1.0),
+        )
+    }
+}
+
+// "undo" finds the pending clipboard undo while it lasts
+pub struct ClipboardUndoSource {
+    clipboard_undo: Arc<ClipboardUndo>,
+}
+
+impl ClipboardUndoSource {
+    pub const KEYWORD: &'static str = "undo";
+
+    pub fn new(clipboard_undo: Arc<ClipboardUndo>) -> Self {
+        Self { clipboard_undo }
+    }
+}
+
+#[async_trait]
+impl SearchSource for ClipboardUndoSource {
+    fn name(&self) -> &str {
+        "Clipboard undo"
+    }
+
+    async fn search(&self, query: &str) -> Vec<SearchResult> {
+        let query = query.trim().to_lowercase();
+        if query.len() < 2 || !Self::KEYWORD.starts_with(&query) {
+            return Vec::new();
+        }
+        self.clipboard_undo.undo_result().into_iter().collect()
+    }
+
+    fn syntax_hints(&self) -> Vec<SyntaxHint> {
+        vec![SyntaxHint::new(
+            Self::KEYWORD,
+            tr("hint.clipboard_undo", "Put back the clipboard contents a copy just replaced"),
+            "undo",
+        )]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+
+    const WINDOW: Duration = Duration::from_secs(10);
+
+    #[test]
+    fn non_text_clipboard_is_not_held() {
+        let clipboard_undo = ClipboardUndo::new(Arc::new(ManualClock::new(Utc::now())));
+        let previous = Err(PlatformError::ClipboardError("not text".to_string()));
+
+        assert!(!clipboard_undo.hold(previous, "copied", WINDOW));
+        assert!(clipboard_undo.undo_result().is_none());
+    }
+}
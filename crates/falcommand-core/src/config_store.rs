@@ -0,0 +1,281 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::{broadcast, mpsc, oneshot, RwLock};
+use log::{info, warn};
+
+use falcommand_config::{Config, ConfigError};
+use crate::path_audit::{audit_config_include_paths, IncludePathReport};
+
+// Published after a change is applied, in the order the changes were made
+#[derive(Debug, Clone)]
+pub struct ConfigChanged {
+    pub revision: u64,
+    pub config: Arc<Config>,
+}
+
+type Mutation = Box<dyn FnOnce(&mut Config) + Send>;
+
+enum WriteRequest {
+    Update {
+        mutate: Mutation,
+        reply: oneshot::Sender<std::result::Result<(), ConfigError>>,
+    },
+    // Profile switch: a config loaded from elsewhere, saved to a new file from now on
+    Replace {
+        config: Box<Config>,
+        save_path: PathBuf,
+        reply: oneshot::Sender<std::result::Result<(), ConfigError>>,
+    },
+}
+
+// Owns the live config; every change goes through one writer task so concurrent saves can't interleave
+pub struct ConfigStore {
+    config: Arc<RwLock<Config>>,
+    requests: mpsc::UnboundedSender<WriteRequest>,
+    events: broadcast::Sender<ConfigChanged>,
+    // Problems with the include paths as of the last change, for the settings to show inline
+    include_path_report: Arc<RwLock<IncludePathReport>>,
+    // Where changes are saved; a profile switch moves it
+    save_path: Arc<RwLock<PathBuf>>,
+}
+
+impl ConfigStore {
+    const EVENT_CAPACITY: usize = 16;
+
+    pub fn new(config: Config, save_path: PathBuf) -> Self {
+        let include_path_report = Arc::new(RwLock::new(audit_config_include_paths(&config)));
+        let config = Arc::new(RwLock::new(config));
+        let (requests, receiver) = mpsc::unbounded_channel();
+        let events = broadcast::channel(Self::EVENT_CAPACITY).0;
+        let save_path = Arc::new(RwLock::new(save_path));
+
+        tokio::spawn(Self::run_writer(config.clone(), save_path.clone(), receiver, events.clone(), include_path_report.clone()));
+
+        Self {
+            config,
+            requests,
+            events,
+            include_path_report,
+            save_path,
+        }
+    }
+
+    pub async fn save_path(&self) -> PathBuf {
+        self.save_path.read().await.clone()
+    }
+
+    pub async fn include_path_report(&self) -> IncludePathReport {
+        self.include_path_report.read().await.clone()
+    }
+
+    // Read access for components that only look at the config
+    pub fn shared(&self) -> Arc<RwLock<Config>> {
+        self.config.clone()
+    }
+
+    pub async fn snapshot(&self) -> Config {
+        self.config.read().await.clone()
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<ConfigChanged> {
+        self.events.subscribe()
+    }
+
+    // Mutates a copy, validates and saves it, and only then makes it live; on error nothing changes
+    pub async fn update<F>(&self, mutate: F) -> std::result::Result<(), ConfigError>
+    where
+        F: FnOnce(&mut Config) + Send + 'static,
+    {
+        let (reply, response) = oneshot::channel();
+        self.send(WriteRequest::Update { mutate: Box::new(mutate), reply }, response).await
+    }
+
+    pub async fn replace(&self, config: Config, save_path: PathBuf) -> std::result::Result<(), ConfigError> {
+        let (reply, response) = oneshot::channel();
+        self.send(WriteRequest::Replace { config: Box::new(config), save_path, reply }, response).await
+    }
+
+    async fn send(
+        &self,
+        request: WriteRequest,
+        response: oneshot::Receiver<std::result::Result<(), ConfigError>>,
+    ) -> std::result::Result<(), ConfigError> {
+        let stopped = || ConfigError::FileSystemError("The config writer is not running".to_string());
+        self.requests.send(request).map_err(|_| stopped())?;
+        response.await.map_err(|_| stopped())?
+    }
+
+    async fn run_writer(
+        config: Arc<RwLock<Config>>,
+        save_path: Arc<RwLock<PathBuf>>,
+        mut requests: mpsc::UnboundedReceiver<WriteRequest>,
+        events: broadcast::Sender<ConfigChanged>,
+        include_path_report: Arc<RwLock<IncludePathReport>>,
+    ) {
+        let mut revision = 0;
+
+        while let Some(request) = requests.recv().await {
+            let (result, reply) = match request {
+                WriteRequest::Update { mutate, reply } => {
+                    let mut candidate = config.read().await.clone();
+                    mutate(&mut candidate);
+
+                    let path = save_path.read().await.clone();
+                    let result = match candidate.validate() {
+                        Ok(()) => candidate.save_to_file(&path).await,
+                        Err(e) => Err(e),
+                    };
+                    match result {
+                        Ok(()) => (Ok(candidate), reply),
+                        Err(e) => {
+                            warn!("Config change rejected: {}", e);
+                            (Err(e), reply)
+                        }
+                    }
+                }
+                WriteRequest::Replace { config, save_path: path, reply } => {
+                    info!("Config now saved to {}", path.display());
+                    *save_path.write().await = path;
+                    (Ok(*config), reply)
+                }
+            };
+
+            let result = match result {
+                Ok(candidate) => {
+                    let previous = config.read().await;
+                    let paths_changed = previous.search.include_paths != candidate.search.include_paths
+                        || previous.search.allow_sensitive_paths != candidate.search.allow_sensitive_paths;
+                    drop(previous);
+                    // Advisory only: the change has been saved either way
+                    if paths_changed {
+                        let report = audit_config_include_paths(&candidate);
+                        for finding in &report.findings {
+                            warn!("Search path problem: {}", finding.message());
+                        }
+                        *include_path_report.write().await = report;
+                    }
+
+                    revision += 1;
+                    let snapshot = Arc::new(candidate.clone());
+                    *config.write().await = candidate;
+                    // No subscribers is not an error
+                    let _ = events.send(ConfigChanged { revision, config: snapshot });
+                    Ok(())
+                }
+                Err(e) => Err(e),
+            };
+            // The caller may have given up waiting; the change stands either way
+            let _ = reply.send(result);
+        }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use super::*;
+    use crate::index::current_os;
+    use crate::path_audit::{IncludePathFinding, IncludePathIssue};
+
+    async fn next_change(changes: &mut broadcast::Receiver<ConfigChanged>) -> ConfigChanged {
+        tokio::time::timeout(Duration::from_secs(5), changes.recv()).await.unwrap().unwrap()
+    }
+
+    #[tokio::test]
+    async fn concurrent_updates_all_land() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        let store = Arc::new(ConfigStore::new(Config::default(), path.clone()));
+
+        let updates: Vec<_> = (0..20)
+            .map(|n| {
+                let store = store.clone();
+                tokio::spawn(async move {
+                    store.update(move |config| config.search.exclude_patterns.push(format!("pattern-{}", n))).await
+                })
+            })
+            .collect();
+        for update in updates {
+            update.await.unwrap().unwrap();
+        }
+
+        let saved = Config::load_from_file(&path).await.unwrap();
+        for config in [store.snapshot().await, saved] {
+            for n in 0..20 {
+                assert!(config.search.exclude_patterns.contains(&format!("pattern-{}", n)), "pattern-{} was lost", n);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn an_invalid_change_leaves_everything_as_it_was() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        let store = ConfigStore::new(Config::default(), path.clone());
+        store.update(|config| config.behavior.max_results = 20).await.unwrap();
+        let saved_before = std::fs::read_to_string(&path).unwrap();
+        let mut changes = store.subscribe();
+
+        let result = store.update(|config| {
+            config.behavior.max_results = 0;
+            config.search.exclude_patterns.push("half-applied".to_string());
+        }).await;
+        assert!(matches!(result, Err(ConfigError::ValidationError(_))));
+
+        let live = store.snapshot().await;
+        assert_eq!(live.behavior.max_results, 20);
+        assert!(!live.search.exclude_patterns.contains(&"half-applied".to_string()));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), saved_before);
+        assert!(matches!(changes.try_recv(), Err(broadcast::error::TryRecvError::Empty)));
+
+        // The writer keeps going after a rejected change
+        store.update(|config| config.behavior.max_results = 30).await.unwrap();
+        assert_eq!(next_change(&mut changes).await.config.behavior.max_results, 30);
+    }
+
+    #[tokio::test]
+    async fn changes_are_announced_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ConfigStore::new(Config::default(), dir.path().join("config.toml"));
+        let mut changes = store.subscribe();
+
+        store.update(|config| config.behavior.max_results = 10).await.unwrap();
+        store.update(|config| config.behavior.max_results = 11).await.unwrap();
+        let first = next_change(&mut changes).await;
+        let second = next_change(&mut changes).await;
+        assert_eq!((first.revision, first.config.behavior.max_results), (1, 10));
+        assert_eq!((second.revision, second.config.behavior.max_results), (2, 11));
+
+        // A profile switch is a change too, and later saves go to the new file
+        let profile_path = dir.path().join("work.toml");
+        let mut profile = Config::default();
+        profile.behavior.max_results = 42;
+        store.replace(profile, profile_path.clone()).await.unwrap();
+        assert_eq!(next_change(&mut changes).await.revision, 3);
+        assert_eq!(store.save_path().await, profile_path);
+
+        store.update(|config| config.behavior.max_results = 43).await.unwrap();
+        assert_eq!(Config::load_from_file(&profile_path).await.unwrap().behavior.max_results, 43);
+        assert_eq!(store.shared().read().await.behavior.max_results, 43);
+    }
+
+    // A missing include path is reported, and the change is saved anyway
+    #[tokio::test]
+    async fn include_path_problems_never_block_a_save() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        let store = ConfigStore::new(Config::default(), path.clone());
+        let missing = dir.path().join("Progam Files").to_string_lossy().into_owned();
+
+        store.update({
+            let missing = missing.clone();
+            move |config| {
+                config.search.include_paths.insert(current_os().to_string(), vec![missing]);
+            }
+        }).await.unwrap();
+
+        let saved = Config::load_from_file(&path).await.unwrap();
+        assert_eq!(saved.search.include_paths[current_os()], vec![missing.clone()]);
+        let report = store.include_path_report().await;
+        assert_eq!(report.findings, [IncludePathFinding { path: missing, issue: IncludePathIssue::Missing }]);
+    }
+}
@@ -0,0 +1,120 @@
+use std::time::{Duration, Instant};
+
+use falcommand_config::{Action, SearchResult, SecondaryAction};
+
+// Two-step confirmation for destructive actions: the first request arms the gate and
+// only a second request for the same action before the window closes lets it run
+#[derive(Debug, Clone)]
+pub struct ConfirmationGate {
+    window: Duration,
+    pending: Option<(Action, Instant)>,
+}
+
+impl ConfirmationGate {
+    pub const DEFAULT_WINDOW: Duration = Duration::from_secs(3);
+
+    pub fn new(window: Duration) -> Self {
+        Self { window, pending: None }
+    }
+
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    // Arming a different action replaces the previous one
+    pub fn arm(&mut self, action: Action, now: Instant) {
+        self.pending = Some((action, now));
+    }
+
+    pub fn is_armed(&self, action: &Action, now: Instant) -> bool {
+        self.pending.as_ref()
+            .map(|(pending, armed_at)| pending == action && now.duration_since(*armed_at) < self.window)
+            .unwrap_or(false)
+    }
+
+    // Consumes the pending confirmation; an expired or different action never runs
+    pub fn confirm(&mut self, action: &Action, now: Instant) -> bool {
+        let confirmed = self.is_armed(action, now);
+        self.pending = None;
+        confirmed
+    }
+
+    pub fn reset(&mut self) {
+        self.pending = None;
+    }
+}
+
+impl Default for ConfirmationGate {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_WINDOW)
+    }
+}
+
+fn confirmation_prompt(original: &SearchResult, secondary: &SecondaryAction) -> String {
+    match secondary.action {
+        Action::TrashFile(_) => format!("Press Enter again to move {} to trash", original.title),
+        _ => format!("Press Enter again to confirm: {}", secondary.title),
+    }
+}
+
+// What a result turns into while its destructive action waits for the second Enter
+pub fn confirmation_result(original: &SearchResult, secondary: &SecondaryAction, window: Duration) -> SearchResult {
+    let mut prompt = SearchResult::new(
+        confirmation_prompt(original, secondary),
+        format!("Cancels in {} seconds", window.as_secs()),
+    )
+    .with_action(secondary.action.clone())
+    .with_category(original.category.clone())
+    .with_score(original.score);
+    prompt.path = original.path.clone();
+    prompt.icon = original.icon.clone();
+    prompt
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn trash(name: &str) -> Action {
+        Action::TrashFile(PathBuf::from(name))
+    }
+
+    #[test]
+    fn only_a_second_request_in_the_window_confirms() {
+        let start = Instant::now();
+        let mut gate = ConfirmationGate::default();
+        assert!(!gate.confirm(&trash("a"), start));
+
+        gate.arm(trash("a"), start);
+        assert!(gate.confirm(&trash("a"), start + Duration::from_secs(2)));
+        // Confirming used it up
+        assert!(!gate.confirm(&trash("a"), start + Duration::from_secs(2)));
+
+        gate.arm(trash("a"), start);
+        assert!(!gate.confirm(&trash("a"), start + ConfirmationGate::DEFAULT_WINDOW));
+    }
+
+    #[test]
+    fn another_action_never_runs() {
+        let start = Instant::now();
+        let mut gate = ConfirmationGate::default();
+        gate.arm(trash("a"), start);
+        gate.arm(trash("b"), start);
+        assert!(!gate.is_armed(&trash("a"), start));
+        // A mismatch disarms too, so the armed action needs asking again
+        assert!(!gate.confirm(&trash("c"), start));
+        assert!(!gate.confirm(&trash("b"), start));
+    }
+
+    #[test]
+    fn the_prompt_runs_the_destructive_action() {
+        let original = SearchResult::new("notes.txt", "/home/me/notes.txt")
+            .with_action(Action::OpenFile(PathBuf::from("/home/me/notes.txt")));
+        let secondary = SecondaryAction::destructive("Move to trash", trash("/home/me/notes.txt"));
+        let prompt = confirmation_result(&original, &secondary, ConfirmationGate::DEFAULT_WINDOW);
+        assert_eq!(prompt.title, "Press Enter again to move notes.txt to trash");
+        assert_eq!(prompt.description, "Cancels in 3 seconds");
+        assert_eq!(prompt.action, secondary.action);
+        assert!(prompt.action.is_destructive());
+    }
+}
@@ -0,0 +1,89 @@
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::sync::watch;
+use log::info;
+
+use crate::crash::TaskSupervisor;
+use crate::power::PowerPolicy;
+
+// Tracks whether the network is reachable so online lookups can be skipped while offline
+#[derive(Debug)]
+pub struct ConnectivityMonitor {
+    online: watch::Sender<bool>,
+    // Probes pause while it defers; the last known state stands meanwhile
+    power_policy: Option<Arc<PowerPolicy>>,
+}
+
+impl ConnectivityMonitor {
+    const PROBE_ADDRESS: &'static str = "1.1.1.1:443";
+    const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+    const PROBE_INTERVAL: Duration = Duration::from_secs(60);
+
+    // Assumes online until the first probe says otherwise
+    pub fn new() -> Self {
+        let (online, _) = watch::channel(true);
+        Self { online, power_policy: None }
+    }
+
+    pub fn with_power_policy(mut self, power_policy: Arc<PowerPolicy>) -> Self {
+        self.power_policy = Some(power_policy);
+        self
+    }
+
+    pub fn is_online(&self) -> bool {
+        *self.online.borrow()
+    }
+
+    pub fn subscribe(&self) -> watch::Receiver<bool> {
+        self.online.subscribe()
+    }
+
+    pub fn set_online(&self, online: bool) {
+        self.online.send_if_modified(|current| {
+            if *current == online {
+                return false;
+            }
+            info!("Connectivity changed: {}", if online { "online" } else { "offline" });
+            *current = online;
+            true
+        });
+    }
+
+    pub async fn probe() -> bool {
+        matches!(
+            tokio::time::timeout(Self::PROBE_TIMEOUT, TcpStream::connect(Self::PROBE_ADDRESS)).await,
+            Ok(Ok(_))
+        )
+    }
+
+    // One periodic probe; false when the power policy deferred it and the last state stands
+    pub async fn probe_round(&self) -> bool {
+        if let Some(ref power_policy) = self.power_policy {
+            if !power_policy.allows("connectivity probes").await {
+                return false;
+            }
+        }
+        self.set_online(Self::probe().await);
+        true
+    }
+
+    pub fn start(self: Arc<Self>) {
+        TaskSupervisor::default().spawn("connectivity", move || {
+            let monitor = self.clone();
+            async move {
+                let mut ticker = tokio::time::interval(Self::PROBE_INTERVAL);
+                loop {
+                    ticker.tick().await;
+                    monitor.probe_round().await;
+                }
+            }
+        });
+    }
+}
+
+impl Default for ConnectivityMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
\ No newline at end of file
@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+
+use falcommand_config::{Category, SearchResult, CONTEXT_BOOST_RANGE};
+
+// Boosts keep results this far below an exact title match, so they never tie with it either
+const EXACT_MATCH_MARGIN: f64 = 1e-6;
+
+// The extension boosts of ranking.context_boosts for the app in front. Keys match any part of its
+// name, ignoring case; with several matching, the longest (most specific) key wins
+pub fn context_boosts_for<'a>(
+    app: &str,
+    table: &'a HashMap<String, HashMap<String, f64>>,
+) -> Option<&'a HashMap<String, f64>> {
+    let app = app.to_lowercase();
+    table.iter()
+        .filter(|(key, _)| !key.is_empty() && app.contains(&key.to_lowercase()))
+        .max_by(|(a, _), (b, _)| a.len().cmp(&b.len()).then_with(|| b.cmp(a)))
+        .map(|(_, boosts)| boosts)
+}
+
+// A file result's boost by its extension, capped to CONTEXT_BOOST_RANGE; 0 for other results
+pub fn context_boost(result: &SearchResult, boosts: &HashMap<String, f64>) -> f64 {
+    if result.category != Category::File {
+        return 0.0;
+    }
+    let Some(extension) = result.path.as_ref().and_then(|path| path.extension()) else {
+        return 0.0;
+    };
+    let extension = extension.to_string_lossy().to_lowercase();
+    boosts.iter()
+        .find(|(key, _)| key.trim_start_matches('.').eq_ignore_ascii_case(&extension))
+        .map(|(_, boost)| boost.clamp(*CONTEXT_BOOST_RANGE.start(), *CONTEXT_BOOST_RANGE.end()))
+        .unwrap_or(0.0)
+}
+
+pub fn is_exact_title_match(result: &SearchResult, query: &str) -> bool {
+    let query = query.trim();
+    !query.is_empty() && result.title.eq_ignore_ascii_case(query)
+}
+
+// The part of `boost` that keeps a result scored `total` below `exact_floor`, the lowest score of
+// an exact title match. Results already above it keep the whole boost; their order against the
+// exact match doesn't change
+pub fn boost_below_exact(total: f64, boost: f64, exact_floor: Option<f64>) -> f64 {
+    match exact_floor {
+        Some(floor) if total < floor => boost.min((floor - EXACT_MATCH_MARGIN - total).max(0.0)),
+        _ => boost,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use falcommand_config::Action;
+    use super::*;
+
+    fn table(entries: &[(&str, &[(&str, f64)])]) -> HashMap<String, HashMap<String, f64>> {
+        entries.iter()
+            .map(|(app, boosts)| (app.to_string(), boosts.iter().map(|(ext, boost)| (ext.to_string(), *boost)).collect()))
+            .collect()
+    }
+
+    fn file(name: &str) -> SearchResult {
+        SearchResult::new(name, "")
+            .with_action(Action::OpenFile(PathBuf::from(format!("/home/me/{}", name))))
+            .with_category(Category::File)
+            .with_path(PathBuf::from(format!("/home/me/{}", name)))
+    }
+
+    // "code" matches "Visual Studio Code"; the more specific "studio code" wins over it
+    #[test]
+    fn the_longest_matching_app_key_wins() {
+        let table = table(&[("code", &[("rs", 0.1)]), ("Studio Code", &[("md", 0.05)]), ("", &[("txt", 0.1)])]);
+        assert!(context_boosts_for("Visual Studio Code", &table).unwrap().contains_key("md"));
+        assert!(context_boosts_for("vscode", &table).unwrap().contains_key("rs"));
+        // An empty key would match every app, so it never does
+        assert_eq!(context_boosts_for("Thunderbird", &table), None);
+    }
+
+    #[test]
+    fn only_file_extensions_are_boosted_and_within_the_cap() {
+        let boosts = HashMap::from([(".RS".to_string(), 0.08), ("toml".to_string(), 0.5), ("md".to_string(), -1.0)]);
+        assert_eq!(context_boost(&file("main.rs"), &boosts), 0.08);
+        assert_eq!(context_boost(&file("Cargo.TOML"), &boosts), 0.1);
+        assert_eq!(context_boost(&file("README.md"), &boosts), 0.0);
+        assert_eq!(context_boost(&file("Makefile"), &boosts), 0.0);
+        assert_eq!(context_boost(&file("notes.txt"), &boosts), 0.0);
+
+        let app = file("main.rs").with_category(Category::Application);
+        assert_eq!(context_boost(&app, &boosts), 0.0);
+    }
+
+    #[test]
+    fn exact_title_matches_ignore_case_and_surrounding_space() {
+        let result = SearchResult::new("Cargo.toml", "");
+        assert!(is_exact_title_match(&result, " cargo.TOML "));
+        assert!(!is_exact_title_match(&result, "cargo"));
+        assert!(!is_exact_title_match(&SearchResult::new("", ""), "  "));
+    }
+
+    // Below the exact match the boost stops just short of it; above it, or with none, it applies whole
+    #[test]
+    fn boosts_stop_short_of_the_exact_match() {
+        let capped = boost_below_exact(0.75, 0.1, Some(0.8));
+        assert!(capped > 0.0 && 0.75 + capped < 0.8, "{}", capped);
+        assert_eq!(boost_below_exact(0.8, 0.1, Some(0.8)), 0.1);
+        assert_eq!(boost_below_exact(0.9, 0.1, Some(0.8)), 0.1);
+        assert_eq!(boost_below_exact(0.5, 0.1, Some(0.8)), 0.1);
+        assert_eq!(boost_below_exact(0.5, 0.1, None), 0.1);
+        assert_eq!(boost_below_exact(0.8 - 1e-7, 0.1, Some(0.8)), 0.0);
+    }
+}
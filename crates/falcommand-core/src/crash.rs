@@ -0,0 +1,546 @@
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use log::{info, warn, error};
+
+use falcommand_config::{Config, PathResolver};
+use crate::http::{HttpError, WebClient};
+use crate::task_registry::{TaskGuard, TaskRegistry};
+
+tokio::task_local! {
+    // Name of the supervised task currently running, picked up by the panic hook
+    static TASK_NAME: String;
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CrashError {
+    #[error("Failed to access crash reports: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Failed to serialize crash report: {0}")]
+    SerializationError(#[from] serde_json::Error),
+
+    #[error("Failed to upload crash report: {0}")]
+    UploadError(#[from] HttpError),
+
+    #[error("Other crash reporting error: {0}")]
+    Other(String),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrashReport {
+    pub message: String,
+    pub location: Option<String>,
+    pub thread: Option<String>,
+    pub task: Option<String>,
+    pub backtrace: String,
+    pub app_version: String,
+    pub os: String,
+    pub arch: String,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub config_summary: serde_json::Value,
+}
+
+#[derive(Debug, Clone)]
+pub struct CrashReporter {
+    crash_dir: PathBuf,
+    app_version: String,
+    config_summary: serde_json::Value,
+}
+
+impl CrashReporter {
+    const NOTIFIED_MARKER: &'static str = ".last_notified";
+    const SENSITIVE_KEYS: [&'static str; 6] = ["key", "token", "password", "secret", "credential", "auth"];
+
+    pub fn new(app_version: impl Into<String>, config: &Config, resolver: &PathResolver) -> Self {
+        let crash_dir = resolver.shared_data_file("crashes");
+
+        Self::with_crash_dir(crash_dir, app_version, config)
+    }
+
+    pub fn with_crash_dir(crash_dir: PathBuf, app_version: impl Into<String>, config: &Config) -> Self {
+        Self {
+            crash_dir,
+            app_version: app_version.into(),
+            config_summary: Self::sanitize_config(config),
+        }
+    }
+
+    pub fn crash_dir(&self) -> &Path {
+        &self.crash_dir
+    }
+
+    // Config snapshot with secrets and filesystem paths removed so reports can be shared safely
+    pub fn sanitize_config(config: &Config) -> serde_json::Value {
+        match serde_json::to_value(config) {
+            Ok(mut value) => {
+                Self::redact_value(None, &mut value);
+                value
+            }
+            Err(e) => serde_json::Value::String(format!("<unavailable: {}>", e)),
+        }
+    }
+
+    fn redact_value(key: Option<&str>, value: &mut serde_json::Value) {
+        let is_sensitive_key = key
+            .map(|k| {
+                let k = k.to_lowercase();
+                Self::SENSITIVE_KEYS.iter().any(|s| k.contains(s))
+            })
+            .unwrap_or(false);
+
+        match value {
+            serde_json::Value::Object(map) => {
+                for (k, v) in map.iter_mut() {
+                    if is_sensitive_key {
+                        *v = serde_json::Value::String("[redacted]".to_string());
+                    } else {
+                        Self::redact_value(Some(k), v);
+                    }
+                }
+            }
+            serde_json::Value::Array(items) => {
+                for item in items.iter_mut() {
+                    Self::redact_value(key, item);
+                }
+            }
+            serde_json::Value::String(s) => {
+                if is_sensitive_key {
+                    *s = "[redacted]".to_string();
+                } else if Self::looks_like_path(s) {
+                    *s = "[path]".to_string();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn looks_like_path(s: &str) -> bool {
+        s.contains('/') || s.contains('\\') || s.starts_with('~')
+    }
+
+    pub fn build_report(&self, info: &std::panic::PanicHookInfo<'_>) -> CrashReport {
+        let message = if let Some(s) = info.payload().downcast_ref::<&str>() {
+            s.to_string()
+        } else if let Some(s) = info.payload().downcast_ref::<String>() {
+            s.clone()
+        } else {
+            "Unknown panic payload".to_string()
+        };
+
+        CrashReport {
+            message,
+            location: info.location().map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column())),
+            thread: std::thread::current().name().map(|n| n.to_string()),
+            task: TASK_NAME.try_with(|name| name.clone()).ok(),
+            backtrace: std::backtrace::Backtrace::force_capture().to_string(),
+            app_version: self.app_version.clone(),
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            timestamp: chrono::Utc::now(),
+            config_summary: self.config_summary.clone(),
+        }
+    }
+
+    // Panic hooks run synchronously, so the report is written with blocking std::fs calls
+    pub fn write_report(&self, report: &CrashReport) -> Result<PathBuf, CrashError> {
+        std::fs::create_dir_all(&self.crash_dir)?;
+
+        let filename = format!("crash-{}.json", report.timestamp.format("%Y%m%d-%H%M%S%.3f"));
+        let path = self.crash_dir.join(filename);
+        std::fs::write(&path, serde_json::to_string_pretty(report)?)?;
+        Ok(path)
+    }
+
+    pub fn install_panic_hook(&self) {
+        let reporter = self.clone();
+        let default_hook = std::panic::take_hook();
+
+        std::panic::set_hook(Box::new(move |info| {
+            let report = reporter.build_report(info);
+            match reporter.write_report(&report) {
+                Ok(path) => error!("Crash report written to {:?}", path),
+                Err(e) => error!("Failed to write crash report: {}", e),
+            }
+            default_hook(info);
+        }));
+
+        info!("Crash reporter installed (reports in {:?})", self.crash_dir);
+    }
+
+    // Reports written since the user was last notified
+    pub fn pending_reports(&self) -> Vec<PathBuf> {
+        let marker_time = std::fs::metadata(self.crash_dir.join(Self::NOTIFIED_MARKER))
+            .and_then(|m| m.modified())
+            .ok();
+
+        let entries = match std::fs::read_dir(&self.crash_dir) {
+            Ok(entries) => entries,
+            Err(_) => return Vec::new(),
+        };
+
+        let mut reports: Vec<PathBuf> = entries
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().extension().map(|ext| ext == "json").unwrap_or(false))
+            .filter(|e| {
+                let modified = e.metadata().and_then(|m| m.modified()).ok();
+                match (modified, marker_time) {
+                    (Some(modified), Some(marker)) => modified > marker,
+                    _ => true,
+                }
+            })
+            .map(|e| e.path())
+            .collect();
+
+        reports.sort();
+        reports
+    }
+
+    // Newest report, notified or not
+    pub fn latest_report(&self) -> Option<PathBuf> {
+        let entries = std::fs::read_dir(&self.crash_dir).ok()?;
+        entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|path| path.extension().map(|ext| ext == "json").unwrap_or(false))
+            .max()
+    }
+
+    pub fn mark_notified(&self) -> Result<(), CrashError> {
+        std::fs::create_dir_all(&self.crash_dir)?;
+        std::fs::write(self.crash_dir.join(Self::NOTIFIED_MARKER), chrono::Utc::now().to_rfc3339())?;
+        Ok(())
+    }
+
+    // Opt-in: posts the report as it was written, with secrets and paths already redacted
+    pub async fn upload_report(&self, client: &WebClient, url: &str, report_path: &Path) -> Result<(), CrashError> {
+        let content = tokio::fs::read_to_string(report_path).await?;
+        client.post_json(url, &content).await?;
+        info!("Uploaded crash report {:?} to {}", report_path, url);
+        Ok(())
+    }
+}
+
+// Detects startups that keep crashing. The marker is written when startup begins and removed once
+// the window is up, so a marker left behind means that startup never finished; quitting later
+// leaves nothing behind
+#[derive(Debug, Clone)]
+pub struct StartupGuard {
+    marker_path: PathBuf,
+}
+
+impl StartupGuard {
+    // Consecutive unfinished startups after which the next one runs in safe mode
+    pub const SAFE_MODE_AFTER: u32 = 2;
+
+    pub fn new(resolver: &PathResolver) -> Self {
+        Self::with_marker_path(resolver.shared_data_file("startup.marker"))
+    }
+
+    pub fn with_marker_path(marker_path: PathBuf) -> Self {
+        Self { marker_path }
+    }
+
+    // Unfinished startups in a row before this one. A marker that can't be read counts as one
+    pub fn unfinished_startups(&self) -> u32 {
+        match std::fs::read_to_string(&self.marker_path) {
+            Ok(content) => content.trim().parse().unwrap_or(1),
+            Err(_) => 0,
+        }
+    }
+
+    // Records that startup began and returns the unfinished startups before it
+    pub fn begin(&self) -> Result<u32, CrashError> {
+        let unfinished = self.unfinished_startups();
+        if let Some(parent) = self.marker_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.marker_path, (unfinished + 1).to_string())?;
+        Ok(unfinished)
+    }
+
+    pub fn needs_safe_mode(unfinished: u32) -> bool {
+        unfinished >= Self::SAFE_MODE_AFTER
+    }
+
+    // Startup finished; the crash count starts over
+    pub fn complete(&self) -> Result<(), CrashError> {
+        match std::fs::remove_file(&self.marker_path) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+// Restarts a non-critical background task with exponential backoff when it panics
+#[derive(Debug, Clone)]
+pub struct TaskSupervisor {
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    max_restarts: u32,
+    stable_after: Duration,
+}
+
+impl Default for TaskSupervisor {
+    fn default() -> Self {
+        Self {
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+            max_restarts: 5,
+            stable_after: Duration::from_secs(300),
+        }
+    }
+}
+
+impl TaskSupervisor {
+    pub fn new(initial_backoff: Duration, max_backoff: Duration, max_restarts: u32) -> Self {
+        Self {
+            initial_backoff,
+            max_backoff,
+            max_restarts,
+            ..Self::default()
+        }
+    }
+
+    pub fn backoff_for(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.min(16));
+        self.initial_backoff.saturating_mul(factor).min(self.max_backoff)
+    }
+
+    // Returns the number of restarts performed before the task finished or was abandoned
+    pub async fn run<F, Fut>(&self, name: &str, factory: F) -> u32
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.run_tracked(name, factory, None).await
+    }
+
+    async fn run_tracked<F, Fut>(&self, name: &str, factory: F, tracked: Option<&TaskGuard>) -> u32
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let mut attempt = 0;
+        let mut restarts = 0;
+
+        loop {
+            let started = Instant::now();
+            let handle = tokio::spawn(TASK_NAME.scope(name.to_string(), factory()));
+
+            match handle.await {
+                Ok(()) => {
+                    info!("Supervised task '{}' finished", name);
+                    return restarts;
+                }
+                Err(e) if e.is_panic() => {
+                    // A task that ran for a while before dying starts its backoff from scratch
+                    if started.elapsed() >= self.stable_after {
+                        attempt = 0;
+                    }
+
+                    if attempt >= self.max_restarts {
+                        error!("Supervised task '{}' panicked too often; giving up", name);
+                        return restarts;
+                    }
+
+                    let backoff = self.backoff_for(attempt);
+                    warn!("Supervised task '{}' panicked; restarting in {:?}", name, backoff);
+                    tokio::time::sleep(backoff).await;
+                    attempt += 1;
+                    restarts += 1;
+                    if let Some(tracked) = tracked {
+                        tracked.state().restarted();
+                    }
+                }
+                Err(_) => {
+                    info!("Supervised task '{}' was cancelled", name);
+                    return restarts;
+                }
+            }
+        }
+    }
+
+    // Recorded in TaskRegistry::global() until it finishes
+    pub fn spawn<F, Fut>(self, name: &str, factory: F) -> tokio::task::JoinHandle<u32>
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let name = name.to_string();
+        let tracked = TaskRegistry::global().track(&name, chrono::Utc::now());
+        tokio::spawn(async move { self.run_tracked(&name, factory, Some(&tracked)).await })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use serde_json::json;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[test]
+    fn secrets_and_paths_are_redacted() {
+        let mut value = json!({
+            "sync": {"provider": "dropbox", "api_token": "abc", "credentials": {"user": "me", "pin": 1234}},
+            "search": {"index_directories": ["/home/me/Documents", "~/Music"], "max_results": 50},
+            "plugins": {"plugin_settings": {"translator": {"endpoint": "https://example.com/translate", "api_key": ["k1"]}}},
+        });
+        CrashReporter::redact_value(None, &mut value);
+        assert_eq!(value, json!({
+            "sync": {"provider": "dropbox", "api_token": "[redacted]", "credentials": {"user": "[redacted]", "pin": "[redacted]"}},
+            "search": {"index_directories": ["[path]", "[path]"], "max_results": 50},
+            "plugins": {"plugin_settings": {"translator": {"endpoint": "[path]", "api_key": ["[redacted]"]}}},
+        }));
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        let supervisor = TaskSupervisor::new(Duration::from_millis(100), Duration::from_secs(1), 5);
+        let backoffs: Vec<_> = (0..6).map(|attempt| supervisor.backoff_for(attempt).as_millis()).collect();
+        assert_eq!(backoffs, vec![100, 200, 400, 800, 1000, 1000]);
+        assert_eq!(supervisor.backoff_for(u32::MAX), Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn panicking_tasks_restart_until_they_finish() {
+        let runs = Arc::new(AtomicU32::new(0));
+        let counted = runs.clone();
+        let supervisor = TaskSupervisor::new(Duration::from_millis(1), Duration::from_millis(5), 5);
+        let restarts = supervisor.run("flaky", move || {
+            let runs = counted.clone();
+            async move {
+                if runs.fetch_add(1, Ordering::SeqCst) < 2 {
+                    panic!("flaky task failed");
+                }
+            }
+        }).await;
+        assert_eq!((restarts, runs.load(Ordering::SeqCst)), (2, 3));
+    }
+
+    #[tokio::test]
+    async fn supervisors_give_up_after_max_restarts() {
+        let runs = Arc::new(AtomicU32::new(0));
+        let counted = runs.clone();
+        let supervisor = TaskSupervisor::new(Duration::from_millis(1), Duration::from_millis(5), 3);
+        let restarts = supervisor.run("broken", move || {
+            let runs = counted.clone();
+            async move {
+                runs.fetch_add(1, Ordering::SeqCst);
+                panic!("broken task failed");
+            }
+        }).await;
+        assert_eq!((restarts, runs.load(Ordering::SeqCst)), (3, 4));
+    }
+
+    #[test]
+    fn two_unfinished_startups_in_a_row_trigger_safe_mode() {
+        let dir = tempfile::tempdir().unwrap();
+        let guard = StartupGuard::with_marker_path(dir.path().join("state").join("startup.marker"));
+        assert_eq!(guard.unfinished_startups(), 0);
+
+        // Each startup that crashes before completing leaves the count one higher
+        let counts: Vec<_> = (0..3).map(|_| guard.begin().unwrap()).collect();
+        assert_eq!(counts, vec![0, 1, 2]);
+        let safe: Vec<_> = counts.iter().map(|&count| StartupGuard::needs_safe_mode(count)).collect();
+        assert_eq!(safe, vec![false, false, true]);
+
+        // One finished startup starts the count over
+        guard.complete().unwrap();
+        assert_eq!(guard.begin().unwrap(), 0);
+    }
+
+    #[test]
+    fn normal_quits_never_count_as_crashes() {
+        let dir = tempfile::tempdir().unwrap();
+        let guard = StartupGuard::with_marker_path(dir.path().join("startup.marker"));
+        for _ in 0..5 {
+            assert_eq!(guard.begin().unwrap(), 0);
+            guard.complete().unwrap();
+        }
+        assert!(!dir.path().join("startup.marker").exists());
+        // Completing twice, or without a marker, is fine
+        guard.complete().unwrap();
+    }
+
+    #[test]
+    fn unreadable_markers_count_as_one_crash() {
+        let dir = tempfile::tempdir().unwrap();
+        let marker = dir.path().join("startup.marker");
+        std::fs::write(&marker, "garbage").unwrap();
+        let guard = StartupGuard::with_marker_path(marker.clone());
+        assert_eq!(guard.begin().unwrap(), 1);
+        assert_eq!(std::fs::read_to_string(&marker).unwrap(), "2");
+        assert!(StartupGuard::needs_safe_mode(guard.unfinished_startups()));
+    }
+
+    // Answers one request with `status` and hands back what was posted
+    async fn endpoint(status: &'static str) -> (String, tokio::task::JoinHandle<String>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/crashes", listener.local_addr().unwrap());
+        let served = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buffer = [0u8; 4096];
+            loop {
+                let read = socket.read(&mut buffer).await.unwrap();
+                request.extend_from_slice(&buffer[..read]);
+                let text = String::from_utf8_lossy(&request).into_owned();
+                if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                    let length = head.lines()
+                        .find_map(|line| line.to_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse::<usize>().unwrap()))
+                        .unwrap_or(0);
+                    if body.len() >= length {
+                        socket.write_all(format!("HTTP/1.1 {}\r\ncontent-length: 0\r\n\r\n", status).as_bytes()).await.unwrap();
+                        return body.to_string();
+                    }
+                }
+            }
+        });
+        (url, served)
+    }
+
+    fn written_report(dir: &Path) -> (CrashReporter, PathBuf) {
+        let reporter = CrashReporter::with_crash_dir(dir.to_path_buf(), "1.2.3", &Config::default());
+        let report = CrashReport {
+            message: "index out of bounds".to_string(),
+            location: None,
+            thread: None,
+            task: Some("auto-sync".to_string()),
+            backtrace: String::new(),
+            app_version: "1.2.3".to_string(),
+            os: "linux".to_string(),
+            arch: "x86_64".to_string(),
+            timestamp: chrono::Utc::now(),
+            config_summary: reporter.config_summary.clone(),
+        };
+        let path = reporter.write_report(&report).unwrap();
+        (reporter, path)
+    }
+
+    #[tokio::test]
+    async fn reports_are_posted_as_written() {
+        let dir = tempfile::tempdir().unwrap();
+        let (reporter, path) = written_report(dir.path());
+        let (url, served) = endpoint("200 OK").await;
+
+        reporter.upload_report(&WebClient::new(), &url, &path).await.unwrap();
+        let posted: CrashReport = serde_json::from_str(&served.await.unwrap()).unwrap();
+        assert_eq!(posted.message, "index out of bounds");
+        assert_eq!(posted.task.as_deref(), Some("auto-sync"));
+    }
+
+    #[tokio::test]
+    async fn rejected_uploads_are_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let (reporter, path) = written_report(dir.path());
+        let (url, _served) = endpoint("500 Internal Server Error").await;
+
+        let uploaded = reporter.upload_report(&WebClient::new(), &url, &path).await;
+        assert!(matches!(uploaded, Err(CrashError::UploadError(HttpError::Status { status: 500, .. }))));
+    }
+}
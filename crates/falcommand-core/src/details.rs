@@ -0,0 +1,266 @@
+use falcommand_config::{Action, AppearanceConfig, Category, ResultRowLayout, SearchResult};
+
+// Detail pairs shown when the selected row expands; the peek overlay shows them all
+pub const EXPANDED_DETAIL_ROWS: usize = 3;
+
+// "Humidity: 62%"
+pub fn detail_line(key: &str, value: &str) -> String {
+    format!("{}: {}", key, value)
+}
+
+// Lines under a selected row: why it matched, then the first few details, or all of them while peeking
+pub fn detail_lines(result: &SearchResult, peeking: bool) -> Vec<String> {
+    let limit = if peeking { result.details.len() } else { EXPANDED_DETAIL_ROWS };
+    let reason = result.match_reason.as_ref().and_then(|reason| reason.row_suffix());
+    reason.into_iter()
+        .chain(result.details.iter().take(limit).map(|(key, value)| detail_line(key, value)))
+        .collect()
+}
+
+// The description with why the result matched after it: "Application · matched keyword: browser"
+pub fn row_description(result: &SearchResult) -> String {
+    match result.match_reason.as_ref().and_then(|reason| reason.row_suffix()) {
+        Some(suffix) if !result.description.is_empty() => format!("{} · {}", result.description, suffix),
+        Some(suffix) => suffix,
+        None => result.description.clone(),
+    }
+}
+
+// Which parts of a result its row shows
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RowFields {
+    pub description: bool,
+    pub path: bool,
+    pub category_badge: bool,
+    pub score: bool,
+    pub shortcut_hints: bool,
+}
+
+impl RowFields {
+    pub fn for_layout(layout: ResultRowLayout) -> Self {
+        match layout {
+            ResultRowLayout::TitleOnly => Self::default(),
+            ResultRowLayout::TitleDescription => Self { description: true, ..Self::default() },
+            ResultRowLayout::TitlePath => Self { path: true, ..Self::default() },
+            ResultRowLayout::Detailed => Self { description: true, path: true, category_badge: true, ..Self::default() },
+        }
+    }
+
+    // The preset with the appearance toggles on top; a toggle only ever adds a field
+    pub fn from_appearance(appearance: &AppearanceConfig) -> Self {
+        let fields = Self::for_layout(appearance.result_row_layout);
+        Self {
+            category_badge: fields.category_badge || appearance.show_category_badge,
+            score: appearance.show_score_debug,
+            shortcut_hints: appearance.show_shortcut_hints,
+            ..fields
+        }
+    }
+}
+
+pub fn category_badge(category: &Category) -> String {
+    match category {
+        Category::Application => "App".to_string(),
+        Category::File => "File".to_string(),
+        Category::Bookmark => "Bookmark".to_string(),
+        Category::Plugin(name) => name.clone(),
+        Category::SystemCommand => "System".to_string(),
+        Category::CustomCommand => "Command".to_string(),
+    }
+}
+
+// A result as one row: the title line and the smaller line under it, if any
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResultRow {
+    pub title: String,
+    // Picks the badge's color
+    pub category: Category,
+    pub badge: Option<String>,
+    pub score: Option<String>,
+    pub subtitle: Option<String>,
+    pub hints: Option<String>,
+    // Shown only while the row is selected, and in the peek overlay
+    pub expanded_details: Vec<String>,
+    pub peek_details: Vec<String>,
+}
+
+pub fn result_row(result: &SearchResult, fields: RowFields) -> ResultRow {
+    let path = result.path.as_ref().map(|path| path.display().to_string());
+    let mut subtitle = Vec::new();
+    if fields.description {
+        subtitle.push(row_description(result));
+    }
+    if fields.path {
+        match path {
+            Some(path) => subtitle.push(path),
+            // A title-and-path row falls back to the description for results without a path
+            None if !fields.description => subtitle.push(row_description(result)),
+            None => {}
+        }
+    }
+    subtitle.retain(|part| !part.is_empty());
+
+    let actions: Vec<&str> = result.secondary_actions.iter().map(|action| action.title.as_str()).collect();
+    let hints = (fields.shortcut_hints && !actions.is_empty()).then(|| format!("More: {}", actions.join(", ")));
+
+    ResultRow {
+        title: result.title.clone(),
+        category: result.category.clone(),
+        badge: fields.category_badge.then(|| category_badge(&result.category)),
+        score: fields.score.then(|| format!("{:.3}", result.score)),
+        subtitle: (!subtitle.is_empty()).then(|| subtitle.join(" · ")),
+        hints,
+        expanded_details: detail_lines(result, false),
+        peek_details: detail_lines(result, true),
+    }
+}
+
+// The row as plain text: "Firefox [App] (0.912)", then the subtitle and hints indented under it
+pub fn plain_row(row: &ResultRow) -> String {
+    let mut text = row.title.clone();
+    if let Some(ref badge) = row.badge {
+        text.push_str(&format!(" [{}]", badge));
+    }
+    if let Some(ref score) = row.score {
+        text.push_str(&format!(" ({})", score));
+    }
+    for line in row.subtitle.iter().chain(&row.hints) {
+        text.push_str(&format!("\n    {}", line));
+    }
+    text
+}
+
+fn copy_title(key: &str, value: &str) -> String {
+    format!("Copy {}", detail_line(&key.to_lowercase(), value))
+}
+
+// One "Copy humidity: 62%" secondary action per detail, copying just the value.
+// Actions from an earlier pass are replaced, so applying it twice changes nothing
+pub fn decorate_with_detail_actions(mut result: SearchResult) -> SearchResult {
+    if result.details.is_empty() {
+        return result;
+    }
+
+    let titles: Vec<String> = result.details.iter().map(|(key, value)| copy_title(key, value)).collect();
+    result.secondary_actions.retain(|action| !titles.contains(&action.title));
+
+    let details = result.details.clone();
+    for ((_, value), title) in details.into_iter().zip(titles) {
+        result = result.with_secondary_action(title, Action::CopyToClipboard(value));
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use falcommand_config::Config;
+
+    fn titles(result: &SearchResult) -> Vec<&str> {
+        result.secondary_actions.iter().map(|action| action.title.as_str()).collect()
+    }
+
+    #[test]
+    fn each_detail_gets_a_copy_action_for_its_value() {
+        let result = SearchResult::new("Weather", "Tokyo")
+            .with_secondary_action("Open forecast", Action::OpenUrl("https://example.com".to_string()))
+            .with_detail("Humidity", "62%")
+            .with_detail("Wind", "3 m/s");
+        let decorated = decorate_with_detail_actions(result);
+
+        assert_eq!(titles(&decorated), ["Open forecast", "Copy humidity: 62%", "Copy wind: 3 m/s"]);
+        assert_eq!(decorated.secondary_actions[1].action, Action::CopyToClipboard("62%".to_string()));
+        assert!(!decorated.secondary_actions[2].destructive);
+        // A second pass replaces rather than duplicates
+        assert_eq!(decorate_with_detail_actions(decorated.clone()), decorated);
+    }
+
+    #[test]
+    fn presets_map_to_their_fields() {
+        assert_eq!(RowFields::for_layout(ResultRowLayout::TitleOnly), RowFields::default());
+        assert_eq!(RowFields::for_layout(ResultRowLayout::TitleDescription), RowFields { description: true, ..RowFields::default() });
+        assert_eq!(RowFields::for_layout(ResultRowLayout::TitlePath), RowFields { path: true, ..RowFields::default() });
+        assert_eq!(
+            RowFields::for_layout(ResultRowLayout::Detailed),
+            RowFields { description: true, path: true, category_badge: true, ..RowFields::default() },
+        );
+    }
+
+    // The toggles add to the preset and never take a field away
+    #[test]
+    fn toggles_add_fields_to_the_preset() {
+        let mut appearance = Config::default().appearance;
+        appearance.result_row_layout = ResultRowLayout::TitleOnly;
+        appearance.show_shortcut_hints = false;
+        assert_eq!(RowFields::from_appearance(&appearance), RowFields::default());
+
+        appearance.show_category_badge = true;
+        appearance.show_score_debug = true;
+        appearance.show_shortcut_hints = true;
+        assert_eq!(
+            RowFields::from_appearance(&appearance),
+            RowFields { category_badge: true, score: true, shortcut_hints: true, ..RowFields::default() },
+        );
+
+        appearance.result_row_layout = ResultRowLayout::Detailed;
+        appearance.show_category_badge = false;
+        assert!(RowFields::from_appearance(&appearance).category_badge);
+    }
+
+    fn file() -> SearchResult {
+        SearchResult::new("report.txt", "File · 2 KB")
+            .with_category(Category::File)
+            .with_path("/home/me/docs/report.txt".into())
+            .with_score(0.5)
+            .with_secondary_action("Open with…", Action::SetQuery("open with".to_string()))
+    }
+
+    fn plain(result: &SearchResult, layout: ResultRowLayout) -> String {
+        plain_row(&result_row(result, RowFields::for_layout(layout)))
+    }
+
+    #[test]
+    fn plain_rows_under_each_preset() {
+        let file = file();
+        assert_eq!(plain(&file, ResultRowLayout::TitleOnly), "report.txt");
+        assert_eq!(plain(&file, ResultRowLayout::TitleDescription), "report.txt\n    File · 2 KB");
+        assert_eq!(plain(&file, ResultRowLayout::TitlePath), "report.txt\n    /home/me/docs/report.txt");
+        assert_eq!(plain(&file, ResultRowLayout::Detailed), "report.txt [File]\n    File · 2 KB · /home/me/docs/report.txt");
+
+        // Without a path the path preset shows the description instead, and an empty one leaves no line
+        let calculator = SearchResult::new("4", "2 + 2").with_category(Category::Plugin("Calculator".to_string()));
+        assert_eq!(plain(&calculator, ResultRowLayout::TitlePath), "4\n    2 + 2");
+        assert_eq!(plain(&calculator, ResultRowLayout::Detailed), "4 [Calculator]\n    2 + 2");
+        assert_eq!(plain(&SearchResult::new("Firefox", ""), ResultRowLayout::TitleDescription), "Firefox");
+    }
+
+    #[test]
+    fn plain_rows_with_score_and_hints() {
+        let fields = RowFields { score: true, shortcut_hints: true, ..RowFields::for_layout(ResultRowLayout::TitleOnly) };
+        assert_eq!(plain_row(&result_row(&file(), fields)), "report.txt (0.500)\n    More: Open with…");
+        // No secondary actions, no hints line
+        assert_eq!(plain_row(&result_row(&SearchResult::new("Firefox", ""), fields)), "Firefox (0.000)");
+    }
+
+    // The selected row grows by the first few details; peeking shows every one
+    #[test]
+    fn rows_carry_their_expanded_and_peek_lines() {
+        let mut result = SearchResult::new("Weather", "Tokyo");
+        for (key, value) in [("Humidity", "62%"), ("Wind", "3 m/s"), ("Pressure", "1012 hPa"), ("UV", "4")] {
+            result = result.with_detail(key, value);
+        }
+        let row = result_row(&result, RowFields::default());
+        assert_eq!(row.expanded_details, ["Humidity: 62%", "Wind: 3 m/s", "Pressure: 1012 hPa"]);
+        assert_eq!(row.peek_details.len(), 4);
+        assert_eq!(row.peek_details[3], "UV: 4");
+
+        let plain = result_row(&SearchResult::new("Firefox", ""), RowFields::default());
+        assert!(plain.expanded_details.is_empty() && plain.peek_details.is_empty());
+    }
+
+    #[test]
+    fn results_without_details_are_untouched() {
+        let result = SearchResult::new("Firefox", "").with_secondary_action("Reveal", Action::OpenFile("/usr/bin".into()));
+        assert_eq!(decorate_with_detail_actions(result.clone()), result);
+    }
+}
@@ -0,0 +1,523 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
+use tokio::sync::RwLock;
+use async_trait::async_trait;
+use serde::Serialize;
+
+use falcommand_config::{Action, Category, Config, PathResolver, SearchResult, SyntaxHint};
+use falcommand_config::i18n::tr;
+use falcommand_platform::PlatformProvider;
+use crate::index::IndexManager;
+use crate::path_audit::audit_config_include_paths;
+use crate::secrets::{KeyResolver, MachineKey, PassphraseKey, SecretStore};
+use crate::source::SearchSource;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+impl CheckStatus {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Pass => "pass",
+            Self::Warn => "warn",
+            Self::Fail => "fail",
+        }
+    }
+}
+
+// The outcome of one check, with what to do about it when it didn't pass
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CheckResult {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+    pub guidance: Option<String>,
+}
+
+impl CheckResult {
+    pub fn new(name: impl Into<String>, status: CheckStatus, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status,
+            detail: detail.into(),
+            guidance: None,
+        }
+    }
+
+    pub fn pass(name: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self::new(name, CheckStatus::Pass, detail)
+    }
+
+    pub fn warn(name: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self::new(name, CheckStatus::Warn, detail)
+    }
+
+    pub fn fail(name: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self::new(name, CheckStatus::Fail, detail)
+    }
+
+    pub fn with_guidance(mut self, guidance: impl Into<String>) -> Self {
+        self.guidance = Some(guidance.into());
+        self
+    }
+
+    // "[warn] Keyring: not available; secrets are stored unencrypted in … — Install or unlock …"
+    pub fn line(&self) -> String {
+        let mut line = format!("[{}] {}: {}", self.status.label(), self.name, self.detail);
+        if let Some(ref guidance) = self.guidance {
+            line.push_str(&format!(" — {}", guidance));
+        }
+        line
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DoctorReport {
+    pub checks: Vec<CheckResult>,
+}
+
+impl DoctorReport {
+    pub fn push(&mut self, check: CheckResult) {
+        self.checks.push(check);
+    }
+
+    pub fn extend(&mut self, checks: impl IntoIterator<Item = CheckResult>) {
+        self.checks.extend(checks);
+    }
+
+    pub fn count(&self, status: CheckStatus) -> usize {
+        self.checks.iter().filter(|check| check.status == status).count()
+    }
+
+    // The worst status of any check; Pass for an empty report
+    pub fn overall(&self) -> CheckStatus {
+        self.checks.iter().map(|check| check.status).max().unwrap_or(CheckStatus::Pass)
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "{} passed, {} warnings, {} failed",
+            self.count(CheckStatus::Pass),
+            self.count(CheckStatus::Warn),
+            self.count(CheckStatus::Fail),
+        )
+    }
+
+    // One line per check, then the summary; what `falcommand doctor` prints
+    pub fn render(&self) -> String {
+        let mut text = String::new();
+        for check in &self.checks {
+            text.push_str(&check.line());
+            text.push('\n');
+        }
+        text.push_str(&self.summary());
+        text.push('\n');
+        text
+    }
+}
+
+// What the checks look at
+#[derive(Clone)]
+pub struct DoctorContext {
+    pub config: Config,
+    pub resolver: PathResolver,
+    pub platform: Arc<dyn PlatformProvider>,
+    pub index_manager: Arc<IndexManager>,
+    // The store `create_secret_store` picked for this run
+    pub secret_store: Arc<dyn SecretStore>,
+}
+
+impl DoctorContext {
+    pub fn new(
+        config: Config,
+        resolver: PathResolver,
+        platform: Arc<dyn PlatformProvider>,
+        index_manager: Arc<IndexManager>,
+        secret_store: Arc<dyn SecretStore>,
+    ) -> Self {
+        Self {
+            config,
+            resolver,
+            platform,
+            index_manager,
+            secret_store,
+        }
+    }
+}
+
+// Registered and released again to see whether global hotkeys work at all; unlikely to be taken
+pub const PROBE_HOTKEY: &str = "Ctrl+Alt+Shift+F12";
+
+// Hosts the built-in plugins talk to, checked while the plugin is enabled
+pub const PLUGIN_HOSTS: [(&str, &str); 1] = [("network", "api.ipify.org:443")];
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+pub async fn check_hotkey(context: &DoctorContext) -> CheckResult {
+    let name = "Hotkey";
+    if let Err(e) = context.platform.register_global_hotkey(PROBE_HOTKEY, Box::new(|| {})) {
+        return CheckResult::fail(name, format!("could not register a test hotkey: {}", e))
+            .with_guidance("Another application may be grabbing global shortcuts, or the desktop does not allow them");
+    }
+    if let Err(e) = context.platform.unregister_global_hotkey(PROBE_HOTKEY) {
+        return CheckResult::warn(name, format!("registered a test hotkey but could not release it: {}", e));
+    }
+    CheckResult::pass(name, format!("global hotkeys can be registered (configured: {})", context.config.behavior.hotkey))
+}
+
+// Loads every file the active configuration is made of, which also validates them
+pub async fn check_config_files(context: &DoctorContext) -> Vec<CheckResult> {
+    let mut files = vec![context.resolver.config_file(), context.resolver.platform_config_file()];
+    files.extend(context.resolver.profile_config_file());
+
+    let mut checks = Vec::new();
+    for file in files {
+        if !file.exists() {
+            continue;
+        }
+        let name = format!("Config {}", file.display());
+        checks.push(match Config::load_from_file(&file).await {
+            Ok(_) => CheckResult::pass(name, "parses and validates"),
+            Err(e) => CheckResult::fail(name, e.to_string())
+                .with_guidance("Fix the file or run `falcommand config schema` to see the expected format"),
+        });
+    }
+    if checks.is_empty() {
+        checks.push(CheckResult::warn("Config", "no configuration file yet; defaults are used"));
+    }
+    checks
+}
+
+fn check_include_path(path: &Path) -> CheckResult {
+    let name = format!("Include path {}", path.display());
+    match std::fs::read_dir(path) {
+        Ok(_) => CheckResult::pass(name, "exists and is readable"),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => CheckResult::warn(name, "does not exist")
+            .with_guidance("Remove it from search.include_paths or create the folder"),
+        Err(e) => CheckResult::fail(name, format!("cannot be read: {}", e))
+            .with_guidance("Check the folder's permissions"),
+    }
+}
+
+// Missing, redundant and protected paths come from the same audit the settings use
+pub async fn check_include_paths(context: &DoctorContext) -> Vec<CheckResult> {
+    let paths = context.index_manager.include_paths().await;
+    if paths.is_empty() {
+        return vec![CheckResult::warn("Include paths", "none configured for this platform; only applications are indexed")];
+    }
+    let audit = audit_config_include_paths(&context.config);
+    paths.iter()
+        .map(|path| match audit.findings.iter().find(|finding| Path::new(&finding.path) == path) {
+            Some(finding) => CheckResult::warn(format!("Include path {}", path.display()), finding.message())
+                .with_guidance("Fix or remove it in search.include_paths"),
+            None => check_include_path(path),
+        })
+        .collect()
+}
+
+pub async fn check_index_cache(context: &DoctorContext) -> CheckResult {
+    let name = "Index cache";
+    match context.index_manager.inspect_cache().await {
+        Some(Ok((apps, files))) => CheckResult::pass(name, format!("{} applications, {} files", apps, files)),
+        Some(Err(e)) => CheckResult::fail(name, format!("cannot be loaded: {}", e))
+            .with_guidance("Run `falcommand index rebuild` to replace it"),
+        None => CheckResult::warn(name, "not built yet; the first start scans everything")
+            .with_guidance("Run `falcommand index rebuild`"),
+    }
+}
+
+pub async fn check_notifications(context: &DoctorContext) -> CheckResult {
+    let name = "Notifications";
+    match context.platform.show_notification("FalCommand", "Test notification from falcommand doctor") {
+        Ok(()) => CheckResult::pass(name, "a test notification was sent; it should be on screen"),
+        Err(e) => CheckResult::fail(name, e.to_string())
+            .with_guidance("Allow notifications for FalCommand in the system settings"),
+    }
+}
+
+// Copies a marker and reads it back, then puts the previous text back
+pub async fn check_clipboard(context: &DoctorContext) -> CheckResult {
+    let name = "Clipboard";
+    let previous = context.platform.paste_from_clipboard().ok();
+    let marker = format!("falcommand doctor {}", chrono::Utc::now().timestamp_millis());
+
+    let outcome = context.platform.copy_to_clipboard(&marker)
+        .and_then(|()| context.platform.paste_from_clipboard());
+    if let Some(ref previous) = previous {
+        let _ = context.platform.copy_to_clipboard(previous);
+    }
+
+    match outcome {
+        Ok(text) if text == marker => CheckResult::pass(name, "copy and paste round-trip"),
+        Ok(_) => CheckResult::warn(name, "the copied text did not read back; another application may own the clipboard"),
+        Err(e) => CheckResult::fail(name, e.to_string()),
+    }
+}
+
+// Whether a tray icon can show up on this desktop, from the session's environment
+pub fn tray_support(os: &str, desktop: Option<&str>, has_display: bool) -> CheckResult {
+    let name = "Tray";
+    if os != "linux" {
+        return CheckResult::pass(name, "supported");
+    }
+    if !has_display {
+        return CheckResult::fail(name, "no graphical session (DISPLAY and WAYLAND_DISPLAY are unset)");
+    }
+    match desktop {
+        Some(desktop) if desktop.to_lowercase().contains("gnome") => CheckResult::warn(name, format!("{} shows no tray icons by default", desktop))
+            .with_guidance("Install the AppIndicator extension, or use the hotkey to open FalCommand"),
+        Some(desktop) => CheckResult::pass(name, format!("{} desktop", desktop)),
+        None => CheckResult::warn(name, "unknown desktop; the tray icon may not appear"),
+    }
+}
+
+pub async fn check_tray(_context: &DoctorContext) -> CheckResult {
+    let desktop = std::env::var("XDG_CURRENT_DESKTOP").ok();
+    let has_display = std::env::var_os("DISPLAY").is_some() || std::env::var_os("WAYLAND_DISPLAY").is_some();
+    tray_support(std::env::consts::OS, desktop.as_deref(), has_display)
+}
+
+pub async fn check_keyring(context: &DoctorContext) -> CheckResult {
+    let name = "Keyring";
+    let store = &context.secret_store;
+    let Some((file, key)) = store.encrypted_file() else {
+        return if store.is_available().await {
+            CheckResult::pass(name, format!("{} is available", store.name()))
+        } else {
+            CheckResult::warn(name, format!("{} is not available", store.name()))
+                .with_guidance("Unlock the keyring, then restart FalCommand")
+        };
+    };
+
+    let storage = if context.resolver.is_portable() {
+        format!("not used in portable mode; secrets are stored encrypted in {}", file.display())
+    } else {
+        format!("not available; secrets are stored encrypted in {}", file.display())
+    };
+    if key != MachineKey.name() {
+        return CheckResult::pass(name, format!("{} under the passphrase", storage));
+    }
+    let guidance = if context.resolver.is_portable() {
+        format!("Set {} so the portable folder's secrets open on other machines", PassphraseKey::ENV)
+    } else {
+        format!("Install or unlock a keyring such as GNOME Keyring or KWallet, or set {}", PassphraseKey::ENV)
+    };
+    CheckResult::warn(name, format!("{} under a key tied to this machine and account", storage)).with_guidance(guidance)
+}
+
+async fn reachable(address: &str) -> bool {
+    matches!(tokio::time::timeout(PROBE_TIMEOUT, TcpStream::connect(address)).await, Ok(Ok(_)))
+}
+
+pub async fn check_plugin_network(context: &DoctorContext) -> Vec<CheckResult> {
+    let mut checks = Vec::new();
+    for (plugin, address) in PLUGIN_HOSTS {
+        if !context.config.plugins.is_enabled(plugin) {
+            continue;
+        }
+        let name = format!("Network for {}", plugin);
+        checks.push(if reachable(address).await {
+            CheckResult::pass(name, format!("{} is reachable", address))
+        } else {
+            CheckResult::warn(name, format!("{} is not reachable", address))
+                .with_guidance("Check the connection or proxy; the plugin's online results stay empty until then")
+        });
+    }
+    checks
+}
+
+// macOS only: global hotkeys need Input Monitoring and menu commands need Accessibility
+pub async fn check_macos_permissions(_context: &DoctorContext) -> Vec<CheckResult> {
+    if !cfg!(target_os = "macos") {
+        return Vec::new();
+    }
+
+    let accessibility = match falcommand_platform::menu_access() {
+        Some(access) if access.is_trusted() => CheckResult::pass("Accessibility", "granted"),
+        Some(_) => CheckResult::fail("Accessibility", "not granted; menu commands are unavailable")
+            .with_guidance("System Settings › Privacy & Security › Accessibility, then enable FalCommand"),
+        None => CheckResult::warn("Accessibility", "this build cannot check it")
+            .with_guidance("If menu commands don't work, enable FalCommand in System Settings › Privacy & Security › Accessibility"),
+    };
+    let input_monitoring = CheckResult::warn("Input Monitoring", "cannot be checked from here")
+        .with_guidance("If the hotkey does nothing, enable FalCommand in System Settings › Privacy & Security › Input Monitoring");
+    vec![accessibility, input_monitoring]
+}
+
+// Runs every check in turn; a failing check never stops the others
+pub async fn run_doctor(context: &DoctorContext) -> DoctorReport {
+    let mut report = DoctorReport::default();
+    report.push(check_hotkey(context).await);
+    report.extend(check_config_files(context).await);
+    report.extend(check_include_paths(context).await);
+    report.push(check_index_cache(context).await);
+    report.push(check_notifications(context).await);
+    report.push(check_clipboard(context).await);
+    report.push(check_tray(context).await);
+    report.push(check_keyring(context).await);
+    report.extend(check_plugin_network(context).await);
+    report.extend(check_macos_permissions(context).await);
+    report
+}
+
+// The `doctor` meta-command. The report is reused for a while so typing the query doesn't send
+// a test notification on every keystroke
+pub struct DoctorSource {
+    context: DoctorContext,
+    config: Arc<RwLock<Config>>,
+    last_report: Mutex<Option<(Instant, DoctorReport)>>,
+}
+
+impl DoctorSource {
+    pub const KEYWORD: &'static str = "doctor";
+    const REPORT_TTL: Duration = Duration::from_secs(60);
+
+    pub fn new(context: DoctorContext, config: Arc<RwLock<Config>>) -> Self {
+        Self {
+            context,
+            config,
+            last_report: Mutex::new(None),
+        }
+    }
+
+    fn cached_report(&self) -> Option<DoctorReport> {
+        self.last_report.lock().unwrap().as_ref()
+            .filter(|(at, _)| at.elapsed() < Self::REPORT_TTL)
+            .map(|(_, report)| report.clone())
+    }
+
+    fn results(report: &DoctorReport) -> Vec<SearchResult> {
+        let copy_report = Action::CopyToClipboard(report.render());
+        let mut results = vec![
+            SearchResult::new(format!("Diagnostics: {}", report.overall().label()), format!("{} · select to copy the report", report.summary()))
+                .with_action(copy_report.clone())
+                .with_category(Category::SystemCommand)
+                .with_score(1.0),
+        ];
+        // Problems first, in the order they were checked
+        let mut checks: Vec<&CheckResult> = report.checks.iter().collect();
+        checks.sort_by_key(|check| std::cmp::Reverse(check.status));
+        for (position, check) in checks.into_iter().enumerate() {
+            let description = match check.guidance {
+                Some(ref guidance) => format!("{} — {}", check.detail, guidance),
+                None => check.detail.clone(),
+            };
+            results.push(
+                SearchResult::new(format!("[{}] {}", check.status.label(), check.name), description)
+                    .with_action(copy_report.clone())
+                    .with_category(Category::SystemCommand)
+                    .with_score(0.99 - position as f64 * 0.001)
+            );
+        }
+        results
+    }
+}
+
+#[async_trait]
+impl SearchSource for DoctorSource {
+    fn name(&self) -> &str {
+        "Diagnostics"
+    }
+
+    fn syntax_hints(&self) -> Vec<SyntaxHint> {
+        vec![SyntaxHint::new(
+            "doctor",
+            tr("hint.doctor", "Check the hotkey, configuration, index and permissions"),
+            "doctor",
+        )]
+    }
+
+    async fn search(&self, query: &str) -> Vec<SearchResult> {
+        if !query.trim().eq_ignore_ascii_case(Self::KEYWORD) {
+            return Vec::new();
+        }
+
+        let report = match self.cached_report() {
+            Some(report) => report,
+            None => {
+                // The configuration as it is now, not as it was at startup
+                let mut context = self.context.clone();
+                context.config = self.config.read().await.clone();
+                let report = run_doctor(&context).await;
+                *self.last_report.lock().unwrap() = Some((Instant::now(), report.clone()));
+                report
+            }
+        };
+        Self::results(&report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> DoctorReport {
+        let mut report = DoctorReport::default();
+        report.push(CheckResult::pass("Hotkey", "global hotkeys can be registered"));
+        report.extend([
+            CheckResult::warn("Tray", "unknown desktop; the tray icon may not appear"),
+            CheckResult::pass("Clipboard", "copy and paste round-trip"),
+        ]);
+        report
+    }
+
+    #[test]
+    fn report_counts_and_takes_the_worst_status() {
+        assert_eq!(DoctorReport::default().overall(), CheckStatus::Pass);
+
+        let mut report = report();
+        assert_eq!((report.count(CheckStatus::Pass), report.count(CheckStatus::Warn)), (2, 1));
+        assert_eq!(report.overall(), CheckStatus::Warn);
+        assert_eq!(report.summary(), "2 passed, 1 warnings, 0 failed");
+
+        report.push(CheckResult::fail("Notifications", "turned off"));
+        assert_eq!(report.overall(), CheckStatus::Fail);
+        assert_eq!(report.summary(), "2 passed, 1 warnings, 1 failed");
+    }
+
+    // One line per check in order, guidance after a dash, the summary last
+    #[test]
+    fn report_renders_a_line_per_check() {
+        let mut report = report();
+        report.push(CheckResult::fail("Keyring", "not available").with_guidance("Unlock the keyring"));
+        assert_eq!(report.render(), "\
+[pass] Hotkey: global hotkeys can be registered
+[warn] Tray: unknown desktop; the tray icon may not appear
+[pass] Clipboard: copy and paste round-trip
+[fail] Keyring: not available — Unlock the keyring
+2 passed, 1 warnings, 1 failed
+");
+    }
+
+    #[test]
+    fn tray_support_depends_on_the_session() {
+        assert_eq!(tray_support("windows", None, false).status, CheckStatus::Pass);
+        assert_eq!(tray_support("linux", Some("KDE"), false).status, CheckStatus::Fail);
+        assert_eq!(tray_support("linux", Some("KDE"), true).status, CheckStatus::Pass);
+        let gnome = tray_support("linux", Some("ubuntu:GNOME"), true);
+        assert_eq!(gnome.status, CheckStatus::Warn);
+        assert!(gnome.guidance.unwrap().contains("AppIndicator"));
+        assert_eq!(tray_support("linux", None, true).status, CheckStatus::Warn);
+    }
+
+    #[test]
+    fn include_path_must_exist_and_be_readable() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(check_include_path(dir.path()).status, CheckStatus::Pass);
+
+        let missing = check_include_path(&dir.path().join("Progam Files"));
+        assert_eq!(missing.status, CheckStatus::Warn);
+        assert_eq!(missing.detail, "does not exist");
+
+        // A file is not a folder to index
+        std::fs::write(dir.path().join("notes.txt"), "").unwrap();
+        assert_eq!(check_include_path(&dir.path().join("notes.txt")).status, CheckStatus::Fail);
+    }
+}
@@ -0,0 +1,305 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::Mutex;
+use log::debug;
+
+use crate::extraction::ExtractionWorker;
+use crate::index::FileInfo;
+
+// Files of the same size are first compared by a hash of this many leading bytes
+pub const PREFIX_HASH_BYTES: u64 = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HashTier {
+    Prefix,
+    Full,
+}
+
+// A hash stays valid while the file keeps its size and modification time
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct HashKey {
+    path: PathBuf,
+    size: u64,
+    modified: SystemTime,
+}
+
+impl HashKey {
+    fn of(file: &FileInfo) -> Self {
+        Self { path: file.path.clone(), size: file.size, modified: file.modified }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct FileHashes {
+    prefix: Option<String>,
+    full: Option<String>,
+}
+
+// Hashes from earlier scans, so a scan cut off by its budget picks up where it stopped
+#[derive(Debug, Default)]
+struct HashCache {
+    entries: HashMap<HashKey, FileHashes>,
+}
+
+impl HashCache {
+    // Dropped wholesale past this; hashes are cheap to redo compared to an unbounded cache
+    const CAPACITY: usize = 50_000;
+
+    fn get(&self, key: &HashKey, tier: HashTier) -> Option<String> {
+        let hashes = self.entries.get(key)?;
+        match tier {
+            HashTier::Prefix => hashes.prefix.clone(),
+            HashTier::Full => hashes.full.clone(),
+        }
+    }
+
+    fn insert(&mut self, key: HashKey, tier: HashTier, hash: String) {
+        if self.entries.len() >= Self::CAPACITY && !self.entries.contains_key(&key) {
+            self.entries.clear();
+        }
+        let hashes = self.entries.entry(key).or_default();
+        match tier {
+            HashTier::Prefix => hashes.prefix = Some(hash),
+            HashTier::Full => hashes.full = Some(hash),
+        }
+    }
+}
+
+// Files with identical contents, newest first
+#[derive(Debug, Clone)]
+pub struct DuplicateGroup {
+    pub size: u64,
+    pub files: Vec<FileInfo>,
+}
+
+impl DuplicateGroup {
+    pub fn newest(&self) -> &FileInfo {
+        &self.files[0]
+    }
+
+    // Space freed by keeping one copy
+    pub fn wasted_bytes(&self) -> u64 {
+        self.size * (self.files.len() as u64).saturating_sub(1)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DuplicateScan {
+    // Most wasted space first
+    pub groups: Vec<DuplicateGroup>,
+    // Files sharing their size with another, the ones that needed hashing
+    pub candidates: usize,
+    // Candidates the budget ran out before; scanning again continues with them
+    pub unhashed: usize,
+}
+
+impl DuplicateScan {
+    pub fn is_complete(&self) -> bool {
+        self.unhashed == 0
+    }
+}
+
+// Finds duplicate files in tiers: by size, then by a hash of the first PREFIX_HASH_BYTES, then by a
+// full hash only where prefixes collide. Hashing runs in the extraction worker, so a file that
+// hangs or crashes the hasher is skipped like any other bad file
+pub struct DuplicateFinder {
+    worker: Arc<ExtractionWorker>,
+    cache: Mutex<HashCache>,
+}
+
+impl DuplicateFinder {
+    pub fn new(worker: Arc<ExtractionWorker>) -> Self {
+        Self { worker, cache: Mutex::new(HashCache::default()) }
+    }
+
+    // Hashes until `budget` is spent and returns the groups confirmed by then, which may lack copies
+    // not hashed yet. Cached hashes cost nothing, so each scan of the same files gets further. A
+    // hash already started finishes
+    pub async fn find(&self, files: Vec<FileInfo>, budget: Duration) -> DuplicateScan {
+        let deadline = Instant::now() + budget;
+
+        // Empty files are all alike and waste nothing
+        let mut by_size: HashMap<u64, Vec<FileInfo>> = HashMap::new();
+        for file in files.into_iter().filter(|file| file.size > 0 && file.archive_member.is_none()) {
+            by_size.entry(file.size).or_default().push(file);
+        }
+        let mut size_groups: Vec<Vec<FileInfo>> = by_size.into_values().filter(|group| group.len() > 1).collect();
+        // Large files first: they waste the most space per copy
+        size_groups.sort_by(|a, b| b[0].size.cmp(&a[0].size));
+
+        let mut scan = DuplicateScan {
+            candidates: size_groups.iter().map(Vec::len).sum(),
+            ..DuplicateScan::default()
+        };
+        for group in size_groups {
+            let size = group[0].size;
+            let (prefix_groups, unhashed) = self.group_by_hash(group, HashTier::Prefix, deadline).await;
+            scan.unhashed += unhashed;
+
+            for prefix_group in prefix_groups {
+                // The prefix already covers all of a small file
+                let (full_groups, unhashed) = if size <= PREFIX_HASH_BYTES {
+                    (vec![prefix_group], 0)
+                } else {
+                    self.group_by_hash(prefix_group, HashTier::Full, deadline).await
+                };
+                scan.unhashed += unhashed;
+                scan.groups.extend(full_groups.into_iter().map(|mut files| {
+                    files.sort_by(|a, b| b.modified.cmp(&a.modified).then_with(|| a.path.cmp(&b.path)));
+                    DuplicateGroup { size, files }
+                }));
+            }
+        }
+
+        scan.groups.sort_by(|a, b| b.wasted_bytes().cmp(&a.wasted_bytes()).then_with(|| a.newest().path.cmp(&b.newest().path)));
+        scan
+    }
+
+    // Groups of two or more files with equal hashes, and how many files went unhashed because the
+    // deadline passed. Files that can't be read are left out
+    async fn group_by_hash(&self, files: Vec<FileInfo>, tier: HashTier, deadline: Instant) -> (Vec<Vec<FileInfo>>, usize) {
+        let mut by_hash: HashMap<String, Vec<FileInfo>> = HashMap::new();
+        let mut unhashed = 0;
+
+        for file in files {
+            let key = HashKey::of(&file);
+            let cached = self.cache.lock().await.get(&key, tier);
+            let hash = match cached {
+                Some(hash) => hash,
+                None if Instant::now() >= deadline => {
+                    unhashed += 1;
+                    continue;
+                }
+                None => {
+                    let max_bytes = (tier == HashTier::Prefix).then_some(PREFIX_HASH_BYTES);
+                    match self.worker.hash_file(&file.path, max_bytes).await {
+                        Ok(hash) => {
+                            self.cache.lock().await.insert(key, tier, hash.clone());
+                            hash
+                        }
+                        Err(e) => {
+                            debug!("Not comparing {}: {}", file.path.display(), e);
+                            continue;
+                        }
+                    }
+                }
+            };
+            by_hash.entry(hash).or_default().push(file);
+        }
+
+        // Groups found before the deadline are real duplicates even if unhashed files would join them
+        (by_hash.into_values().filter(|group| group.len() > 1).collect(), unhashed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn finder() -> DuplicateFinder {
+        DuplicateFinder::new(Arc::new(ExtractionWorker::with_program(None)))
+    }
+
+    // Written with a modification time `age` seconds in the past, so "newest" is predictable
+    fn file(dir: &Path, name: &str, content: &[u8], age: u64) -> FileInfo {
+        let path = dir.join(name);
+        std::fs::write(&path, content).unwrap();
+        let modified = SystemTime::now() - Duration::from_secs(age);
+        std::fs::File::options().write(true).open(&path).unwrap().set_modified(modified).unwrap();
+        FileInfo::new(path).unwrap()
+    }
+
+    fn names(group: &DuplicateGroup) -> Vec<&str> {
+        group.files.iter().map(|file| file.name.as_str()).collect()
+    }
+
+    // Larger than the prefix and identical in it, so only the full hash tells them apart
+    fn large(tail: u8) -> Vec<u8> {
+        let mut data = vec![7u8; PREFIX_HASH_BYTES as usize + 100];
+        *data.last_mut().unwrap() = tail;
+        data
+    }
+
+    #[tokio::test]
+    async fn files_are_compared_by_size_prefix_and_full_hash() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = vec![
+            file(dir.path(), "a.txt", b"same small text", 30),
+            file(dir.path(), "b.txt", b"same small text", 10),
+            file(dir.path(), "c.txt", b"other small txt", 20),
+            file(dir.path(), "x.bin", &large(1), 10),
+            file(dir.path(), "y.bin", &large(1), 20),
+            file(dir.path(), "z.bin", &large(2), 30),
+            file(dir.path(), "unique.txt", b"no other file is this long", 0),
+            file(dir.path(), "empty1", b"", 0),
+            file(dir.path(), "empty2", b"", 0),
+        ];
+        let finder = finder();
+        let scan = finder.find(files, Duration::from_secs(60)).await;
+
+        assert!(scan.is_complete());
+        assert_eq!(scan.candidates, 6);
+        // Most wasted space first, newest copy first
+        assert_eq!(scan.groups.iter().map(names).collect::<Vec<_>>(), [vec!["x.bin", "y.bin"], vec!["b.txt", "a.txt"]]);
+        assert_eq!(scan.groups[0].wasted_bytes(), PREFIX_HASH_BYTES + 100);
+
+        // Small files never need the full hash; a large one whose prefix was unique never gets it
+        let cache = finder.cache.lock().await;
+        let hashes = |name: &str| cache.entries.iter()
+            .find(|(key, _)| key.path.ends_with(name))
+            .map(|(_, hashes)| (hashes.prefix.is_some(), hashes.full.is_some()))
+            .unwrap();
+        assert_eq!(hashes("a.txt"), (true, false));
+        assert_eq!(hashes("z.bin"), (true, true));
+        assert!(!cache.entries.keys().any(|key| key.path.ends_with("unique.txt") || key.path.ends_with("empty1")));
+    }
+
+    // Out of time: groups confirmed from cached hashes come back, the rest is counted as left
+    #[tokio::test]
+    async fn the_budget_cuts_a_scan_short() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = vec![
+            file(dir.path(), "a.txt", b"same", 20),
+            file(dir.path(), "b.txt", b"same", 10),
+            file(dir.path(), "c.txt", b"same", 0),
+        ];
+        let finder = finder();
+
+        let scan = finder.find(files.clone(), Duration::ZERO).await;
+        assert!(scan.groups.is_empty());
+        assert_eq!((scan.candidates, scan.unhashed), (3, 3));
+
+        finder.find(files[..2].to_vec(), Duration::from_secs(60)).await;
+        let scan = finder.find(files.clone(), Duration::ZERO).await;
+        assert_eq!(scan.groups.iter().map(names).collect::<Vec<_>>(), [vec!["b.txt", "a.txt"]]);
+        assert_eq!(scan.unhashed, 1);
+        assert!(!scan.is_complete());
+
+        let scan = finder.find(files, Duration::from_secs(60)).await;
+        assert!(scan.is_complete());
+        assert_eq!(names(&scan.groups[0]), ["c.txt", "b.txt", "a.txt"]);
+    }
+
+    // A hash is reused while size and modification time hold, and redone once either changes
+    #[tokio::test]
+    async fn cached_hashes_are_reused_across_scans() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = file(dir.path(), "a.txt", b"same", 20);
+        let b = file(dir.path(), "b.txt", b"same", 10);
+        let finder = finder();
+        assert_eq!(finder.find(vec![a.clone(), b.clone()], Duration::from_secs(60)).await.groups.len(), 1);
+
+        // Rewritten in place with the old time: the cached hash still stands in for the contents
+        std::fs::write(&b.path, "diff").unwrap();
+        std::fs::File::options().write(true).open(&b.path).unwrap().set_modified(b.modified).unwrap();
+        let reused = FileInfo::new(b.path.clone()).unwrap();
+        assert_eq!(finder.find(vec![a.clone(), reused], Duration::ZERO).await.groups.len(), 1);
+
+        let touched = file(dir.path(), "b.txt", b"diff", 5);
+        let scan = finder.find(vec![a, touched], Duration::from_secs(60)).await;
+        assert!(scan.groups.is_empty() && scan.is_complete());
+    }
+}
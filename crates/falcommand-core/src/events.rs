@@ -0,0 +1,322 @@
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+use crate::crash::TaskSupervisor;
+use crate::index::{IndexManager, IndexProgress};
+use crate::sync::{SyncCategory, SyncErrorKind, SyncEvent, SyncManager};
+
+// First line a client sends on the instance channel to turn its connection into an event stream:
+// "subscribe sync,index", or just "subscribe" for every kind
+pub const SUBSCRIBE_VERB: &str = "subscribe";
+
+#[derive(Debug, thiserror::Error)]
+pub enum EventError {
+    #[error("Unknown event kind '{0}' (expected one of index, sync, search, window, execution)")]
+    UnknownKind(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    Index,
+    Sync,
+    Search,
+    Window,
+    Execution,
+}
+
+impl EventKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            EventKind::Index => "index",
+            EventKind::Sync => "sync",
+            EventKind::Search => "search",
+            EventKind::Window => "window",
+            EventKind::Execution => "execution",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "index" => Some(EventKind::Index),
+            "sync" => Some(EventKind::Sync),
+            "search" => Some(EventKind::Search),
+            "window" => Some(EventKind::Window),
+            "execution" => Some(EventKind::Execution),
+            _ => None,
+        }
+    }
+}
+
+// The kinds a subscriber asked for; empty means every kind
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EventFilter {
+    kinds: BTreeSet<EventKind>,
+}
+
+impl EventFilter {
+    pub fn only(kinds: impl IntoIterator<Item = EventKind>) -> Self {
+        Self { kinds: kinds.into_iter().collect() }
+    }
+
+    // "sync,index". An unknown name is an error rather than skipped, so a typo doesn't quietly
+    // subscribe to nothing or to everything
+    pub fn parse(list: &str) -> Result<Self, EventError> {
+        list.split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(|name| EventKind::parse(name).ok_or_else(|| EventError::UnknownKind(name.to_string())))
+            .collect::<Result<BTreeSet<_>, _>>()
+            .map(|kinds| Self { kinds })
+    }
+
+    // Dropped markers reach every subscriber whatever it filters on
+    pub fn matches(&self, event: &LauncherEvent) -> bool {
+        match event.kind() {
+            Some(kind) => self.kinds.is_empty() || self.kinds.contains(&kind),
+            None => true,
+        }
+    }
+
+    // The subscribe line, newline included
+    pub fn encode_request(&self) -> String {
+        if self.kinds.is_empty() {
+            return format!("{}\n", SUBSCRIBE_VERB);
+        }
+        let kinds: Vec<&str> = self.kinds.iter().map(EventKind::name).collect();
+        format!("{} {}\n", SUBSCRIBE_VERB, kinds.join(","))
+    }
+
+    // None when `line` isn't a subscribe request at all
+    pub fn decode_request(line: &str) -> Option<Result<Self, EventError>> {
+        let line = line.trim();
+        let (verb, kinds) = line.split_once(' ').unwrap_or((line, ""));
+        (verb == SUBSCRIBE_VERB).then(|| Self::parse(kinds))
+    }
+}
+
+// What external tools are told about, one JSON line each. Queries, paths and action arguments are
+// never included: searches report their length, executions the kind of action
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum LauncherEvent {
+    IndexProgress { in_progress: bool, items_indexed: usize },
+    SyncCompleted,
+    SyncFailed { kind: SyncErrorKind },
+    SyncConflicts { count: usize },
+    SyncConflictResolved { category: SyncCategory },
+    SearchExecuted { query_chars: usize, results: usize },
+    WindowShown,
+    WindowHidden,
+    Executed { action: String, succeeded: bool },
+    // This subscriber fell behind and lost `count` events, of any kind, oldest first
+    Dropped { count: u64 },
+}
+
+impl LauncherEvent {
+    // None for Dropped, which belongs to no kind
+    pub fn kind(&self) -> Option<EventKind> {
+        match self {
+            LauncherEvent::IndexProgress { .. } => Some(EventKind::Index),
+            LauncherEvent::SyncCompleted
+            | LauncherEvent::SyncFailed { .. }
+            | LauncherEvent::SyncConflicts { .. }
+            | LauncherEvent::SyncConflictResolved { .. } => Some(EventKind::Sync),
+            LauncherEvent::SearchExecuted { .. } => Some(EventKind::Search),
+            LauncherEvent::WindowShown | LauncherEvent::WindowHidden => Some(EventKind::Window),
+            LauncherEvent::Executed { .. } => Some(EventKind::Execution),
+            LauncherEvent::Dropped { .. } => None,
+        }
+    }
+
+    pub fn from_index_progress(progress: &IndexProgress) -> Self {
+        LauncherEvent::IndexProgress { in_progress: progress.in_progress, items_indexed: progress.items_indexed }
+    }
+
+    pub fn from_sync(event: &SyncEvent) -> Self {
+        match event {
+            SyncEvent::Completed => LauncherEvent::SyncCompleted,
+            SyncEvent::Failed(kind) => LauncherEvent::SyncFailed { kind: *kind },
+            SyncEvent::ConflictsDetected(count) => LauncherEvent::SyncConflicts { count: *count },
+            SyncEvent::ConflictResolved(category) => LauncherEvent::SyncConflictResolved { category: *category },
+        }
+    }
+}
+
+// Fan-out of launcher events to subscribers. Publishing never waits: each subscriber has
+// CAPACITY events of room, and one that falls further behind loses the oldest and is sent a
+// Dropped marker in their place
+#[derive(Debug)]
+pub struct EventBus {
+    sender: broadcast::Sender<LauncherEvent>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self { sender: broadcast::channel(Self::CAPACITY).0 }
+    }
+}
+
+impl EventBus {
+    const CAPACITY: usize = 256;
+
+    // Without subscribers the event is simply discarded
+    pub fn publish(&self, event: LauncherEvent) {
+        let _ = self.sender.send(event);
+    }
+
+    pub fn subscribe(&self, filter: EventFilter) -> EventSubscription {
+        EventSubscription { receiver: self.sender.subscribe(), filter }
+    }
+
+    pub fn subscriber_count(&self) -> usize {
+        self.sender.receiver_count()
+    }
+
+    // Publishes index progress and sync events for as long as the launcher runs
+    pub fn forward_from(self: &Arc<Self>, index_manager: Arc<IndexManager>, sync_manager: Arc<SyncManager>) {
+        let events = self.clone();
+        TaskSupervisor::default().spawn("event-index-progress", move || {
+            let events = events.clone();
+            let mut progress = index_manager.subscribe_progress();
+            async move {
+                while progress.changed().await.is_ok() {
+                    let event = LauncherEvent::from_index_progress(&progress.borrow_and_update());
+                    events.publish(event);
+                }
+            }
+        });
+
+        let events = self.clone();
+        TaskSupervisor::default().spawn("event-sync", move || {
+            let events = events.clone();
+            let mut sync_events = sync_manager.subscribe();
+            async move {
+                loop {
+                    match sync_events.recv().await {
+                        Ok(event) => events.publish(LauncherEvent::from_sync(&event)),
+                        Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(broadcast::error::RecvError::Closed) => return,
+                    }
+                }
+            }
+        });
+    }
+}
+
+// One subscriber's view of the bus; dropping it unsubscribes
+pub struct EventSubscription {
+    receiver: broadcast::Receiver<LauncherEvent>,
+    filter: EventFilter,
+}
+
+impl EventSubscription {
+    // The next event the filter lets through, or a Dropped marker after falling behind. None once
+    // the bus is gone
+    pub async fn next(&mut self) -> Option<LauncherEvent> {
+        loop {
+            match self.receiver.recv().await {
+                Ok(event) if self.filter.matches(&event) => return Some(event),
+                Ok(_) => continue,
+                Err(broadcast::error::RecvError::Lagged(count)) => return Some(LauncherEvent::Dropped { count }),
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn progress(items_indexed: usize) -> LauncherEvent {
+        LauncherEvent::IndexProgress { in_progress: true, items_indexed }
+    }
+
+    #[test]
+    fn filters_parse_from_the_subscribe_line() {
+        let filter = EventFilter::parse(" Sync, index ,").unwrap();
+        assert_eq!(filter, EventFilter::only([EventKind::Index, EventKind::Sync]));
+        assert_eq!(filter.encode_request(), "subscribe index,sync\n");
+        assert_eq!(EventFilter::decode_request(&filter.encode_request()).unwrap().unwrap(), filter);
+        assert_eq!(EventFilter::default().encode_request(), "subscribe\n");
+        assert_eq!(EventFilter::decode_request("subscribe").unwrap().unwrap(), EventFilter::default());
+
+        let error = EventFilter::parse("sync,indx").unwrap_err();
+        assert!(error.to_string().contains("Unknown event kind 'indx'"), "{}", error);
+        assert!(EventFilter::decode_request("subscribe sync,indx").unwrap().is_err());
+        assert!(EventFilter::decode_request("execute 42").is_none());
+    }
+
+    // An empty filter takes everything; Dropped markers get past any filter
+    #[test]
+    fn filters_match_by_kind() {
+        let sync = EventFilter::only([EventKind::Sync]);
+        assert!(sync.matches(&LauncherEvent::SyncCompleted));
+        assert!(sync.matches(&LauncherEvent::SyncConflicts { count: 2 }));
+        assert!(!sync.matches(&progress(10)));
+        assert!(!sync.matches(&LauncherEvent::WindowShown));
+        assert!(sync.matches(&LauncherEvent::Dropped { count: 3 }));
+        assert!(EventFilter::default().matches(&LauncherEvent::Executed { action: "OpenUrl".to_string(), succeeded: true }));
+    }
+
+    // Searches and executions go out as counts and action kinds, one flat JSON object per event
+    #[test]
+    fn events_serialize_without_payloads() {
+        let search = serde_json::to_value(LauncherEvent::SearchExecuted { query_chars: 7, results: 3 }).unwrap();
+        assert_eq!(search, serde_json::json!({ "event": "search_executed", "query_chars": 7, "results": 3 }));
+        let executed = serde_json::to_value(LauncherEvent::Executed { action: "OpenFile".to_string(), succeeded: false }).unwrap();
+        assert_eq!(executed, serde_json::json!({ "event": "executed", "action": "OpenFile", "succeeded": false }));
+        assert_eq!(serde_json::to_value(LauncherEvent::WindowHidden).unwrap(), serde_json::json!({ "event": "window_hidden" }));
+        assert_eq!(LauncherEvent::from_sync(&SyncEvent::ConflictsDetected(4)), LauncherEvent::SyncConflicts { count: 4 });
+    }
+
+    // Filtered-out events are skipped without being counted as lost
+    #[tokio::test]
+    async fn subscribers_only_see_their_kinds() {
+        let bus = EventBus::default();
+        let mut subscription = bus.subscribe(EventFilter::only([EventKind::Sync]));
+        bus.publish(progress(1));
+        bus.publish(LauncherEvent::WindowShown);
+        bus.publish(LauncherEvent::SyncCompleted);
+        assert_eq!(subscription.next().await, Some(LauncherEvent::SyncCompleted));
+    }
+
+    // A subscriber that falls behind loses the oldest events, is told how many, and carries on
+    // with the oldest one still buffered
+    #[tokio::test]
+    async fn a_slow_subscriber_loses_the_oldest_events() {
+        let bus = EventBus::default();
+        let mut everything = bus.subscribe(EventFilter::default());
+        let mut windows = bus.subscribe(EventFilter::only([EventKind::Window]));
+        for items_indexed in 0..EventBus::CAPACITY + 5 {
+            bus.publish(progress(items_indexed));
+        }
+        bus.publish(LauncherEvent::WindowShown);
+
+        assert_eq!(everything.next().await, Some(LauncherEvent::Dropped { count: 6 }));
+        assert_eq!(everything.next().await, Some(progress(6)));
+        assert_eq!(windows.next().await, Some(LauncherEvent::Dropped { count: 6 }));
+        assert_eq!(windows.next().await, Some(LauncherEvent::WindowShown));
+
+        // Publishing never waited on either of them
+        bus.publish(LauncherEvent::WindowHidden);
+        assert_eq!(windows.next().await, Some(LauncherEvent::WindowHidden));
+    }
+
+    #[tokio::test]
+    async fn dropping_a_subscription_unsubscribes() {
+        let bus = EventBus::default();
+        let subscription = bus.subscribe(EventFilter::default());
+        let mut other = bus.subscribe(EventFilter::default());
+        assert_eq!(bus.subscriber_count(), 2);
+        drop(subscription);
+        assert_eq!(bus.subscriber_count(), 1);
+
+        // The stream ends once the bus is gone
+        drop(bus);
+        assert_eq!(other.next().await, None);
+    }
+}
@@ -0,0 +1,156 @@
+use falcommand_platform::AppInfo;
+
+// `*` matches any run of characters and `?` a single one; the whole text must match
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    // Greedy match with backtracking to the most recent `*`
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((star_p, star_t)) => {
+                    p = star_p + 1;
+                    t = star_t + 1;
+                    star = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+pub fn is_glob(pattern: &str) -> bool {
+    pattern.contains(['*', '?'])
+}
+
+// File exclusion: globs match the whole path, plain patterns any part of it
+pub fn path_excluded(path: &str, patterns: &[String]) -> bool {
+    patterns.iter().any(|pattern| {
+        if is_glob(pattern) {
+            glob_match(pattern, path)
+        } else {
+            path.contains(pattern.as_str())
+        }
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AppExclusion {
+    Name,
+    Path,
+}
+
+fn is_path_pattern(pattern: &str) -> bool {
+    pattern.contains(['/', '\\'])
+}
+
+// App exclusion, case-insensitive. Patterns containing a path separator match the executable
+// path, all others the app name, so "*setup*" never hides an app just for living under "Setup Tools"
+pub fn app_excluded(app: &AppInfo, patterns: &[String]) -> Option<AppExclusion> {
+    let name = app.name.to_lowercase();
+    let path = app.executable_path.to_string_lossy().to_lowercase();
+
+    let matches = |pattern: &str, text: &str| {
+        if is_glob(pattern) {
+            glob_match(pattern, text)
+        } else {
+            text == pattern
+        }
+    };
+
+    let (path_patterns, name_patterns): (Vec<String>, Vec<String>) = patterns.iter()
+        .map(|pattern| pattern.to_lowercase())
+        .partition(|pattern| is_path_pattern(pattern));
+
+    if name_patterns.iter().any(|pattern| matches(pattern, &name)) {
+        Some(AppExclusion::Name)
+    } else if path_patterns.iter().any(|pattern| matches(pattern, &path) || path.starts_with(pattern.as_str())) {
+        Some(AppExclusion::Path)
+    } else {
+        None
+    }
+}
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use falcommand_config::Config;
+    use super::*;
+
+    fn app(name: &str, path: &str) -> AppInfo {
+        AppInfo::new(name, PathBuf::from(path))
+    }
+
+    fn patterns(patterns: &[&str]) -> Vec<String> {
+        patterns.iter().map(|pattern| pattern.to_string()).collect()
+    }
+
+    #[test]
+    fn globs_match_the_whole_text() {
+        assert!(glob_match("uninstall*", "uninstall firefox"));
+        assert!(!glob_match("uninstall*", "firefox uninstaller"));
+        assert!(glob_match("*setup*", "setup"));
+        assert!(glob_match("a*b*c", "aXbYbZc"));
+        assert!(glob_match("v?.txt", "v1.txt"));
+        assert!(!glob_match("v?.txt", "v10.txt"));
+        assert!(glob_match("**", ""));
+        assert!(!glob_match("", "a"));
+    }
+
+    #[test]
+    fn name_patterns_win_over_path_patterns() {
+        let setup = app("Setup Wizard", "/opt/tools/wizard");
+        let rules = patterns(&["*setup*", "/opt/tools/"]);
+        assert_eq!(app_excluded(&setup, &rules), Some(AppExclusion::Name));
+
+        // Living under a matching folder only counts for patterns with a separator
+        let tool = app("Wizard", "/opt/Setup Tools/wizard");
+        assert_eq!(app_excluded(&tool, &patterns(&["*setup*"])), None);
+        assert_eq!(app_excluded(&tool, &patterns(&["/opt/setup tools/"])), Some(AppExclusion::Path));
+        assert_eq!(app_excluded(&tool, &patterns(&["*\\setup tools\\*", "/opt/*/wizard"])), Some(AppExclusion::Path));
+
+        // Plain name patterns must match the whole name, in any case
+        assert_eq!(app_excluded(&tool, &patterns(&["WIZARD"])), Some(AppExclusion::Name));
+        assert_eq!(app_excluded(&tool, &patterns(&["wiz"])), None);
+    }
+
+    #[test]
+    fn default_patterns_hide_installers_only() {
+        let defaults = Config::default().search.app_exclude_patterns;
+        let apps = [
+            app("Uninstall Firefox", "C:\\Program Files\\Mozilla Firefox\\uninstall\\helper.exe"),
+            app("Microsoft Visual C++ 2015-2022 Redistributable (x64)", "C:\\ProgramData\\Package Cache\\vc_redist.x64.exe"),
+            app("Python 3.12 Setup", "C:\\Users\\me\\Downloads\\python-3.12.exe"),
+            app("Firefox", "C:\\Program Files\\Mozilla Firefox\\firefox.exe"),
+            app("Setapp", "/Applications/Setapp.app"),
+            app("Inkscape", "/opt/Setup/inkscape"),
+            app("Uninstaller Pro", "/usr/bin/uninstaller-pro"),
+        ];
+
+        let kept: Vec<&str> = apps.iter()
+            .filter(|app| app_excluded(app, &defaults).is_none())
+            .map(|app| app.name.as_str())
+            .collect();
+        assert_eq!(kept, ["Firefox", "Setapp", "Inkscape"]);
+        assert!(apps.iter().all(|app| app_excluded(app, &[]).is_none()));
+    }
+
+    #[test]
+    fn file_patterns_match_globs_or_substrings() {
+        let rules = patterns(&["*.tmp", "node_modules"]);
+        assert!(path_excluded("/home/me/a.tmp", &rules));
+        assert!(path_excluded("/home/me/app/node_modules/x.js", &rules));
+        assert!(!path_excluded("/home/me/a.tmp.txt", &rules));
+    }
+}
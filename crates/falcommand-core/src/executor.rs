@@ -0,0 +1,471 @@
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use async_trait::async_trait;
+use log::{info, warn, error};
+
+use falcommand_config::{Action, ActionError};
+use falcommand_config::schema::set_config_value;
+use falcommand_platform::{ForegroundApp, MenuAccess, PlatformProvider};
+use crate::scheduler::ActionScheduler;
+use crate::crash::TaskSupervisor;
+use crate::profiles::ProfileManager;
+use crate::config_store::ConfigStore;
+use crate::index::IndexManager;
+use crate::search::SearchEngine;
+use crate::sync::SyncManager;
+use crate::archives::{extract_member, extraction_dir};
+use crate::safe_mode::restart_normally;
+use crate::app_watch::{launch_target_missing, AppIndexRefresher};
+use crate::macros::{resolve_macro, MacroReport, StepOutcome};
+use crate::query_macros::validate_query_macro;
+use crate::clipboard_undo::{ClipboardRestore, ClipboardUndo};
+use crate::events::{EventBus, LauncherEvent};
+
+type ActionFuture<'a> = Pin<Box<dyn Future<Output = std::result::Result<(), ActionError>> + Send + 'a>>;
+
+// Runs Action::PluginAction through the plugin that produced it
+#[async_trait]
+pub trait PluginActionHandler: Send + Sync {
+    async fn execute_plugin_action(
+        &self,
+        plugin_id: &str,
+        action: &Action,
+    ) -> std::result::Result<(), ActionError>;
+}
+
+// Single entry point for running result actions, including the ones that need app state
+pub struct ActionExecutor {
+    platform_provider: Arc<dyn PlatformProvider>,
+    scheduler: Arc<ActionScheduler>,
+    profile_manager: Option<Arc<ProfileManager>>,
+    plugin_handler: Option<Arc<dyn PluginActionHandler>>,
+    index_manager: Option<Arc<IndexManager>>,
+    search_engine: Option<Arc<SearchEngine>>,
+    config_store: Option<Arc<ConfigStore>>,
+    sync_manager: Option<Arc<SyncManager>>,
+    menu_access: Option<Arc<dyn MenuAccess>>,
+    app_refresher: Option<Arc<AppIndexRefresher>>,
+    clipboard_undo: Option<Arc<ClipboardUndo>>,
+    events: Option<Arc<EventBus>>,
+    cache_dir: Option<PathBuf>,
+    // Asks the app loop to end; without it there is nothing to quit
+    quit: Option<Arc<dyn Fn() + Send + Sync>>,
+}
+
+impl ActionExecutor {
+    const SCHEDULER_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+    pub fn new(platform_provider: Arc<dyn PlatformProvider>, scheduler: Arc<ActionScheduler>) -> Self {
+        Self {
+            platform_provider,
+            scheduler,
+            profile_manager: None,
+            plugin_handler: None,
+            index_manager: None,
+            search_engine: None,
+            config_store: None,
+            sync_manager: None,
+            menu_access: None,
+            app_refresher: None,
+            clipboard_undo: None,
+            events: None,
+            cache_dir: None,
+            quit: None,
+        }
+    }
+
+    pub fn with_plugin_handler(mut self, plugin_handler: Arc<dyn PluginActionHandler>) -> Self {
+        self.plugin_handler = Some(plugin_handler);
+        self
+    }
+
+    pub fn with_index_manager(mut self, index_manager: Arc<IndexManager>) -> Self {
+        self.index_manager = Some(index_manager);
+        self
+    }
+
+    // Needed for actions on the per-profile stores the engine owns, such as clearing usage data
+    pub fn with_search_engine(mut self, search_engine: Arc<SearchEngine>) -> Self {
+        self.search_engine = Some(search_engine);
+        self
+    }
+
+    // Needed for actions that change the config, such as excluding a folder from the index
+    pub fn with_config_store(mut self, config_store: Arc<ConfigStore>) -> Self {
+        self.config_store = Some(config_store);
+        self
+    }
+
+    // Needed to resolve sync conflicts
+    pub fn with_sync_manager(mut self, sync_manager: Arc<SyncManager>) -> Self {
+        self.sync_manager = Some(sync_manager);
+        self
+    }
+
+    pub fn with_menu_access(mut self, menu_access: Arc<dyn MenuAccess>) -> Self {
+        self.menu_access = Some(menu_access);
+        self
+    }
+
+    // Launches of uninstalled apps prune them and refresh the app index
+    pub fn with_app_refresher(mut self, app_refresher: Arc<AppIndexRefresher>) -> Self {
+        self.app_refresher = Some(app_refresher);
+        self
+    }
+
+    // Copies hold what they replaced for behavior.clipboard_undo_secs; needs the config store too
+    pub fn with_clipboard_undo(mut self, clipboard_undo: Arc<ClipboardUndo>) -> Self {
+        self.clipboard_undo = Some(clipboard_undo);
+        self
+    }
+
+    pub fn with_profile_manager(mut self, profile_manager: Arc<ProfileManager>) -> Self {
+        self.profile_manager = Some(profile_manager);
+        self
+    }
+
+    // QuitApp ends the app loop through this instead of exiting the process
+    pub fn with_quit(mut self, quit: Arc<dyn Fn() + Send + Sync>) -> Self {
+        self.quit = Some(quit);
+        self
+    }
+
+    // Each execution is published with the kind of action and whether it succeeded
+    pub fn with_events(mut self, events: Arc<EventBus>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    // Archive members are extracted below it before they are opened
+    pub fn with_cache_dir(mut self, cache_dir: PathBuf) -> Self {
+        self.cache_dir = Some(cache_dir);
+        self
+    }
+
+    pub fn scheduler(&self) -> Arc<ActionScheduler> {
+        self.scheduler.clone()
+    }
+
+    pub async fn execute(&self, action: &Action) -> std::result::Result<(), ActionError> {
+        let outcome = self.run_action(action).await;
+        if let Some(ref events) = self.events {
+            events.publish(LauncherEvent::Executed { action: action.kind().to_string(), succeeded: outcome.is_ok() });
+        }
+        outcome
+    }
+
+    async fn run_action(&self, action: &Action) -> std::result::Result<(), ActionError> {
+        match action {
+            Action::ScheduleAction { action, delay_secs, label } => {
+                let delay = chrono::Duration::seconds(*delay_secs as i64);
+                self.scheduler.schedule((**action).clone(), delay, label.clone()).await
+                    .map_err(|e| ActionError::Other(e.to_string()))?;
+                Ok(())
+            }
+            Action::CancelScheduledAction(id) => {
+                self.scheduler.cancel(id).await
+                    .map_err(|e| ActionError::Other(e.to_string()))?;
+                Ok(())
+            }
+            Action::SwitchProfile(name) => {
+                let manager = self.profile_manager.as_ref()
+                    .ok_or_else(|| ActionError::Other("Profiles are not available".to_string()))?;
+                manager.switch_profile(name).await
+                    .map_err(|e| ActionError::Other(e.to_string()))?;
+                Ok(())
+            }
+            Action::ExcludeFromIndex(dir) => {
+                let index_manager = self.index_manager.as_ref()
+                    .ok_or_else(|| ActionError::Other("The index is not available".to_string()))?;
+                let config_store = self.config_store.as_ref()
+                    .ok_or_else(|| ActionError::Other("The configuration is not available".to_string()))?;
+
+                let pattern = dir.to_string_lossy().to_string();
+                config_store.update(move |config| {
+                    if !config.search.exclude_patterns.contains(&pattern) {
+                        config.search.exclude_patterns.push(pattern);
+                    }
+                }).await.map_err(|e| ActionError::Other(e.to_string()))?;
+
+                index_manager.exclude_directory(dir).await;
+                Ok(())
+            }
+            Action::TrashFile(path) => self.trash_file(path).await,
+            // There is no settings window yet, so the settings file is opened instead
+            Action::OpenSettings(field) => {
+                let config_store = self.config_store.as_ref()
+                    .ok_or_else(|| ActionError::Other("The configuration is not available".to_string()))?;
+                info!("Opening settings at {}", field);
+                Action::OpenFile(config_store.save_path().await).execute().await
+            }
+            Action::SetSetting { path, value } => {
+                let config_store = self.config_store.as_ref()
+                    .ok_or_else(|| ActionError::Other("The configuration is not available".to_string()))?;
+
+                // Checked first so an invalid value is reported; applied to whatever is live when it runs
+                set_config_value(&config_store.snapshot().await, path, value.clone())
+                    .map_err(ActionError::Other)?;
+                let (path, value) = (path.clone(), value.clone());
+                config_store.update(move |config| {
+                    if let Ok(updated) = set_config_value(config, &path, value) {
+                        *config = updated;
+                    }
+                }).await.map_err(|e| ActionError::Other(e.to_string()))
+            }
+            Action::SaveQueryMacro { name, template } => {
+                let config_store = self.config_store.as_ref()
+                    .ok_or_else(|| ActionError::Other("The configuration is not available".to_string()))?;
+
+                validate_query_macro(&config_store.snapshot().await.search.query_macros, name, template)
+                    .map_err(|e| ActionError::Other(e.to_string()))?;
+                info!("Saving query macro @{}", name);
+                let (name, template) = (name.clone(), template.clone());
+                config_store.update(move |config| {
+                    // Saving under a name that differs only in case replaces that macro
+                    config.search.query_macros.retain(|existing, _| !existing.eq_ignore_ascii_case(&name));
+                    config.search.query_macros.insert(name, template);
+                }).await.map_err(|e| ActionError::Other(e.to_string()))
+            }
+            Action::ExecuteApplication { path, args } => match action.execute().await {
+                Err(e) if launch_target_missing(path) => self.launch_moved_app(path, args, e).await,
+                result => result,
+            },
+            Action::ResolveSyncConflict { category, resolution } => {
+                let sync_manager = self.sync_manager.as_ref()
+                    .ok_or_else(|| ActionError::Other("Sync is not available".to_string()))?;
+                sync_manager.resolve_conflict(*category, *resolution).await
+                    .map_err(|e| ActionError::Other(e.to_string()))
+            }
+            Action::ClearUsageData => {
+                let search_engine = self.search_engine.as_ref()
+                    .ok_or_else(|| ActionError::Other("Usage data is not available".to_string()))?;
+                search_engine.clear_usage_data().await
+                    .map_err(|e| ActionError::Other(e.to_string()))
+            }
+            Action::DeleteHistoryEntry(query) => {
+                let search_engine = self.search_engine.as_ref()
+                    .ok_or_else(|| ActionError::Other("Search history is not available".to_string()))?;
+                search_engine.delete_history_entry(query).await
+                    .map_err(|e| ActionError::Other(e.to_string()))
+            }
+            Action::PressMenuItem { pid, app, path } => {
+                let menu_access = self.menu_access.clone()
+                    .ok_or_else(|| ActionError::Other("Menu access is not available".to_string()))?;
+                let app = ForegroundApp { pid: *pid, name: app.clone() };
+                let path = path.clone();
+
+                // Pressed first, then the app is brought back to the front
+                tokio::task::spawn_blocking(move || {
+                    menu_access.press(&app, &path)?;
+                    menu_access.activate(&app)
+                })
+                .await
+                .map_err(|e| ActionError::Other(e.to_string()))?
+                .map_err(|e| ActionError::PlatformError(e.to_string()))
+            }
+            Action::OpenArchiveMember { archive, member } => {
+                let cache_dir = self.cache_dir.as_ref()
+                    .ok_or_else(|| ActionError::Other("The cache directory is not available".to_string()))?;
+                let destination = extraction_dir(cache_dir, archive);
+                let (archive, member) = (archive.clone(), member.clone());
+                let extracted = tokio::task::spawn_blocking(move || extract_member(&archive, &member, &destination))
+                    .await
+                    .map_err(|e| ActionError::Other(e.to_string()))?
+                    .map_err(|e| ActionError::Other(e.to_string()))?;
+                Action::OpenFile(extracted).execute().await
+            }
+            Action::RestartNormally => restart_normally(),
+            // The app loop then shuts plugins down and returns through the normal exit path
+            Action::QuitApp => match self.quit {
+                Some(ref quit) => {
+                    info!("Quit requested from the launcher");
+                    quit();
+                    Ok(())
+                }
+                None => Err(ActionError::Other("There is no running launcher to quit".to_string())),
+            },
+            Action::RunCustomCommand(name) => self.run_custom_command(name).await,
+            Action::CopyToClipboard(text) => self.copy_to_clipboard(text).await,
+            Action::UndoClipboardChange => self.undo_clipboard_change().await,
+            Action::PluginAction { plugin_id, .. } => match self.plugin_handler {
+                Some(ref handler) => handler.execute_plugin_action(plugin_id, action).await,
+                None => action.execute().await,
+            },
+            // The launcher window types pivots in itself; one that gets here was routed wrong
+            Action::SetQuery(_) => Err(ActionError::Other("Query changes must be handled by the launcher window".to_string())),
+            other => other.execute().await,
+        }
+    }
+
+    // Steps run one after another. A macro's outcome is announced as "3/3 steps succeeded"; a failure
+    // is returned naming the step, after the remaining steps ran or were skipped as the command says
+    // Boxed by hand: steps go back through execute, and an async fn can't await itself
+    fn run_custom_command<'a>(&'a self, name: &'a str) -> ActionFuture<'a> {
+        Box::pin(async move {
+            let config_store = self.config_store.as_ref()
+                .ok_or_else(|| ActionError::Other("The configuration is not available".to_string()))?;
+            let commands = config_store.snapshot().await.custom_commands;
+            let resolved = resolve_macro(&commands, name).map_err(|e| ActionError::Other(e.to_string()))?;
+
+            let mut report = MacroReport::new(resolved.steps.len());
+            for (index, step) in resolved.steps.iter().enumerate() {
+                if index > 0 && !resolved.step_delay.is_zero() {
+                    tokio::time::sleep(resolved.step_delay).await;
+                }
+                let result = self.execute(&step.action).await.map_err(|e| e.to_string());
+                let failed = result.is_err();
+                report.outcomes.push(StepOutcome { label: step.label.clone(), result });
+                if failed && resolved.stop_on_failure {
+                    break;
+                }
+            }
+
+            let summary = report.summary();
+            if !report.is_success() {
+                return Err(ActionError::Other(format!("{}: {}", resolved.name, summary)));
+            }
+            info!("{}: {}", resolved.name, summary);
+            if report.total_steps > 1 {
+                if let Err(e) = self.platform_provider.show_notification(&resolved.name, &summary) {
+                    warn!("Failed to show macro notification: {}", e);
+                }
+            }
+            Ok(())
+        })
+    }
+
+    // The app at `path` is gone: launched again from wherever the refreshed index now finds it
+    async fn launch_moved_app(
+        &self,
+        path: &std::path::Path,
+        args: &[String],
+        error: ActionError,
+    ) -> std::result::Result<(), ActionError> {
+        let Some(ref refresher) = self.app_refresher else {
+            return Err(error);
+        };
+        match refresher.recover_missing_app(path).await {
+            Some(app) => {
+                info!("Retrying the launch from {}", app.executable_path.display());
+                Action::ExecuteApplication { path: app.executable_path, args: args.to_vec() }.execute().await
+            }
+            None => Err(error),
+        }
+    }
+
+    // The outcome is reported as a notification; the file leaves the index only once it is in the trash
+    async fn trash_file(&self, path: &std::path::Path) -> std::result::Result<(), ActionError> {
+        let name = path.file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.display().to_string());
+
+        let platform = self.platform_provider.clone();
+        let target = path.to_path_buf();
+        let outcome = tokio::task::spawn_blocking(move || platform.trash_file(&target))
+            .await
+            .map_err(|e| ActionError::Other(e.to_string()))?;
+
+        if let Err(e) = outcome {
+            if let Err(notify_error) = self.platform_provider.show_notification("Move to trash failed", &e.to_string()) {
+                warn!("Failed to show trash notification: {}", notify_error);
+            }
+            return Err(ActionError::PlatformError(e.to_string()));
+        }
+
+        if let Some(ref index_manager) = self.index_manager {
+            index_manager.remove_file(path).await;
+        }
+        if let Err(e) = self.platform_provider.show_notification("Moved to trash", &name) {
+            warn!("Failed to show trash notification: {}", e);
+        }
+        Ok(())
+    }
+
+    // The clipboard is read first so the copy can be undone for a few seconds
+    async fn copy_to_clipboard(&self, text: &str) -> std::result::Result<(), ActionError> {
+        let window = match (&self.clipboard_undo, &self.config_store) {
+            (Some(_), Some(config_store)) => Duration::from_secs(config_store.snapshot().await.behavior.clipboard_undo_secs),
+            _ => Duration::ZERO,
+        };
+        let previous = if window.is_zero() {
+            None
+        } else {
+            Some(self.platform_provider.paste_from_clipboard())
+        };
+
+        self.platform_provider.copy_to_clipboard(text)
+            .map_err(|e| ActionError::PlatformError(e.to_string()))?;
+
+        let (Some(clipboard_undo), Some(previous)) = (self.clipboard_undo.clone(), previous) else {
+            return Ok(());
+        };
+        if !clipboard_undo.hold(previous, text, window) {
+            return Ok(());
+        }
+        // Let go of the replaced text as soon as it can't be restored anymore
+        let expiring = clipboard_undo.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(window).await;
+            expiring.expire();
+        });
+        if let Err(e) = clipboard_undo.notify(self.platform_provider.clone(), window) {
+            warn!("Failed to show clipboard notification: {}", e);
+        }
+        Ok(())
+    }
+
+    async fn undo_clipboard_change(&self) -> std::result::Result<(), ActionError> {
+        let clipboard_undo = self.clipboard_undo.as_ref()
+            .ok_or_else(|| ActionError::Other("Clipboard undo is not available".to_string()))?;
+        let restore = clipboard_undo.restore(self.platform_provider.as_ref())
+            .map_err(|e| ActionError::PlatformError(e.to_string()))?;
+        match restore {
+            ClipboardRestore::Restore(_) => {
+                info!("Clipboard change undone");
+                Ok(())
+            }
+            ClipboardRestore::Changed => Err(ActionError::Other(
+                "The clipboard changed since the copy; it was left as it is".to_string(),
+            )),
+            ClipboardRestore::Expired => Err(ActionError::Other("The clipboard change can no longer be undone".to_string())),
+        }
+    }
+
+    pub fn start_scheduler(self: &Arc<Self>) {
+        info!("Starting action scheduler loop");
+        let executor = self.clone();
+
+        TaskSupervisor::default().spawn("action-scheduler", move || {
+            let executor = executor.clone();
+            async move {
+                let mut interval_timer = tokio::time::interval(Self::SCHEDULER_POLL_INTERVAL);
+
+                loop {
+                    interval_timer.tick().await;
+                    let tick = executor.scheduler.poll().await;
+
+                    for entry in tick.expiring {
+                        let seconds_left = (entry.due_at - executor.scheduler.now()).num_seconds().max(0);
+                        let message = format!(
+                            "Opening in {} seconds. Search 'scheduled' to cancel.",
+                            seconds_left
+                        );
+                        if let Err(e) = executor.platform_provider.show_notification(&entry.label, &message) {
+                            error!("Failed to show scheduled action notification: {}", e);
+                        }
+                    }
+
+                    for entry in tick.due {
+                        info!("Running scheduled action '{}'", entry.label);
+                        if let Err(e) = executor.execute(&entry.action).await {
+                            error!("Scheduled action '{}' failed: {}", entry.label, e);
+                        }
+                    }
+                }
+            }
+        });
+    }
+}
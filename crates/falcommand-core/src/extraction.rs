@@ -0,0 +1,420 @@
+use std::collections::HashSet;
+use std::io::{BufRead, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tokio::sync::Mutex;
+use log::{info, warn};
+use sha1::{Digest, Sha1};
+
+use crate::archives::{list_archive, ArchiveEntry};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ExtractionError {
+    #[error("Extraction failed: {0}")]
+    Failed(String),
+
+    #[error("{0} crashed the extraction worker earlier and is skipped for this session")]
+    Blacklisted(PathBuf),
+
+    #[error("Extraction worker crashed on {0}")]
+    WorkerCrashed(PathBuf),
+
+    #[error("Extraction of {0} took too long")]
+    TimedOut(PathBuf),
+
+    #[error("Extraction worker I/O error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Malformed worker message: {0}")]
+    Protocol(String),
+}
+
+// Parsing work that can crash or hang on a malformed file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum JobKind {
+    ListArchive(PathBuf),
+    // The first `max_bytes` of a text file
+    TextPreview { path: PathBuf, max_bytes: usize },
+    // Format and dimensions from the image header
+    ImageInfo(PathBuf),
+    // SHA-1 of the first `max_bytes`, or of the whole file
+    HashFile { path: PathBuf, max_bytes: Option<u64> },
+}
+
+impl JobKind {
+    pub fn path(&self) -> &Path {
+        match self {
+            JobKind::ListArchive(path) | JobKind::ImageInfo(path) => path,
+            JobKind::TextPreview { path, .. } | JobKind::HashFile { path, .. } => path,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtractionJob {
+    pub id: u64,
+    pub kind: JobKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum JobOutput {
+    ArchiveEntries(Vec<ArchiveEntry>),
+    Text(String),
+    Image { format: String, width: u32, height: u32 },
+    // Lowercase hex
+    Hash(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobResponse {
+    pub id: u64,
+    pub result: std::result::Result<JobOutput, String>,
+}
+
+// One JSON message per line. serde_json escapes newlines inside strings, so a frame never spans lines
+pub const MAX_FRAME_BYTES: usize = 16 * 1024 * 1024;
+
+pub fn encode_frame<T: Serialize>(message: &T) -> std::result::Result<Vec<u8>, ExtractionError> {
+    let mut frame = serde_json::to_vec(message).map_err(|e| ExtractionError::Protocol(e.to_string()))?;
+    frame.push(b'\n');
+    Ok(frame)
+}
+
+pub fn decode_frame<T: DeserializeOwned>(line: &str) -> std::result::Result<T, ExtractionError> {
+    if line.len() > MAX_FRAME_BYTES {
+        return Err(ExtractionError::Protocol(format!("frame of {} bytes", line.len())));
+    }
+    serde_json::from_str(line.trim_end()).map_err(|e| ExtractionError::Protocol(e.to_string()))
+}
+
+// Blocking side, for the worker process. None at end of input
+pub fn read_frame<T: DeserializeOwned>(reader: &mut impl BufRead) -> std::result::Result<Option<T>, ExtractionError> {
+    let mut line = String::new();
+    let read = reader.take(MAX_FRAME_BYTES as u64 + 1).read_line(&mut line)?;
+    if read == 0 {
+        return Ok(None);
+    }
+    decode_frame(&line).map(Some)
+}
+
+pub fn write_frame<T: Serialize>(writer: &mut impl Write, message: &T) -> std::result::Result<(), ExtractionError> {
+    writer.write_all(&encode_frame(message)?)?;
+    writer.flush()?;
+    Ok(())
+}
+
+// Runs a job in the current process; the worker calls this, and so does the fallback
+pub fn run_job(kind: &JobKind) -> std::result::Result<JobOutput, String> {
+    match kind {
+        JobKind::ListArchive(path) => list_archive(path)
+            .map(JobOutput::ArchiveEntries)
+            .map_err(|e| e.to_string()),
+        JobKind::TextPreview { path, max_bytes } => text_preview(path, *max_bytes).map(JobOutput::Text),
+        JobKind::ImageInfo(path) => image_info(path),
+        JobKind::HashFile { path, max_bytes } => hash_file(path, *max_bytes).map(JobOutput::Hash),
+    }
+}
+
+fn hash_file(path: &Path, max_bytes: Option<u64>) -> std::result::Result<String, String> {
+    let file = std::fs::File::open(path).map_err(|e| e.to_string())?;
+    let mut hasher = Sha1::new();
+    let mut reader = file.take(max_bytes.unwrap_or(u64::MAX));
+    std::io::copy(&mut reader, &mut hasher).map_err(|e| e.to_string())?;
+    Ok(hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+fn text_preview(path: &Path, max_bytes: usize) -> std::result::Result<String, String> {
+    let mut bytes = Vec::new();
+    std::fs::File::open(path)
+        .and_then(|file| file.take(max_bytes as u64).read_to_end(&mut bytes))
+        .map_err(|e| e.to_string())?;
+    if bytes.contains(&0) {
+        return Err("binary file".to_string());
+    }
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+// Header-only: enough for "PNG · 1920×1080" without decoding any pixels
+fn image_info(path: &Path) -> std::result::Result<JobOutput, String> {
+    const HEADER_BYTES: u64 = 64 * 1024;
+
+    let mut header = Vec::new();
+    std::fs::File::open(path)
+        .and_then(|file| file.take(HEADER_BYTES).read_to_end(&mut header))
+        .map_err(|e| e.to_string())?;
+
+    let be16 = |at: usize| header.get(at..at + 2).map(|b| u16::from_be_bytes([b[0], b[1]]) as u32);
+    let le16 = |at: usize| header.get(at..at + 2).map(|b| u16::from_le_bytes([b[0], b[1]]) as u32);
+    let be32 = |at: usize| header.get(at..at + 4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]));
+    let image = |format: &str, width: Option<u32>, height: Option<u32>| match (width, height) {
+        (Some(width), Some(height)) => Ok(JobOutput::Image { format: format.to_string(), width, height }),
+        _ => Err(format!("truncated {} header", format)),
+    };
+
+    if header.starts_with(b"\x89PNG\r\n\x1a\n") {
+        return image("PNG", be32(16), be32(20));
+    }
+    if header.starts_with(b"GIF87a") || header.starts_with(b"GIF89a") {
+        return image("GIF", le16(6), le16(8));
+    }
+    if header.starts_with(&[0xFF, 0xD8]) {
+        // Walk the segments to the first start-of-frame marker
+        let mut at = 2;
+        while let (Some(&0xFF), Some(&marker)) = (header.get(at), header.get(at + 1)) {
+            let length = be16(at + 2).ok_or("truncated JPEG header")? as usize;
+            if matches!(marker, 0xC0..=0xCF) && !matches!(marker, 0xC4 | 0xC8 | 0xCC) {
+                return image("JPEG", be16(at + 7), be16(at + 5));
+            }
+            at += 2 + length;
+        }
+        return Err("no JPEG frame header".to_string());
+    }
+    Err("unsupported image format".to_string())
+}
+
+struct WorkerProcess {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl WorkerProcess {
+    async fn exchange(&mut self, job: &ExtractionJob) -> std::result::Result<JobResponse, ExtractionError> {
+        self.stdin.write_all(&encode_frame(job)?).await?;
+        self.stdin.flush().await?;
+
+        let mut line = String::new();
+        if self.stdout.read_line(&mut line).await? == 0 {
+            return Err(ExtractionError::WorkerCrashed(job.kind.path().to_path_buf()));
+        }
+        decode_frame(&line)
+    }
+}
+
+#[derive(Default)]
+struct WorkerState {
+    process: Option<WorkerProcess>,
+    // Set once the worker fails to start; jobs then run in-process for the rest of the session
+    unavailable: bool,
+    // Files that crashed or hung the worker
+    blacklist: HashSet<PathBuf>,
+}
+
+// Runs extraction jobs in `falcommand-worker`, started on first use and again after a crash.
+// A file that takes the worker down is not tried again this session
+pub struct ExtractionWorker {
+    program: Option<PathBuf>,
+    memory_limit_mb: u64,
+    time_limit: Duration,
+    next_id: AtomicU64,
+    state: Mutex<WorkerState>,
+}
+
+impl std::fmt::Debug for ExtractionWorker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut debug = f.debug_struct("ExtractionWorker");
+        debug.field("program", &self.program);
+        // A job holds the state lock while it runs; its fields are then left out
+        if let Ok(state) = self.state.try_lock() {
+            debug.field("running", &state.process.is_some())
+                .field("unavailable", &state.unavailable)
+                .field("blacklisted", &state.blacklist.len());
+        }
+        debug.finish_non_exhaustive()
+    }
+}
+
+impl ExtractionWorker {
+    pub const WORKER_NAME: &'static str = "falcommand-worker";
+    pub const MEMORY_LIMIT_MB: u64 = 512;
+    pub const TIME_LIMIT: Duration = Duration::from_secs(10);
+
+    // The worker binary next to the running executable
+    pub fn new() -> Self {
+        let program = std::env::current_exe().ok()
+            .map(|exe| exe.with_file_name(format!("{}{}", Self::WORKER_NAME, std::env::consts::EXE_SUFFIX)));
+        Self::with_program(program)
+    }
+
+    // None runs every job in-process
+    pub fn with_program(program: Option<PathBuf>) -> Self {
+        Self {
+            program,
+            memory_limit_mb: Self::MEMORY_LIMIT_MB,
+            time_limit: Self::TIME_LIMIT,
+            next_id: AtomicU64::new(1),
+            state: Mutex::new(WorkerState::default()),
+        }
+    }
+
+    pub fn with_limits(mut self, memory_limit_mb: u64, time_limit: Duration) -> Self {
+        self.memory_limit_mb = memory_limit_mb;
+        self.time_limit = time_limit;
+        self
+    }
+
+    pub async fn is_blacklisted(&self, path: &Path) -> bool {
+        self.state.lock().await.blacklist.contains(path)
+    }
+
+    fn spawn(&self) -> std::result::Result<WorkerProcess, ExtractionError> {
+        let program = self.program.as_ref()
+            .ok_or_else(|| ExtractionError::Failed("no worker program".to_string()))?;
+        let mut child = Command::new(program)
+            .arg("--memory-limit-mb")
+            .arg(self.memory_limit_mb.to_string())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .kill_on_drop(true)
+            .spawn()?;
+        let stdin = child.stdin.take().ok_or_else(|| ExtractionError::Failed("worker has no stdin".to_string()))?;
+        let stdout = child.stdout.take().ok_or_else(|| ExtractionError::Failed("worker has no stdout".to_string()))?;
+        info!("Started extraction worker (pid {:?})", child.id());
+        Ok(WorkerProcess { child, stdin, stdout: BufReader::new(stdout) })
+    }
+
+    async fn run_in_process(kind: JobKind) -> std::result::Result<JobOutput, ExtractionError> {
+        tokio::task::spawn_blocking(move || run_job(&kind))
+            .await
+            .map_err(|e| ExtractionError::Failed(e.to_string()))?
+            .map_err(ExtractionError::Failed)
+    }
+
+    pub async fn run(&self, kind: JobKind) -> std::result::Result<JobOutput, ExtractionError> {
+        let path = kind.path().to_path_buf();
+        // Jobs go one at a time; the worker handles a single request per exchange
+        let mut state = self.state.lock().await;
+        if state.blacklist.contains(&path) {
+            return Err(ExtractionError::Blacklisted(path));
+        }
+        if state.unavailable {
+            drop(state);
+            return Self::run_in_process(kind).await;
+        }
+
+        if state.process.is_none() {
+            match self.spawn() {
+                Ok(process) => state.process = Some(process),
+                Err(e) => {
+                    warn!("Extraction worker unavailable, parsing files in-process: {}", e);
+                    state.unavailable = true;
+                    drop(state);
+                    return Self::run_in_process(kind).await;
+                }
+            }
+        }
+
+        let job = ExtractionJob { id: self.next_id.fetch_add(1, Ordering::Relaxed), kind };
+        let Some(process) = state.process.as_mut() else {
+            return Err(ExtractionError::Failed("worker not running".to_string()));
+        };
+        let outcome = tokio::time::timeout(self.time_limit, process.exchange(&job)).await;
+
+        match outcome {
+            Ok(Ok(response)) if response.id == job.id => response.result.map_err(ExtractionError::Failed),
+            Ok(Ok(response)) => {
+                state.process = None;
+                Err(ExtractionError::Protocol(format!("reply to job {} while waiting for {}", response.id, job.id)))
+            }
+            // The worker died or sent garbage mid-job; the next job starts a fresh one
+            Ok(Err(e)) => {
+                warn!("Extraction worker failed on {}, skipping it for this session: {}", path.display(), e);
+                Self::discard(&mut state).await;
+                state.blacklist.insert(path.clone());
+                Err(ExtractionError::WorkerCrashed(path))
+            }
+            Err(_) => {
+                warn!("Extraction of {} exceeded {:?}, skipping it for this session", path.display(), self.time_limit);
+                Self::discard(&mut state).await;
+                state.blacklist.insert(path.clone());
+                Err(ExtractionError::TimedOut(path))
+            }
+        }
+    }
+
+    async fn discard(state: &mut WorkerState) {
+        if let Some(mut process) = state.process.take() {
+            let _ = process.child.kill().await;
+        }
+    }
+
+    pub async fn list_archive(&self, path: &Path) -> std::result::Result<Vec<ArchiveEntry>, ExtractionError> {
+        match self.run(JobKind::ListArchive(path.to_path_buf())).await? {
+            JobOutput::ArchiveEntries(entries) => Ok(entries),
+            other => Err(ExtractionError::Protocol(format!("unexpected output {:?}", other))),
+        }
+    }
+
+    pub async fn hash_file(&self, path: &Path, max_bytes: Option<u64>) -> std::result::Result<String, ExtractionError> {
+        match self.run(JobKind::HashFile { path: path.to_path_buf(), max_bytes }).await? {
+            JobOutput::Hash(hash) => Ok(hash),
+            other => Err(ExtractionError::Protocol(format!("unexpected output {:?}", other))),
+        }
+    }
+
+    pub async fn shutdown(&self) {
+        Self::discard(&mut *self.state.lock().await).await;
+    }
+}
+
+impl Default for ExtractionWorker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_are_single_lines_that_round_trip() {
+        let job = ExtractionJob { id: 7, kind: JobKind::TextPreview { path: PathBuf::from("/tmp/a\nb.txt"), max_bytes: 64 } };
+        let frame = encode_frame(&job).unwrap();
+        assert_eq!(frame.iter().filter(|&&byte| byte == b'\n').count(), 1);
+        assert_eq!(frame.last(), Some(&b'\n'));
+        assert_eq!(decode_frame::<ExtractionJob>(std::str::from_utf8(&frame).unwrap()).unwrap(), job);
+
+        let response = JobResponse { id: 7, result: Err("binary file".to_string()) };
+        let mut stream = Vec::new();
+        write_frame(&mut stream, &job).unwrap();
+        write_frame(&mut stream, &response).unwrap();
+        let mut reader = std::io::Cursor::new(stream);
+        assert_eq!(read_frame::<ExtractionJob>(&mut reader).unwrap(), Some(job));
+        assert_eq!(read_frame::<JobResponse>(&mut reader).unwrap(), Some(response));
+        assert_eq!(read_frame::<JobResponse>(&mut reader).unwrap(), None);
+    }
+
+    #[test]
+    fn malformed_and_oversized_frames_are_protocol_errors() {
+        assert!(matches!(decode_frame::<ExtractionJob>("{\"id\": 1}"), Err(ExtractionError::Protocol(_))));
+        assert!(matches!(decode_frame::<ExtractionJob>("not json"), Err(ExtractionError::Protocol(_))));
+
+        let oversized = format!("\"{}\"\n", "x".repeat(MAX_FRAME_BYTES));
+        let mut reader = std::io::Cursor::new(oversized.into_bytes());
+        assert!(matches!(read_frame::<String>(&mut reader), Err(ExtractionError::Protocol(_))));
+    }
+
+    #[tokio::test]
+    async fn jobs_run_in_process_when_the_worker_cannot_start() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("notes.txt");
+        std::fs::write(&file, "hello").unwrap();
+
+        for program in [None, Some(dir.path().join("missing-worker"))] {
+            let worker = ExtractionWorker::with_program(program);
+            assert_eq!(worker.hash_file(&file, None).await.unwrap(), "aaf4c61ddcc5e8a2dabede0f3b482cd9aea9434d");
+            let preview = worker.run(JobKind::TextPreview { path: file.clone(), max_bytes: 4 }).await.unwrap();
+            assert_eq!(preview, JobOutput::Text("hell".to_string()));
+            // Job errors still come back as errors, and nothing is blacklisted in-process
+            assert!(matches!(worker.hash_file(&dir.path().join("gone"), None).await, Err(ExtractionError::Failed(_))));
+            assert!(!worker.is_blacklisted(&dir.path().join("gone")).await);
+        }
+    }
+}
@@ -0,0 +1,210 @@
+use std::time::SystemTime;
+use chrono::{DateTime, Datelike, FixedOffset, Timelike, Utc};
+
+use falcommand_config::i18n::Language;
+
+// Thousands separator and decimal mark; English and Japanese happen to share them
+fn separators(language: Language) -> (char, char) {
+    match language {
+        Language::English | Language::Japanese => (',', '.'),
+    }
+}
+
+fn group_digits(digits: &str, separator: char) -> String {
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(separator);
+        }
+        grouped.push(c);
+    }
+    grouped
+}
+
+// 1234567.5 -> "1,234,567.5". Up to `max_decimals` digits, trailing zeros dropped;
+// values too large for grouping to help (or not finite) use Rust's own formatting
+pub fn format_decimal(value: f64, max_decimals: usize, language: Language) -> String {
+    if !value.is_finite() || value.abs() >= 1e21 {
+        return value.to_string();
+    }
+
+    let (group, decimal) = separators(language);
+    let fixed = format!("{:.*}", max_decimals, value.abs());
+    let (integer, fraction) = fixed.split_once('.').unwrap_or((&fixed, ""));
+    let fraction = fraction.trim_end_matches('0');
+
+    let mut formatted = String::new();
+    if value < 0.0 && (integer != "0" || !fraction.is_empty()) {
+        formatted.push('-');
+    }
+    formatted.push_str(&group_digits(integer, group));
+    if !fraction.is_empty() {
+        formatted.push(decimal);
+        formatted.push_str(fraction);
+    }
+    formatted
+}
+
+// Calculator results and other computed values
+pub fn format_number(value: f64, language: Language) -> String {
+    format_decimal(value, 10, language)
+}
+
+// Counts: 12430 -> "12,430"
+pub fn format_count(count: usize, language: Language) -> String {
+    group_digits(&count.to_string(), separators(language).0)
+}
+
+// Binary units with one decimal from KB up: "0 B", "512 B", "1.5 MB"
+pub fn format_size(bytes: u64, language: Language) -> String {
+    const UNITS: [&str; 6] = ["KB", "MB", "GB", "TB", "PB", "EB"];
+
+    if bytes < 1024 {
+        return match language {
+            Language::English => format!("{} B", bytes),
+            Language::Japanese => format!("{} バイト", bytes),
+        };
+    }
+
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    // 1023.96 KB would print as "1,024 KB"
+    if format!("{:.1}", value) == "1024.0" && unit < UNITS.len() - 1 {
+        value = 1.0;
+        unit += 1;
+    }
+    format!("{} {}", format_decimal(value, 1, language), UNITS[unit])
+}
+
+// "just now", "5 minutes ago", "2 hours ago", "3 days ago"; times in the future count as now
+pub fn format_relative_time(then: SystemTime, now: SystemTime, language: Language) -> String {
+    let seconds = now.duration_since(then).map(|d| d.as_secs()).unwrap_or(0);
+    let (amount, unit) = match seconds {
+        0..=59 => {
+            return match language {
+                Language::English => "just now".to_string(),
+                Language::Japanese => "たった今".to_string(),
+            };
+        }
+        60..=3599 => (seconds / 60, 0),
+        3600..=86399 => (seconds / 3600, 1),
+        _ => (seconds / 86400, 2),
+    };
+
+    let amount_text = format_count(amount as usize, language);
+    match language {
+        Language::English => {
+            let unit = ["minute", "hour", "day"][unit];
+            let plural = if amount == 1 { "" } else { "s" };
+            format!("{} {}{} ago", amount_text, unit, plural)
+        }
+        Language::Japanese => {
+            let unit = ["分", "時間", "日"][unit];
+            format!("{}{}前", amount_text, unit)
+        }
+    }
+}
+
+// "Jun 1, 2024 14:05" / "2024年6月1日 14:05", in the given UTC offset
+pub fn format_datetime(time: DateTime<Utc>, offset: FixedOffset, language: Language) -> String {
+    const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+    let local = time.with_timezone(&offset);
+    match language {
+        Language::English => format!(
+            "{} {}, {} {:02}:{:02}",
+            MONTHS[local.month0() as usize], local.day(), local.year(), local.hour(), local.minute()
+        ),
+        Language::Japanese => format!(
+            "{}年{}月{}日 {:02}:{:02}",
+            local.year(), local.month(), local.day(), local.hour(), local.minute()
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+    use chrono::TimeZone;
+    use super::*;
+
+    const LANGUAGES: [Language; 2] = [Language::English, Language::Japanese];
+
+    #[test]
+    fn numbers_are_grouped_in_every_language() {
+        for language in LANGUAGES {
+            assert_eq!(format_number(0.0, language), "0");
+            assert_eq!(format_number(1234567.5, language), "1,234,567.5");
+            assert_eq!(format_number(-1234.25, language), "-1,234.25");
+            assert_eq!(format_number(0.1 + 0.2, language), "0.3");
+            // Rounded away entirely: no "-0"
+            assert_eq!(format_number(-1e-13, language), "0");
+            // Past grouping range the plain digits are kept
+            assert_eq!(format_number(1e21, language), "1000000000000000000000");
+            assert_eq!(format_number(1e20, language), "100,000,000,000,000,000,000");
+            assert_eq!(format_number(f64::INFINITY, language), "inf");
+            assert_eq!(format_count(12430, language), "12,430");
+            assert_eq!(format_count(usize::MAX, language), "18,446,744,073,709,551,615");
+        }
+    }
+
+    #[test]
+    fn sizes_use_binary_units() {
+        let cases: [(u64, &str, &str); 7] = [
+            (0, "0 B", "0 バイト"),
+            (1023, "1023 B", "1023 バイト"),
+            (1024, "1 KB", "1 KB"),
+            (1536, "1.5 KB", "1.5 KB"),
+            // Just short of a megabyte rounds up into the next unit
+            (1024 * 1024 - 1, "1 MB", "1 MB"),
+            (5 * 1024u64.pow(4) / 2, "2.5 TB", "2.5 TB"),
+            (u64::MAX, "16 EB", "16 EB"),
+        ];
+        for (bytes, english, japanese) in cases {
+            assert_eq!(format_size(bytes, Language::English), english, "{} bytes", bytes);
+            assert_eq!(format_size(bytes, Language::Japanese), japanese, "{} bytes", bytes);
+        }
+    }
+
+    #[test]
+    fn relative_times_count_minutes_hours_and_days() {
+        let now = UNIX_EPOCH + Duration::from_secs(200_000_000_000);
+        let cases: [(u64, &str, &str); 7] = [
+            (0, "just now", "たった今"),
+            (59, "just now", "たった今"),
+            (60, "1 minute ago", "1分前"),
+            (150, "2 minutes ago", "2分前"),
+            (2 * 3600, "2 hours ago", "2時間前"),
+            (86400, "1 day ago", "1日前"),
+            (1_000_000 * 86400, "1,000,000 days ago", "1,000,000日前"),
+        ];
+        for (ago, english, japanese) in cases {
+            let then = now - Duration::from_secs(ago);
+            assert_eq!(format_relative_time(then, now, Language::English), english);
+            assert_eq!(format_relative_time(then, now, Language::Japanese), japanese);
+        }
+        // Clock skew puts `then` after `now`
+        for language in LANGUAGES {
+            let future = format_relative_time(now + Duration::from_secs(3600), now, language);
+            assert_eq!(future, format_relative_time(now, now, language));
+        }
+    }
+
+    #[test]
+    fn datetimes_are_shown_in_the_given_offset() {
+        let tokyo = FixedOffset::east_opt(9 * 3600).unwrap();
+        let new_york = FixedOffset::west_opt(5 * 3600).unwrap();
+        let june = Utc.with_ymd_and_hms(2024, 6, 1, 5, 5, 0).unwrap();
+        assert_eq!(format_datetime(june, tokyo, Language::English), "Jun 1, 2024 14:05");
+        assert_eq!(format_datetime(june, tokyo, Language::Japanese), "2024年6月1日 14:05");
+
+        // Crossing back over midnight and the year
+        let new_year = Utc.with_ymd_and_hms(2024, 1, 1, 3, 0, 0).unwrap();
+        assert_eq!(format_datetime(new_year, new_york, Language::English), "Dec 31, 2023 22:00");
+        assert_eq!(format_datetime(new_year, new_york, Language::Japanese), "2023年12月31日 22:00");
+    }
+}
@@ -0,0 +1,267 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use async_trait::async_trait;
+use fuzzy_matcher::{FuzzyMatcher, skim::SkimMatcherV2};
+use log::{info, warn};
+
+use falcommand_config::{Action, Category, SearchResult, SyntaxHint};
+use falcommand_config::i18n::tr;
+use falcommand_platform::{flatten_menu, ForegroundApp, MenuAccess, MenuItem, ACCESSIBILITY_SETTINGS_URL};
+use crate::source::SearchSource;
+
+// Best matches first; an item matches on its own title or on its full path ("file export pdf")
+pub fn match_menu_items(items: &[MenuItem], query: &str, limit: usize) -> Vec<(MenuItem, i64)> {
+    let matcher = SkimMatcherV2::default();
+    let mut matches: Vec<(MenuItem, i64)> = items.iter()
+        .filter_map(|item| {
+            let title_score = matcher.fuzzy_match(item.title(), query);
+            let path_score = matcher.fuzzy_match(&item.path.join(" "), query);
+            title_score.max(path_score).map(|score| (item.clone(), score))
+        })
+        .collect();
+
+    matches.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.path.len().cmp(&b.0.path.len())));
+    matches.truncate(limit);
+    matches
+}
+
+struct CapturedMenu {
+    app: ForegroundApp,
+    // Read on the first search after the capture
+    items: Option<Vec<MenuItem>>,
+}
+
+// Menu commands of the app that was in front when the launcher opened (macOS, `ax-integration`)
+pub struct FrontAppMenuSource {
+    access: Arc<dyn MenuAccess>,
+    captured: Mutex<Option<CapturedMenu>>,
+}
+
+impl FrontAppMenuSource {
+    const MAX_DEPTH: usize = 4;
+    const READ_BUDGET: Duration = Duration::from_millis(300);
+    const MIN_QUERY_LEN: usize = 3;
+    const LIMIT: usize = 5;
+    // Menu commands rank below direct app and file matches
+    const SCORE_SCALE: f64 = 0.8;
+
+    pub fn new(access: Arc<dyn MenuAccess>) -> Self {
+        Self {
+            access,
+            captured: Mutex::new(None),
+        }
+    }
+
+    // Call before the launcher window takes focus; the launcher itself is never captured
+    pub fn capture_foreground(&self) {
+        let app = self.access.foreground_app()
+            .filter(|app| app.pid != std::process::id() as i32);
+        if let Some(ref app) = app {
+            info!("Captured foreground app {} ({})", app.name, app.pid);
+        }
+        *self.captured.lock().unwrap() = app.map(|app| CapturedMenu { app, items: None });
+    }
+
+    async fn menu_items(&self) -> Option<(ForegroundApp, Vec<MenuItem>)> {
+        let app = match *self.captured.lock().unwrap() {
+            Some(CapturedMenu { ref app, items: Some(ref items) }) => return Some((app.clone(), items.clone())),
+            Some(CapturedMenu { ref app, items: None }) => app.clone(),
+            None => return None,
+        };
+
+        let access = self.access.clone();
+        let target = app.clone();
+        let tree = tokio::task::spawn_blocking(move || access.menu_tree(&target, Self::MAX_DEPTH, Self::READ_BUDGET)).await;
+        let items = match tree {
+            Ok(Ok(tree)) => flatten_menu(&tree, Self::MAX_DEPTH),
+            Ok(Err(e)) => {
+                warn!("Failed to read the menus of {}: {}", app.name, e);
+                Vec::new()
+            }
+            Err(e) => {
+                warn!("Menu reader for {} stopped: {}", app.name, e);
+                Vec::new()
+            }
+        };
+
+        // Another capture may have happened while reading
+        if let Some(captured) = self.captured.lock().unwrap().as_mut().filter(|c| c.app == app) {
+            captured.items = Some(items.clone());
+        }
+        Some((app, items))
+    }
+
+    fn permission_result() -> SearchResult {
+        SearchResult::new(
+            "Allow FalCommand to use Accessibility",
+            "Needed to search the menus of the app in front. Opens System Settings",
+        )
+        .with_action(Action::OpenUrl(ACCESSIBILITY_SETTINGS_URL.to_string()))
+        .with_category(Category::SystemCommand)
+        .with_score(0.1)
+    }
+
+    fn item_result(app: &ForegroundApp, item: MenuItem, score: i64) -> SearchResult {
+        SearchResult::new(item.title().to_string(), format!("{} › {}", app.name, item.breadcrumb()))
+            .with_action(Action::PressMenuItem {
+                pid: app.pid,
+                app: app.name.clone(),
+                path: item.path,
+            })
+            .with_category(Category::SystemCommand)
+            .with_score((score as f64 / 100.0).min(1.0) * Self::SCORE_SCALE)
+    }
+}
+
+#[async_trait]
+impl SearchSource for FrontAppMenuSource {
+    fn name(&self) -> &str {
+        "App menu"
+    }
+
+    fn syntax_hints(&self) -> Vec<SyntaxHint> {
+        vec![SyntaxHint::new(
+            "<menu command>",
+            tr("hint.app_menu", "Run a menu command of the app in front"),
+            "export as pdf",
+        )]
+    }
+
+    async fn search(&self, query: &str) -> Vec<SearchResult> {
+        let query = query.trim();
+        if query.chars().count() < Self::MIN_QUERY_LEN || self.captured.lock().unwrap().is_none() {
+            return Vec::new();
+        }
+        if !self.access.is_trusted() {
+            return vec![Self::permission_result()];
+        }
+
+        let Some((app, items)) = self.menu_items().await else {
+            return Vec::new();
+        };
+        match_menu_items(&items, query, Self::LIMIT)
+            .into_iter()
+            .map(|(item, score)| Self::item_result(&app, item, score))
+            .collect()
+    }
+}
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use falcommand_platform::{MenuNode, PlatformError};
+    use super::*;
+
+    // An accessibility tree for "Preview" that counts how often it is read
+    #[derive(Default)]
+    struct MockMenus {
+        untrusted: AtomicBool,
+        failing: AtomicBool,
+        reads: AtomicUsize,
+        foreground_pid: Option<i32>,
+    }
+
+    impl MenuAccess for MockMenus {
+        fn foreground_app(&self) -> Option<ForegroundApp> {
+            Some(ForegroundApp { pid: self.foreground_pid.unwrap_or(4242), name: "Preview".to_string() })
+        }
+
+        fn is_trusted(&self) -> bool {
+            !self.untrusted.load(Ordering::SeqCst)
+        }
+
+        fn menu_tree(&self, _app: &ForegroundApp, _max_depth: usize, _budget: Duration) -> Result<Vec<MenuNode>, PlatformError> {
+            self.reads.fetch_add(1, Ordering::SeqCst);
+            if self.failing.load(Ordering::SeqCst) {
+                return Err(PlatformError::Other("AX error".to_string()));
+            }
+            let leaf = |title: &str| MenuNode::new(title, Vec::new());
+            Ok(vec![
+                MenuNode::new("File", vec![
+                    leaf("Open…"),
+                    MenuNode::new("Export", vec![leaf("Export as PDF…"), leaf("Export as PNG")]),
+                    leaf("Print…").disabled(),
+                ]),
+                MenuNode::new("View", vec![leaf("Zoom In"), leaf("Zoom Out")]),
+            ])
+        }
+
+        fn press(&self, _app: &ForegroundApp, _path: &[String]) -> Result<(), PlatformError> {
+            Ok(())
+        }
+
+        fn activate(&self, _app: &ForegroundApp) -> Result<(), PlatformError> {
+            Ok(())
+        }
+    }
+
+    fn menu_source(menus: MockMenus) -> (FrontAppMenuSource, Arc<MockMenus>) {
+        let menus = Arc::new(menus);
+        (FrontAppMenuSource::new(menus.clone()), menus)
+    }
+
+    #[test]
+    fn titles_and_paths_both_match() {
+        let item = |path: &[&str]| MenuItem { path: path.iter().map(|part| part.to_string()).collect() };
+        let items = vec![item(&["File", "Export", "Export as PDF…"]), item(&["View", "Zoom In"]), item(&["Edit", "Find", "Find…"])];
+
+        let titles: Vec<String> = match_menu_items(&items, "pdf", 5).into_iter().map(|(item, _)| item.title().to_string()).collect();
+        assert_eq!(titles, ["Export as PDF…"]);
+        // "view zoom" only matches the path
+        assert_eq!(match_menu_items(&items, "view zoom", 5)[0].0.title(), "Zoom In");
+        assert_eq!(match_menu_items(&items, "find", 1).len(), 1);
+        assert!(match_menu_items(&items, "qqq", 5).is_empty());
+    }
+
+    #[tokio::test]
+    async fn menu_items_of_the_captured_app_press_their_path() {
+        let (source, menus) = menu_source(MockMenus::default());
+        source.capture_foreground();
+
+        let results = source.search("export pdf").await;
+        assert_eq!(results[0].title, "Export as PDF…");
+        assert_eq!(results[0].description, "Preview › File › Export › Export as PDF…");
+        assert_eq!(results[0].action, Action::PressMenuItem {
+            pid: 4242,
+            app: "Preview".to_string(),
+            path: vec!["File".to_string(), "Export".to_string(), "Export as PDF…".to_string()],
+        });
+        assert!(results.iter().all(|result| result.score <= FrontAppMenuSource::SCORE_SCALE));
+        // Disabled items are never offered
+        assert!(source.search("print").await.is_empty());
+
+        // The tree is read once per capture
+        source.search("zoom").await;
+        assert_eq!(menus.reads.load(Ordering::SeqCst), 1);
+        source.capture_foreground();
+        source.search("zoom").await;
+        assert_eq!(menus.reads.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn nothing_is_read_without_a_capture_permission_or_long_enough_query() {
+        let (source, menus) = menu_source(MockMenus::default());
+        assert!(source.search("zoom").await.is_empty());
+
+        source.capture_foreground();
+        assert!(source.search("zo").await.is_empty());
+
+        menus.untrusted.store(true, Ordering::SeqCst);
+        let results = source.search("zoom").await;
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].action, Action::OpenUrl(ACCESSIBILITY_SETTINGS_URL.to_string()));
+        assert_eq!(menus.reads.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn the_launcher_itself_and_unreadable_menus_give_no_results() {
+        let (source, menus) = menu_source(MockMenus { foreground_pid: Some(std::process::id() as i32), ..MockMenus::default() });
+        source.capture_foreground();
+        assert!(source.search("zoom").await.is_empty());
+        assert_eq!(menus.reads.load(Ordering::SeqCst), 0);
+
+        let (source, menus) = menu_source(MockMenus::default());
+        menus.failing.store(true, Ordering::SeqCst);
+        source.capture_foreground();
+        assert!(source.search("zoom").await.is_empty());
+    }
+}
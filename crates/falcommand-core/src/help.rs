@@ -0,0 +1,109 @@
+use std::collections::HashSet;
+use async_trait::async_trait;
+
+use falcommand_config::{Action, Category, SearchResult, SyntaxHint};
+use falcommand_config::i18n::tr;
+
+// Hints contributed by one component, shown under its name in the help overlay
+#[derive(Debug, Clone, PartialEq)]
+pub struct HintGroup {
+    pub source: String,
+    pub hints: Vec<SyntaxHint>,
+}
+
+impl HintGroup {
+    pub fn new(source: impl Into<String>, hints: Vec<SyntaxHint>) -> Self {
+        Self {
+            source: source.into(),
+            hints,
+        }
+    }
+}
+
+// Components outside core (e.g. the plugin system) that contribute help entries
+#[async_trait]
+pub trait SyntaxHintProvider: Send + Sync {
+    async fn syntax_hints(&self) -> Vec<HintGroup>;
+}
+
+pub fn is_help_query(query: &str) -> bool {
+    let query = query.trim();
+    query == "?" || query.eq_ignore_ascii_case("help")
+}
+
+// Drops patterns already listed by an earlier group, then empty groups
+pub fn aggregate_hints(groups: Vec<HintGroup>) -> Vec<HintGroup> {
+    let mut seen = HashSet::new();
+
+    groups.into_iter()
+        .map(|group| HintGroup {
+            source: group.source,
+            hints: group.hints.into_iter()
+                .filter(|hint| seen.insert(hint.pattern.clone()))
+                .collect(),
+        })
+        .filter(|group| !group.hints.is_empty())
+        .collect()
+}
+
+pub fn help_results(groups: &[HintGroup]) -> Vec<SearchResult> {
+    let example_label = tr("help.example", "e.g.");
+    let total = groups.iter().map(|g| g.hints.len()).sum::<usize>().max(1) as f64;
+    let mut position = 0.0;
+
+    let mut results = Vec::new();
+    for group in groups {
+        for hint in &group.hints {
+            // Keep the overlay order when results are re-sorted by score
+            let score = 1.0 - position / total;
+            position += 1.0;
+
+            results.push(
+                SearchResult::new(
+                    hint.pattern.clone(),
+                    format!("{} · {} ({}: {})", group.source, hint.description, example_label, hint.example),
+                )
+                .with_action(Action::CopyToClipboard(hint.example.clone()))
+                .with_category(Category::SystemCommand)
+                .with_score(score)
+            );
+        }
+    }
+    results
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hint(pattern: &str) -> SyntaxHint {
+        SyntaxHint::new(pattern, "", pattern)
+    }
+
+    #[test]
+    fn a_pattern_is_listed_once_under_its_first_source() {
+        let groups = aggregate_hints(vec![
+            HintGroup::new("FalCommand", vec![hint("?"), hint("in:<folder> <name>")]),
+            HintGroup::new("Timer", vec![hint("timer <duration>"), hint("?")]),
+            HintGroup::new("Help", vec![hint("?")]),
+        ]);
+
+        assert_eq!(groups, [
+            HintGroup::new("FalCommand", vec![hint("?"), hint("in:<folder> <name>")]),
+            HintGroup::new("Timer", vec![hint("timer <duration>")]),
+        ]);
+    }
+
+    #[test]
+    fn help_results_keep_the_overlay_order() {
+        let groups = [
+            HintGroup::new("FalCommand", vec![hint("?")]),
+            HintGroup::new("Timer", vec![hint("timer <duration>")]),
+        ];
+        let results = help_results(&groups);
+
+        assert_eq!(results.len(), 2);
+        assert!(results[0].score > results[1].score);
+        assert_eq!(results[1].action, Action::CopyToClipboard("timer <duration>".to_string()));
+        assert!(is_help_query(" ? ") && is_help_query("HELP") && !is_help_query("helpful"));
+    }
+}
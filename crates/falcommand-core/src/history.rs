@@ -0,0 +1,522 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Duration, Utc};
+use log::{info, warn};
+
+use falcommand_config::{Action, Category, PathResolver, SearchResult, SyntaxHint};
+use falcommand_config::i18n::{current_language, tr};
+use crate::clock::Clock;
+use crate::search::SearchEngine;
+use crate::source::SearchSource;
+use crate::format::format_relative_time;
+
+#[derive(Debug, thiserror::Error)]
+pub enum HistoryError {
+    #[error("Failed to access search history: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Failed to serialize search history: {0}")]
+    SerializationError(#[from] serde_json::Error),
+
+    #[error("Other history error: {0}")]
+    Other(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub query: String,
+    pub selected_title: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+// Deletion marker: entries for `query` recorded up to `deleted_at` stay deleted, including copies merged in by sync
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryTombstone {
+    pub query: String,
+    pub deleted_at: DateTime<Utc>,
+}
+
+impl HistoryTombstone {
+    pub fn covers(&self, entry: &HistoryEntry) -> bool {
+        entry.timestamp <= self.deleted_at && entry.query.to_lowercase() == self.query.to_lowercase()
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct HistoryState {
+    entries: Vec<HistoryEntry>,
+    #[serde(default)]
+    tombstones: Vec<HistoryTombstone>,
+}
+
+impl HistoryState {
+    fn is_deleted(&self, entry: &HistoryEntry) -> bool {
+        self.tombstones.iter().any(|tombstone| tombstone.covers(entry))
+    }
+
+    // Keeps the latest deletion per query; false when an existing tombstone already covers it
+    fn add_tombstone(&mut self, tombstone: HistoryTombstone) -> bool {
+        let query = tombstone.query.to_lowercase();
+        match self.tombstones.iter_mut().find(|t| t.query.to_lowercase() == query) {
+            Some(existing) if existing.deleted_at >= tombstone.deleted_at => return false,
+            Some(existing) => *existing = tombstone,
+            None => self.tombstones.push(tombstone),
+        }
+
+        if self.tombstones.len() > SearchHistory::MAX_TOMBSTONES {
+            self.tombstones.sort_by_key(|t| t.deleted_at);
+            let overflow = self.tombstones.len() - SearchHistory::MAX_TOMBSTONES;
+            self.tombstones.drain(..overflow);
+        }
+        true
+    }
+
+    // Removes entries covered by a tombstone, returning how many were removed
+    fn drop_deleted(&mut self) -> usize {
+        let HistoryState { entries, tombstones } = self;
+        let before = entries.len();
+        entries.retain(|entry| !tombstones.iter().any(|tombstone| tombstone.covers(entry)));
+        before - entries.len()
+    }
+}
+
+// Stores written before deletions existed hold a bare list of entries
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredHistory {
+    Current(HistoryState),
+    Legacy(Vec<HistoryEntry>),
+}
+
+#[derive(Debug)]
+pub struct SearchHistory {
+    storage_path: PathBuf,
+    clock: Arc<dyn Clock>,
+    state: RwLock<HistoryState>,
+}
+
+impl SearchHistory {
+    pub const MAX_ENTRIES: usize = 1000;
+    pub const MAX_TOMBSTONES: usize = 1000;
+    // Frecency half-life for suggestions: a query used two weeks ago counts half as much
+    const RECENCY_HALF_LIFE_DAYS: f64 = 14.0;
+
+    pub async fn new(resolver: &PathResolver, clock: Arc<dyn Clock>) -> std::result::Result<Self, HistoryError> {
+        let storage_path = resolver.data_file("search_history.json");
+
+        Self::with_storage_path(storage_path, clock).await
+    }
+
+    pub async fn with_storage_path(
+        storage_path: PathBuf,
+        clock: Arc<dyn Clock>,
+    ) -> std::result::Result<Self, HistoryError> {
+        let state = Self::load_state(&storage_path).await;
+        info!("Loaded {} search history entries", state.entries.len());
+
+        Ok(Self {
+            storage_path,
+            clock,
+            state: RwLock::new(state),
+        })
+    }
+
+    async fn load_state(path: &Path) -> HistoryState {
+        if !path.exists() {
+            return HistoryState::default();
+        }
+
+        match tokio::fs::read_to_string(path).await {
+            Ok(content) => match serde_json::from_str(&content) {
+                Ok(StoredHistory::Current(state)) => state,
+                Ok(StoredHistory::Legacy(entries)) => HistoryState { entries, tombstones: Vec::new() },
+                Err(e) => {
+                    warn!("Ignoring unreadable search history {:?}: {}", path, e);
+                    HistoryState::default()
+                }
+            },
+            Err(e) => {
+                warn!("Failed to read search history {:?}: {}", path, e);
+                HistoryState::default()
+            }
+        }
+    }
+
+    async fn persist(&self, state: &HistoryState) -> std::result::Result<(), HistoryError> {
+        if let Some(parent) = self.storage_path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        let content = serde_json::to_string(state)?;
+        let tmp_path = self.storage_path.with_extension("json.tmp");
+        tokio::fs::write(&tmp_path, content).await?;
+        tokio::fs::rename(&tmp_path, &self.storage_path).await?;
+        Ok(())
+    }
+
+    pub async fn record(&self, query: &str, selected_title: &str) -> std::result::Result<(), HistoryError> {
+        let query = query.trim();
+        if query.is_empty() {
+            return Ok(());
+        }
+
+        let mut state = self.state.write().await;
+        state.entries.push(HistoryEntry {
+            query: query.to_string(),
+            selected_title: selected_title.to_string(),
+            timestamp: self.clock.now(),
+        });
+        Self::enforce_capacity(&mut state.entries);
+
+        self.persist(&state).await
+    }
+
+    fn enforce_capacity(entries: &mut Vec<HistoryEntry>) {
+        if entries.len() > Self::MAX_ENTRIES {
+            let overflow = entries.len() - Self::MAX_ENTRIES;
+            entries.drain(..overflow);
+        }
+    }
+
+    pub async fn entries(&self) -> Vec<HistoryEntry> {
+        self.state.read().await.entries.clone()
+    }
+
+    pub async fn tombstones(&self) -> Vec<HistoryTombstone> {
+        self.state.read().await.tombstones.clone()
+    }
+
+    pub async fn clear(&self) -> std::result::Result<(), HistoryError> {
+        let mut state = self.state.write().await;
+        state.entries.clear();
+        self.persist(&state).await
+    }
+
+    // The latest entry of each distinct query, newest first
+    pub async fn recent(&self, limit: usize) -> Vec<HistoryEntry> {
+        let state = self.state.read().await;
+        let mut seen = std::collections::HashSet::new();
+        state.entries.iter()
+            .rev()
+            .filter(|entry| seen.insert(entry.query.to_lowercase()))
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    // Deletes every entry of `query` (case-insensitive) and leaves a tombstone so synced copies are deleted too
+    pub async fn delete_query(&self, query: &str) -> std::result::Result<usize, HistoryError> {
+        let mut state = self.state.write().await;
+        state.add_tombstone(HistoryTombstone {
+            query: query.trim().to_string(),
+            deleted_at: self.clock.now(),
+        });
+        let removed = state.drop_deleted();
+
+        self.persist(&state).await?;
+        info!("Deleted {} search history entries", removed);
+        Ok(removed)
+    }
+
+    // Drops entries older than `retention_days`; 0 keeps everything
+    pub async fn prune(&self, retention_days: u32) -> std::result::Result<usize, HistoryError> {
+        if retention_days == 0 {
+            return Ok(0);
+        }
+
+        let cutoff = self.clock.now() - Duration::days(retention_days as i64);
+        let mut state = self.state.write().await;
+        let before = state.entries.len();
+        state.entries.retain(|entry| entry.timestamp >= cutoff);
+        let removed = before - state.entries.len();
+
+        if removed > 0 {
+            self.persist(&state).await?;
+            info!("Pruned {} search history entries older than {} days", removed, retention_days);
+        }
+        Ok(removed)
+    }
+
+    // Applies deletions made on another machine
+    pub async fn apply_tombstones(&self, tombstones: Vec<HistoryTombstone>) -> std::result::Result<usize, HistoryError> {
+        let mut state = self.state.write().await;
+        let mut changed = false;
+        for tombstone in tombstones {
+            changed |= state.add_tombstone(tombstone);
+        }
+        let removed = state.drop_deleted();
+
+        if changed || removed > 0 {
+            self.persist(&state).await?;
+        }
+        Ok(removed)
+    }
+
+    // Imports synced entries, either replacing the local ones or merged with them; deleted entries never come back
+    pub async fn merge_entries(&self, remote: Vec<HistoryEntry>, replace: bool) -> std::result::Result<(), HistoryError> {
+        let mut state = self.state.write().await;
+        let remote: Vec<HistoryEntry> = remote.into_iter()
+            .filter(|entry| !state.is_deleted(entry))
+            .collect();
+
+        if replace {
+            state.entries = remote;
+        } else {
+            for entry in remote {
+                if !state.entries.contains(&entry) {
+                    state.entries.push(entry);
+                }
+            }
+        }
+        state.entries.sort_by_key(|entry| entry.timestamp);
+        Self::enforce_capacity(&mut state.entries);
+
+        self.persist(&state).await
+    }
+
+    // Past queries extending `prefix`, ranked by recency-weighted frequency
+    pub async fn ranked_completions(&self, prefix: &str) -> Vec<(String, f64)> {
+        let prefix_lower = prefix.to_lowercase();
+        if prefix_lower.is_empty() {
+            return Vec::new();
+        }
+
+        let now = self.clock.now();
+        let state = self.state.read().await;
+        let mut scores: HashMap<String, f64> = HashMap::new();
+
+        for entry in state.entries.iter() {
+            let query_lower = entry.query.to_lowercase();
+            if query_lower.len() <= prefix_lower.len() || !query_lower.starts_with(&prefix_lower) {
+                continue;
+            }
+
+            let age_days = (now - entry.timestamp).num_seconds().max(0) as f64 / 86400.0;
+            let weight = 0.5f64.powf(age_days / Self::RECENCY_HALF_LIFE_DAYS);
+            *scores.entry(entry.query.clone()).or_insert(0.0) += weight;
+        }
+
+        let mut ranked: Vec<(String, f64)> = scores.into_iter().collect();
+        ranked.sort_by(|a, b| {
+            b.1.partial_cmp(&a.1)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.0.cmp(&b.0))
+        });
+        ranked
+    }
+
+    // The text to show after the caret: the typed text is kept verbatim, only the remainder is suggested
+    pub async fn suggest(&self, typed: &str) -> Option<String> {
+        let (best, _) = self.ranked_completions(typed).await.into_iter().next()?;
+        let remainder = best.get(typed.len()..)?;
+        Some(format!("{}{}", typed, remainder))
+    }
+}
+
+// "history [filter]" lists recent queries, each with an action to delete it
+pub struct HistorySource {
+    search_engine: Arc<SearchEngine>,
+}
+
+impl HistorySource {
+    pub const KEYWORD: &'static str = "history";
+    const LIMIT: usize = 20;
+
+    pub fn new(search_engine: Arc<SearchEngine>) -> Self {
+        Self { search_engine }
+    }
+
+    fn entry_result(entry: &HistoryEntry, now: DateTime<Utc>, score: f64) -> SearchResult {
+        let age = format_relative_time(entry.timestamp.into(), now.into(), current_language());
+        SearchResult::new(entry.query.clone(), format!("{} · opened {}", age, entry.selected_title))
+            .with_action(Action::CopyToClipboard(entry.query.clone()))
+            .with_category(Category::SystemCommand)
+            .with_score(score)
+            .with_secondary_action("Delete this entry", Action::DeleteHistoryEntry(entry.query.clone()))
+    }
+}
+
+#[async_trait]
+impl SearchSource for HistorySource {
+    fn name(&self) -> &str {
+        "Search history"
+    }
+
+    fn syntax_hints(&self) -> Vec<SyntaxHint> {
+        vec![SyntaxHint::new(
+            "history [filter]",
+            tr("hint.history", "List recent searches; each can be deleted"),
+            "history",
+        )]
+    }
+
+    async fn search(&self, query: &str) -> Vec<SearchResult> {
+        let query = query.trim();
+        let Some(rest) = query.get(..Self::KEYWORD.len())
+            .filter(|keyword| keyword.eq_ignore_ascii_case(Self::KEYWORD))
+            .map(|_| &query[Self::KEYWORD.len()..])
+        else {
+            return Vec::new();
+        };
+        if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+            return Vec::new();
+        }
+
+        let filter = rest.trim().to_lowercase();
+        let history = self.search_engine.history().await;
+        let now = history.clock.now();
+        history.recent(SearchHistory::MAX_ENTRIES).await
+            .iter()
+            .filter(|entry| entry.query.to_lowercase().contains(&filter))
+            .take(Self::LIMIT)
+            .enumerate()
+            .map(|(rank, entry)| Self::entry_result(entry, now, 1.0 - rank as f64 * 0.01))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::manual_clock;
+
+    async fn history(dir: &tempfile::TempDir) -> (SearchHistory, Arc<crate::clock::ManualClock>) {
+        let clock = manual_clock();
+        let history = SearchHistory::with_storage_path(dir.path().join("history.json"), clock.clone()).await.unwrap();
+        (history, clock)
+    }
+
+    #[tokio::test]
+    async fn frequent_recent_queries_are_suggested_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let (history, clock) = history(&dir).await;
+        for _ in 0..3 {
+            history.record("firefox", "Firefox").await.unwrap();
+        }
+        history.record("finder", "Finder").await.unwrap();
+        assert_eq!(history.suggest("fi").await.as_deref(), Some("firefox"));
+
+        // Four weeks on, three old uses weigh less than one fresh one
+        clock.advance(Duration::days(28));
+        history.record("finder", "Finder").await.unwrap();
+        let ranked = history.ranked_completions("fi").await;
+        assert_eq!(ranked[0].0, "finder");
+        assert!((ranked[1].1 - 0.75).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn suggestions_keep_the_typed_text() {
+        let dir = tempfile::tempdir().unwrap();
+        let (history, _) = history(&dir).await;
+        history.record("firefox", "Firefox").await.unwrap();
+
+        assert_eq!(history.suggest("FIre").await.as_deref(), Some("FIrefox"));
+        // Nothing longer starts with it, so there is nothing to add
+        assert_eq!(history.suggest("firefox").await, None);
+        assert_eq!(history.suggest("chrome").await, None);
+        assert_eq!(history.suggest("").await, None);
+    }
+
+    fn queries(entries: &[HistoryEntry]) -> Vec<&str> {
+        entries.iter().map(|entry| entry.query.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn pruning_drops_what_is_older_than_the_retention() {
+        let dir = tempfile::tempdir().unwrap();
+        let (history, clock) = history(&dir).await;
+        history.record("old", "Old").await.unwrap();
+        clock.advance(Duration::days(30));
+        history.record("recent", "Recent").await.unwrap();
+        clock.advance(Duration::days(65));
+        history.record("today", "Today").await.unwrap();
+
+        // 0 keeps everything
+        assert_eq!(history.prune(0).await.unwrap(), 0);
+        assert_eq!(history.prune(90).await.unwrap(), 1);
+        assert_eq!(queries(&history.entries().await), ["recent", "today"]);
+        assert_eq!(history.prune(90).await.unwrap(), 0);
+
+        // The pruned history is what the next start loads
+        drop(history);
+        let reloaded = SearchHistory::with_storage_path(dir.path().join("history.json"), clock).await.unwrap();
+        assert_eq!(queries(&reloaded.entries().await), ["recent", "today"]);
+    }
+
+    #[tokio::test]
+    async fn deleting_a_query_removes_all_of_its_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let (history, clock) = history(&dir).await;
+        history.record("firefox", "Firefox").await.unwrap();
+        history.record("Firefox", "Firefox").await.unwrap();
+        history.record("finder", "Finder").await.unwrap();
+
+        assert_eq!(history.delete_query(" FIREFOX ").await.unwrap(), 2);
+        assert_eq!(queries(&history.entries().await), ["finder"]);
+        assert_eq!(history.suggest("fi").await.as_deref(), Some("finder"));
+        assert_eq!(history.tombstones().await.len(), 1);
+
+        // Searching it again afterwards is recorded as usual
+        clock.advance(Duration::seconds(1));
+        history.record("firefox", "Firefox").await.unwrap();
+        assert_eq!(queries(&history.entries().await), ["finder", "firefox"]);
+    }
+
+    #[tokio::test]
+    async fn synced_tombstones_delete_and_keep_entries_deleted() {
+        let dir = tempfile::tempdir().unwrap();
+        let clock = manual_clock();
+        let local = SearchHistory::with_storage_path(dir.path().join("local.json"), clock.clone()).await.unwrap();
+        let remote = SearchHistory::with_storage_path(dir.path().join("remote.json"), clock.clone()).await.unwrap();
+        for history in [&local, &remote] {
+            history.record("secret project", "Notes").await.unwrap();
+            history.record("firefox", "Firefox").await.unwrap();
+        }
+        let remote_before_deletion = remote.entries().await;
+
+        clock.advance(Duration::minutes(1));
+        local.delete_query("secret project").await.unwrap();
+        assert_eq!(remote.apply_tombstones(local.tombstones().await).await.unwrap(), 1);
+        assert_eq!(queries(&remote.entries().await), ["firefox"]);
+        // Applying them again changes nothing
+        assert_eq!(remote.apply_tombstones(local.tombstones().await).await.unwrap(), 0);
+
+        // Old copies coming back through a merge or a replace stay deleted
+        local.merge_entries(remote_before_deletion.clone(), false).await.unwrap();
+        assert_eq!(queries(&local.entries().await), ["firefox"]);
+        local.merge_entries(remote_before_deletion, true).await.unwrap();
+        assert_eq!(queries(&local.entries().await), ["firefox"]);
+    }
+
+    #[tokio::test]
+    async fn only_the_latest_tombstone_per_query_is_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        let (history, clock) = history(&dir).await;
+        history.delete_query("firefox").await.unwrap();
+        let first = history.tombstones().await;
+        clock.advance(Duration::days(1));
+        history.delete_query("Firefox").await.unwrap();
+        let latest = history.tombstones().await;
+
+        assert_eq!(latest.len(), 1);
+        assert!(latest[0].deleted_at > first[0].deleted_at);
+        // An older deletion from another machine doesn't replace it
+        history.apply_tombstones(first).await.unwrap();
+        assert_eq!(history.tombstones().await, latest);
+    }
+
+    #[tokio::test]
+    async fn legacy_entry_lists_still_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let entries = vec![HistoryEntry { query: "firefox".to_string(), selected_title: "Firefox".to_string(), timestamp: Utc::now() }];
+        std::fs::write(dir.path().join("history.json"), serde_json::to_string(&entries).unwrap()).unwrap();
+
+        let (history, _) = history(&dir).await;
+        assert_eq!(history.entries().await, entries);
+        assert!(history.tombstones().await.is_empty());
+    }
+}
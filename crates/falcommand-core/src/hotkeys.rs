@@ -0,0 +1,38 @@
+use std::collections::HashMap;
+
+use falcommand_config::TOGGLE_INTENT;
+
+// What a hotkey binding does when pressed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HotkeyIntent {
+    Toggle,
+    // Open the window with the query pre-filled, e.g. "timer " for the timer plugin
+    Prefill(String),
+}
+
+// Non-toggle intents are plugin ids; `trigger_prefixes` maps plugin id to that plugin's prefix.
+// None when no loaded plugin provides a prefix for the intent
+pub fn resolve_intent(intent: &str, trigger_prefixes: &HashMap<String, String>) -> Option<HotkeyIntent> {
+    if intent.eq_ignore_ascii_case(TOGGLE_INTENT) {
+        return Some(HotkeyIntent::Toggle);
+    }
+    trigger_prefixes.get(&intent.to_lowercase())
+        .map(|prefix| HotkeyIntent::Prefill(prefix.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intents_map_to_toggle_or_the_plugin_prefix() {
+        let prefixes = HashMap::from([("timer".to_string(), "timer ".to_string())]);
+
+        assert_eq!(resolve_intent("toggle", &prefixes), Some(HotkeyIntent::Toggle));
+        assert_eq!(resolve_intent("Toggle", &HashMap::new()), Some(HotkeyIntent::Toggle));
+        assert_eq!(resolve_intent("timer", &prefixes), Some(HotkeyIntent::Prefill("timer ".to_string())));
+        assert_eq!(resolve_intent("Timer", &prefixes), Some(HotkeyIntent::Prefill("timer ".to_string())));
+        // Plugins that aren't loaded, or have no prefix, have nothing to open
+        assert_eq!(resolve_intent("emoji", &prefixes), None);
+    }
+}
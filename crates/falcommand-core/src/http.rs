@@ -0,0 +1,62 @@
+use std::time::Duration;
+use log::debug;
+
+#[derive(Debug, thiserror::Error)]
+pub enum HttpError {
+    #[error("HTTP request to {url} failed: {message}")]
+    Request { url: String, message: String },
+
+    #[error("HTTP {status} from {url}")]
+    Status { url: String, status: u16 },
+}
+
+// The one HTTPS client of the app: plugin lookups go through it and crash reports are uploaded with it.
+// Requests time out instead of hanging, and non-2xx answers are errors rather than bodies
+#[derive(Debug, Clone)]
+pub struct WebClient {
+    client: reqwest::Client,
+}
+
+impl WebClient {
+    pub const TIMEOUT: Duration = Duration::from_secs(10);
+    const USER_AGENT: &'static str = concat!("falcommand/", env!("CARGO_PKG_VERSION"));
+
+    pub fn new() -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Self::TIMEOUT)
+            .user_agent(Self::USER_AGENT)
+            .build()
+            .unwrap_or_default();
+        Self { client }
+    }
+
+    pub async fn get(&self, url: &str) -> Result<String, HttpError> {
+        debug!("GET {}", url);
+        self.send(url, self.client.get(url)).await
+    }
+
+    // Bodies are JSON everywhere this is used
+    pub async fn post_json(&self, url: &str, body: &str) -> Result<String, HttpError> {
+        debug!("POST {}", url);
+        let request = self.client.post(url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body.to_string());
+        self.send(url, request).await
+    }
+
+    async fn send(&self, url: &str, request: reqwest::RequestBuilder) -> Result<String, HttpError> {
+        let failed = |e: reqwest::Error| HttpError::Request { url: url.to_string(), message: e.to_string() };
+        let response = request.send().await.map_err(failed)?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(HttpError::Status { url: url.to_string(), status: status.as_u16() });
+        }
+        response.text().await.map_err(failed)
+    }
+}
+
+impl Default for WebClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,13 +1,32 @@
 use std::collections::{HashMap, BTreeMap};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::SystemTime;
-use tokio::sync::RwLock;
+use tokio::sync::{watch, RwLock};
 use serde::{Deserialize, Serialize};
 use log::{info, warn, error, debug};
 
-use falcommand_config::{Config, SearchResult, Action, Category};
-use falcommand_platform::{PlatformProvider, AppInfo};
+use falcommand_config::{Config, SearchResult, Action, Category, MatchReason, ResultId};
+use falcommand_config::launch;
+use falcommand_config::i18n::{current_language, Language};
+use falcommand_platform::{PlatformProvider, AppInfo, ScanReport, ScanWarning};
+use crate::sensitive::SensitivePaths;
+use crate::format::{format_datetime, format_size};
+use crate::app_names::{group_app_versions, normalize_app_name};
+use crate::exclude::{app_excluded, path_excluded};
+use crate::archives::{list_archive, ArchiveEntry, ArchiveKind, ArchiveListing};
+use crate::moves::relocated_path;
+use crate::scan_backoff::{parent_reachable, ScanHistory};
+use crate::extraction::ExtractionWorker;
+use crate::arguments::is_app_name_prefix;
+use crate::open_with::open_with_query;
+use crate::scope::folder_scope_query;
+use crate::app_watch::launch_target_missing;
+use crate::index_check::{
+    drift_verdict, include_paths_hash, read_cache_file, sample_indices, write_cache_file, CacheHeader, CacheProblem,
+    CacheVerification,
+};
 
 #[derive(Debug, thiserror::Error)]
 pub enum IndexError {
@@ -27,6 +46,16 @@ pub enum IndexError {
     Other(String),
 }
 
+pub(crate) fn current_os() -> &'static str {
+    if cfg!(target_os = "windows") {
+        "windows"
+    } else if cfg!(target_os = "macos") {
+        "macos"
+    } else {
+        "linux"
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileInfo {
     pub name: String,
@@ -35,6 +64,9 @@ pub struct FileInfo {
     pub size: u64,
     pub modified: SystemTime,
     pub keywords: Vec<String>,
+    // Set for files listed inside an archive, whose `path` is the archive itself
+    #[serde(default)]
+    pub archive_member: Option<String>,
 }
 
 impl FileInfo {
@@ -56,24 +88,187 @@ impl FileInfo {
             size: metadata.len(),
             modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
             keywords: Vec::new(),
+            archive_member: None,
         })
     }
     
+    // An entry of `archive`; it shares the archive's modification time
+    pub fn in_archive(archive: &FileInfo, entry: &ArchiveEntry) -> Self {
+        let name = entry.file_name().to_string();
+        let extension = Path::new(&name).extension()
+            .and_then(|e| e.to_str())
+            .map(|s| s.to_string());
+        
+        Self {
+            name,
+            path: archive.path.clone(),
+            extension,
+            size: entry.size,
+            modified: archive.modified,
+            keywords: Vec::new(),
+            archive_member: Some(entry.path.clone()),
+        }
+    }
+    
+    // Points the entry at its new location after `from` was renamed or moved to `to`
+    fn relocate(&mut self, from: &Path, to: &Path) {
+        let Some(path) = relocated_path(&self.path, from, to) else {
+            return;
+        };
+        self.path = path;
+        if self.archive_member.is_none() {
+            self.name = self.path.file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or("")
+                .to_string();
+            self.extension = self.path.extension()
+                .and_then(|e| e.to_str())
+                .map(|s| s.to_string());
+        }
+    }
+    
+    // Archive entries get the archive path and member appended so they never replace a plain file
+    fn index_key(&self) -> String {
+        let name = self.name.to_lowercase();
+        match self.archive_member {
+            Some(ref member) => format!("{}{}{}{}{}", name, ARCHIVE_KEY_SEPARATOR, self.path.display(), ARCHIVE_KEY_SEPARATOR, member),
+            None => name,
+        }
+    }
+    
+    // Windows executables and scripts (.bat, .cmd, .ps1) are launched rather than opened
+    pub fn is_executable(&self) -> bool {
+        cfg!(target_os = "windows")
+            && self.archive_member.is_none()
+            && launch::is_windows_executable(&self.path, &launch::pathext_list(std::env::var("PATHEXT").ok().as_deref()))
+    }
+    
     pub fn to_search_result(&self) -> SearchResult {
-        SearchResult::new(&self.name, &format!("File: {}", self.path.display()))
-            .with_action(Action::OpenFile(self.path.clone()))
+        if let Some(ref member) = self.archive_member {
+            return self.archive_member_result(member);
+        }
+        
+        // Canonical paths keep their \\?\ prefix for launching but not for display
+        let display = launch::display_path(&self.path);
+        let (description, action) = if self.is_executable() {
+            (format!("Executable: {}", display), Action::ExecuteApplication { path: self.path.clone(), args: Vec::new() })
+        } else {
+            (format!("File: {}", display), Action::OpenFile(self.path.clone()))
+        };
+        let description = format!("{} · {}", description, self.size_and_date(current_language()));
+        
+        let mut result = SearchResult::new(&self.name, &description)
+            .with_action(action)
             .with_category(Category::File)
             .with_path(self.path.clone())
             .with_score(0.5)
+            .with_open_later_actions();
+        
+        result = result.with_secondary_action("Open with…", Action::SetQuery(open_with_query(&self.path)));
+        if let Some(parent) = self.path.parent() {
+            result = result.with_secondary_action("Search in this folder", Action::SetQuery(folder_scope_query(parent)));
+        }
+        if let Some(parent) = self.path.parent() {
+            result = result.with_secondary_action(
+                "Exclude this folder from indexing",
+                Action::ExcludeFromIndex(parent.to_path_buf()),
+            );
+        }
+        result.with_destructive_action("Move to trash", Action::TrashFile(self.path.clone()))
+    }
+    
+    // "1.5 MB · Jun 1, 2024 14:05" in the local time zone
+    fn size_and_date(&self, language: Language) -> String {
+        let offset = *chrono::Local::now().offset();
+        format!(
+            "{} · {}",
+            format_size(self.size, language),
+            format_datetime(self.modified.into(), offset, language),
+        )
     }
+    
+    fn archive_member_result(&self, member: &str) -> SearchResult {
+        let archive_name = self.path.file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| launch::display_path(&self.path));
+        
+        SearchResult::new(&self.name, format!("{} inside {}", member, archive_name))
+            .with_action(Action::OpenArchiveMember {
+                archive: self.path.clone(),
+                member: member.to_string(),
+            })
+            .with_category(Category::File)
+            .with_path(self.path.clone())
+            .with_score(0.5)
+            .with_secondary_action("Open archive", Action::OpenFile(self.path.clone()))
+    }
+}
+
+// Cannot appear in a file name, so the part before it is always the lowercase name
+const ARCHIVE_KEY_SEPARATOR: char = '\u{0}';
+
+fn key_name(key: &str) -> &str {
+    key.split(ARCHIVE_KEY_SEPARATOR).next().unwrap_or(key)
+}
+
+// Archive handling during one file index rebuild
+struct ArchiveScan {
+    size_limit: u64,
+    // search.isolated_extraction: list archives in the worker process
+    isolated: bool,
+    // Listings from the previous rebuild, reused while the archive's mtime is unchanged
+    previous: HashMap<PathBuf, ArchiveListing>,
+    // Every archive visited by this rebuild, so none is read or indexed twice
+    listings: HashMap<PathBuf, ArchiveListing>,
+}
+
+// Snapshot of both indexes so a short-lived process can search without rebuilding
+#[derive(Debug, Serialize, Deserialize)]
+struct IndexCache {
+    apps: HashMap<String, AppInfo>,
+    files: BTreeMap<String, FileInfo>,
+    #[serde(default)]
+    archives: HashMap<PathBuf, ArchiveListing>,
+    #[serde(default)]
+    scans: ScanHistory,
+    built_at: SystemTime,
+}
+
+// Deterministic order within one index: score, then title, then id
+fn by_score_then_title(a: &SearchResult, b: &SearchResult) -> std::cmp::Ordering {
+    b.score.total_cmp(&a.score)
+        .then_with(|| a.title.cmp(&b.title))
+        .then_with(|| a.id.cmp(&b.id))
 }
 
 #[derive(Debug)]
 pub struct IndexManager {
     config: Arc<RwLock<Config>>,
     app_index: RwLock<HashMap<String, AppInfo>>,
+    excluded_apps: RwLock<usize>,
+    // Per-source outcome of the last application scan
+    app_scan_report: RwLock<ScanReport>,
     file_index: RwLock<BTreeMap<String, FileInfo>>,
+    archive_listings: RwLock<HashMap<PathBuf, ArchiveListing>>,
+    // Lists archives out of process when search.isolated_extraction is on
+    extractor: Option<Arc<ExtractionWorker>>,
+    // Include paths that failed to scan back off instead of being retried on every rebuild
+    scan_history: RwLock<ScanHistory>,
     last_rebuild: RwLock<Option<SystemTime>>,
+    progress: watch::Sender<IndexProgress>,
+    cache_path: Option<PathBuf>,
+    // How the cache checked out when it was last loaded
+    cache_verification: RwLock<Option<CacheVerification>>,
+    // Set when the loaded cache drifted from disk; the next rebuild clears it
+    marked_stale: RwLock<bool>,
+    // Bumped whenever indexed entries are added, removed or moved
+    generation: AtomicU64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IndexProgress {
+    pub in_progress: bool,
+    pub items_indexed: usize,
 }
 
 impl IndexManager {
@@ -83,14 +278,169 @@ impl IndexManager {
         Ok(Self {
             config,
             app_index: RwLock::new(HashMap::new()),
+            excluded_apps: RwLock::new(0),
+            app_scan_report: RwLock::new(ScanReport::default()),
             file_index: RwLock::new(BTreeMap::new()),
+            archive_listings: RwLock::new(HashMap::new()),
+            extractor: None,
+            scan_history: RwLock::new(ScanHistory::default()),
             last_rebuild: RwLock::new(None),
+            progress: watch::channel(IndexProgress::default()).0,
+            cache_path: None,
+            cache_verification: RwLock::new(None),
+            marked_stale: RwLock::new(false),
+            generation: AtomicU64::new(0),
+        })
+    }
+    
+    // Rebuilds are written here and load_cache reads them back
+    pub fn with_cache_path(mut self, cache_path: PathBuf) -> Self {
+        self.cache_path = Some(cache_path);
+        self
+    }
+    
+    pub fn with_extractor(mut self, extractor: Arc<ExtractionWorker>) -> Self {
+        self.extractor = Some(extractor);
+        self
+    }
+    
+    // Changes whenever the indexed entries do, so results computed earlier can be told apart
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+    
+    fn entries_changed(&self) {
+        self.generation.fetch_add(1, Ordering::AcqRel);
+    }
+    
+    // None when there is no cache. A cache that fails verification is discarded and the index stays
+    // empty; one that drifted from disk is loaded, marked stale and should be rebuilt right away
+    pub async fn load_cache(&self) -> Option<CacheVerification> {
+        let path = self.cache_path.as_ref()?;
+        let content = match tokio::fs::read_to_string(path).await {
+            Ok(content) => content,
+            Err(e) => {
+                debug!("No index cache at {}: {}", path.display(), e);
+                return None;
+            }
+        };
+        
+        let verification = self.verify_and_load(&content).await;
+        match verification {
+            CacheVerification::Passed { .. } => info!("Index cache {}", verification.describe()),
+            _ => warn!("Index cache {}: {}", path.display(), verification.describe()),
+        }
+        *self.cache_verification.write().await = Some(verification.clone());
+        Some(verification)
+    }
+    
+    async fn verify_and_load(&self, content: &str) -> CacheVerification {
+        let (header, payload) = match read_cache_file(content) {
+            Ok(parts) => parts,
+            Err(problem) => return CacheVerification::Rejected(problem),
+        };
+        if header.include_paths_hash != self.include_paths_hash().await {
+            return CacheVerification::Rejected(CacheProblem::IncludePathsChanged);
+        }
+        let cache: IndexCache = match serde_json::from_str(payload) {
+            Ok(cache) => cache,
+            Err(e) => return CacheVerification::Rejected(CacheProblem::Unreadable(e.to_string())),
+        };
+        
+        let sample_size = self.config.read().await.search.cache_sample_size;
+        let (sampled, missing) = Self::sample_missing(&cache, sample_size).await;
+        let verification = drift_verdict(sampled, missing);
+        
+        info!("Loaded index cache with {} apps and {} files", cache.apps.len(), cache.files.len());
+        *self.app_index.write().await = cache.apps;
+        *self.file_index.write().await = cache.files;
+        *self.archive_listings.write().await = cache.archives;
+        *self.scan_history.write().await = cache.scans;
+        *self.last_rebuild.write().await = Some(cache.built_at);
+        *self.marked_stale.write().await = verification.needs_rebuild();
+        self.entries_changed();
+        verification
+    }
+    
+    // Checks a random sample of the cached apps and files for existence: (sampled, missing)
+    async fn sample_missing(cache: &IndexCache, sample_size: usize) -> (usize, usize) {
+        let paths: Vec<&Path> = cache.apps.values()
+            .map(|app| app.executable_path.as_path())
+            .chain(cache.files.values().map(|file_info| file_info.path.as_path()))
+            .collect();
+        let seed = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_nanos() as u64)
+            .unwrap_or_default();
+        let sample: Vec<PathBuf> = sample_indices(paths.len(), sample_size, seed).into_iter()
+            .map(|index| paths[index].to_path_buf())
+            .collect();
+        
+        let sampled = sample.len();
+        let missing = tokio::task::spawn_blocking(move || {
+            sample.iter().filter(|path| launch_target_missing(path)).count()
         })
+        .await
+        .unwrap_or_default();
+        (sampled, missing)
+    }
+    
+    async fn include_paths_hash(&self) -> u64 {
+        let exclude_patterns = self.config.read().await.search.exclude_patterns.clone();
+        include_paths_hash(&self.include_paths().await, &exclude_patterns)
+    }
+    
+    // Reads the cache without replacing the index: None without one, else its app and file counts
+    // or why it can't be used
+    pub async fn inspect_cache(&self) -> Option<std::result::Result<(usize, usize), String>> {
+        let path = self.cache_path.as_ref()?;
+        let content = match tokio::fs::read_to_string(path).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return None,
+            Err(e) => return Some(Err(e.to_string())),
+        };
+        let (header, payload) = match read_cache_file(&content) {
+            Ok(parts) => parts,
+            Err(problem) => return Some(Err(problem.to_string())),
+        };
+        if header.include_paths_hash != self.include_paths_hash().await {
+            return Some(Err(CacheProblem::IncludePathsChanged.to_string()));
+        }
+        Some(serde_json::from_str::<IndexCache>(payload)
+            .map(|cache| (cache.apps.len(), cache.files.len()))
+            .map_err(|e| e.to_string()))
+    }
+    
+    async fn save_cache(&self, built_at: SystemTime) -> std::result::Result<(), IndexError> {
+        let Some(ref path) = self.cache_path else {
+            return Ok(());
+        };
+        let cache = IndexCache {
+            apps: self.app_index.read().await.clone(),
+            files: self.file_index.read().await.clone(),
+            archives: self.archive_listings.read().await.clone(),
+            scans: self.scan_history.read().await.clone(),
+            built_at,
+        };
+        let payload = serde_json::to_string(&cache).map_err(|e| IndexError::Other(e.to_string()))?;
+        let header = CacheHeader::new(self.include_paths_hash().await, &payload);
+        let content = write_cache_file(&header, &payload).map_err(|e| IndexError::Other(e.to_string()))?;
+        
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let tmp_path = path.with_extension("json.tmp");
+        tokio::fs::write(&tmp_path, content).await?;
+        tokio::fs::rename(&tmp_path, path).await?;
+        Ok(())
     }
     
     pub async fn rebuild_index(&self, platform_provider: Arc<dyn PlatformProvider>) -> std::result::Result<(), IndexError> {
         info!("Starting index rebuild...");
         let start_time = SystemTime::now();
+        self.progress.send_replace(IndexProgress {
+            in_progress: true,
+            items_indexed: 0,
+        });
         
         // Rebuild in parallel
         let (app_result, file_result) = tokio::join!(
@@ -108,6 +458,17 @@ impl IndexManager {
         
         // Update last rebuild time
         *self.last_rebuild.write().await = Some(start_time);
+        *self.marked_stale.write().await = false;
+        
+        if let Err(e) = self.save_cache(start_time).await {
+            warn!("Failed to save index cache: {}", e);
+        }
+        
+        let stats = self.get_index_stats().await;
+        self.progress.send_replace(IndexProgress {
+            in_progress: false,
+            items_indexed: stats.app_count + stats.file_count,
+        });
         
         if let Ok(elapsed) = start_time.elapsed() {
             info!("Index rebuild completed in {:?}", elapsed);
@@ -116,21 +477,86 @@ impl IndexManager {
         Ok(())
     }
     
+    // `index rebuild --force`: include paths in backoff are scanned again right away
+    pub async fn force_rebuild_index(&self, platform_provider: Arc<dyn PlatformProvider>) -> std::result::Result<(), IndexError> {
+        self.scan_history.write().await.clear();
+        self.rebuild_index(platform_provider).await
+    }
+    
+    // Rescans applications only, e.g. after one was installed. The file index and the time of the
+    // last full rebuild are left alone, so the daily rebuild still comes when due
+    pub async fn refresh_applications(&self, platform_provider: Arc<dyn PlatformProvider>) -> std::result::Result<usize, IndexError> {
+        self.rebuild_app_index(platform_provider).await?;
+        
+        let built_at = self.last_rebuild.read().await.unwrap_or(SystemTime::UNIX_EPOCH);
+        if let Err(e) = self.save_cache(built_at).await {
+            warn!("Failed to save index cache: {}", e);
+        }
+        
+        let stats = self.get_index_stats().await;
+        self.progress.send_replace(IndexProgress {
+            in_progress: false,
+            items_indexed: stats.app_count + stats.file_count,
+        });
+        Ok(stats.app_count)
+    }
+    
     async fn rebuild_app_index(&self, platform_provider: Arc<dyn PlatformProvider>) -> std::result::Result<(), IndexError> {
         info!("Rebuilding application index...");
         
-        let apps = platform_provider.get_installed_applications().await
-            .map_err(|e| IndexError::PlatformError(e.to_string()))?;
+        let apps = platform_provider.get_installed_applications().await;
+        // Sources that failed are reported, the apps of the others are still indexed
+        let report = platform_provider.last_scan_report();
+        for warning in &report.warnings {
+            warn!("{}", warning);
+        }
+        *self.app_scan_report.write().await = report;
+        let apps = apps.map_err(|e| IndexError::PlatformError(e.to_string()))?;
+        
+        let (ungrouped, exclude_patterns) = {
+            let config = self.config.read().await;
+            (config.search.ungrouped_apps.clone(), config.search.app_exclude_patterns.clone())
+        };
+        
+        let before = apps.len();
+        let apps: Vec<AppInfo> = apps.into_iter()
+            .filter(|app| match app_excluded(app, &exclude_patterns) {
+                Some(reason) => {
+                    debug!("Excluding app {} ({:?} match)", app.name, reason);
+                    false
+                }
+                None => true,
+            })
+            .collect();
+        let excluded = before - apps.len();
+        *self.excluded_apps.write().await = excluded;
         
         let mut app_index = self.app_index.write().await;
-        app_index.clear();
+        // Launch counts live in the index, so apps found again keep theirs
+        let mut usage: HashMap<PathBuf, (u32, Option<SystemTime>)> = HashMap::new();
+        for (_, app) in app_index.drain() {
+            for app in std::iter::once(&app).chain(&app.other_versions) {
+                usage.insert(app.executable_path.clone(), (app.usage_count, app.last_used));
+            }
+        }
+        let restore_usage = |app: &mut AppInfo| {
+            if let Some(&(usage_count, last_used)) = usage.get(&app.executable_path) {
+                app.usage_count = usage_count;
+                app.last_used = last_used;
+            }
+        };
         
-        for app in apps {
-            let key = app.name.to_lowercase();
+        // Keyed by canonical name so "python" matches every Python version exactly
+        for (key, mut app) in group_app_versions(apps, &ungrouped) {
+            restore_usage(&mut app);
+            app.other_versions.iter_mut().for_each(restore_usage);
             app_index.insert(key, app);
         }
         
-        info!("Application index rebuilt with {} entries", app_index.len());
+        info!("Application index rebuilt with {} entries, {} excluded", app_index.len(), excluded);
+        self.entries_changed();
+        let app_count = app_index.len();
+        self.progress.send_modify(|p| p.items_indexed += app_count);
         Ok(())
     }
     
@@ -141,25 +567,53 @@ impl IndexManager {
         let mut file_index = self.file_index.write().await;
         file_index.clear();
         
-        // Get platform-specific include paths
-        let current_os = if cfg!(target_os = "windows") {
-            "windows"
-        } else if cfg!(target_os = "macos") {
-            "macos"
-        } else {
-            "linux"
-        };
+        let sensitive = SensitivePaths::for_current_user(&config.search.allow_sensitive_paths);
+        let mut archives = config.search.index_archive_contents.then(|| ArchiveScan {
+            size_limit: config.search.archive_size_limit_mb * 1024 * 1024,
+            isolated: config.search.isolated_extraction,
+            previous: HashMap::new(),
+            listings: HashMap::new(),
+        });
+        if let Some(ref mut archives) = archives {
+            archives.previous = std::mem::take(&mut *self.archive_listings.write().await);
+        }
         
-        if let Some(paths) = config.search.include_paths.get(current_os) {
-            for path_str in paths {
-                let path = PathBuf::from(path_str);
-                if let Err(e) = self.scan_directory(&path, &mut file_index, &config.search.exclude_patterns).await {
-                    warn!("Failed to scan directory {}: {}", path.display(), e);
+        // Get platform-specific include paths
+        let paths: Vec<PathBuf> = config.search.include_paths.get(current_os())
+            .map(|paths| paths.iter().map(PathBuf::from).collect())
+            .unwrap_or_default();
+        let mut scans = self.scan_history.write().await;
+        scans.retain_paths(&paths);
+        for path in paths {
+            let now = SystemTime::now();
+            if scans.should_skip(&path, parent_reachable(&path), now) {
+                debug!("Skipping {} until its scan backoff expires", path.display());
+                continue;
+            }
+            
+            let before = file_index.len();
+            match self.scan_directory(&path, &mut file_index, &config.search.exclude_patterns, &sensitive, archives.as_mut()).await {
+                Ok(()) => scans.record_success(&path, now),
+                Err(e) => {
+                    // Only the first failure in a row is worth a warning; later ones are backed off anyway
+                    let failures = scans.record_failure(&path, e.to_string(), parent_reachable(&path), now);
+                    if failures == 1 {
+                        warn!("Failed to scan directory {}: {}", path.display(), e);
+                    } else {
+                        debug!("Failed to scan directory {} ({} times in a row): {}", path.display(), failures, e);
+                    }
                 }
             }
+            let added = file_index.len() - before;
+            self.progress.send_modify(|p| p.items_indexed += added);
         }
+        drop(scans);
+        
+        // Archives that are gone, or no longer indexed, drop out of the listing cache
+        *self.archive_listings.write().await = archives.map(|archives| archives.listings).unwrap_or_default();
         
         info!("File index rebuilt with {} entries", file_index.len());
+        self.entries_changed();
         Ok(())
     }
     
@@ -168,19 +622,22 @@ impl IndexManager {
         dir: &Path,
         file_index: &mut BTreeMap<String, FileInfo>,
         exclude_patterns: &[String],
+        sensitive: &SensitivePaths,
+        mut archives: Option<&mut ArchiveScan>,
     ) -> std::result::Result<(), IndexError> {
+        // An offline share or a removed folder; counted as a failure so it backs off
         if !dir.exists() {
-            debug!("Directory does not exist: {}", dir.display());
+            return Err(IndexError::BuildError(format!("{} does not exist or is not reachable", dir.display())));
+        }
+        
+        // Deny list entries are canonical, so the scanned directory is too
+        let dir = tokio::fs::canonicalize(dir).await.unwrap_or_else(|_| dir.to_path_buf());
+        if sensitive.is_denied(&dir) {
+            info!("Skipping sensitive directory: {}", dir.display());
             return Ok(());
         }
         
-        let mut entries = match tokio::fs::read_dir(dir).await {
-            Ok(entries) => entries,
-            Err(e) => {
-                warn!("Cannot read directory {}: {}", dir.display(), e);
-                return Ok(());
-            }
-        };
+        let mut entries = tokio::fs::read_dir(&dir).await?;
         
         while let Some(entry) = entries.next_entry().await? {
             let path = entry.path();
@@ -190,10 +647,19 @@ impl IndexManager {
                 continue;
             }
             
+            // Symlinks aren't indexed, so entries of a canonical directory are canonical too
+            if sensitive.is_denied(&path) {
+                continue;
+            }
+            
             if entry.file_type().await?.is_file() {
                 if let Ok(file_info) = FileInfo::new(path.clone()) {
-                    let key = file_info.name.to_lowercase();
-                    file_index.insert(key, file_info);
+                    if let Some(ref mut archives) = archives {
+                        for member in self.archive_members(&file_info, archives).await {
+                            file_index.insert(member.index_key(), member);
+                        }
+                    }
+                    file_index.insert(file_info.index_key(), file_info);
                 }
             }
         }
@@ -201,72 +667,134 @@ impl IndexManager {
         Ok(())
     }
     
-    fn should_exclude(&self, path: &Path, exclude_patterns: &[String]) -> bool {
-        let path_str = path.to_string_lossy();
-        
-        for pattern in exclude_patterns {
-            // Simple glob-like matching
-            if pattern.contains('*') {
-                let pattern_parts: Vec<&str> = pattern.split('*').collect();
-                if pattern_parts.len() == 2 {
-                    let prefix = pattern_parts[0];
-                    let suffix = pattern_parts[1];
-                    
-                    if path_str.starts_with(prefix) && path_str.ends_with(suffix) {
-                        return true;
+    // Entries of `file_info` when it is an archive within the size limit; empty for unreadable archives
+    async fn archive_members(&self, file_info: &FileInfo, archives: &mut ArchiveScan) -> Vec<FileInfo> {
+        if ArchiveKind::from_path(&file_info.path).is_none() || file_info.size > archives.size_limit {
+            return Vec::new();
+        }
+        if archives.listings.contains_key(&file_info.path) {
+            return Vec::new();
+        }
+        
+        let listing = match archives.previous.remove(&file_info.path) {
+            Some(listing) if listing.modified == file_info.modified => listing,
+            _ => {
+                let extractor = self.extractor.as_deref().filter(|_| archives.isolated);
+                let entries = match extractor {
+                    Some(extractor) => match extractor.list_archive(&file_info.path).await {
+                        Ok(entries) => Some(entries),
+                        Err(e) => {
+                            warn!("Skipping archive {}: {}", file_info.path.display(), e);
+                            None
+                        }
+                    },
+                    None => {
+                        let path = file_info.path.clone();
+                        match tokio::task::spawn_blocking(move || list_archive(&path)).await {
+                            Ok(Ok(entries)) => Some(entries),
+                            Ok(Err(e)) => {
+                                warn!("Skipping archive {}: {}", file_info.path.display(), e);
+                                None
+                            }
+                            Err(e) => {
+                                warn!("Archive reader for {} stopped: {}", file_info.path.display(), e);
+                                None
+                            }
+                        }
                     }
-                }
-            } else if path_str.contains(pattern) {
-                return true;
+                };
+                ArchiveListing { modified: file_info.modified, entries }
             }
-        }
+        };
         
-        false
+        let members = listing.entries.iter()
+            .flatten()
+            .map(|entry| FileInfo::in_archive(file_info, entry))
+            .collect();
+        archives.listings.insert(file_info.path.clone(), listing);
+        members
     }
     
-    pub async fn search_applications(&self, query: &str) -> std::result::Result<Vec<SearchResult>, IndexError> {
+    fn should_exclude(&self, path: &Path, exclude_patterns: &[String]) -> bool {
+        path_excluded(&path.to_string_lossy(), exclude_patterns)
+    }
+    
+    // `aliases` (alias -> application name) find apps by names the user gave them
+    pub async fn search_applications(&self, query: &str, aliases: &HashMap<String, String>) -> std::result::Result<Vec<SearchResult>, IndexError> {
         let app_index = self.app_index.read().await;
         let mut results = Vec::new();
         
         let query_lower = query.to_lowercase();
         
         for (key, app_info) in app_index.iter() {
-            let score = if key.contains(&query_lower) {
-                self.calculate_app_match_score(key, &query_lower, app_info)
-            } else if app_info.keywords.iter().any(|k| k.to_lowercase().contains(&query_lower)) {
-                0.6 // Lower score for keyword matches
+            let display_lower = app_info.name.to_lowercase();
+            let (score, reason) = if key.contains(&query_lower) {
+                (self.calculate_app_match_score(key, &query_lower, app_info), MatchReason::TitleMatch)
+            } else if display_lower.contains(&query_lower) {
+                // "python 3.12" matches the display name of a grouped entry
+                (self.calculate_app_match_score(&display_lower, &query_lower, app_info), MatchReason::TitleMatch)
+            } else if let Some(keyword) = app_info.keywords.iter().find(|k| k.to_lowercase().contains(&query_lower)) {
+                (0.6, MatchReason::KeywordMatch(keyword.clone())) // Lower score for keyword matches
             } else {
                 continue;
             };
             
-            let mut result = app_info.to_search_result();
+            let mut result = app_info.to_search_result().with_match_reason(reason);
             result.score = score;
             results.push(result);
         }
         
-        // Sort by score
-        results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+        // Aliases only add apps their name or keywords didn't already find
+        for (alias, target) in aliases {
+            let alias_lower = alias.to_lowercase();
+            if !alias_lower.starts_with(&query_lower) {
+                continue;
+            }
+            let Some(app_info) = Self::lookup_app(&app_index, target) else {
+                continue;
+            };
+            let mut result = app_info.to_search_result().with_match_reason(MatchReason::AliasMatch(alias.clone()));
+            if results.iter().any(|other| other.id == result.id) {
+                continue;
+            }
+            result.score = if alias_lower == query_lower { 0.8 } else { 0.7 };
+            results.push(result);
+        }
+        
+        // The app index is a HashMap; ties fall back to title and id so its iteration order never shows
+        results.sort_by(by_score_then_title);
         
         Ok(results)
     }
     
     pub async fn search_files(&self, query: &str) -> std::result::Result<Vec<SearchResult>, IndexError> {
+        self.search_files_in(query, None).await
+    }
+    
+    // Files whose name contains `query`, only those below `folder` when given (an empty query lists them all)
+    pub async fn search_files_in(&self, query: &str, folder: Option<&Path>) -> std::result::Result<Vec<SearchResult>, IndexError> {
         let file_index = self.file_index.read().await;
         let mut results = Vec::new();
         
         let query_lower = query.to_lowercase();
+        // The index holds canonical paths
+        let folder = folder.map(|folder| std::fs::canonicalize(folder).unwrap_or_else(|_| folder.to_path_buf()));
         
         for (key, file_info) in file_index.iter() {
-            if key.contains(&query_lower) {
-                let score = self.calculate_file_match_score(key, &query_lower);
-                let mut result = file_info.to_search_result();
+            if folder.as_ref().is_some_and(|folder| !file_info.path.starts_with(folder)) {
+                continue;
+            }
+            let name = key_name(key);
+            if name.contains(&query_lower) {
+                let score = self.calculate_file_match_score(name, &query_lower);
+                let mut result = file_info.to_search_result().with_match_reason(MatchReason::TitleMatch);
                 result.score = score;
                 results.push(result);
             }
         }
         
-        // Sort by score
-        results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+        // Ties are broken before truncating, so the same 20 files make the cut every time
+        results.sort_by(by_score_then_title);
         
         // Limit to reasonable number for file results
         results.truncate(20);
@@ -274,6 +802,22 @@ impl IndexManager {
         Ok(results)
     }
     
+    // Indexed files on disk (not archive members) whose name contains `query`, below `folder` when
+    // given; unlike search_files_in, every match and no ranking
+    pub async fn files_in(&self, query: &str, folder: Option<&Path>) -> Vec<FileInfo> {
+        let query_lower = query.to_lowercase();
+        let folder = folder.map(|folder| std::fs::canonicalize(folder).unwrap_or_else(|_| folder.to_path_buf()));
+        self.file_index.read().await
+            .iter()
+            .filter(|(key, file_info)| {
+                file_info.archive_member.is_none()
+                    && folder.as_ref().is_none_or(|folder| file_info.path.starts_with(folder))
+                    && key_name(key).contains(&query_lower)
+            })
+            .map(|(_, file_info)| file_info.clone())
+            .collect()
+    }
+    
     fn calculate_app_match_score(&self, app_name: &str, query: &str, app_info: &AppInfo) -> f64 {
         let mut score = 0.5;
         
@@ -317,19 +861,176 @@ impl IndexManager {
         score.min(1.0)
     }
     
+    // Resolves the first query token to a single app: exact name or alias, otherwise a unique prefix match.
+    // The reason says which of those it was
+    pub async fn resolve_app_token(&self, token: &str, aliases: &HashMap<String, String>) -> Option<(AppInfo, MatchReason)> {
+        let app_index = self.app_index.read().await;
+        let token_lower = token.to_lowercase();
+        
+        if let Some(app_info) = app_index.get(&token_lower) {
+            return Some((app_info.clone(), MatchReason::TitleMatch));
+        }
+        
+        if let Some((alias, target)) = aliases.iter()
+            .find(|(alias, _)| alias.to_lowercase() == token_lower)
+        {
+            if let Some(app_info) = Self::lookup_app(&app_index, target) {
+                return Some((app_info.clone(), MatchReason::AliasMatch(alias.clone())));
+            }
+        }
+        
+        let mut candidates = app_index.iter()
+            .filter(|(key, _)| key.starts_with(&token_lower))
+            .map(|(_, app_info)| app_info);
+        
+        match (candidates.next(), candidates.next()) {
+            (Some(app_info), None) => Some((app_info.clone(), MatchReason::TitleMatch)),
+            _ => None,
+        }
+    }
+    
+    // True while the query could still be an app's name being typed, spaces included
+    pub async fn is_app_name_prefix(&self, query: &str) -> bool {
+        let app_index = self.app_index.read().await;
+        is_app_name_prefix(query, app_index.values().map(|app_info| app_info.name.as_str()))
+    }
+    
+    // Ungrouped apps are keyed by their full name, grouped ones by canonical name
+    fn app_key(app_index: &HashMap<String, AppInfo>, app_name: &str) -> String {
+        let key = app_name.to_lowercase();
+        if app_index.contains_key(&key) {
+            key
+        } else {
+            normalize_app_name(app_name).canonical
+        }
+    }
+    
+    fn lookup_app<'a>(app_index: &'a HashMap<String, AppInfo>, app_name: &str) -> Option<&'a AppInfo> {
+        app_index.get(&Self::app_key(app_index, app_name))
+    }
+    
+    pub async fn applications(&self) -> Vec<AppInfo> {
+        self.app_index.read().await.values().cloned().collect()
+    }
+    
     pub async fn get_app_info(&self, app_name: &str) -> Option<AppInfo> {
         let app_index = self.app_index.read().await;
-        app_index.get(&app_name.to_lowercase()).cloned()
+        Self::lookup_app(&app_index, app_name).cloned()
     }
     
     pub async fn update_app_usage(&self, app_name: &str) {
         let mut app_index = self.app_index.write().await;
-        if let Some(app_info) = app_index.get_mut(&app_name.to_lowercase()) {
+        let key = Self::app_key(&app_index, app_name);
+        if let Some(app_info) = app_index.get_mut(&key) {
             app_info.increment_usage();
             info!("Updated usage for app: {}", app_name);
         }
     }
     
+    // Drops the folder's files from the live index right away; rebuilds skip it once it is in exclude_patterns
+    pub async fn exclude_directory(&self, dir: &Path) -> usize {
+        let mut file_index = self.file_index.write().await;
+        let before = file_index.len();
+        file_index.retain(|_, file_info| !file_info.path.starts_with(dir));
+        let removed = before - file_index.len();
+        if removed > 0 {
+            self.entries_changed();
+        }
+        
+        info!("Excluded {} from indexing, removed {} files", dir.display(), removed);
+        removed
+    }
+    
+    // Drops a single file, e.g. after it was moved to the trash
+    pub async fn remove_file(&self, path: &Path) -> bool {
+        let mut file_index = self.file_index.write().await;
+        let before = file_index.len();
+        file_index.retain(|_, file_info| file_info.path != path);
+        let removed = before != file_index.len();
+        if removed {
+            self.entries_changed();
+        }
+        removed
+    }
+    
+    // Drops the app launched from `path`, e.g. after it was uninstalled; its other versions go with it
+    pub async fn remove_app(&self, path: &Path) -> Option<AppInfo> {
+        let mut app_index = self.app_index.write().await;
+        let key = app_index.iter()
+            .find(|(_, app)| app.executable_path == path || app.other_versions.iter().any(|other| other.executable_path == path))
+            .map(|(key, _)| key.clone())?;
+        self.entries_changed();
+        app_index.remove(&key)
+    }
+    
+    // Folders whose top level is indexed on this platform
+    pub async fn include_paths(&self) -> Vec<PathBuf> {
+        self.config.read().await.search.include_paths.get(current_os())
+            .map(|paths| paths.iter().map(PathBuf::from).collect())
+            .unwrap_or_default()
+    }
+    
+    // The indexed app or file whose result has `id`, without running a search
+    pub async fn find_by_id(&self, id: ResultId) -> Option<SearchResult> {
+        let app = self.app_index.read().await.values()
+            .map(AppInfo::to_search_result)
+            .find(|result| result.id == id);
+        if app.is_some() {
+            return app;
+        }
+        self.file_index.read().await.values()
+            .map(FileInfo::to_search_result)
+            .find(|result| result.id == id)
+    }
+    
+    // The plain (non-archive) entry for `path`
+    pub async fn file_at(&self, path: &Path) -> Option<FileInfo> {
+        self.file_index.read().await.values()
+            .find(|file_info| file_info.archive_member.is_none() && file_info.path == path)
+            .cloned()
+    }
+    
+    // Re-keys entries at or below `from` to `to` in place, keeping their keywords and archive listings.
+    // Returns old -> new result ids of the moved entries
+    pub async fn move_path(&self, from: &Path, to: &Path) -> HashMap<ResultId, ResultId> {
+        let mut file_index = self.file_index.write().await;
+        let moved_keys: Vec<String> = file_index.iter()
+            .filter(|(_, file_info)| file_info.path.starts_with(from))
+            .map(|(key, _)| key.clone())
+            .collect();
+        
+        let mut ids = HashMap::new();
+        for key in moved_keys {
+            let Some(mut file_info) = file_index.remove(&key) else {
+                continue;
+            };
+            let old_id = file_info.to_search_result().id;
+            file_info.relocate(from, to);
+            ids.insert(old_id, file_info.to_search_result().id);
+            file_index.insert(file_info.index_key(), file_info);
+        }
+        drop(file_index);
+        
+        let mut listings = self.archive_listings.write().await;
+        let moved_archives: Vec<PathBuf> = listings.keys().filter(|path| path.starts_with(from)).cloned().collect();
+        for path in moved_archives {
+            if let (Some(listing), Some(moved)) = (listings.remove(&path), relocated_path(&path, from, to)) {
+                listings.insert(moved, listing);
+            }
+        }
+        
+        if !ids.is_empty() {
+            self.entries_changed();
+            info!("Moved {} index entries from {} to {}", ids.len(), from.display(), to.display());
+        }
+        ids
+    }
+    
+    // Watched by the tray to show rebuild progress and refresh once a rebuild completes
+    pub fn subscribe_progress(&self) -> watch::Receiver<IndexProgress> {
+        self.progress.subscribe()
+    }
+    
     pub async fn get_index_stats(&self) -> IndexStats {
         let app_index = self.app_index.read().await;
         let file_index = self.file_index.read().await;
@@ -337,8 +1038,13 @@ impl IndexManager {
         
         IndexStats {
             app_count: app_index.len(),
+            excluded_app_count: *self.excluded_apps.read().await,
             file_count: file_index.len(),
+            backed_off_dirs: self.scan_history.read().await.backed_off(SystemTime::now()),
+            app_scan_warnings: self.app_scan_report.read().await.warnings.clone(),
             last_rebuild,
+            cache_verification: self.cache_verification.read().await.clone(),
+            marked_stale: *self.marked_stale.read().await,
         }
     }
 }
@@ -346,12 +1052,25 @@ impl IndexManager {
 #[derive(Debug, Clone)]
 pub struct IndexStats {
     pub app_count: usize,
+    // Apps hidden by app_exclude_patterns in the last rebuild
+    pub excluded_app_count: usize,
     pub file_count: usize,
+    // Include paths skipped after failing to scan, with when they are tried again
+    pub backed_off_dirs: Vec<(PathBuf, SystemTime)>,
+    // Application sources that failed in the last rebuild, e.g. "Start Menu scan failed: access denied"
+    pub app_scan_warnings: Vec<ScanWarning>,
     pub last_rebuild: Option<SystemTime>,
+    // How the cache checked out on load; None when none was loaded
+    pub cache_verification: Option<CacheVerification>,
+    // The loaded cache drifted from disk and no rebuild has finished since
+    pub marked_stale: bool,
 }
 
 impl IndexStats {
     pub fn is_stale(&self) -> bool {
+        if self.marked_stale {
+            return true;
+        }
         match self.last_rebuild {
             Some(last_rebuild) => {
                 if let Ok(elapsed) = SystemTime::now().duration_since(last_rebuild) {
@@ -364,3 +1083,192 @@ impl IndexStats {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sensitive::default_sensitive_dirs;
+    use crate::testing::write_zip;
+
+    // Scanning an include path inside the planted ~/.ssh indexes nothing unless it is allowed
+    #[tokio::test]
+    async fn sensitive_include_paths_are_not_scanned() {
+        let home = tempfile::tempdir().unwrap();
+        let ssh = home.path().join(".ssh");
+        std::fs::create_dir_all(&ssh).unwrap();
+        std::fs::write(ssh.join("id_ed25519"), "PRIVATE KEY").unwrap();
+        let index = IndexManager::new(Arc::new(RwLock::new(Config::default()))).await.unwrap();
+
+        let denied = SensitivePaths::new(default_sensitive_dirs(home.path()), &[]);
+        let mut file_index = BTreeMap::new();
+        index.scan_directory(&ssh, &mut file_index, &[], &denied, None).await.unwrap();
+        assert!(file_index.is_empty());
+
+        let allowed = SensitivePaths::new(default_sensitive_dirs(home.path()), &[ssh.to_string_lossy().into_owned()]);
+        index.scan_directory(&ssh, &mut file_index, &[], &allowed, None).await.unwrap();
+        assert_eq!(file_index.len(), 1);
+    }
+
+    fn archive_scan(previous: HashMap<PathBuf, ArchiveListing>) -> ArchiveScan {
+        ArchiveScan { size_limit: 1024 * 1024, isolated: false, previous, listings: HashMap::new() }
+    }
+
+    fn members(file_index: &BTreeMap<String, FileInfo>) -> Vec<String> {
+        let mut members: Vec<String> = file_index.values().filter_map(|file| file.archive_member.clone()).collect();
+        members.sort();
+        members
+    }
+
+    // A listing is reused while the archive's mtime is unchanged and read again once it moves
+    #[tokio::test]
+    async fn archive_listings_are_cached_by_mtime() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("bundle.zip");
+        write_zip(&archive, &[("docs/a.txt", "a")], true);
+        let modified = std::fs::metadata(&archive).unwrap().modified().unwrap();
+        let index = IndexManager::new(Arc::new(RwLock::new(Config::default()))).await.unwrap();
+        let sensitive = SensitivePaths::new(Vec::new(), &[]);
+
+        let mut scan = archive_scan(HashMap::new());
+        let mut file_index = BTreeMap::new();
+        index.scan_directory(dir.path(), &mut file_index, &[], &sensitive, Some(&mut scan)).await.unwrap();
+        assert_eq!(members(&file_index), ["docs/a.txt"]);
+
+        // Rewritten with the old mtime: the cached listing stands
+        write_zip(&archive, &[("docs/a.txt", "a"), ("docs/b.txt", "b")], true);
+        std::fs::File::options().write(true).open(&archive).unwrap().set_modified(modified).unwrap();
+        let mut scan = archive_scan(scan.listings);
+        let mut file_index = BTreeMap::new();
+        index.scan_directory(dir.path(), &mut file_index, &[], &sensitive, Some(&mut scan)).await.unwrap();
+        assert_eq!(members(&file_index), ["docs/a.txt"]);
+
+        std::fs::File::options().write(true).open(&archive).unwrap()
+            .set_modified(modified + std::time::Duration::from_secs(60)).unwrap();
+        let mut scan = archive_scan(scan.listings);
+        let mut file_index = BTreeMap::new();
+        index.scan_directory(dir.path(), &mut file_index, &[], &sensitive, Some(&mut scan)).await.unwrap();
+        assert_eq!(members(&file_index), ["docs/a.txt", "docs/b.txt"]);
+
+        // Within one rebuild an archive is only listed once
+        let archive_info = FileInfo::new(archive.clone()).unwrap();
+        assert!(index.archive_members(&archive_info, &mut scan).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn unreadable_and_oversized_archives_add_no_members() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("broken.zip"), b"not a zip").unwrap();
+        write_zip(&dir.path().join("big.zip"), &[("inside.txt", &"x".repeat(4096))], false);
+        let index = IndexManager::new(Arc::new(RwLock::new(Config::default()))).await.unwrap();
+
+        let mut scan = archive_scan(HashMap::new());
+        scan.size_limit = 1024;
+        let mut file_index = BTreeMap::new();
+        index.scan_directory(dir.path(), &mut file_index, &[], &SensitivePaths::new(Vec::new(), &[]), Some(&mut scan)).await.unwrap();
+        assert!(members(&file_index).is_empty());
+        assert_eq!(file_index.len(), 2);
+        // The failed read is remembered so the next rebuild doesn't retry an unchanged archive
+        assert!(scan.listings.values().any(|listing| listing.entries.is_none()));
+    }
+
+    // A file offers its folder as a pivot whose query scopes the search to that folder
+    #[test]
+    fn file_results_pivot_to_their_folder() {
+        let dir = tempfile::tempdir().unwrap();
+        let folder = dir.path().join("My Files");
+        std::fs::create_dir_all(&folder).unwrap();
+        std::fs::write(folder.join("report.txt"), "x").unwrap();
+        let result = FileInfo::new(folder.join("report.txt")).unwrap().to_search_result();
+
+        let pivot = result.secondary_actions.iter().find(|secondary| secondary.title == "Search in this folder").unwrap();
+        assert!(!pivot.destructive);
+        let Action::SetQuery(ref query) = pivot.action else { panic!("{:?}", pivot.action) };
+        assert_eq!(crate::scope::folder_scope(query), Some((folder.clone(), "")));
+    }
+
+    async fn cached_index(dir: &Path, include: &Path, exclude_patterns: Vec<String>) -> IndexManager {
+        let mut config = Config::default();
+        config.search.include_paths = HashMap::from([(current_os().to_string(), vec![include.to_string_lossy().into_owned()])]);
+        config.search.exclude_patterns = exclude_patterns;
+        IndexManager::new(Arc::new(RwLock::new(config))).await.unwrap().with_cache_path(dir.join("index.json"))
+    }
+
+    // Ten indexed files saved to the cache
+    async fn save_ten_files(dir: &Path) -> Vec<PathBuf> {
+        let paths: Vec<PathBuf> = (0..10).map(|n| dir.join(format!("file{}.txt", n))).collect();
+        let index = cached_index(dir, dir, Vec::new()).await;
+        for path in &paths {
+            std::fs::write(path, "x").unwrap();
+            let file_info = FileInfo::new(path.clone()).unwrap();
+            index.file_index.write().await.insert(file_info.index_key(), file_info);
+        }
+        index.save_cache(SystemTime::now()).await.unwrap();
+        paths
+    }
+
+    #[tokio::test]
+    async fn an_intact_cache_loads() {
+        let dir = tempfile::tempdir().unwrap();
+        save_ten_files(dir.path()).await;
+        let index = cached_index(dir.path(), dir.path(), Vec::new()).await;
+
+        assert_eq!(index.load_cache().await, Some(CacheVerification::Passed { sampled: 10, missing: 0 }));
+        let stats = index.get_index_stats().await;
+        assert_eq!(stats.file_count, 10);
+        assert!(!stats.is_stale());
+    }
+
+    // Files deleted while the app was closed: still served, but stale until the rebuild
+    #[tokio::test]
+    async fn a_drifted_cache_is_served_and_marked_stale() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = save_ten_files(dir.path()).await;
+        for path in &paths[..2] {
+            std::fs::remove_file(path).unwrap();
+        }
+        let index = cached_index(dir.path(), dir.path(), Vec::new()).await;
+
+        let verification = index.load_cache().await.unwrap();
+        assert_eq!(verification, CacheVerification::Drifted { sampled: 10, missing: 2 });
+        let stats = index.get_index_stats().await;
+        assert_eq!(stats.file_count, 10);
+        assert_eq!(stats.cache_verification, Some(verification));
+        assert!(stats.marked_stale && stats.is_stale());
+
+        // One missing of ten is within the threshold
+        std::fs::write(&paths[0], "x").unwrap();
+        let index = cached_index(dir.path(), dir.path(), Vec::new()).await;
+        assert_eq!(index.load_cache().await, Some(CacheVerification::Passed { sampled: 10, missing: 1 }));
+    }
+
+    #[tokio::test]
+    async fn a_damaged_cache_is_discarded() {
+        let dir = tempfile::tempdir().unwrap();
+        save_ten_files(dir.path()).await;
+        let cache_path = dir.path().join("index.json");
+        let content = std::fs::read_to_string(&cache_path).unwrap();
+        std::fs::write(&cache_path, content.replace("file3.txt", "file9.txt")).unwrap();
+        let index = cached_index(dir.path(), dir.path(), Vec::new()).await;
+
+        assert_eq!(index.load_cache().await, Some(CacheVerification::Rejected(CacheProblem::ChecksumMismatch)));
+        let stats = index.get_index_stats().await;
+        assert_eq!(stats.file_count, 0);
+        assert!(stats.is_stale());
+    }
+
+    // Other search folders or exclude patterns would have indexed other files
+    #[tokio::test]
+    async fn a_cache_for_other_search_paths_is_discarded() {
+        let dir = tempfile::tempdir().unwrap();
+        save_ten_files(dir.path()).await;
+        let changed = CacheVerification::Rejected(CacheProblem::IncludePathsChanged);
+
+        let other_folder = cached_index(dir.path(), &dir.path().join("elsewhere"), Vec::new()).await;
+        assert_eq!(other_folder.load_cache().await, Some(changed.clone()));
+        assert_eq!(other_folder.get_index_stats().await.file_count, 0);
+
+        let other_patterns = cached_index(dir.path(), dir.path(), vec!["*.txt".to_string()]).await;
+        assert_eq!(other_patterns.load_cache().await, Some(changed));
+        assert_eq!(other_patterns.inspect_cache().await, Some(Err("built for other search paths".to_string())));
+    }
+}
@@ -0,0 +1,226 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+
+use falcommand_config::fnv1a;
+
+// First line of the index cache file; the serialized cache follows on the second
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheHeader {
+    pub schema_version: u32,
+    // Of the include paths and exclude patterns the index was built with
+    pub include_paths_hash: u64,
+    // Of the serialized cache
+    pub checksum: u64,
+}
+
+impl CacheHeader {
+    // Bump when the cached structures change incompatibly
+    pub const SCHEMA_VERSION: u32 = 1;
+
+    pub fn new(include_paths_hash: u64, payload: &str) -> Self {
+        Self {
+            schema_version: Self::SCHEMA_VERSION,
+            include_paths_hash,
+            checksum: fnv1a(payload.as_bytes()),
+        }
+    }
+}
+
+// Why a cache was thrown away instead of loaded
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum CacheProblem {
+    #[error("unreadable: {0}")]
+    Unreadable(String),
+    // None for caches written before they had a header
+    #[error("written by an incompatible version (schema {found:?}, expected {expected})")]
+    SchemaMismatch { found: Option<u32>, expected: u32 },
+    #[error("checksum mismatch, the file is damaged")]
+    ChecksumMismatch,
+    #[error("built for other search paths")]
+    IncludePathsChanged,
+}
+
+// The outcome of checking the cache on load
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CacheVerification {
+    Passed { sampled: usize, missing: usize },
+    // Loaded and served, but too many sampled entries are gone: rebuild now
+    Drifted { sampled: usize, missing: usize },
+    Rejected(CacheProblem),
+}
+
+impl CacheVerification {
+    // Whether the cached entries were loaded
+    pub fn is_loaded(&self) -> bool {
+        !matches!(self, CacheVerification::Rejected(_))
+    }
+
+    pub fn needs_rebuild(&self) -> bool {
+        !matches!(self, CacheVerification::Passed { .. })
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            CacheVerification::Passed { sampled: 0, .. } => "loaded, entries not sampled".to_string(),
+            CacheVerification::Passed { sampled, missing } => format!("loaded, {} of {} sampled entries missing", missing, sampled),
+            CacheVerification::Drifted { sampled, missing } => {
+                format!("loaded but out of date, {} of {} sampled entries missing; rebuilding", missing, sampled)
+            }
+            CacheVerification::Rejected(problem) => format!("discarded, {}", problem),
+        }
+    }
+}
+
+// Above this share of missing sampled entries the cache is rebuilt
+pub const MAX_MISS_RATE: f64 = 0.1;
+
+pub fn drift_verdict(sampled: usize, missing: usize) -> CacheVerification {
+    if sampled > 0 && missing as f64 / sampled as f64 > MAX_MISS_RATE {
+        CacheVerification::Drifted { sampled, missing }
+    } else {
+        CacheVerification::Passed { sampled, missing }
+    }
+}
+
+// Changes whenever the file index would be built from other folders or skip other files
+pub fn include_paths_hash(include_paths: &[PathBuf], exclude_patterns: &[String]) -> u64 {
+    let mut key = String::new();
+    for path in include_paths {
+        key.push_str(&path.to_string_lossy());
+        key.push('\u{1f}');
+    }
+    key.push('\u{1e}');
+    for pattern in exclude_patterns {
+        key.push_str(pattern);
+        key.push('\u{1f}');
+    }
+    fnv1a(key.as_bytes())
+}
+
+pub fn write_cache_file(header: &CacheHeader, payload: &str) -> Result<String, serde_json::Error> {
+    Ok(format!("{}\n{}", serde_json::to_string(header)?, payload))
+}
+
+// The header and payload of a cache file, checked for schema version and checksum
+pub fn read_cache_file(content: &str) -> Result<(CacheHeader, &str), CacheProblem> {
+    let legacy = CacheProblem::SchemaMismatch { found: None, expected: CacheHeader::SCHEMA_VERSION };
+    let Some((first_line, payload)) = content.split_once('\n') else {
+        return Err(legacy);
+    };
+    let header: CacheHeader = serde_json::from_str(first_line).map_err(|_| legacy)?;
+
+    if header.schema_version != CacheHeader::SCHEMA_VERSION {
+        return Err(CacheProblem::SchemaMismatch {
+            found: Some(header.schema_version),
+            expected: CacheHeader::SCHEMA_VERSION,
+        });
+    }
+    if fnv1a(payload.as_bytes()) != header.checksum {
+        return Err(CacheProblem::ChecksumMismatch);
+    }
+    Ok((header, payload))
+}
+
+// Up to `count` distinct indices below `len`, picked at random from `seed` (Floyd's algorithm)
+pub fn sample_indices(len: usize, count: usize, seed: u64) -> Vec<usize> {
+    if count >= len {
+        return (0..len).collect();
+    }
+
+    // xorshift64; a zero state would stay zero
+    let mut state = seed | 1;
+    let mut next = |bound: usize| {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state % bound as u64) as usize
+    };
+
+    let mut picked = HashSet::with_capacity(count);
+    for upper in len - count..len {
+        let candidate = next(upper + 1);
+        if !picked.insert(candidate) {
+            picked.insert(upper);
+        }
+    }
+    let mut indices: Vec<usize> = picked.into_iter().collect();
+    indices.sort_unstable();
+    indices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cache_files_read_back_and_damage_is_caught() {
+        let payload = r#"{"apps":{},"files":{}}"#;
+        let header = CacheHeader::new(7, payload);
+        let content = write_cache_file(&header, payload).unwrap();
+        assert_eq!(read_cache_file(&content).unwrap(), (header, payload));
+
+        let damaged = content.replace("files", "filez");
+        assert_eq!(read_cache_file(&damaged).unwrap_err(), CacheProblem::ChecksumMismatch);
+    }
+
+    #[test]
+    fn other_schema_versions_are_refused() {
+        let payload = "{}";
+        let expected = CacheHeader::SCHEMA_VERSION;
+        // A cache from before the header is just the payload
+        assert_eq!(read_cache_file(payload).unwrap_err(), CacheProblem::SchemaMismatch { found: None, expected });
+
+        let newer = CacheHeader { schema_version: expected + 1, ..CacheHeader::new(7, payload) };
+        let content = write_cache_file(&newer, payload).unwrap();
+        assert_eq!(
+            read_cache_file(&content).unwrap_err(),
+            CacheProblem::SchemaMismatch { found: Some(expected + 1), expected },
+        );
+    }
+
+    // Rebuilt only once more than a tenth of the sample is gone
+    #[test]
+    fn drift_is_judged_by_the_miss_rate() {
+        assert_eq!(drift_verdict(100, 10), CacheVerification::Passed { sampled: 100, missing: 10 });
+        assert_eq!(drift_verdict(100, 11), CacheVerification::Drifted { sampled: 100, missing: 11 });
+        assert_eq!(drift_verdict(0, 0), CacheVerification::Passed { sampled: 0, missing: 0 });
+
+        assert!(drift_verdict(100, 11).is_loaded() && drift_verdict(100, 11).needs_rebuild());
+        let rejected = CacheVerification::Rejected(CacheProblem::IncludePathsChanged);
+        assert!(!rejected.is_loaded() && rejected.needs_rebuild());
+        assert_eq!(rejected.describe(), "discarded, built for other search paths");
+        assert_eq!(drift_verdict(0, 0).describe(), "loaded, entries not sampled");
+    }
+
+    #[test]
+    fn include_paths_hash_follows_paths_and_patterns() {
+        let paths = [PathBuf::from("/home/me/Documents"), PathBuf::from("/home/me/Music")];
+        let patterns = ["*.tmp".to_string()];
+        let hash = include_paths_hash(&paths, &patterns);
+        assert_eq!(include_paths_hash(&paths, &patterns), hash);
+        assert_ne!(include_paths_hash(&paths[..1], &patterns), hash);
+        assert_ne!(include_paths_hash(&paths, &[]), hash);
+        // Entries are delimited, so moving a boundary is a change
+        assert_ne!(
+            include_paths_hash(&[PathBuf::from("/a"), PathBuf::from("b")], &[]),
+            include_paths_hash(&[PathBuf::from("/ab")], &[]),
+        );
+        assert_ne!(include_paths_hash(&[PathBuf::from("*.tmp")], &[]), include_paths_hash(&[], &patterns));
+    }
+
+    #[test]
+    fn samples_are_distinct_and_in_range() {
+        assert_eq!(sample_indices(5, 200, 42), [0, 1, 2, 3, 4]);
+        assert!(sample_indices(0, 200, 42).is_empty());
+
+        for seed in [0, 1, 42, u64::MAX] {
+            let sample = sample_indices(10_000, 200, seed);
+            assert_eq!(sample.len(), 200);
+            assert!(sample.windows(2).all(|pair| pair[0] < pair[1]));
+            assert!(sample.iter().all(|&index| index < 10_000));
+            assert_eq!(sample_indices(10_000, 200, seed), sample);
+        }
+        assert_ne!(sample_indices(10_000, 200, 1), sample_indices(10_000, 200, 2));
+    }
+}
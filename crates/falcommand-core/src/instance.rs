@@ -0,0 +1,382 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use log::{info, warn};
+
+use falcommand_config::{PathResolver, ResultId};
+use crate::crash::TaskSupervisor;
+use crate::events::{EventBus, EventFilter, EventSubscription};
+use crate::paging::{PageReply, PageRequest};
+use crate::search::SearchEngine;
+
+#[derive(Debug, thiserror::Error)]
+pub enum InstanceError {
+    #[error("Instance channel I/O error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("No running instance")]
+    NotRunning,
+
+    #[error("The running instance refused the subscription")]
+    SubscriptionRefused,
+
+    #[error("The running instance sent an unreadable reply: {0}")]
+    InvalidReply(String),
+}
+
+// What a second launch asks the running instance to do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstanceMessage {
+    Execute(ResultId),
+}
+
+impl InstanceMessage {
+    // One line per message: "execute <id>"
+    pub fn encode(&self) -> String {
+        match self {
+            InstanceMessage::Execute(id) => format!("execute {}\n", id),
+        }
+    }
+
+    pub fn decode(line: &str) -> Option<Self> {
+        let (verb, argument) = line.trim().split_once(' ')?;
+        match verb {
+            "execute" => argument.parse().ok().map(InstanceMessage::Execute),
+            _ => None,
+        }
+    }
+}
+
+// Loopback connection between launches. The running instance listens on a port it writes to
+// the runtime directory; later launches read it and forward their request instead of starting
+pub struct InstanceChannel {
+    port_file: PathBuf,
+    // Connections that open with a subscribe line stream these instead
+    events: Option<Arc<EventBus>>,
+    // Connections that open with a search line are answered a page at a time from this
+    search_engine: Option<Arc<SearchEngine>>,
+}
+
+impl InstanceChannel {
+    // Sent once before the events so a client can tell an accepted subscription from a refused one
+    const SUBSCRIBED: &'static str = "{\"event\":\"subscribed\"}";
+
+    pub fn new(resolver: &PathResolver) -> Self {
+        Self::with_port_file(resolver.runtime_dir().join("instance.port"))
+    }
+
+    pub fn with_port_file(port_file: PathBuf) -> Self {
+        Self { port_file, events: None, search_engine: None }
+    }
+
+    pub fn with_events(mut self, events: Arc<EventBus>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    pub fn with_search(mut self, search_engine: Arc<SearchEngine>) -> Self {
+        self.search_engine = Some(search_engine);
+        self
+    }
+
+    // Sends `message` to the running instance; NotRunning when there is none to take it
+    pub async fn forward(&self, message: InstanceMessage) -> std::result::Result<(), InstanceError> {
+        let mut stream = self.connect().await?;
+        stream.write_all(message.encode().as_bytes()).await?;
+        stream.shutdown().await?;
+        Ok(())
+    }
+
+    // Events of the running instance as JSON lines, until it exits or the lines are dropped
+    pub async fn subscribe(&self, filter: &EventFilter) -> std::result::Result<Lines<BufReader<TcpStream>>, InstanceError> {
+        let mut stream = self.connect().await?;
+        stream.write_all(filter.encode_request().as_bytes()).await?;
+        // Anything but the acknowledgement means the instance refused and hung up
+        let mut lines = BufReader::new(stream).lines();
+        match lines.next_line().await? {
+            Some(line) if line == Self::SUBSCRIBED => Ok(lines),
+            _ => Err(InstanceError::SubscriptionRefused),
+        }
+    }
+
+    // One page of the running instance's ranking for `request.query`
+    pub async fn search(&self, request: &PageRequest) -> std::result::Result<PageReply, InstanceError> {
+        let mut stream = self.connect().await?;
+        stream.write_all(request.encode().as_bytes()).await?;
+        let mut lines = BufReader::new(stream).lines();
+        let line = lines.next_line().await?
+            .ok_or_else(|| InstanceError::InvalidReply("the connection closed without a reply".to_string()))?;
+        serde_json::from_str(&line).map_err(|e| InstanceError::InvalidReply(e.to_string()))
+    }
+
+    async fn connect(&self) -> std::result::Result<TcpStream, InstanceError> {
+        let port: u16 = match tokio::fs::read_to_string(&self.port_file).await {
+            Ok(content) => content.trim().parse().map_err(|_| InstanceError::NotRunning)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Err(InstanceError::NotRunning),
+            Err(e) => return Err(e.into()),
+        };
+
+        // A port file left behind by a crash points at nothing
+        TcpStream::connect(("127.0.0.1", port)).await
+            .map_err(|_| InstanceError::NotRunning)
+    }
+
+    // Starts accepting forwarded requests and passes them to `sender`
+    pub async fn listen(&self, sender: mpsc::UnboundedSender<InstanceMessage>) -> std::result::Result<(), InstanceError> {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await?;
+        let port = listener.local_addr()?.port();
+        if let Some(parent) = self.port_file.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(&self.port_file, port.to_string()).await?;
+        info!("Listening for other launches on port {}", port);
+
+        let listener = Arc::new(listener);
+        let events = self.events.clone();
+        let search_engine = self.search_engine.clone();
+        TaskSupervisor::default().spawn("instance-channel", move || {
+            let listener = listener.clone();
+            let sender = sender.clone();
+            let events = events.clone();
+            let search_engine = search_engine.clone();
+            async move {
+                loop {
+                    let (stream, _) = match listener.accept().await {
+                        Ok(accepted) => accepted,
+                        Err(e) => {
+                            warn!("Failed to accept a forwarded launch: {}", e);
+                            continue;
+                        }
+                    };
+                    let mut lines = BufReader::new(stream).lines();
+                    while let Ok(Some(line)) = lines.next_line().await {
+                        // A subscription takes the rest of the connection, on a task of its own
+                        if let Some(request) = EventFilter::decode_request(&line) {
+                            match (request, events.as_ref()) {
+                                (Ok(filter), Some(events)) => {
+                                    let stream = lines.into_inner().into_inner();
+                                    tokio::spawn(Self::stream_events(stream, events.subscribe(filter)));
+                                }
+                                (Err(e), _) => warn!("Refusing an event subscription: {}", e),
+                                (Ok(_), None) => warn!("Refusing an event subscription: events are not published"),
+                            }
+                            break;
+                        }
+                        // So does a search client, which may ask for further pages on it
+                        if let Some(request) = PageRequest::decode(&line) {
+                            tokio::spawn(Self::serve_pages(lines, request, search_engine.clone()));
+                            break;
+                        }
+                        match InstanceMessage::decode(&line) {
+                            Some(message) => {
+                                if sender.send(message).is_err() {
+                                    return;
+                                }
+                            }
+                            None => warn!("Ignoring unknown forwarded request: {}", line),
+                        }
+                    }
+                }
+            }
+        });
+        Ok(())
+    }
+
+    // Ends when the client disconnects, which also drops its subscription. A client that reads
+    // slowly only holds up this task; the bus drops its oldest events instead
+    async fn stream_events(stream: TcpStream, mut subscription: EventSubscription) {
+        let (reader, mut writer) = stream.into_split();
+        let mut reader = BufReader::new(reader);
+        let mut ignored = Vec::new();
+        info!("Event subscriber connected");
+        if writer.write_all(format!("{}\n", Self::SUBSCRIBED).as_bytes()).await.is_err() {
+            return;
+        }
+        loop {
+            tokio::select! {
+                event = subscription.next() => {
+                    let Some(event) = event else { break };
+                    let line = match serde_json::to_string(&event) {
+                        Ok(line) => line,
+                        Err(e) => {
+                            warn!("Failed to encode an event: {}", e);
+                            continue;
+                        }
+                    };
+                    if writer.write_all(format!("{}\n", line).as_bytes()).await.is_err() {
+                        break;
+                    }
+                }
+                // Clients send nothing after subscribing; end of input means they're gone
+                read = reader.read_until(b'\n', &mut ignored) => match read {
+                    Ok(0) | Err(_) => break,
+                    Ok(_) => ignored.clear(),
+                },
+            }
+        }
+        info!("Event subscriber disconnected");
+    }
+
+    // Answers `first` and every further line of the connection, each of which should be a search
+    // request, until the client hangs up
+    async fn serve_pages(
+        mut lines: Lines<BufReader<TcpStream>>,
+        first: std::result::Result<PageRequest, serde_json::Error>,
+        search_engine: Option<Arc<SearchEngine>>,
+    ) {
+        let mut request = first.map_err(|e| e.to_string());
+        loop {
+            let reply = match (request, search_engine.as_ref()) {
+                (Ok(request), Some(search_engine)) => PageReply::from(
+                    search_engine.search_page(&request.query, request.offset, request.limit, request.generation).await,
+                ),
+                (Err(message), _) => PageReply::Invalid { message },
+                (Ok(_), None) => PageReply::Invalid { message: "This launcher does not serve searches".to_string() },
+            };
+            let line = match serde_json::to_string(&reply) {
+                Ok(line) => line,
+                Err(e) => {
+                    warn!("Failed to encode a search page: {}", e);
+                    return;
+                }
+            };
+            if lines.get_mut().get_mut().write_all(format!("{}\n", line).as_bytes()).await.is_err() {
+                return;
+            }
+
+            request = match lines.next_line().await {
+                Ok(Some(line)) => match PageRequest::decode(&line) {
+                    Some(request) => request.map_err(|e| e.to_string()),
+                    None => Err(format!("Expected a search request, got: {}", line)),
+                },
+                _ => return,
+            };
+        }
+    }
+
+    // Removes the port file so later launches start on their own
+    pub fn close(&self) {
+        if let Err(e) = std::fs::remove_file(&self.port_file) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                warn!("Failed to remove {:?}: {}", self.port_file, e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::{EventKind, LauncherEvent};
+    use crate::jump_list::EXECUTE_ID_FLAG;
+
+    #[tokio::test]
+    async fn jump_list_launches_are_forwarded_to_the_running_instance() {
+        let dir = tempfile::tempdir().unwrap();
+        let port_file = dir.path().join("instance.port");
+        let running = InstanceChannel::with_port_file(port_file.clone());
+        let (sender, mut received) = mpsc::unbounded_channel();
+        running.listen(sender).await.unwrap();
+
+        // What a jump list entry starts the second launch with
+        let arguments = [EXECUTE_ID_FLAG.to_string(), "1234567890".to_string()];
+        let id: ResultId = arguments[1].parse().unwrap();
+        InstanceChannel::with_port_file(port_file).forward(InstanceMessage::Execute(id)).await.unwrap();
+
+        assert_eq!(received.recv().await, Some(InstanceMessage::Execute(id)));
+        running.close();
+    }
+
+    #[tokio::test]
+    async fn without_an_instance_the_launch_runs_itself() {
+        let dir = tempfile::tempdir().unwrap();
+        let port_file = dir.path().join("instance.port");
+        let id: ResultId = "42".parse().unwrap();
+        let forwarded = InstanceChannel::with_port_file(port_file.clone()).forward(InstanceMessage::Execute(id)).await;
+        assert!(matches!(forwarded, Err(InstanceError::NotRunning)));
+
+        // A port file left by a crash points at nothing listening
+        let unused = TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
+        let port = unused.local_addr().unwrap().port();
+        drop(unused);
+        std::fs::write(&port_file, port.to_string()).unwrap();
+        let forwarded = InstanceChannel::with_port_file(port_file).forward(InstanceMessage::Execute(id)).await;
+        assert!(matches!(forwarded, Err(InstanceError::NotRunning)));
+    }
+
+    #[test]
+    fn messages_are_one_line_each() {
+        let id: ResultId = "42".parse().unwrap();
+        let line = InstanceMessage::Execute(id).encode();
+        assert_eq!(line, format!("execute {}\n", id));
+        assert_eq!(InstanceMessage::decode(&line), Some(InstanceMessage::Execute(id)));
+        assert_eq!(InstanceMessage::decode("launch 42"), None);
+        assert_eq!(InstanceMessage::decode("execute nope"), None);
+    }
+
+    async fn running_with_events(dir: &std::path::Path) -> (InstanceChannel, Arc<EventBus>) {
+        let events = Arc::new(EventBus::default());
+        let running = InstanceChannel::with_port_file(dir.join("instance.port")).with_events(events.clone());
+        running.listen(mpsc::unbounded_channel().0).await.unwrap();
+        (running, events)
+    }
+
+    // Subscribers are counted once the server has taken the connection over
+    async fn wait_for_subscribers(events: &EventBus, count: usize) {
+        tokio::time::timeout(std::time::Duration::from_secs(5), async {
+            while events.subscriber_count() != count {
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            }
+        }).await.unwrap();
+    }
+
+    // Only the kinds asked for arrive, one JSON line each
+    #[tokio::test]
+    async fn subscribers_receive_their_events_as_json_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let (running, events) = running_with_events(dir.path()).await;
+        let client = InstanceChannel::with_port_file(dir.path().join("instance.port"));
+        let mut lines = client.subscribe(&EventFilter::only([EventKind::Sync])).await.unwrap();
+        wait_for_subscribers(&events, 1).await;
+
+        events.publish(LauncherEvent::IndexProgress { in_progress: true, items_indexed: 10 });
+        events.publish(LauncherEvent::SyncCompleted);
+        assert_eq!(lines.next_line().await.unwrap().as_deref(), Some("{\"event\":\"sync_completed\"}"));
+        running.close();
+    }
+
+    // The stream task ends and unsubscribes as soon as the client goes, without another event
+    #[tokio::test]
+    async fn a_disconnecting_client_is_unsubscribed() {
+        let dir = tempfile::tempdir().unwrap();
+        let (running, events) = running_with_events(dir.path()).await;
+        let client = InstanceChannel::with_port_file(dir.path().join("instance.port"));
+        let lines = client.subscribe(&EventFilter::default()).await.unwrap();
+        wait_for_subscribers(&events, 1).await;
+
+        drop(lines);
+        wait_for_subscribers(&events, 0).await;
+        running.close();
+    }
+
+    // Either way the connection closes without the acknowledgement
+    #[tokio::test]
+    async fn unknown_kinds_and_instances_without_events_are_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let (running, _events) = running_with_events(dir.path()).await;
+        let port: u16 = std::fs::read_to_string(dir.path().join("instance.port")).unwrap().parse().unwrap();
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+        stream.write_all(b"subscribe sync,indx\n").await.unwrap();
+        assert_eq!(BufReader::new(stream).lines().next_line().await.unwrap(), None);
+        running.close();
+
+        let silent = InstanceChannel::with_port_file(dir.path().join("silent.port"));
+        silent.listen(mpsc::unbounded_channel().0).await.unwrap();
+        let refused = InstanceChannel::with_port_file(dir.path().join("silent.port")).subscribe(&EventFilter::default()).await;
+        assert!(matches!(refused, Err(InstanceError::SubscriptionRefused)));
+        silent.close();
+    }
+}
+
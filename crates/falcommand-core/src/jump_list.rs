@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use log::{info, warn};
+
+use falcommand_config::ResultId;
+use falcommand_platform::{JumpList, JumpListItem};
+use crate::crash::TaskSupervisor;
+use crate::usage::{UsageRecord, UsageStore};
+
+// Command-line flag that runs a result by id in the running instance; used by jump list entries
+pub const EXECUTE_ID_FLAG: &str = "--execute-id";
+
+// A launch a week ago counts half as much as one today
+const RECENCY_HALF_LIFE_DAYS: f64 = 7.0;
+
+// The most frecent launched results, titled as they were last launched. Records without an id
+// (written before ids were kept) are skipped, and so are items whose file is gone
+pub fn jump_list_items<F>(
+    records: &[UsageRecord],
+    now: DateTime<Utc>,
+    limit: usize,
+    executable: &Path,
+    target_exists: F,
+) -> Vec<JumpListItem>
+where
+    F: Fn(&Path) -> bool,
+{
+    let mut scores: HashMap<ResultId, (f64, &UsageRecord)> = HashMap::new();
+    for record in records.iter().filter(|record| record.id != ResultId::default()) {
+        let age_days = (now - record.timestamp).num_seconds().max(0) as f64 / 86400.0;
+        let weight = 0.5f64.powf(age_days / RECENCY_HALF_LIFE_DAYS);
+        let entry = scores.entry(record.id).or_insert((0.0, record));
+        entry.0 += weight;
+        if record.timestamp >= entry.1.timestamp {
+            entry.1 = record;
+        }
+    }
+
+    let mut ranked: Vec<(f64, &UsageRecord)> = scores.into_values()
+        .filter(|(_, record)| record.path.as_deref().is_none_or(&target_exists))
+        .collect();
+    ranked.sort_by(|a, b| {
+        b.0.partial_cmp(&a.0)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.1.title.cmp(&b.1.title))
+    });
+
+    ranked.into_iter()
+        .take(limit)
+        .map(|(_, record)| JumpListItem {
+            title: record.title.clone(),
+            description: record.path.as_ref().map(|path| path.display().to_string()).unwrap_or_default(),
+            executable: executable.to_path_buf(),
+            arguments: vec![EXECUTE_ID_FLAG.to_string(), record.id.to_string()],
+            icon: None,
+        })
+        .collect()
+}
+
+// Republishes the jump list when usage changes, at most once per interval
+pub struct JumpListUpdater {
+    usage: Arc<UsageStore>,
+    jump_list: Arc<dyn JumpList>,
+    executable: PathBuf,
+}
+
+impl JumpListUpdater {
+    // Windows shows about ten recent entries by default
+    pub const MAX_ITEMS: usize = 10;
+    const MIN_UPDATE_INTERVAL: Duration = Duration::from_secs(30);
+
+    pub fn new(usage: Arc<UsageStore>, jump_list: Arc<dyn JumpList>, executable: PathBuf) -> Self {
+        Self { usage, jump_list, executable }
+    }
+
+    pub async fn items(&self) -> Vec<JumpListItem> {
+        jump_list_items(&self.usage.records().await, self.usage.now(), Self::MAX_ITEMS, &self.executable, Path::exists)
+    }
+
+    // The COM call blocks, so it runs off the async workers
+    async fn publish(&self) {
+        let items = self.items().await;
+        let jump_list = self.jump_list.clone();
+        match tokio::task::spawn_blocking(move || jump_list.update_jump_list(items)).await {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => warn!("Failed to update jump list: {}", e),
+            Err(e) => warn!("Jump list update did not finish: {}", e),
+        }
+    }
+
+    pub fn start(self: Arc<Self>) {
+        info!("Starting jump list updates");
+
+        TaskSupervisor::default().spawn("jump-list", move || {
+            let updater = self.clone();
+            async move {
+                let mut changes = updater.usage.subscribe_changes();
+                updater.publish().await;
+
+                loop {
+                    // Launches during the wait are folded into the next update
+                    tokio::time::sleep(Self::MIN_UPDATE_INTERVAL).await;
+                    if changes.changed().await.is_err() {
+                        return;
+                    }
+                    updater.publish().await;
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use falcommand_config::{Action, SearchResult};
+    use falcommand_platform::PlatformError;
+    use crate::testing::manual_clock;
+
+    // Keeps what was published instead of calling the shell
+    #[derive(Default)]
+    struct RecordingJumpList {
+        published: Mutex<Vec<Vec<JumpListItem>>>,
+    }
+
+    impl JumpList for RecordingJumpList {
+        fn update_jump_list(&self, items: Vec<JumpListItem>) -> Result<(), PlatformError> {
+            self.published.lock().unwrap().push(items);
+            Ok(())
+        }
+    }
+
+    fn launched(path: &Path) -> SearchResult {
+        let title = path.file_name().unwrap().to_string_lossy().into_owned();
+        SearchResult::new(title, "").with_action(Action::OpenFile(path.to_path_buf())).with_path(path.to_path_buf())
+    }
+
+    #[tokio::test]
+    async fn items_come_from_frecent_usage() {
+        let dir = tempfile::tempdir().unwrap();
+        let (old, fresh) = (dir.path().join("old.txt"), dir.path().join("fresh.txt"));
+        std::fs::write(&old, "").unwrap();
+        std::fs::write(&fresh, "").unwrap();
+        let clock = manual_clock();
+        let usage = Arc::new(UsageStore::with_storage_path(dir.path().join("usage.json"), clock.clone()).await.unwrap());
+
+        // Three launches three weeks ago weigh less than one today
+        for _ in 0..3 {
+            usage.record(&launched(&old)).await.unwrap();
+        }
+        usage.record(&launched(&dir.path().join("deleted.txt"))).await.unwrap();
+        clock.advance(chrono::Duration::days(21));
+        usage.record(&launched(&fresh)).await.unwrap();
+
+        let jump_list = Arc::new(RecordingJumpList::default());
+        let executable = PathBuf::from("/opt/falcommand/falcommand");
+        let updater = JumpListUpdater::new(usage, jump_list.clone(), executable.clone());
+        updater.publish().await;
+
+        let published = jump_list.published.lock().unwrap().clone();
+        let titles: Vec<_> = published[0].iter().map(|item| item.title.as_str()).collect();
+        assert_eq!(titles, vec!["fresh.txt", "old.txt"]);
+        let item = &published[0][0];
+        assert_eq!(item.executable, executable);
+        assert_eq!(item.arguments, vec![EXECUTE_ID_FLAG.to_string(), launched(&fresh).id.to_string()]);
+    }
+
+    #[test]
+    fn records_without_ids_are_left_out() {
+        let now = crate::testing::start_time();
+        let record = |id: ResultId, title: &str| UsageRecord {
+            id,
+            title: title.to_string(),
+            path: None,
+            category: falcommand_config::Category::Application,
+            timestamp: now,
+        };
+        let records = vec![record(ResultId::default(), "legacy"), record(launched(Path::new("/a")).id, "current")];
+        let items = jump_list_items(&records, now, 10, Path::new("falcommand"), |_| true);
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].title, "current");
+    }
+}
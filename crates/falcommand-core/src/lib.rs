@@ -1,7 +1,130 @@
 pub mod search;
 pub mod index;
 pub mod sync;
+pub mod clock;
+pub mod source;
+pub mod scheduler;
+pub mod executor;
+pub mod crash;
+pub mod arguments;
+pub mod arithmetic;
+pub mod history;
+pub mod profiles;
+pub mod tray;
+pub mod help;
+pub mod secrets;
+pub mod refresh;
+pub mod connectivity;
+pub mod sensitive;
+pub mod share;
+pub mod usage;
+pub mod stats;
+pub mod confirm;
+pub mod metrics;
+pub mod app_names;
+pub mod exclude;
+pub mod hotkeys;
+pub mod config_store;
+pub mod front_menu;
+pub mod archives;
+pub mod spelling;
+pub mod selections;
+pub mod safe_mode;
+pub mod jump_list;
+pub mod instance;
+pub mod format;
+pub mod details;
+pub mod watchdog;
+pub mod moves;
+pub mod scan_backoff;
+pub mod extraction;
+pub mod open_with;
+pub mod scope;
+pub mod doctor;
+pub mod path_audit;
+pub mod usage_sync;
+pub mod app_watch;
+pub mod settings;
+pub mod macros;
+pub mod power;
+pub mod sync_payload;
+pub mod index_check;
+pub mod query_macros;
+pub mod paging;
+pub mod duplicates;
+pub mod path_completion;
+pub mod theme_watch;
+pub mod clipboard_undo;
+pub mod clipboard_context;
+pub mod task_registry;
+pub mod soak;
+pub mod context_boost;
+pub mod events;
+pub mod http;
+#[cfg(test)]
+mod testing;
+// Also compiled for the crate's own tests, so the boundary tests run without the feature
 
 pub use search::*;
 pub use index::*;
-pub use sync::*;
\ No newline at end of file
+pub use sync::*;
+pub use clock::*;
+pub use source::*;
+pub use scheduler::*;
+pub use executor::*;
+pub use crash::*;
+pub use arguments::*;
+pub use arithmetic::*;
+pub use history::*;
+pub use profiles::*;
+pub use tray::*;
+pub use help::*;
+pub use secrets::*;
+pub use refresh::*;
+pub use connectivity::*;
+pub use sensitive::*;
+pub use share::*;
+pub use usage::*;
+pub use stats::*;
+pub use confirm::*;
+pub use metrics::*;
+pub use app_names::*;
+pub use exclude::*;
+pub use hotkeys::*;
+pub use config_store::*;
+pub use front_menu::*;
+pub use archives::*;
+pub use spelling::*;
+pub use selections::*;
+pub use safe_mode::*;
+pub use jump_list::*;
+pub use instance::*;
+pub use format::*;
+pub use details::*;
+pub use watchdog::*;
+pub use moves::*;
+pub use scan_backoff::*;
+pub use extraction::*;
+pub use open_with::*;
+pub use scope::*;
+pub use doctor::*;
+pub use path_audit::*;
+pub use usage_sync::*;
+pub use app_watch::*;
+pub use settings::*;
+pub use macros::*;
+pub use power::*;
+pub use sync_payload::*;
+pub use index_check::*;
+pub use query_macros::*;
+pub use paging::*;
+pub use duplicates::*;
+pub use path_completion::*;
+pub use theme_watch::*;
+pub use clipboard_undo::*;
+pub use clipboard_context::*;
+pub use task_registry::*;
+pub use soak::*;
+pub use context_boost::*;
+pub use events::*;
+pub use http::*;
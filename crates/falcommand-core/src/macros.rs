@@ -0,0 +1,272 @@
+use std::sync::Arc;
+use std::time::Duration;
+use async_trait::async_trait;
+use fuzzy_matcher::{FuzzyMatcher, skim::SkimMatcherV2};
+use tokio::sync::RwLock;
+
+use falcommand_config::{Action, Category, Config, CustomCommand, SearchResult};
+use falcommand_config::launch::display_path;
+use crate::source::SearchSource;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum MacroError {
+    #[error("No custom command is named '{0}'")]
+    UnknownCommand(String),
+    #[error("Custom command '{0}' runs itself")]
+    Cycle(String),
+    // Only one level of nesting is resolved
+    #[error("Custom command '{0}' nests commands more than one level deep")]
+    TooDeep(String),
+}
+
+// One action of a resolved macro, with the name the feedback uses for it
+#[derive(Debug, Clone, PartialEq)]
+pub struct MacroStep {
+    pub label: String,
+    pub action: Action,
+}
+
+// A custom command with its referenced commands expanded into plain steps
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedMacro {
+    pub name: String,
+    pub steps: Vec<MacroStep>,
+    pub step_delay: Duration,
+    pub stop_on_failure: bool,
+}
+
+pub fn find_command<'a>(commands: &'a [CustomCommand], name: &str) -> Option<&'a CustomCommand> {
+    commands.iter().find(|command| command.name.eq_ignore_ascii_case(name))
+}
+
+// How a step is named in "Step 2 (Open https://calendar.example.com) failed"
+pub fn step_label(action: &Action) -> String {
+    match action {
+        Action::ExecuteApplication { path, .. } => format!(
+            "Run {}",
+            path.file_stem().map(|name| name.to_string_lossy().into_owned()).unwrap_or_else(|| display_path(path)),
+        ),
+        Action::OpenFile(path) => format!("Open {}", display_path(path)),
+        Action::OpenUrl(url) => format!("Open {}", url),
+        Action::CopyToClipboard(_) => "Copy to clipboard".to_string(),
+        Action::ExecuteCommand { command, .. } => format!("Run {}", command),
+        Action::SwitchProfile(name) => format!("Switch to profile {}", name),
+        Action::RunCustomCommand(name) => name.clone(),
+        Action::PluginAction { plugin_id, .. } => format!("Run {} action", plugin_id),
+        _ => "Run action".to_string(),
+    }
+}
+
+// The steps of `name`. A step naming another custom command is replaced by that command's
+// actions; those can't name commands in turn, and a command reached again is a cycle
+pub fn resolve_macro(commands: &[CustomCommand], name: &str) -> Result<ResolvedMacro, MacroError> {
+    let command = find_command(commands, name).ok_or_else(|| MacroError::UnknownCommand(name.to_string()))?;
+
+    let mut steps = Vec::new();
+    for action in command.actions() {
+        let Action::RunCustomCommand(ref nested_name) = *action else {
+            steps.push(MacroStep { label: step_label(action), action: action.clone() });
+            continue;
+        };
+        let nested = find_command(commands, nested_name)
+            .ok_or_else(|| MacroError::UnknownCommand(nested_name.clone()))?;
+        if nested.name.eq_ignore_ascii_case(&command.name) {
+            return Err(MacroError::Cycle(command.name.clone()));
+        }
+
+        for nested_action in nested.actions() {
+            match *nested_action {
+                Action::RunCustomCommand(ref inner) if inner.eq_ignore_ascii_case(&command.name) || inner.eq_ignore_ascii_case(&nested.name) => {
+                    return Err(MacroError::Cycle(command.name.clone()));
+                }
+                Action::RunCustomCommand(_) => return Err(MacroError::TooDeep(command.name.clone())),
+                _ => steps.push(MacroStep {
+                    label: format!("{}: {}", nested.name, step_label(nested_action)),
+                    action: nested_action.clone(),
+                }),
+            }
+        }
+    }
+
+    Ok(ResolvedMacro {
+        name: command.name.clone(),
+        steps,
+        step_delay: Duration::from_millis(command.step_delay_ms),
+        stop_on_failure: command.stop_on_failure,
+    })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StepOutcome {
+    pub label: String,
+    // The error message when the step failed
+    pub result: Result<(), String>,
+}
+
+// What running a macro did, step by step; steps skipped after a failure have no outcome
+#[derive(Debug, Clone, PartialEq)]
+pub struct MacroReport {
+    pub total_steps: usize,
+    pub outcomes: Vec<StepOutcome>,
+}
+
+impl MacroReport {
+    pub fn new(total_steps: usize) -> Self {
+        Self { total_steps, outcomes: Vec::new() }
+    }
+
+    pub fn succeeded(&self) -> usize {
+        self.outcomes.iter().filter(|outcome| outcome.result.is_ok()).count()
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.succeeded() == self.total_steps
+    }
+
+    // "3/3 steps succeeded", or the first failing step: "1/3 steps succeeded; step 2 (Open notes.md) failed: ..."
+    pub fn summary(&self) -> String {
+        let counts = format!("{}/{} steps succeeded", self.succeeded(), self.total_steps);
+        let failure = self.outcomes.iter()
+            .enumerate()
+            .find_map(|(index, outcome)| outcome.result.as_ref().err().map(|error| (index, outcome, error)));
+        match failure {
+            Some((index, outcome, error)) => format!("{}; step {} ({}) failed: {}", counts, index + 1, outcome.label, error),
+            None => counts,
+        }
+    }
+}
+
+// Custom commands from the config, found by name like apps
+pub struct CustomCommandSource {
+    config: Arc<RwLock<Config>>,
+}
+
+impl CustomCommandSource {
+    const LIMIT: usize = 5;
+
+    pub fn new(config: Arc<RwLock<Config>>) -> Self {
+        Self { config }
+    }
+
+    fn result(command: &CustomCommand, score: f64) -> SearchResult {
+        let description = match (command.description.is_empty(), command.is_macro()) {
+            (false, _) => command.description.clone(),
+            (true, true) => format!("Macro with {} steps", command.actions().len()),
+            (true, false) => step_label(&command.actions()[0]),
+        };
+        SearchResult::new(command.name.clone(), description)
+            .with_action(Action::RunCustomCommand(command.name.clone()))
+            .with_category(Category::CustomCommand)
+            .with_score(score)
+    }
+}
+
+#[async_trait]
+impl SearchSource for CustomCommandSource {
+    fn name(&self) -> &str {
+        "Custom commands"
+    }
+
+    async fn search(&self, query: &str) -> Vec<SearchResult> {
+        let query = query.trim();
+        if query.is_empty() {
+            return Vec::new();
+        }
+
+        let config = self.config.read().await;
+        let matcher = SkimMatcherV2::default();
+        let mut matches: Vec<(&CustomCommand, i64)> = config.custom_commands.iter()
+            .filter_map(|command| matcher.fuzzy_match(&command.name, query).map(|score| (command, score)))
+            .collect();
+        matches.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.name.cmp(&b.0.name)));
+        matches.truncate(Self::LIMIT);
+
+        matches.into_iter()
+            .map(|(command, score)| Self::result(command, (score as f64 / 100.0).min(1.0)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use falcommand_config::CommandSteps;
+
+    fn command(name: &str, actions: Vec<Action>) -> CustomCommand {
+        CustomCommand {
+            name: name.to_string(),
+            description: String::new(),
+            steps: CommandSteps::Macro { actions },
+            step_delay_ms: 0,
+            stop_on_failure: true,
+        }
+    }
+
+    fn labels(resolved: &ResolvedMacro) -> Vec<&str> {
+        resolved.steps.iter().map(|step| step.label.as_str()).collect()
+    }
+
+    #[test]
+    fn nested_commands_are_expanded_in_place() {
+        let commands = [
+            command("Morning", vec![
+                Action::OpenUrl("https://mail.example.com".to_string()),
+                Action::RunCustomCommand("notes".to_string()),
+                Action::SwitchProfile("work".to_string()),
+            ]),
+            command("Notes", vec![Action::OpenFile("/home/me/notes.md".into())]),
+        ];
+
+        let resolved = resolve_macro(&commands, "morning").unwrap();
+        assert_eq!(resolved.name, "Morning");
+        assert_eq!(labels(&resolved), [
+            "Open https://mail.example.com",
+            &format!("Notes: Open {}", display_path(std::path::Path::new("/home/me/notes.md"))),
+            "Switch to profile work",
+        ]);
+    }
+
+    #[test]
+    fn cycles_and_deep_nesting_are_refused() {
+        let itself = [command("Loop", vec![Action::RunCustomCommand("loop".to_string())])];
+        assert_eq!(resolve_macro(&itself, "Loop"), Err(MacroError::Cycle("Loop".to_string())));
+
+        let mutual = [
+            command("A", vec![Action::RunCustomCommand("B".to_string())]),
+            command("B", vec![Action::RunCustomCommand("A".to_string())]),
+        ];
+        assert_eq!(resolve_macro(&mutual, "A"), Err(MacroError::Cycle("A".to_string())));
+
+        let deep = [
+            command("A", vec![Action::RunCustomCommand("B".to_string())]),
+            command("B", vec![Action::RunCustomCommand("C".to_string())]),
+            command("C", vec![Action::QuitApp]),
+        ];
+        assert_eq!(resolve_macro(&deep, "A"), Err(MacroError::TooDeep("A".to_string())));
+
+        let missing = [command("A", vec![Action::RunCustomCommand("Gone".to_string())])];
+        assert_eq!(resolve_macro(&missing, "A"), Err(MacroError::UnknownCommand("Gone".to_string())));
+    }
+
+    // Commands saved before macros have a single `action` and keep loading as one step
+    #[test]
+    fn single_action_commands_still_load() {
+        let json = r#"{"name": "Mail", "action": {"OpenUrl": "https://mail.example.com"}}"#;
+        let command: CustomCommand = serde_json::from_str(json).unwrap();
+
+        assert!(!command.is_macro());
+        assert!(command.stop_on_failure);
+        assert_eq!(command.actions(), [Action::OpenUrl("https://mail.example.com".to_string())]);
+        assert_eq!(serde_json::from_value::<CustomCommand>(serde_json::to_value(&command).unwrap()).unwrap(), command);
+    }
+
+    #[test]
+    fn summary_names_the_first_failing_step() {
+        let mut report = MacroReport::new(3);
+        report.outcomes.push(StepOutcome { label: "Run code".to_string(), result: Ok(()) });
+        report.outcomes.push(StepOutcome { label: "Open notes.md".to_string(), result: Err("not found".to_string()) });
+
+        assert!(!report.is_success());
+        assert_eq!(report.summary(), "1/3 steps succeeded; step 2 (Open notes.md) failed: not found");
+    }
+}
@@ -0,0 +1,131 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::Duration;
+use log::{debug, warn};
+
+// Hotkey (or show request) until the first frame with the window visible has been rendered
+pub const SHOW_LATENCY: &str = "show_to_first_paint";
+pub const SHOW_LATENCY_BUDGET: Duration = Duration::from_millis(100);
+
+// Rolling latency samples per metric, with an optional budget that is logged when exceeded
+#[derive(Debug, Default)]
+pub struct LatencyMetrics {
+    samples: Mutex<HashMap<String, VecDeque<Duration>>>,
+    budgets: Mutex<HashMap<String, Duration>>,
+}
+
+impl LatencyMetrics {
+    const MAX_SAMPLES: usize = 100;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_budget(self, name: &str, budget: Duration) -> Self {
+        self.budgets.lock().unwrap().insert(name.to_string(), budget);
+        self
+    }
+
+    pub fn budget(&self, name: &str) -> Option<Duration> {
+        self.budgets.lock().unwrap().get(name).copied()
+    }
+
+    pub fn record(&self, name: &str, duration: Duration) {
+        match self.budget(name) {
+            Some(budget) if duration > budget => {
+                warn!("{} took {:?} (budget {:?})", name, duration, budget)
+            }
+            _ => debug!("{} took {:?}", name, duration),
+        }
+
+        let mut samples = self.samples.lock().unwrap();
+        let series = samples.entry(name.to_string()).or_default();
+        series.push_back(duration);
+        if series.len() > Self::MAX_SAMPLES {
+            series.pop_front();
+        }
+    }
+
+    pub fn samples(&self, name: &str) -> Vec<Duration> {
+        self.samples.lock().unwrap()
+            .get(name)
+            .map(|series| series.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    // Nearest-rank percentile over the retained samples
+    pub fn percentile(&self, name: &str, percentile: f64) -> Option<Duration> {
+        let mut samples = self.samples(name);
+        if samples.is_empty() {
+            return None;
+        }
+        samples.sort();
+        let rank = ((percentile.clamp(0.0, 100.0) / 100.0) * samples.len() as f64).ceil() as usize;
+        samples.get(rank.saturating_sub(1)).copied()
+    }
+
+    // True when the 95th percentile is within budget; metrics without samples or a budget pass
+    pub fn within_budget(&self, name: &str) -> bool {
+        match (self.budget(name), self.percentile(name, 95.0)) {
+            (Some(budget), Some(p95)) => p95 <= budget,
+            _ => true,
+        }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn percentiles_use_the_nearest_rank() {
+        let metrics = LatencyMetrics::new();
+        assert_eq!(metrics.percentile(SHOW_LATENCY, 50.0), None);
+
+        for millis in [40, 10, 30, 20, 50] {
+            metrics.record(SHOW_LATENCY, ms(millis));
+        }
+        assert_eq!(metrics.percentile(SHOW_LATENCY, 0.0), Some(ms(10)));
+        assert_eq!(metrics.percentile(SHOW_LATENCY, 50.0), Some(ms(30)));
+        assert_eq!(metrics.percentile(SHOW_LATENCY, 95.0), Some(ms(50)));
+        assert_eq!(metrics.percentile(SHOW_LATENCY, 250.0), Some(ms(50)));
+        // Other metrics are kept apart
+        assert!(metrics.samples("search").is_empty());
+    }
+
+    #[test]
+    fn only_the_latest_samples_are_kept() {
+        let metrics = LatencyMetrics::new();
+        for millis in 0..150 {
+            metrics.record(SHOW_LATENCY, ms(millis));
+        }
+
+        let samples = metrics.samples(SHOW_LATENCY);
+        assert_eq!(samples.len(), 100);
+        assert_eq!(samples[0], ms(50));
+        assert_eq!(samples[99], ms(149));
+    }
+
+    #[test]
+    fn the_budget_is_judged_on_the_95th_percentile() {
+        let metrics = LatencyMetrics::new().with_budget(SHOW_LATENCY, SHOW_LATENCY_BUDGET);
+        assert_eq!(metrics.budget(SHOW_LATENCY), Some(SHOW_LATENCY_BUDGET));
+        assert!(metrics.within_budget(SHOW_LATENCY));
+
+        // One slow show in twenty stays within budget, two don't
+        for _ in 0..19 {
+            metrics.record(SHOW_LATENCY, ms(60));
+        }
+        metrics.record(SHOW_LATENCY, ms(400));
+        assert!(metrics.within_budget(SHOW_LATENCY));
+        metrics.record(SHOW_LATENCY, ms(400));
+        assert!(!metrics.within_budget(SHOW_LATENCY));
+
+        // Without a budget anything passes
+        metrics.record("search", ms(10_000));
+        assert!(metrics.within_budget("search"));
+    }
+}
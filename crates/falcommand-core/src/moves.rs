@@ -0,0 +1,308 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use notify::event::{ModifyKind, RenameMode};
+use notify::{EventKind, RecursiveMode, Watcher};
+use tokio::sync::mpsc;
+use log::{debug, info, warn};
+
+use crate::crash::TaskSupervisor;
+use crate::index::IndexManager;
+use crate::search::SearchEngine;
+
+// `path` after `from` was moved to `to`; None when `path` is not at or below `from`
+pub fn relocated_path(path: &Path, from: &Path, to: &Path) -> Option<PathBuf> {
+    let rest = path.strip_prefix(from).ok()?;
+    if rest.as_os_str().is_empty() {
+        Some(to.to_path_buf())
+    } else {
+        Some(to.join(rest))
+    }
+}
+
+// What the watcher reports, reduced to what move detection needs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FsEvent {
+    Renamed { from: PathBuf, to: PathBuf },
+    Removed(PathBuf),
+    Created(PathBuf),
+}
+
+impl FsEvent {
+    fn from_notify(event: notify::Event) -> Vec<FsEvent> {
+        let mut paths = event.paths.into_iter();
+        match event.kind {
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => match (paths.next(), paths.next()) {
+                (Some(from), Some(to)) => vec![FsEvent::Renamed { from, to }],
+                _ => Vec::new(),
+            },
+            // Halves of a rename the watcher could not pair; the heuristic below tries
+            EventKind::Modify(ModifyKind::Name(RenameMode::From)) | EventKind::Remove(_) => paths.map(FsEvent::Removed).collect(),
+            EventKind::Modify(ModifyKind::Name(RenameMode::To)) | EventKind::Create(_) => paths.map(FsEvent::Created).collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+// Size, modification time and device of a file, compared to pair a removal with a creation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileFingerprint {
+    pub size: u64,
+    pub modified: SystemTime,
+    // None where the platform doesn't expose it
+    pub device: Option<u64>,
+}
+
+impl FileFingerprint {
+    pub fn of(path: &Path) -> Option<Self> {
+        let metadata = std::fs::metadata(path).ok()?;
+        Some(Self {
+            size: metadata.len(),
+            modified: metadata.modified().ok()?,
+            device: device_of(&metadata),
+        })
+    }
+}
+
+#[cfg(unix)]
+fn device_of(metadata: &std::fs::Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(metadata.dev())
+}
+
+#[cfg(not(unix))]
+fn device_of(_metadata: &std::fs::Metadata) -> Option<u64> {
+    None
+}
+
+// Device of the nearest directory that still exists above a removed path
+fn device_near(path: &Path) -> Option<u64> {
+    path.ancestors().skip(1)
+        .find_map(|dir| std::fs::metadata(dir).ok())
+        .and_then(|metadata| device_of(&metadata))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileMove {
+    pub from: PathBuf,
+    pub to: PathBuf,
+}
+
+#[derive(Debug)]
+struct PendingRemoval {
+    path: PathBuf,
+    fingerprint: FileFingerprint,
+    removed_at: Instant,
+}
+
+// Pairs a removal with a creation of the same size and modification time on the same device.
+// Removals nobody claims within the window were deletions
+#[derive(Debug)]
+pub struct MoveDetector {
+    window: Duration,
+    pending: Vec<PendingRemoval>,
+}
+
+impl MoveDetector {
+    pub const WINDOW: Duration = Duration::from_secs(2);
+
+    pub fn new(window: Duration) -> Self {
+        Self { window, pending: Vec::new() }
+    }
+
+    // `fingerprint` is what was known about the file before it disappeared
+    pub fn removed(&mut self, path: PathBuf, fingerprint: FileFingerprint, now: Instant) {
+        self.pending.push(PendingRemoval { path, fingerprint, removed_at: now });
+    }
+
+    // The move that `path` completes, if it looks like one of the pending removals.
+    // A move between devices is a copy and a delete, so it never pairs
+    pub fn created(&mut self, path: &Path, fingerprint: FileFingerprint, now: Instant) -> Option<FileMove> {
+        let position = self.pending.iter().position(|pending| {
+            now.duration_since(pending.removed_at) <= self.window
+                && pending.fingerprint == fingerprint
+                && pending.path != path
+        })?;
+        let pending = self.pending.remove(position);
+        Some(FileMove { from: pending.path, to: path.to_path_buf() })
+    }
+
+    // Removals older than the window, which are now taken as deletions
+    pub fn expire(&mut self, now: Instant) -> Vec<PathBuf> {
+        let (expired, pending): (Vec<_>, Vec<_>) = std::mem::take(&mut self.pending).into_iter()
+            .partition(|pending| now.duration_since(pending.removed_at) > self.window);
+        self.pending = pending;
+        expired.into_iter().map(|pending| pending.path).collect()
+    }
+}
+
+impl Default for MoveDetector {
+    fn default() -> Self {
+        Self::new(Self::WINDOW)
+    }
+}
+
+// Watches the indexed folders and carries usage and index entries over to moved files
+pub struct FileMoveTracker {
+    index_manager: Arc<IndexManager>,
+    // Usage is looked up on each move since switching profiles replaces the store
+    search_engine: Arc<SearchEngine>,
+}
+
+impl FileMoveTracker {
+    pub fn new(index_manager: Arc<IndexManager>, search_engine: Arc<SearchEngine>) -> Self {
+        Self { index_manager, search_engine }
+    }
+
+    // Re-keys the index entries and usage records of `from` to `to`
+    pub async fn apply_move(&self, file_move: &FileMove) {
+        let ids = self.index_manager.move_path(&file_move.from, &file_move.to).await;
+        let usage = self.search_engine.usage_store().await;
+        match usage.migrate_path(&file_move.from, &file_move.to, &ids).await {
+            Ok(0) => {}
+            Ok(moved) => info!("Usage of {} follows it to {} ({} launches)", file_move.from.display(), file_move.to.display(), moved),
+            Err(e) => warn!("Failed to migrate usage of {}: {}", file_move.from.display(), e),
+        }
+    }
+
+    pub async fn apply_delete(&self, path: &Path) {
+        self.index_manager.remove_file(path).await;
+        let usage = self.search_engine.usage_store().await;
+        if let Err(e) = usage.forget_path(path).await {
+            warn!("Failed to forget usage of {}: {}", path.display(), e);
+        }
+    }
+
+    // Feeds one event through `detector`; deletions are only applied once they expire
+    pub async fn handle_event(&self, detector: &mut MoveDetector, event: FsEvent, now: Instant) {
+        match event {
+            FsEvent::Renamed { from, to } => self.apply_move(&FileMove { from, to }).await,
+            FsEvent::Removed(path) => {
+                let Some(file_info) = self.index_manager.file_at(&path).await else {
+                    debug!("Ignoring removal of unindexed {}", path.display());
+                    return;
+                };
+                let fingerprint = FileFingerprint {
+                    size: file_info.size,
+                    modified: file_info.modified,
+                    device: device_near(&path),
+                };
+                detector.removed(path, fingerprint, now);
+            }
+            FsEvent::Created(path) => {
+                let Some(fingerprint) = FileFingerprint::of(&path) else {
+                    return;
+                };
+                if let Some(file_move) = detector.created(&path, fingerprint, now) {
+                    self.apply_move(&file_move).await;
+                }
+            }
+        }
+    }
+
+    pub fn start(self: Arc<Self>, paths: Vec<PathBuf>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            match event {
+                Ok(event) => {
+                    for event in FsEvent::from_notify(event) {
+                        let _ = sender.send(event);
+                    }
+                }
+                Err(e) => warn!("File watcher error: {}", e),
+            }
+        });
+        let mut watcher = match watcher {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                warn!("File watching is unavailable, moved files will lose their usage: {}", e);
+                return;
+            }
+        };
+
+        // Only the top level of each folder is indexed
+        for path in paths.iter().filter(|path| path.exists()) {
+            // The index holds canonical paths, so events should carry them too
+            let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.clone());
+            if let Err(e) = watcher.watch(&path, RecursiveMode::NonRecursive) {
+                warn!("Failed to watch {}: {}", path.display(), e);
+            }
+        }
+
+        let receiver = Arc::new(tokio::sync::Mutex::new(receiver));
+        let watcher = Arc::new(std::sync::Mutex::new(watcher));
+        TaskSupervisor::default().spawn("file-moves", move || {
+            let tracker = self.clone();
+            let receiver = receiver.clone();
+            let watcher = watcher.clone();
+            async move {
+                // Dropping the watcher stops the events
+                let _watcher = watcher;
+                let mut receiver = receiver.lock().await;
+                let mut detector = MoveDetector::default();
+                let mut interval = tokio::time::interval(MoveDetector::WINDOW / 2);
+                loop {
+                    tokio::select! {
+                        event = receiver.recv() => match event {
+                            Some(event) => tracker.handle_event(&mut detector, event, Instant::now()).await,
+                            None => return,
+                        },
+                        _ = interval.tick() => {
+                            for path in detector.expire(Instant::now()) {
+                                tracker.apply_delete(&path).await;
+                            }
+                        }
+                    }
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fingerprint(size: u64, device: Option<u64>) -> FileFingerprint {
+        FileFingerprint { size, modified: SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000), device }
+    }
+
+    #[test]
+    fn paths_below_a_moved_folder_are_relocated() {
+        let (from, to) = (Path::new("/home/me/project"), Path::new("/home/me/archive/project"));
+        assert_eq!(relocated_path(from, from, to), Some(to.to_path_buf()));
+        assert_eq!(relocated_path(Path::new("/home/me/project/src/main.rs"), from, to), Some(to.join("src/main.rs")));
+        // A sibling that shares the name's prefix is not inside the folder
+        assert_eq!(relocated_path(Path::new("/home/me/project-old/a.txt"), from, to), None);
+    }
+
+    #[test]
+    fn a_removal_pairs_with_a_matching_creation_within_the_window() {
+        let start = Instant::now();
+        let mut detector = MoveDetector::new(Duration::from_secs(2));
+        detector.removed(PathBuf::from("/docs/a.txt"), fingerprint(10, Some(1)), start);
+        detector.removed(PathBuf::from("/docs/b.txt"), fingerprint(20, Some(1)), start);
+
+        // A different size is another file
+        assert_eq!(detector.created(Path::new("/docs/c.txt"), fingerprint(30, Some(1)), start), None);
+        let file_move = detector.created(Path::new("/other/b.txt"), fingerprint(20, Some(1)), start + Duration::from_secs(1));
+        assert_eq!(file_move, Some(FileMove { from: PathBuf::from("/docs/b.txt"), to: PathBuf::from("/other/b.txt") }));
+
+        // Nothing claimed a.txt in time, so it was deleted
+        assert!(detector.expire(start + Duration::from_secs(2)).is_empty());
+        assert_eq!(detector.expire(start + Duration::from_secs(3)), vec![PathBuf::from("/docs/a.txt")]);
+        assert!(detector.expire(start + Duration::from_secs(4)).is_empty());
+    }
+
+    #[test]
+    fn moves_between_devices_and_late_creations_are_not_paired() {
+        let start = Instant::now();
+        let mut detector = MoveDetector::new(Duration::from_secs(2));
+        detector.removed(PathBuf::from("/docs/a.txt"), fingerprint(10, Some(1)), start);
+        assert_eq!(detector.created(Path::new("/mnt/usb/a.txt"), fingerprint(10, Some(2)), start), None);
+        // Recreating the same path is an edit, not a move
+        assert_eq!(detector.created(Path::new("/docs/a.txt"), fingerprint(10, Some(1)), start), None);
+        assert_eq!(detector.created(Path::new("/docs/b.txt"), fingerprint(10, Some(1)), start + Duration::from_secs(3)), None);
+        assert_eq!(detector.expire(start + Duration::from_secs(3)), vec![PathBuf::from("/docs/a.txt")]);
+    }
+}
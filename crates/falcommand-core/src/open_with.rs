@@ -0,0 +1,286 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::sync::RwLock;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use log::{info, warn};
+
+use falcommand_config::{Action, Category, Config, ResultId, SearchResult, SyntaxHint};
+use falcommand_config::i18n::tr;
+use falcommand_platform::AppInfo;
+use crate::index::IndexManager;
+use crate::search::SearchEngine;
+use crate::source::SearchSource;
+
+// Lowercase extension without the dot, the key used by the config and the learned associations
+pub fn extension_key(extension: &str) -> String {
+    extension.trim().trim_start_matches('.').to_lowercase()
+}
+
+pub fn file_extension(path: &Path) -> Option<String> {
+    path.extension().map(|extension| extension_key(&extension.to_string_lossy()))
+}
+
+// The query that lists the applications `path` can be opened with
+pub fn open_with_query(path: &Path) -> String {
+    format!("{} \"{}\" ", OpenWithSource::KEYWORD, path.display())
+}
+
+// `openwith "<path>" [filter]` -> the path and the filter typed after it
+pub fn parse_open_with_query(query: &str) -> Option<(PathBuf, String)> {
+    let query = query.trim_start();
+    let rest = query.get(..OpenWithSource::KEYWORD.len())
+        .filter(|keyword| keyword.eq_ignore_ascii_case(OpenWithSource::KEYWORD))
+        .map(|_| &query[OpenWithSource::KEYWORD.len()..])?;
+    let rest = rest.strip_prefix(char::is_whitespace)?.trim_start();
+    let (path, filter) = rest.strip_prefix('"')?.split_once('"')?;
+    if path.is_empty() {
+        return None;
+    }
+    Some((PathBuf::from(path), filter.trim().to_string()))
+}
+
+// Extension -> application name -> times it was picked with Open with…
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OpenWithAssociations {
+    extensions: HashMap<String, HashMap<String, u32>>,
+}
+
+impl OpenWithAssociations {
+    pub fn record(&mut self, extension: &str, app_name: &str) {
+        *self.extensions.entry(extension_key(extension))
+            .or_default()
+            .entry(app_name.to_string())
+            .or_insert(0) += 1;
+    }
+
+    pub fn counts(&self, extension: &str) -> HashMap<String, u32> {
+        self.extensions.get(&extension_key(extension)).cloned().unwrap_or_default()
+    }
+}
+
+// An application offered for a file, with how strongly it is associated with the extension
+#[derive(Debug, Clone)]
+pub struct OpenWithCandidate {
+    pub app: AppInfo,
+    pub picks: u32,
+    pub seeded: bool,
+}
+
+impl OpenWithCandidate {
+    fn associated(&self) -> bool {
+        self.picks > 0 || self.seeded
+    }
+}
+
+// Applications for a file with `extension`: those picked before come first, most picked first,
+// then the ones seeded in the config. With a `filter`, other applications matching it follow
+pub fn open_with_candidates(
+    apps: Vec<AppInfo>,
+    extension: Option<&str>,
+    seeds: &HashMap<String, Vec<String>>,
+    learned: &HashMap<String, u32>,
+    filter: &str,
+) -> Vec<OpenWithCandidate> {
+    let seeded: Vec<String> = extension
+        .and_then(|extension| seeds.iter().find(|(key, _)| extension_key(key) == extension))
+        .map(|(_, names)| names.iter().map(|name| name.to_lowercase()).collect())
+        .unwrap_or_default();
+    let filter = filter.to_lowercase();
+
+    let mut candidates: Vec<OpenWithCandidate> = apps.into_iter()
+        .map(|app| OpenWithCandidate {
+            picks: learned.get(&app.name).copied().unwrap_or(0),
+            seeded: seeded.contains(&app.name.to_lowercase()),
+            app,
+        })
+        .filter(|candidate| candidate.app.name.to_lowercase().contains(&filter))
+        .filter(|candidate| candidate.associated() || !filter.is_empty())
+        .collect();
+
+    candidates.sort_by(|a, b| {
+        b.picks.cmp(&a.picks)
+            .then(b.seeded.cmp(&a.seeded))
+            .then_with(|| a.app.name.to_lowercase().cmp(&b.app.name.to_lowercase()))
+    });
+    candidates.truncate(OpenWithSource::MAX_CANDIDATES);
+    candidates
+}
+
+// Answers `openwith "<path>"`, the query behind a file's "Open with…" action, with the
+// applications that can open it. Picking one is learned for the file's extension
+pub struct OpenWithSource {
+    config: Arc<RwLock<Config>>,
+    index_manager: Arc<IndexManager>,
+    // The learned associations live in the usage store, which switching profiles replaces
+    search_engine: Arc<SearchEngine>,
+    // Results of the latest search -> extension and application, to learn from a launch
+    offered: Mutex<HashMap<ResultId, (String, String)>>,
+}
+
+impl OpenWithSource {
+    pub const KEYWORD: &'static str = "openwith";
+    pub const MAX_CANDIDATES: usize = 20;
+
+    pub fn new(config: Arc<RwLock<Config>>, index_manager: Arc<IndexManager>, search_engine: Arc<SearchEngine>) -> Self {
+        Self {
+            config,
+            index_manager,
+            search_engine,
+            offered: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn candidate_result(path: &Path, candidate: &OpenWithCandidate, position: usize) -> SearchResult {
+        let mut args = candidate.app.args.clone();
+        args.push(path.to_string_lossy().into_owned());
+
+        let file_name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        let description = match candidate.picks {
+            0 => format!("Open {}", file_name),
+            picks => format!("Open {} · picked {} times for this type", file_name, picks),
+        };
+        // Associated apps stay above the rest; the order within is the one computed above
+        let score = if candidate.associated() { 1.0 } else { 0.8 } - position as f64 * 0.001;
+
+        SearchResult::new(format!("Open with {}", candidate.app.name), description)
+            .with_action(Action::ExecuteApplication { path: candidate.app.executable_path.clone(), args })
+            .with_category(Category::Application)
+            .with_score(score)
+    }
+}
+
+#[async_trait]
+impl SearchSource for OpenWithSource {
+    fn name(&self) -> &str {
+        "Open with"
+    }
+
+    fn syntax_hints(&self) -> Vec<SyntaxHint> {
+        vec![SyntaxHint::new(
+            "openwith \"<path>\" [app]",
+            tr("hint.open_with", "Open a file with another application"),
+            "openwith \"report.pdf\"",
+        )]
+    }
+
+    async fn search(&self, query: &str) -> Vec<SearchResult> {
+        let Some((path, filter)) = parse_open_with_query(query) else {
+            return Vec::new();
+        };
+
+        let extension = file_extension(&path);
+        let seeds = self.config.read().await.search.open_with.clone();
+        let learned = match extension {
+            Some(ref extension) => self.search_engine.usage_store().await.open_with_counts(extension).await,
+            None => HashMap::new(),
+        };
+        let apps = self.index_manager.applications().await;
+        let candidates = open_with_candidates(apps, extension.as_deref(), &seeds, &learned, &filter);
+
+        let results: Vec<SearchResult> = candidates.iter()
+            .enumerate()
+            .map(|(position, candidate)| Self::candidate_result(&path, candidate, position))
+            .collect();
+
+        let mut offered = self.offered.lock().unwrap();
+        offered.clear();
+        if let Some(extension) = extension {
+            for (result, candidate) in results.iter().zip(&candidates) {
+                offered.insert(result.id, (extension.clone(), candidate.app.name.clone()));
+            }
+        }
+        results
+    }
+
+    async fn launched(&self, result: &SearchResult) {
+        let Some((extension, app_name)) = self.offered.lock().unwrap().get(&result.id).cloned() else {
+            return;
+        };
+
+        let usage = self.search_engine.usage_store().await;
+        match usage.record_open_with(&extension, &app_name).await {
+            Ok(()) => info!("Learned that .{} files open with {}", extension, app_name),
+            Err(e) => warn!("Failed to record the application for .{} files: {}", extension, e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::manual_clock;
+    use crate::usage::UsageStore;
+
+    fn apps() -> Vec<AppInfo> {
+        ["Firefox", "Google Chrome", "Code", "Vim", "GIMP"].into_iter()
+            .map(|name| AppInfo::new(name, PathBuf::from(format!("/usr/bin/{}", name.to_lowercase()))))
+            .collect()
+    }
+
+    fn names(candidates: &[OpenWithCandidate]) -> Vec<&str> {
+        candidates.iter().map(|candidate| candidate.app.name.as_str()).collect()
+    }
+
+    #[test]
+    fn queries_carry_the_path_and_a_filter() {
+        let path = Path::new("/home/me/My Report.html");
+        let query = open_with_query(path);
+        assert_eq!(parse_open_with_query(&query), Some((path.to_path_buf(), String::new())));
+        assert_eq!(parse_open_with_query(&format!("{}fire ", query)), Some((path.to_path_buf(), "fire".to_string())));
+        assert_eq!(parse_open_with_query("OpenWith \"a.txt\""), Some((PathBuf::from("a.txt"), String::new())));
+
+        for query in ["openwith a.txt", "openwith \"\"", "openwithx \"a.txt\"", "openwith \"a.txt"] {
+            assert_eq!(parse_open_with_query(query), None, "{:?}", query);
+        }
+        assert_eq!(file_extension(path).as_deref(), Some("html"));
+        assert_eq!(extension_key(" .HTML "), "html");
+    }
+
+    #[test]
+    fn only_apps_associated_with_the_extension_are_offered() {
+        let seeds = HashMap::from([(".HTML".to_string(), vec!["firefox".to_string(), "google chrome".to_string()])]);
+        let learned = HashMap::from([("Code".to_string(), 2), ("Google Chrome".to_string(), 1)]);
+
+        // Most picked first, then the seeded ones; Vim and GIMP have nothing to do with .html
+        let candidates = open_with_candidates(apps(), Some("html"), &seeds, &learned, "");
+        assert_eq!(names(&candidates), ["Code", "Google Chrome", "Firefox"]);
+        assert!(candidates[2].seeded && candidates[2].picks == 0);
+
+        // Another extension has no seeds, and without a filter nothing to offer
+        assert!(open_with_candidates(apps(), Some("png"), &seeds, &HashMap::new(), "").is_empty());
+        // A filter brings in any matching app, after the associated ones
+        let filtered = open_with_candidates(apps(), Some("png"), &seeds, &HashMap::from([("GIMP".to_string(), 1)]), "i");
+        assert_eq!(names(&filtered), ["GIMP", "Firefox", "Vim"]);
+        let filtered = open_with_candidates(apps(), Some("html"), &seeds, &learned, "CO");
+        assert_eq!(names(&filtered), ["Code"]);
+    }
+
+    #[tokio::test]
+    async fn picks_are_learned_per_extension_and_persisted() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("usage.json");
+        let usage = UsageStore::with_storage_path(path.clone(), manual_clock()).await.unwrap();
+        usage.record_open_with("HTML", "Firefox").await.unwrap();
+        usage.record_open_with(".html", "Firefox").await.unwrap();
+        usage.record_open_with("html", "Code").await.unwrap();
+        usage.record_open_with("md", "Code").await.unwrap();
+        drop(usage);
+
+        let reloaded = UsageStore::with_storage_path(path, manual_clock()).await.unwrap();
+        let learned = reloaded.open_with_counts(".Html").await;
+        assert_eq!(learned, HashMap::from([("Firefox".to_string(), 2), ("Code".to_string(), 1)]));
+        assert!(reloaded.open_with_counts("pdf").await.is_empty());
+
+        // The learned favourite goes to the top of the chooser
+        let candidates = open_with_candidates(apps(), Some("html"), &HashMap::new(), &learned, "");
+        assert_eq!(names(&candidates), ["Firefox", "Code"]);
+        let result = OpenWithSource::candidate_result(Path::new("/tmp/index.html"), &candidates[0], 0);
+        assert_eq!(result.description, "Open index.html · picked 2 times for this type");
+        assert_eq!(result.action, Action::ExecuteApplication {
+            path: PathBuf::from("/usr/bin/firefox"),
+            args: vec!["/tmp/index.html".to_string()],
+        });
+    }
+}
@@ -0,0 +1,183 @@
+use std::collections::VecDeque;
+use serde::{Deserialize, Serialize};
+
+use falcommand_config::SearchResult;
+
+// Paged searches rank at most this many results; later pages end there
+pub const MAX_PAGED_RESULTS: usize = 500;
+
+// Results per page when a client gives no limit
+pub const DEFAULT_PAGE_SIZE: usize = 20;
+
+// Verb of a page request on the instance channel: "search {json}"
+pub const SEARCH_VERB: &str = "search";
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum PageError {
+    // The pages fetched so far no longer line up with the index; start over from offset 0
+    #[error("The index changed since the first page (generation {cursor}, now {current}); search again from the start")]
+    IndexChanged { cursor: u64, current: u64 },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchPage {
+    pub results: Vec<SearchResult>,
+    pub offset: usize,
+    // Ranked results across all pages, at most MAX_PAGED_RESULTS
+    pub total: usize,
+    // Index generation the pages were ranked at; passed back with the next page's request
+    pub generation: u64,
+    // None on the last page
+    pub next_offset: Option<usize>,
+}
+
+impl SearchPage {
+    // "Results 21-40 of 120"
+    pub fn describe(&self) -> String {
+        match self.results.len() {
+            0 => format!("No results from {} of {}", self.offset + 1, self.total),
+            shown => format!("Results {}-{} of {}", self.offset + 1, self.offset + shown, self.total),
+        }
+    }
+}
+
+// One page asked of the running launcher. The first page leaves `generation` out; the following
+// ones pass back the generation and next_offset of the page before
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PageRequest {
+    pub query: String,
+    #[serde(default)]
+    pub offset: usize,
+    #[serde(default = "default_page_size")]
+    pub limit: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generation: Option<u64>,
+}
+
+fn default_page_size() -> usize {
+    DEFAULT_PAGE_SIZE
+}
+
+impl PageRequest {
+    pub fn first(query: impl Into<String>, limit: usize) -> Self {
+        Self { query: query.into(), offset: 0, limit, generation: None }
+    }
+
+    // The request line, newline included
+    pub fn encode(&self) -> String {
+        let json = serde_json::to_string(self).expect("page requests always serialize");
+        format!("{} {}\n", SEARCH_VERB, json)
+    }
+
+    // None when `line` isn't a page request at all
+    pub fn decode(line: &str) -> Option<Result<Self, serde_json::Error>> {
+        let (verb, json) = line.trim().split_once(' ')?;
+        (verb == SEARCH_VERB).then(|| serde_json::from_str(json))
+    }
+}
+
+// The reply to a page request, one JSON line
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum PageReply {
+    Page(SearchPage),
+    // PageError::IndexChanged: search again from offset 0 without a generation
+    IndexChanged { cursor: u64, current: u64 },
+    // The request line couldn't be read, or this launcher doesn't serve searches
+    Invalid { message: String },
+}
+
+impl From<Result<SearchPage, PageError>> for PageReply {
+    fn from(page: Result<SearchPage, PageError>) -> Self {
+        match page {
+            Ok(page) => PageReply::Page(page),
+            Err(PageError::IndexChanged { cursor, current }) => PageReply::IndexChanged { cursor, current },
+        }
+    }
+}
+
+pub fn page_of(results: &[SearchResult], offset: usize, limit: usize, generation: u64) -> SearchPage {
+    let start = offset.min(results.len());
+    let end = start.saturating_add(limit).min(results.len());
+    SearchPage {
+        results: results[start..end].to_vec(),
+        offset: start,
+        total: results.len(),
+        generation,
+        next_offset: (end < results.len()).then_some(end),
+    }
+}
+
+// Every ranked result of a query at one index generation
+#[derive(Debug, Clone)]
+struct RankedQuery {
+    query: String,
+    generation: u64,
+    results: Vec<SearchResult>,
+}
+
+// The full rankings of recent paged queries, so later pages are sliced from the same list instead
+// of ranked again, which could reorder them
+#[derive(Debug, Default)]
+pub struct PageCache {
+    entries: VecDeque<RankedQuery>,
+}
+
+impl PageCache {
+    const CAPACITY: usize = 8;
+
+    pub fn get(&self, query: &str, generation: u64) -> Option<&[SearchResult]> {
+        self.entries.iter()
+            .find(|entry| entry.query == query && entry.generation == generation)
+            .map(|entry| entry.results.as_slice())
+    }
+
+    pub fn insert(&mut self, query: &str, generation: u64, results: Vec<SearchResult>) {
+        // Rankings from older generations can't be asked for again
+        self.entries.retain(|entry| entry.query != query && entry.generation == generation);
+        if self.entries.len() == Self::CAPACITY {
+            self.entries.pop_back();
+        }
+        self.entries.push_front(RankedQuery { query: query.to_string(), generation, results });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn results(count: usize) -> Vec<SearchResult> {
+        (0..count).map(|index| SearchResult::new(format!("Result {}", index), "")).collect()
+    }
+
+    #[test]
+    fn pages_end_exactly_at_the_last_result() {
+        let results = results(45);
+
+        let second = page_of(&results, 20, 20, 1);
+        assert_eq!((second.offset, second.results.len(), second.next_offset), (20, 20, Some(40)));
+        let last = page_of(&results, 40, 20, 1);
+        assert_eq!((last.results.len(), last.next_offset), (5, None));
+        let past = page_of(&results, 60, 20, 1);
+        assert_eq!((past.offset, past.results.len(), past.next_offset), (45, 0, None));
+    }
+
+    #[test]
+    fn page_requests_round_trip_as_instance_lines() {
+        let request = PageRequest { query: "fire fox".to_string(), offset: 20, limit: 10, generation: Some(3) };
+
+        assert_eq!(PageRequest::decode(&request.encode()).unwrap().unwrap(), request);
+        assert!(PageRequest::decode("execute 42").is_none());
+        let first = PageRequest::decode("search {\"query\":\"code\"}").unwrap().unwrap();
+        assert_eq!(first, PageRequest::first("code", DEFAULT_PAGE_SIZE));
+    }
+
+    #[test]
+    fn index_change_is_a_distinct_reply() {
+        let reply = PageReply::from(Err(PageError::IndexChanged { cursor: 2, current: 5 }));
+        let json = serde_json::to_value(&reply).unwrap();
+
+        assert_eq!(json, serde_json::json!({ "status": "index_changed", "cursor": 2, "current": 5 }));
+        assert_eq!(serde_json::from_value::<PageReply>(json).unwrap(), reply);
+    }
+}
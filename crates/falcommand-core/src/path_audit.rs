@@ -0,0 +1,261 @@
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use log::warn;
+
+use falcommand_config::Config;
+use crate::arguments::expand_home;
+use crate::index::current_os;
+use crate::sensitive::SensitivePaths;
+
+// Why an include path won't index what its author expects. Advisory: the config is saved anyway
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IncludePathIssue {
+    Missing,
+    NotADirectory,
+    // Already covered by this other include path, as written in the config
+    NestedIn(String),
+    // Under a credential or browser profile directory that is never indexed
+    Sensitive,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IncludePathFinding {
+    // As written in the config
+    pub path: String,
+    pub issue: IncludePathIssue,
+}
+
+impl IncludePathFinding {
+    pub fn message(&self) -> String {
+        match self.issue {
+            IncludePathIssue::Missing => format!("{} does not exist", self.path),
+            IncludePathIssue::NotADirectory => format!("{} is not a folder", self.path),
+            IncludePathIssue::NestedIn(ref parent) => format!("{} is already inside {}", self.path, parent),
+            IncludePathIssue::Sensitive => format!("{} is a protected folder and is never indexed", self.path),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IncludePathReport {
+    pub findings: Vec<IncludePathFinding>,
+}
+
+impl IncludePathReport {
+    pub fn is_empty(&self) -> bool {
+        self.findings.is_empty()
+    }
+
+    // One line for a notification: "2 search paths could not be found"
+    pub fn summary(&self) -> Option<String> {
+        let missing = self.findings.iter()
+            .filter(|finding| matches!(finding.issue, IncludePathIssue::Missing | IncludePathIssue::NotADirectory))
+            .count();
+        let others = self.findings.len() - missing;
+        let plural = |count: usize| if count == 1 { "search path" } else { "search paths" };
+
+        match (missing, others) {
+            (0, 0) => None,
+            (missing, 0) => Some(format!("{} {} could not be found", missing, plural(missing))),
+            (0, others) => Some(format!("{} {} will not be indexed as written", others, plural(others))),
+            (missing, others) => Some(format!(
+                "{} {} could not be found and {} more will not be indexed as written",
+                missing, plural(missing), others,
+            )),
+        }
+    }
+
+    // Identifies the set of problems, so the same ones are only announced once
+    pub fn fingerprint(&self) -> String {
+        let mut lines: Vec<String> = self.findings.iter().map(IncludePathFinding::message).collect();
+        lines.sort();
+        lines.join("\n")
+    }
+}
+
+// Case-insensitive file systems compare paths without case; canonicalizing resolves symlinks and `..`
+fn comparison_key(path: &Path) -> PathBuf {
+    let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    if cfg!(any(target_os = "windows", target_os = "macos")) {
+        PathBuf::from(path.to_string_lossy().to_lowercase())
+    } else {
+        path
+    }
+}
+
+// Checks each include path, as written, for the problems above
+pub fn audit_include_paths(paths: &[String], sensitive: &SensitivePaths) -> IncludePathReport {
+    let expanded: Vec<PathBuf> = paths.iter().map(|path| PathBuf::from(expand_home(path))).collect();
+    let keys: Vec<PathBuf> = expanded.iter().map(|path| comparison_key(path)).collect();
+
+    let mut report = IncludePathReport::default();
+    for (index, (path, expanded)) in paths.iter().zip(&expanded).enumerate() {
+        let issue = match std::fs::metadata(expanded) {
+            Err(_) => Some(IncludePathIssue::Missing),
+            Ok(metadata) if !metadata.is_dir() => Some(IncludePathIssue::NotADirectory),
+            Ok(_) if sensitive.is_denied(&std::fs::canonicalize(expanded).unwrap_or_else(|_| expanded.clone())) => {
+                Some(IncludePathIssue::Sensitive)
+            }
+            // A path listed twice counts as nested in its first occurrence
+            Ok(_) => keys.iter()
+                .enumerate()
+                .find(|&(other, key)| other != index && keys[index].starts_with(key) && (keys[index] != *key || other < index))
+                .map(|(other, _)| IncludePathIssue::NestedIn(paths[other].clone())),
+        };
+        if let Some(issue) = issue {
+            report.findings.push(IncludePathFinding { path: path.clone(), issue });
+        }
+    }
+    report
+}
+
+// The include paths of this platform in `config`, with its sensitive path exceptions
+pub fn audit_config_include_paths(config: &Config) -> IncludePathReport {
+    let paths = config.search.include_paths.get(current_os()).cloned().unwrap_or_default();
+    audit_include_paths(&paths, &SensitivePaths::for_current_user(&config.search.allow_sensitive_paths))
+}
+
+// Remembers which problems were last announced at startup, so each set is announced once
+#[derive(Debug, Clone)]
+pub struct IncludePathNotice {
+    marker_path: PathBuf,
+}
+
+impl IncludePathNotice {
+    pub fn new(marker_path: PathBuf) -> Self {
+        Self { marker_path }
+    }
+
+    // The notification text if `report` has problems not announced before; records them as announced
+    pub fn take_summary(&self, report: &IncludePathReport) -> Option<String> {
+        let fingerprint = report.fingerprint();
+        let announced = std::fs::read_to_string(&self.marker_path).unwrap_or_default();
+        if announced == fingerprint {
+            return None;
+        }
+
+        if let Some(parent) = self.marker_path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        if let Err(e) = std::fs::write(&self.marker_path, &fingerprint) {
+            warn!("Failed to record the announced search path problems: {}", e);
+        }
+        report.summary()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sensitive::default_sensitive_dirs;
+
+    fn issues(report: &IncludePathReport) -> Vec<(&str, &IncludePathIssue)> {
+        report.findings.iter().map(|finding| (finding.path.as_str(), &finding.issue)).collect()
+    }
+
+    fn path(path: &Path) -> String {
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn each_kind_of_problem_is_found() {
+        let dir = tempfile::tempdir().unwrap();
+        let home = std::fs::canonicalize(dir.path()).unwrap();
+        let documents = home.join("Documents");
+        let projects = documents.join("projects");
+        let ssh = home.join(".ssh");
+        for folder in [&projects, &ssh] {
+            std::fs::create_dir_all(folder).unwrap();
+        }
+        std::fs::write(home.join("notes.txt"), "").unwrap();
+
+        let paths = [documents.clone(), projects.clone(), home.join("Progam Files"), home.join("notes.txt"), ssh.clone()]
+            .map(|folder| path(&folder));
+        let sensitive = SensitivePaths::new(default_sensitive_dirs(&home), &[]);
+        let report = audit_include_paths(&paths, &sensitive);
+        assert_eq!(issues(&report), [
+            (paths[1].as_str(), &IncludePathIssue::NestedIn(paths[0].clone())),
+            (paths[2].as_str(), &IncludePathIssue::Missing),
+            (paths[3].as_str(), &IncludePathIssue::NotADirectory),
+            (paths[4].as_str(), &IncludePathIssue::Sensitive),
+        ]);
+
+        // Allowing the sensitive folder clears that finding only
+        let allowed = SensitivePaths::new(default_sensitive_dirs(&home), &[path(&ssh)]);
+        assert_eq!(audit_include_paths(&paths, &allowed).findings.len(), 3);
+    }
+
+    // Spelled differently, the same folder is still nested; a duplicate is nested in its first listing
+    #[test]
+    fn nesting_compares_resolved_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = std::fs::canonicalize(dir.path()).unwrap();
+        std::fs::create_dir_all(root.join("a").join("b")).unwrap();
+        let sensitive = SensitivePaths::new(Vec::new(), &[]);
+
+        let dotted = format!("{}/a/b/../b", path(&root));
+        let report = audit_include_paths(&[path(&root.join("a")), dotted.clone()], &sensitive);
+        assert_eq!(issues(&report), [(dotted.as_str(), &IncludePathIssue::NestedIn(path(&root.join("a"))))]);
+
+        let twice = [path(&root.join("a")), path(&root.join("a"))];
+        assert_eq!(issues(&audit_include_paths(&twice, &sensitive)), [(twice[1].as_str(), &IncludePathIssue::NestedIn(twice[0].clone()))]);
+
+        // Siblings sharing a name prefix are not nested
+        std::fs::create_dir_all(root.join("ab")).unwrap();
+        assert!(audit_include_paths(&[path(&root.join("a")), path(&root.join("ab"))], &sensitive).is_empty());
+    }
+
+    #[cfg(any(target_os = "windows", target_os = "macos"))]
+    #[test]
+    fn nesting_ignores_case_where_the_file_system_does() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = std::fs::canonicalize(dir.path()).unwrap();
+        std::fs::create_dir_all(root.join("Docs").join("Work")).unwrap();
+        let upper = path(&root.join("DOCS").join("Work"));
+        let report = audit_include_paths(&[path(&root.join("Docs")), upper.clone()], &SensitivePaths::new(Vec::new(), &[]));
+        assert_eq!(issues(&report), [(upper.as_str(), &IncludePathIssue::NestedIn(path(&root.join("Docs"))))]);
+    }
+
+    fn finding(path: &str, issue: IncludePathIssue) -> IncludePathFinding {
+        IncludePathFinding { path: path.to_string(), issue }
+    }
+
+    #[test]
+    fn summaries_count_missing_and_other_problems() {
+        let missing = finding("C:\\Progam Files", IncludePathIssue::Missing);
+        let file = finding("C:\\notes.txt", IncludePathIssue::NotADirectory);
+        let nested = finding("C:\\a\\b", IncludePathIssue::NestedIn("C:\\a".to_string()));
+        let report = |findings: &[&IncludePathFinding]| IncludePathReport { findings: findings.iter().map(|f| (*f).clone()).collect() };
+
+        assert_eq!(report(&[]).summary(), None);
+        assert_eq!(report(&[&missing]).summary().unwrap(), "1 search path could not be found");
+        assert_eq!(report(&[&missing, &file]).summary().unwrap(), "2 search paths could not be found");
+        assert_eq!(report(&[&nested]).summary().unwrap(), "1 search path will not be indexed as written");
+        assert_eq!(
+            report(&[&missing, &file, &nested]).summary().unwrap(),
+            "2 search paths could not be found and 1 more will not be indexed as written",
+        );
+        assert_eq!(nested.message(), "C:\\a\\b is already inside C:\\a");
+    }
+
+    // The same problems are announced at one startup only, whatever their order; new ones are announced again
+    #[test]
+    fn repeated_startup_warnings_are_announced_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let notice = IncludePathNotice::new(dir.path().join("state").join("include-paths.txt"));
+        let missing = finding("/mnt/share", IncludePathIssue::Missing);
+        let file = finding("/home/me/notes.txt", IncludePathIssue::NotADirectory);
+
+        let first = IncludePathReport { findings: vec![missing.clone(), file.clone()] };
+        assert_eq!(notice.take_summary(&first).unwrap(), "2 search paths could not be found");
+        assert_eq!(notice.take_summary(&first), None);
+        assert_eq!(notice.take_summary(&IncludePathReport { findings: vec![file.clone(), missing.clone()] }), None);
+
+        let changed = IncludePathReport { findings: vec![missing.clone()] };
+        assert_eq!(notice.take_summary(&changed).unwrap(), "1 search path could not be found");
+
+        // Fixed, then broken the same way again: announced again
+        assert_eq!(notice.take_summary(&IncludePathReport::default()), None);
+        assert!(notice.take_summary(&changed).is_some());
+    }
+}
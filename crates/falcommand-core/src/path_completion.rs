@@ -0,0 +1,362 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use async_trait::async_trait;
+use log::debug;
+
+use falcommand_config::{Action, Category, Config, SearchResult, SyntaxHint};
+use falcommand_config::i18n::tr;
+use falcommand_config::launch::display_path;
+use crate::arguments::expand_home;
+use crate::exclude::path_excluded;
+use crate::sensitive::SensitivePaths;
+use crate::source::SearchSource;
+
+// Entries listed per directory
+pub const MAX_COMPLETIONS: usize = 100;
+// Entries read before giving up on finding more matches in a huge directory
+const MAX_SCANNED_ENTRIES: usize = 20_000;
+
+// A path being typed, split at its last separator: "~/Doc" is the directory "~/" and the prefix
+// "Doc". `typed_dir` keeps the user's spelling so completions extend what they typed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathQuery {
+    pub typed_dir: String,
+    pub prefix: String,
+    pub separator: char,
+}
+
+fn is_separator(c: char) -> bool {
+    c == '/' || c == '\\'
+}
+
+// "/usr/lo", "~/Doc", "./src", "../", "C:\Pro", "c:/", "\\server\share\": the forms shells complete,
+// with either separator on any OS. A bare word or "~" alone is not a path yet
+pub fn is_path_shaped(query: &str) -> bool {
+    let mut chars = query.chars();
+    match (chars.next(), chars.next(), chars.next()) {
+        (Some('/'), _, _) => true,
+        (Some('\\'), Some('\\'), _) => true,
+        (Some('~'), Some(second), _) => is_separator(second),
+        (Some('.'), Some(second), _) if is_separator(second) => true,
+        (Some('.'), Some('.'), Some(third)) => is_separator(third),
+        (Some(drive), Some(':'), Some(third)) => drive.is_ascii_alphabetic() && is_separator(third),
+        _ => false,
+    }
+}
+
+pub fn parse_path_query(query: &str) -> Option<PathQuery> {
+    if !is_path_shaped(query) {
+        return None;
+    }
+    let split = query.rfind(is_separator)?;
+    let separator = query[split..].chars().next()?;
+    Some(PathQuery {
+        typed_dir: query[..=split].to_string(),
+        prefix: query[split + 1..].to_string(),
+        separator,
+    })
+}
+
+impl PathQuery {
+    // The directory to list: "~" is the home directory and relative paths start there too, since
+    // the launcher has no working directory of its own
+    pub fn directory(&self) -> Option<PathBuf> {
+        if self.typed_dir.starts_with('.') {
+            return Some(dirs::home_dir()?.join(&self.typed_dir));
+        }
+        Some(PathBuf::from(expand_home(&self.typed_dir)))
+    }
+
+    // The query that lists the inside of `name`, or completes to it
+    pub fn completed(&self, name: &str, is_dir: bool) -> String {
+        let mut query = format!("{}{}", self.typed_dir, name);
+        if is_dir {
+            query.push(self.separator);
+        }
+        query
+    }
+
+    fn matches(&self, name: &str) -> bool {
+        // Hidden entries only once a dot is typed, as in shells
+        if name.starts_with('.') && !self.prefix.starts_with('.') {
+            return false;
+        }
+        name.to_lowercase().starts_with(&self.prefix.to_lowercase())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathEntry {
+    pub name: String,
+    pub path: PathBuf,
+    pub is_dir: bool,
+}
+
+// Directories before files; within each, names matching the typed case first, then by name
+pub fn rank_path_entries(entries: &mut [PathEntry], prefix: &str) {
+    entries.sort_by(|a, b| {
+        b.is_dir.cmp(&a.is_dir)
+            .then_with(|| b.name.starts_with(prefix).cmp(&a.name.starts_with(prefix)))
+            .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+    });
+}
+
+// Longest start shared by every name, compared case-insensitively and spelled as in the first
+pub fn common_prefix<'a>(names: impl IntoIterator<Item = &'a str>) -> String {
+    let mut names = names.into_iter();
+    let Some(first) = names.next() else {
+        return String::new();
+    };
+    let mut length = first.len();
+    for name in names {
+        length = first.char_indices()
+            .zip(name.chars())
+            .take_while(|((_, a), b)| a.to_lowercase().eq(b.to_lowercase()))
+            .map(|((index, a), _)| index + a.len_utf8())
+            .last()
+            .unwrap_or(0)
+            .min(length);
+    }
+    first[..length].to_string()
+}
+
+// What the exclude patterns and the sensitive-directory deny list hide
+pub struct PathFilter {
+    exclude_patterns: Vec<String>,
+    sensitive: SensitivePaths,
+}
+
+impl PathFilter {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            exclude_patterns: config.search.exclude_patterns.clone(),
+            sensitive: SensitivePaths::for_current_user(&config.search.allow_sensitive_paths),
+        }
+    }
+
+    fn hides(&self, path: &Path) -> bool {
+        self.sensitive.is_denied(path) || path_excluded(&path.to_string_lossy(), &self.exclude_patterns)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathListing {
+    // Ranked, at most MAX_COMPLETIONS
+    Entries(Vec<PathEntry>),
+    // The typed directory doesn't exist, or isn't one
+    NotFound(PathBuf),
+}
+
+// Reads `query`'s directory for entries starting with its prefix
+pub fn list_path_completions(query: &PathQuery, filter: &PathFilter) -> Option<PathListing> {
+    let mut listing = matching_entries(query, filter)?;
+    if let PathListing::Entries(ref mut entries) = listing {
+        entries.truncate(MAX_COMPLETIONS);
+    }
+    Some(listing)
+}
+
+// Every match, ranked; completing to a common prefix has to see the ones past the cap too
+fn matching_entries(query: &PathQuery, filter: &PathFilter) -> Option<PathListing> {
+    let dir = query.directory()?;
+    if !dir.is_dir() {
+        return Some(PathListing::NotFound(dir));
+    }
+    if filter.hides(&dir) {
+        return Some(PathListing::Entries(Vec::new()));
+    }
+    let reader = match std::fs::read_dir(&dir) {
+        Ok(reader) => reader,
+        Err(e) => {
+            debug!("Can't list {}: {}", dir.display(), e);
+            return Some(PathListing::Entries(Vec::new()));
+        }
+    };
+
+    let mut entries: Vec<PathEntry> = reader
+        .take(MAX_SCANNED_ENTRIES)
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if !query.matches(&name) {
+                return None;
+            }
+            let path = entry.path();
+            if filter.hides(&path) {
+                return None;
+            }
+            // Symlinks count as what they point to
+            let is_dir = path.is_dir();
+            Some(PathEntry { name, path, is_dir })
+        })
+        .collect();
+    rank_path_entries(&mut entries, &query.prefix);
+    Some(PathListing::Entries(entries))
+}
+
+// Tab: the query extended to the longest prefix its entries share, descending into a directory
+// when it is the only match. None when there is nothing to add
+pub fn complete_path_query(query: &str, filter: &PathFilter) -> Option<String> {
+    let path_query = parse_path_query(query)?;
+    let PathListing::Entries(entries) = matching_entries(&path_query, filter)? else {
+        return None;
+    };
+    let completed = match entries.as_slice() {
+        [] => return None,
+        [only] => path_query.completed(&only.name, only.is_dir),
+        entries => {
+            let shared = common_prefix(entries.iter().map(|entry| entry.name.as_str()));
+            // Names differing only in case can share less than was typed
+            if shared.chars().count() <= path_query.prefix.chars().count() {
+                return None;
+            }
+            path_query.completed(&shared, false)
+        }
+    };
+    (completed != query).then_some(completed)
+}
+
+// Lists the directory entries of a path being typed, like shell completion, whether or not the
+// directory is indexed
+pub struct PathCompletionSource {
+    config: Arc<RwLock<Config>>,
+}
+
+impl PathCompletionSource {
+    pub fn new(config: Arc<RwLock<Config>>) -> Self {
+        Self { config }
+    }
+
+    fn entry_result(query: &PathQuery, entry: &PathEntry, position: usize) -> SearchResult {
+        let title = if entry.is_dir {
+            format!("{}{}", entry.name, query.separator)
+        } else {
+            entry.name.clone()
+        };
+        let result = SearchResult::new(title, display_path(&entry.path))
+            .with_action(Action::OpenFile(entry.path.clone()))
+            .with_category(Category::File)
+            .with_path(entry.path.clone())
+            .with_score(0.97 - position as f64 * 0.001);
+        if entry.is_dir {
+            result.with_secondary_action("Show contents", Action::SetQuery(query.completed(&entry.name, true)))
+        } else {
+            result
+        }
+    }
+
+    // Where the typed path stops existing, with a way back to the deepest part that does
+    fn not_found_result(query: &PathQuery, missing: &Path) -> SearchResult {
+        let existing = missing.ancestors().skip(1).find(|dir| dir.is_dir());
+        let result = SearchResult::new(format!("Path not found: {}", query.typed_dir), display_path(missing))
+            .with_category(Category::SystemCommand)
+            .with_score(0.97);
+        match existing {
+            Some(dir) => {
+                let mut back = display_path(dir);
+                if !back.ends_with(is_separator) {
+                    back.push(query.separator);
+                }
+                result.with_action(Action::SetQuery(back))
+            }
+            None => result,
+        }
+    }
+}
+
+#[async_trait]
+impl SearchSource for PathCompletionSource {
+    fn name(&self) -> &str {
+        "Path completion"
+    }
+
+    async fn search(&self, query: &str) -> Vec<SearchResult> {
+        let Some(path_query) = parse_path_query(query) else {
+            return Vec::new();
+        };
+        let filter = PathFilter::from_config(&*self.config.read().await);
+        let listing_query = path_query.clone();
+        let listing = tokio::task::spawn_blocking(move || list_path_completions(&listing_query, &filter))
+            .await
+            .ok()
+            .flatten();
+
+        match listing {
+            Some(PathListing::Entries(entries)) => entries.iter()
+                .enumerate()
+                .map(|(position, entry)| Self::entry_result(&path_query, entry, position))
+                .collect(),
+            Some(PathListing::NotFound(missing)) => vec![Self::not_found_result(&path_query, &missing)],
+            None => Vec::new(),
+        }
+    }
+
+    fn syntax_hints(&self) -> Vec<SyntaxHint> {
+        vec![SyntaxHint::new(
+            "/path, ~/path, C:\\path",
+            tr("hint.path_completion", "List a folder's entries as you type a path; Tab completes"),
+            "~/Doc",
+        )]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn typed(dir: &Path, rest: &str) -> String {
+        format!("{}/{}", dir.display(), rest)
+    }
+
+    fn listed(query: &str) -> Vec<PathEntry> {
+        let query = parse_path_query(query).unwrap();
+        match list_path_completions(&query, &PathFilter::from_config(&Config::default())) {
+            Some(PathListing::Entries(entries)) => entries,
+            other => panic!("expected entries, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn path_shapes() {
+        for query in ["/usr/lo", "\\\\server\\share", "~/Doc", "~\\Doc", "./src", "../", "C:\\Pro", "c:/"] {
+            assert!(is_path_shaped(query), "{}", query);
+        }
+        for query in ["firefox", "~", "~user", "\\server", ".vimrc", "C:", "a/b", ""] {
+            assert!(!is_path_shaped(query), "{}", query);
+        }
+
+        let query = parse_path_query("~\\Doc").unwrap();
+        assert_eq!((query.typed_dir.as_str(), query.prefix.as_str(), query.separator), ("~\\", "Doc", '\\'));
+        assert_eq!(query.completed("Documents", true), "~\\Documents\\");
+    }
+
+    #[test]
+    fn listings_stop_at_the_cap() {
+        let dir = tempfile::tempdir().unwrap();
+        for i in 0..MAX_COMPLETIONS + 20 {
+            std::fs::write(dir.path().join(format!("file{:03}", i)), "").unwrap();
+        }
+        let entries = listed(&typed(dir.path(), "file"));
+        assert_eq!(entries.len(), MAX_COMPLETIONS);
+        assert_eq!(entries[0].name, "file000");
+
+        // Tab still sees the matches past the cap
+        assert_eq!(complete_path_query(&typed(dir.path(), "fi"), &PathFilter::from_config(&Config::default())),
+            Some(typed(dir.path(), "file")));
+    }
+
+    #[test]
+    fn directories_rank_above_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("alpha.txt"), "").unwrap();
+        std::fs::create_dir(dir.path().join("zulu")).unwrap();
+        std::fs::write(dir.path().join(".hidden"), "").unwrap();
+
+        let names: Vec<_> = listed(&typed(dir.path(), "")).into_iter().map(|entry| (entry.name, entry.is_dir)).collect();
+        assert_eq!(names, vec![("zulu".to_string(), true), ("alpha.txt".to_string(), false)]);
+        // The only match is a directory, so Tab descends into it
+        assert_eq!(complete_path_query(&typed(dir.path(), "z"), &PathFilter::from_config(&Config::default())),
+            Some(typed(dir.path(), "zulu/")));
+    }
+}
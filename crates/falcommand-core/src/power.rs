@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+use log::{info, warn};
+
+use falcommand_config::{Config, PowerConfig};
+use falcommand_platform::{PlatformProvider, PowerStatus};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerDecision {
+    Run,
+    // On battery below the configured threshold
+    Defer { percent: u8, threshold: u8 },
+}
+
+impl PowerDecision {
+    pub fn allows(&self) -> bool {
+        *self == PowerDecision::Run
+    }
+}
+
+// Background work is deferred on battery below the threshold; on AC, or without a battery
+// reading, it always runs
+pub fn power_decision(status: PowerStatus, config: &PowerConfig) -> PowerDecision {
+    match (config.defer_on_battery, status.on_ac, status.battery_percent) {
+        (true, false, Some(percent)) if percent < config.battery_threshold_percent => PowerDecision::Defer {
+            percent,
+            threshold: config.battery_threshold_percent,
+        },
+        _ => PowerDecision::Run,
+    }
+}
+
+// "Background work: deferred (on battery at 18%, below 30%)"
+pub fn power_summary(status: PowerStatus, decision: PowerDecision) -> String {
+    match decision {
+        PowerDecision::Run => format!("Background work: running ({})", status.describe()),
+        PowerDecision::Defer { percent, threshold } => {
+            format!("Background work: deferred (on battery at {}%, below {}%)", percent, threshold)
+        }
+    }
+}
+
+// Consulted by periodic background work (auto-sync, connectivity probes, history maintenance,
+// app rescans) before each run. Work the user asked for never asks
+pub struct PowerPolicy {
+    platform_provider: Arc<dyn PlatformProvider>,
+    config: Arc<RwLock<Config>>,
+    // Reading the status runs a command on some platforms, so a recent one is reused
+    reading: Mutex<Option<(Instant, PowerStatus)>>,
+    // The last decision logged per task, so each change is logged once
+    logged: Mutex<HashMap<String, PowerDecision>>,
+}
+
+impl std::fmt::Debug for PowerPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PowerPolicy")
+            .field("reading", &self.reading)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PowerPolicy {
+    const READING_TTL: Duration = Duration::from_secs(60);
+
+    pub fn new(platform_provider: Arc<dyn PlatformProvider>, config: Arc<RwLock<Config>>) -> Self {
+        Self {
+            platform_provider,
+            config,
+            reading: Mutex::new(None),
+            logged: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // A status that can't be read counts as AC, so a broken reading never stops background work
+    pub async fn status(&self) -> PowerStatus {
+        if let Some((read_at, status)) = *self.reading.lock().unwrap() {
+            if read_at.elapsed() < Self::READING_TTL {
+                return status;
+            }
+        }
+
+        let platform_provider = self.platform_provider.clone();
+        let status = match tokio::task::spawn_blocking(move || platform_provider.get_power_status()).await {
+            Ok(Ok(status)) => status,
+            Ok(Err(e)) => {
+                warn!("Failed to read the power status: {}", e);
+                PowerStatus::AC
+            }
+            Err(e) => {
+                warn!("Power status reader stopped: {}", e);
+                PowerStatus::AC
+            }
+        };
+        *self.reading.lock().unwrap() = Some((Instant::now(), status));
+        status
+    }
+
+    pub async fn decision(&self) -> PowerDecision {
+        let status = self.status().await;
+        power_decision(status, &self.config.read().await.power)
+    }
+
+    pub async fn summary(&self) -> String {
+        let status = self.status().await;
+        power_summary(status, power_decision(status, &self.config.read().await.power))
+    }
+
+    // Whether the periodic `task` may run now
+    pub async fn allows(&self, task: &str) -> bool {
+        let decision = self.decision().await;
+        let previous = self.logged.lock().unwrap().insert(task.to_string(), decision);
+        if previous != Some(decision) {
+            match decision {
+                PowerDecision::Defer { percent, threshold } => {
+                    info!("Deferring {} on battery ({}%, below {}%)", task, percent, threshold);
+                }
+                PowerDecision::Run if previous.is_some() => info!("Resuming {}", task),
+                PowerDecision::Run => {}
+            }
+        }
+        decision.allows()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn battery(percent: u8) -> PowerStatus {
+        PowerStatus { on_ac: false, battery_percent: Some(percent) }
+    }
+
+    fn config(threshold: u8) -> PowerConfig {
+        PowerConfig { defer_on_battery: true, battery_threshold_percent: threshold }
+    }
+
+    #[test]
+    fn only_a_low_battery_defers() {
+        assert_eq!(power_decision(battery(18), &config(30)), PowerDecision::Defer { percent: 18, threshold: 30 });
+        assert_eq!(power_decision(battery(30), &config(30)), PowerDecision::Run);
+        assert_eq!(power_decision(battery(80), &config(30)), PowerDecision::Run);
+        // Plugged in, unknown charge, or deferral turned off
+        assert_eq!(power_decision(PowerStatus { on_ac: true, battery_percent: Some(5) }, &config(30)), PowerDecision::Run);
+        assert_eq!(power_decision(PowerStatus { on_ac: false, battery_percent: None }, &config(30)), PowerDecision::Run);
+        assert_eq!(power_decision(PowerStatus::AC, &config(30)), PowerDecision::Run);
+        let off = PowerConfig { defer_on_battery: false, ..config(30) };
+        assert_eq!(power_decision(battery(5), &off), PowerDecision::Run);
+    }
+
+    #[test]
+    fn summaries_for_status() {
+        let deferred = power_decision(battery(18), &config(30));
+        assert!(!deferred.allows());
+        assert_eq!(power_summary(battery(18), deferred), "Background work: deferred (on battery at 18%, below 30%)");
+        assert_eq!(power_summary(battery(45), PowerDecision::Run), "Background work: running (on battery (45%))");
+        assert_eq!(power_summary(PowerStatus::AC, PowerDecision::Run), "Background work: running (on AC)");
+    }
+}
@@ -0,0 +1,243 @@
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use async_trait::async_trait;
+use log::{info, warn};
+
+use falcommand_config::{Action, Category, Config, ConfigError, PathResolver, SearchResult, SyntaxHint};
+use falcommand_config::i18n::tr;
+use crate::clock::Clock;
+use crate::config_store::ConfigStore;
+use crate::history::{HistoryError, SearchHistory};
+use crate::usage::{UsageError, UsageStore};
+use crate::search::SearchEngine;
+use crate::source::SearchSource;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ProfileError {
+    #[error("Failed to load profile configuration: {0}")]
+    Config(#[from] ConfigError),
+
+    #[error("Failed to load profile history: {0}")]
+    History(#[from] HistoryError),
+
+    #[error("Failed to load profile usage data: {0}")]
+    Usage(#[from] UsageError),
+
+    #[error("Failed to access profile directory: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Invalid profile name: {0}")]
+    InvalidName(String),
+}
+
+// Owns the active profile and swaps the per-profile stores when it changes
+pub struct ProfileManager {
+    config_store: Arc<ConfigStore>,
+    search_engine: Arc<SearchEngine>,
+    clock: Arc<dyn Clock>,
+    resolver: RwLock<PathResolver>,
+}
+
+impl ProfileManager {
+    pub const DEFAULT_PROFILE: &'static str = "default";
+
+    pub fn new(
+        config_store: Arc<ConfigStore>,
+        search_engine: Arc<SearchEngine>,
+        clock: Arc<dyn Clock>,
+        resolver: PathResolver,
+    ) -> Self {
+        Self {
+            config_store,
+            search_engine,
+            clock,
+            resolver: RwLock::new(resolver),
+        }
+    }
+
+    pub async fn resolver(&self) -> PathResolver {
+        self.resolver.read().await.clone()
+    }
+
+    pub async fn current_profile(&self) -> Option<String> {
+        self.resolver.read().await.profile().map(|p| p.to_string())
+    }
+
+    pub async fn list_profiles(&self) -> Vec<String> {
+        let profiles_dir = self.resolver.read().await.profiles_dir();
+        let mut entries = match tokio::fs::read_dir(&profiles_dir).await {
+            Ok(entries) => entries,
+            Err(_) => return Vec::new(),
+        };
+
+        let mut profiles = Vec::new();
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            if entry.file_type().await.map(|t| t.is_dir()).unwrap_or(false) {
+                profiles.push(entry.file_name().to_string_lossy().into_owned());
+            }
+        }
+
+        profiles.sort();
+        profiles
+    }
+
+    // Reloads the config layer stack, history and usage data for `name`; "default" returns to the base profile
+    pub async fn switch_profile(&self, name: &str) -> std::result::Result<(), ProfileError> {
+        let name = name.trim();
+        if name.contains(['/', '\\']) || name == ".." {
+            return Err(ProfileError::InvalidName(name.to_string()));
+        }
+
+        let profile = if name.is_empty() || name.eq_ignore_ascii_case(Self::DEFAULT_PROFILE) {
+            None
+        } else {
+            Some(name.to_string())
+        };
+
+        let resolver = self.resolver.read().await.with_profile(profile);
+        if let Some(dir) = resolver.profile_dir() {
+            tokio::fs::create_dir_all(&dir).await?;
+        }
+
+        let config = Config::load_with_resolver(&resolver).await?;
+        let history = SearchHistory::new(&resolver, self.clock.clone()).await?;
+        let usage = UsageStore::new(&resolver, self.clock.clone()).await?;
+
+        self.config_store.replace(config, resolver.active_config_file()).await?;
+        self.search_engine.set_history(Arc::new(history)).await;
+        self.search_engine.set_usage_store(Arc::new(usage)).await;
+        self.search_engine.apply_history_retention().await;
+        *self.resolver.write().await = resolver;
+
+        info!("Switched to profile '{}'", name);
+        Ok(())
+    }
+}
+
+// Answers "profile" with the known profiles and "profile <name>" with a switch action
+pub struct ProfileSource {
+    manager: Arc<ProfileManager>,
+}
+
+impl ProfileSource {
+    pub const KEYWORD: &'static str = "profile";
+
+    pub fn new(manager: Arc<ProfileManager>) -> Self {
+        Self { manager }
+    }
+
+    fn switch_result(name: &str, current: bool) -> SearchResult {
+        let description = if current {
+            "Current profile".to_string()
+        } else {
+            format!("Switch to the '{}' profile", name)
+        };
+
+        SearchResult::new(format!("Profile: {}", name), description)
+            .with_action(Action::SwitchProfile(name.to_string()))
+            .with_category(Category::SystemCommand)
+            .with_score(1.0)
+    }
+}
+
+#[async_trait]
+impl SearchSource for ProfileSource {
+    fn name(&self) -> &str {
+        "Profiles"
+    }
+
+    fn syntax_hints(&self) -> Vec<SyntaxHint> {
+        vec![SyntaxHint::new(
+            "profile <name>",
+            tr("hint.profile", "Switch to another profile"),
+            "profile work",
+        )]
+    }
+
+    async fn search(&self, query: &str) -> Vec<SearchResult> {
+        let query = query.trim();
+        let Some(rest) = query.get(..Self::KEYWORD.len())
+            .filter(|keyword| keyword.eq_ignore_ascii_case(Self::KEYWORD))
+            .map(|_| &query[Self::KEYWORD.len()..])
+        else {
+            return Vec::new();
+        };
+        if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+            return Vec::new();
+        }
+
+        let requested = rest.trim();
+        let current = self.manager.current_profile().await
+            .unwrap_or_else(|| ProfileManager::DEFAULT_PROFILE.to_string());
+
+        let mut profiles = self.manager.list_profiles().await;
+        profiles.insert(0, ProfileManager::DEFAULT_PROFILE.to_string());
+
+        let requested_lower = requested.to_lowercase();
+        let mut results: Vec<SearchResult> = profiles.iter()
+            .filter(|name| name.to_lowercase().starts_with(&requested_lower))
+            .map(|name| Self::switch_result(name, *name == current))
+            .collect();
+
+        // Switching to an unknown name creates the profile on first use
+        if !requested.is_empty() && !profiles.iter().any(|name| name == requested) {
+            if requested.contains(['/', '\\']) {
+                warn!("Ignoring invalid profile name: {}", requested);
+            } else {
+                let mut result = Self::switch_result(requested, false).with_score(0.9);
+                result.description = format!("Create the '{}' profile and switch to it", requested);
+                results.push(result);
+            }
+        }
+
+        results
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::IndexManager;
+    use crate::testing::{manual_clock, resolver};
+
+    async fn manager(dir: &std::path::Path) -> (ProfileManager, Arc<SearchEngine>, Arc<ConfigStore>) {
+        let clock = manual_clock();
+        let resolver = resolver(dir);
+        let config_store = Arc::new(ConfigStore::new(Config::default(), resolver.config_file()));
+        let index = Arc::new(IndexManager::new(config_store.shared()).await.unwrap());
+        let history = Arc::new(SearchHistory::new(&resolver, clock.clone()).await.unwrap());
+        let usage = Arc::new(UsageStore::new(&resolver, clock.clone()).await.unwrap());
+        let engine = Arc::new(SearchEngine::new(config_store.shared(), index, history, usage).await.unwrap());
+        let manager = ProfileManager::new(config_store.clone(), engine.clone(), clock, resolver);
+        (manager, engine, config_store)
+    }
+
+    // Each profile sees only its own history, and switching back finds the earlier one intact
+    #[tokio::test]
+    async fn switching_profiles_swaps_the_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let (manager, engine, config_store) = manager(dir.path()).await;
+        engine.add_to_history("firefox", &SearchResult::new("Firefox", "")).await;
+
+        manager.switch_profile("work").await.unwrap();
+        assert_eq!(manager.current_profile().await.as_deref(), Some("work"));
+        assert_eq!(config_store.save_path().await, dir.path().join("config/profiles/work/config.json"));
+        assert_eq!(engine.suggest_completion("fi").await, None);
+        engine.add_to_history("figma", &SearchResult::new("Figma", "")).await;
+        assert!(dir.path().join("config/profiles/work/search_history.json").exists());
+
+        manager.switch_profile("Default").await.unwrap();
+        assert_eq!(manager.current_profile().await, None);
+        assert_eq!(engine.suggest_completion("fi").await.as_deref(), Some("firefox"));
+        assert_eq!(manager.list_profiles().await, ["work"]);
+    }
+
+    #[tokio::test]
+    async fn profile_names_cannot_leave_the_profiles_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let (manager, _, _) = manager(dir.path()).await;
+
+        assert!(matches!(manager.switch_profile("../other").await, Err(ProfileError::InvalidName(_))));
+        assert!(matches!(manager.switch_profile("..").await, Err(ProfileError::InvalidName(_))));
+        assert_eq!(manager.current_profile().await, None);
+    }
+}
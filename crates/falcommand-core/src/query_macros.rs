@@ -0,0 +1,220 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use async_trait::async_trait;
+use tokio::sync::RwLock;
+
+use falcommand_config::{Action, Category, Config, SearchResult, SyntaxHint};
+use falcommand_config::i18n::tr;
+use crate::source::SearchSource;
+
+// `@inv 2024` runs the query saved as "inv"
+pub const MACRO_PREFIX: char = '@';
+// Replaced by what follows the macro name; without it, that text is appended
+pub const ARGS_PLACEHOLDER: &str = "{args}";
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum QueryMacroError {
+    #[error("'{0}' can't be a macro name; use letters, digits, '-' and '_'")]
+    InvalidName(String),
+    #[error("Macro '{0}' has an empty query")]
+    EmptyTemplate(String),
+    // Macros expand once, so a macro naming a macro would never run it
+    #[error("Macro '{name}' refers to macro '{referenced}'; macros can't use other macros")]
+    Recursive { name: String, referenced: String },
+}
+
+// A typed macro and the query it searches
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MacroExpansion {
+    pub name: String,
+    pub query: String,
+}
+
+pub fn is_valid_macro_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+}
+
+// "@inv 2024" -> ("inv", "2024")
+pub fn macro_invocation(query: &str) -> Option<(&str, &str)> {
+    let rest = query.trim_start().strip_prefix(MACRO_PREFIX)?;
+    let (name, args) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+    is_valid_macro_name(name).then(|| (name, args.trim()))
+}
+
+pub fn find_query_macro<'a>(macros: &'a HashMap<String, String>, name: &str) -> Option<(&'a String, &'a String)> {
+    macros.iter().find(|(macro_name, _)| macro_name.eq_ignore_ascii_case(name))
+}
+
+pub fn expand_template(template: &str, args: &str) -> String {
+    let expanded = if template.contains(ARGS_PLACEHOLDER) {
+        template.replace(ARGS_PLACEHOLDER, args)
+    } else if args.is_empty() {
+        template.to_string()
+    } else {
+        format!("{} {}", template, args)
+    };
+    expanded.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// The query `query` stands for, when it starts with a saved macro's name
+pub fn expand_query_macro(query: &str, macros: &HashMap<String, String>) -> Option<MacroExpansion> {
+    let (name, args) = macro_invocation(query)?;
+    let (name, template) = find_query_macro(macros, name)?;
+    Some(MacroExpansion { name: name.clone(), query: expand_template(template, args) })
+}
+
+// Shown under the input so what is actually searched is visible: "@inv → in:~/work ext:pdf invoice 2024"
+pub fn expansion_hint(expansion: &MacroExpansion) -> String {
+    format!("{}{} → {}", MACRO_PREFIX, expansion.name, expansion.query)
+}
+
+// Checks a macro before it is saved as `name`, next to the macros already saved
+pub fn validate_query_macro(macros: &HashMap<String, String>, name: &str, template: &str) -> Result<(), QueryMacroError> {
+    if !is_valid_macro_name(name) {
+        return Err(QueryMacroError::InvalidName(name.to_string()));
+    }
+    if template.trim().is_empty() {
+        return Err(QueryMacroError::EmptyTemplate(name.to_string()));
+    }
+
+    let referenced = template.split_whitespace()
+        .filter_map(|word| word.strip_prefix(MACRO_PREFIX))
+        .find(|word| word.eq_ignore_ascii_case(name) || find_query_macro(macros, word).is_some());
+    match referenced {
+        Some(referenced) => Err(QueryMacroError::Recursive { name: name.to_string(), referenced: referenced.to_string() }),
+        None => Ok(()),
+    }
+}
+
+// Offers to save "@name <query>" as a macro when no macro has that name yet
+pub struct QueryMacroSource {
+    config: Arc<RwLock<Config>>,
+}
+
+impl QueryMacroSource {
+    pub fn new(config: Arc<RwLock<Config>>) -> Self {
+        Self { config }
+    }
+}
+
+#[async_trait]
+impl SearchSource for QueryMacroSource {
+    fn name(&self) -> &str {
+        "Query macros"
+    }
+
+    async fn search(&self, query: &str) -> Vec<SearchResult> {
+        let Some((name, template)) = macro_invocation(query) else {
+            return Vec::new();
+        };
+        if template.is_empty() {
+            return Vec::new();
+        }
+        let config = self.config.read().await;
+        if find_query_macro(&config.search.query_macros, name).is_some() {
+            return Vec::new();
+        }
+
+        let description = match validate_query_macro(&config.search.query_macros, name, template) {
+            Ok(()) => template.to_string(),
+            Err(e) => e.to_string(),
+        };
+        vec![SearchResult::new(format!("Save as macro {}{}…", MACRO_PREFIX, name), description)
+            .with_action(Action::SaveQueryMacro { name: name.to_string(), template: template.to_string() })
+            .with_category(Category::SystemCommand)
+            .with_score(0.9)]
+    }
+
+    fn syntax_hints(&self) -> Vec<SyntaxHint> {
+        vec![SyntaxHint::new(
+            "@<name> <query>",
+            tr("hint.query_macro", "Run a saved query, or save the query under a new name"),
+            "@inv in:~/work ext:pdf invoice {args}",
+        )]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn macros() -> HashMap<String, String> {
+        HashMap::from([
+            ("inv".to_string(), "in:~/work ext:pdf invoice {args}".to_string()),
+            ("Notes".to_string(), "in:~/notes".to_string()),
+        ])
+    }
+
+    #[test]
+    fn invocations_are_a_name_and_the_rest() {
+        assert_eq!(macro_invocation("@inv 2024 march"), Some(("inv", "2024 march")));
+        assert_eq!(macro_invocation("  @inv"), Some(("inv", "")));
+        assert_eq!(macro_invocation("@my-macro_2\targs "), Some(("my-macro_2", "args")));
+        assert_eq!(macro_invocation("inv 2024"), None);
+        assert_eq!(macro_invocation("@ 2024"), None);
+        assert_eq!(macro_invocation("@in:v 2024"), None);
+    }
+
+    #[test]
+    fn expansion_with_and_without_args() {
+        let expand = |query: &str| expand_query_macro(query, &macros()).map(|expansion| expansion.query);
+        assert_eq!(expand("@inv 2024").as_deref(), Some("in:~/work ext:pdf invoice 2024"));
+        // An unused placeholder leaves no stray space
+        assert_eq!(expand("@inv").as_deref(), Some("in:~/work ext:pdf invoice"));
+        // Without a placeholder the args are appended
+        assert_eq!(expand("@notes todo").as_deref(), Some("in:~/notes todo"));
+        assert_eq!(expand("@notes").as_deref(), Some("in:~/notes"));
+        assert_eq!(expand("@unknown 2024"), None);
+        assert_eq!(expand("invoice"), None);
+
+        assert_eq!(expand_template("{args} and {args}", "x"), "x and x");
+    }
+
+    // Names match whatever the case, and the saved spelling is shown
+    #[test]
+    fn hint_shows_what_is_searched() {
+        let expansion = expand_query_macro("@INV 2024", &macros()).unwrap();
+        assert_eq!(expansion.name, "inv");
+        assert_eq!(expansion_hint(&expansion), "@inv → in:~/work ext:pdf invoice 2024");
+        let expansion = expand_query_macro("@notes", &macros()).unwrap();
+        assert_eq!(expansion_hint(&expansion), "@Notes → in:~/notes");
+    }
+
+    #[test]
+    fn macros_may_not_use_macros() {
+        let saved = macros();
+        assert_eq!(validate_query_macro(&saved, "tax", "ext:pdf tax {args}"), Ok(()));
+        assert_eq!(
+            validate_query_macro(&saved, "tax", "@inv tax"),
+            Err(QueryMacroError::Recursive { name: "tax".to_string(), referenced: "inv".to_string() }),
+        );
+        assert_eq!(
+            validate_query_macro(&saved, "loop", "ext:pdf @LOOP"),
+            Err(QueryMacroError::Recursive { name: "loop".to_string(), referenced: "LOOP".to_string() }),
+        );
+        // An @ word that names no macro is just search text
+        assert_eq!(validate_query_macro(&saved, "mail", "from @someone"), Ok(()));
+
+        assert_eq!(validate_query_macro(&saved, "in v", "x"), Err(QueryMacroError::InvalidName("in v".to_string())));
+        assert_eq!(validate_query_macro(&saved, "empty", "  "), Err(QueryMacroError::EmptyTemplate("empty".to_string())));
+    }
+
+    // "@name <query>" offers to save under an unused name, and says why it can't be saved
+    #[tokio::test]
+    async fn unused_names_offer_to_save_the_query() {
+        let mut config = Config::default();
+        config.search.query_macros = macros();
+        let source = QueryMacroSource::new(Arc::new(RwLock::new(config)));
+
+        let results = source.search("@tax ext:pdf tax").await;
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].title, "Save as macro @tax…");
+        assert_eq!(results[0].action, Action::SaveQueryMacro { name: "tax".to_string(), template: "ext:pdf tax".to_string() });
+
+        let refused = source.search("@tax @inv").await;
+        assert_eq!(refused[0].description, "Macro 'tax' refers to macro 'inv'; macros can't use other macros");
+
+        assert!(source.search("@inv 2024").await.is_empty());
+        assert!(source.search("@tax").await.is_empty());
+    }
+}
@@ -0,0 +1,198 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+use async_trait::async_trait;
+use log::info;
+
+use falcommand_config::{Config, ResultId, SearchResult};
+
+// Producers of results that go stale while displayed (timers, weather snapshots)
+#[async_trait]
+pub trait ResultRefresher: Send + Sync {
+    fn refresh_interval(&self, result: &SearchResult) -> Option<Duration>;
+    async fn refresh(&self, result: &SearchResult) -> Option<SearchResult>;
+}
+
+// Refreshed results keep their category and action, and so their id
+pub fn same_result(a: &SearchResult, b: &SearchResult) -> bool {
+    a.id == b.id
+}
+
+// Replaces the matching result in place so list order and selection are untouched
+pub fn swap_refreshed(results: &mut [SearchResult], refreshed: SearchResult) -> bool {
+    match results.iter_mut().find(|r| same_result(r, &refreshed)) {
+        Some(slot) => {
+            *slot = refreshed;
+            true
+        }
+        None => false,
+    }
+}
+
+// Refresh loop over the on-screen results, only running while the window is shown
+pub struct LiveRefresh {
+    refresher: Arc<dyn ResultRefresher>,
+    handle: std::sync::Mutex<Option<JoinHandle<()>>>,
+}
+
+impl LiveRefresh {
+    const TICK: Duration = Duration::from_secs(1);
+
+    pub fn new(refresher: Arc<dyn ResultRefresher>) -> Self {
+        Self {
+            refresher,
+            handle: std::sync::Mutex::new(None),
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.handle.lock().unwrap()
+            .as_ref()
+            .map(|handle| !handle.is_finished())
+            .unwrap_or(false)
+    }
+
+    // `redraw` is called after every swap with the updated results, for the window to repaint them
+    pub fn start<F>(&self, results: Arc<RwLock<Vec<SearchResult>>>, config: Arc<RwLock<Config>>, redraw: F)
+    where
+        F: Fn(&[SearchResult], &Config) + Send + Sync + 'static,
+    {
+        self.stop();
+        info!("Starting live result refresh");
+
+        let refresher = self.refresher.clone();
+        let handle = tokio::spawn(async move {
+            let mut interval_timer = tokio::time::interval(Self::TICK);
+            let mut last_refreshed: Vec<(ResultId, Instant)> = Vec::new();
+
+            loop {
+                interval_timer.tick().await;
+                let visible = config.read().await.appearance.visible_result_rows;
+                let snapshot: Vec<SearchResult> = results.read().await
+                    .iter()
+                    .take(visible)
+                    .cloned()
+                    .collect();
+
+                // Forget results that scrolled away or were replaced by a new query
+                last_refreshed.retain(|(id, _)| snapshot.iter().any(|r| r.id == *id));
+
+                for result in snapshot {
+                    let Some(interval) = refresher.refresh_interval(&result) else {
+                        continue;
+                    };
+
+                    let now = Instant::now();
+                    let due = last_refreshed.iter()
+                        .find(|(id, _)| *id == result.id)
+                        .map(|(_, last)| now.duration_since(*last) >= interval)
+                        .unwrap_or(true);
+                    if !due {
+                        continue;
+                    }
+
+                    last_refreshed.retain(|(id, _)| *id != result.id);
+                    last_refreshed.push((result.id, now));
+
+                    let Some(refreshed) = refresher.refresh(&result).await else {
+                        continue;
+                    };
+                    let mut current = results.write().await;
+                    if swap_refreshed(&mut current, refreshed) {
+                        redraw(&current, &*config.read().await);
+                    }
+                }
+            }
+        });
+
+        *self.handle.lock().unwrap() = Some(handle);
+    }
+
+    pub fn stop(&self) {
+        if let Some(handle) = self.handle.lock().unwrap().take() {
+            handle.abort();
+            info!("Stopped live result refresh");
+        }
+    }
+}
+
+impl Drop for LiveRefresh {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use falcommand_config::Action;
+
+    // Results with "live" in the title refresh once an hour, appending the refresh count
+    #[derive(Default)]
+    struct CountingRefresher {
+        refreshes: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl ResultRefresher for CountingRefresher {
+        fn refresh_interval(&self, result: &SearchResult) -> Option<Duration> {
+            result.title.contains("live").then_some(Duration::from_secs(3600))
+        }
+
+        async fn refresh(&self, result: &SearchResult) -> Option<SearchResult> {
+            let count = self.refreshes.fetch_add(1, Ordering::SeqCst) + 1;
+            let mut refreshed = result.clone();
+            refreshed.title = format!("{} #{}", result.title, count);
+            Some(refreshed)
+        }
+    }
+
+    fn result(title: &str) -> SearchResult {
+        SearchResult::new(title, "").with_action(Action::CopyToClipboard(title.to_string()))
+    }
+
+    #[test]
+    fn refreshed_results_replace_the_old_ones_in_place() {
+        let mut results = vec![result("a"), result("b"), result("c")];
+        let mut refreshed = results[1].clone();
+        refreshed.title = "b, refreshed".to_string();
+
+        assert!(swap_refreshed(&mut results, refreshed));
+        let titles: Vec<&str> = results.iter().map(|r| r.title.as_str()).collect();
+        assert_eq!(titles, ["a", "b, refreshed", "c"]);
+        assert!(!swap_refreshed(&mut results, result("d")));
+    }
+
+    #[tokio::test]
+    async fn only_visible_results_are_refreshed_while_running() {
+        let refresher = Arc::new(CountingRefresher::default());
+        let live_refresh = LiveRefresh::new(refresher.clone());
+        let mut config = Config::default();
+        config.appearance.visible_result_rows = 2;
+        let results = Arc::new(RwLock::new(vec![result("live a"), result("static"), result("live below the fold")]));
+        let redrawn = Arc::new(std::sync::Mutex::new(Vec::new()));
+
+        let redrawn_titles = redrawn.clone();
+        live_refresh.start(results.clone(), Arc::new(RwLock::new(config)), move |results, _| {
+            redrawn_titles.lock().unwrap().push(results[0].title.clone());
+        });
+        assert!(live_refresh.is_running());
+        // The first tick is immediate
+        for _ in 0..100 {
+            if refresher.refreshes.load(Ordering::SeqCst) > 0 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        live_refresh.stop();
+        assert!(!live_refresh.is_running());
+
+        let titles: Vec<String> = results.read().await.iter().map(|r| r.title.clone()).collect();
+        assert_eq!(titles, ["live a #1", "static", "live below the fold"]);
+        assert_eq!(refresher.refreshes.load(Ordering::SeqCst), 1);
+        // One redraw per swap, seeing the swapped result
+        assert_eq!(*redrawn.lock().unwrap(), ["live a #1"]);
+    }
+}
@@ -0,0 +1,89 @@
+use std::path::PathBuf;
+use async_trait::async_trait;
+use log::info;
+
+use falcommand_config::{Action, ActionError, Category, SearchResult, SyntaxHint};
+use falcommand_config::i18n::tr;
+use crate::source::SearchSource;
+
+// Command-line flag that starts FalCommand in safe mode
+pub const SAFE_MODE_FLAG: &str = "--safe-mode";
+
+// Ways out of safe mode: fix the config, read why it crashed, or start normally again
+pub struct SafeModeSource {
+    config_file: PathBuf,
+    crash_report: Option<PathBuf>,
+}
+
+impl SafeModeSource {
+    pub const KEYWORD: &'static str = "safe mode";
+
+    pub fn new(config_file: PathBuf, crash_report: Option<PathBuf>) -> Self {
+        Self { config_file, crash_report }
+    }
+
+    fn results(&self) -> Vec<SearchResult> {
+        let mut results = vec![
+            SearchResult::new("Open config file", self.config_file.display().to_string())
+                .with_action(Action::OpenFile(self.config_file.clone()))
+                .with_category(Category::SystemCommand)
+                .with_score(1.0),
+        ];
+        if let Some(ref report) = self.crash_report {
+            results.push(
+                SearchResult::new("View last crash report", report.display().to_string())
+                    .with_action(Action::OpenFile(report.clone()))
+                    .with_category(Category::SystemCommand)
+                    .with_score(0.99)
+            );
+        }
+        results.push(
+            SearchResult::new("Restart normally", "Leave safe mode and load plugins, sync and the full configuration")
+                .with_action(Action::RestartNormally)
+                .with_category(Category::SystemCommand)
+                .with_score(0.98)
+        );
+        results
+    }
+}
+
+#[async_trait]
+impl SearchSource for SafeModeSource {
+    fn name(&self) -> &str {
+        "Safe mode"
+    }
+
+    fn syntax_hints(&self) -> Vec<SyntaxHint> {
+        vec![SyntaxHint::new(
+            Self::KEYWORD,
+            tr("hint.safe_mode", "Open the config file, view the last crash report or restart normally"),
+            "safe",
+        )]
+    }
+
+    // "safe mode" or any part of it lists every command; otherwise commands whose title matches
+    async fn search(&self, query: &str) -> Vec<SearchResult> {
+        let query = query.trim().to_lowercase();
+        if Self::KEYWORD.starts_with(&query) {
+            return self.results();
+        }
+        self.results()
+            .into_iter()
+            .filter(|result| result.title.to_lowercase().contains(&query))
+            .collect()
+    }
+}
+
+// Starts a new instance with the same arguments minus the safe-mode flag, then ends this one
+pub fn restart_normally() -> std::result::Result<(), ActionError> {
+    let executable = std::env::current_exe()
+        .map_err(|e| ActionError::PlatformError(format!("Failed to locate the executable: {}", e)))?;
+    let args: Vec<String> = std::env::args().skip(1).filter(|arg| arg != SAFE_MODE_FLAG).collect();
+
+    std::process::Command::new(executable)
+        .args(args)
+        .spawn()
+        .map_err(|e| ActionError::PlatformError(format!("Failed to restart: {}", e)))?;
+    info!("Restarting in normal mode");
+    std::process::exit(0)
+}
\ No newline at end of file
@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScanStatus {
+    Ok,
+    Failed(String),
+}
+
+// Outcome of the latest attempts to scan one include path
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanRecord {
+    pub status: ScanStatus,
+    // Failures in a row; zero after a successful scan
+    pub failures: u32,
+    pub last_attempt: SystemTime,
+    // Whether the path's parent existed at the last failure. A parent that comes back
+    // (a network share reconnecting) ends the backoff early
+    #[serde(default)]
+    pub parent_reachable: bool,
+}
+
+impl ScanRecord {
+    // Time until the next attempt: 1 minute after the first failure, doubling up to a day
+    pub fn backoff(&self) -> Duration {
+        const BASE: Duration = Duration::from_secs(60);
+        const MAX: Duration = Duration::from_secs(24 * 60 * 60);
+
+        if self.failures == 0 {
+            return Duration::ZERO;
+        }
+        let doublings = (self.failures - 1).min(16);
+        (BASE * 2u32.pow(doublings)).min(MAX)
+    }
+
+    pub fn retry_at(&self) -> SystemTime {
+        self.last_attempt + self.backoff()
+    }
+
+    pub fn backed_off(&self, now: SystemTime) -> bool {
+        self.failures > 0 && now < self.retry_at()
+    }
+}
+
+// Cheap probe: a stat of the parent, without listing anything
+pub fn parent_reachable(path: &Path) -> bool {
+    path.parent().is_some_and(|parent| parent.exists())
+}
+
+// Scan outcomes per include path, kept in the index cache so backoff survives restarts
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ScanHistory {
+    records: HashMap<PathBuf, ScanRecord>,
+}
+
+impl ScanHistory {
+    pub fn record(&self, path: &Path) -> Option<&ScanRecord> {
+        self.records.get(path)
+    }
+
+    pub fn record_success(&mut self, path: &Path, now: SystemTime) {
+        self.records.insert(path.to_path_buf(), ScanRecord {
+            status: ScanStatus::Ok,
+            failures: 0,
+            last_attempt: now,
+            parent_reachable: true,
+        });
+    }
+
+    // Returns the number of failures in a row, including this one
+    pub fn record_failure(&mut self, path: &Path, error: String, parent_reachable: bool, now: SystemTime) -> u32 {
+        let failures = self.records.get(path).map_or(0, |record| record.failures) + 1;
+        self.records.insert(path.to_path_buf(), ScanRecord {
+            status: ScanStatus::Failed(error),
+            failures,
+            last_attempt: now,
+            parent_reachable,
+        });
+        failures
+    }
+
+    // Whether `path` is skipped this time. `parent_reachable` is the current state of its parent,
+    // from a cheap existence check; a parent that was missing at the last failure and is back resets it
+    pub fn should_skip(&mut self, path: &Path, parent_reachable: bool, now: SystemTime) -> bool {
+        let Some(record) = self.records.get(path) else {
+            return false;
+        };
+        if !record.parent_reachable && parent_reachable {
+            self.records.remove(path);
+            return false;
+        }
+        record.backed_off(now)
+    }
+
+    // Include paths waiting out their backoff, with when they are tried again
+    pub fn backed_off(&self, now: SystemTime) -> Vec<(PathBuf, SystemTime)> {
+        let mut paths: Vec<(PathBuf, SystemTime)> = self.records.iter()
+            .filter(|(_, record)| record.backed_off(now))
+            .map(|(path, record)| (path.clone(), record.retry_at()))
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    // `index rebuild --force`: every path is tried again
+    pub fn clear(&mut self) {
+        self.records.clear();
+    }
+
+    // Paths no longer configured don't need their outcomes
+    pub fn retain_paths(&mut self, paths: &[PathBuf]) {
+        self.records.retain(|path, _| paths.contains(path));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINUTE: Duration = Duration::from_secs(60);
+
+    fn start() -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000)
+    }
+
+    #[test]
+    fn backoff_doubles_from_a_minute_up_to_a_day() {
+        let record = |failures| ScanRecord { status: ScanStatus::Ok, failures, last_attempt: start(), parent_reachable: true };
+        let backoffs: Vec<Duration> = (0..5).map(|failures| record(failures).backoff()).collect();
+        assert_eq!(backoffs, [Duration::ZERO, MINUTE, 2 * MINUTE, 4 * MINUTE, 8 * MINUTE]);
+        assert_eq!(record(12).backoff(), Duration::from_secs(24 * 60 * 60));
+        assert_eq!(record(u32::MAX).backoff(), Duration::from_secs(24 * 60 * 60));
+        assert_eq!(record(3).retry_at(), start() + 4 * MINUTE);
+    }
+
+    #[test]
+    fn failing_paths_are_skipped_until_their_backoff_expires() {
+        let share = Path::new("/mnt/share/docs");
+        let mut history = ScanHistory::default();
+        assert!(!history.should_skip(share, true, start()));
+
+        history.record_failure(share, "timed out".to_string(), true, start());
+        assert!(history.should_skip(share, true, start() + MINUTE / 2));
+        assert!(!history.should_skip(share, true, start() + MINUTE));
+
+        // The second failure in a row waits twice as long
+        let second = start() + MINUTE;
+        assert_eq!(history.record_failure(share, "timed out".to_string(), true, second), 2);
+        assert!(history.should_skip(share, true, second + MINUTE));
+        assert_eq!(history.backed_off(second + MINUTE), vec![(share.to_path_buf(), second + 2 * MINUTE)]);
+
+        // One success starts over
+        history.record_success(share, second + 2 * MINUTE);
+        assert_eq!(history.record(share).unwrap().failures, 0);
+        assert!(history.backed_off(second + 2 * MINUTE).is_empty());
+    }
+
+    #[test]
+    fn a_parent_coming_back_ends_the_backoff() {
+        let share = Path::new("/mnt/share/docs");
+        let mut history = ScanHistory::default();
+        history.record_failure(share, "not found".to_string(), false, start());
+        assert!(history.should_skip(share, false, start()));
+        assert!(!history.should_skip(share, true, start()));
+        assert_eq!(history.record(share), None);
+
+        // A parent that was there all along says nothing about the path itself
+        history.record_failure(share, "permission denied".to_string(), true, start());
+        assert!(history.should_skip(share, true, start()));
+    }
+
+    #[test]
+    fn clearing_forces_every_path_and_unconfigured_paths_are_forgotten() {
+        let (kept, removed) = (PathBuf::from("/data/a"), PathBuf::from("/data/b"));
+        let mut history = ScanHistory::default();
+        history.record_failure(&kept, "denied".to_string(), true, start());
+        history.record_failure(&removed, "denied".to_string(), true, start());
+
+        history.retain_paths(std::slice::from_ref(&kept));
+        assert_eq!(history.backed_off(start()).len(), 1);
+        assert!(history.record(&removed).is_none());
+
+        let saved: ScanHistory = serde_json::from_str(&serde_json::to_string(&history).unwrap()).unwrap();
+        assert_eq!(saved, history);
+
+        history.clear();
+        assert!(!history.should_skip(&kept, true, start()));
+    }
+}
@@ -0,0 +1,298 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::sync::RwLock;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Duration, Utc};
+use log::{info, warn};
+
+use falcommand_config::{Action, Category, PathResolver, SearchResult, SyntaxHint};
+use falcommand_config::i18n::tr;
+use crate::clock::Clock;
+use crate::source::SearchSource;
+
+#[derive(Debug, thiserror::Error)]
+pub enum SchedulerError {
+    #[error("Failed to access scheduled actions: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Failed to serialize scheduled actions: {0}")]
+    SerializationError(#[from] serde_json::Error),
+
+    #[error("Scheduled action not found: {0}")]
+    NotFound(String),
+
+    #[error("Other scheduler error: {0}")]
+    Other(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScheduledAction {
+    pub id: String,
+    pub label: String,
+    pub action: Action,
+    pub due_at: DateTime<Utc>,
+    #[serde(default)]
+    pub warned: bool,
+}
+
+// Result of a scheduler poll: actions to run now and actions entering the cancel window
+#[derive(Debug, Default)]
+pub struct SchedulerTick {
+    pub due: Vec<ScheduledAction>,
+    pub expiring: Vec<ScheduledAction>,
+}
+
+#[derive(Debug)]
+pub struct ActionScheduler {
+    storage_path: PathBuf,
+    clock: Arc<dyn Clock>,
+    cancel_window: Duration,
+    entries: RwLock<Vec<ScheduledAction>>,
+    next_id: AtomicU64,
+}
+
+impl ActionScheduler {
+    // How long before firing the user is notified and can still cancel
+    pub const DEFAULT_CANCEL_WINDOW_SECS: i64 = 60;
+
+    pub async fn new(resolver: &PathResolver, clock: Arc<dyn Clock>) -> std::result::Result<Self, SchedulerError> {
+        let storage_path = resolver.shared_data_file("scheduled_actions.json");
+
+        Self::with_storage_path(storage_path, clock).await
+    }
+
+    pub async fn with_storage_path(
+        storage_path: PathBuf,
+        clock: Arc<dyn Clock>,
+    ) -> std::result::Result<Self, SchedulerError> {
+        let entries = Self::load_entries(&storage_path).await?;
+        if !entries.is_empty() {
+            info!("Restored {} scheduled actions from {:?}", entries.len(), storage_path);
+        }
+
+        Ok(Self {
+            storage_path,
+            clock,
+            cancel_window: Duration::seconds(Self::DEFAULT_CANCEL_WINDOW_SECS),
+            entries: RwLock::new(entries),
+            next_id: AtomicU64::new(0),
+        })
+    }
+
+    pub fn with_cancel_window(mut self, cancel_window: Duration) -> Self {
+        self.cancel_window = cancel_window;
+        self
+    }
+
+    async fn load_entries(path: &Path) -> std::result::Result<Vec<ScheduledAction>, SchedulerError> {
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let content = tokio::fs::read_to_string(path).await?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    async fn persist(&self, entries: &[ScheduledAction]) -> std::result::Result<(), SchedulerError> {
+        if let Some(parent) = self.storage_path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        // Write to a temporary file first so a crash never leaves a truncated store
+        let content = serde_json::to_string_pretty(entries)?;
+        let tmp_path = self.storage_path.with_extension("json.tmp");
+        tokio::fs::write(&tmp_path, content).await?;
+        tokio::fs::rename(&tmp_path, &self.storage_path).await?;
+        Ok(())
+    }
+
+    pub async fn schedule(
+        &self,
+        action: Action,
+        delay: Duration,
+        label: impl Into<String>,
+    ) -> std::result::Result<ScheduledAction, SchedulerError> {
+        let now = self.clock.now();
+        let id = format!(
+            "{}-{}",
+            now.timestamp_millis(),
+            self.next_id.fetch_add(1, Ordering::Relaxed)
+        );
+
+        let entry = ScheduledAction {
+            id,
+            label: label.into(),
+            action,
+            due_at: now + delay,
+            warned: false,
+        };
+
+        let mut entries = self.entries.write().await;
+        entries.push(entry.clone());
+        self.persist(&entries).await?;
+
+        info!("Scheduled '{}' for {}", entry.label, entry.due_at);
+        Ok(entry)
+    }
+
+    pub async fn cancel(&self, id: &str) -> std::result::Result<ScheduledAction, SchedulerError> {
+        let mut entries = self.entries.write().await;
+        let position = entries.iter()
+            .position(|e| e.id == id)
+            .ok_or_else(|| SchedulerError::NotFound(id.to_string()))?;
+
+        let entry = entries.remove(position);
+        self.persist(&entries).await?;
+
+        info!("Cancelled scheduled action '{}'", entry.label);
+        Ok(entry)
+    }
+
+    pub async fn list(&self) -> Vec<ScheduledAction> {
+        let mut entries = self.entries.read().await.clone();
+        entries.sort_by_key(|e| e.due_at);
+        entries
+    }
+
+    pub fn now(&self) -> DateTime<Utc> {
+        self.clock.now()
+    }
+
+    // Removes due entries and marks the ones entering the cancel window as warned
+    pub async fn poll(&self) -> SchedulerTick {
+        let now = self.clock.now();
+        let mut entries = self.entries.write().await;
+        let mut tick = SchedulerTick::default();
+
+        let mut remaining = Vec::with_capacity(entries.len());
+        for mut entry in entries.drain(..) {
+            if entry.due_at <= now {
+                tick.due.push(entry);
+            } else {
+                if !entry.warned && entry.due_at - self.cancel_window <= now {
+                    entry.warned = true;
+                    tick.expiring.push(entry.clone());
+                }
+                remaining.push(entry);
+            }
+        }
+        *entries = remaining;
+
+        if !tick.due.is_empty() || !tick.expiring.is_empty() {
+            // The in-memory state stays authoritative; the next successful write catches up
+            if let Err(e) = self.persist(&entries).await {
+                warn!("Failed to persist scheduled actions: {}", e);
+            }
+        }
+
+        tick
+    }
+}
+
+// Lists pending scheduled actions for the `scheduled` meta-query
+pub struct ScheduledActionsSource {
+    scheduler: Arc<ActionScheduler>,
+}
+
+impl ScheduledActionsSource {
+    pub const KEYWORD: &'static str = "scheduled";
+
+    pub fn new(scheduler: Arc<ActionScheduler>) -> Self {
+        Self { scheduler }
+    }
+}
+
+#[async_trait]
+impl SearchSource for ScheduledActionsSource {
+    fn name(&self) -> &str {
+        "Scheduled actions"
+    }
+
+    fn syntax_hints(&self) -> Vec<SyntaxHint> {
+        vec![SyntaxHint::new(
+            Self::KEYWORD,
+            tr("hint.scheduled", "List and cancel scheduled actions"),
+            Self::KEYWORD,
+        )]
+    }
+
+    async fn search(&self, query: &str) -> Vec<SearchResult> {
+        if !query.trim().eq_ignore_ascii_case(Self::KEYWORD) {
+            return Vec::new();
+        }
+
+        let now = self.scheduler.now();
+        self.scheduler.list().await
+            .into_iter()
+            .map(|entry| {
+                let minutes_left = (entry.due_at - now).num_minutes().max(0);
+                SearchResult::new(
+                    format!("Cancel: {}", entry.label),
+                    format!("Scheduled in {} min ({})", minutes_left, entry.due_at.with_timezone(&chrono::Local).format("%H:%M")),
+                )
+                .with_action(Action::CancelScheduledAction(entry.id.clone()))
+                .with_category(Category::SystemCommand)
+                .with_score(1.0)
+                .with_secondary_action("Run now", entry.action.clone())
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::manual_clock;
+
+    fn lock_screen() -> Action {
+        Action::ExecuteCommand { command: "loginctl".to_string(), args: vec!["lock-session".to_string()] }
+    }
+
+    #[tokio::test]
+    async fn scheduled_actions_survive_a_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("scheduled_actions.json");
+        let clock = manual_clock();
+        let scheduler = ActionScheduler::with_storage_path(path.clone(), clock.clone()).await.unwrap();
+        let kept = scheduler.schedule(lock_screen(), Duration::minutes(30), "Lock").await.unwrap();
+        let cancelled = scheduler.schedule(lock_screen(), Duration::minutes(10), "Lock sooner").await.unwrap();
+        scheduler.cancel(&cancelled.id).await.unwrap();
+        drop(scheduler);
+
+        let restarted = ActionScheduler::with_storage_path(path, clock.clone()).await.unwrap();
+        assert_eq!(restarted.list().await, vec![kept.clone()]);
+
+        // Due while the launcher wasn't running: runs on the first poll
+        clock.advance(Duration::hours(1));
+        let tick = restarted.poll().await;
+        assert_eq!(tick.due, vec![kept]);
+        assert!(restarted.list().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn the_cancel_window_notifies_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let clock = manual_clock();
+        let scheduler = ActionScheduler::with_storage_path(dir.path().join("scheduled_actions.json"), clock.clone()).await.unwrap()
+            .with_cancel_window(Duration::seconds(60));
+        let entry = scheduler.schedule(lock_screen(), Duration::minutes(5), "Lock").await.unwrap();
+
+        clock.advance(Duration::seconds(239));
+        assert!(scheduler.poll().await.expiring.is_empty());
+
+        clock.advance(Duration::seconds(1));
+        let tick = scheduler.poll().await;
+        assert_eq!(tick.expiring.iter().map(|e| e.id.as_str()).collect::<Vec<_>>(), vec![entry.id.as_str()]);
+        assert!(tick.due.is_empty());
+
+        clock.advance(Duration::seconds(30));
+        assert!(scheduler.poll().await.expiring.is_empty());
+
+        clock.advance(Duration::seconds(30));
+        let tick = scheduler.poll().await;
+        assert_eq!(tick.due.len(), 1);
+        assert!(tick.due[0].warned);
+    }
+}
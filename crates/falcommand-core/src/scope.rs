@@ -0,0 +1,58 @@
+use std::path::{Path, PathBuf};
+
+// `in:<folder> <name>` searches only the files in that folder
+pub const FOLDER_OPERATOR: &str = "in:";
+
+// `in:/some/folder report` or `in:"C:\My Files" report` -> the folder and the rest of the query
+pub fn folder_scope(query: &str) -> Option<(PathBuf, &str)> {
+    let query = query.trim_start();
+    let rest = query.get(..FOLDER_OPERATOR.len())
+        .filter(|operator| operator.eq_ignore_ascii_case(FOLDER_OPERATOR))
+        .map(|_| &query[FOLDER_OPERATOR.len()..])?;
+
+    let (folder, rest) = match rest.strip_prefix('"') {
+        Some(quoted) => quoted.split_once('"')?,
+        None => rest.split_once(char::is_whitespace).unwrap_or((rest, "")),
+    };
+    if folder.is_empty() {
+        return None;
+    }
+    Some((PathBuf::from(folder), rest.trim()))
+}
+
+// The query a "Search in this folder" pivot types in, ready for a name to be added
+pub fn folder_scope_query(folder: &Path) -> String {
+    let folder = folder.display().to_string();
+    if folder.contains(char::is_whitespace) {
+        format!("{}\"{}\" ", FOLDER_OPERATOR, folder)
+    } else {
+        format!("{}{} ", FOLDER_OPERATOR, folder)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn operator_splits_folder_and_name() {
+        assert_eq!(folder_scope("in:/home/me/docs report"), Some((PathBuf::from("/home/me/docs"), "report")));
+        assert_eq!(folder_scope("IN:/home/me/docs"), Some((PathBuf::from("/home/me/docs"), "")));
+        assert_eq!(folder_scope(r#"in:"/home/me/My Files"  report "#), Some((PathBuf::from("/home/me/My Files"), "report")));
+        assert_eq!(folder_scope("in: report"), None);
+        assert_eq!(folder_scope(r#"in:"/home/me/My Files report"#), None);
+        assert_eq!(folder_scope("index report"), None);
+    }
+
+    // What the pivot types in parses back to the same folder with nothing searched for yet
+    #[test]
+    fn pivot_query_composes_with_the_operator() {
+        for folder in ["/home/me/docs", "/home/me/My Files", "/tmp/tab\tname"] {
+            let query = folder_scope_query(Path::new(folder));
+            assert!(query.ends_with(' '), "{:?}", query);
+            assert_eq!(folder_scope(&query), Some((PathBuf::from(folder), "")));
+            assert_eq!(folder_scope(&format!("{}report", query)), Some((PathBuf::from(folder), "report")));
+        }
+        assert_eq!(folder_scope_query(Path::new("/home/me/My Files")), "in:\"/home/me/My Files\" ");
+    }
+}
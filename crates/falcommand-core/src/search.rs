@@ -1,10 +1,32 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use fuzzy_matcher::{FuzzyMatcher, skim::SkimMatcherV2};
-use log::{info, error};
+use log::{info, warn, error};
 
-use falcommand_config::{Config, SearchResult};
+use falcommand_config::{Config, MatchReason, RankingConfig, ResultId, SearchResult, SyntaxHint, Action, Category};
+use falcommand_config::i18n::tr;
+use falcommand_platform::AppInfo;
 use crate::index::IndexManager;
+use crate::history::{HistoryError, SearchHistory};
+use crate::crash::TaskSupervisor;
+use crate::usage::{category_label, UsageError, UsageStore};
+use crate::stats::{clear_usage_data, compute_usage_stats, StatsWindow, UsageStats};
+use crate::arguments::{split_app_arguments, expand_home};
+use crate::source::SearchSource;
+use crate::help::{HintGroup, SyntaxHintProvider, aggregate_hints, help_results, is_help_query};
+use crate::share::decorate_with_share_actions;
+use crate::details::decorate_with_detail_actions;
+use crate::spelling::{correction_results, is_correctable, suggest_corrections, SpellCandidate};
+use crate::scope::folder_scope;
+use crate::power::PowerPolicy;
+use crate::query_macros::{expand_query_macro, MacroExpansion};
+use crate::paging::{page_of, PageCache, PageError, SearchPage, MAX_PAGED_RESULTS};
+use crate::path_completion::{complete_path_query, list_path_completions, parse_path_query, PathFilter, PathListing};
+use crate::context_boost::{boost_below_exact, context_boost, context_boosts_for, is_exact_title_match};
+use crate::events::{EventBus, LauncherEvent};
 
 #[derive(Debug, thiserror::Error)]
 pub enum SearchError {
@@ -21,10 +43,157 @@ pub enum SearchError {
     Other(String),
 }
 
+// Position of `category` in search.category_priority: "plugin:<id>" before "plugin", unlisted last
+pub fn category_rank(category: &Category, priority: &[String]) -> usize {
+    let label = category_label(category);
+    priority.iter()
+        .position(|entry| entry.eq_ignore_ascii_case(&label))
+        .or_else(|| match category {
+            Category::Plugin(_) => priority.iter().position(|entry| entry.eq_ignore_ascii_case("plugin")),
+            _ => None,
+        })
+        .unwrap_or(priority.len())
+}
+
+// Result order, each step only consulted when the previous ones tie:
+// 1. score, highest first
+// 2. category, in search.category_priority order
+// 3. usage score (decayed launches), most used first
+// 4. title length, shortest first
+// 5. ResultId, so two distinct results never compare equal
+pub fn compare_results(
+    a: &SearchResult,
+    b: &SearchResult,
+    category_priority: &[String],
+    usage_scores: &HashMap<ResultId, f64>,
+) -> Ordering {
+    let usage = |result: &SearchResult| usage_scores.get(&result.id).copied().unwrap_or(0.0);
+    b.score.total_cmp(&a.score)
+        .then_with(|| category_rank(&a.category, category_priority).cmp(&category_rank(&b.category, category_priority)))
+        .then_with(|| usage(b).total_cmp(&usage(a)))
+        .then_with(|| a.title.chars().count().cmp(&b.title.chars().count()))
+        .then_with(|| a.id.cmp(&b.id))
+}
+
+// The titles ranking.pinned keeps at the top for `query`, in order
+pub fn pinned_titles<'a>(query: &str, pinned: &'a HashMap<String, Vec<String>>) -> &'a [String] {
+    let query = query.trim().to_lowercase();
+    pinned.iter()
+        .find(|(pinned_query, _)| pinned_query.trim().to_lowercase() == query)
+        .map_or(&[], |(_, titles)| titles.as_slice())
+}
+
+// Moves the results titled in `titles` (already sorted) to the front in that order, marked as
+// pinned; the rest keep their order
+pub fn pin_results(results: &mut Vec<SearchResult>, titles: &[String]) {
+    let mut pinned = Vec::new();
+    for title in titles {
+        if let Some(index) = results.iter().position(|result| &result.title == title) {
+            let mut result = results.remove(index);
+            result.match_reason = Some(MatchReason::Pinned);
+            pinned.push(result);
+        }
+    }
+    results.splice(0..0, pinned);
+}
+
+// `results` (already sorted) cut to `max_results`, except that every source (category) with a result
+// at or above `threshold` keeps at least `min_per_source` of them. Room is made by dropping the
+// lowest-ranked results of whichever source has the most, as long as it stays above the minimum
+pub fn truncate_fairly(mut results: Vec<SearchResult>, max_results: usize, min_per_source: usize, threshold: f64) -> Vec<SearchResult> {
+    if results.len() <= max_results || min_per_source == 0 {
+        results.truncate(max_results);
+        return results;
+    }
+    
+    let sources: Vec<String> = results.iter().map(|result| category_label(&result.category)).collect();
+    let mut kept: Vec<bool> = (0..results.len()).map(|index| index < max_results).collect();
+    
+    for index in max_results..results.len() {
+        if results[index].score < threshold {
+            continue;
+        }
+        let mut counts: std::collections::BTreeMap<&str, usize> = std::collections::BTreeMap::new();
+        for (source, _) in sources.iter().zip(&kept).filter(|(_, kept)| **kept) {
+            *counts.entry(source.as_str()).or_insert(0) += 1;
+        }
+        if counts.get(sources[index].as_str()).copied().unwrap_or(0) >= min_per_source {
+            continue;
+        }
+        
+        let Some((dominant, count)) = counts.into_iter().max_by_key(|(_, count)| *count) else {
+            break;
+        };
+        if count <= min_per_source {
+            break;
+        }
+        let dominant = dominant.to_string();
+        if let Some(evicted) = (0..index).rev().find(|&other| kept[other] && sources[other] == dominant) {
+            kept[evicted] = false;
+            kept[index] = true;
+        }
+    }
+    
+    results.into_iter()
+        .zip(kept)
+        .filter_map(|(result, kept)| kept.then_some(result))
+        .collect()
+}
+
+// How a result's score was put together
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ScoreBreakdown {
+    // What the source gave it
+    pub source: f64,
+    // None when the title doesn't fuzzy-match the query
+    pub fuzzy: Option<f64>,
+    pub selection: f64,
+    pub usage: f64,
+    // For the app in front (ranking.context_boosts)
+    pub context: f64,
+    pub total: f64,
+}
+
+impl ScoreBreakdown {
+    pub fn new(source: f64, fuzzy: Option<f64>, selection_bonus: f64, usage_score: f64, ranking: &RankingConfig) -> Self {
+        let blended = match fuzzy {
+            Some(fuzzy) => source * (1.0 - ranking.fuzzy_weight) + fuzzy * ranking.fuzzy_weight,
+            None => source,
+        };
+        let selection = selection_bonus * ranking.selection_weight;
+        // Half of the weight at one fresh launch, approaching all of it
+        let usage = ranking.usage_weight * usage_score / (usage_score + 1.0);
+        
+        Self {
+            source,
+            fuzzy,
+            selection,
+            usage,
+            context: 0.0,
+            total: blended + selection + usage,
+        }
+    }
+    
+    pub fn with_context(mut self, context: f64) -> Self {
+        self.context = context;
+        self.total += context;
+        self
+    }
+}
+
 pub struct SearchEngine {
     config: Arc<RwLock<Config>>,
     index_manager: Arc<IndexManager>,
+    history: RwLock<Arc<SearchHistory>>,
+    usage: RwLock<Arc<UsageStore>>,
+    sources: RwLock<Vec<Arc<dyn SearchSource>>>,
+    hint_providers: RwLock<Vec<Arc<dyn SyntaxHintProvider>>>,
     matcher: SkimMatcherV2,
+    // Rankings behind search_page
+    page_cache: std::sync::Mutex<PageCache>,
+    // Name of the app in front when the launcher last opened, for ranking.context_boosts
+    foreground_app: std::sync::Mutex<Option<String>>,
+    events: Option<Arc<EventBus>>,
 }
 
 impl std::fmt::Debug for SearchEngine {
@@ -38,42 +207,270 @@ impl std::fmt::Debug for SearchEngine {
 }
 
 impl SearchEngine {
+    const MAINTENANCE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(24 * 60 * 60);
+    
     pub async fn new(
         config: Arc<RwLock<Config>>,
         index_manager: Arc<IndexManager>,
+        history: Arc<SearchHistory>,
+        usage: Arc<UsageStore>,
     ) -> std::result::Result<Self, SearchError> {
         info!("Initializing search engine...");
         
         Ok(Self {
             config,
             index_manager,
+            history: RwLock::new(history),
+            usage: RwLock::new(usage),
+            sources: RwLock::new(Vec::new()),
+            hint_providers: RwLock::new(Vec::new()),
             matcher: SkimMatcherV2::default(),
+            page_cache: std::sync::Mutex::new(PageCache::default()),
+            foreground_app: std::sync::Mutex::new(None),
+            events: None,
         })
     }
     
+    // Each search is published with the length of its query and how many results it found
+    pub fn with_events(mut self, events: Arc<EventBus>) -> Self {
+        self.events = Some(events);
+        self
+    }
+    
+    // Recorded as the launcher opens; None where the platform can't tell, which turns the context
+    // boosts off
+    pub fn set_foreground_app(&self, app: Option<String>) {
+        *self.foreground_app.lock().unwrap() = app;
+    }
+    
+    // Swapped when the active profile changes; the index stays shared between profiles
+    pub async fn set_history(&self, history: Arc<SearchHistory>) {
+        *self.history.write().await = history;
+    }
+    
+    pub async fn history(&self) -> Arc<SearchHistory> {
+        self.history.read().await.clone()
+    }
+    
+    pub fn index_manager(&self) -> Arc<IndexManager> {
+        self.index_manager.clone()
+    }
+    
+    pub async fn usage_store(&self) -> Arc<UsageStore> {
+        self.usage.read().await.clone()
+    }
+    
+    pub async fn set_usage_store(&self, usage: Arc<UsageStore>) {
+        *self.usage.write().await = usage;
+    }
+    
+    pub async fn register_source(&self, source: Arc<dyn SearchSource>) {
+        info!("Registering search source: {}", source.name());
+        self.sources.write().await.push(source);
+    }
+    
+    pub async fn register_hint_provider(&self, provider: Arc<dyn SyntaxHintProvider>) {
+        self.hint_providers.write().await.push(provider);
+    }
+    
+    // Help entries from the engine, registered sources and hint providers, deduplicated by pattern
+    pub async fn syntax_hints(&self) -> Vec<HintGroup> {
+        let mut groups = vec![HintGroup::new("FalCommand", vec![
+            SyntaxHint::new("?", tr("hint.help", "List the available query syntax"), "help"),
+            SyntaxHint::new("<app> <arguments>", tr("hint.app_arguments", "Launch an app with arguments"), "code ~/projects"),
+            SyntaxHint::new("in:<folder> <name>", tr("hint.folder_scope", "Search only the files in a folder"), "in:~/Documents report"),
+        ])];
+        
+        for source in self.sources.read().await.iter() {
+            groups.push(HintGroup::new(source.name(), source.syntax_hints()));
+        }
+        
+        let providers = self.hint_providers.read().await.clone();
+        for provider in providers {
+            groups.extend(provider.syntax_hints().await);
+        }
+        
+        aggregate_hints(groups)
+    }
+    
+    // The saved query "@inv 2024" stands for, if it names a macro
+    pub async fn expand_macro(&self, query: &str) -> Option<MacroExpansion> {
+        expand_query_macro(query, &self.config.read().await.search.query_macros)
+    }
+    
+    // Tab on a typed path: the query completed as far as its directory's entries agree
+    pub async fn complete_typed_path(&self, query: &str) -> Option<String> {
+        let query = query.to_string();
+        let filter = PathFilter::from_config(&*self.config.read().await);
+        tokio::task::spawn_blocking(move || complete_path_query(&query, &filter)).await.ok().flatten()
+    }
+    
     pub async fn search(&self, query: &str) -> Vec<SearchResult> {
         if query.trim().is_empty() {
             return Vec::new();
         }
+        let results = self.search_query(query).await;
+        if let Some(ref events) = self.events {
+            events.publish(LauncherEvent::SearchExecuted { query_chars: query.chars().count(), results: results.len() });
+        }
+        results
+    }
+    
+    async fn search_query(&self, query: &str) -> Vec<SearchResult> {
+        
+        // Macros expand before anything else reads the query
+        let expansion = self.expand_macro(query).await;
+        let query = expansion.as_ref().map_or(query, |expansion| expansion.query.as_str());
+        
+        // "?" and "help" list the syntax instead of searching; keep the overlay order unranked
+        if is_help_query(query) {
+            return help_results(&self.syntax_hints().await);
+        }
         
         info!("Searching for: '{}'", query);
+        let all_results = self.collect_results(query).await;
+        
+        // Sort by score and limit results
+        let results = self.sort_and_limit_results(all_results, query).await;
+        
+        // Nothing good enough usually means a typo; the closest known names go first
+        let threshold = self.config.read().await.search.fuzzy_threshold;
+        if results.iter().any(|result| result.score >= threshold) {
+            return results;
+        }
+        let mut corrected = self.spell_corrections(query).await;
+        if corrected.is_empty() {
+            return results;
+        }
+        let max_results = self.config.read().await.behavior.max_results;
+        corrected.extend(results);
+        corrected.truncate(max_results);
+        corrected
+    }
+    
+    // One page of the ranked results, for the CLI and other clients that page instead of truncating.
+    // `generation` is the one the first page returned: pages of a query are sliced from one ranking
+    // and never reorder, and once the index changes the request fails instead of skipping or
+    // repeating results
+    pub async fn search_page(
+        &self,
+        query: &str,
+        offset: usize,
+        limit: usize,
+        generation: Option<u64>,
+    ) -> std::result::Result<SearchPage, PageError> {
+        let current = self.index_manager.generation();
+        if let Some(cursor) = generation.filter(|&cursor| cursor != current) {
+            return Err(PageError::IndexChanged { cursor, current });
+        }
+        if let Some(results) = self.page_cache.lock().unwrap().get(query, current) {
+            return Ok(page_of(results, offset, limit, current));
+        }
+        
+        let results = self.rank_all(query).await;
+        let page = page_of(&results, offset, limit, current);
+        self.page_cache.lock().unwrap().insert(query, current, results);
+        Ok(page)
+    }
+    
+    // Like search, without spelling corrections, up to MAX_PAGED_RESULTS instead of max_results
+    async fn rank_all(&self, query: &str) -> Vec<SearchResult> {
+        if query.trim().is_empty() {
+            return Vec::new();
+        }
+        let expansion = self.expand_macro(query).await;
+        let query = expansion.as_ref().map_or(query, |expansion| expansion.query.as_str());
+        if is_help_query(query) {
+            return help_results(&self.syntax_hints().await);
+        }
+        
+        let all_results = self.collect_results(query).await;
+        let ranking = self.config.read().await.ranking.clone();
+        self.rank_limited(all_results, query, &ranking, MAX_PAGED_RESULTS).await
+            .into_iter()
+            .map(|(result, _)| result)
+            .collect()
+    }
+    
+    // Ranked results with how each score came about, under `ranking` instead of the configured
+    // weights. Spelling corrections are left out since they are not ranked
+    pub async fn search_with_breakdown(&self, query: &str, ranking: &RankingConfig) -> Vec<(SearchResult, ScoreBreakdown)> {
+        if query.trim().is_empty() {
+            return Vec::new();
+        }
+        let expansion = self.expand_macro(query).await;
+        let query = expansion.as_ref().map_or(query, |expansion| expansion.query.as_str());
+        let all_results = self.collect_results(query).await;
+        self.rank_results(all_results, query, ranking).await
+    }
+    
+    async fn collect_results(&self, query: &str) -> Vec<SearchResult> {
+        // "in:<folder> <name>" only looks at the files in that folder
+        if let Some((folder, rest)) = folder_scope(query) {
+            let folder = PathBuf::from(expand_home(&folder.to_string_lossy()));
+            return self.search_files_in(rest, Some(&folder)).await;
+        }
+        
         let mut all_results = Vec::new();
         
         // Search in parallel
-        let (app_results, file_results) = tokio::join!(
+        let (argument_results, app_results, file_results, source_results) = tokio::join!(
+            self.search_application_arguments(query),
             self.search_applications(query),
-            self.search_files(query)
+            self.search_files(query),
+            self.search_sources(query)
         );
         
+        all_results.extend(argument_results);
         all_results.extend(app_results);
         all_results.extend(file_results);
+        all_results.extend(source_results);
+        all_results
+    }
+    
+    // Bounded edit-distance pass over app names and recently launched items
+    async fn spell_corrections(&self, query: &str) -> Vec<SearchResult> {
+        const LIMIT: usize = 3;
+        const MAX_CANDIDATES: usize = 5_000;
+        const TOP_LAUNCHED: usize = 50;
+        const BUDGET: std::time::Duration = std::time::Duration::from_millis(30);
         
-        // Sort by score and limit results
-        self.sort_and_limit_results(all_results, query).await
+        if !is_correctable(query) || folder_scope(query).is_some() || self.is_keyword_query(query).await {
+            return Vec::new();
+        }
+        
+        let deadline = std::time::Instant::now() + BUDGET;
+        let mut candidates: Vec<SpellCandidate> = self.index_manager.applications().await
+            .into_iter()
+            .map(SpellCandidate::app)
+            .collect();
+        candidates.truncate(MAX_CANDIDATES);
+        let launched = self.usage_stats(StatsWindow::default(), TOP_LAUNCHED).await.top_items;
+        candidates.extend(launched.into_iter().map(|(title, _)| SpellCandidate::text(title)));
+        
+        correction_results(&suggest_corrections(query, &candidates, LIMIT, deadline))
+    }
+    
+    // "history fierfox" is a source keyword with an argument, not a misspelled name
+    async fn is_keyword_query(&self, query: &str) -> bool {
+        let Some(first_word) = query.split_whitespace().next().map(str::to_lowercase) else {
+            return false;
+        };
+        self.syntax_hints().await.iter()
+            .flat_map(|group| group.hints.iter())
+            .filter_map(|hint| hint.pattern.split_whitespace().next())
+            .any(|keyword| keyword.to_lowercase() == first_word)
     }
     
     async fn search_applications(&self, query: &str) -> Vec<SearchResult> {
-        match self.index_manager.search_applications(query).await {
+        let config = self.config.read().await;
+        if !config.search.enable_app_search {
+            return Vec::new();
+        }
+        let aliases = config.search.app_aliases.clone();
+        drop(config);
+        
+        match self.index_manager.search_applications(query, &aliases).await {
             Ok(results) => results,
             Err(e) => {
                 error!("Application search failed: {}", e);
@@ -82,13 +479,85 @@ impl SearchEngine {
         }
     }
     
+    // Argument mode: "code ~/projects/foo" opens the unambiguous app with the rest as its argument
+    async fn search_application_arguments(&self, query: &str) -> Vec<SearchResult> {
+        const PATH_COMPLETION_LIMIT: usize = 5;
+        
+        let Some((token, rest)) = split_app_arguments(query) else {
+            return Vec::new();
+        };
+        
+        let config = self.config.read().await;
+        if !config.search.enable_app_search {
+            return Vec::new();
+        }
+        let aliases = config.search.app_aliases.clone();
+        drop(config);
+        // "google chrome" is the name of Google Chrome, not "google" with an argument
+        if self.index_manager.is_app_name_prefix(query).await {
+            return Vec::new();
+        }
+        let Some((app_info, reason)) = self.index_manager.resolve_app_token(token, &aliases).await else {
+            return Vec::new();
+        };
+        
+        let mut results = vec![Self::argument_result(&app_info, rest, 1.0).with_match_reason(reason.clone())];
+        
+        for completion in self.complete_argument_path(rest, PATH_COMPLETION_LIMIT).await {
+            if completion != rest {
+                results.push(Self::argument_result(&app_info, &completion, 0.9).with_match_reason(reason.clone()));
+            }
+        }
+        
+        results
+    }
+    
+    // Paths extending a path-shaped argument, through the same filter as path mode so excluded and
+    // sensitive folders stay hidden here too
+    async fn complete_argument_path(&self, argument: &str, limit: usize) -> Vec<String> {
+        let Some(path_query) = parse_path_query(argument) else {
+            return Vec::new();
+        };
+        let filter = PathFilter::from_config(&*self.config.read().await);
+        let listing_query = path_query.clone();
+        match tokio::task::spawn_blocking(move || list_path_completions(&listing_query, &filter)).await {
+            Ok(Some(PathListing::Entries(entries))) => entries.iter()
+                .take(limit)
+                .map(|entry| path_query.completed(&entry.name, entry.is_dir))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+    
+    fn argument_result(app_info: &AppInfo, argument: &str, score: f64) -> SearchResult {
+        // The user's argument follows any launch arguments the app already carries
+        let mut args = app_info.args.clone();
+        args.push(expand_home(argument));
+        
+        SearchResult::new(
+            format!("Open {} with '{}'", app_info.name, argument),
+            app_info.executable_path.display().to_string(),
+        )
+        .with_action(Action::ExecuteApplication {
+            path: app_info.executable_path.clone(),
+            args,
+        })
+        .with_category(Category::Application)
+        .with_path(app_info.executable_path.clone())
+        .with_score(score)
+    }
+    
     async fn search_files(&self, query: &str) -> Vec<SearchResult> {
+        self.search_files_in(query, None).await
+    }
+    
+    async fn search_files_in(&self, query: &str, folder: Option<&Path>) -> Vec<SearchResult> {
         let config = self.config.read().await;
         if !config.search.enable_file_search {
             return Vec::new();
         }
         
-        match self.index_manager.search_files(query).await {
+        match self.index_manager.search_files_in(query, folder).await {
             Ok(results) => results,
             Err(e) => {
                 error!("File search failed: {}", e);
@@ -97,33 +566,518 @@ impl SearchEngine {
         }
     }
     
+    async fn search_sources(&self, query: &str) -> Vec<SearchResult> {
+        let sources = self.sources.read().await.clone();
+        let mut results = Vec::new();
+        
+        for source in sources {
+            results.extend(source.search(query).await);
+        }
+        
+        results
+    }
+    
+    async fn sort_and_limit_results(&self, results: Vec<SearchResult>, query: &str) -> Vec<SearchResult> {
+        let ranking = self.config.read().await.ranking.clone();
+        self.rank_results(results, query, &ranking).await
+            .into_iter()
+            .map(|(result, _)| result)
+            .collect()
+    }
+    
+    async fn rank_results(
+        &self,
+        results: Vec<SearchResult>,
+        query: &str,
+        ranking: &RankingConfig,
+    ) -> Vec<(SearchResult, ScoreBreakdown)> {
+        let max_results = self.config.read().await.behavior.max_results;
+        self.rank_limited(results, query, ranking, max_results).await
+    }
     
-    async fn sort_and_limit_results(&self, mut results: Vec<SearchResult>, query: &str) -> Vec<SearchResult> {
+    async fn rank_limited(
+        &self,
+        mut results: Vec<SearchResult>,
+        query: &str,
+        ranking: &RankingConfig,
+        max_results: usize,
+    ) -> Vec<(SearchResult, ScoreBreakdown)> {
         let config = self.config.read().await;
-        let max_results = config.behavior.max_results;
+        let usage = self.usage.read().await.clone();
+        let selection_bonuses = usage.selection_bonuses(query).await;
+        let usage_scores = usage.usage_scores(ranking.usage_half_life_days).await;
+        
+        // Fuzzy matching blends into the source's score; results picked before for this query
+        // prefix and often launched ones move up, by capped amounts
+        let scored: Vec<(ScoreBreakdown, f64)> = results.iter()
+            .map(|result| {
+                let fuzzy = self.matcher.fuzzy_match(&result.title, query)
+                    .map(|score| score as f64 / 100.0); // Normalize to 0.0-1.0
+                let bonus = selection_bonuses.get(&result.id).copied().unwrap_or(0.0);
+                let usage_score = usage_scores.get(&result.id).copied().unwrap_or(0.0);
+                (ScoreBreakdown::new(result.score, fuzzy, bonus, usage_score, ranking), bonus)
+            })
+            .collect();
         
-        // Sort by score (descending)
-        results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+        // Files that suit the app in front move up a little, never past an exact title match
+        let foreground_app = self.foreground_app.lock().unwrap().clone();
+        let context = foreground_app.as_deref()
+            .and_then(|app| Some((app, context_boosts_for(app, &ranking.context_boosts)?)));
+        let exact_floor = results.iter()
+            .zip(&scored)
+            .filter(|(result, _)| is_exact_title_match(result, query))
+            .map(|(_, (breakdown, _))| breakdown.total)
+            .min_by(|a, b| a.total_cmp(b));
         
-        // Apply fuzzy matching boost for better matches
-        for result in &mut results {
-            if let Some(score) = self.matcher.fuzzy_match(&result.title, query) {
-                let normalized_score = score as f64 / 100.0; // Normalize to 0.0-1.0
-                result.score = (result.score + normalized_score) / 2.0; // Combine scores
+        let mut breakdowns = HashMap::new();
+        for (result, (mut breakdown, bonus)) in results.iter_mut().zip(scored) {
+            if let Some((app, boosts)) = context {
+                let boost = boost_below_exact(breakdown.total, context_boost(result, boosts), exact_floor);
+                if boost > 0.0 {
+                    breakdown = breakdown.with_context(boost);
+                    // Explains the move where nothing but the title did
+                    if matches!(result.match_reason, None | Some(MatchReason::TitleMatch)) {
+                        result.match_reason = Some(MatchReason::ContextBoost(app.to_string()));
+                    }
+                }
+            }
+            result.score = breakdown.total;
+            // Results a source can't explain are at least explained by what they were picked for
+            if result.match_reason.is_none() && bonus > 0.0 {
+                result.match_reason = Some(MatchReason::HistoryBoost);
+            }
+            let kept = breakdowns.entry(result.id).or_insert(breakdown);
+            if breakdown.total > kept.total {
+                *kept = breakdown;
             }
         }
         
-        // Re-sort after fuzzy boost
-        results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+        // Sources answer in parallel, so equal scores are ordered by the tie-break chain, never by arrival
+        results.sort_by(|a, b| compare_results(a, b, &config.search.category_priority, &usage_scores));
         
-        // Limit results
-        results.truncate(max_results);
+        // The same result from several sources is listed once, at its best score
+        let mut seen = std::collections::HashSet::new();
+        results.retain(|result| seen.insert(result.id));
         
-        results
+        // Pinned results go first whatever they scored, so truncation never drops them
+        pin_results(&mut results, pinned_titles(query, &ranking.pinned));
+        
+        // Limit results, keeping a few from each source that matched well
+        results = truncate_fairly(results, max_results, config.search.min_results_per_source, config.search.fuzzy_threshold);
+        
+        results = results.into_iter().map(decorate_with_detail_actions).collect();
+        if config.behavior.share_actions {
+            results = results.into_iter().map(decorate_with_share_actions).collect();
+        }
+        
+        results.into_iter()
+            .map(|result| {
+                let breakdown = breakdowns.get(&result.id).copied().unwrap_or_default();
+                (result, breakdown)
+            })
+            .collect()
     }
     
-    pub fn add_to_history(&self, query: &str, selected_result: &SearchResult) {
+    pub async fn add_to_history(&self, query: &str, selected_result: &SearchResult) {
+        let config = self.config.read().await;
+        let (save_history, learn_selection) = (config.behavior.save_search_history, config.behavior.record_usage_stats);
+        drop(config);
+        
+        if learn_selection && !query.trim().is_empty() {
+            let usage = self.usage.read().await.clone();
+            if let Err(e) = usage.record_selection(query, selected_result.id).await {
+                error!("Failed to record selection: {}", e);
+            }
+        }
+        
+        if !save_history {
+            return;
+        }
+        
         info!("Adding to search history: '{}' -> '{}'", query, selected_result.title);
-        // This would store search history for learning user preferences
+        let history = self.history.read().await.clone();
+        if let Err(e) = history.record(query, &selected_result.title).await {
+            error!("Failed to record search history: {}", e);
+        }
+    }
+    
+    pub async fn record_launch(&self, result: &SearchResult) {
+        if !self.config.read().await.behavior.record_usage_stats {
+            return;
+        }
+        
+        let usage = self.usage.read().await.clone();
+        if let Err(e) = usage.record(result).await {
+            error!("Failed to record usage: {}", e);
+        }
+        
+        let sources = self.sources.read().await.clone();
+        for source in sources {
+            source.launched(result).await;
+        }
+    }
+    
+    // A result found again by id, e.g. for --execute-id. Indexed apps and files are looked up
+    // directly; for source results the last launch gives the title to ask the sources for.
+    // Unlike search this publishes no event and leaves history alone
+    pub async fn find_by_id(&self, id: ResultId) -> Option<SearchResult> {
+        if let Some(result) = self.index_manager.find_by_id(id).await {
+            return Some(result);
+        }
+        
+        let usage = self.usage.read().await.clone();
+        let record = usage.records().await.into_iter().rev().find(|record| record.id == id)?;
+        let sources = self.sources.read().await.clone();
+        for source in sources {
+            if let Some(result) = source.search(&record.title).await.into_iter().find(|result| result.id == id) {
+                return Some(result);
+            }
+        }
+        None
+    }
+
+    pub async fn usage_stats(&self, window: StatsWindow, limit: usize) -> UsageStats {
+        let usage = self.usage.read().await.clone();
+        let history = self.history.read().await.clone();
+        let utc_offset = *chrono::Local::now().offset();
+        
+        compute_usage_stats(&usage.records().await, &history.entries().await, window, usage.now(), utc_offset, limit)
+    }
+    
+    pub async fn clear_usage_data(&self) -> std::result::Result<(), UsageError> {
+        let usage = self.usage.read().await.clone();
+        let history = self.history.read().await.clone();
+        clear_usage_data(&usage, &history).await?;
+        info!("Cleared all usage data");
+        Ok(())
+    }
+    
+    // Removing the entries also removes them from completion and stats, which are computed from history
+    pub async fn delete_history_entry(&self, query: &str) -> std::result::Result<(), HistoryError> {
+        let history = self.history.read().await.clone();
+        history.delete_query(query).await?;
+        Ok(())
+    }
+    
+    pub async fn apply_history_retention(&self) {
+        let retention_days = self.config.read().await.behavior.history_retention_days;
+        let history = self.history.read().await.clone();
+        if let Err(e) = history.prune(retention_days).await {
+            warn!("Failed to prune search history: {}", e);
+        }
+    }
+    
+    // One retention pass of the maintenance task; false when `power_policy` deferred it
+    pub async fn history_maintenance_round(&self, power_policy: &PowerPolicy) -> bool {
+        if !power_policy.allows("history maintenance").await {
+            return false;
+        }
+        self.apply_history_retention().await;
+        true
+    }
+    
+    // Daily retention pass for long-running sessions; the store is also pruned when it is loaded
+    // Pruning waits while `power_policy` defers background work
+    pub fn start_history_maintenance(self: &Arc<Self>, power_policy: Arc<PowerPolicy>) {
+        let engine = self.clone();
+        TaskSupervisor::default().spawn("history-maintenance", move || {
+            let engine = engine.clone();
+            let power_policy = power_policy.clone();
+            async move {
+                let mut interval_timer = tokio::time::interval(Self::MAINTENANCE_INTERVAL);
+                // The first tick completes immediately; loading already pruned
+                interval_timer.tick().await;
+                
+                loop {
+                    interval_timer.tick().await;
+                    engine.history_maintenance_round(&power_policy).await;
+                }
+            }
+        });
+    }
+    
+    // Ghost-text completion for the input: the most frecent past query extending what was typed
+    pub async fn suggest_completion(&self, typed: &str) -> Option<String> {
+        let config = self.config.read().await;
+        if !config.behavior.save_search_history || typed.trim().is_empty() {
+            return None;
+        }
+        drop(config);
+        
+        let history = self.history.read().await.clone();
+        history.suggest(typed).await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Results are deduplicated by id, so the argument result must not collide with the plain app
+    #[test]
+    fn argument_result_has_its_own_id() {
+        let app = AppInfo::new("Code", PathBuf::from("/usr/bin/code")).with_args(vec!["--new-window".to_string()]);
+        let plain = app.to_search_result();
+        let with_argument = SearchEngine::argument_result(&app, "~/projects", 1.0);
+        
+        assert_ne!(plain.id, with_argument.id);
+        assert_ne!(with_argument.id, SearchEngine::argument_result(&app, "~/other", 1.0).id);
+    }
+    
+    #[test]
+    fn pinned_results_go_first_in_pin_order() {
+        let mut results: Vec<SearchResult> = ["Terminal", "Alacritty", "Kitty", "Termius"].into_iter()
+            .map(|title| SearchResult::new(title, ""))
+            .collect();
+        let pinned = HashMap::from([(" Term ".to_string(), vec!["Kitty".to_string(), "Missing".to_string(), "Alacritty".to_string()])]);
+        
+        pin_results(&mut results, pinned_titles("term", &pinned));
+        
+        let titles: Vec<&str> = results.iter().map(|result| result.title.as_str()).collect();
+        assert_eq!(titles, ["Kitty", "Alacritty", "Terminal", "Termius"]);
+        assert_eq!(results[0].match_reason, Some(MatchReason::Pinned));
+        assert_eq!(results[2].match_reason, None);
+        assert!(pinned_titles("ter", &pinned).is_empty());
+    }
+    
+    // Argument completions go through path mode's filter: excluded folders never show up
+    #[tokio::test]
+    async fn argument_paths_complete_like_path_mode() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = dir.path().join("files");
+        std::fs::create_dir_all(files.join("Projects")).unwrap();
+        std::fs::create_dir_all(files.join("private")).unwrap();
+        std::fs::write(files.join("presentation.txt"), "").unwrap();
+        std::fs::write(files.join(".profile"), "").unwrap();
+        let mut config = Config::default();
+        config.search.exclude_patterns = vec!["private".to_string()];
+        let test = crate::testing::engine(dir.path(), config).await;
+        let typed = format!("{}/", files.display());
+        
+        let completions = test.engine.complete_argument_path(&format!("{}p", typed), 5).await;
+        assert_eq!(completions, [format!("{}Projects/", typed), format!("{}presentation.txt", typed)]);
+        assert_eq!(test.engine.complete_argument_path(&format!("{}.p", typed), 5).await, [format!("{}.profile", typed)]);
+        assert_eq!(test.engine.complete_argument_path(&typed, 1).await.len(), 1);
+        // Not a path yet
+        assert!(test.engine.complete_argument_path("notes", 5).await.is_empty());
+    }
+    
+    #[tokio::test]
+    async fn completions_are_off_without_search_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let test = crate::testing::engine(dir.path(), Config::default()).await;
+        test.history.record("firefox", "Firefox").await.unwrap();
+        assert_eq!(test.engine.suggest_completion("fi").await.as_deref(), Some("firefox"));
+        
+        test.config.write().await.behavior.save_search_history = false;
+        assert_eq!(test.engine.suggest_completion("fi").await, None);
+    }
+    
+    // The app's own launch arguments come first and the user's argument is expanded after them
+    #[test]
+    fn argument_result_appends_the_expanded_argument() {
+        let app = AppInfo::new("Code", PathBuf::from("/usr/bin/code")).with_args(vec!["--new-window".to_string()]);
+        let result = SearchEngine::argument_result(&app, "~/projects/foo", 1.0);
+        let expected = Action::ExecuteApplication {
+            path: PathBuf::from("/usr/bin/code"),
+            args: vec!["--new-window".to_string(), expand_home("~/projects/foo")],
+        };
+        assert_eq!(result.action, expected);
+        assert_eq!(result.title, "Open Code with '~/projects/foo'");
+        
+        let json = serde_json::to_value(&result.action).unwrap();
+        assert_eq!(json["ExecuteApplication"]["args"][0], "--new-window");
+        assert_eq!(serde_json::from_value::<Action>(json).unwrap(), expected);
+    }
+    
+    fn titles(results: &[SearchResult]) -> Vec<&str> {
+        results.iter().map(|result| result.title.as_str()).collect()
+    }
+    
+    // Three picks of Terminal after typing "te" put it above Teams for "te", and only there
+    #[tokio::test]
+    async fn selections_flip_the_ranking_for_the_trained_prefix_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let test = crate::testing::engine(dir.path(), Config::default()).await;
+        let app = |title: &str, score: f64| SearchResult::new(title, "")
+            .with_action(Action::OpenFile(PathBuf::from(format!("/apps/{}", title))))
+            .with_category(Category::Application)
+            .with_score(score);
+        let results = || vec![app("Teams", 0.9), app("Terminal", 0.62)];
+        
+        for query in ["te", "tea", "x"] {
+            assert_eq!(titles(&test.engine.sort_and_limit_results(results(), query).await)[0], "Teams", "before training, {:?}", query);
+        }
+        
+        for picks in 1..=3 {
+            test.engine.add_to_history("te", &app("Terminal", 0.62)).await;
+            let ranked = test.engine.sort_and_limit_results(results(), "te").await;
+            // One or two picks aren't enough to overturn the sources' own ranking
+            assert_eq!(titles(&ranked)[0], if picks == 3 { "Terminal" } else { "Teams" }, "after {} picks", picks);
+        }
+        
+        let usage = test.engine.usage_store().await;
+        assert_eq!(usage.selection_bonuses("TE").await, usage.selection_bonuses("te").await);
+        assert_eq!(titles(&test.engine.sort_and_limit_results(results(), "tea").await)[0], "Teams");
+        assert_eq!(titles(&test.engine.sort_and_limit_results(results(), "x").await)[0], "Teams");
+    }
+    
+    // Each pair differs only in the step being checked, so the earlier steps all tie
+    #[test]
+    fn equal_scores_order_by_the_documented_chain() {
+        let priority = Config::default().search.category_priority;
+        let result = |title: &str, category: Category| SearchResult::new(title, "")
+            .with_action(Action::OpenFile(PathBuf::from(format!("/{}", title))))
+            .with_category(category)
+            .with_score(0.5);
+        let compare = |a: &SearchResult, b: &SearchResult, usage: &HashMap<ResultId, f64>| compare_results(a, b, &priority, usage);
+        let none = HashMap::new();
+        
+        // Score beats everything after it
+        let file = result("Report", Category::File);
+        let app = result("Reporter", Category::Application);
+        assert_eq!(compare(&file.clone().with_score(0.6), &app, &none), Ordering::Less);
+        // Category: applications before files
+        assert_eq!(compare(&app, &file, &none), Ordering::Less);
+        // Usage within a category, even against a shorter title
+        let short = result("Notes", Category::File);
+        let used = HashMap::from([(file.id, 3.0)]);
+        assert_eq!(compare(&file, &short, &used), Ordering::Less);
+        // Title length when usage ties
+        assert_eq!(compare(&short, &file, &none), Ordering::Less);
+        // The id decides between otherwise equal results, and only identical results compare equal
+        let other = result("Repoxt", Category::File);
+        assert_eq!(compare(&file, &other, &none), file.id.cmp(&other.id));
+        assert_ne!(compare(&file, &other, &none), Ordering::Equal);
+        assert_eq!(compare(&file, &file.clone(), &none), Ordering::Equal);
+        
+        // Plugin results honour a per-plugin entry before the generic one
+        let priority = vec!["plugin:calc".to_string(), "application".to_string(), "plugin".to_string()];
+        assert_eq!(category_rank(&Category::Plugin("calc".to_string()), &priority), 0);
+        assert_eq!(category_rank(&Category::Plugin("notes".to_string()), &priority), 2);
+        assert_eq!(category_rank(&Category::File, &priority), priority.len());
+    }
+    
+    // Same input in any order, same output: the parallel sources finish in arbitrary order
+    #[tokio::test]
+    async fn shuffled_input_sorts_identically() {
+        let dir = tempfile::tempdir().unwrap();
+        let test = crate::testing::engine(dir.path(), Config::default()).await;
+        let categories = [Category::Application, Category::File, Category::Bookmark, Category::SystemCommand];
+        let input: Vec<SearchResult> = (0..24)
+            .map(|i| SearchResult::new(format!("Item {}{}", "x".repeat(i % 3), i % 5), format!("#{}", i))
+                .with_action(Action::OpenFile(PathBuf::from(format!("/items/{}", i))))
+                .with_category(categories[i % categories.len()].clone())
+                .with_score([0.8, 0.5][i % 2]))
+            .collect();
+        test.engine.add_to_history("unrelated", &input[7]).await;
+        
+        let expected = test.engine.sort_and_limit_results(input.clone(), "item").await;
+        let expected: Vec<ResultId> = expected.iter().map(|result| result.id).collect();
+        // A small LCG keeps the shuffles reproducible without pulling in rand
+        let mut seed: u64 = 0x2452;
+        for round in 0..50 {
+            let mut shuffled = input.clone();
+            for i in (1..shuffled.len()).rev() {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                shuffled.swap(i, (seed >> 33) as usize % (i + 1));
+            }
+            let sorted = test.engine.sort_and_limit_results(shuffled, "item").await;
+            let ids: Vec<ResultId> = sorted.iter().map(|result| result.id).collect();
+            assert_eq!(ids, expected, "shuffle {}", round);
+        }
+    }
+    
+    // 50 files outscoring both apps would otherwise leave no app in a page of 10
+    #[test]
+    fn every_source_above_the_threshold_keeps_its_minimum() {
+        let result = |title: String, category: Category, score: f64| SearchResult::new(title, "").with_category(category).with_score(score);
+        let mut results: Vec<SearchResult> = (0..50)
+            .map(|i| result(format!("report-{:02}.txt", i), Category::File, 0.95 - i as f64 * 0.005))
+            .collect();
+        results.push(result("Reporter".to_string(), Category::Application, 0.6));
+        results.push(result("Report Viewer".to_string(), Category::Application, 0.5));
+        results.push(result("report (bookmark)".to_string(), Category::Bookmark, 0.1));
+        let count = |results: &[SearchResult], category: &Category| results.iter().filter(|result| &result.category == category).count();
+        
+        let kept = truncate_fairly(results.clone(), 10, 1, 0.3);
+        assert_eq!(kept.len(), 10);
+        assert_eq!((count(&kept, &Category::File), count(&kept, &Category::Application)), (9, 1));
+        // The lowest-ranked file made room, and the order is unchanged
+        assert_eq!(titles(&kept)[8..], ["report-08.txt", "Reporter"]);
+        // Below the threshold nothing is brought back
+        assert_eq!(count(&kept, &Category::Bookmark), 0);
+        
+        let kept = truncate_fairly(results.clone(), 10, 2, 0.3);
+        assert_eq!(titles(&kept)[7..], ["report-07.txt", "Reporter", "Report Viewer"]);
+        
+        // Without a minimum it is a plain cut, and a short list is left alone
+        assert_eq!(truncate_fairly(results.clone(), 10, 0, 0.3), results[..10].to_vec());
+        assert_eq!(truncate_fairly(results[48..].to_vec(), 10, 1, 0.3), results[48..].to_vec());
+    }
+    
+    fn editor_config() -> Config {
+        let mut config = Config::default();
+        config.ranking.context_boosts = HashMap::from([("code".to_string(), HashMap::from([("rs".to_string(), 0.1)]))]);
+        config
+    }
+    
+    fn file(name: &str, score: f64) -> SearchResult {
+        SearchResult::new(name, "")
+            .with_action(Action::OpenFile(PathBuf::from(format!("/src/{}", name))))
+            .with_category(Category::File)
+            .with_path(PathBuf::from(format!("/src/{}", name)))
+            .with_score(score)
+    }
+    
+    // With the editor in front a Rust file moves above the text file that outscored it, and says why
+    #[tokio::test]
+    async fn the_app_in_front_boosts_its_file_types() {
+        let dir = tempfile::tempdir().unwrap();
+        let test = crate::testing::engine(dir.path(), editor_config()).await;
+        let results = || vec![file("main.txt", 0.62), file("main.rs", 0.6)];
+        assert_eq!(titles(&test.engine.sort_and_limit_results(results(), "mai").await), ["main.txt", "main.rs"]);
+        
+        test.engine.set_foreground_app(Some("Visual Studio Code".to_string()));
+        let ranked = test.engine.sort_and_limit_results(results(), "mai").await;
+        assert_eq!(titles(&ranked), ["main.rs", "main.txt"]);
+        assert_eq!(ranked[0].match_reason, Some(MatchReason::ContextBoost("Visual Studio Code".to_string())));
+        assert_eq!(ranked[1].match_reason, None);
+        
+        // An app without an entry boosts nothing
+        test.engine.set_foreground_app(Some("Thunderbird".to_string()));
+        assert_eq!(titles(&test.engine.sort_and_limit_results(results(), "mai").await), ["main.txt", "main.rs"]);
+    }
+    
+    // The boost is capped just below the exact match, so a boosted file never overtakes or ties it
+    #[tokio::test]
+    async fn boosted_files_stay_below_an_exact_title_match() {
+        let dir = tempfile::tempdir().unwrap();
+        let test = crate::testing::engine(dir.path(), editor_config()).await;
+        test.engine.set_foreground_app(Some("code".to_string()));
+        let exact = SearchResult::new("main", "")
+            .with_action(Action::OpenFile(PathBuf::from("/apps/main")))
+            .with_category(Category::Application)
+            .with_score(0.6);
+        
+        let ranked = test.engine.sort_and_limit_results(vec![file("main.rs", 0.58), exact], "main").await;
+        assert_eq!(titles(&ranked), ["main", "main.rs"]);
+        assert!(ranked[1].score < ranked[0].score, "{} vs {}", ranked[1].score, ranked[0].score);
+        assert_eq!(ranked[1].match_reason, Some(MatchReason::ContextBoost("code".to_string())));
+    }
+    
+    // Platforms that can't tell which app is in front leave the ranking as it was
+    #[tokio::test]
+    async fn no_app_in_front_means_no_boost() {
+        let dir = tempfile::tempdir().unwrap();
+        let test = crate::testing::engine(dir.path(), editor_config()).await;
+        let unboosted = test.engine.sort_and_limit_results(vec![file("main.rs", 0.6)], "mai").await;
+        
+        test.engine.set_foreground_app(Some("code".to_string()));
+        test.engine.set_foreground_app(None);
+        let ranked = test.engine.sort_and_limit_results(vec![file("main.rs", 0.6)], "mai").await;
+        assert_eq!(ranked[0].score, unboosted[0].score);
+        assert_eq!(ranked[0].match_reason, None);
+    }
+}
+
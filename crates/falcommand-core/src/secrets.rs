@@ -0,0 +1,437 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::RwLock;
+use async_trait::async_trait;
+use log::{info, warn};
+
+use falcommand_config::PathResolver;
+
+#[derive(Debug, thiserror::Error)]
+pub enum SecretError {
+    #[error("Failed to access secret store: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Failed to serialize secrets: {0}")]
+    SerializationError(#[from] serde_json::Error),
+
+    #[error("Secret store unavailable: {0}")]
+    Unavailable(String),
+
+    #[error("OS keyring error: {0}")]
+    KeyringError(#[from] keyring::Error),
+}
+
+// Storage for credentials such as cloud provider tokens
+#[async_trait]
+pub trait SecretStore: Send + Sync {
+    fn name(&self) -> &str;
+    // False when anyone who can read the user's files can read the secrets
+    fn encrypts_at_rest(&self) -> bool;
+    async fn is_available(&self) -> bool;
+    // The file the secrets are encrypted into and the name of where its key comes from; None for
+    // stores that keep them elsewhere
+    fn encrypted_file(&self) -> Option<(&std::path::Path, &str)> {
+        None
+    }
+    async fn get(&self, key: &str) -> std::result::Result<Option<String>, SecretError>;
+    async fn set(&self, key: &str, value: &str) -> std::result::Result<(), SecretError>;
+    async fn delete(&self, key: &str) -> std::result::Result<(), SecretError>;
+}
+
+// The OS credential store: Keychain on macOS, Credential Manager on Windows, the Secret Service
+// (GNOME Keyring, KWallet) elsewhere. Each secret is one entry under SERVICE, named by its key
+#[derive(Debug)]
+pub struct KeyringSecretStore;
+
+impl KeyringSecretStore {
+    pub const SERVICE: &'static str = "falcommand";
+    const PROBE_KEY: &'static str = "availability-probe";
+
+    // The keyring APIs block, and on Linux talk to D-Bus, so they run off the async runtime
+    async fn with_entry<T: Send + 'static>(
+        key: &str,
+        operation: impl FnOnce(&keyring::Entry) -> keyring::Result<T> + Send + 'static,
+    ) -> keyring::Result<T> {
+        let key = key.to_string();
+        tokio::task::spawn_blocking(move || operation(&keyring::Entry::new(Self::SERVICE, &key)?))
+            .await
+            .unwrap_or_else(|e| Err(keyring::Error::PlatformFailure(Box::new(e))))
+    }
+}
+
+#[async_trait]
+impl SecretStore for KeyringSecretStore {
+    fn name(&self) -> &str {
+        "Keyring"
+    }
+
+    fn encrypts_at_rest(&self) -> bool {
+        true
+    }
+
+    // A lookup that reaches the store answers even when there is nothing under the key
+    async fn is_available(&self) -> bool {
+        matches!(
+            Self::with_entry(Self::PROBE_KEY, |entry| entry.get_password()).await,
+            Ok(_) | Err(keyring::Error::NoEntry)
+        )
+    }
+
+    async fn get(&self, key: &str) -> std::result::Result<Option<String>, SecretError> {
+        match Self::with_entry(key, |entry| entry.get_password()).await {
+            Ok(secret) => Ok(Some(secret)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn set(&self, key: &str, value: &str) -> std::result::Result<(), SecretError> {
+        let value = value.to_string();
+        Ok(Self::with_entry(key, move |entry| entry.set_password(&value)).await?)
+    }
+
+    async fn delete(&self, key: &str) -> std::result::Result<(), SecretError> {
+        match Self::with_entry(key, |entry| entry.delete_credential()).await {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+// Where the file store's key comes from. Only the material is resolved; the key derived from it
+// is never written anywhere
+#[async_trait]
+pub trait KeyResolver: Send + Sync {
+    fn name(&self) -> &str;
+    async fn key_material(&self) -> std::result::Result<Vec<u8>, SecretError>;
+}
+
+// A passphrase from FALCOMMAND_SECRETS_PASSPHRASE, so a portable copy can open its secrets on
+// any machine
+pub struct PassphraseKey {
+    passphrase: String,
+}
+
+impl PassphraseKey {
+    pub const ENV: &'static str = "FALCOMMAND_SECRETS_PASSPHRASE";
+
+    pub fn new(passphrase: impl Into<String>) -> Self {
+        Self { passphrase: passphrase.into() }
+    }
+
+    pub fn from_env() -> Option<Self> {
+        std::env::var(Self::ENV).ok().filter(|passphrase| !passphrase.is_empty()).map(Self::new)
+    }
+}
+
+#[async_trait]
+impl KeyResolver for PassphraseKey {
+    fn name(&self) -> &str {
+        "Passphrase"
+    }
+
+    async fn key_material(&self) -> std::result::Result<Vec<u8>, SecretError> {
+        Ok(self.passphrase.as_bytes().to_vec())
+    }
+}
+
+// This machine's id together with the user's home directory: the file is useless copied to
+// another machine or read by another account
+pub struct MachineKey;
+
+impl MachineKey {
+    fn machine_id() -> Option<String> {
+        if cfg!(target_os = "macos") {
+            // "IOPlatformUUID" = "564D..."
+            let output = std::process::Command::new("ioreg").args(["-rd1", "-c", "IOPlatformExpertDevice"]).output().ok()?;
+            let text = String::from_utf8_lossy(&output.stdout).into_owned();
+            let line = text.lines().find(|line| line.contains("IOPlatformUUID"))?;
+            return line.rsplit('"').nth(1).map(str::to_string);
+        }
+        if cfg!(windows) {
+            // MachineGuid    REG_SZ    1a2b...
+            let output = std::process::Command::new("reg")
+                .args(["query", r"HKLM\SOFTWARE\Microsoft\Cryptography", "/v", "MachineGuid"])
+                .output()
+                .ok()?;
+            let text = String::from_utf8_lossy(&output.stdout).into_owned();
+            let line = text.lines().find(|line| line.contains("MachineGuid"))?;
+            return line.split_whitespace().last().map(str::to_string);
+        }
+        ["/etc/machine-id", "/var/lib/dbus/machine-id"].iter()
+            .filter_map(|path| std::fs::read_to_string(path).ok())
+            .map(|id| id.trim().to_string())
+            .find(|id| !id.is_empty())
+    }
+}
+
+#[async_trait]
+impl KeyResolver for MachineKey {
+    fn name(&self) -> &str {
+        "Machine key"
+    }
+
+    async fn key_material(&self) -> std::result::Result<Vec<u8>, SecretError> {
+        let id = tokio::task::spawn_blocking(Self::machine_id)
+            .await
+            .ok()
+            .flatten()
+            .ok_or_else(|| SecretError::Unavailable("No machine id to derive the secrets key from".to_string()))?;
+        let home = dirs::home_dir().unwrap_or_default();
+        Ok(format!("{}\n{}", id, home.display()).into_bytes())
+    }
+}
+
+// The secrets as encrypted JSON next to the rest of the data, for portable mode and where no
+// keyring is available. The file is MAGIC, a salt, a nonce, then the ChaCha20-Poly1305 ciphertext
+// under a key derived from the resolver's material with PBKDF2; a fresh nonce is drawn per write
+pub struct EncryptedFileSecretStore {
+    path: PathBuf,
+    key_resolver: Arc<dyn KeyResolver>,
+    secrets: RwLock<Option<HashMap<String, String>>>,
+    // Derived once per salt; deriving is deliberately slow
+    key: RwLock<Option<([u8; SALT_LEN], [u8; 32])>>,
+}
+
+const MAGIC: &[u8] = b"FCSECRETS1";
+const SALT_LEN: usize = 16;
+const PBKDF2_ITERATIONS: u32 = 100_000;
+
+impl EncryptedFileSecretStore {
+    pub fn new(path: PathBuf, key_resolver: Arc<dyn KeyResolver>) -> Self {
+        Self {
+            path,
+            key_resolver,
+            secrets: RwLock::new(None),
+            key: RwLock::new(None),
+        }
+    }
+
+    pub fn path(&self) -> &std::path::Path {
+        &self.path
+    }
+
+    async fn derive_key(&self, salt: [u8; SALT_LEN]) -> std::result::Result<ring::aead::LessSafeKey, SecretError> {
+        let cached = *self.key.read().await;
+        let key = match cached {
+            Some((cached_salt, key)) if cached_salt == salt => key,
+            _ => {
+                let material = self.key_resolver.key_material().await?;
+                let mut key = [0u8; 32];
+                let iterations = std::num::NonZeroU32::new(PBKDF2_ITERATIONS).expect("non-zero");
+                ring::pbkdf2::derive(ring::pbkdf2::PBKDF2_HMAC_SHA256, iterations, &salt, &material, &mut key);
+                *self.key.write().await = Some((salt, key));
+                key
+            }
+        };
+        let key = ring::aead::UnboundKey::new(&ring::aead::CHACHA20_POLY1305, &key).map_err(crypto_error)?;
+        Ok(ring::aead::LessSafeKey::new(key))
+    }
+
+    async fn decrypt(&self, content: &[u8]) -> std::result::Result<HashMap<String, String>, SecretError> {
+        let body = content.strip_prefix(MAGIC)
+            .filter(|body| body.len() >= SALT_LEN + ring::aead::NONCE_LEN)
+            .ok_or_else(|| SecretError::Unavailable(format!("{} is not a secrets file", self.path.display())))?;
+        let (salt, body) = body.split_at(SALT_LEN);
+        let (nonce, ciphertext) = body.split_at(ring::aead::NONCE_LEN);
+
+        let key = self.derive_key(salt.try_into().expect("salt length")).await?;
+        let nonce = ring::aead::Nonce::try_assume_unique_for_key(nonce).map_err(crypto_error)?;
+        let mut plaintext = ciphertext.to_vec();
+        let plaintext = key.open_in_place(nonce, ring::aead::Aad::from(MAGIC), &mut plaintext)
+            .map_err(|_| SecretError::Unavailable(format!(
+                "Can't decrypt {}: the {} differs from the one it was saved with", self.path.display(), self.key_resolver.name(),
+            )))?;
+        Ok(serde_json::from_slice(plaintext)?)
+    }
+
+    async fn encrypt(&self, secrets: &HashMap<String, String>) -> std::result::Result<Vec<u8>, SecretError> {
+        use ring::rand::SecureRandom;
+        let random = ring::rand::SystemRandom::new();
+        // The salt stays with the file, so the key is derived once
+        let salt = match *self.key.read().await {
+            Some((salt, _)) => salt,
+            None => {
+                let mut salt = [0u8; SALT_LEN];
+                random.fill(&mut salt).map_err(crypto_error)?;
+                salt
+            }
+        };
+        let mut nonce = [0u8; ring::aead::NONCE_LEN];
+        random.fill(&mut nonce).map_err(crypto_error)?;
+
+        let key = self.derive_key(salt).await?;
+        let mut ciphertext = serde_json::to_vec(secrets)?;
+        key.seal_in_place_append_tag(ring::aead::Nonce::assume_unique_for_key(nonce), ring::aead::Aad::from(MAGIC), &mut ciphertext)
+            .map_err(crypto_error)?;
+        Ok([MAGIC, &salt, &nonce, &ciphertext].concat())
+    }
+
+    async fn load(&self) -> std::result::Result<HashMap<String, String>, SecretError> {
+        if let Some(ref secrets) = *self.secrets.read().await {
+            return Ok(secrets.clone());
+        }
+
+        let secrets = if self.path.exists() {
+            self.decrypt(&tokio::fs::read(&self.path).await?).await?
+        } else {
+            HashMap::new()
+        };
+
+        *self.secrets.write().await = Some(secrets.clone());
+        Ok(secrets)
+    }
+
+    async fn persist(&self, secrets: &HashMap<String, String>) -> std::result::Result<(), SecretError> {
+        if let Some(parent) = self.path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        let content = self.encrypt(secrets).await?;
+        let tmp_path = self.path.with_extension("tmp");
+        let mut options = tokio::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+        let mut file = options.open(&tmp_path).await?;
+        file.write_all(&content).await?;
+        file.sync_all().await?;
+        drop(file);
+        tokio::fs::rename(&tmp_path, &self.path).await?;
+
+        *self.secrets.write().await = Some(secrets.clone());
+        Ok(())
+    }
+}
+
+fn crypto_error(_: ring::error::Unspecified) -> SecretError {
+    SecretError::Unavailable("The secrets file could not be encrypted".to_string())
+}
+
+#[async_trait]
+impl SecretStore for EncryptedFileSecretStore {
+    fn name(&self) -> &str {
+        "Encrypted file"
+    }
+
+    fn encrypts_at_rest(&self) -> bool {
+        true
+    }
+
+    async fn is_available(&self) -> bool {
+        self.key_resolver.key_material().await.is_ok()
+    }
+
+    fn encrypted_file(&self) -> Option<(&std::path::Path, &str)> {
+        Some((&self.path, self.key_resolver.name()))
+    }
+
+    async fn get(&self, key: &str) -> std::result::Result<Option<String>, SecretError> {
+        Ok(self.load().await?.get(key).cloned())
+    }
+
+    async fn set(&self, key: &str, value: &str) -> std::result::Result<(), SecretError> {
+        let mut secrets = self.load().await?;
+        secrets.insert(key.to_string(), value.to_string());
+        self.persist(&secrets).await
+    }
+
+    async fn delete(&self, key: &str) -> std::result::Result<(), SecretError> {
+        let mut secrets = self.load().await?;
+        if secrets.remove(key).is_some() {
+            self.persist(&secrets).await?;
+        }
+        Ok(())
+    }
+}
+
+// Portable mode never touches the OS keyring; otherwise the keyring is preferred when present.
+// The file store's key is the passphrase when one is set, else this machine's
+pub async fn create_secret_store(resolver: &PathResolver) -> Arc<dyn SecretStore> {
+    let key_resolver: Arc<dyn KeyResolver> = match PassphraseKey::from_env() {
+        Some(passphrase) => Arc::new(passphrase),
+        None => Arc::new(MachineKey),
+    };
+    let file_store = Arc::new(EncryptedFileSecretStore::new(resolver.shared_data_file("secrets.enc"), key_resolver));
+
+    if resolver.is_portable() {
+        info!("Portable mode: secrets are stored encrypted in {:?}", file_store.path());
+        return file_store;
+    }
+
+    let keyring = Arc::new(KeyringSecretStore);
+    if keyring.is_available().await {
+        info!("Storing secrets in the OS keyring");
+        keyring
+    } else {
+        warn!("OS keyring unavailable: secrets are stored encrypted in {:?}", file_store.path());
+        file_store
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // A passphrase that counts how often the store asks for it
+    struct RecordingResolver {
+        passphrase: &'static str,
+        calls: AtomicUsize,
+    }
+
+    impl RecordingResolver {
+        fn new(passphrase: &'static str) -> Arc<Self> {
+            Arc::new(Self { passphrase, calls: AtomicUsize::new(0) })
+        }
+    }
+
+    #[async_trait]
+    impl KeyResolver for RecordingResolver {
+        fn name(&self) -> &str {
+            "Recording passphrase"
+        }
+
+        async fn key_material(&self) -> std::result::Result<Vec<u8>, SecretError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(self.passphrase.as_bytes().to_vec())
+        }
+    }
+
+    #[tokio::test]
+    async fn secrets_round_trip_encrypted() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("secrets.enc");
+        let store = EncryptedFileSecretStore::new(path.clone(), RecordingResolver::new("correct horse"));
+        store.set("dropbox.token", "s3cr3t-token").await.unwrap();
+        store.set("plugin.translator.api_key", "k").await.unwrap();
+        store.delete("plugin.translator.api_key").await.unwrap();
+
+        let content = std::fs::read(&path).unwrap();
+        assert!(content.starts_with(MAGIC));
+        assert!(!String::from_utf8_lossy(&content).contains("s3cr3t-token"));
+
+        let reopened = EncryptedFileSecretStore::new(path, Arc::new(PassphraseKey::new("correct horse")));
+        assert_eq!(reopened.get("dropbox.token").await.unwrap().as_deref(), Some("s3cr3t-token"));
+        assert_eq!(reopened.get("plugin.translator.api_key").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn the_key_is_resolved_once_and_must_match() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("secrets.enc");
+        let resolver = RecordingResolver::new("correct horse");
+        let store = EncryptedFileSecretStore::new(path.clone(), resolver.clone());
+        for i in 0..3 {
+            store.set(&format!("key{}", i), "value").await.unwrap();
+        }
+        assert_eq!(resolver.calls.load(Ordering::SeqCst), 1);
+
+        let wrong = RecordingResolver::new("battery staple");
+        let other = EncryptedFileSecretStore::new(path, wrong.clone());
+        assert!(other.get("key0").await.is_err());
+        assert_eq!(wrong.calls.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,215 @@
+use std::collections::HashMap;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use falcommand_config::ResultId;
+
+// How often a result was picked after typing a query prefix; `weight` is as of `updated`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SelectionCount {
+    pub id: ResultId,
+    pub weight: f64,
+    pub updated: DateTime<Utc>,
+}
+
+impl SelectionCount {
+    fn weight_at(&self, now: DateTime<Utc>) -> f64 {
+        let age_days = (now - self.updated).num_seconds().max(0) as f64 / 86_400.0;
+        self.weight * 0.5f64.powf(age_days / SelectionAssociations::HALF_LIFE_DAYS)
+    }
+}
+
+// Query prefix -> results picked for it, so "te" learns to prefer Terminal over Teams.
+// Counts decay exponentially and are bounded per prefix and in total
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SelectionAssociations {
+    prefixes: HashMap<String, Vec<SelectionCount>>,
+}
+
+impl SelectionAssociations {
+    // A query is learned under each of its prefixes up to this many characters
+    pub const MAX_PREFIX_LEN: usize = 8;
+    pub const TOP_K: usize = 8;
+    pub const MAX_PREFIXES: usize = 5_000;
+    pub const HALF_LIFE_DAYS: f64 = 14.0;
+    // Below this a count is forgotten
+    pub const MIN_WEIGHT: f64 = 0.05;
+    // Upper bound of the ranking bonus, below the gap between a prefix match and a substring match
+    pub const MAX_BONUS: f64 = 0.2;
+
+    fn prefixes_of(query: &str) -> Vec<String> {
+        let query = query.trim().to_lowercase();
+        let chars: Vec<char> = query.chars().collect();
+        (1..=chars.len().min(Self::MAX_PREFIX_LEN))
+            .map(|len| chars[..len].iter().collect())
+            .collect()
+    }
+
+    fn lookup_key(query: &str) -> Option<String> {
+        Self::prefixes_of(query).pop()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.prefixes.is_empty()
+    }
+
+    pub fn record(&mut self, query: &str, id: ResultId, now: DateTime<Utc>) {
+        for prefix in Self::prefixes_of(query) {
+            let counts = self.prefixes.entry(prefix).or_default();
+            match counts.iter_mut().find(|count| count.id == id) {
+                Some(count) => {
+                    count.weight = count.weight_at(now) + 1.0;
+                    count.updated = now;
+                }
+                None => counts.push(SelectionCount { id, weight: 1.0, updated: now }),
+            }
+            counts.sort_by(|a, b| b.weight_at(now).total_cmp(&a.weight_at(now)));
+            counts.truncate(Self::TOP_K);
+        }
+        self.prune(now);
+    }
+
+    // Drops forgotten counts, then the least recently used prefixes beyond the limit
+    pub fn prune(&mut self, now: DateTime<Utc>) {
+        for counts in self.prefixes.values_mut() {
+            counts.retain(|count| count.weight_at(now) >= Self::MIN_WEIGHT);
+        }
+        self.prefixes.retain(|_, counts| !counts.is_empty());
+
+        if self.prefixes.len() > Self::MAX_PREFIXES {
+            let mut by_use: Vec<(String, DateTime<Utc>)> = self.prefixes.iter()
+                .map(|(prefix, counts)| (prefix.clone(), counts.iter().map(|c| c.updated).max().unwrap_or(now)))
+                .collect();
+            by_use.sort_by_key(|(_, updated)| *updated);
+            let overflow = self.prefixes.len() - Self::MAX_PREFIXES;
+            for (prefix, _) in by_use.into_iter().take(overflow) {
+                self.prefixes.remove(&prefix);
+            }
+        }
+    }
+
+    // Ranking bonus per result for `query`, between 0 and MAX_BONUS. Three recent picks
+    // give three quarters of the maximum
+    pub fn bonuses(&self, query: &str, now: DateTime<Utc>) -> HashMap<ResultId, f64> {
+        let Some(counts) = Self::lookup_key(query).and_then(|key| self.prefixes.get(&key)) else {
+            return HashMap::new();
+        };
+        counts.iter()
+            .map(|count| {
+                let weight = count.weight_at(now);
+                (count.id, Self::MAX_BONUS * weight / (weight + 1.0))
+            })
+            .filter(|(_, bonus)| *bonus > 0.0)
+            .collect()
+    }
+
+    // Follows results whose id changed, e.g. a file that was renamed
+    pub fn rename_ids(&mut self, ids: &HashMap<ResultId, ResultId>) {
+        for counts in self.prefixes.values_mut() {
+            for count in counts.iter_mut() {
+                if let Some(&id) = ids.get(&count.id) {
+                    count.id = id;
+                }
+            }
+        }
+    }
+
+    // Sync merge: the larger decayed weight of each pair wins
+    pub fn merge(&mut self, other: &SelectionAssociations, now: DateTime<Utc>) {
+        for (prefix, remote_counts) in &other.prefixes {
+            let counts = self.prefixes.entry(prefix.clone()).or_default();
+            for remote in remote_counts {
+                match counts.iter_mut().find(|count| count.id == remote.id) {
+                    Some(count) if count.weight_at(now) >= remote.weight_at(now) => {}
+                    Some(count) => *count = remote.clone(),
+                    None => counts.push(remote.clone()),
+                }
+            }
+            counts.sort_by(|a, b| b.weight_at(now).total_cmp(&a.weight_at(now)));
+            counts.truncate(Self::TOP_K);
+        }
+        self.prune(now);
+    }
+}
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use super::*;
+    use crate::testing::start_time;
+
+    fn bonus(associations: &SelectionAssociations, query: &str, id: u64, now: DateTime<Utc>) -> f64 {
+        associations.bonuses(query, now).get(&ResultId(id)).copied().unwrap_or(0.0)
+    }
+
+    #[test]
+    fn bonuses_grow_with_picks_and_decay_with_time() {
+        let now = start_time();
+        let mut associations = SelectionAssociations::default();
+        associations.record("term", ResultId(1), now);
+        assert!((bonus(&associations, "term", 1, now) - 0.1).abs() < 1e-9);
+        associations.record("term", ResultId(1), now);
+        associations.record("term", ResultId(1), now);
+        assert!((bonus(&associations, "term", 1, now) - 0.15).abs() < 1e-9);
+
+        // Two half-lives later three picks weigh 0.75
+        let later = now + Duration::days(28);
+        let expected = SelectionAssociations::MAX_BONUS * 0.75 / 1.75;
+        assert!((bonus(&associations, "term", 1, later) - expected).abs() < 1e-9);
+        // Shorter prefixes learned it too; longer ones and other results didn't
+        assert!(bonus(&associations, "t", 1, now) > 0.0);
+        assert_eq!(bonus(&associations, "terminal", 1, now), 0.0);
+        assert_eq!(bonus(&associations, "term", 2, now), 0.0);
+    }
+
+    #[test]
+    fn long_queries_are_learned_under_their_first_characters() {
+        let now = start_time();
+        let mut associations = SelectionAssociations::default();
+        associations.record("visual studio code", ResultId(1), now);
+
+        // Lookups past the prefix length share the longest prefix
+        assert!(bonus(&associations, "visual s", 1, now) > 0.0);
+        assert!(bonus(&associations, "visual studio", 1, now) > 0.0);
+        assert_eq!(bonus(&associations, "visual x", 1, now), 0.0);
+    }
+
+    #[test]
+    fn each_prefix_keeps_its_top_results_and_forgets_old_ones() {
+        let now = start_time();
+        let mut associations = SelectionAssociations::default();
+        for id in 0..=SelectionAssociations::TOP_K as u64 {
+            associations.record("te", ResultId(id), now);
+        }
+        associations.record("te", ResultId(3), now);
+        assert_eq!(associations.bonuses("te", now).len(), SelectionAssociations::TOP_K);
+        assert!(bonus(&associations, "te", 3, now) > bonus(&associations, "te", 0, now));
+
+        // Five months on every count is below the minimum weight
+        associations.prune(now + Duration::days(150));
+        assert!(associations.is_empty());
+    }
+
+    #[test]
+    fn merging_keeps_the_larger_weight_and_renames_follow_results() {
+        let now = start_time();
+        let mut local = SelectionAssociations::default();
+        local.record("te", ResultId(1), now);
+        let mut remote = SelectionAssociations::default();
+        for _ in 0..3 {
+            remote.record("te", ResultId(1), now);
+        }
+        remote.record("fi", ResultId(2), now);
+
+        local.merge(&remote, now);
+        assert!((bonus(&local, "te", 1, now) - 0.15).abs() < 1e-9);
+        assert!(bonus(&local, "fi", 2, now) > 0.0);
+        // Merging the weaker side back changes nothing
+        let merged = local.clone();
+        local.merge(&SelectionAssociations::default(), now);
+        assert_eq!(local, merged);
+
+        local.rename_ids(&HashMap::from([(ResultId(2), ResultId(20))]));
+        assert_eq!(bonus(&local, "fi", 2, now), 0.0);
+        assert!(bonus(&local, "fi", 20, now) > 0.0);
+    }
+}
@@ -0,0 +1,124 @@
+use std::path::{Path, PathBuf};
+
+// Credential stores, browser profiles and password vaults under the home directory
+pub fn default_sensitive_dirs(home: &Path) -> Vec<PathBuf> {
+    let mut relative = vec![
+        ".ssh",
+        ".gnupg",
+        ".aws",
+        ".azure",
+        ".kube",
+        ".docker",
+        ".password-store",
+        ".mozilla",
+    ];
+
+    if cfg!(target_os = "windows") {
+        relative.extend([
+            "AppData/Roaming/Microsoft/Credentials",
+            "AppData/Local/Microsoft/Credentials",
+            "AppData/Roaming/Microsoft/Protect",
+            "AppData/Local/Microsoft/Vault",
+            "AppData/Local/Google/Chrome/User Data",
+            "AppData/Local/Microsoft/Edge/User Data",
+            "AppData/Roaming/Mozilla/Firefox/Profiles",
+            "AppData/Local/1Password",
+            "AppData/Roaming/Bitwarden",
+        ]);
+    } else if cfg!(target_os = "macos") {
+        relative.extend([
+            "Library/Keychains",
+            "Library/Cookies",
+            "Library/Application Support/Google/Chrome",
+            "Library/Application Support/Firefox/Profiles",
+            "Library/Application Support/Microsoft Edge",
+            "Library/Group Containers/2BUA8C4S2C.com.1password",
+            "Library/Application Support/Bitwarden",
+        ]);
+    } else {
+        relative.extend([
+            ".local/share/keyrings",
+            ".config/google-chrome",
+            ".config/chromium",
+            ".config/BraveSoftware",
+            ".config/microsoft-edge",
+            ".config/1Password",
+            ".config/Bitwarden",
+        ]);
+    }
+
+    relative.into_iter().map(|dir| home.join(dir)).collect()
+}
+
+// Canonicalizes where possible so symlinked homes still match; missing dirs are kept as written
+fn canonical(path: PathBuf) -> PathBuf {
+    std::fs::canonicalize(&path).unwrap_or(path)
+}
+
+// Paths that are never indexed regardless of include_paths, unless explicitly allowed
+#[derive(Debug, Clone, Default)]
+pub struct SensitivePaths {
+    denied: Vec<PathBuf>,
+    allowed: Vec<PathBuf>,
+}
+
+impl SensitivePaths {
+    pub fn new(denied: Vec<PathBuf>, allowed: &[String]) -> Self {
+        Self {
+            denied: denied.into_iter().map(canonical).collect(),
+            allowed: allowed.iter().map(|p| canonical(PathBuf::from(p))).collect(),
+        }
+    }
+
+    pub fn for_current_user(allowed: &[String]) -> Self {
+        let denied = dirs::home_dir()
+            .map(|home| default_sensitive_dirs(&home))
+            .unwrap_or_default();
+        Self::new(denied, allowed)
+    }
+
+    // Expects a canonical path; this is a plain prefix check so it can run per directory entry
+    pub fn is_denied(&self, path: &Path) -> bool {
+        self.denied.iter().any(|dir| path.starts_with(dir))
+            && !self.allowed.iter().any(|dir| path.starts_with(dir))
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A home directory with a planted ~/.ssh key next to ordinary documents
+    fn fixture_home() -> tempfile::TempDir {
+        let home = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(home.path().join(".ssh")).unwrap();
+        std::fs::write(home.path().join(".ssh/id_ed25519"), "PRIVATE KEY").unwrap();
+        std::fs::create_dir_all(home.path().join("Documents")).unwrap();
+        home
+    }
+
+    fn canonical_join(home: &Path, relative: &str) -> PathBuf {
+        std::fs::canonicalize(home).unwrap().join(relative)
+    }
+
+    #[test]
+    fn defaults_deny_credential_directories() {
+        let home = fixture_home();
+        let sensitive = SensitivePaths::new(default_sensitive_dirs(home.path()), &[]);
+
+        assert!(sensitive.is_denied(&canonical_join(home.path(), ".ssh")));
+        assert!(sensitive.is_denied(&canonical_join(home.path(), ".ssh/id_ed25519")));
+        assert!(!sensitive.is_denied(&canonical_join(home.path(), "Documents")));
+        // A prefix of the name alone is a different directory
+        assert!(!sensitive.is_denied(&canonical_join(home.path(), ".ssh-notes")));
+    }
+
+    #[test]
+    fn allowed_paths_override_the_defaults() {
+        let home = fixture_home();
+        let allowed = [home.path().join(".ssh").to_string_lossy().into_owned()];
+        let sensitive = SensitivePaths::new(default_sensitive_dirs(home.path()), &allowed);
+
+        assert!(!sensitive.is_denied(&canonical_join(home.path(), ".ssh/id_ed25519")));
+        assert!(sensitive.is_denied(&canonical_join(home.path(), ".gnupg")));
+    }
+}
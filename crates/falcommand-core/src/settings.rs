@@ -0,0 +1,266 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use async_trait::async_trait;
+use fuzzy_matcher::{FuzzyMatcher, skim::SkimMatcherV2};
+use serde_json::Value;
+use tokio::sync::RwLock;
+
+use falcommand_config::{Action, Category, Config, SearchResult};
+use falcommand_config::schema::{config_schema, get_config_value, schema_fields, SchemaField, SchemaFieldKind};
+use crate::source::SearchSource;
+
+// Never offered as results: they may hold credentials or private endpoints
+const HIDDEN_SETTINGS: &[&str] = &["sync.provider", "crash_reporting.upload_url", "plugins.plugin_settings"];
+
+// A setting as a search result: its schema entry, the name it is listed by and its current value
+#[derive(Debug, Clone, PartialEq)]
+pub struct SettingEntry {
+    pub field: SchemaField,
+    pub label: String,
+    pub value: Value,
+}
+
+// "visible_result_rows" -> "Visible result rows"
+fn humanize(name: &str) -> String {
+    let words = name.replace('_', " ");
+    let mut chars = words.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+// The settings of `config` in schema order, without the hidden ones. Names shared by several
+// sections ("enabled") are prefixed with their section: "Sync enabled"
+pub fn setting_entries(schema: &Value, config: &Config) -> Vec<SettingEntry> {
+    let fields: Vec<SchemaField> = schema_fields(schema).into_iter()
+        .filter(|field| !HIDDEN_SETTINGS.contains(&field.path.as_str()))
+        .collect();
+
+    let mut name_counts: HashMap<&str, usize> = HashMap::new();
+    for field in &fields {
+        *name_counts.entry(field_name(&field.path)).or_default() += 1;
+    }
+
+    let labels: Vec<String> = fields.iter()
+        .map(|field| match field.path.rsplit_once('.') {
+            Some((section, name)) if name_counts[name] > 1 => humanize(&format!("{}_{}", section.replace('.', "_"), name)),
+            _ => humanize(field_name(&field.path)),
+        })
+        .collect();
+
+    fields.into_iter()
+        .zip(labels)
+        .filter_map(|(field, label)| {
+            let value = get_config_value(config, &field.path).ok()?;
+            Some(SettingEntry { field, label, value })
+        })
+        .collect()
+}
+
+fn field_name(path: &str) -> &str {
+    path.rsplit('.').next().unwrap_or(path)
+}
+
+// How a value reads in a result title: "On", "Ctrl+Space", "3 items"
+pub fn display_value(value: &Value) -> String {
+    match value {
+        Value::Bool(true) => "On".to_string(),
+        Value::Bool(false) => "Off".to_string(),
+        Value::Null => "Not set".to_string(),
+        Value::String(text) => text.clone(),
+        Value::Number(number) => number.to_string(),
+        Value::Array(items) => match items.len() {
+            1 => "1 item".to_string(),
+            count => format!("{} items", count),
+        },
+        Value::Object(entries) => match entries.len() {
+            1 => "1 entry".to_string(),
+            count => format!("{} entries", count),
+        },
+    }
+}
+
+// The value after `current` in schema order, wrapping around; the first one when `current` isn't listed
+pub fn next_enum_value(values: &[String], current: &Value) -> Option<String> {
+    let position = values.iter().position(|value| Some(value.as_str()) == current.as_str());
+    let next = match position {
+        Some(position) => (position + 1) % values.len(),
+        None => 0,
+    };
+    values.get(next).cloned()
+}
+
+// The change a setting offers inline: a toggle for booleans, the next value for enums
+pub fn inline_change(entry: &SettingEntry) -> Option<(String, Action)> {
+    let (title, value) = match entry.field.kind {
+        SchemaFieldKind::Boolean => ("Toggle".to_string(), Value::Bool(!entry.value.as_bool()?)),
+        SchemaFieldKind::Enum(ref values) => {
+            let next = next_enum_value(values, &entry.value)?;
+            (format!("Switch to {}", next), Value::String(next))
+        }
+        SchemaFieldKind::Other => return None,
+    };
+    Some((title, Action::SetSetting { path: entry.field.path.clone(), value }))
+}
+
+pub fn setting_result(entry: &SettingEntry, score: f64) -> SearchResult {
+    let mut result = SearchResult::new(
+        format!("Setting: {} ({})", entry.label, display_value(&entry.value)),
+        format!("{} · {}", entry.field.description, entry.field.path),
+    )
+    .with_action(Action::OpenSettings(entry.field.path.clone()))
+    .with_category(Category::SystemCommand)
+    .with_score(score);
+    if let Some((title, action)) = inline_change(entry) {
+        result = result.with_secondary_action(title, action);
+    }
+    result
+}
+
+// Best matches first; a setting matches on its name, its dotted path or its description
+pub fn match_settings(entries: &[SettingEntry], query: &str, limit: usize) -> Vec<(SettingEntry, i64)> {
+    let matcher = SkimMatcherV2::default();
+    let mut matches: Vec<(SettingEntry, i64)> = entries.iter()
+        .filter_map(|entry| {
+            let label_score = matcher.fuzzy_match(&entry.label, query);
+            let path_score = matcher.fuzzy_match(&entry.field.path.replace(['.', '_'], " "), query);
+            let description_score = matcher.fuzzy_match(&entry.field.description, query).map(|score| score / 2);
+            label_score.max(path_score).max(description_score).map(|score| (entry.clone(), score))
+        })
+        .collect();
+
+    matches.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.field.path.cmp(&b.0.field.path)));
+    matches.truncate(limit);
+    matches
+}
+
+// The configuration itself as results: "transparency" finds the window opacity setting
+pub struct SettingsSource {
+    config: Arc<RwLock<Config>>,
+    schema: Value,
+}
+
+impl SettingsSource {
+    const MIN_QUERY_LEN: usize = 3;
+    const LIMIT: usize = 5;
+    // Settings rank below direct app and file matches
+    const SCORE_SCALE: f64 = 0.7;
+
+    pub fn new(config: Arc<RwLock<Config>>) -> Self {
+        Self { config, schema: config_schema() }
+    }
+}
+
+#[async_trait]
+impl SearchSource for SettingsSource {
+    fn name(&self) -> &str {
+        "Settings"
+    }
+
+    async fn search(&self, query: &str) -> Vec<SearchResult> {
+        let query = query.trim();
+        if query.chars().count() < Self::MIN_QUERY_LEN {
+            return Vec::new();
+        }
+
+        // Read on every search so the titles show the values as they are now
+        let entries = setting_entries(&self.schema, &*self.config.read().await);
+        match_settings(&entries, query, Self::LIMIT).into_iter()
+            .map(|(entry, score)| setting_result(&entry, (score as f64 / 100.0).min(1.0) * Self::SCORE_SCALE))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use falcommand_config::ResultRowLayout;
+
+    fn entry<'a>(entries: &'a [SettingEntry], path: &str) -> Option<&'a SettingEntry> {
+        entries.iter().find(|entry| entry.field.path == path)
+    }
+
+    // Every leaf of the schema becomes a setting with the live value, except the hidden ones
+    #[test]
+    fn schema_fields_become_settings_with_their_values() {
+        let mut config = Config::default();
+        config.behavior.hotkey = "Alt+Space".to_string();
+        let entries = setting_entries(&config_schema(), &config);
+
+        let hotkey = entry(&entries, "behavior.hotkey").unwrap();
+        assert_eq!((hotkey.label.as_str(), &hotkey.value), ("Hotkey", &Value::from("Alt+Space")));
+        assert_eq!(hotkey.field.kind, SchemaFieldKind::Other);
+        assert!(!hotkey.field.description.is_empty());
+
+        assert_eq!(entry(&entries, "appearance.fade_in").unwrap().field.kind, SchemaFieldKind::Boolean);
+        assert_eq!(entry(&entries, "appearance.visible_result_rows").unwrap().label, "Visible result rows");
+        for hidden in HIDDEN_SETTINGS {
+            assert!(entry(&entries, hidden).is_none(), "{} is listed", hidden);
+        }
+        // "enabled" is in more than one section
+        assert_eq!(entry(&entries, "sync.enabled").unwrap().label, "Sync enabled");
+        assert_eq!(entry(&entries, "plugins.enabled").unwrap().label, "Plugins enabled");
+
+        let paths: Vec<&str> = entries.iter().map(|entry| entry.field.path.as_str()).collect();
+        let fields: Vec<String> = schema_fields(&config_schema()).into_iter().map(|field| field.path).collect();
+        assert_eq!(paths.len(), fields.len() - HIDDEN_SETTINGS.len());
+        assert!(paths.iter().all(|path| fields.iter().any(|field| field == path)));
+    }
+
+    #[test]
+    fn results_show_the_value_and_offer_the_inline_change() {
+        let config = Config::default();
+        let entries = setting_entries(&config_schema(), &config);
+
+        let hotkey = setting_result(entry(&entries, "behavior.hotkey").unwrap(), 0.5);
+        assert_eq!(hotkey.title, "Setting: Hotkey (Ctrl+Space)");
+        assert!(hotkey.description.ends_with(" · behavior.hotkey"));
+        assert_eq!(hotkey.action, Action::OpenSettings("behavior.hotkey".to_string()));
+        assert!(hotkey.secondary_actions.is_empty());
+
+        let auto_hide = setting_result(entry(&entries, "behavior.auto_hide").unwrap(), 0.5);
+        assert_eq!(auto_hide.title, "Setting: Auto hide (On)");
+        assert_eq!(auto_hide.secondary_actions[0].title, "Toggle");
+        assert_eq!(
+            auto_hide.secondary_actions[0].action,
+            Action::SetSetting { path: "behavior.auto_hide".to_string(), value: Value::Bool(false) },
+        );
+    }
+
+    // Values cycle in schema order and wrap; an unlisted value starts over at the first
+    #[test]
+    fn enums_cycle_in_schema_order() {
+        let values: Vec<String> = ["TitleOnly", "TitleDescription", "TitlePath", "Detailed"].map(String::from).to_vec();
+        assert_eq!(next_enum_value(&values, &Value::from("TitleOnly")).as_deref(), Some("TitleDescription"));
+        assert_eq!(next_enum_value(&values, &Value::from("TitlePath")).as_deref(), Some("Detailed"));
+        assert_eq!(next_enum_value(&values, &Value::from("Detailed")).as_deref(), Some("TitleOnly"));
+        assert_eq!(next_enum_value(&values, &Value::from("Compact")).as_deref(), Some("TitleOnly"));
+        assert_eq!(next_enum_value(&[], &Value::from("Detailed")), None);
+
+        // Following the inline change from the default visits every layout and comes back
+        let mut config = Config::default();
+        let mut seen = Vec::new();
+        for _ in 0..values.len() {
+            let entries = setting_entries(&config_schema(), &config);
+            let (title, action) = inline_change(entry(&entries, "appearance.result_row_layout").unwrap()).unwrap();
+            let Action::SetSetting { path, value } = action else { panic!("{:?}", action) };
+            seen.push(title);
+            config = falcommand_config::schema::set_config_value(&config, &path, value).unwrap();
+        }
+        assert_eq!(seen, ["Switch to TitlePath", "Switch to Detailed", "Switch to TitleOnly", "Switch to TitleDescription"]);
+        assert_eq!(config.appearance.result_row_layout, ResultRowLayout::TitleDescription);
+    }
+
+    #[tokio::test]
+    async fn short_queries_find_nothing_and_matches_are_capped() {
+        let source = SettingsSource::new(Arc::new(RwLock::new(Config::default())));
+        assert!(source.search("ho").await.is_empty());
+
+        let results = source.search("hotkey").await;
+        assert_eq!(results[0].title, "Setting: Hotkey (Ctrl+Space)");
+        assert!(results.len() <= SettingsSource::LIMIT);
+        assert!(results.iter().all(|result| result.score <= SettingsSource::SCORE_SCALE));
+        assert!(!source.search("upload url").await.iter().any(|result| result.title.contains("Upload url")));
+    }
+}
@@ -0,0 +1,152 @@
+use std::path::Path;
+
+use falcommand_config::{Action, SearchResult};
+use falcommand_config::launch::display_path;
+
+pub const COPY_MARKDOWN_TITLE: &str = "Copy as markdown link";
+pub const COPY_JSON_TITLE: &str = "Copy as JSON";
+pub const SHARE_EMAIL_TITLE: &str = "Share via email";
+
+const SHARE_TITLES: [&str; 3] = [COPY_MARKDOWN_TITLE, COPY_JSON_TITLE, SHARE_EMAIL_TITLE];
+
+fn is_unreserved(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~')
+}
+
+// Percent-encodes UTF-8 bytes, leaving unreserved characters and `extra` untouched
+pub fn percent_encode(text: &str, extra: &[u8]) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        if is_unreserved(byte) || extra.contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+// Works on the path text so Windows paths convert the same way on every OS:
+// C:\a b\c -> file:///C:/a%20b/c, \\server\share\c -> file://server/share/c, /a b -> file:///a%20b
+pub fn file_uri(path: &Path) -> String {
+    let text = display_path(path);
+
+    if let Some(unc) = text.strip_prefix(r"\\") {
+        let unc = unc.replace('\\', "/");
+        let (host, rest) = unc.split_once('/').unwrap_or((&unc, ""));
+        return format!("file://{}/{}", percent_encode(host, &[]), percent_encode(rest, b"/"));
+    }
+
+    let bytes = text.as_bytes();
+    let has_drive = bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':';
+    if has_drive {
+        let rest = text[2..].replace('\\', "/");
+        return format!("file:///{}:{}", &text[..1], percent_encode(&rest, b"/"));
+    }
+
+    format!("file://{}", percent_encode(&text, b"/"))
+}
+
+pub fn markdown_link(name: &str, path: &Path) -> String {
+    let name = name.replace('[', "\\[").replace(']', "\\]");
+    format!("[{}]({})", name, file_uri(path))
+}
+
+// RFC 6068: every reserved character is escaped and line breaks are sent as CRLF
+pub fn mailto_url(subject: &str, body: &str) -> String {
+    let body = body.replace("\r\n", "\n").replace('\n', "\r\n");
+    format!(
+        "mailto:?subject={}&body={}",
+        percent_encode(subject, &[]),
+        percent_encode(&body, &[])
+    )
+}
+
+// The path or URL a result points at, used as the shared email body
+fn share_target(result: &SearchResult) -> String {
+    match result.action {
+        Action::OpenUrl(ref url) => url.clone(),
+        Action::OpenFile(ref path) => display_path(path),
+        _ => result.path.as_deref()
+            .map(display_path)
+            .unwrap_or_else(|| result.description.clone()),
+    }
+}
+
+fn without_share_actions(result: &SearchResult) -> SearchResult {
+    let mut result = result.clone();
+    result.secondary_actions.retain(|a| !SHARE_TITLES.contains(&a.title.as_str()));
+    result
+}
+
+// Attaches copy/share secondary actions to merged results; applying it twice changes nothing
+pub fn decorate_with_share_actions(result: SearchResult) -> SearchResult {
+    let mut result = without_share_actions(&result);
+
+    if let Some(path) = result.path.clone() {
+        let markdown = markdown_link(&result.title, &path);
+        let json = serde_json::to_string_pretty(&result).unwrap_or_default();
+        result = result
+            .with_secondary_action(COPY_MARKDOWN_TITLE, Action::CopyToClipboard(markdown))
+            .with_secondary_action(COPY_JSON_TITLE, Action::CopyToClipboard(json));
+    }
+
+    let mailto = mailto_url(&result.title, &share_target(&result));
+    result.with_secondary_action(SHARE_EMAIL_TITLE, Action::OpenUrl(mailto))
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn file_uris_for_every_path_shape() {
+        assert_eq!(file_uri(Path::new("/home/me/My Notes/日記.md")), "file:///home/me/My%20Notes/%E6%97%A5%E8%A8%98.md");
+        assert_eq!(file_uri(Path::new(r"C:\Users\me\R&D (1)\a#b.txt")), "file:///C:/Users/me/R%26D%20%281%29/a%23b.txt");
+        assert_eq!(file_uri(Path::new(r"\\?\C:\Tools\x.exe")), "file:///C:/Tools/x.exe");
+        assert_eq!(file_uri(Path::new(r"\\server\share\a b.txt")), "file://server/share/a%20b.txt");
+        assert_eq!(file_uri(Path::new(r"\\?\UNC\server\share\x")), "file://server/share/x");
+    }
+
+    #[test]
+    fn markdown_links_escape_brackets() {
+        assert_eq!(markdown_link("notes [draft]", Path::new("/tmp/n.md")), r"[notes \[draft\]](file:///tmp/n.md)");
+    }
+
+    #[test]
+    fn mailto_escapes_reserved_characters_and_uses_crlf() {
+        assert_eq!(
+            mailto_url("Q&A: 50% = ?", "line 1\nline 2\r\nhttps://example.com/?a=1&b=2"),
+            "mailto:?subject=Q%26A%3A%2050%25%20%3D%20%3F\
+             &body=line%201%0D%0Aline%202%0D%0Ahttps%3A%2F%2Fexample.com%2F%3Fa%3D1%26b%3D2",
+        );
+        assert_eq!(mailto_url("", ""), "mailto:?subject=&body=");
+    }
+
+    #[test]
+    fn decorating_twice_changes_nothing() {
+        let result = SearchResult::new("report", "File")
+            .with_action(Action::OpenFile(PathBuf::from("/home/me/report.pdf")))
+            .with_path(PathBuf::from("/home/me/report.pdf"))
+            .with_secondary_action("Open containing folder", Action::OpenFile(PathBuf::from("/home/me")));
+
+        let once = decorate_with_share_actions(result);
+        let twice = decorate_with_share_actions(once.clone());
+        assert_eq!(twice, once);
+
+        let titles: Vec<&str> = once.secondary_actions.iter().map(|action| action.title.as_str()).collect();
+        assert_eq!(titles, ["Open containing folder", COPY_MARKDOWN_TITLE, COPY_JSON_TITLE, SHARE_EMAIL_TITLE]);
+        // The JSON copy describes the result without share actions of its own
+        let Action::CopyToClipboard(ref json) = once.secondary_actions[2].action else { panic!("not a copy") };
+        assert!(!json.contains(SHARE_EMAIL_TITLE));
+    }
+
+    #[test]
+    fn results_without_a_path_can_only_be_mailed() {
+        let result = SearchResult::new("Docs", "").with_action(Action::OpenUrl("https://docs.rs".to_string()));
+        let decorated = decorate_with_share_actions(result);
+
+        assert_eq!(decorated.secondary_actions.len(), 1);
+        assert_eq!(decorated.secondary_actions[0].action, Action::OpenUrl("mailto:?subject=Docs&body=https%3A%2F%2Fdocs.rs".to_string()));
+    }
+}
@@ -0,0 +1,453 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+
+use crate::task_registry::TaskCounts;
+
+// Queries a soak run cycles through when no corpus is given: app names, files, plugin triggers
+// and typos, so every source sees traffic
+pub const DEFAULT_QUERY_CORPUS: [&str; 16] = [
+    "term", "firefox", "settings", "calc", "notes", "readme", "report.pdf", "= 12*34",
+    "timer 5m", "dev rust vec", "wifi", "downlods", "code", "~/Documents", "ip", "mail",
+];
+
+#[derive(Debug, thiserror::Error)]
+pub enum SoakError {
+    #[error("Failed to read the query corpus {path}: {reason}")]
+    Corpus { path: PathBuf, reason: String },
+
+    #[error("The query corpus {0} has no queries")]
+    EmptyCorpus(PathBuf),
+
+    #[error("Failed to write the soak report {path}: {reason}")]
+    Report { path: PathBuf, reason: String },
+}
+
+// Limits a soak run must stay within to pass
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SoakThresholds {
+    // Slope of resident memory over the run, after the first sample
+    pub max_rss_growth_mb_per_hour: f64,
+    // Failed share of attempts, per subsystem
+    pub max_error_rate: f64,
+}
+
+impl Default for SoakThresholds {
+    fn default() -> Self {
+        Self { max_rss_growth_mb_per_hour: 20.0, max_error_rate: 0.01 }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SoakOptions {
+    pub duration: Duration,
+    // Synthetic searches per second
+    pub qps: f64,
+    pub thresholds: SoakThresholds,
+    // JSONL, one sample per line and the summary last
+    pub report: PathBuf,
+}
+
+impl SoakOptions {
+    pub const SAMPLE_INTERVAL: Duration = Duration::from_secs(60);
+
+    // A search slower than this counts as failed
+    pub const SEARCH_TIMEOUT: Duration = Duration::from_secs(5);
+
+    pub fn search_interval(&self) -> Duration {
+        Duration::from_secs_f64(1.0 / self.qps.max(0.001))
+    }
+}
+
+// Attempts and failures of one subsystem since the run started
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubsystemStats {
+    pub attempts: u64,
+    pub failures: u64,
+}
+
+impl SubsystemStats {
+    pub fn error_rate(&self) -> f64 {
+        if self.attempts == 0 {
+            return 0.0;
+        }
+        self.failures as f64 / self.attempts as f64
+    }
+}
+
+// Synthetic searches since start, and the latencies of those since the last sample
+#[derive(Debug, Default)]
+pub struct SoakCounters {
+    searches: AtomicU64,
+    failed_searches: AtomicU64,
+    latencies: Mutex<Vec<Duration>>,
+}
+
+impl SoakCounters {
+    pub fn record_search(&self, latency: Duration) {
+        self.searches.fetch_add(1, Ordering::Relaxed);
+        self.latencies.lock().unwrap().push(latency);
+    }
+
+    pub fn record_failed_search(&self) {
+        self.searches.fetch_add(1, Ordering::Relaxed);
+        self.failed_searches.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn searches(&self) -> SubsystemStats {
+        SubsystemStats {
+            attempts: self.searches.load(Ordering::Relaxed),
+            failures: self.failed_searches.load(Ordering::Relaxed),
+        }
+    }
+
+    // Sorted; the next interval starts empty
+    pub fn take_latencies(&self) -> Vec<Duration> {
+        let mut latencies = std::mem::take(&mut *self.latencies.lock().unwrap());
+        latencies.sort();
+        latencies
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SoakSample {
+    pub elapsed_secs: u64,
+    // None where the platform doesn't report it
+    pub rss_bytes: Option<u64>,
+    pub tasks: TaskCounts,
+    // Over the searches since the previous sample
+    pub searches: usize,
+    pub p50_ms: Option<f64>,
+    pub p95_ms: Option<f64>,
+    pub p99_ms: Option<f64>,
+    // Cumulative, by subsystem name
+    pub subsystems: BTreeMap<String, SubsystemStats>,
+}
+
+impl SoakSample {
+    pub fn new(
+        elapsed: Duration,
+        rss_bytes: Option<u64>,
+        tasks: TaskCounts,
+        latencies: &[Duration],
+        subsystems: BTreeMap<String, SubsystemStats>,
+    ) -> Self {
+        let ms = |percentile| nearest_rank(latencies, percentile).map(|latency| latency.as_secs_f64() * 1000.0);
+        Self {
+            elapsed_secs: elapsed.as_secs(),
+            rss_bytes,
+            tasks,
+            searches: latencies.len(),
+            p50_ms: ms(50.0),
+            p95_ms: ms(95.0),
+            p99_ms: ms(99.0),
+            subsystems,
+        }
+    }
+}
+
+// Same ranking as LatencyMetrics::percentile, over every sample of the interval
+fn nearest_rank(sorted: &[Duration], percentile: f64) -> Option<Duration> {
+    let rank = ((percentile / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted.get(rank.saturating_sub(1)).copied()
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SoakViolation {
+    MemoryGrowth { mb_per_hour: f64, limit: f64 },
+    ErrorRate { subsystem: String, rate: f64, limit: f64 },
+}
+
+impl std::fmt::Display for SoakViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SoakViolation::MemoryGrowth { mb_per_hour, limit } => {
+                write!(f, "memory grew {:.1} MB/h (limit {:.1} MB/h)", mb_per_hour, limit)
+            }
+            SoakViolation::ErrorRate { subsystem, rate, limit } => {
+                write!(f, "{} failed {:.2}% of attempts (limit {:.2}%)", subsystem, rate * 100.0, limit * 100.0)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SoakSummary {
+    pub duration_secs: u64,
+    pub samples: usize,
+    // None with fewer than three memory readings
+    pub rss_growth_mb_per_hour: Option<f64>,
+    pub subsystems: BTreeMap<String, SubsystemStats>,
+    pub violations: Vec<SoakViolation>,
+    pub passed: bool,
+}
+
+impl SoakSummary {
+    pub fn evaluate(samples: &[SoakSample], elapsed: Duration, thresholds: &SoakThresholds) -> Self {
+        let rss_growth_mb_per_hour = rss_growth_mb_per_hour(samples);
+        let subsystems = samples.last().map(|sample| sample.subsystems.clone()).unwrap_or_default();
+
+        let mut violations = Vec::new();
+        if let Some(mb_per_hour) = rss_growth_mb_per_hour.filter(|growth| *growth > thresholds.max_rss_growth_mb_per_hour) {
+            violations.push(SoakViolation::MemoryGrowth { mb_per_hour, limit: thresholds.max_rss_growth_mb_per_hour });
+        }
+        for (subsystem, stats) in &subsystems {
+            if stats.error_rate() > thresholds.max_error_rate {
+                violations.push(SoakViolation::ErrorRate {
+                    subsystem: subsystem.clone(),
+                    rate: stats.error_rate(),
+                    limit: thresholds.max_error_rate,
+                });
+            }
+        }
+
+        Self {
+            duration_secs: elapsed.as_secs(),
+            samples: samples.len(),
+            rss_growth_mb_per_hour,
+            subsystems,
+            passed: violations.is_empty(),
+            violations,
+        }
+    }
+}
+
+// Least-squares slope of resident memory in MB per hour. The first sample is skipped: caches and
+// the index fill during it, which is warm-up rather than a leak
+pub fn rss_growth_mb_per_hour(samples: &[SoakSample]) -> Option<f64> {
+    let points: Vec<(f64, f64)> = samples.iter()
+        .skip(1)
+        .filter_map(|sample| {
+            let rss = sample.rss_bytes?;
+            Some((sample.elapsed_secs as f64 / 3600.0, rss as f64 / (1024.0 * 1024.0)))
+        })
+        .collect();
+    if points.len() < 3 {
+        return None;
+    }
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let covariance: f64 = points.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+    let variance: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    if variance == 0.0 {
+        return None;
+    }
+    Some(covariance / variance)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SoakRecord {
+    Sample(SoakSample),
+    Summary(SoakSummary),
+}
+
+// Appends one JSON line per record, flushed each time so a killed run still leaves its samples
+pub struct SoakReport {
+    path: PathBuf,
+    file: std::fs::File,
+}
+
+impl SoakReport {
+    // Replaces an earlier report at `path`
+    pub fn create(path: &Path) -> Result<Self, SoakError> {
+        let error = |e: std::io::Error| SoakError::Report { path: path.to_path_buf(), reason: e.to_string() };
+        if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent).map_err(error)?;
+        }
+        let file = std::fs::File::create(path).map_err(error)?;
+        Ok(Self { path: path.to_path_buf(), file })
+    }
+
+    pub fn write(&mut self, record: &SoakRecord) -> Result<(), SoakError> {
+        let error = |reason: String| SoakError::Report { path: self.path.clone(), reason };
+        let line = serde_json::to_string(record).map_err(|e| error(e.to_string()))?;
+        writeln!(self.file, "{}", line).and_then(|_| self.file.flush()).map_err(|e| error(e.to_string()))
+    }
+}
+
+// One query per line; blank lines and lines starting with '#' are skipped
+pub fn load_query_corpus(path: &Path) -> Result<Vec<String>, SoakError> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| SoakError::Corpus { path: path.to_path_buf(), reason: e.to_string() })?;
+    let queries: Vec<String> = text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect();
+    if queries.is_empty() {
+        return Err(SoakError::EmptyCorpus(path.to_path_buf()));
+    }
+    Ok(queries)
+}
+
+// "2h", "90m", "1h30m" or "45s"; a bare number is minutes, as for timers
+pub fn parse_soak_duration(text: &str) -> Option<Duration> {
+    let text = text.trim().to_lowercase();
+    if let Ok(minutes) = text.parse::<u64>() {
+        return (minutes > 0).then(|| Duration::from_secs(minutes * 60));
+    }
+    let mut total = 0;
+    let mut digits = String::new();
+    for c in text.chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+        let unit = match c {
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            _ => return None,
+        };
+        total += digits.parse::<u64>().ok()? * unit;
+        digits.clear();
+    }
+    (digits.is_empty() && total > 0).then(|| Duration::from_secs(total))
+}
+
+// Resident set size of this process; Linux only
+pub fn process_rss_bytes() -> Option<u64> {
+    #[cfg(target_os = "linux")]
+    {
+        let status = std::fs::read_to_string("/proc/self/status").ok()?;
+        let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+        let kb: u64 = line.trim_start_matches("VmRSS:").trim().trim_end_matches("kB").trim().parse().ok()?;
+        Some(kb * 1024)
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MB: u64 = 1024 * 1024;
+
+    fn sample(minutes: u64, rss_mb: Option<u64>, subsystems: &[(&str, u64, u64)]) -> SoakSample {
+        let subsystems = subsystems.iter()
+            .map(|(name, attempts, failures)| (name.to_string(), SubsystemStats { attempts: *attempts, failures: *failures }))
+            .collect();
+        SoakSample::new(Duration::from_secs(minutes * 60), rss_mb.map(|mb| mb * MB), TaskCounts::default(), &[], subsystems)
+    }
+
+    #[test]
+    fn latency_percentiles_use_the_nearest_rank() {
+        let latencies: Vec<Duration> = (1..=100).map(Duration::from_millis).collect();
+        let sample = SoakSample::new(Duration::from_secs(60), None, TaskCounts::default(), &latencies, BTreeMap::new());
+        assert_eq!((sample.searches, sample.p50_ms, sample.p95_ms, sample.p99_ms), (100, Some(50.0), Some(95.0), Some(99.0)));
+
+        let idle = SoakSample::new(Duration::from_secs(60), None, TaskCounts::default(), &[], BTreeMap::new());
+        assert_eq!((idle.searches, idle.p50_ms), (0, None));
+    }
+
+    #[test]
+    fn counters_hand_over_each_interval_sorted() {
+        let counters = SoakCounters::default();
+        counters.record_search(Duration::from_millis(30));
+        counters.record_search(Duration::from_millis(10));
+        counters.record_failed_search();
+
+        assert_eq!(counters.take_latencies(), [Duration::from_millis(10), Duration::from_millis(30)]);
+        assert!(counters.take_latencies().is_empty());
+        assert_eq!(counters.searches(), SubsystemStats { attempts: 3, failures: 1 });
+    }
+
+    // 10 MB every six minutes is 100 MB/h; the warm-up sample doesn't count
+    #[test]
+    fn memory_growth_is_the_slope_after_warm_up() {
+        let samples: Vec<SoakSample> = [(0, 10), (6, 400), (12, 410), (18, 420)].iter()
+            .map(|(minutes, mb)| sample(*minutes, Some(*mb), &[]))
+            .collect();
+        assert!((rss_growth_mb_per_hour(&samples).unwrap() - 100.0).abs() < 1e-9);
+
+        // Too few readings, or none at all on this platform
+        assert_eq!(rss_growth_mb_per_hour(&samples[..3]), None);
+        let unreported: Vec<SoakSample> = (0..5).map(|minutes| sample(minutes, None, &[])).collect();
+        assert_eq!(rss_growth_mb_per_hour(&unreported), None);
+    }
+
+    #[test]
+    fn thresholds_decide_the_outcome() {
+        let thresholds = SoakThresholds::default();
+        let flat: Vec<SoakSample> = (0..4).map(|minutes| sample(minutes, Some(300), &[("search", 100, 1)])).collect();
+        let summary = SoakSummary::evaluate(&flat, Duration::from_secs(240), &thresholds);
+        assert!(summary.passed && summary.violations.is_empty());
+        assert_eq!((summary.samples, summary.rss_growth_mb_per_hour), (4, Some(0.0)));
+
+        let failing: Vec<SoakSample> = (0..4)
+            .map(|minutes| sample(minutes * 6, Some(300 + minutes * 10), &[("search", 100, 0), ("sync", 50, 2)]))
+            .collect();
+        let summary = SoakSummary::evaluate(&failing, Duration::from_secs(1080), &thresholds);
+        assert!(!summary.passed);
+        let descriptions: Vec<String> = summary.violations.iter().map(ToString::to_string).collect();
+        assert_eq!(descriptions, ["memory grew 100.0 MB/h (limit 20.0 MB/h)", "sync failed 4.00% of attempts (limit 1.00%)"]);
+
+        // Stricter or looser limits move the line
+        let lenient = SoakThresholds { max_rss_growth_mb_per_hour: 150.0, max_error_rate: 0.05 };
+        assert!(SoakSummary::evaluate(&failing, Duration::from_secs(1080), &lenient).passed);
+    }
+
+    // Each line is a tagged record; the field names are what report readers rely on
+    #[test]
+    fn report_lines_follow_the_schema() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("reports").join("soak.jsonl");
+        let samples = vec![sample(1, Some(300), &[("search", 10, 0)]), sample(2, None, &[("search", 20, 1)])];
+        let summary = SoakSummary::evaluate(&samples, Duration::from_secs(120), &SoakThresholds::default());
+
+        let mut report = SoakReport::create(&path).unwrap();
+        for sample in &samples {
+            report.write(&SoakRecord::Sample(sample.clone())).unwrap();
+        }
+        report.write(&SoakRecord::Summary(summary.clone())).unwrap();
+
+        let lines: Vec<serde_json::Value> = std::fs::read_to_string(&path).unwrap().lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0]["type"], "sample");
+        assert_eq!(lines[0]["elapsed_secs"], 60);
+        assert_eq!(lines[0]["rss_bytes"], 300 * MB);
+        assert_eq!(lines[0]["tasks"], serde_json::json!({ "alive": 0, "finished": 0, "alive_by_name": {} }));
+        assert_eq!(lines[0]["subsystems"]["search"], serde_json::json!({ "attempts": 10, "failures": 0 }));
+        for key in ["searches", "p50_ms", "p95_ms", "p99_ms"] {
+            assert!(lines[0].get(key).is_some(), "{}", key);
+        }
+        assert_eq!(lines[1]["rss_bytes"], serde_json::Value::Null);
+
+        assert_eq!(lines[2]["type"], "summary");
+        assert_eq!(lines[2]["passed"], false);
+        assert_eq!(lines[2]["violations"][0]["kind"], "error_rate");
+        assert_eq!(lines[2]["violations"][0]["subsystem"], "search");
+        assert_eq!(serde_json::from_value::<SoakRecord>(lines[2].clone()).unwrap(), SoakRecord::Summary(summary));
+    }
+
+    #[test]
+    fn durations_and_corpora() {
+        assert_eq!(parse_soak_duration("2h"), Some(Duration::from_secs(7200)));
+        assert_eq!(parse_soak_duration("1h30m"), Some(Duration::from_secs(5400)));
+        assert_eq!(parse_soak_duration("45s"), Some(Duration::from_secs(45)));
+        assert_eq!(parse_soak_duration("90"), Some(Duration::from_secs(5400)));
+        for invalid in ["0", "2d", "h", "5m3", ""] {
+            assert_eq!(parse_soak_duration(invalid), None, "{}", invalid);
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let corpus = dir.path().join("queries.txt");
+        std::fs::write(&corpus, "# apps\nfirefox\n\n  term \n").unwrap();
+        assert_eq!(load_query_corpus(&corpus).unwrap(), ["firefox", "term"]);
+        std::fs::write(&corpus, "# nothing here\n\n").unwrap();
+        assert!(matches!(load_query_corpus(&corpus), Err(SoakError::EmptyCorpus(_))));
+        assert!(matches!(load_query_corpus(&dir.path().join("missing.txt")), Err(SoakError::Corpus { .. })));
+    }
+}
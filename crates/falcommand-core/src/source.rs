@@ -0,0 +1,18 @@
+use async_trait::async_trait;
+
+use falcommand_config::{SearchResult, SyntaxHint};
+
+// Additional result providers merged into SearchEngine results alongside the indexes
+#[async_trait]
+pub trait SearchSource: Send + Sync {
+    fn name(&self) -> &str;
+    async fn search(&self, query: &str) -> Vec<SearchResult>;
+
+    // Query syntax this source responds to, listed by the help overlay
+    fn syntax_hints(&self) -> Vec<SyntaxHint> {
+        Vec::new()
+    }
+
+    // Called after any result, from this source or another, was launched
+    async fn launched(&self, _result: &SearchResult) {}
+}
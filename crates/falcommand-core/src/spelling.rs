@@ -0,0 +1,238 @@
+use std::time::Instant;
+
+use falcommand_config::{Action, Category, SearchResult};
+use falcommand_platform::AppInfo;
+
+// Edit distance with adjacent transpositions counted as one edit (optimal string alignment).
+// None once the distance is certain to exceed `max`
+pub fn damerau_levenshtein(a: &str, b: &str, max: usize) -> Option<usize> {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.len().abs_diff(b.len()) > max {
+        return None;
+    }
+
+    // Three rows are enough: a transposition looks two rows back
+    let mut before_previous: Vec<usize> = vec![0; b.len() + 1];
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current: Vec<usize> = vec![0; b.len() + 1];
+    for i in 1..=a.len() {
+        current[0] = i;
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            current[j] = (previous[j] + 1)
+                .min(current[j - 1] + 1)
+                .min(previous[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                current[j] = current[j].min(before_previous[j - 2] + 1);
+            }
+        }
+        // Later rows build on this one, or on the previous one plus a transposition
+        let row_min = current.iter().min().copied().unwrap_or(0);
+        let previous_min = previous.iter().min().copied().unwrap_or(0);
+        if row_min > max && previous_min >= max {
+            return None;
+        }
+        std::mem::swap(&mut before_previous, &mut previous);
+        std::mem::swap(&mut previous, &mut current);
+    }
+    Some(previous[b.len()]).filter(|&distance| distance <= max)
+}
+
+// A known name a misspelled query may have meant; `app` is set for indexed applications
+#[derive(Debug, Clone)]
+pub struct SpellCandidate {
+    pub text: String,
+    pub app: Option<AppInfo>,
+}
+
+impl SpellCandidate {
+    pub fn app(app: AppInfo) -> Self {
+        Self {
+            text: app.name.clone(),
+            app: Some(app),
+        }
+    }
+
+    pub fn text(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            app: None,
+        }
+    }
+
+    // Multi-word names also match on each word, so "fierfox" finds "Mozilla Firefox"
+    fn distance(&self, query: &str, max: usize) -> Option<usize> {
+        let text = self.text.to_lowercase();
+        let whole = damerau_levenshtein(query, &text, max);
+        let words = text.split_whitespace()
+            .filter(|word| *word != text)
+            .filter_map(|word| damerau_levenshtein(query, word, max));
+        whole.into_iter().chain(words).min()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Correction {
+    pub candidate: SpellCandidate,
+    pub distance: usize,
+}
+
+pub const MAX_EDIT_DISTANCE: usize = 2;
+// Shorter queries are within two edits of far too many names
+const MIN_QUERY_LEN: usize = 4;
+
+// Queries made of words only; anything with operators or paths is left alone
+pub fn is_correctable(query: &str) -> bool {
+    let query = query.trim();
+    query.chars().count() >= MIN_QUERY_LEN
+        && query.chars().any(char::is_alphabetic)
+        && query.chars().all(|c| c.is_alphanumeric() || c == ' ' || c == '-' || c == '_' || c == '.')
+}
+
+// Closest candidates first, apps before other names at the same distance. Candidates that would
+// be an exact match are skipped: the query didn't find them, so it wasn't a typo. Stops at `deadline`
+pub fn suggest_corrections(query: &str, candidates: &[SpellCandidate], limit: usize, deadline: Instant) -> Vec<Correction> {
+    let query = query.trim().to_lowercase();
+    let mut seen = std::collections::HashSet::new();
+    let mut corrections = Vec::new();
+    for candidate in candidates {
+        if Instant::now() > deadline {
+            break;
+        }
+        if !seen.insert(candidate.text.to_lowercase()) {
+            continue;
+        }
+        if let Some(distance) = candidate.distance(&query, MAX_EDIT_DISTANCE).filter(|&d| d > 0) {
+            corrections.push(Correction { candidate: candidate.clone(), distance });
+        }
+    }
+
+    corrections.sort_by(|a, b| {
+        a.distance.cmp(&b.distance)
+            .then_with(|| b.candidate.app.is_some().cmp(&a.candidate.app.is_some()))
+            .then_with(|| a.candidate.text.cmp(&b.candidate.text))
+    });
+    corrections.truncate(limit);
+    corrections
+}
+
+// The best correction is an app and nothing else is as close, so it can be launched directly
+pub fn dominant_app(corrections: &[Correction]) -> Option<&AppInfo> {
+    let best = corrections.first()?;
+    let tied = corrections.iter().skip(1).any(|other| other.distance == best.distance);
+    best.candidate.app.as_ref().filter(|_| !tied)
+}
+
+pub fn correction_results(corrections: &[Correction]) -> Vec<SearchResult> {
+    let direct = dominant_app(corrections).is_some();
+    corrections.iter()
+        .enumerate()
+        .map(|(index, correction)| {
+            let text = &correction.candidate.text;
+            let title = format!("Did you mean '{}'?", text);
+            let result = match correction.candidate.app {
+                Some(ref app) if direct && index == 0 => {
+                    let mut result = app.to_search_result();
+                    result.title = title;
+                    result
+                }
+                _ => SearchResult::new(title, format!("Search for '{}'", text))
+                    .with_action(Action::SetQuery(text.clone()))
+                    .with_category(Category::SystemCommand),
+            };
+            result.with_score(0.5 - 0.1 * correction.distance as f64)
+        })
+        .collect()
+}
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::time::Duration;
+    use super::*;
+
+    fn app(name: &str) -> SpellCandidate {
+        SpellCandidate::app(AppInfo::new(name, PathBuf::from(format!("/apps/{}", name.to_lowercase()))))
+    }
+
+    fn later() -> Instant {
+        Instant::now() + Duration::from_secs(5)
+    }
+
+    fn suggested(query: &str, candidates: &[SpellCandidate]) -> Vec<(String, usize)> {
+        suggest_corrections(query, candidates, 3, later()).into_iter()
+            .map(|correction| (correction.candidate.text, correction.distance))
+            .collect()
+    }
+
+    #[test]
+    fn each_kind_of_typo_is_one_edit() {
+        // Transposition, insertion, deletion and substitution
+        assert_eq!(damerau_levenshtein("fierfox", "firefox", 2), Some(1));
+        assert_eq!(damerau_levenshtein("firrefox", "firefox", 2), Some(1));
+        assert_eq!(damerau_levenshtein("firfox", "firefox", 2), Some(1));
+        assert_eq!(damerau_levenshtein("firefix", "firefox", 2), Some(1));
+        assert_eq!(damerau_levenshtein("fierfx", "firefox", 2), Some(2));
+        assert_eq!(damerau_levenshtein("firefox", "firefox", 2), Some(0));
+
+        // Beyond the limit there is no distance at all
+        assert_eq!(damerau_levenshtein("chrome", "firefox", 2), None);
+        assert_eq!(damerau_levenshtein("fx", "firefox", 2), None);
+        assert_eq!(damerau_levenshtein("ab", "ba", 0), None);
+    }
+
+    #[test]
+    fn closest_names_come_first_and_apps_win_ties() {
+        let candidates = [app("Firefox"), SpellCandidate::text("Firebox"), app("Mozilla Thunderbird"), app("Finder")];
+
+        // "fierfox" is one transposition from Firefox and two edits from Firebox
+        assert_eq!(suggested("fierfox", &candidates), [("Firefox".to_string(), 1), ("Firebox".to_string(), 2)]);
+        // Words of longer names match too
+        assert_eq!(suggested("thunderbrid", &candidates), [("Mozilla Thunderbird".to_string(), 1)]);
+        assert_eq!(suggested("firebax", &[SpellCandidate::text("Firebox"), app("Firefox")])[0].0, "Firebox");
+        // An exact name wasn't a typo, and duplicates are suggested once
+        assert!(suggested("finder", &[app("Finder"), app("finder")]).is_empty());
+        assert_eq!(suggested("findr", &[app("Finder"), app("finder")]).len(), 1);
+    }
+
+    #[test]
+    fn only_word_queries_are_corrected() {
+        assert!(is_correctable("fierfox"));
+        assert!(is_correctable("visual studo"));
+        assert!(!is_correctable("fox"));
+        assert!(!is_correctable("1234"));
+        assert!(!is_correctable("~/docs"));
+        assert!(!is_correctable("2+2*3"));
+        assert!(!is_correctable("ext:pdf"));
+    }
+
+    #[test]
+    fn a_single_closest_app_is_launched_directly() {
+        let corrections = suggest_corrections("fierfox", &[app("Firefox"), SpellCandidate::text("Firebox")], 3, later());
+        assert_eq!(dominant_app(&corrections).map(|app| app.name.as_str()), Some("Firefox"));
+
+        let results = correction_results(&corrections);
+        assert_eq!(results[0].title, "Did you mean 'Firefox'?");
+        assert!(matches!(results[0].action, Action::ExecuteApplication { .. }));
+        assert_eq!(results[1].action, Action::SetQuery("Firebox".to_string()));
+        assert!(results[0].score > results[1].score);
+    }
+
+    #[test]
+    fn ties_and_non_apps_only_refill_the_query() {
+        // Two apps equally close: neither is launched
+        let tied = suggest_corrections("fonder", &[app("Finder"), app("Folder")], 3, later());
+        assert!(dominant_app(&tied).is_none());
+        assert!(correction_results(&tied).iter().all(|result| matches!(result.action, Action::SetQuery(_))));
+
+        // The closest is a launched item, not an app
+        let text_first = suggest_corrections("fierfox", &[SpellCandidate::text("Firefox"), app("Firebox")], 3, later());
+        assert!(dominant_app(&text_first).is_none());
+    }
+
+    #[test]
+    fn nothing_is_suggested_past_the_deadline() {
+        let past = Instant::now() - Duration::from_millis(1);
+        assert!(suggest_corrections("fierfox", &[app("Firefox")], 3, past).is_empty());
+    }
+}
@@ -0,0 +1,397 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, FixedOffset, Timelike, Utc};
+use log::warn;
+use serde::Serialize;
+
+use falcommand_config::{Action, Category, SearchResult, SyntaxHint};
+use falcommand_config::i18n::{current_language, tr, Language};
+use crate::format::format_count;
+use crate::history::{HistoryEntry, SearchHistory};
+use crate::search::SearchEngine;
+use crate::source::SearchSource;
+use crate::usage::{category_label, UsageError, UsageRecord, UsageStore};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+pub enum StatsWindow {
+    Week,
+    #[default]
+    Month,
+    Quarter,
+}
+
+impl StatsWindow {
+    pub const ALL: [StatsWindow; 3] = [StatsWindow::Week, StatsWindow::Month, StatsWindow::Quarter];
+
+    pub fn days(&self) -> i64 {
+        match self {
+            StatsWindow::Week => 7,
+            StatsWindow::Month => 30,
+            StatsWindow::Quarter => 90,
+        }
+    }
+
+    // "7", "30d" or "90 days"
+    pub fn parse(text: &str) -> Option<Self> {
+        let digits: String = text.trim().chars().take_while(|c| c.is_ascii_digit()).collect();
+        let days: i64 = digits.parse().ok()?;
+        Self::ALL.into_iter().find(|window| window.days() == days)
+    }
+
+    pub fn contains(&self, timestamp: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+        timestamp <= now && now - timestamp < Duration::days(self.days())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UsageStats {
+    pub window: StatsWindow,
+    pub total_launches: usize,
+    // (title, launches), most launched first
+    pub top_items: Vec<(String, usize)>,
+    // Launches per local hour of day
+    pub hourly: [usize; 24],
+    // (query, searches), most searched first
+    pub top_queries: Vec<(String, usize)>,
+}
+
+impl UsageStats {
+    pub fn busiest_hour(&self) -> Option<u32> {
+        let (hour, count) = self.hourly.iter().enumerate().max_by_key(|(hour, count)| (**count, std::cmp::Reverse(*hour)))?;
+        (*count > 0).then_some(hour as u32)
+    }
+}
+
+// Counts keyed by `key`, ties broken alphabetically so the order is stable
+fn ranked<T, K, L>(items: &[T], key: K, label: L, limit: usize) -> Vec<(String, usize)>
+where
+    K: Fn(&T) -> String,
+    L: Fn(&T) -> String,
+{
+    let mut counts: HashMap<String, (String, usize)> = HashMap::new();
+    for item in items {
+        counts.entry(key(item)).or_insert_with(|| (label(item), 0)).1 += 1;
+    }
+
+    let mut ranked: Vec<(String, usize)> = counts.into_values().collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    ranked.truncate(limit);
+    ranked
+}
+
+pub fn compute_usage_stats(
+    records: &[UsageRecord],
+    history: &[HistoryEntry],
+    window: StatsWindow,
+    now: DateTime<Utc>,
+    utc_offset: FixedOffset,
+    limit: usize,
+) -> UsageStats {
+    let records: Vec<UsageRecord> = records.iter()
+        .filter(|record| window.contains(record.timestamp, now))
+        .cloned()
+        .collect();
+    let history: Vec<HistoryEntry> = history.iter()
+        .filter(|entry| window.contains(entry.timestamp, now))
+        .cloned()
+        .collect();
+
+    let mut hourly = [0usize; 24];
+    for record in records.iter() {
+        hourly[record.timestamp.with_timezone(&utc_offset).hour() as usize] += 1;
+    }
+
+    UsageStats {
+        window,
+        total_launches: records.len(),
+        top_items: ranked(&records, UsageRecord::item_key, |record| record.title.clone(), limit),
+        hourly,
+        top_queries: ranked(&history, |entry| entry.query.to_lowercase(), |entry| entry.query.clone(), limit),
+    }
+}
+
+// One bar character per hour, scaled to the busiest hour
+pub fn format_histogram(hourly: &[usize; 24]) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let max = hourly.iter().copied().max().unwrap_or(0);
+    if max == 0 {
+        return BARS[0].to_string().repeat(24);
+    }
+
+    hourly.iter()
+        .map(|count| BARS[(count * (BARS.len() - 1)).div_ceil(max)])
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+impl ExportFormat {
+    pub fn parse(text: &str) -> Option<Self> {
+        match text.trim().to_lowercase().as_str() {
+            "csv" => Some(ExportFormat::Csv),
+            "json" => Some(ExportFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+// RFC 4180: fields containing a separator, quote or line break are quoted and quotes doubled
+pub fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+pub fn export_usage_records(records: &[UsageRecord], format: ExportFormat) -> std::result::Result<String, UsageError> {
+    match format {
+        ExportFormat::Json => Ok(serde_json::to_string_pretty(records)?),
+        ExportFormat::Csv => {
+            let mut csv = String::from("timestamp,title,path,category\r\n");
+            for record in records {
+                let path = record.path.as_ref()
+                    .map(|path| path.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                csv.push_str(&format!(
+                    "{},{},{},{}\r\n",
+                    record.timestamp.to_rfc3339(),
+                    csv_field(&record.title),
+                    csv_field(&path),
+                    csv_field(&category_label(&record.category)),
+                ));
+            }
+            Ok(csv)
+        }
+    }
+}
+
+// Clears both stores or neither: usage records are restored if the history can't be cleared
+pub async fn clear_usage_data(usage: &UsageStore, history: &SearchHistory) -> std::result::Result<(), UsageError> {
+    let backup = usage.snapshot().await;
+    usage.clear().await?;
+
+    if let Err(e) = history.clear().await {
+        warn!("Failed to clear search history, restoring usage data: {}", e);
+        usage.restore(backup).await?;
+        return Err(UsageError::Other(e.to_string()));
+    }
+    Ok(())
+}
+
+// "stats [7|30|90]" meta-command
+pub struct StatsSource {
+    search_engine: Arc<SearchEngine>,
+}
+
+impl StatsSource {
+    pub const KEYWORD: &'static str = "stats";
+    const TOP_LIMIT: usize = 5;
+
+    pub fn new(search_engine: Arc<SearchEngine>) -> Self {
+        Self { search_engine }
+    }
+
+    fn stat_result(title: String, description: String, score: f64) -> SearchResult {
+        SearchResult::new(title.clone(), description.clone())
+            .with_action(Action::CopyToClipboard(format!("{}: {}", title, description)))
+            .with_category(Category::SystemCommand)
+            .with_score(score)
+    }
+
+    fn results(stats: &UsageStats, language: Language) -> Vec<SearchResult> {
+        let mut results = Vec::new();
+
+        let busiest = stats.busiest_hour()
+            .map(|hour| format!("Busiest hour {:02}:00–{:02}:00", hour, (hour + 1) % 24))
+            .unwrap_or_else(|| "No launches yet".to_string());
+        results.push(Self::stat_result(
+            format!("{} launches in the last {} days", format_count(stats.total_launches, language), stats.window.days()),
+            busiest,
+            1.0,
+        ));
+        results.push(Self::stat_result(
+            "Launches by hour".to_string(),
+            format!("00h {} 23h", format_histogram(&stats.hourly)),
+            0.99,
+        ));
+
+        for (rank, (title, count)) in stats.top_items.iter().enumerate() {
+            results.push(Self::stat_result(
+                format!("#{} {}", rank + 1, title),
+                format!("{} launches", format_count(*count, language)),
+                0.98 - rank as f64 * 0.01,
+            ));
+        }
+        for (rank, (query, count)) in stats.top_queries.iter().enumerate() {
+            results.push(Self::stat_result(
+                format!("Query \"{}\"", query),
+                format!("Searched {} times", format_count(*count, language)),
+                0.9 - rank as f64 * 0.01,
+            ));
+        }
+
+        results.push(
+            SearchResult::new("Clear all usage data", "Delete launch statistics and search history")
+                .with_action(Action::ClearUsageData)
+                .with_category(Category::SystemCommand)
+                .with_score(0.5)
+        );
+        results
+    }
+}
+
+#[async_trait]
+impl SearchSource for StatsSource {
+    fn name(&self) -> &str {
+        "Usage stats"
+    }
+
+    fn syntax_hints(&self) -> Vec<SyntaxHint> {
+        vec![SyntaxHint::new(
+            "stats [7|30|90]",
+            tr("hint.stats", "Show launch statistics for the last 7, 30 or 90 days"),
+            "stats 7",
+        )]
+    }
+
+    async fn search(&self, query: &str) -> Vec<SearchResult> {
+        let query = query.trim();
+        let Some(rest) = query.get(..Self::KEYWORD.len())
+            .filter(|keyword| keyword.eq_ignore_ascii_case(Self::KEYWORD))
+            .map(|_| &query[Self::KEYWORD.len()..])
+        else {
+            return Vec::new();
+        };
+        if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+            return Vec::new();
+        }
+
+        let window = match rest.trim() {
+            "" => StatsWindow::default(),
+            text => match StatsWindow::parse(text) {
+                Some(window) => window,
+                None => return Vec::new(),
+            },
+        };
+
+        let stats = self.search_engine.usage_stats(window, Self::TOP_LIMIT).await;
+        Self::results(&stats, current_language())
+    }
+}
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use super::*;
+    use crate::testing::{manual_clock, start_time};
+
+    fn launch(title: &str, path: Option<&str>) -> SearchResult {
+        let mut result = SearchResult::new(title, "").with_category(Category::Application);
+        result.path = path.map(PathBuf::from);
+        result
+    }
+
+    #[tokio::test]
+    async fn windows_aggregate_what_the_clock_has_seen() {
+        let dir = tempfile::tempdir().unwrap();
+        let clock = manual_clock();
+        let usage = UsageStore::with_storage_path(dir.path().join("usage.json"), clock.clone()).await.unwrap();
+        let history = SearchHistory::with_storage_path(dir.path().join("history.json"), clock.clone()).await.unwrap();
+        let firefox = launch("Firefox", Some("/usr/bin/firefox"));
+        let terminal = launch("Terminal", None);
+
+        // 35, 25 and 0 days before the stats are taken
+        usage.record(&firefox).await.unwrap();
+        clock.advance(Duration::days(10));
+        usage.record(&firefox).await.unwrap();
+        usage.record(&terminal).await.unwrap();
+        history.record("fire", "Firefox").await.unwrap();
+        history.record("FIRE", "Firefox").await.unwrap();
+        clock.advance(Duration::days(25));
+        usage.record(&terminal).await.unwrap();
+        usage.record(&terminal).await.unwrap();
+        history.record("term", "Terminal").await.unwrap();
+
+        let records = usage.records().await;
+        let entries = history.entries().await;
+        let stats = |window| compute_usage_stats(&records, &entries, window, usage.now(), FixedOffset::east_opt(9 * 3600).unwrap(), 5);
+
+        let week = stats(StatsWindow::Week);
+        assert_eq!(week.total_launches, 2);
+        assert_eq!(week.top_items, [("Terminal".to_string(), 2)]);
+        assert_eq!(week.top_queries, [("term".to_string(), 1)]);
+
+        let month = stats(StatsWindow::Month);
+        assert_eq!(month.total_launches, 4);
+        assert_eq!(month.top_items, [("Terminal".to_string(), 3), ("Firefox".to_string(), 1)]);
+        // Queries are counted case-insensitively under the first spelling seen
+        assert_eq!(month.top_queries, [("fire".to_string(), 2), ("term".to_string(), 1)]);
+
+        let quarter = stats(StatsWindow::Quarter);
+        assert_eq!(quarter.total_launches, 5);
+        assert_eq!(quarter.top_items[1], ("Firefox".to_string(), 2));
+        // Every launch was at 12:00 UTC, 21:00 at UTC+9
+        assert_eq!(quarter.hourly[21], 5);
+        assert_eq!(quarter.busiest_hour(), Some(21));
+    }
+
+    #[test]
+    fn windows_exclude_their_first_day_and_the_future() {
+        let now = start_time();
+        assert!(StatsWindow::Week.contains(now, now));
+        assert!(StatsWindow::Week.contains(now - Duration::days(7) + Duration::seconds(1), now));
+        assert!(!StatsWindow::Week.contains(now - Duration::days(7), now));
+        assert!(!StatsWindow::Week.contains(now + Duration::seconds(1), now));
+
+        assert_eq!(StatsWindow::parse("30d"), Some(StatsWindow::Month));
+        assert_eq!(StatsWindow::parse("90 days"), Some(StatsWindow::Quarter));
+        assert_eq!(StatsWindow::parse("14"), None);
+    }
+
+    #[test]
+    fn csv_fields_are_escaped_per_rfc_4180() {
+        assert_eq!(csv_field("Firefox"), "Firefox");
+        assert_eq!(csv_field("Tom, Jerry"), "\"Tom, Jerry\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+        assert_eq!(csv_field("cr\r"), "\"cr\r\"");
+        assert_eq!(csv_field(""), "");
+    }
+
+    #[test]
+    fn csv_export_quotes_titles_and_paths() {
+        let records = vec![
+            UsageRecord {
+                id: Default::default(),
+                title: "Report \"Q1\", final".to_string(),
+                path: Some(PathBuf::from("/home/me/a,b.txt")),
+                category: Category::File,
+                timestamp: start_time(),
+            },
+            UsageRecord {
+                id: Default::default(),
+                title: "Timer".to_string(),
+                path: None,
+                category: Category::Plugin("Timer".to_string()),
+                timestamp: start_time(),
+            },
+        ];
+
+        let csv = export_usage_records(&records, ExportFormat::Csv).unwrap();
+        let lines: Vec<&str> = csv.split("\r\n").collect();
+        assert_eq!(lines, [
+            "timestamp,title,path,category",
+            "2026-03-01T12:00:00+00:00,\"Report \"\"Q1\"\", final\",\"/home/me/a,b.txt\",file",
+            "2026-03-01T12:00:00+00:00,Timer,,plugin:timer",
+            "",
+        ]);
+
+        let json = export_usage_records(&records, ExportFormat::Json).unwrap();
+        assert_eq!(serde_json::from_str::<Vec<UsageRecord>>(&json).unwrap(), records);
+    }
+}
@@ -1,10 +1,27 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, RwLock};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
 use log::{info, warn, error};
 
-use falcommand_config::Config;
+use falcommand_config::{Action, Category, Config, PathResolver, SearchResult};
+pub use falcommand_config::{Resolution, SyncCategory};
+use crate::config_store::ConfigStore;
+use crate::crash::TaskSupervisor;
+use crate::history::{HistoryEntry, HistoryTombstone, SearchHistory};
+use crate::selections::SelectionAssociations;
+use crate::usage::UsageStore;
+use crate::usage_sync::{sync_usage_records, AppIdentities, SyncedUsageRecord};
+use crate::index::IndexManager;
+use crate::search::SearchEngine;
+use crate::power::PowerPolicy;
+use crate::source::SearchSource;
+use crate::sync_payload::{
+    compress_payload, content_hash, decompress_payload, read_envelope, write_envelope, PayloadHeader, UploadRecord,
+};
 
 #[derive(Debug, thiserror::Error)]
 pub enum SyncError {
@@ -36,6 +53,79 @@ pub enum SyncError {
     Other(String),
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SyncErrorKind {
+    Initialization,
+    Authentication,
+    Network,
+    Upload,
+    Download,
+    Encryption,
+    Io,
+    Other,
+}
+
+impl SyncError {
+    pub fn kind(&self) -> SyncErrorKind {
+        match self {
+            SyncError::InitializationError(_) => SyncErrorKind::Initialization,
+            SyncError::AuthenticationError(_) => SyncErrorKind::Authentication,
+            SyncError::NetworkError(_) => SyncErrorKind::Network,
+            SyncError::UploadError(_) => SyncErrorKind::Upload,
+            SyncError::DownloadError(_) => SyncErrorKind::Download,
+            SyncError::EncryptionError(_) | SyncError::DecryptionError(_) => SyncErrorKind::Encryption,
+            SyncError::IoError(_) => SyncErrorKind::Io,
+            SyncError::Other(_) => SyncErrorKind::Other,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyncConflict {
+    pub category: SyncCategory,
+    pub local_modified: DateTime<Utc>,
+    pub remote_modified: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SyncErrorInfo {
+    pub kind: SyncErrorKind,
+    pub message: String,
+    pub occurred_at: DateTime<Utc>,
+}
+
+// A conflict already answered by the user, keyed by the remote change it was about
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct ResolvedConflict {
+    category: SyncCategory,
+    remote_modified: DateTime<Utc>,
+    resolution: Resolution,
+}
+
+// Published to observers such as the tray so they can refresh without polling
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyncEvent {
+    Completed,
+    Failed(SyncErrorKind),
+    ConflictsDetected(usize),
+    ConflictResolved(SyncCategory),
+}
+
+// Sync state shared between the manager and its auto-sync task
+#[derive(Debug, Default)]
+struct SyncHealth {
+    last_sync: Option<DateTime<Utc>>,
+    last_error: Option<SyncErrorInfo>,
+    next_sync: Option<DateTime<Utc>>,
+    local_changes: HashMap<SyncCategory, DateTime<Utc>>,
+    pending_conflicts: Vec<SyncConflict>,
+    pending_remote: Option<SyncData>,
+    resolutions: Vec<ResolvedConflict>,
+    last_upload: Option<UploadRecord>,
+    // Uploads skipped because nothing changed, since start
+    skipped_uploads: u64,
+}
+
 #[async_trait]
 pub trait CloudProvider: Send + Sync {
     fn name(&self) -> &str;
@@ -49,27 +139,63 @@ pub struct LocalStorage {
     storage_path: std::path::PathBuf,
 }
 
+// The engine's stores that sync exports from and imports into
+#[derive(Debug, Clone)]
+pub struct SyncStores {
+    pub history: Arc<SearchHistory>,
+    pub usage: Arc<UsageStore>,
+    // Identifies the apps behind launches, so they merge with the same apps on other machines
+    pub index_manager: Arc<IndexManager>,
+}
+
 impl LocalStorage {
-    pub fn new() -> std::result::Result<Self, SyncError> {
-        let storage_path = dirs::data_dir()
-            .ok_or_else(|| SyncError::InitializationError("Cannot determine data directory".to_string()))?
-            .join("falcommand")
-            .join("sync_data");
+    pub fn new(resolver: &PathResolver) -> std::result::Result<Self, SyncError> {
+        let storage_path = resolver.data_file("sync_data");
         
         Ok(Self { storage_path })
     }
     
-    pub async fn export_data(&self) -> std::result::Result<Vec<u8>, SyncError> {
+    pub fn storage_path(&self) -> &std::path::Path {
+        &self.storage_path
+    }
+    
+    pub async fn export_data(
+        &self,
+        category_modified: HashMap<SyncCategory, DateTime<Utc>>,
+        stores: Option<&SyncStores>,
+        config_store: Option<&ConfigStore>,
+    ) -> std::result::Result<Vec<u8>, SyncError> {
         info!("Exporting local data for sync...");
         
-        // In a real implementation, this would export user settings, 
-        // search history, usage statistics, etc.
+        // Deleted entries are already gone from the store; their tombstones travel
+        // along so other machines delete their copies too
+        let (search_history, history_tombstones, selection_associations, usage_records) = match stores {
+            Some(stores) => (
+                stores.history.entries().await.into_iter().map(SearchHistoryEntry::from).collect(),
+                stores.history.tombstones().await,
+                stores.usage.selections().await,
+                sync_usage_records(
+                    &stores.usage.records().await,
+                    &AppIdentities::new(&stores.index_manager.applications().await),
+                ),
+            ),
+            None => (Vec::new(), Vec::new(), SelectionAssociations::default(), Vec::new()),
+        };
+        
+        let config = match config_store {
+            Some(store) => Some(store.snapshot().await),
+            None => None,
+        };
         let sync_data = SyncData {
             version: "1.0.0".to_string(),
             exported_at: chrono::Utc::now(),
-            config: None, // Would be populated with actual config
-            search_history: Vec::new(),
+            config,
+            search_history,
             usage_stats: Vec::new(),
+            category_modified,
+            history_tombstones,
+            selection_associations,
+            usage_records,
         };
         
         serde_json::to_vec(&sync_data)
@@ -79,31 +205,134 @@ impl LocalStorage {
     pub async fn import_data(&self, data: &[u8]) -> std::result::Result<(), SyncError> {
         info!("Importing sync data...");
         
-        let sync_data: SyncData = serde_json::from_slice(data)
-            .map_err(|e| SyncError::Other(format!("Failed to deserialize sync data: {}", e)))?;
+        let sync_data = Self::parse_data(data)?;
         
         info!("Imported sync data version: {}", sync_data.version);
         Ok(())
     }
+    
+    fn parse_data(data: &[u8]) -> std::result::Result<SyncData, SyncError> {
+        serde_json::from_slice(data)
+            .map_err(|e| SyncError::Other(format!("Failed to deserialize sync data: {}", e)))
+    }
+    
+    async fn import_categories(
+        &self,
+        sync_data: &SyncData,
+        categories: &[SyncCategory],
+        resolution: Resolution,
+        stores: Option<&SyncStores>,
+        config_store: Option<&ConfigStore>,
+    ) -> std::result::Result<(), SyncError> {
+        for category in categories {
+            info!("Importing {:?} from sync data version {} ({:?})", category, sync_data.version, resolution);
+            
+            if *category == SyncCategory::Settings {
+                Self::import_settings(sync_data, resolution, config_store).await?;
+                continue;
+            }
+            let Some(stores) = stores else {
+                continue;
+            };
+            match category {
+                SyncCategory::SearchHistory => {
+                    let entries = sync_data.search_history.iter().cloned().map(HistoryEntry::from).collect();
+                    stores.history.merge_entries(entries, resolution == Resolution::KeepRemote).await
+                        .map_err(|e| SyncError::Other(e.to_string()))?;
+                }
+                SyncCategory::UsageStats => {
+                    let replace = resolution == Resolution::KeepRemote;
+                    stores.usage.import_selections(&sync_data.selection_associations, replace).await
+                        .map_err(|e| SyncError::Other(e.to_string()))?;
+                    let identities = AppIdentities::new(&stores.index_manager.applications().await);
+                    stores.usage.import_records(sync_data.usage_records.clone(), &identities, replace).await
+                        .map_err(|e| SyncError::Other(e.to_string()))?;
+                }
+                SyncCategory::Settings => {}
+            }
+        }
+        Ok(())
+    }
+    
+    // The remote settings replace the local ones through the config store, so they are validated
+    // and saved like any other change. The sync section stays local: it says how this machine syncs
+    async fn import_settings(
+        sync_data: &SyncData,
+        resolution: Resolution,
+        config_store: Option<&ConfigStore>,
+    ) -> std::result::Result<(), SyncError> {
+        check_settings_resolution(resolution, config_store)?;
+        let (Some(config_store), Some(remote)) = (config_store, sync_data.config.clone()) else {
+            warn!("The sync data carries no settings to import");
+            return Ok(());
+        };
+        config_store.update(move |config| {
+            let sync = config.sync.clone();
+            *config = remote;
+            config.sync = sync;
+        }).await.map_err(|e| SyncError::Other(format!("Failed to apply the synced settings: {}", e)))
+    }
+}
+
+// Settings are one document: there is no field-by-field merge, and replacing them needs the
+// config store. Checked before a conflict is marked resolved, so a refused answer leaves it pending
+fn check_settings_resolution(resolution: Resolution, config_store: Option<&ConfigStore>) -> std::result::Result<(), SyncError> {
+    match resolution {
+        Resolution::KeepLocal => Ok(()),
+        Resolution::Merge => Err(SyncError::Other("Settings can't be merged; keep the local or the remote copy".to_string())),
+        Resolution::KeepRemote if config_store.is_none() => {
+            Err(SyncError::Other("The configuration is not available to replace".to_string()))
+        }
+        Resolution::KeepRemote => Ok(()),
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct SyncData {
     version: String,
     exported_at: chrono::DateTime<chrono::Utc>,
     config: Option<Config>,
     search_history: Vec<SearchHistoryEntry>,
     usage_stats: Vec<UsageStatEntry>,
+    #[serde(default)]
+    category_modified: HashMap<SyncCategory, DateTime<Utc>>,
+    #[serde(default)]
+    history_tombstones: Vec<HistoryTombstone>,
+    #[serde(default)]
+    selection_associations: SelectionAssociations,
+    // Launches with the identities of their apps; `usage_stats` stays empty for older versions to read
+    #[serde(default)]
+    usage_records: Vec<SyncedUsageRecord>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct SearchHistoryEntry {
     query: String,
     selected_result: String,
     timestamp: chrono::DateTime<chrono::Utc>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl From<HistoryEntry> for SearchHistoryEntry {
+    fn from(entry: HistoryEntry) -> Self {
+        Self {
+            query: entry.query,
+            selected_result: entry.selected_title,
+            timestamp: entry.timestamp,
+        }
+    }
+}
+
+impl From<SearchHistoryEntry> for HistoryEntry {
+    fn from(entry: SearchHistoryEntry) -> Self {
+        Self {
+            query: entry.query,
+            selected_title: entry.selected_result,
+            timestamp: entry.timestamp,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct UsageStatEntry {
     item: String,
     usage_count: u32,
@@ -114,23 +343,162 @@ pub struct SyncManager {
     config: Arc<RwLock<Config>>,
     local_storage: Arc<LocalStorage>,
     cloud_providers: Vec<Arc<dyn CloudProvider>>,
+    health: Arc<RwLock<SyncHealth>>,
+    resolutions_path: PathBuf,
+    upload_record_path: PathBuf,
+    events: broadcast::Sender<SyncEvent>,
+    search_engine: Option<Arc<SearchEngine>>,
+    // Settings are exported from and imported into it; without one they don't sync
+    config_store: Option<Arc<ConfigStore>>,
+    // Auto-sync waits while it defers; sync_up and sync_down called directly never ask
+    power_policy: Option<Arc<PowerPolicy>>,
 }
 
 impl SyncManager {
-    pub async fn new(config: Arc<RwLock<Config>>) -> std::result::Result<Self, SyncError> {
+    const EVENT_CAPACITY: usize = 16;
+    
+    pub async fn new(
+        config: Arc<RwLock<Config>>,
+        resolver: &PathResolver,
+    ) -> std::result::Result<Self, SyncError> {
         info!("Initializing sync manager...");
         
-        let local_storage = Arc::new(LocalStorage::new()?);
+        let local_storage = Arc::new(LocalStorage::new(resolver)?);
         let cloud_providers = Vec::new(); // Would be populated with actual providers
         
+        let resolutions_path = resolver.data_file("sync_resolutions.json");
+        let resolutions = Self::load_resolutions(&resolutions_path).await;
+        let upload_record_path = resolver.data_file("sync_upload.json");
+        let last_upload = Self::load_upload_record(&upload_record_path).await;
+        
         Ok(Self {
             config,
             local_storage,
             cloud_providers,
+            health: Arc::new(RwLock::new(SyncHealth {
+                resolutions,
+                last_upload,
+                ..SyncHealth::default()
+            })),
+            resolutions_path,
+            upload_record_path,
+            events: broadcast::channel(Self::EVENT_CAPACITY).0,
+            search_engine: None,
+            config_store: None,
+            power_policy: None,
         })
     }
     
+    // Source of the search history and usage data to export and import; the engine holds the active profile's stores
+    pub fn with_search_engine(mut self, search_engine: Arc<SearchEngine>) -> Self {
+        self.search_engine = Some(search_engine);
+        self
+    }
+    
+    pub fn with_config_store(mut self, config_store: Arc<ConfigStore>) -> Self {
+        self.config_store = Some(config_store);
+        self
+    }
+    
+    pub fn with_power_policy(mut self, power_policy: Arc<PowerPolicy>) -> Self {
+        self.power_policy = Some(power_policy);
+        self
+    }
+    
+    async fn stores(&self) -> Option<SyncStores> {
+        match self.search_engine {
+            Some(ref engine) => Some(SyncStores {
+                history: engine.history().await,
+                usage: engine.usage_store().await,
+                index_manager: engine.index_manager(),
+            }),
+            None => None,
+        }
+    }
+    
+    pub fn subscribe(&self) -> broadcast::Receiver<SyncEvent> {
+        self.events.subscribe()
+    }
+    
+    fn publish(&self, event: SyncEvent) {
+        // No subscribers is not an error
+        let _ = self.events.send(event);
+    }
+    
+    async fn load_resolutions(path: &std::path::Path) -> Vec<ResolvedConflict> {
+        match tokio::fs::read_to_string(path).await {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!("Ignoring unreadable sync resolutions {:?}: {}", path, e);
+                Vec::new()
+            }),
+            Err(_) => Vec::new(),
+        }
+    }
+    
+    async fn persist_resolutions(&self, resolutions: &[ResolvedConflict]) -> std::result::Result<(), SyncError> {
+        let path = &self.resolutions_path;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let content = serde_json::to_string_pretty(resolutions)
+            .map_err(|e| SyncError::Other(format!("Failed to serialize sync resolutions: {}", e)))?;
+        // Renamed over the old file, so a crash mid-write can't lose the answers already given
+        let tmp_path = path.with_extension("json.tmp");
+        tokio::fs::write(&tmp_path, content).await?;
+        tokio::fs::rename(&tmp_path, path).await?;
+        Ok(())
+    }
+    
+    async fn load_upload_record(path: &std::path::Path) -> Option<UploadRecord> {
+        let content = tokio::fs::read_to_string(path).await.ok()?;
+        serde_json::from_str(&content).unwrap_or_else(|e| {
+            warn!("Ignoring unreadable sync upload record {:?}: {}", path, e);
+            None
+        })
+    }
+    
+    // Kept across restarts so the first auto-sync after a start can be skipped too
+    async fn persist_upload_record(&self, record: Option<&UploadRecord>) -> std::result::Result<(), SyncError> {
+        let path = &self.upload_record_path;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let content = serde_json::to_string(&record)
+            .map_err(|e| SyncError::Other(format!("Failed to serialize the sync upload record: {}", e)))?;
+        tokio::fs::write(path, content).await?;
+        Ok(())
+    }
+    
+    // Called by the stores when a synced category changes locally, for conflict detection
+    pub async fn mark_local_change(&self, category: SyncCategory) {
+        self.health.write().await.local_changes.insert(category, Utc::now());
+    }
+    
+    async fn record_outcome(&self, result: &std::result::Result<(), SyncError>) {
+        let mut health = self.health.write().await;
+        match result {
+            Ok(()) => {
+                health.last_error = None;
+                self.publish(SyncEvent::Completed);
+            }
+            Err(e) => {
+                health.last_error = Some(SyncErrorInfo {
+                    kind: e.kind(),
+                    message: e.to_string(),
+                    occurred_at: Utc::now(),
+                });
+                self.publish(SyncEvent::Failed(e.kind()));
+            }
+        }
+    }
+    
     pub async fn sync_up(&self) -> std::result::Result<(), SyncError> {
+        let result = self.upload_local_data().await;
+        self.record_outcome(&result).await;
+        result
+    }
+    
+    async fn upload_local_data(&self) -> std::result::Result<(), SyncError> {
         let config = self.config.read().await;
         if !config.sync.enabled {
             return Ok(());
@@ -139,21 +507,49 @@ impl SyncManager {
         info!("Starting sync upload...");
         
         // Export local data
-        let data = self.local_storage.export_data().await?;
+        let category_modified = self.health.read().await.local_changes.clone();
+        let stores = self.stores().await;
+        let data = self.local_storage.export_data(category_modified, stores.as_ref(), self.config_store.as_deref()).await?;
+        let content_hash = content_hash(&data)?;
         
-        // Encrypt if enabled
-        let encrypted_data = if config.sync.encrypt_data {
-            self.encrypt_data(&data)?
+        // Compress, then encrypt if enabled; the header says how, so any later version can read it
+        let header = PayloadHeader::current();
+        let body = compress_payload(&data, header.compression)?;
+        let body = if config.sync.encrypt_data {
+            self.encrypt_data(&body)?
         } else {
-            data
+            body
         };
+        let payload = write_envelope(header, &body);
         
-        // Upload to first available provider
+        // Upload to first available provider, unless it already holds this content
         for provider in &self.cloud_providers {
             if provider.is_available().await {
-                match provider.upload(&encrypted_data).await {
+                let record = UploadRecord {
+                    provider: provider.name().to_string(),
+                    content_hash,
+                    encrypted: config.sync.encrypt_data,
+                    payload_size: payload.len(),
+                };
+                
+                let mut health = self.health.write().await;
+                if health.last_upload.as_ref().is_some_and(|last| last.matches(&record)) {
+                    info!("Sync data unchanged since the last upload to {}, skipping", provider.name());
+                    health.skipped_uploads += 1;
+                    health.last_sync = Some(Utc::now());
+                    return Ok(());
+                }
+                drop(health);
+                
+                match provider.upload(&payload).await {
                     Ok(()) => {
-                        info!("Successfully uploaded data to {}", provider.name());
+                        info!("Uploaded {} bytes ({} uncompressed) to {}", payload.len(), data.len(), provider.name());
+                        if let Err(e) = self.persist_upload_record(Some(&record)).await {
+                            warn!("Failed to save the sync upload record: {}", e);
+                        }
+                        let mut health = self.health.write().await;
+                        health.last_sync = Some(Utc::now());
+                        health.last_upload = Some(record);
                         return Ok(());
                     }
                     Err(e) => {
@@ -167,6 +563,12 @@ impl SyncManager {
     }
     
     pub async fn sync_down(&self) -> std::result::Result<(), SyncError> {
+        let result = self.download_remote_data().await;
+        self.record_outcome(&result).await;
+        result
+    }
+    
+    async fn download_remote_data(&self) -> std::result::Result<(), SyncError> {
         let config = self.config.read().await;
         if !config.sync.enabled {
             return Ok(());
@@ -178,16 +580,25 @@ impl SyncManager {
         for provider in &self.cloud_providers {
             if provider.is_available().await {
                 match provider.download().await {
-                    Ok(encrypted_data) => {
-                        // Decrypt if needed
-                        let data = if config.sync.encrypt_data {
-                            self.decrypt_data(&encrypted_data)?
+                    Ok(blob) => {
+                        // Decrypt if needed, then decompress as the header says
+                        let (header, body) = read_envelope(&blob)?;
+                        let body = if config.sync.encrypt_data {
+                            self.decrypt_data(body)?
                         } else {
-                            encrypted_data
+                            body.to_vec()
                         };
+                        let data = decompress_payload(&body, header.compression)?;
                         
-                        // Import data
-                        self.local_storage.import_data(&data).await?;
+                        // Import data, holding back categories changed on both sides
+                        let remote = LocalStorage::parse_data(&data)?;
+                        self.apply_remote_data(remote).await?;
+                        
+                        // The remote copy may now be another machine's, so the next upload isn't skipped
+                        self.health.write().await.last_upload = None;
+                        if let Err(e) = self.persist_upload_record(None).await {
+                            warn!("Failed to save the sync upload record: {}", e);
+                        }
                         info!("Successfully downloaded and imported data from {}", provider.name());
                         return Ok(());
                     }
@@ -201,6 +612,111 @@ impl SyncManager {
         Err(SyncError::DownloadError("No available cloud providers".to_string()))
     }
     
+    async fn apply_remote_data(&self, remote: SyncData) -> std::result::Result<(), SyncError> {
+        let stores = self.stores().await;
+        let stores = stores.as_ref();
+        
+        // Deletions always apply, whichever side wins a history conflict
+        if let Some(stores) = stores {
+            stores.history.apply_tombstones(remote.history_tombstones.clone()).await
+                .map_err(|e| SyncError::Other(e.to_string()))?;
+        }
+        
+        let mut health = self.health.write().await;
+        let last_sync = health.last_sync;
+        let mut importable = Vec::new();
+        let mut conflicts = Vec::new();
+        
+        for (&category, &remote_modified) in &remote.category_modified {
+            if last_sync.is_some_and(|last| remote_modified <= last) {
+                continue;
+            }
+            
+            let local_modified = health.local_changes.get(&category)
+                .copied()
+                .filter(|local| last_sync.is_none_or(|last| *local > last));
+            
+            let Some(local_modified) = local_modified else {
+                importable.push(category);
+                continue;
+            };
+            
+            // A conflict the user already answered is resolved the same way again
+            let previous = health.resolutions.iter()
+                .find(|r| r.category == category && r.remote_modified == remote_modified)
+                .map(|r| r.resolution);
+            
+            match previous {
+                Some(Resolution::KeepLocal) => {}
+                Some(resolution) => {
+                    self.local_storage.import_categories(&remote, &[category], resolution, stores, self.config_store.as_deref()).await?;
+                }
+                None => conflicts.push(SyncConflict {
+                    category,
+                    local_modified,
+                    remote_modified,
+                }),
+            }
+        }
+        
+        self.local_storage.import_categories(&remote, &importable, Resolution::KeepRemote, stores, self.config_store.as_deref()).await?;
+        
+        if conflicts.is_empty() {
+            health.last_sync = Some(Utc::now());
+            health.pending_remote = None;
+        } else {
+            warn!("Sync conflicts need resolution: {:?}", conflicts.iter().map(|c| c.category).collect::<Vec<_>>());
+            health.pending_remote = Some(remote);
+            self.publish(SyncEvent::ConflictsDetected(conflicts.len()));
+        }
+        health.pending_conflicts = conflicts;
+        
+        Ok(())
+    }
+    
+    pub async fn resolve_conflict(&self, category: SyncCategory, resolution: Resolution) -> std::result::Result<(), SyncError> {
+        let stores = self.stores().await;
+        let mut health = self.health.write().await;
+        
+        let position = health.pending_conflicts.iter()
+            .position(|c| c.category == category)
+            .ok_or_else(|| SyncError::Other(format!("No pending conflict for {:?}", category)))?;
+        let conflict = health.pending_conflicts[position].clone();
+        if category == SyncCategory::Settings {
+            check_settings_resolution(resolution, self.config_store.as_deref())?;
+        }
+        
+        match resolution {
+            Resolution::KeepLocal => {
+                // Re-stamp the local copy so the next upload supersedes the remote one
+                health.local_changes.insert(category, Utc::now());
+            }
+            Resolution::KeepRemote | Resolution::Merge => {
+                if let Some(ref remote) = health.pending_remote {
+                    self.local_storage.import_categories(remote, &[category], resolution, stores.as_ref(), self.config_store.as_deref()).await?;
+                }
+            }
+        }
+        
+        health.resolutions.retain(|r| r.category != category);
+        health.resolutions.push(ResolvedConflict {
+            category,
+            remote_modified: conflict.remote_modified,
+            resolution,
+        });
+        self.persist_resolutions(&health.resolutions).await?;
+        
+        health.pending_conflicts.remove(position);
+        if health.pending_conflicts.is_empty() {
+            health.pending_remote = None;
+            health.last_sync = Some(Utc::now());
+        }
+        
+        info!("Resolved sync conflict for {:?} with {:?}", category, resolution);
+        self.publish(SyncEvent::ConflictResolved(category));
+        Ok(())
+    }
+    
     pub async fn start_auto_sync(&self) -> std::result::Result<(), SyncError> {
         let config = self.config.read().await;
         if !config.sync.enabled {
@@ -211,22 +727,33 @@ impl SyncManager {
         info!("Starting auto-sync with interval of {} seconds", interval);
         
         // Clone for the background task
-        let sync_manager = SyncManager {
+        let sync_manager = Arc::new(SyncManager {
             config: self.config.clone(),
             local_storage: self.local_storage.clone(),
             cloud_providers: self.cloud_providers.clone(),
-        };
+            health: self.health.clone(),
+            resolutions_path: self.resolutions_path.clone(),
+            upload_record_path: self.upload_record_path.clone(),
+            events: self.events.clone(),
+            search_engine: self.search_engine.clone(),
+            config_store: self.config_store.clone(),
+            power_policy: self.power_policy.clone(),
+        });
         
-        tokio::spawn(async move {
-            let mut interval_timer = tokio::time::interval(
-                std::time::Duration::from_secs(interval as u64)
-            );
-            
-            loop {
-                interval_timer.tick().await;
+        // Supervised so a panic inside a provider doesn't silently stop syncing
+        TaskSupervisor::default().spawn("auto-sync", move || {
+            let sync_manager = sync_manager.clone();
+            async move {
+                let mut interval_timer = tokio::time::interval(
+                    std::time::Duration::from_secs(interval as u64)
+                );
                 
-                if let Err(e) = sync_manager.sync_up().await {
-                    error!("Auto-sync failed: {}", e);
+                loop {
+                    interval_timer.tick().await;
+                    sync_manager.auto_sync_round().await;
+                    
+                    let next_sync = Utc::now() + chrono::Duration::seconds(interval as i64);
+                    sync_manager.health.write().await.next_sync = Some(next_sync);
                 }
             }
         });
@@ -234,6 +761,19 @@ impl SyncManager {
         Ok(())
     }
     
+    // One timed upload; false when the power policy deferred it. A manual sync_up never asks
+    pub async fn auto_sync_round(&self) -> bool {
+        if let Some(ref power_policy) = self.power_policy {
+            if !power_policy.allows("auto-sync").await {
+                return false;
+            }
+        }
+        if let Err(e) = self.sync_up().await {
+            error!("Auto-sync failed: {}", e);
+        }
+        true
+    }
+    
     fn encrypt_data(&self, data: &[u8]) -> std::result::Result<Vec<u8>, SyncError> {
         // Placeholder encryption implementation
         // In a real implementation, this would use proper encryption
@@ -248,6 +788,12 @@ impl SyncManager {
         Ok(encrypted_data.to_vec())
     }
     
+    pub fn with_cloud_provider(mut self, provider: Arc<dyn CloudProvider>) -> Self {
+        info!("Adding cloud provider: {}", provider.name());
+        self.cloud_providers.push(provider);
+        self
+    }
+    
     pub async fn add_cloud_provider(&mut self, provider: Arc<dyn CloudProvider>) {
         info!("Adding cloud provider: {}", provider.name());
         self.cloud_providers.push(provider);
@@ -265,10 +811,17 @@ impl SyncManager {
             providers
         };
         
+        let health = self.health.read().await;
+        
         SyncStatus {
             enabled: config.sync.enabled,
             available_providers,
-            last_sync: None, // Would track actual last sync time
+            last_sync: health.last_sync,
+            last_error: health.last_error.clone(),
+            pending_conflicts: health.pending_conflicts.clone(),
+            next_sync: health.next_sync,
+            payload_size: health.last_upload.as_ref().map(|record| record.payload_size),
+            skipped_uploads: health.skipped_uploads,
         }
     }
 }
@@ -278,6 +831,12 @@ pub struct SyncStatus {
     pub enabled: bool,
     pub available_providers: Vec<String>,
     pub last_sync: Option<chrono::DateTime<chrono::Utc>>,
+    pub last_error: Option<SyncErrorInfo>,
+    pub pending_conflicts: Vec<SyncConflict>,
+    pub next_sync: Option<chrono::DateTime<chrono::Utc>>,
+    // Bytes of the last upload, after compression
+    pub payload_size: Option<usize>,
+    pub skipped_uploads: u64,
 }
 
 // Placeholder cloud provider implementation
@@ -312,3 +871,345 @@ impl CloudProvider for DropboxProvider {
         Ok(())
     }
 }
+
+// Keeps the sync payload in a folder, such as one a file-syncing client already mirrors
+#[derive(Debug)]
+pub struct FolderProvider {
+    folder: PathBuf,
+}
+
+impl FolderProvider {
+    const FILE_NAME: &'static str = "falcommand-sync.bin";
+    
+    pub fn new(folder: PathBuf) -> Self {
+        Self { folder }
+    }
+    
+    fn payload_path(&self) -> PathBuf {
+        self.folder.join(Self::FILE_NAME)
+    }
+}
+
+#[async_trait]
+impl CloudProvider for FolderProvider {
+    fn name(&self) -> &str {
+        "Folder"
+    }
+    
+    async fn upload(&self, data: &[u8]) -> std::result::Result<(), SyncError> {
+        tokio::fs::create_dir_all(&self.folder).await?;
+        // Written beside the payload and renamed over it, so a reader never sees half of it
+        let temp = self.folder.join(format!("{}.tmp", Self::FILE_NAME));
+        tokio::fs::write(&temp, data).await
+            .map_err(|e| SyncError::UploadError(format!("{}: {}", temp.display(), e)))?;
+        tokio::fs::rename(&temp, self.payload_path()).await
+            .map_err(|e| SyncError::UploadError(format!("{}: {}", self.payload_path().display(), e)))?;
+        Ok(())
+    }
+    
+    async fn download(&self) -> std::result::Result<Vec<u8>, SyncError> {
+        tokio::fs::read(self.payload_path()).await
+            .map_err(|e| SyncError::DownloadError(format!("{}: {}", self.payload_path().display(), e)))
+    }
+    
+    async fn is_available(&self) -> bool {
+        tokio::fs::create_dir_all(&self.folder).await.is_ok()
+    }
+    
+    async fn authenticate(&self) -> std::result::Result<(), SyncError> {
+        Ok(())
+    }
+}
+
+// "Resolve sync conflict: Settings" per pending conflict. Enter keeps this device's copy; the
+// secondary actions keep the cloud copy or merge both, where merging is possible
+pub struct SyncConflictSource {
+    sync_manager: Arc<SyncManager>,
+}
+
+impl SyncConflictSource {
+    const KEYWORDS: &'static [&'static str] = &["sync conflicts", "resolve sync conflict"];
+    const MIN_QUERY_LEN: usize = 2;
+
+    pub fn new(sync_manager: Arc<SyncManager>) -> Self {
+        Self { sync_manager }
+    }
+
+    fn conflict_result(conflict: &SyncConflict) -> SearchResult {
+        let local = |time: DateTime<Utc>| time.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M");
+        let resolve = |resolution| Action::ResolveSyncConflict { category: conflict.category, resolution };
+        let mut result = SearchResult::new(
+            format!("Resolve sync conflict: {}", conflict.category.label()),
+            format!(
+                "Changed here {} and in the cloud {}; Enter keeps this device's copy",
+                local(conflict.local_modified), local(conflict.remote_modified)
+            ),
+        )
+        .with_action(resolve(Resolution::KeepLocal))
+        .with_category(Category::SystemCommand)
+        .with_score(0.9)
+        .with_secondary_action("Keep the cloud copy", resolve(Resolution::KeepRemote));
+        // Settings are replaced whole, never merged
+        if conflict.category != SyncCategory::Settings {
+            result = result.with_secondary_action("Merge both copies", resolve(Resolution::Merge));
+        }
+        result
+    }
+}
+
+#[async_trait]
+impl SearchSource for SyncConflictSource {
+    fn name(&self) -> &str {
+        "Sync conflicts"
+    }
+
+    async fn search(&self, query: &str) -> Vec<SearchResult> {
+        let query = query.trim().to_lowercase();
+        if query.chars().count() < Self::MIN_QUERY_LEN || !Self::KEYWORDS.iter().any(|keyword| keyword.starts_with(&query)) {
+            return Vec::new();
+        }
+        self.sync_manager.get_sync_status().await.pending_conflicts.iter()
+            .map(Self::conflict_result)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{engine, resolver};
+
+    // One machine: its own stores and config, syncing through the shared folder
+    struct Machine {
+        _dir: tempfile::TempDir,
+        history: Arc<SearchHistory>,
+        config_store: Arc<ConfigStore>,
+        manager: SyncManager,
+    }
+
+    async fn machine(folder: &std::path::Path, font_size: u32) -> Machine {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.sync.enabled = true;
+        config.appearance.font_size = font_size;
+        let test = engine(dir.path(), config.clone()).await;
+        let config_store = Arc::new(ConfigStore::new(config, dir.path().join("config.json")));
+        let manager = SyncManager::new(config_store.shared(), &resolver(dir.path())).await.unwrap()
+            .with_search_engine(test.engine.clone())
+            .with_config_store(config_store.clone())
+            .with_cloud_provider(Arc::new(FolderProvider::new(folder.to_path_buf())));
+        Machine { history: test.history.clone(), _dir: dir, config_store, manager }
+    }
+
+    async fn pending(machine: &Machine) -> Vec<SyncCategory> {
+        machine.manager.get_sync_status().await.pending_conflicts.iter().map(|c| c.category).collect()
+    }
+
+    #[tokio::test]
+    async fn remote_settings_replace_the_local_ones() {
+        let folder = tempfile::tempdir().unwrap();
+        let a = machine(folder.path(), 18).await;
+        a.manager.mark_local_change(SyncCategory::Settings).await;
+        a.manager.sync_up().await.unwrap();
+
+        let b = machine(folder.path(), 14).await;
+        b.config_store.update(|config| config.sync.auto_sync_interval = 60).await.unwrap();
+        b.manager.mark_local_change(SyncCategory::Settings).await;
+        b.manager.sync_down().await.unwrap();
+        assert_eq!(pending(&b).await, vec![SyncCategory::Settings]);
+
+        // Refused answers leave the conflict open and the settings untouched
+        assert!(b.manager.resolve_conflict(SyncCategory::Settings, Resolution::Merge).await.is_err());
+        assert_eq!(pending(&b).await, vec![SyncCategory::Settings]);
+        assert_eq!(b.config_store.snapshot().await.appearance.font_size, 14);
+
+        b.manager.resolve_conflict(SyncCategory::Settings, Resolution::KeepRemote).await.unwrap();
+        let settings = b.config_store.snapshot().await;
+        assert_eq!(settings.appearance.font_size, 18);
+        // How this machine syncs is its own
+        assert_eq!(settings.sync.auto_sync_interval, 60);
+        assert!(pending(&b).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn history_conflicts_merge_both_sides() {
+        let folder = tempfile::tempdir().unwrap();
+        let a = machine(folder.path(), 14).await;
+        a.history.record("firefox", "Firefox").await.unwrap();
+        a.manager.mark_local_change(SyncCategory::SearchHistory).await;
+        a.manager.sync_up().await.unwrap();
+
+        let b = machine(folder.path(), 14).await;
+        b.history.record("terminal", "Terminal").await.unwrap();
+        b.manager.mark_local_change(SyncCategory::SearchHistory).await;
+        b.manager.sync_down().await.unwrap();
+        assert_eq!(pending(&b).await, vec![SyncCategory::SearchHistory]);
+
+        b.manager.resolve_conflict(SyncCategory::SearchHistory, Resolution::Merge).await.unwrap();
+        let mut queries: Vec<_> = b.history.entries().await.into_iter().map(|entry| entry.query).collect();
+        queries.sort();
+        assert_eq!(queries, vec!["firefox", "terminal"]);
+    }
+
+    #[tokio::test]
+    async fn answers_are_saved_whole_and_reused() {
+        let folder = tempfile::tempdir().unwrap();
+        let a = machine(folder.path(), 18).await;
+        a.manager.mark_local_change(SyncCategory::Settings).await;
+        a.manager.sync_up().await.unwrap();
+
+        let b = machine(folder.path(), 14).await;
+        b.manager.mark_local_change(SyncCategory::Settings).await;
+        b.manager.sync_down().await.unwrap();
+        b.manager.resolve_conflict(SyncCategory::Settings, Resolution::KeepLocal).await.unwrap();
+
+        let saved = b.manager.resolutions_path.clone();
+        assert!(!saved.with_extension("json.tmp").exists());
+        let answers = SyncManager::load_resolutions(&saved).await;
+        assert_eq!(answers.len(), 1);
+        assert_eq!(answers[0].resolution, Resolution::KeepLocal);
+
+        // The same remote change is answered the same way without asking again
+        b.manager.health.write().await.last_sync = None;
+        b.manager.sync_down().await.unwrap();
+        assert!(pending(&b).await.is_empty());
+        assert_eq!(b.config_store.snapshot().await.appearance.font_size, 14);
+    }
+
+    // Each pending conflict is listed with its answers; settings can't be merged
+    #[tokio::test]
+    async fn pending_conflicts_are_offered_as_results() {
+        let folder = tempfile::tempdir().unwrap();
+        let a = machine(folder.path(), 18).await;
+        a.history.record("firefox", "Firefox").await.unwrap();
+        a.manager.mark_local_change(SyncCategory::Settings).await;
+        a.manager.mark_local_change(SyncCategory::SearchHistory).await;
+        a.manager.sync_up().await.unwrap();
+
+        let b = machine(folder.path(), 14).await;
+        b.manager.mark_local_change(SyncCategory::Settings).await;
+        b.manager.mark_local_change(SyncCategory::SearchHistory).await;
+        b.manager.sync_down().await.unwrap();
+        let manager = Arc::new(b.manager);
+        let source = SyncConflictSource::new(manager.clone());
+        assert!(source.search("weather").await.is_empty());
+
+        let mut results = source.search("sync conf").await;
+        results.sort_by(|a, b| a.title.cmp(&b.title));
+        let titles: Vec<&str> = results.iter().map(|result| result.title.as_str()).collect();
+        assert_eq!(titles, ["Resolve sync conflict: Search history", "Resolve sync conflict: Settings"]);
+        let actions = |result: &SearchResult| result.secondary_actions.iter().map(|action| action.title.clone()).collect::<Vec<_>>();
+        assert_eq!(actions(&results[0]), ["Keep the cloud copy", "Merge both copies"]);
+        assert_eq!(actions(&results[1]), ["Keep the cloud copy"]);
+        assert_eq!(results[1].action, Action::ResolveSyncConflict { category: SyncCategory::Settings, resolution: Resolution::KeepLocal });
+
+        // Answered conflicts drop out of the list
+        manager.resolve_conflict(SyncCategory::Settings, Resolution::KeepLocal).await.unwrap();
+        assert_eq!(source.search("resolve sync").await.len(), 1);
+    }
+
+    fn blob(folder: &std::path::Path) -> Vec<u8> {
+        std::fs::read(folder.join("falcommand-sync.bin")).unwrap()
+    }
+
+    // The same data uploads once; the export time alone doesn't count as a change
+    #[tokio::test]
+    async fn unchanged_data_is_not_uploaded_again() {
+        let folder = tempfile::tempdir().unwrap();
+        let a = machine(folder.path(), 14).await;
+        a.history.record("firefox", "Firefox").await.unwrap();
+        a.manager.sync_up().await.unwrap();
+        let first = blob(folder.path());
+        let status = a.manager.get_sync_status().await;
+        assert_eq!((status.payload_size, status.skipped_uploads), (Some(first.len()), 0));
+
+        a.manager.sync_up().await.unwrap();
+        assert_eq!(blob(folder.path()), first);
+        assert_eq!(a.manager.get_sync_status().await.skipped_uploads, 1);
+
+        a.history.record("terminal", "Terminal").await.unwrap();
+        a.manager.sync_up().await.unwrap();
+        assert_ne!(blob(folder.path()), first);
+        assert_eq!(a.manager.get_sync_status().await.skipped_uploads, 1);
+
+        // Turning encryption off changes what the remote copy should be
+        a.config_store.update(|config| config.sync.encrypt_data = false).await.unwrap();
+        let before = a.manager.get_sync_status().await.skipped_uploads;
+        a.manager.sync_up().await.unwrap();
+        assert_eq!(a.manager.get_sync_status().await.skipped_uploads, before);
+    }
+
+    #[tokio::test]
+    async fn the_upload_record_survives_a_restart() {
+        let folder = tempfile::tempdir().unwrap();
+        let a = machine(folder.path(), 14).await;
+        a.manager.sync_up().await.unwrap();
+
+        let restarted = SyncManager::new(a.config_store.shared(), &resolver(a._dir.path())).await.unwrap()
+            .with_config_store(a.config_store.clone())
+            .with_cloud_provider(Arc::new(FolderProvider::new(folder.path().to_path_buf())));
+        restarted.sync_up().await.unwrap();
+        assert_eq!(restarted.get_sync_status().await.skipped_uploads, 1);
+    }
+
+    // Someone else's upload may have replaced ours, so a download forgets what we sent
+    #[tokio::test]
+    async fn a_download_means_the_next_upload_is_sent() {
+        let folder = tempfile::tempdir().unwrap();
+        let a = machine(folder.path(), 14).await;
+        a.manager.sync_up().await.unwrap();
+        a.manager.sync_down().await.unwrap();
+        assert_eq!(a.manager.get_sync_status().await.payload_size, None);
+
+        a.manager.sync_up().await.unwrap();
+        assert_eq!(a.manager.get_sync_status().await.skipped_uploads, 0);
+    }
+
+    // Compressed, through the encryption layer and back on another machine
+    #[tokio::test]
+    async fn encrypted_uploads_round_trip() {
+        let folder = tempfile::tempdir().unwrap();
+        let a = machine(folder.path(), 14).await;
+        assert!(a.config_store.snapshot().await.sync.encrypt_data);
+        a.history.record("firefox", "Firefox").await.unwrap();
+        a.manager.mark_local_change(SyncCategory::SearchHistory).await;
+        a.manager.sync_up().await.unwrap();
+        let (header, _) = read_envelope(&blob(folder.path())).unwrap();
+        assert_eq!(header, PayloadHeader::current());
+
+        let b = machine(folder.path(), 14).await;
+        b.manager.sync_down().await.unwrap();
+        let queries: Vec<_> = b.history.entries().await.into_iter().map(|entry| entry.query).collect();
+        assert_eq!(queries, vec!["firefox"]);
+    }
+
+    // Bare JSON written before the envelope still imports
+    #[tokio::test]
+    async fn legacy_uploads_still_import() {
+        let folder = tempfile::tempdir().unwrap();
+        let a = machine(folder.path(), 14).await;
+        a.history.record("firefox", "Firefox").await.unwrap();
+        a.manager.mark_local_change(SyncCategory::SearchHistory).await;
+        a.manager.sync_up().await.unwrap();
+        let current = blob(folder.path());
+        let (header, body) = read_envelope(&current).unwrap();
+        let json = decompress_payload(body, header.compression).unwrap();
+        std::fs::write(folder.path().join("falcommand-sync.bin"), &json).unwrap();
+
+        let b = machine(folder.path(), 14).await;
+        b.manager.sync_down().await.unwrap();
+        let queries: Vec<_> = b.history.entries().await.into_iter().map(|entry| entry.query).collect();
+        assert_eq!(queries, vec!["firefox"]);
+    }
+
+    // Data from a newer version is refused rather than misread
+    #[tokio::test]
+    async fn newer_formats_are_refused() {
+        let folder = tempfile::tempdir().unwrap();
+        std::fs::write(folder.path().join("falcommand-sync.bin"), b"FCSY\x09\x01data").unwrap();
+        let b = machine(folder.path(), 14).await;
+        let error = b.manager.sync_down().await.unwrap_err().to_string();
+        assert!(error.contains("update FalCommand"), "{}", error);
+        assert!(b.history.entries().await.is_empty());
+    }
+}
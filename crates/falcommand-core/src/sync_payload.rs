@@ -0,0 +1,197 @@
+use std::io::{Read, Write};
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use falcommand_config::fnv1a;
+use crate::sync::SyncError;
+
+// Uploaded blobs start with this plain header; the body after it is the serialized SyncData,
+// compressed and then, when enabled, encrypted. Blobs from before the header are bare JSON
+const MAGIC: &[u8; 4] = b"FCSY";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PayloadCompression {
+    None,
+    Gzip,
+}
+
+impl PayloadCompression {
+    fn to_byte(self) -> u8 {
+        match self {
+            PayloadCompression::None => 0,
+            PayloadCompression::Gzip => 1,
+        }
+    }
+
+    fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(PayloadCompression::None),
+            1 => Some(PayloadCompression::Gzip),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PayloadHeader {
+    // 0 for blobs written before the header existed
+    pub version: u8,
+    pub compression: PayloadCompression,
+}
+
+impl PayloadHeader {
+    pub const CURRENT_VERSION: u8 = 1;
+
+    // How blobs are written now
+    pub fn current() -> Self {
+        Self { version: Self::CURRENT_VERSION, compression: PayloadCompression::Gzip }
+    }
+
+    pub fn legacy() -> Self {
+        Self { version: 0, compression: PayloadCompression::None }
+    }
+}
+
+pub fn write_envelope(header: PayloadHeader, body: &[u8]) -> Vec<u8> {
+    let mut blob = Vec::with_capacity(MAGIC.len() + 2 + body.len());
+    blob.extend_from_slice(MAGIC);
+    blob.push(header.version);
+    blob.push(header.compression.to_byte());
+    blob.extend_from_slice(body);
+    blob
+}
+
+// The header and body of a downloaded blob; one without the magic is a legacy blob, all body
+pub fn read_envelope(blob: &[u8]) -> std::result::Result<(PayloadHeader, &[u8]), SyncError> {
+    let Some(rest) = blob.strip_prefix(MAGIC.as_slice()) else {
+        return Ok((PayloadHeader::legacy(), blob));
+    };
+    let [version, compression, body @ ..] = rest else {
+        return Err(SyncError::Other("Sync data header is truncated".to_string()));
+    };
+
+    if *version == 0 || *version > PayloadHeader::CURRENT_VERSION {
+        return Err(SyncError::Other(format!(
+            "Sync data format {} is not supported (this version reads up to {}); update FalCommand",
+            version, PayloadHeader::CURRENT_VERSION
+        )));
+    }
+    let compression = PayloadCompression::from_byte(*compression)
+        .ok_or_else(|| SyncError::Other(format!("Unknown sync data compression {}", compression)))?;
+    Ok((PayloadHeader { version: *version, compression }, body))
+}
+
+pub fn compress_payload(data: &[u8], compression: PayloadCompression) -> std::result::Result<Vec<u8>, SyncError> {
+    match compression {
+        PayloadCompression::None => Ok(data.to_vec()),
+        PayloadCompression::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(data)?;
+            Ok(encoder.finish()?)
+        }
+    }
+}
+
+pub fn decompress_payload(body: &[u8], compression: PayloadCompression) -> std::result::Result<Vec<u8>, SyncError> {
+    match compression {
+        PayloadCompression::None => Ok(body.to_vec()),
+        PayloadCompression::Gzip => {
+            let mut data = Vec::new();
+            GzDecoder::new(body).read_to_end(&mut data)
+                .map_err(|e| SyncError::Other(format!("Failed to decompress sync data: {}", e)))?;
+            Ok(data)
+        }
+    }
+}
+
+// Hash of what serialized SyncData holds, without its export time, so exports of unchanged data
+// hash the same. Keys are hashed sorted, whatever order the maps were serialized in
+pub fn content_hash(data: &[u8]) -> std::result::Result<u64, SyncError> {
+    let mut value: Value = serde_json::from_slice(data)
+        .map_err(|e| SyncError::Other(format!("Failed to read sync data: {}", e)))?;
+    if let Some(object) = value.as_object_mut() {
+        object.remove("exported_at");
+    }
+    let canonical = serde_json::to_vec(&value)
+        .map_err(|e| SyncError::Other(format!("Failed to serialize sync data: {}", e)))?;
+    Ok(fnv1a(&canonical))
+}
+
+// The last upload, so uploading the same content to the same place again can be skipped
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UploadRecord {
+    pub provider: String,
+    pub content_hash: u64,
+    pub encrypted: bool,
+    // Bytes sent, after compression
+    pub payload_size: usize,
+}
+
+impl UploadRecord {
+    // Whether uploading `next` would send the same content to the same place
+    pub fn matches(&self, next: &UploadRecord) -> bool {
+        self.provider == next.provider && self.content_hash == next.content_hash && self.encrypted == next.encrypted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DATA: &[u8] = br#"{"exported_at":"2026-03-01T12:00:00Z","history":[{"query":"firefox"},{"query":"firefox"}]}"#;
+
+    #[test]
+    fn compression_round_trips() {
+        for compression in [PayloadCompression::None, PayloadCompression::Gzip] {
+            let body = compress_payload(DATA, compression).unwrap();
+            assert_eq!(decompress_payload(&body, compression).unwrap(), DATA);
+        }
+        let repetitive = DATA.repeat(100);
+        assert!(compress_payload(&repetitive, PayloadCompression::Gzip).unwrap().len() < repetitive.len() / 10);
+        assert!(decompress_payload(b"not gzip", PayloadCompression::Gzip).is_err());
+    }
+
+    #[test]
+    fn envelopes_carry_their_header() {
+        let body = compress_payload(DATA, PayloadCompression::Gzip).unwrap();
+        let blob = write_envelope(PayloadHeader::current(), &body);
+        assert!(blob.starts_with(b"FCSY\x01\x01"));
+        let (header, read_body) = read_envelope(&blob).unwrap();
+        assert_eq!((header, read_body), (PayloadHeader::current(), body.as_slice()));
+
+        // Bare JSON from before the header: all body, uncompressed
+        assert_eq!(read_envelope(DATA).unwrap(), (PayloadHeader::legacy(), DATA));
+    }
+
+    #[test]
+    fn unreadable_headers_are_refused() {
+        let newer = read_envelope(b"FCSY\x02\x01body").unwrap_err().to_string();
+        assert!(newer.contains("format 2 is not supported") && newer.contains("update FalCommand"), "{}", newer);
+        assert!(read_envelope(b"FCSY\x00\x00{}").is_err());
+        assert!(read_envelope(b"FCSY\x01").unwrap_err().to_string().contains("truncated"));
+        assert!(read_envelope(b"FCSY\x01\x07body").unwrap_err().to_string().contains("Unknown sync data compression 7"));
+    }
+
+    // The export time and key order don't change the hash; the content does
+    #[test]
+    fn content_hash_ignores_the_export_time() {
+        let later = br#"{"history":[{"query":"firefox"},{"query":"firefox"}],"exported_at":"2026-03-02T08:00:00Z"}"#;
+        assert_eq!(content_hash(DATA).unwrap(), content_hash(later).unwrap());
+        let changed = br#"{"exported_at":"2026-03-01T12:00:00Z","history":[{"query":"firefox"}]}"#;
+        assert_ne!(content_hash(DATA).unwrap(), content_hash(changed).unwrap());
+        assert!(content_hash(b"not json").is_err());
+    }
+
+    #[test]
+    fn uploads_match_on_provider_content_and_encryption() {
+        let record = UploadRecord { provider: "Folder".to_string(), content_hash: 1, encrypted: false, payload_size: 100 };
+        // The size follows from the content, so it doesn't take part
+        assert!(record.matches(&UploadRecord { payload_size: 120, ..record.clone() }));
+        assert!(!record.matches(&UploadRecord { provider: "WebDAV".to_string(), ..record.clone() }));
+        assert!(!record.matches(&UploadRecord { content_hash: 2, ..record.clone() }));
+        assert!(!record.matches(&UploadRecord { encrypted: true, ..record.clone() }));
+    }
+}
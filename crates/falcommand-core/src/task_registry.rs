@@ -0,0 +1,286 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use log::warn;
+
+use crate::crash::TaskSupervisor;
+
+// Written by the resident launcher for `falcommand status`, which runs as a separate process
+pub const TASK_SNAPSHOT_FILE: &str = "tasks.json";
+
+// Bookkeeping of one spawned task, shared with the task itself
+#[derive(Debug)]
+pub struct TaskState {
+    name: String,
+    spawned_at: DateTime<Utc>,
+    restarts: AtomicU32,
+    finished: AtomicBool,
+}
+
+impl TaskState {
+    pub fn restarted(&self) {
+        self.restarts.fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub fn is_alive(&self) -> bool {
+        !self.finished.load(Ordering::SeqCst)
+    }
+}
+
+// Marks its task finished when dropped, so a task that returns, is cancelled or panics past its
+// supervisor all stop counting as alive
+#[derive(Debug)]
+pub struct TaskGuard(Arc<TaskState>);
+
+impl TaskGuard {
+    pub fn state(&self) -> &TaskState {
+        &self.0
+    }
+}
+
+impl Drop for TaskGuard {
+    fn drop(&mut self) {
+        self.0.finished.store(true, Ordering::SeqCst);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrackedTask {
+    pub name: String,
+    pub spawned_at: DateTime<Utc>,
+    pub alive: bool,
+    pub restarts: u32,
+}
+
+// Running and finished counts, and how many of each name are running
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskCounts {
+    pub alive: usize,
+    pub finished: usize,
+    pub alive_by_name: BTreeMap<String, usize>,
+}
+
+// The long-running tasks spawned in this process, with when they started and whether they still
+// run. A count that keeps climbing is a task leak
+#[derive(Debug, Default)]
+pub struct TaskRegistry {
+    tasks: Mutex<VecDeque<Arc<TaskState>>>,
+}
+
+impl TaskRegistry {
+    // Finished tasks kept for reporting; older ones are forgotten
+    const MAX_FINISHED: usize = 100;
+
+    // The registry TaskSupervisor::spawn records into
+    pub fn global() -> &'static TaskRegistry {
+        static REGISTRY: OnceLock<TaskRegistry> = OnceLock::new();
+        REGISTRY.get_or_init(TaskRegistry::default)
+    }
+
+    // Records a task spawned now; the task holds the guard for as long as it runs
+    pub fn track(&self, name: &str, now: DateTime<Utc>) -> TaskGuard {
+        let state = Arc::new(TaskState {
+            name: name.to_string(),
+            spawned_at: now,
+            restarts: AtomicU32::new(0),
+            finished: AtomicBool::new(false),
+        });
+        let mut tasks = self.tasks.lock().unwrap();
+        tasks.push_back(state.clone());
+        Self::prune(&mut tasks);
+        TaskGuard(state)
+    }
+
+    fn prune(tasks: &mut VecDeque<Arc<TaskState>>) {
+        let finished = tasks.iter().filter(|task| !task.is_alive()).count();
+        let mut excess = finished.saturating_sub(Self::MAX_FINISHED);
+        tasks.retain(|task| {
+            if excess > 0 && !task.is_alive() {
+                excess -= 1;
+                return false;
+            }
+            true
+        });
+    }
+
+    // In spawn order
+    pub fn tasks(&self) -> Vec<TrackedTask> {
+        let mut tasks = self.tasks.lock().unwrap();
+        Self::prune(&mut tasks);
+        tasks.iter()
+            .map(|task| TrackedTask {
+                name: task.name.clone(),
+                spawned_at: task.spawned_at,
+                alive: task.is_alive(),
+                restarts: task.restarts.load(Ordering::SeqCst),
+            })
+            .collect()
+    }
+
+    pub fn counts(&self) -> TaskCounts {
+        let mut counts = TaskCounts::default();
+        for task in self.tasks() {
+            if task.alive {
+                counts.alive += 1;
+                *counts.alive_by_name.entry(task.name).or_default() += 1;
+            } else {
+                counts.finished += 1;
+            }
+        }
+        counts
+    }
+}
+
+// The registry as the resident launcher last wrote it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaskSnapshot {
+    pub written_at: DateTime<Utc>,
+    pub pid: u32,
+    pub tasks: Vec<TrackedTask>,
+}
+
+impl TaskSnapshot {
+    pub const INTERVAL: Duration = Duration::from_secs(30);
+
+    pub fn capture(registry: &TaskRegistry, now: DateTime<Utc>) -> Self {
+        Self { written_at: now, pid: std::process::id(), tasks: registry.tasks() }
+    }
+
+    pub async fn read(path: &Path) -> Option<Self> {
+        let content = tokio::fs::read_to_string(path).await.ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    // Older than two intervals means the launcher that wrote it is no longer running
+    pub fn is_stale(&self, now: DateTime<Utc>) -> bool {
+        let interval = chrono::Duration::from_std(Self::INTERVAL * 2).unwrap_or_default();
+        now - self.written_at > interval
+    }
+
+    // "Background tasks: 9 running, 1 finished", then one line per task
+    pub fn describe(&self, now: DateTime<Utc>) -> String {
+        let local = |time: DateTime<Utc>| chrono::DateTime::<chrono::Local>::from(time).format("%Y-%m-%d %H:%M");
+        let alive = self.tasks.iter().filter(|task| task.alive).count();
+        let mut text = format!("Background tasks: {} running, {} finished", alive, self.tasks.len() - alive);
+        if self.is_stale(now) {
+            text.push_str(&format!(" (as of {}; the launcher is not running)", local(self.written_at)));
+        }
+        text.push('\n');
+        for task in &self.tasks {
+            let state = if task.alive { "running" } else { "finished" };
+            text.push_str(&format!("  {} {} since {}", task.name, state, local(task.spawned_at)));
+            if task.restarts > 0 {
+                text.push_str(&format!(", restarted {} times", task.restarts));
+            }
+            text.push('\n');
+        }
+        text
+    }
+}
+
+// Keeps `path` up to date with the global registry while the launcher runs
+pub fn start_task_snapshots(path: PathBuf) {
+    TaskSupervisor::default().spawn("task-snapshots", move || {
+        let path = path.clone();
+        async move {
+            let mut interval = tokio::time::interval(TaskSnapshot::INTERVAL);
+            loop {
+                interval.tick().await;
+                let snapshot = TaskSnapshot::capture(TaskRegistry::global(), Utc::now());
+                let written = match serde_json::to_vec_pretty(&snapshot) {
+                    Ok(json) => tokio::fs::write(&path, json).await.map_err(|e| e.to_string()),
+                    Err(e) => Err(e.to_string()),
+                };
+                if let Err(e) = written {
+                    warn!("Failed to write the task snapshot to {}: {}", path.display(), e);
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::start_time;
+
+    #[test]
+    fn tasks_count_as_alive_until_their_guard_drops() {
+        let registry = TaskRegistry::default();
+        let sync = registry.track("auto-sync", start_time());
+        let watcher = registry.track("app-watch", start_time());
+        let second_watcher = registry.track("app-watch", start_time());
+        sync.state().restarted();
+        sync.state().restarted();
+        drop(watcher);
+
+        let counts = registry.counts();
+        assert_eq!((counts.alive, counts.finished), (2, 1));
+        assert_eq!(counts.alive_by_name, BTreeMap::from([("app-watch".to_string(), 1), ("auto-sync".to_string(), 1)]));
+
+        let tasks = registry.tasks();
+        assert_eq!(tasks.iter().map(|task| (task.name.as_str(), task.alive)).collect::<Vec<_>>(), [
+            ("auto-sync", true), ("app-watch", false), ("app-watch", true),
+        ]);
+        assert_eq!(tasks[0].restarts, 2);
+        drop(second_watcher);
+        assert_eq!(registry.counts().alive, 1);
+    }
+
+    // Only finished tasks are forgotten, oldest first
+    #[test]
+    fn finished_tasks_are_capped() {
+        let registry = TaskRegistry::default();
+        let resident = registry.track("resident", start_time());
+        for n in 0..TaskRegistry::MAX_FINISHED + 5 {
+            drop(registry.track(&format!("job-{}", n), start_time()));
+        }
+
+        let tasks = registry.tasks();
+        assert_eq!(tasks.len(), TaskRegistry::MAX_FINISHED + 1);
+        assert_eq!(tasks[0].name, "resident");
+        assert_eq!(tasks[1].name, "job-5");
+        assert_eq!(registry.counts().alive, 1);
+        drop(resident);
+    }
+
+    #[test]
+    fn snapshots_describe_tasks_and_go_stale() {
+        let registry = TaskRegistry::default();
+        let _sync = registry.track("auto-sync", start_time());
+        let restarted = registry.track("app-watch", start_time());
+        restarted.state().restarted();
+        drop(registry.track("index-rebuild", start_time()));
+        let snapshot = TaskSnapshot::capture(&registry, start_time());
+
+        let fresh = snapshot.describe(start_time() + chrono::Duration::seconds(30));
+        let lines: Vec<&str> = fresh.lines().collect();
+        assert_eq!(lines[0], "Background tasks: 2 running, 1 finished");
+        assert!(lines[1].starts_with("  auto-sync running since "));
+        assert!(lines[2].ends_with(", restarted 1 times"));
+        assert!(lines[3].starts_with("  index-rebuild finished since "));
+
+        assert!(!snapshot.is_stale(start_time() + chrono::Duration::seconds(60)));
+        assert!(snapshot.is_stale(start_time() + chrono::Duration::seconds(61)));
+        let stale = snapshot.describe(start_time() + chrono::Duration::minutes(5));
+        assert!(stale.lines().next().unwrap().ends_with("; the launcher is not running)"));
+    }
+
+    #[tokio::test]
+    async fn snapshots_read_back_from_disk() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = TaskRegistry::default();
+        let _task = registry.track("auto-sync", start_time());
+        let snapshot = TaskSnapshot::capture(&registry, start_time());
+        let path = dir.path().join(TASK_SNAPSHOT_FILE);
+        std::fs::write(&path, serde_json::to_vec(&snapshot).unwrap()).unwrap();
+
+        assert_eq!(TaskSnapshot::read(&path).await, Some(snapshot));
+        std::fs::write(&path, "garbage").unwrap();
+        assert_eq!(TaskSnapshot::read(&path).await, None);
+    }
+}
@@ -0,0 +1,98 @@
+// Stores and engines for unit tests, kept in a temporary directory
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+use chrono::{DateTime, TimeZone, Utc};
+use flate2::Compression;
+use flate2::write::DeflateEncoder;
+use tokio::sync::RwLock;
+
+use falcommand_config::{Config, PathResolver};
+use crate::clock::ManualClock;
+use crate::history::SearchHistory;
+use crate::index::IndexManager;
+use crate::search::SearchEngine;
+use crate::usage::UsageStore;
+
+// A fixed moment, so tests never depend on the date they run on
+pub(crate) fn start_time() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap()
+}
+
+pub(crate) fn manual_clock() -> Arc<ManualClock> {
+    Arc::new(ManualClock::new(start_time()))
+}
+
+// Config, data and cache all under `dir`
+pub(crate) fn resolver(dir: &Path) -> PathResolver {
+    PathResolver::with_roots(dir.join("config"), dir.join("data"), None)
+}
+
+pub(crate) struct TestEngine {
+    pub engine: Arc<SearchEngine>,
+    pub config: Arc<RwLock<Config>>,
+    pub history: Arc<SearchHistory>,
+}
+
+pub(crate) async fn engine(dir: &Path, config: Config) -> TestEngine {
+    let clock = manual_clock();
+    let config = Arc::new(RwLock::new(config));
+    let index = Arc::new(IndexManager::new(config.clone()).await.unwrap());
+    let history = Arc::new(SearchHistory::with_storage_path(dir.join("history.json"), clock.clone()).await.unwrap());
+    let usage = Arc::new(UsageStore::with_storage_path(dir.join("usage.json"), clock.clone()).await.unwrap());
+    let engine = Arc::new(SearchEngine::new(config.clone(), index, history.clone(), usage).await.unwrap());
+    TestEngine { engine, config, history }
+}
+
+// A minimal zip writer for fixtures: `(name, contents)`, names ending in '/' are directories.
+// Members are deflated when `deflate` is set and stored otherwise
+pub(crate) fn write_zip(path: &Path, members: &[(&str, &str)], deflate: bool) {
+    let mut data = Vec::new();
+    let mut directory = Vec::new();
+    for (name, contents) in members {
+        let mut crc = flate2::Crc::new();
+        crc.update(contents.as_bytes());
+        let (method, stored): (u16, Vec<u8>) = if deflate {
+            let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(contents.as_bytes()).unwrap();
+            (8, encoder.finish().unwrap())
+        } else {
+            (0, contents.as_bytes().to_vec())
+        };
+
+        let offset = data.len() as u32;
+        let fields = |header: &mut Vec<u8>| {
+            header.extend_from_slice(&method.to_le_bytes());
+            header.extend_from_slice(&[0; 4]);
+            header.extend_from_slice(&crc.sum().to_le_bytes());
+            header.extend_from_slice(&(stored.len() as u32).to_le_bytes());
+            header.extend_from_slice(&(contents.len() as u32).to_le_bytes());
+            header.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            header.extend_from_slice(&0u16.to_le_bytes());
+        };
+
+        data.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+        data.extend_from_slice(&[20, 0, 0, 0]);
+        fields(&mut data);
+        data.extend_from_slice(name.as_bytes());
+        data.extend_from_slice(&stored);
+
+        directory.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+        directory.extend_from_slice(&[20, 0, 20, 0, 0, 0]);
+        fields(&mut directory);
+        directory.extend_from_slice(&[0; 10]);
+        directory.extend_from_slice(&offset.to_le_bytes());
+        directory.extend_from_slice(name.as_bytes());
+    }
+
+    let mut end = Vec::new();
+    end.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+    end.extend_from_slice(&[0; 4]);
+    end.extend_from_slice(&(members.len() as u16).to_le_bytes());
+    end.extend_from_slice(&(members.len() as u16).to_le_bytes());
+    end.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+    end.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    end.extend_from_slice(&0u16.to_le_bytes());
+
+    std::fs::write(path, [data, directory, end].concat()).unwrap();
+}
@@ -0,0 +1,71 @@
+use std::collections::HashSet;
+use std::path::Path;
+use std::time::Duration;
+use notify::{EventKind, RecursiveMode, Watcher};
+use tokio::sync::mpsc;
+use log::{debug, warn};
+
+// Theme files saved while the launcher runs, so the window can re-read the one it shows
+pub struct ThemeWatch {
+    // Dropping the watcher stops the events
+    _watcher: notify::RecommendedWatcher,
+    changes: mpsc::UnboundedReceiver<String>,
+}
+
+impl ThemeWatch {
+    // Editors save in bursts (write, rename, touch); one reload per save is enough
+    const SETTLE: Duration = Duration::from_millis(150);
+
+    // Watches `themes_dir`, creating it so a theme added later is seen too. None when watching is
+    // unavailable; themes then apply on the next start or settings change
+    pub fn start(themes_dir: &Path) -> Option<Self> {
+        if let Err(e) = std::fs::create_dir_all(themes_dir) {
+            warn!("Failed to create {}: {}", themes_dir.display(), e);
+            return None;
+        }
+        let (sender, changes) = mpsc::unbounded_channel();
+        let watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            match event {
+                Ok(event) if matches!(event.kind, EventKind::Create(_) | EventKind::Remove(_) | EventKind::Modify(_)) => {
+                    for name in event.paths.iter().filter_map(|path| theme_name(path)) {
+                        let _ = sender.send(name);
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => warn!("Theme watcher error: {}", e),
+            }
+        });
+        let mut watcher = match watcher {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                warn!("Theme watching is unavailable, theme edits apply after a restart: {}", e);
+                return None;
+            }
+        };
+        if let Err(e) = watcher.watch(themes_dir, RecursiveMode::NonRecursive) {
+            warn!("Failed to watch {}: {}", themes_dir.display(), e);
+            return None;
+        }
+        debug!("Watching {} for theme changes", themes_dir.display());
+        Some(Self { _watcher: watcher, changes })
+    }
+
+    // Names of the themes changed since the last call, once the save settles; None when the
+    // watcher is gone
+    pub async fn changed(&mut self) -> Option<HashSet<String>> {
+        let mut names = HashSet::from([self.changes.recv().await?]);
+        tokio::time::sleep(Self::SETTLE).await;
+        while let Ok(name) = self.changes.try_recv() {
+            names.insert(name);
+        }
+        Some(names)
+    }
+}
+
+// "dracula" for "themes/dracula.json"; editor backups and other files are not themes
+fn theme_name(path: &Path) -> Option<String> {
+    if path.extension()? != "json" {
+        return None;
+    }
+    Some(path.file_stem()?.to_string_lossy().into_owned())
+}
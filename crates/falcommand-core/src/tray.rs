@@ -0,0 +1,446 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::{broadcast, RwLock};
+use async_trait::async_trait;
+use log::{info, warn};
+
+use falcommand_config::{Action, Category, Config, SearchResult};
+use falcommand_config::i18n::{current_language, Language};
+use falcommand_platform::PlatformProvider;
+use crate::index::{IndexManager, IndexProgress, IndexStats};
+use crate::sync::{SyncManager, SyncStatus};
+use crate::crash::TaskSupervisor;
+use crate::format::{format_count, format_relative_time, format_size};
+use crate::source::SearchSource;
+
+// Long-running work owned by other crates (plugin tasks) that the tooltip should mention
+pub trait BackgroundTasks: Send + Sync {
+    fn task_summaries(&self) -> Vec<String>;
+}
+
+// Builds the multi-line tray tooltip from the current hotkey, index, sync and task state
+pub fn tooltip_text(
+    hotkey: &str,
+    stats: &IndexStats,
+    progress: &IndexProgress,
+    sync: &SyncStatus,
+    tasks: &[String],
+    now: SystemTime,
+    language: Language,
+) -> String {
+    let mut lines = vec![format!("FalCommand ({})", hotkey)];
+
+    if progress.in_progress {
+        lines.push(format!("Indexing… {} items", format_count(progress.items_indexed, language)));
+    } else {
+        let items = format_count(stats.app_count + stats.file_count, language);
+        let mut line = match stats.last_rebuild {
+            Some(last) => format!("{} items · updated {}", items, format_relative_time(last, now, language)),
+            None => format!("{} items · not indexed yet", items),
+        };
+        if stats.excluded_app_count > 0 {
+            line.push_str(&format!(" · {} apps excluded", format_count(stats.excluded_app_count, language)));
+        }
+        if !stats.backed_off_dirs.is_empty() {
+            line.push_str(&format!(" · {} folders skipped", format_count(stats.backed_off_dirs.len(), language)));
+        }
+        lines.push(line);
+    }
+
+    lines.push(sync_line(sync, now, language));
+    lines.extend(tasks.iter().cloned());
+    lines.join("\n")
+}
+
+fn sync_line(sync: &SyncStatus, now: SystemTime, language: Language) -> String {
+    if !sync.enabled {
+        return "Sync off".to_string();
+    }
+    if !sync.pending_conflicts.is_empty() {
+        return format!("Sync: {} conflicts to resolve", sync.pending_conflicts.len());
+    }
+    if let Some(ref error) = sync.last_error {
+        return format!("Sync failed: {:?}", error.kind);
+    }
+    let mut line = match sync.last_sync {
+        Some(last) => format!("Synced {}", format_relative_time(last.into(), now, language)),
+        None => "Not synced yet".to_string(),
+    };
+    if let Some(size) = sync.payload_size {
+        line.push_str(&format!(" · {}", format_size(size as u64, language)));
+    }
+    if sync.skipped_uploads > 0 {
+        line.push_str(&format!(" · {} unchanged uploads skipped", format_count(sync.skipped_uploads as usize, language)));
+    }
+    line
+}
+
+// Limits routine tooltip refreshes; forced updates (events, hotkey changes) always pass
+#[derive(Debug, Clone)]
+pub struct TooltipThrottle {
+    min_interval: Duration,
+    last_update: Option<Instant>,
+    last_text: Option<String>,
+}
+
+impl TooltipThrottle {
+    pub fn new(min_interval: Duration) -> Self {
+        Self {
+            min_interval,
+            last_update: None,
+            last_text: None,
+        }
+    }
+
+    pub fn should_update(&mut self, text: &str, now: Instant, forced: bool) -> bool {
+        if self.last_text.as_deref() == Some(text) {
+            return false;
+        }
+
+        let due = self.last_update
+            .map(|last| now.duration_since(last) >= self.min_interval)
+            .unwrap_or(true);
+        if !forced && !due {
+            return false;
+        }
+
+        self.last_update = Some(now);
+        self.last_text = Some(text.to_string());
+        true
+    }
+}
+
+// Copies a square RGBA icon and draws a filled dot in the bottom-right corner
+pub fn render_progress_badge(icon_data: &[u8], size: usize) -> Vec<u8> {
+    let mut badged = icon_data.to_vec();
+    let radius = (size / 5).max(2) as isize;
+    let center = size as isize - radius - 1;
+
+    for y in 0..size {
+        for x in 0..size {
+            let dx = x as isize - center;
+            let dy = y as isize - center;
+            if dx * dx + dy * dy <= radius * radius {
+                let offset = (y * size + x) * 4;
+                if let Some(pixel) = badged.get_mut(offset..offset + 4) {
+                    pixel.copy_from_slice(&[255, 170, 0, 255]);
+                }
+            }
+        }
+    }
+
+    badged
+}
+
+pub struct TrayStatusUpdater {
+    config: Arc<RwLock<Config>>,
+    platform_provider: Arc<dyn PlatformProvider>,
+    index_manager: Arc<IndexManager>,
+    sync_manager: Arc<SyncManager>,
+    background_tasks: Option<Arc<dyn BackgroundTasks>>,
+    icon_data: Vec<u8>,
+}
+
+impl TrayStatusUpdater {
+    pub const MIN_UPDATE_INTERVAL: Duration = Duration::from_secs(30);
+    const ICON_SIZE: usize = 32;
+
+    pub fn new(
+        config: Arc<RwLock<Config>>,
+        platform_provider: Arc<dyn PlatformProvider>,
+        index_manager: Arc<IndexManager>,
+        sync_manager: Arc<SyncManager>,
+        icon_data: Vec<u8>,
+    ) -> Self {
+        Self {
+            config,
+            platform_provider,
+            index_manager,
+            sync_manager,
+            background_tasks: None,
+            icon_data,
+        }
+    }
+
+    pub fn with_background_tasks(mut self, tasks: Arc<dyn BackgroundTasks>) -> Self {
+        self.background_tasks = Some(tasks);
+        self
+    }
+
+    async fn current_text(&self) -> (String, String) {
+        let hotkey = self.config.read().await.behavior.hotkey.clone();
+        let stats = self.index_manager.get_index_stats().await;
+        let progress = self.index_manager.subscribe_progress().borrow().clone();
+        let sync = self.sync_manager.get_sync_status().await;
+        let tasks = self.background_tasks.as_ref()
+            .map(|tasks| tasks.task_summaries())
+            .unwrap_or_default();
+        let text = tooltip_text(&hotkey, &stats, &progress, &sync, &tasks, SystemTime::now(), current_language());
+        (hotkey, text)
+    }
+
+    fn apply(&self, text: &str) {
+        if let Err(e) = self.platform_provider.set_tray_tooltip(text) {
+            warn!("Failed to update tray tooltip: {}", e);
+        }
+    }
+
+    fn apply_icon(&self, indexing: bool) {
+        let icon = if indexing {
+            render_progress_badge(&self.icon_data, Self::ICON_SIZE)
+        } else {
+            self.icon_data.clone()
+        };
+        if let Err(e) = self.platform_provider.set_tray_icon(&icon) {
+            warn!("Failed to update tray icon: {}", e);
+        }
+    }
+
+    pub fn start(self: Arc<Self>) {
+        info!("Starting tray status updates");
+
+        TaskSupervisor::default().spawn("tray-status", move || {
+            let updater = self.clone();
+            async move {
+                let mut throttle = TooltipThrottle::new(Self::MIN_UPDATE_INTERVAL);
+                let mut progress = updater.index_manager.subscribe_progress();
+                let mut sync_events = updater.sync_manager.subscribe();
+                let mut ticker = tokio::time::interval(Duration::from_secs(5));
+                let mut last_hotkey: Option<String> = None;
+                let mut was_indexing = false;
+
+                loop {
+                    let mut forced = tokio::select! {
+                        _ = ticker.tick() => false,
+                        // Progress ticks during a rebuild are throttled; only completion forces a refresh
+                        changed = progress.changed() => changed.is_ok() && !progress.borrow().in_progress,
+                        event = sync_events.recv() => !matches!(event, Err(broadcast::error::RecvError::Closed)),
+                    };
+
+                    let indexing = progress.borrow().in_progress;
+                    if indexing != was_indexing {
+                        updater.apply_icon(indexing);
+                        was_indexing = indexing;
+                        forced = true;
+                    }
+
+                    let (hotkey, text) = updater.current_text().await;
+                    if last_hotkey.as_deref() != Some(hotkey.as_str()) {
+                        forced = true;
+                        last_hotkey = Some(hotkey);
+                    }
+
+                    if throttle.should_update(&text, Instant::now(), forced) {
+                        updater.apply(&text);
+                    }
+                }
+            }
+        });
+    }
+}
+// How the resident launcher is reached besides the hotkey
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrayMode {
+    Tray,
+    // The tray is wanted but this desktop can't show one (GNOME without AppIndicator): the window
+    // keeps its taskbar entry and a Quit result stands in for the tray menu
+    Fallback,
+    Off,
+}
+
+impl TrayMode {
+    // `wanted`: appearance.enable_system_tray in a mode that has a tray at all
+    pub fn select(wanted: bool, supported: bool) -> Self {
+        match (wanted, supported) {
+            (true, true) => TrayMode::Tray,
+            (true, false) => TrayMode::Fallback,
+            (false, _) => TrayMode::Off,
+        }
+    }
+
+    // The effective state for `falcommand status`
+    pub fn describe(&self, hotkey: &str) -> String {
+        match self {
+            TrayMode::Tray => "System tray: on".to_string(),
+            TrayMode::Fallback => format!("System tray: unavailable on this desktop; {} shows the window", hotkey),
+            TrayMode::Off => "System tray: off".to_string(),
+        }
+    }
+}
+
+// Explains the missing tray once, not on every start
+pub struct TrayFallbackNotice {
+    marker_path: PathBuf,
+}
+
+impl TrayFallbackNotice {
+    pub fn new(marker_path: PathBuf) -> Self {
+        Self { marker_path }
+    }
+
+    // The notification text the first time; records it as shown
+    pub fn take_message(&self, hotkey: &str) -> Option<String> {
+        if self.marker_path.exists() {
+            return None;
+        }
+
+        if let Some(parent) = self.marker_path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        if let Err(e) = std::fs::write(&self.marker_path, "shown") {
+            warn!("Failed to record the tray notice as shown: {}", e);
+        }
+        Some(format!(
+            "This desktop has no system tray. Press {} to open FalCommand, and search \"quit\" to close it.",
+            hotkey
+        ))
+    }
+}
+
+// "Quit FalCommand" for when there is no tray menu to quit from
+pub struct QuitSource;
+
+impl QuitSource {
+    const KEYWORDS: &'static [&'static str] = &["quit falcommand", "exit falcommand"];
+    const MIN_QUERY_LEN: usize = 2;
+}
+
+#[async_trait]
+impl SearchSource for QuitSource {
+    fn name(&self) -> &str {
+        "Quit"
+    }
+
+    async fn search(&self, query: &str) -> Vec<SearchResult> {
+        let query = query.trim().to_lowercase();
+        if query.chars().count() < Self::MIN_QUERY_LEN || !Self::KEYWORDS.iter().any(|keyword| keyword.starts_with(&query)) {
+            return Vec::new();
+        }
+        vec![
+            SearchResult::new("Quit FalCommand", "Close the launcher; the hotkey works again once it is started")
+                .with_action(Action::QuitApp)
+                .with_category(Category::SystemCommand)
+                .with_score(0.9),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sync::{SyncErrorInfo, SyncErrorKind};
+
+    fn stats(app_count: usize, file_count: usize, last_rebuild: Option<SystemTime>) -> IndexStats {
+        IndexStats {
+            app_count,
+            excluded_app_count: 0,
+            file_count,
+            backed_off_dirs: Vec::new(),
+            app_scan_warnings: Vec::new(),
+            last_rebuild,
+            cache_verification: None,
+            marked_stale: false,
+        }
+    }
+
+    fn sync(enabled: bool) -> SyncStatus {
+        SyncStatus {
+            enabled,
+            available_providers: Vec::new(),
+            last_sync: None,
+            last_error: None,
+            pending_conflicts: Vec::new(),
+            next_sync: None,
+            payload_size: None,
+            skipped_uploads: 0,
+        }
+    }
+
+    #[test]
+    fn tooltip_lists_hotkey_index_sync_and_tasks() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_800_000_000);
+        let mut stats = stats(120, 1_380, Some(now - Duration::from_secs(2 * 3600)));
+        stats.excluded_app_count = 3;
+        stats.backed_off_dirs = vec![(PathBuf::from("/mnt/share"), now)];
+        let mut sync = sync(true);
+        sync.last_sync = Some((now - Duration::from_secs(300)).into());
+        sync.payload_size = Some(1536);
+        let tasks = ["Weather: refreshing".to_string()];
+
+        let text = tooltip_text("Alt+Space", &stats, &IndexProgress::default(), &sync, &tasks, now, Language::English);
+        assert_eq!(text, [
+            "FalCommand (Alt+Space)",
+            "1,500 items · updated 2 hours ago · 3 apps excluded · 1 folders skipped",
+            "Synced 5 minutes ago · 1.5 KB",
+            "Weather: refreshing",
+        ].join("\n"));
+    }
+
+    #[test]
+    fn tooltip_shows_indexing_progress_and_sync_trouble() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_800_000_000);
+        let progress = IndexProgress { in_progress: true, items_indexed: 42 };
+        let mut failed = sync(true);
+        failed.last_error = Some(SyncErrorInfo {
+            kind: SyncErrorKind::Network,
+            message: "offline".to_string(),
+            occurred_at: now.into(),
+        });
+
+        let text = tooltip_text("Ctrl+K", &stats(0, 0, None), &progress, &failed, &[], now, Language::English);
+        assert_eq!(text, "FalCommand (Ctrl+K)\nIndexing… 42 items\nSync failed: Network");
+
+        let text = tooltip_text("Ctrl+K", &stats(0, 0, None), &IndexProgress::default(), &sync(false), &[], now, Language::English);
+        assert_eq!(text, "FalCommand (Ctrl+K)\n0 items · not indexed yet\nSync off");
+    }
+
+    #[test]
+    fn throttle_holds_routine_updates_but_not_forced_ones() {
+        let start = Instant::now();
+        let mut throttle = TooltipThrottle::new(Duration::from_secs(30));
+
+        assert!(throttle.should_update("a", start, false));
+        // Unchanged text is never pushed again, even when forced
+        assert!(!throttle.should_update("a", start + Duration::from_secs(60), true));
+        assert!(!throttle.should_update("b", start + Duration::from_secs(10), false));
+        assert!(throttle.should_update("b", start + Duration::from_secs(10), true));
+        assert!(!throttle.should_update("c", start + Duration::from_secs(39), false));
+        assert!(throttle.should_update("c", start + Duration::from_secs(40), false));
+    }
+
+    #[test]
+    fn tray_mode_matrix() {
+        assert_eq!(TrayMode::select(true, true), TrayMode::Tray);
+        assert_eq!(TrayMode::select(true, false), TrayMode::Fallback);
+        assert_eq!(TrayMode::select(false, true), TrayMode::Off);
+        assert_eq!(TrayMode::select(false, false), TrayMode::Off);
+
+        assert_eq!(TrayMode::Tray.describe("Alt+Space"), "System tray: on");
+        assert_eq!(TrayMode::Fallback.describe("Alt+Space"), "System tray: unavailable on this desktop; Alt+Space shows the window");
+        assert_eq!(TrayMode::Off.describe("Alt+Space"), "System tray: off");
+    }
+
+    #[test]
+    fn fallback_notice_is_shown_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let notice = TrayFallbackNotice::new(dir.path().join("state").join("tray_notice"));
+        let message = notice.take_message("Alt+Space").unwrap();
+        assert!(message.contains("Press Alt+Space to open FalCommand"), "{}", message);
+        assert_eq!(notice.take_message("Alt+Space"), None);
+        assert_eq!(TrayFallbackNotice::new(dir.path().join("state").join("tray_notice")).take_message("Alt+Space"), None);
+    }
+
+    #[tokio::test]
+    async fn quit_result_matches_its_keywords() {
+        for query in ["quit", "Exit Fal", "quit falcommand"] {
+            let results = QuitSource.search(query).await;
+            assert_eq!(results.len(), 1, "{}", query);
+            assert_eq!(results[0].title, "Quit FalCommand");
+            assert_eq!(results[0].action, Action::QuitApp);
+        }
+        for query in ["q", "quite", "falcommand", "quit firefox"] {
+            assert!(QuitSource.search(query).await.is_empty(), "{}", query);
+        }
+    }
+}
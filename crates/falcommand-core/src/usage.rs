@@ -0,0 +1,479 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::{watch, RwLock};
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use log::{info, warn};
+
+use falcommand_config::{result_id, Action, Category, PathResolver, ResultId, SearchResult};
+use falcommand_platform::AppInfo;
+use crate::clock::Clock;
+use crate::selections::SelectionAssociations;
+use crate::moves::relocated_path;
+use crate::open_with::OpenWithAssociations;
+use crate::usage_sync::{merge_usage_records, AppIdentities, SyncedUsageRecord};
+
+#[derive(Debug, thiserror::Error)]
+pub enum UsageError {
+    #[error("Failed to access usage data: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Failed to serialize usage data: {0}")]
+    SerializationError(#[from] serde_json::Error),
+
+    #[error("Other usage error: {0}")]
+    Other(String),
+}
+
+// One launch of a result, kept raw so stats can be recomputed for any window
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageRecord {
+    // Records written before ids were kept read as the default id and can't be run by id
+    #[serde(default)]
+    pub id: ResultId,
+    pub title: String,
+    pub path: Option<PathBuf>,
+    pub category: Category,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl UsageRecord {
+    // Launches of the same item are grouped by path, falling back to the title
+    pub fn item_key(&self) -> String {
+        match self.path {
+            Some(ref path) => path.to_string_lossy().into_owned(),
+            None => self.title.clone(),
+        }
+    }
+}
+
+pub fn category_label(category: &Category) -> String {
+    match category {
+        Category::Application => "application".to_string(),
+        Category::File => "file".to_string(),
+        Category::Bookmark => "bookmark".to_string(),
+        Category::Plugin(name) => format!("plugin:{}", name.to_lowercase()),
+        Category::SystemCommand => "system".to_string(),
+        Category::CustomCommand => "custom".to_string(),
+    }
+}
+
+// A head start for a common app on a fresh install, so early ranking isn't purely textual.
+// It decays like a launch made at `seeded_at`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageSeed {
+    pub id: ResultId,
+    pub title: String,
+    pub weight: f64,
+    pub seeded_at: DateTime<Utc>,
+}
+
+// Everything persisted in usage_stats.json
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UsageState {
+    pub records: Vec<UsageRecord>,
+    #[serde(default)]
+    pub selections: SelectionAssociations,
+    #[serde(default)]
+    pub open_with: OpenWithAssociations,
+    #[serde(default)]
+    pub seeds: Vec<UsageSeed>,
+    // Seeding happens once; a store that was seeded or cleared is never seeded again
+    #[serde(default)]
+    pub seeded: bool,
+}
+
+// Weight of a launch `age` ago: 1 when new, halving every `half_life_days`
+pub fn decay_weight(age: chrono::Duration, half_life_days: f64) -> f64 {
+    let age_days = age.num_seconds().max(0) as f64 / 86_400.0;
+    0.5f64.powf(age_days / half_life_days)
+}
+
+// Frecency per result id: every launch adds its decayed weight, so new habits overtake old ones
+// and an abandoned one fades. Worked out from the raw records at `now`, nothing stored changes
+pub fn usage_scores(state: &UsageState, now: DateTime<Utc>, half_life_days: f64) -> HashMap<ResultId, f64> {
+    let mut scores = HashMap::new();
+    for record in state.records.iter().filter(|record| record.id != ResultId::default()) {
+        *scores.entry(record.id).or_insert(0.0) += decay_weight(now - record.timestamp, half_life_days);
+    }
+    for seed in &state.seeds {
+        *scores.entry(seed.id).or_insert(0.0) += seed.weight * decay_weight(now - seed.seeded_at, half_life_days);
+    }
+    scores
+}
+
+// Seeds for the indexed apps named in `names` (ignoring case), each app once
+pub fn seed_candidates(apps: &[AppInfo], names: &[&str], now: DateTime<Utc>) -> Vec<UsageSeed> {
+    let mut seeds: Vec<UsageSeed> = Vec::new();
+    for name in names {
+        for app in apps.iter().filter(|app| app.name.eq_ignore_ascii_case(name)) {
+            let id = app.to_search_result().id;
+            if seeds.iter().any(|seed| seed.id == id) {
+                continue;
+            }
+            seeds.push(UsageSeed { id, title: app.name.clone(), weight: UsageStore::SEED_WEIGHT, seeded_at: now });
+        }
+    }
+    seeds
+}
+
+// Stores written before selection learning existed hold a bare list of records
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredUsage {
+    Current(UsageState),
+    Legacy(Vec<UsageRecord>),
+}
+
+#[derive(Debug)]
+pub struct UsageStore {
+    storage_path: PathBuf,
+    clock: Arc<dyn Clock>,
+    state: RwLock<UsageState>,
+    // Bumped after every write, for listeners such as the jump list
+    changes: watch::Sender<u64>,
+}
+
+impl UsageStore {
+    pub const MAX_RECORDS: usize = 20_000;
+    // Half of one launch, so the first real launch of anything outranks every seed
+    pub const SEED_WEIGHT: f64 = 0.5;
+
+    pub async fn new(resolver: &PathResolver, clock: Arc<dyn Clock>) -> std::result::Result<Self, UsageError> {
+        let storage_path = resolver.data_file("usage_stats.json");
+
+        Self::with_storage_path(storage_path, clock).await
+    }
+
+    pub async fn with_storage_path(
+        storage_path: PathBuf,
+        clock: Arc<dyn Clock>,
+    ) -> std::result::Result<Self, UsageError> {
+        let state = Self::load_state(&storage_path).await;
+        info!("Loaded {} usage records", state.records.len());
+
+        Ok(Self {
+            storage_path,
+            clock,
+            state: RwLock::new(state),
+            changes: watch::channel(0).0,
+        })
+    }
+
+    async fn load_state(path: &Path) -> UsageState {
+        if !path.exists() {
+            return UsageState::default();
+        }
+
+        match tokio::fs::read_to_string(path).await {
+            Ok(content) => match serde_json::from_str(&content) {
+                Ok(StoredUsage::Current(state)) => state,
+                Ok(StoredUsage::Legacy(records)) => UsageState { records, ..UsageState::default() },
+                Err(e) => {
+                    warn!("Ignoring unreadable usage data {:?}: {}", path, e);
+                    UsageState::default()
+                }
+            },
+            Err(e) => {
+                warn!("Failed to read usage data {:?}: {}", path, e);
+                UsageState::default()
+            }
+        }
+    }
+
+    async fn persist(&self, state: &UsageState) -> std::result::Result<(), UsageError> {
+        if let Some(parent) = self.storage_path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        let content = serde_json::to_string(state)?;
+        let tmp_path = self.storage_path.with_extension("json.tmp");
+        tokio::fs::write(&tmp_path, content).await?;
+        tokio::fs::rename(&tmp_path, &self.storage_path).await?;
+        self.changes.send_modify(|version| *version += 1);
+        Ok(())
+    }
+
+    pub fn now(&self) -> DateTime<Utc> {
+        self.clock.now()
+    }
+
+    pub async fn record(&self, result: &SearchResult) -> std::result::Result<(), UsageError> {
+        let mut state = self.state.write().await;
+        let records = &mut state.records;
+        records.push(UsageRecord {
+            id: result.id,
+            title: result.title.clone(),
+            path: result.path.clone(),
+            category: result.category.clone(),
+            timestamp: self.clock.now(),
+        });
+
+        if records.len() > Self::MAX_RECORDS {
+            let overflow = records.len() - Self::MAX_RECORDS;
+            records.drain(..overflow);
+        }
+
+        self.persist(&state).await
+    }
+
+    pub fn subscribe_changes(&self) -> watch::Receiver<u64> {
+        self.changes.subscribe()
+    }
+
+    // Decayed launches per result id, plus the seeds; records from before ids were kept are not counted
+    pub async fn usage_scores(&self, half_life_days: f64) -> HashMap<ResultId, f64> {
+        usage_scores(&*self.state.read().await, self.clock.now(), half_life_days)
+    }
+
+    // On a fresh install, seeds the common apps (by the platform's `names`) found in the app index.
+    // Skipped once there are launches or seeding was done; returns the number of apps seeded
+    pub async fn seed_common_apps(&self, apps: &[AppInfo], names: &[&str]) -> std::result::Result<usize, UsageError> {
+        let mut state = self.state.write().await;
+        // An index that isn't built yet would leave nothing to seed for good
+        if state.seeded || !state.records.is_empty() || apps.is_empty() {
+            return Ok(0);
+        }
+        state.seeds = seed_candidates(apps, names, self.clock.now());
+        state.seeded = true;
+        self.persist(&state).await?;
+        Ok(state.seeds.len())
+    }
+
+    pub async fn records(&self) -> Vec<UsageRecord> {
+        self.state.read().await.records.clone()
+    }
+
+    // Learns that `id` was picked for `query`, for the ranking bonus
+    pub async fn record_selection(&self, query: &str, id: ResultId) -> std::result::Result<(), UsageError> {
+        let mut state = self.state.write().await;
+        state.selections.record(query, id, self.clock.now());
+        self.persist(&state).await
+    }
+
+    pub async fn selection_bonuses(&self, query: &str) -> HashMap<ResultId, f64> {
+        self.state.read().await.selections.bonuses(query, self.clock.now())
+    }
+
+    pub async fn selections(&self) -> SelectionAssociations {
+        self.state.read().await.selections.clone()
+    }
+
+    // Learns that files with `extension` were opened with `app_name`, for Open with…
+    pub async fn record_open_with(&self, extension: &str, app_name: &str) -> std::result::Result<(), UsageError> {
+        let mut state = self.state.write().await;
+        state.open_with.record(extension, app_name);
+        self.persist(&state).await
+    }
+
+    pub async fn open_with_counts(&self, extension: &str) -> HashMap<String, u32> {
+        self.state.read().await.open_with.counts(extension)
+    }
+
+    // Sync import: `replace` takes the remote associations as they are, otherwise they are merged
+    pub async fn import_selections(&self, remote: &SelectionAssociations, replace: bool) -> std::result::Result<(), UsageError> {
+        let mut state = self.state.write().await;
+        if replace {
+            state.selections = remote.clone();
+            state.selections.prune(self.clock.now());
+        } else {
+            state.selections.merge(remote, self.clock.now());
+        }
+        self.persist(&state).await
+    }
+
+    // Sync import of launches from other machines: `replace` keeps only the remote ones, otherwise
+    // they are added to the local ones. Launches of apps installed here count for the local app
+    pub async fn import_records(
+        &self,
+        remote: Vec<SyncedUsageRecord>,
+        identities: &AppIdentities,
+        replace: bool,
+    ) -> std::result::Result<(), UsageError> {
+        let mut state = self.state.write().await;
+        let local = if replace { Vec::new() } else { std::mem::take(&mut state.records) };
+        state.records = merge_usage_records(&local, remote, identities, Self::MAX_RECORDS);
+        self.persist(&state).await
+    }
+
+    // Moves launches of `from` (or of anything below it, for a folder) to `to`. Ids found in `ids`
+    // are replaced; other moved files get the id of their new path. Returns the number of records moved
+    pub async fn migrate_path(
+        &self,
+        from: &Path,
+        to: &Path,
+        ids: &HashMap<ResultId, ResultId>,
+    ) -> std::result::Result<usize, UsageError> {
+        let mut state = self.state.write().await;
+        let mut renamed = ids.clone();
+        let mut moved = 0;
+        for record in state.records.iter_mut() {
+            let Some(path) = record.path.as_deref().and_then(|path| relocated_path(path, from, to)) else {
+                continue;
+            };
+
+            if record.path.as_deref() == Some(from) && from.file_name().is_some_and(|name| *name == *record.title) {
+                record.title = to.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+            }
+            let id = match ids.get(&record.id) {
+                Some(&id) => id,
+                None if record.category == Category::File => result_id(&Category::File, &Action::OpenFile(path.clone())),
+                None => record.id,
+            };
+            if record.id != ResultId::default() && record.id != id {
+                renamed.insert(record.id, id);
+                record.id = id;
+            }
+            record.path = Some(path);
+            moved += 1;
+        }
+
+        if moved == 0 {
+            return Ok(0);
+        }
+        state.selections.rename_ids(&renamed);
+        self.persist(&state).await?;
+        Ok(moved)
+    }
+
+    // Drops launches of a deleted file, or of everything in a deleted folder
+    pub async fn forget_path(&self, path: &Path) -> std::result::Result<usize, UsageError> {
+        let mut state = self.state.write().await;
+        let before = state.records.len();
+        state.records.retain(|record| !record.path.as_deref().is_some_and(|recorded| recorded.starts_with(path)));
+        let removed = before - state.records.len();
+
+        if removed == 0 {
+            return Ok(0);
+        }
+        self.persist(&state).await?;
+        Ok(removed)
+    }
+
+    pub async fn snapshot(&self) -> UsageState {
+        self.state.read().await.clone()
+    }
+
+    pub async fn clear(&self) -> std::result::Result<(), UsageError> {
+        self.restore(UsageState { seeded: true, ..UsageState::default() }).await
+    }
+
+    // Replaces the records and selections; used to roll back a clear that could not be completed
+    pub async fn restore(&self, state: UsageState) -> std::result::Result<(), UsageError> {
+        let mut current = self.state.write().await;
+        *current = state;
+        self.persist(&current).await
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{manual_clock, start_time};
+
+    fn days(count: i64) -> chrono::Duration {
+        chrono::Duration::days(count)
+    }
+
+    fn close(actual: f64, expected: f64) -> bool {
+        (actual - expected).abs() < 1e-9
+    }
+
+    fn app(name: &str) -> AppInfo {
+        AppInfo::new(name, PathBuf::from(format!("/opt/{}/bin", name.to_lowercase())))
+    }
+
+    #[test]
+    fn launches_halve_every_half_life() {
+        for (age, weight) in [(0, 1.0), (15, 0.5f64.sqrt()), (30, 0.5), (60, 0.25), (90, 0.125), (300, 0.5f64.powi(10))] {
+            assert!(close(decay_weight(days(age), 30.0), weight), "{} days", age);
+        }
+        assert!(close(decay_weight(days(7), 7.0), 0.5));
+        // A launch "from the future" (a clock that went back) counts as new
+        assert!(close(decay_weight(days(-3), 30.0), 1.0));
+    }
+
+    // Reading scores changes nothing stored, so reading again at the same time gives the same scores
+    #[tokio::test]
+    async fn decay_is_worked_out_at_read_time() {
+        let dir = tempfile::tempdir().unwrap();
+        let clock = manual_clock();
+        let store = UsageStore::with_storage_path(dir.path().join("usage.json"), clock.clone()).await.unwrap();
+        let firefox = app("Firefox").to_search_result();
+        store.record(&firefox).await.unwrap();
+        clock.advance(days(30));
+        store.record(&firefox).await.unwrap();
+        let stored = std::fs::read_to_string(dir.path().join("usage.json")).unwrap();
+
+        clock.advance(days(30));
+        let first = store.usage_scores(30.0).await;
+        assert!(close(first[&firefox.id], 0.25 + 0.5));
+        assert_eq!(store.usage_scores(30.0).await, first);
+        assert_eq!(std::fs::read_to_string(dir.path().join("usage.json")).unwrap(), stored);
+
+        // Later reads decay from the raw records, not from an earlier read
+        clock.advance(days(30));
+        assert!(close(store.usage_scores(30.0).await[&firefox.id], 0.125 + 0.25));
+        assert!(close(store.usage_scores(60.0).await[&firefox.id], 0.5 + 0.5f64.powf(1.5)));
+    }
+
+    #[test]
+    fn records_without_an_id_are_not_scored() {
+        let record = |id| UsageRecord { id, title: "Firefox".to_string(), path: None, category: Category::Application, timestamp: start_time() };
+        let state = UsageState { records: vec![record(ResultId::default()), record(ResultId(7))], ..UsageState::default() };
+        let scores = usage_scores(&state, start_time(), 30.0);
+        assert_eq!(scores.len(), 1);
+        assert!(close(scores[&ResultId(7)], 1.0));
+    }
+
+    // Only names found in the index are seeded, ignoring case and once per app
+    #[test]
+    fn only_installed_apps_are_seeded() {
+        let apps = [app("Firefox"), app("Terminal"), app("Blender")];
+        let seeds = seed_candidates(&apps, &["firefox", "Google Chrome", "Terminal", "Firefox"], start_time());
+        let titles: Vec<&str> = seeds.iter().map(|seed| seed.title.as_str()).collect();
+        assert_eq!(titles, ["Firefox", "Terminal"]);
+        assert_eq!(seeds[0].id, apps[0].to_search_result().id);
+        assert!(seeds.iter().all(|seed| seed.weight < 1.0 && seed.seeded_at == start_time()));
+    }
+
+    #[tokio::test]
+    async fn seeding_happens_once_and_real_launches_overtake_it() {
+        let dir = tempfile::tempdir().unwrap();
+        let clock = manual_clock();
+        let store = UsageStore::with_storage_path(dir.path().join("usage.json"), clock.clone()).await.unwrap();
+        let (firefox, blender) = (app("Firefox"), app("Blender"));
+
+        // Nothing indexed yet: wait for the index rather than seeding nothing for good
+        assert_eq!(store.seed_common_apps(&[], &["Firefox"]).await.unwrap(), 0);
+        assert_eq!(store.seed_common_apps(&[firefox.clone(), blender.clone()], &["Firefox"]).await.unwrap(), 1);
+        assert_eq!(store.seed_common_apps(std::slice::from_ref(&firefox), &["Firefox"]).await.unwrap(), 0);
+
+        let firefox_id = firefox.to_search_result().id;
+        let scores = store.usage_scores(30.0).await;
+        assert!(close(scores[&firefox_id], UsageStore::SEED_WEIGHT));
+        assert!(!scores.contains_key(&blender.to_search_result().id));
+
+        // One real launch, even a month old, outranks a fresh seed
+        store.record(&blender.to_search_result()).await.unwrap();
+        clock.advance(days(29));
+        let scores = store.usage_scores(30.0).await;
+        assert!(scores[&blender.to_search_result().id] > scores[&firefox_id]);
+
+        // Survives a reload; a cleared store is never seeded again
+        let reloaded = UsageStore::with_storage_path(dir.path().join("usage.json"), clock.clone()).await.unwrap();
+        assert_eq!(reloaded.snapshot().await.seeds.len(), 1);
+        reloaded.clear().await.unwrap();
+        assert_eq!(reloaded.seed_common_apps(&[firefox], &["Firefox"]).await.unwrap(), 0);
+    }
+
+    // A store with launches from before seeding existed is not seeded
+    #[tokio::test]
+    async fn stores_with_launches_are_not_seeded() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = UsageStore::with_storage_path(dir.path().join("usage.json"), manual_clock()).await.unwrap();
+        store.record(&app("Blender").to_search_result()).await.unwrap();
+        assert_eq!(store.seed_common_apps(&[app("Firefox")], &["Firefox"]).await.unwrap(), 0);
+        assert!(store.snapshot().await.seeds.is_empty());
+    }
+}
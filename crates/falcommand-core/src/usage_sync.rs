@@ -0,0 +1,112 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use falcommand_config::Category;
+use falcommand_platform::AppInfo;
+use crate::usage::UsageRecord;
+
+// A launch as it travels between machines: the local record plus the app's portable identity,
+// so launches of the same app merge although its path differs on each machine
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyncedUsageRecord {
+    #[serde(flatten)]
+    pub record: UsageRecord,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity: Option<String>,
+}
+
+// The indexed applications by path and by identity
+#[derive(Debug, Clone, Default)]
+pub struct AppIdentities {
+    by_path: HashMap<PathBuf, String>,
+    by_identity: HashMap<String, AppInfo>,
+}
+
+impl AppIdentities {
+    // The first app of an identity is the one remote launches are credited to
+    pub fn new(apps: &[AppInfo]) -> Self {
+        let mut identities = Self::default();
+        for app in apps {
+            let Some(ref identity) = app.identity else {
+                continue;
+            };
+            identities.by_path.insert(app.executable_path.clone(), identity.clone());
+            identities.by_identity.entry(identity.clone()).or_insert_with(|| app.clone());
+        }
+        identities
+    }
+
+    pub fn identity_of(&self, path: &Path) -> Option<&str> {
+        self.by_path.get(path).map(String::as_str)
+    }
+
+    pub fn local_app(&self, identity: &str) -> Option<&AppInfo> {
+        self.by_identity.get(identity)
+    }
+
+    // Files and apps without an identity keep only their path
+    fn record_identity(&self, record: &UsageRecord) -> Option<String> {
+        if record.category != Category::Application {
+            return None;
+        }
+        record.path.as_deref().and_then(|path| self.identity_of(path)).map(String::from)
+    }
+}
+
+pub fn sync_usage_records(records: &[UsageRecord], identities: &AppIdentities) -> Vec<SyncedUsageRecord> {
+    records.iter()
+        .map(|record| SyncedUsageRecord {
+            identity: identities.record_identity(record),
+            record: record.clone(),
+        })
+        .collect()
+}
+
+// Launches are the same launch when they have the same key and time
+fn merge_key(record: &UsageRecord, identity: Option<&str>) -> String {
+    match identity {
+        Some(identity) => format!("app:{}", identity),
+        None => record.item_key(),
+    }
+}
+
+// A remote launch of an app installed here is credited to the local app: its path, id and name.
+// Anything else is kept as it came
+pub fn localize_usage_record(remote: SyncedUsageRecord, identities: &AppIdentities) -> UsageRecord {
+    let mut record = remote.record;
+    if let Some(app) = remote.identity.as_deref().and_then(|identity| identities.local_app(identity)) {
+        record.id = app.to_search_result().id;
+        record.title = app.name.clone();
+        record.path = Some(app.executable_path.clone());
+    }
+    record
+}
+
+// Local launches plus the remote ones not already among them, oldest first and at most `limit`.
+// Launches that round-trip back from another machine are recognized by identity and time
+pub fn merge_usage_records(
+    local: &[UsageRecord],
+    remote: Vec<SyncedUsageRecord>,
+    identities: &AppIdentities,
+    limit: usize,
+) -> Vec<UsageRecord> {
+    let mut seen: HashSet<(String, DateTime<Utc>)> = local.iter()
+        .map(|record| (merge_key(record, identities.record_identity(record).as_deref()), record.timestamp))
+        .collect();
+
+    let mut merged = local.to_vec();
+    for synced in remote {
+        let key = (merge_key(&synced.record, synced.identity.as_deref()), synced.record.timestamp);
+        if seen.insert(key) {
+            merged.push(localize_usage_record(synced, identities));
+        }
+    }
+
+    merged.sort_by_key(|record| record.timestamp);
+    if merged.len() > limit {
+        merged.drain(..merged.len() - limit);
+    }
+    merged
+}
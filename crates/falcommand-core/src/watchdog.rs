@@ -0,0 +1,326 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use log::{info, warn};
+
+use falcommand_config::WatchdogConfig;
+use falcommand_platform::PlatformProvider;
+use crate::crash::TaskSupervisor;
+use crate::metrics::LatencyMetrics;
+
+// How long the UI event loop went without a heartbeat, recorded once it responds again
+pub const UI_STALL: &str = "ui_stall";
+
+// Time since the UI event loop last reported in; faked in tests
+pub trait HeartbeatSource: Send + Sync {
+    fn since_last_beat(&self) -> Duration;
+
+    // Where the UI thread is stuck, when the platform can tell
+    fn stack(&self) -> Option<String> {
+        None
+    }
+}
+
+// Updated from a timer inside the UI event loop, so it stops moving when the loop does
+#[derive(Debug)]
+pub struct Heartbeat {
+    origin: Instant,
+    last_beat_ms: AtomicU64,
+    // "/proc/<pid>/task/<tid>" of the UI thread, for its kernel stack
+    thread: Mutex<Option<std::path::PathBuf>>,
+}
+
+impl Heartbeat {
+    pub fn new() -> Self {
+        Self {
+            origin: Instant::now(),
+            last_beat_ms: AtomicU64::new(0),
+            thread: Mutex::new(None),
+        }
+    }
+
+    pub fn beat(&self) {
+        let elapsed = self.origin.elapsed().as_millis() as u64;
+        self.last_beat_ms.store(elapsed, Ordering::Relaxed);
+
+        let mut thread = self.thread.lock().unwrap();
+        if thread.is_none() {
+            *thread = Self::current_thread();
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    fn current_thread() -> Option<std::path::PathBuf> {
+        std::fs::read_link("/proc/thread-self").ok()
+            .map(|path| std::path::Path::new("/proc").join(path))
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn current_thread() -> Option<std::path::PathBuf> {
+        None
+    }
+}
+
+impl Default for Heartbeat {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HeartbeatSource for Heartbeat {
+    fn since_last_beat(&self) -> Duration {
+        let last_beat = Duration::from_millis(self.last_beat_ms.load(Ordering::Relaxed));
+        self.origin.elapsed().saturating_sub(last_beat)
+    }
+
+    // The kernel stack needs elevated permissions; the wait channel is readable by the owner
+    fn stack(&self) -> Option<String> {
+        let thread = self.thread.lock().unwrap().clone()?;
+        ["stack", "wchan"].iter()
+            .filter_map(|file| std::fs::read_to_string(thread.join(file)).ok())
+            .map(|content| content.trim().to_string())
+            .find(|content| !content.is_empty() && content != "0")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StallEvent {
+    // No heartbeat for longer than the threshold; reported once per stall
+    Stalled(Duration),
+    // Heartbeats resumed after a reported stall, which lasted at least this long
+    Recovered(Duration),
+}
+
+#[derive(Debug)]
+pub struct StallDetector {
+    threshold: Duration,
+    longest: Option<Duration>,
+}
+
+impl StallDetector {
+    pub fn new(threshold: Duration) -> Self {
+        Self { threshold, longest: None }
+    }
+
+    pub fn threshold(&self) -> Duration {
+        self.threshold
+    }
+
+    pub fn is_stalled(&self) -> bool {
+        self.longest.is_some()
+    }
+
+    pub fn check(&mut self, source: &dyn HeartbeatSource) -> Option<StallEvent> {
+        let silence = source.since_last_beat();
+        match self.longest {
+            None if silence > self.threshold => {
+                self.longest = Some(silence);
+                Some(StallEvent::Stalled(silence))
+            }
+            None => None,
+            Some(longest) if silence > self.threshold => {
+                self.longest = Some(longest.max(silence));
+                None
+            }
+            Some(longest) => {
+                self.longest = None;
+                Some(StallEvent::Recovered(longest))
+            }
+        }
+    }
+}
+
+// Restarts the UI once it has stalled `max_stalls` times within `window`
+#[derive(Debug)]
+pub struct RestartCoordinator {
+    enabled: bool,
+    max_stalls: usize,
+    window: Duration,
+    stalls: VecDeque<Instant>,
+}
+
+impl RestartCoordinator {
+    pub const MAX_STALLS: usize = 3;
+    pub const WINDOW: Duration = Duration::from_secs(10 * 60);
+
+    pub fn new(enabled: bool) -> Self {
+        Self::with_limits(enabled, Self::MAX_STALLS, Self::WINDOW)
+    }
+
+    pub fn with_limits(enabled: bool, max_stalls: usize, window: Duration) -> Self {
+        Self { enabled, max_stalls, window, stalls: VecDeque::new() }
+    }
+
+    // True when this stall should restart the UI; the count starts over after a restart
+    pub fn record_stall(&mut self, now: Instant) -> bool {
+        while let Some(&oldest) = self.stalls.front() {
+            if now.duration_since(oldest) > self.window {
+                self.stalls.pop_front();
+            } else {
+                break;
+            }
+        }
+        self.stalls.push_back(now);
+
+        if self.enabled && self.stalls.len() >= self.max_stalls {
+            self.stalls.clear();
+            return true;
+        }
+        false
+    }
+}
+
+// Watches the UI heartbeat: logs and notifies on stalls, records them and restarts the UI if configured
+pub struct UiWatchdog {
+    heartbeat: Arc<dyn HeartbeatSource>,
+    platform_provider: Arc<dyn PlatformProvider>,
+    metrics: Arc<LatencyMetrics>,
+    config: WatchdogConfig,
+    restart: Option<Arc<dyn Fn() + Send + Sync>>,
+}
+
+impl UiWatchdog {
+    pub fn new(
+        heartbeat: Arc<dyn HeartbeatSource>,
+        platform_provider: Arc<dyn PlatformProvider>,
+        metrics: Arc<LatencyMetrics>,
+        config: WatchdogConfig,
+    ) -> Self {
+        Self { heartbeat, platform_provider, metrics, config, restart: None }
+    }
+
+    // Called from the watchdog task when repeated stalls call for a new window
+    pub fn with_restart(mut self, restart: Arc<dyn Fn() + Send + Sync>) -> Self {
+        self.restart = Some(restart);
+        self
+    }
+
+    pub fn start(self: Arc<Self>) {
+        let threshold = Duration::from_millis(self.config.stall_threshold_ms);
+        TaskSupervisor::default().spawn("ui-watchdog", move || {
+            let watchdog = self.clone();
+            async move {
+                let mut detector = StallDetector::new(threshold);
+                let mut restarts = RestartCoordinator::new(watchdog.config.auto_restart_ui);
+                let mut interval = tokio::time::interval(threshold / 4);
+                interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+                loop {
+                    interval.tick().await;
+                    match detector.check(watchdog.heartbeat.as_ref()) {
+                        Some(StallEvent::Stalled(silence)) => {
+                            watchdog.report_stall(silence);
+                            if restarts.record_stall(Instant::now()) {
+                                watchdog.request_restart();
+                            }
+                        }
+                        Some(StallEvent::Recovered(longest)) => {
+                            info!("UI event loop responding again after {:?}", longest);
+                            watchdog.metrics.record(UI_STALL, longest);
+                        }
+                        None => {}
+                    }
+                }
+            }
+        });
+    }
+
+    fn report_stall(&self, silence: Duration) {
+        match self.heartbeat.stack() {
+            Some(stack) => warn!("UI event loop has not responded for {:?}; UI thread at:\n{}", silence, stack),
+            None => warn!("UI event loop has not responded for {:?}", silence),
+        }
+
+        let message = format!("The window has not responded for {} seconds", silence.as_secs());
+        if let Err(e) = self.platform_provider.show_notification("FalCommand is not responding", &message) {
+            warn!("Failed to show stall notification: {}", e);
+        }
+    }
+
+    fn request_restart(&self) {
+        match self.restart {
+            Some(ref restart) => {
+                warn!("UI event loop stalled repeatedly, restarting the window");
+                restart();
+            }
+            None => warn!("UI event loop stalled repeatedly, but no restart is available"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A heartbeat whose silence the test sets
+    #[derive(Default)]
+    struct FakeHeartbeat {
+        silence: Mutex<Duration>,
+    }
+
+    impl FakeHeartbeat {
+        fn silent_for(&self, millis: u64) {
+            *self.silence.lock().unwrap() = Duration::from_millis(millis);
+        }
+    }
+
+    impl HeartbeatSource for FakeHeartbeat {
+        fn since_last_beat(&self) -> Duration {
+            *self.silence.lock().unwrap()
+        }
+    }
+
+    #[test]
+    fn stalls_are_reported_once_and_recover_with_the_longest_silence() {
+        let heartbeat = FakeHeartbeat::default();
+        let mut detector = StallDetector::new(Duration::from_millis(2000));
+
+        heartbeat.silent_for(2000);
+        assert_eq!(detector.check(&heartbeat), None);
+
+        heartbeat.silent_for(2500);
+        assert_eq!(detector.check(&heartbeat), Some(StallEvent::Stalled(Duration::from_millis(2500))));
+        assert!(detector.is_stalled());
+        // Still stuck: nothing new to report, but the longest silence grows
+        heartbeat.silent_for(6000);
+        assert_eq!(detector.check(&heartbeat), None);
+        heartbeat.silent_for(4000);
+        assert_eq!(detector.check(&heartbeat), None);
+
+        heartbeat.silent_for(10);
+        assert_eq!(detector.check(&heartbeat), Some(StallEvent::Recovered(Duration::from_millis(6000))));
+        assert!(!detector.is_stalled());
+        assert_eq!(detector.check(&heartbeat), None);
+
+        // A later stall is reported again
+        heartbeat.silent_for(3000);
+        assert_eq!(detector.check(&heartbeat), Some(StallEvent::Stalled(Duration::from_millis(3000))));
+    }
+
+    #[test]
+    fn restarts_follow_repeated_stalls_within_the_window() {
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+        let mut restarts = RestartCoordinator::with_limits(true, 3, Duration::from_secs(600));
+
+        assert!(!restarts.record_stall(at(0)));
+        assert!(!restarts.record_stall(at(100)));
+        assert!(restarts.record_stall(at(200)));
+        // The count starts over after a restart
+        assert!(!restarts.record_stall(at(300)));
+        assert!(!restarts.record_stall(at(400)));
+
+        // By 1100 the stalls at 300 and 400 have left the window, so it takes three more
+        assert!(!restarts.record_stall(at(1100)));
+        assert!(!restarts.record_stall(at(1200)));
+        assert!(restarts.record_stall(at(1300)));
+    }
+
+    #[test]
+    fn restarts_never_happen_when_disabled() {
+        let start = Instant::now();
+        let mut restarts = RestartCoordinator::new(false);
+        let restarted = (0..10).any(|i| restarts.record_stall(start + Duration::from_secs(i)));
+        assert!(!restarted);
+    }
+}
@@ -0,0 +1,100 @@
+// Targeted app rescans against a RecordingPlatform whose installed apps change between scans
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+use falcommand_config::Config;
+use falcommand_core::{AppIndexRefresher, IndexManager, RescanLimiter};
+use falcommand_platform::AppInfo;
+use falcommand_plugin_testkit::RecordingPlatform;
+
+const SECOND: Duration = Duration::from_secs(1);
+
+fn installed(platform: &RecordingPlatform, apps: &[(&str, &Path)]) {
+    let apps = apps.iter().map(|(name, path)| AppInfo::new(*name, path.to_path_buf())).collect();
+    platform.set_app_sources(vec![("Applications", Ok(apps))]);
+}
+
+async fn indexed_names(index: &IndexManager) -> Vec<String> {
+    let mut names: Vec<String> = index.applications().await.into_iter().map(|app| app.name).collect();
+    names.sort();
+    names
+}
+
+async fn refresher(platform: Arc<RecordingPlatform>) -> (AppIndexRefresher, Arc<IndexManager>) {
+    let index = Arc::new(IndexManager::new(Arc::new(RwLock::new(Config::default()))).await.unwrap());
+    index.refresh_applications(platform.clone()).await.unwrap();
+    let refresher = AppIndexRefresher::with_limiter(index.clone(), platform, RescanLimiter::new(10 * SECOND, 300 * SECOND));
+    (refresher, index)
+}
+
+// An install shows up once the changes settle; the next one waits out the rate limit. Launch counts
+// survive every rescan
+#[tokio::test]
+async fn watched_changes_rescan_after_the_debounce_and_rate_limit() {
+    let platform = Arc::new(RecordingPlatform::new());
+    installed(&platform, &[("Firefox", Path::new("/opt/firefox/firefox"))]);
+    let (refresher, index) = refresher(platform.clone()).await;
+    index.update_app_usage("Firefox").await;
+
+    let start = Instant::now();
+    installed(&platform, &[("Firefox", Path::new("/opt/firefox/firefox")), ("Slack", Path::new("/opt/slack/slack"))]);
+    refresher.changed(start).await;
+    assert!(!refresher.rescan_if_due(start + 5 * SECOND).await);
+    assert_eq!(indexed_names(&index).await, ["Firefox"]);
+    assert!(refresher.rescan_if_due(start + 10 * SECOND).await);
+    assert_eq!(indexed_names(&index).await, ["Firefox", "Slack"]);
+
+    installed(&platform, &[
+        ("Firefox", Path::new("/opt/firefox/firefox")),
+        ("Slack", Path::new("/opt/slack/slack")),
+        ("Zed", Path::new("/opt/zed/zed")),
+    ]);
+    refresher.changed(start + 20 * SECOND).await;
+    assert!(!refresher.rescan_if_due(start + 60 * SECOND).await);
+    assert_eq!(indexed_names(&index).await, ["Firefox", "Slack"]);
+    assert!(refresher.rescan_if_due(start + 310 * SECOND).await);
+    assert_eq!(indexed_names(&index).await, ["Firefox", "Slack", "Zed"]);
+
+    assert_eq!(index.get_app_info("Firefox").await.unwrap().usage_count, 1);
+}
+
+// An update moved the app: the missing copy is dropped and the rescan finds it at its new place
+#[tokio::test]
+async fn a_missing_app_is_found_again_where_it_moved() {
+    let dir = tempfile::tempdir().unwrap();
+    let old = dir.path().join("old").join("editor");
+    let new = dir.path().join("new").join("editor");
+    std::fs::create_dir_all(new.parent().unwrap()).unwrap();
+    std::fs::write(&new, "").unwrap();
+
+    let platform = Arc::new(RecordingPlatform::new());
+    installed(&platform, &[("Editor", &old), ("Firefox", Path::new("/opt/firefox/firefox"))]);
+    let (refresher, index) = refresher(platform.clone()).await;
+
+    installed(&platform, &[("Editor", &new), ("Firefox", Path::new("/opt/firefox/firefox"))]);
+    let found = refresher.recover_missing_app(&old).await.unwrap();
+    assert_eq!(found.executable_path, new);
+    assert_eq!(index.get_app_info("Editor").await.unwrap().executable_path, new);
+    assert_eq!(indexed_names(&index).await, ["Editor", "Firefox"]);
+}
+
+// Uninstalled for good: the app leaves the index and there is nothing to retry
+#[tokio::test]
+async fn an_uninstalled_app_is_pruned() {
+    let dir = tempfile::tempdir().unwrap();
+    let gone = dir.path().join("editor");
+    let platform = Arc::new(RecordingPlatform::new());
+    installed(&platform, &[("Editor", &gone), ("Firefox", Path::new("/opt/firefox/firefox"))]);
+    let (refresher, index) = refresher(platform.clone()).await;
+
+    installed(&platform, &[("Firefox", Path::new("/opt/firefox/firefox"))]);
+    assert_eq!(refresher.recover_missing_app(&gone).await, None);
+    assert_eq!(indexed_names(&index).await, ["Firefox"]);
+
+    // A path that was never indexed triggers no rescan
+    installed(&platform, &[("Firefox", Path::new("/opt/firefox/firefox")), ("Slack", Path::new("/opt/slack/slack"))]);
+    assert_eq!(refresher.recover_missing_app(&PathBuf::from("/opt/unknown/app")).await, None);
+    assert_eq!(indexed_names(&index).await, ["Firefox"]);
+}
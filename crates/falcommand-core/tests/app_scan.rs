@@ -0,0 +1,64 @@
+// The app index when one sub-source of the platform's scan fails: the other sources' apps are
+// indexed and the failure shows in IndexStats
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use falcommand_config::Config;
+use falcommand_core::IndexManager;
+use falcommand_platform::{AppInfo, PlatformProvider, ScanWarning};
+use falcommand_plugin_testkit::RecordingPlatform;
+
+fn apps(names: &[&str]) -> Vec<AppInfo> {
+    names.iter().map(|name| AppInfo::new(*name, PathBuf::from(format!("/opt/{}/bin", name)))).collect()
+}
+
+async fn indexed_names(index: &IndexManager) -> Vec<String> {
+    let mut names: Vec<String> = index.applications().await.into_iter().map(|app| app.name).collect();
+    names.sort();
+    names
+}
+
+#[tokio::test]
+async fn a_failing_source_leaves_the_others_indexed_and_is_reported() {
+    let platform = Arc::new(RecordingPlatform::new());
+    platform.set_app_sources(vec![
+        ("Registry", Ok(apps(&["Firefox", "Slack"]))),
+        ("Start Menu", Err("access denied".to_string())),
+        ("Program Files", Ok(apps(&["Blender"]))),
+    ]);
+    let index = IndexManager::new(Arc::new(RwLock::new(Config::default()))).await.unwrap();
+    index.rebuild_index(platform.clone()).await.unwrap();
+
+    assert_eq!(indexed_names(&index).await, ["Blender", "Firefox", "Slack"]);
+    let stats = index.get_index_stats().await;
+    assert_eq!(stats.app_count, 3);
+    assert_eq!(stats.app_scan_warnings, [ScanWarning { source: "Start Menu".to_string(), error: "Failed to get installed applications: access denied".to_string() }]);
+    assert_eq!(platform.last_scan_report().scanned.len(), 2);
+
+    // The source recovers: the warning goes away
+    platform.set_app_sources(vec![
+        ("Registry", Ok(apps(&["Firefox", "Slack"]))),
+        ("Start Menu", Ok(apps(&["Notepad"]))),
+    ]);
+    index.refresh_applications(platform.clone()).await.unwrap();
+    assert_eq!(indexed_names(&index).await, ["Firefox", "Notepad", "Slack"]);
+    assert!(index.get_index_stats().await.app_scan_warnings.is_empty());
+}
+
+#[tokio::test]
+async fn apps_are_kept_when_every_source_fails() {
+    let platform = Arc::new(RecordingPlatform::new());
+    platform.set_app_sources(vec![("Registry", Ok(apps(&["Firefox"])))]);
+    let index = IndexManager::new(Arc::new(RwLock::new(Config::default()))).await.unwrap();
+    index.rebuild_index(platform.clone()).await.unwrap();
+
+    platform.set_app_sources(vec![
+        ("Registry", Err("timed out".to_string())),
+        ("Start Menu", Err("access denied".to_string())),
+    ]);
+    index.rebuild_index(platform).await.unwrap();
+    assert_eq!(indexed_names(&index).await, ["Firefox"]);
+    let sources: Vec<String> = index.get_index_stats().await.app_scan_warnings.into_iter().map(|warning| warning.source).collect();
+    assert_eq!(sources, ["Registry", "Start Menu"]);
+}
@@ -0,0 +1,39 @@
+// search.enable_app_search gating the indexed apps, both plain matches and app-with-argument
+// results
+use std::path::PathBuf;
+use std::sync::Arc;
+use chrono::Utc;
+use tokio::sync::RwLock;
+
+use falcommand_config::{Category, Config, SearchResult};
+use falcommand_core::{IndexManager, ManualClock, SearchEngine, SearchHistory, UsageStore};
+use falcommand_platform::AppInfo;
+use falcommand_plugin_testkit::RecordingPlatform;
+
+#[tokio::test]
+async fn disabling_app_search_hides_apps_from_every_query() {
+    let dir = tempfile::tempdir().unwrap();
+    let platform = Arc::new(RecordingPlatform::new());
+    platform.set_app_sources(vec![("Applications", Ok(vec![AppInfo::new("Firefox", PathBuf::from("/usr/bin/firefox"))]))]);
+
+    let config = Arc::new(RwLock::new(Config::default()));
+    let index = Arc::new(IndexManager::new(config.clone()).await.unwrap());
+    index.rebuild_index(platform).await.unwrap();
+    let clock = Arc::new(ManualClock::new(Utc::now()));
+    let history = Arc::new(SearchHistory::with_storage_path(dir.path().join("history.json"), clock.clone()).await.unwrap());
+    let usage = Arc::new(UsageStore::with_storage_path(dir.path().join("usage.json"), clock).await.unwrap());
+    let engine = SearchEngine::new(config.clone(), index, history, usage).await.unwrap();
+
+    let apps = |results: Vec<SearchResult>| -> Vec<String> {
+        results.into_iter().filter(|result| result.category == Category::Application).map(|result| result.title).collect()
+    };
+    assert_eq!(apps(engine.search("firefox").await), ["Firefox"]);
+    assert!(!apps(engine.search("firefox ~/index.html").await).is_empty());
+
+    config.write().await.search.enable_app_search = false;
+    assert!(apps(engine.search("firefox").await).is_empty());
+    assert!(apps(engine.search("firefox ~/index.html").await).is_empty());
+
+    config.write().await.search.enable_app_search = true;
+    assert_eq!(apps(engine.search("firefox").await), ["Firefox"]);
+}
@@ -0,0 +1,46 @@
+// Clipboard suggestions read through the testkit's RecordingPlatform
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use falcommand_config::Config;
+use falcommand_core::ClipboardSuggestions;
+use falcommand_platform::PlatformProvider;
+use falcommand_plugin_testkit::RecordingPlatform;
+
+fn suggestions(platform: Arc<RecordingPlatform>, enabled: bool) -> (ClipboardSuggestions, Arc<RwLock<Config>>) {
+    let mut config = Config::default();
+    config.behavior.clipboard_suggestions = enabled;
+    let config = Arc::new(RwLock::new(config));
+    (ClipboardSuggestions::new(platform, config.clone()), config)
+}
+
+async fn titles(suggestions: &ClipboardSuggestions) -> Vec<String> {
+    suggestions.suggestions().await.into_iter().map(|result| result.title).collect()
+}
+
+// Off by default; nothing is offered until it is turned on
+#[tokio::test]
+async fn suggestions_follow_the_setting() {
+    assert!(!Config::default().behavior.clipboard_suggestions);
+    let platform = Arc::new(RecordingPlatform::new());
+    platform.copy_to_clipboard("https://example.com").unwrap();
+
+    let (suggestions, config) = suggestions(platform, false);
+    assert!(titles(&suggestions).await.is_empty());
+
+    config.write().await.behavior.clipboard_suggestions = true;
+    assert_eq!(titles(&suggestions).await, ["Open copied URL", "Search for copied text"]);
+}
+
+#[tokio::test]
+async fn large_or_empty_clipboards_offer_nothing() {
+    let platform = Arc::new(RecordingPlatform::new());
+    let (suggestions, _) = suggestions(platform.clone(), true);
+    assert!(titles(&suggestions).await.is_empty());
+
+    platform.copy_to_clipboard(&"word ".repeat(1000)).unwrap();
+    assert!(titles(&suggestions).await.is_empty());
+
+    platform.copy_to_clipboard("12+30").unwrap();
+    assert_eq!(titles(&suggestions).await, ["Calculate copied expression"]);
+}
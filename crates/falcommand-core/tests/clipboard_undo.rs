@@ -0,0 +1,42 @@
+// The Undo button on the overwrite notification, driven through the testkit's RecordingPlatform
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::Utc;
+
+use falcommand_core::{ClipboardUndo, ManualClock};
+use falcommand_platform::PlatformProvider;
+use falcommand_plugin_testkit::RecordingPlatform;
+
+const WINDOW: Duration = Duration::from_secs(10);
+
+// "previous" was on the clipboard, "copied" replaced it and was held for WINDOW
+fn copied_over(clock: Arc<ManualClock>) -> (Arc<ClipboardUndo>, Arc<RecordingPlatform>) {
+    let platform = Arc::new(RecordingPlatform::new());
+    platform.copy_to_clipboard("previous").unwrap();
+    let clipboard_undo = Arc::new(ClipboardUndo::new(clock));
+    let previous = platform.paste_from_clipboard();
+    platform.copy_to_clipboard("copied").unwrap();
+    assert!(clipboard_undo.hold(previous, "copied", WINDOW));
+    clipboard_undo.notify(platform.clone(), WINDOW).unwrap();
+    (clipboard_undo, platform)
+}
+
+#[test]
+fn undo_button_restores_the_replaced_text() {
+    let (_, platform) = copied_over(Arc::new(ManualClock::new(Utc::now())));
+
+    assert_eq!(platform.notifications()[0].action.as_deref(), Some("Undo"));
+    assert!(platform.click_notification_action(0));
+    assert_eq!(platform.clipboard().as_deref(), Some("previous"));
+}
+
+#[test]
+fn undo_button_does_nothing_after_the_window() {
+    let clock = Arc::new(ManualClock::new(Utc::now()));
+    let (clipboard_undo, platform) = copied_over(clock.clone());
+
+    clock.advance(chrono::Duration::seconds(11));
+    assert!(platform.click_notification_action(0));
+    assert_eq!(platform.clipboard().as_deref(), Some("copied"));
+    assert!(clipboard_undo.undo_result().is_none());
+}
@@ -0,0 +1,12 @@
+// Helpers shared by the integration tests, each of which includes this with `mod common;`
+
+// The key search.include_paths uses for the OS the tests run on
+pub fn os_key() -> &'static str {
+    if cfg!(target_os = "windows") {
+        "windows"
+    } else if cfg!(target_os = "macos") {
+        "macos"
+    } else {
+        "linux"
+    }
+}
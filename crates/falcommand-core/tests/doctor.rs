@@ -0,0 +1,219 @@
+// Doctor checks against the testkit's RecordingPlatform and a stub keyring
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use async_trait::async_trait;
+use tokio::sync::RwLock;
+
+use falcommand_config::{Config, PathResolver};
+use falcommand_core::{
+    check_clipboard, check_config_files, check_hotkey, check_include_paths, check_index_cache, check_keyring,
+    check_notifications, run_doctor, CheckStatus, DoctorContext, EncryptedFileSecretStore, IndexManager, MachineKey,
+    PassphraseKey, SecretError, SecretStore,
+};
+use falcommand_platform::PlatformProvider;
+use falcommand_plugin_testkit::RecordingPlatform;
+
+mod common;
+use common::os_key;
+
+// A keyring that is there or not, and never holds anything
+struct StubKeyring(bool);
+
+#[async_trait]
+impl SecretStore for StubKeyring {
+    fn name(&self) -> &str {
+        "Stub keyring"
+    }
+
+    fn encrypts_at_rest(&self) -> bool {
+        true
+    }
+
+    async fn is_available(&self) -> bool {
+        self.0
+    }
+
+    async fn get(&self, _key: &str) -> Result<Option<String>, SecretError> {
+        Ok(None)
+    }
+
+    async fn set(&self, _key: &str, _value: &str) -> Result<(), SecretError> {
+        Ok(())
+    }
+
+    async fn delete(&self, _key: &str) -> Result<(), SecretError> {
+        Ok(())
+    }
+}
+
+// Nothing to index and no online plugins, so every check stays on this machine
+fn config(include_paths: Vec<String>) -> Config {
+    let mut config = Config::default();
+    config.search.include_paths = HashMap::from([(os_key().to_string(), include_paths)]);
+    config.plugins.enabled.clear();
+    config
+}
+
+async fn context(dir: &Path, config: Config, platform: Arc<RecordingPlatform>) -> DoctorContext {
+    let resolver = PathResolver::with_roots(dir.join("config"), dir.join("data"), None);
+    let index = IndexManager::new(Arc::new(RwLock::new(config.clone()))).await.unwrap()
+        .with_cache_path(dir.join("index.json"));
+    DoctorContext::new(config, resolver, platform, Arc::new(index), Arc::new(StubKeyring(true)))
+}
+
+#[tokio::test]
+async fn hotkey_probe_is_registered_and_refusals_fail() {
+    let dir = tempfile::tempdir().unwrap();
+    let platform = Arc::new(RecordingPlatform::new());
+    let context = context(dir.path(), config(Vec::new()), platform.clone()).await;
+    assert_eq!(check_hotkey(&context).await.status, CheckStatus::Pass);
+
+    platform.refuse_hotkeys();
+    let refused = check_hotkey(&context).await;
+    assert_eq!(refused.status, CheckStatus::Fail);
+    assert!(refused.detail.contains("Ctrl+Alt+Shift+F12 is not allowed"), "{}", refused.detail);
+    assert!(refused.guidance.is_some());
+}
+
+#[tokio::test]
+async fn notification_check_sends_one_and_reports_refusal() {
+    let dir = tempfile::tempdir().unwrap();
+    let platform = Arc::new(RecordingPlatform::new());
+    let context = context(dir.path(), config(Vec::new()), platform.clone()).await;
+
+    assert_eq!(check_notifications(&context).await.status, CheckStatus::Pass);
+    assert_eq!(platform.notifications().len(), 1);
+    assert_eq!(platform.notifications()[0].message, "Test notification from falcommand doctor");
+
+    platform.refuse_notifications();
+    let refused = check_notifications(&context).await;
+    assert_eq!(refused.status, CheckStatus::Fail);
+    assert_eq!(refused.detail, "Failed to show notification: notifications are turned off");
+}
+
+// The marker reads back and the user's clipboard text is put back afterwards
+#[tokio::test]
+async fn clipboard_round_trip_restores_the_previous_text() {
+    let dir = tempfile::tempdir().unwrap();
+    let platform = Arc::new(RecordingPlatform::new());
+    platform.copy_to_clipboard("user text").unwrap();
+    let context = context(dir.path(), config(Vec::new()), platform.clone()).await;
+
+    assert_eq!(check_clipboard(&context).await.status, CheckStatus::Pass);
+    let history = platform.clipboard_history();
+    assert_eq!(history.len(), 3);
+    assert!(history[1].starts_with("falcommand doctor "));
+    assert_eq!(platform.clipboard().as_deref(), Some("user text"));
+}
+
+#[tokio::test]
+async fn keyring_check_follows_availability() {
+    let dir = tempfile::tempdir().unwrap();
+    let platform = Arc::new(RecordingPlatform::new());
+    let context = context(dir.path(), config(Vec::new()), platform).await;
+    assert_eq!(check_keyring(&context).await.detail, "Stub keyring is available");
+
+    let locked = DoctorContext { secret_store: Arc::new(StubKeyring(false)), ..context };
+    assert_eq!(check_keyring(&locked).await.status, CheckStatus::Warn);
+}
+
+// Without a keyring the secrets are encrypted to secrets.enc; only a machine-bound key is worth a warning
+#[tokio::test]
+async fn file_store_is_reported_as_encrypted() {
+    let dir = tempfile::tempdir().unwrap();
+    let platform = Arc::new(RecordingPlatform::new());
+    let context = context(dir.path(), config(Vec::new()), platform).await;
+    let secrets_file = context.resolver.shared_data_file("secrets.enc");
+
+    let passphrase = DoctorContext {
+        secret_store: Arc::new(EncryptedFileSecretStore::new(secrets_file.clone(), Arc::new(PassphraseKey::new("pw")))),
+        ..context.clone()
+    };
+    let check = check_keyring(&passphrase).await;
+    assert_eq!(check.status, CheckStatus::Pass);
+    assert_eq!(
+        check.detail,
+        format!("not available; secrets are stored encrypted in {} under the passphrase", secrets_file.display())
+    );
+
+    let machine = DoctorContext {
+        secret_store: Arc::new(EncryptedFileSecretStore::new(secrets_file.clone(), Arc::new(MachineKey))),
+        resolver: PathResolver::portable(dir.path().to_path_buf(), None),
+        ..context
+    };
+    let check = check_keyring(&machine).await;
+    assert_eq!(check.status, CheckStatus::Warn);
+    assert!(check.detail.starts_with("not used in portable mode; secrets are stored encrypted in"), "{}", check.detail);
+    assert!(check.guidance.unwrap().contains(PassphraseKey::ENV));
+}
+
+#[tokio::test]
+async fn config_files_that_fail_to_load_fail() {
+    let dir = tempfile::tempdir().unwrap();
+    let platform = Arc::new(RecordingPlatform::new());
+    let context = context(dir.path(), config(Vec::new()), platform).await;
+
+    // No file yet: defaults, which is only worth a warning
+    let checks = check_config_files(&context).await;
+    assert_eq!(checks.len(), 1);
+    assert_eq!(checks[0].status, CheckStatus::Warn);
+
+    Config::default().save_to_file(context.resolver.config_file()).await.unwrap();
+    std::fs::write(context.resolver.platform_config_file(), "{ not json").unwrap();
+    let checks = check_config_files(&context).await;
+    assert_eq!(checks.iter().map(|check| check.status).collect::<Vec<_>>(), [CheckStatus::Pass, CheckStatus::Fail]);
+    assert!(checks[1].guidance.as_deref().unwrap().contains("falcommand config schema"));
+}
+
+#[tokio::test]
+async fn include_paths_are_checked_one_by_one() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = std::fs::canonicalize(dir.path()).unwrap();
+    let documents = root.join("Documents");
+    std::fs::create_dir_all(&documents).unwrap();
+    let include = [&documents, &root.join("Progam Files")].map(|path| path.to_string_lossy().into_owned());
+    let context = context(dir.path(), config(include.to_vec()), Arc::new(RecordingPlatform::new())).await;
+
+    let checks = check_include_paths(&context).await;
+    assert_eq!(checks.iter().map(|check| check.status).collect::<Vec<_>>(), [CheckStatus::Pass, CheckStatus::Warn]);
+    assert!(checks[1].name.ends_with("Progam Files"));
+
+    let none = self::context(dir.path(), config(Vec::new()), Arc::new(RecordingPlatform::new())).await;
+    assert_eq!(check_include_paths(&none).await[0].status, CheckStatus::Warn);
+}
+
+#[tokio::test]
+async fn index_cache_missing_built_and_corrupt() {
+    let dir = tempfile::tempdir().unwrap();
+    let platform = Arc::new(RecordingPlatform::new());
+    let context = context(dir.path(), config(Vec::new()), platform.clone()).await;
+    assert_eq!(check_index_cache(&context).await.status, CheckStatus::Warn);
+
+    context.index_manager.rebuild_index(platform).await.unwrap();
+    let built = check_index_cache(&context).await;
+    assert_eq!((built.status, built.detail.as_str()), (CheckStatus::Pass, "0 applications, 0 files"));
+
+    std::fs::write(dir.path().join("index.json"), "garbage").unwrap();
+    let corrupt = check_index_cache(&context).await;
+    assert_eq!(corrupt.status, CheckStatus::Fail);
+    assert!(corrupt.guidance.unwrap().contains("falcommand index rebuild"));
+}
+
+// A failing check is reported and the ones after it still run
+#[tokio::test]
+async fn a_failing_check_does_not_stop_the_rest() {
+    let dir = tempfile::tempdir().unwrap();
+    let platform = Arc::new(RecordingPlatform::new());
+    platform.refuse_hotkeys();
+    let context = context(dir.path(), config(Vec::new()), platform.clone()).await;
+
+    let report = run_doctor(&context).await;
+    assert_eq!(report.checks[0].name, "Hotkey");
+    assert_eq!(report.overall(), CheckStatus::Fail);
+    for name in ["Config", "Index cache", "Notifications", "Clipboard", "Tray", "Keyring"] {
+        assert!(report.checks.iter().any(|check| check.name == name), "missing {}", name);
+    }
+    assert_eq!(platform.notifications().len(), 1);
+    assert!(report.render().ends_with(&format!("{}\n", report.summary())));
+}
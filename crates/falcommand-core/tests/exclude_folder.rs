@@ -0,0 +1,49 @@
+// "Exclude this folder from indexing" run through the ActionExecutor, from a file result to the
+// saved config
+use std::collections::HashMap;
+use std::sync::Arc;
+use chrono::Utc;
+
+use falcommand_config::{Action, Config};
+use falcommand_core::{ActionExecutor, ActionScheduler, ConfigStore, IndexManager, ManualClock};
+use falcommand_plugin_testkit::RecordingPlatform;
+
+mod common;
+use common::os_key;
+
+#[tokio::test]
+async fn excluding_a_folder_drops_its_files_and_keeps_them_out() {
+    let dir = tempfile::tempdir().unwrap();
+    let documents = std::fs::canonicalize(dir.path()).unwrap().join("Documents");
+    std::fs::create_dir_all(&documents).unwrap();
+    std::fs::write(documents.join("quarterly-report.txt"), "").unwrap();
+
+    let mut config = Config::default();
+    config.search.include_paths = HashMap::from([(os_key().to_string(), vec![documents.to_string_lossy().into_owned()])]);
+    let config_store = Arc::new(ConfigStore::new(config, dir.path().join("config.json")));
+    let index = Arc::new(IndexManager::new(config_store.shared()).await.unwrap());
+    let platform = Arc::new(RecordingPlatform::new());
+    index.rebuild_index(platform.clone()).await.unwrap();
+
+    let results = index.search_files("quarterly").await.unwrap();
+    let exclude = results[0].secondary_actions.iter()
+        .find(|action| action.title == "Exclude this folder from indexing")
+        .map(|action| action.action.clone())
+        .unwrap();
+    assert_eq!(exclude, Action::ExcludeFromIndex(documents.clone()));
+
+    let clock = Arc::new(ManualClock::new(Utc::now()));
+    let scheduler = Arc::new(ActionScheduler::with_storage_path(dir.path().join("scheduled.json"), clock).await.unwrap());
+    let executor = ActionExecutor::new(platform.clone(), scheduler)
+        .with_index_manager(index.clone())
+        .with_config_store(config_store.clone());
+    executor.execute(&exclude).await.unwrap();
+
+    assert!(index.search_files("quarterly").await.unwrap().is_empty());
+    let saved = std::fs::read_to_string(dir.path().join("config.json")).unwrap();
+    assert!(saved.contains(&*serde_json::to_string(&documents.to_string_lossy()).unwrap()));
+
+    // Still excluded after the next full rebuild
+    index.rebuild_index(platform).await.unwrap();
+    assert!(index.search_files("quarterly").await.unwrap().is_empty());
+}
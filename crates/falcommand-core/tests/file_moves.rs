@@ -0,0 +1,111 @@
+// Usage following renamed and moved files through the FileMoveTracker, while a real deletion
+// forgets it
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use chrono::Utc;
+use tokio::sync::RwLock;
+
+use falcommand_config::Config;
+use falcommand_core::{FileMoveTracker, FsEvent, IndexManager, ManualClock, MoveDetector, SearchEngine, SearchHistory, UsageStore};
+use falcommand_plugin_testkit::RecordingPlatform;
+
+mod common;
+use common::os_key;
+
+struct Fixture {
+    documents: PathBuf,
+    index: Arc<IndexManager>,
+    usage: Arc<UsageStore>,
+    tracker: FileMoveTracker,
+}
+
+// Documents/report.txt, indexed and launched twice
+async fn fixture(dir: &Path) -> Fixture {
+    let documents = std::fs::canonicalize(dir).unwrap().join("Documents");
+    std::fs::create_dir_all(&documents).unwrap();
+    std::fs::write(documents.join("report.txt"), "quarterly numbers").unwrap();
+
+    let mut config = Config::default();
+    config.search.include_paths = HashMap::from([(os_key().to_string(), vec![documents.to_string_lossy().into_owned()])]);
+    let config = Arc::new(RwLock::new(config));
+    let index = Arc::new(IndexManager::new(config.clone()).await.unwrap());
+    index.rebuild_index(Arc::new(RecordingPlatform::new())).await.unwrap();
+
+    let clock = Arc::new(ManualClock::new(Utc::now()));
+    let history = Arc::new(SearchHistory::with_storage_path(dir.join("history.json"), clock.clone()).await.unwrap());
+    let usage = Arc::new(UsageStore::with_storage_path(dir.join("usage.json"), clock).await.unwrap());
+    let engine = Arc::new(SearchEngine::new(config, index.clone(), history, usage.clone()).await.unwrap());
+
+    let report = index.search_files("report").await.unwrap().remove(0);
+    usage.record(&report).await.unwrap();
+    usage.record(&report).await.unwrap();
+    Fixture { documents, index: index.clone(), usage, tracker: FileMoveTracker::new(index, engine) }
+}
+
+// Launches recorded for `path`, and whether they count towards ranking under its current result
+async fn launches(fixture: &Fixture, path: &Path) -> (usize, bool) {
+    let count = fixture.usage.records().await.iter().filter(|record| record.path.as_deref() == Some(path)).count();
+    let scored = match fixture.index.file_at(path).await {
+        Some(file) => fixture.usage.usage_scores(14.0).await.contains_key(&file.to_search_result().id),
+        None => false,
+    };
+    (count, scored)
+}
+
+#[tokio::test]
+async fn usage_follows_a_renamed_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let fixture = fixture(dir.path()).await;
+    let (from, to) = (fixture.documents.join("report.txt"), fixture.documents.join("report-2026.txt"));
+    assert_eq!(launches(&fixture, &from).await, (2, true));
+
+    std::fs::rename(&from, &to).unwrap();
+    let mut detector = MoveDetector::default();
+    fixture.tracker.handle_event(&mut detector, FsEvent::Renamed { from: from.clone(), to: to.clone() }, Instant::now()).await;
+
+    assert_eq!(launches(&fixture, &to).await, (2, true));
+    assert_eq!(launches(&fixture, &from).await, (0, false));
+    assert_eq!(fixture.index.search_files("report-2026").await.unwrap()[0].path.as_deref(), Some(to.as_path()));
+}
+
+// Watchers that report a move as a removal and a creation are paired by size and modification time
+#[tokio::test]
+async fn usage_follows_a_move_reported_as_remove_and_create() {
+    let dir = tempfile::tempdir().unwrap();
+    let fixture = fixture(dir.path()).await;
+    let from = fixture.documents.join("report.txt");
+    let archive = fixture.documents.join("archive");
+    std::fs::create_dir(&archive).unwrap();
+    let to = archive.join("report.txt");
+
+    std::fs::rename(&from, &to).unwrap();
+    let now = Instant::now();
+    let mut detector = MoveDetector::default();
+    fixture.tracker.handle_event(&mut detector, FsEvent::Removed(from.clone()), now).await;
+    fixture.tracker.handle_event(&mut detector, FsEvent::Created(to.clone()), now).await;
+
+    assert_eq!(launches(&fixture, &to).await, (2, true));
+    assert!(detector.expire(now + MoveDetector::WINDOW * 2).is_empty());
+}
+
+#[tokio::test]
+async fn a_deleted_file_takes_its_usage_with_it() {
+    let dir = tempfile::tempdir().unwrap();
+    let fixture = fixture(dir.path()).await;
+    let path = fixture.documents.join("report.txt");
+
+    std::fs::remove_file(&path).unwrap();
+    let now = Instant::now();
+    let mut detector = MoveDetector::default();
+    fixture.tracker.handle_event(&mut detector, FsEvent::Removed(path.clone()), now).await;
+    // Nothing happens until the window has passed without a matching creation
+    assert_eq!(launches(&fixture, &path).await.0, 2);
+
+    for expired in detector.expire(now + MoveDetector::WINDOW + Duration::from_secs(1)) {
+        fixture.tracker.apply_delete(&expired).await;
+    }
+    assert_eq!(launches(&fixture, &path).await, (0, false));
+    assert!(fixture.usage.records().await.is_empty());
+}
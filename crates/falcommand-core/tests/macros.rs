@@ -0,0 +1,84 @@
+// Macros run through the ActionExecutor against the testkit's RecordingPlatform
+use std::sync::Arc;
+use chrono::Utc;
+
+use falcommand_config::{Action, ActionError, CommandSteps, Config, CustomCommand};
+use falcommand_core::{ActionExecutor, ActionScheduler, ConfigStore, ManualClock};
+use falcommand_plugin_testkit::RecordingPlatform;
+
+// Copies "first", fails on a profile switch (there are no profiles here), then copies "second"
+async fn executor(dir: &std::path::Path, stop_on_failure: bool) -> (ActionExecutor, Arc<RecordingPlatform>) {
+    let custom_commands = vec![
+        CustomCommand {
+            name: "Demo".to_string(),
+            description: String::new(),
+            steps: CommandSteps::Macro { actions: vec![
+                Action::CopyToClipboard("first".to_string()),
+                Action::SwitchProfile("work".to_string()),
+                Action::CopyToClipboard("second".to_string()),
+            ] },
+            step_delay_ms: 0,
+            stop_on_failure,
+        },
+        CustomCommand {
+            name: "Copy both".to_string(),
+            description: String::new(),
+            steps: CommandSteps::Macro { actions: vec![
+                Action::CopyToClipboard("one".to_string()),
+                Action::CopyToClipboard("two".to_string()),
+            ] },
+            step_delay_ms: 0,
+            stop_on_failure: true,
+        },
+    ];
+    let config = Config { custom_commands, ..Config::default() };
+
+    let platform = Arc::new(RecordingPlatform::new());
+    let clock = Arc::new(ManualClock::new(Utc::now()));
+    let scheduler = Arc::new(ActionScheduler::with_storage_path(dir.join("scheduled.json"), clock).await.unwrap());
+    let config_store = Arc::new(ConfigStore::new(config, dir.join("config.json")));
+    let executor = ActionExecutor::new(platform.clone(), scheduler).with_config_store(config_store);
+    (executor, platform)
+}
+
+fn failure(result: Result<(), ActionError>) -> String {
+    match result {
+        Err(ActionError::Other(message)) => message,
+        other => panic!("expected a macro failure, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn steps_run_in_order_and_announce_the_count() {
+    let dir = tempfile::tempdir().unwrap();
+    let (executor, platform) = executor(dir.path(), true).await;
+
+    executor.execute(&Action::RunCustomCommand("copy both".to_string())).await.unwrap();
+
+    assert_eq!(platform.clipboard_history(), ["one", "two"]);
+    assert_eq!(platform.notifications()[0].title, "Copy both");
+    assert_eq!(platform.notifications()[0].message, "2/2 steps succeeded");
+}
+
+#[tokio::test]
+async fn a_failing_step_stops_the_macro() {
+    let dir = tempfile::tempdir().unwrap();
+    let (executor, platform) = executor(dir.path(), true).await;
+
+    let message = failure(executor.execute(&Action::RunCustomCommand("Demo".to_string())).await);
+
+    assert_eq!(platform.clipboard_history(), ["first"]);
+    assert!(message.starts_with("Demo: 1/3 steps succeeded; step 2 (Switch to profile work) failed"), "{}", message);
+    assert!(platform.notifications().is_empty());
+}
+
+#[tokio::test]
+async fn the_macro_can_continue_past_a_failure() {
+    let dir = tempfile::tempdir().unwrap();
+    let (executor, platform) = executor(dir.path(), false).await;
+
+    let message = failure(executor.execute(&Action::RunCustomCommand("Demo".to_string())).await);
+
+    assert_eq!(platform.clipboard_history(), ["first", "second"]);
+    assert!(message.starts_with("Demo: 2/3 steps succeeded; step 2"), "{}", message);
+}
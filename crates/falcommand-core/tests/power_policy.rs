@@ -0,0 +1,164 @@
+// The power policy gate in each background consumer, with the battery reported by the testkit's
+// RecordingPlatform
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use async_trait::async_trait;
+use tokio::sync::RwLock;
+
+use falcommand_config::{Config, PathResolver};
+use falcommand_core::{
+    AppIndexRefresher, CloudProvider, ConnectivityMonitor, IndexManager, ManualClock, PowerPolicy, RescanLimiter,
+    SearchEngine, SearchHistory, SyncError, SyncManager, UsageStore,
+};
+use falcommand_platform::{AppInfo, BatteryStatus};
+use falcommand_plugin_testkit::RecordingPlatform;
+
+const SECOND: Duration = Duration::from_secs(1);
+
+fn platform(percent: u8, charging: bool) -> Arc<RecordingPlatform> {
+    let platform = Arc::new(RecordingPlatform::new());
+    platform.set_battery(Some(BatteryStatus { percent, charging }));
+    platform
+}
+
+// Deferral below 30%, the default
+fn policy(platform: Arc<RecordingPlatform>, config: Config) -> Arc<PowerPolicy> {
+    Arc::new(PowerPolicy::new(platform, Arc::new(RwLock::new(config))))
+}
+
+#[tokio::test]
+async fn low_battery_defers_and_ac_or_a_lower_threshold_runs() {
+    let low = policy(platform(18, false), Config::default());
+    assert!(!low.allows("auto-sync").await);
+    assert_eq!(low.summary().await, "Background work: deferred (on battery at 18%, below 30%)");
+
+    assert!(policy(platform(18, true), Config::default()).allows("auto-sync").await);
+    assert!(policy(platform(45, false), Config::default()).allows("auto-sync").await);
+
+    let mut lenient = Config::default();
+    lenient.power.battery_threshold_percent = 10;
+    assert!(policy(platform(18, false), lenient).allows("auto-sync").await);
+
+    // Without a battery there is nothing to save
+    let desktop = Arc::new(RecordingPlatform::new());
+    assert_eq!(policy(desktop, Config::default()).summary().await, "Background work: running (on AC)");
+}
+
+// Watched install changes wait while deferred and stay pending; a launch of a missing app doesn't wait
+#[tokio::test]
+async fn app_rescans_wait_but_missing_app_recovery_does_not() {
+    let platform = platform(18, false);
+    let config = Arc::new(RwLock::new(Config::default()));
+    let index = Arc::new(IndexManager::new(config.clone()).await.unwrap());
+    platform.set_app_sources(vec![("Applications", Ok(vec![AppInfo::new("Editor", "/opt/editor/gone".into())]))]);
+    index.refresh_applications(platform.clone()).await.unwrap();
+
+    let power_policy = Arc::new(PowerPolicy::new(platform.clone(), config.clone()));
+    let refresher = AppIndexRefresher::with_limiter(index.clone(), platform.clone(), RescanLimiter::new(SECOND, SECOND))
+        .with_power_policy(power_policy);
+    platform.set_app_sources(vec![("Applications", Ok(vec![AppInfo::new("Slack", "/opt/slack/slack".into())]))]);
+
+    let start = Instant::now();
+    refresher.changed(start).await;
+    assert!(!refresher.rescan_if_due(start + 10 * SECOND).await);
+    assert!(index.get_app_info("Slack").await.is_none());
+
+    // Explicit: rescans right away
+    refresher.recover_missing_app(Path::new("/opt/editor/gone")).await;
+    assert!(index.get_app_info("Slack").await.is_some());
+
+    // The watched change was covered by that rescan; once allowed, a new one goes through
+    config.write().await.power.defer_on_battery = false;
+    assert!(!refresher.rescan_if_due(start + 20 * SECOND).await);
+    refresher.changed(start + 30 * SECOND).await;
+    assert!(refresher.rescan_if_due(start + 40 * SECOND).await);
+}
+
+struct CountingProvider(AtomicUsize);
+
+#[async_trait]
+impl CloudProvider for CountingProvider {
+    fn name(&self) -> &str {
+        "Counting"
+    }
+
+    async fn upload(&self, _data: &[u8]) -> Result<(), SyncError> {
+        self.0.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+
+    async fn download(&self) -> Result<Vec<u8>, SyncError> {
+        Err(SyncError::Other("nothing uploaded".to_string()))
+    }
+
+    async fn is_available(&self) -> bool {
+        true
+    }
+
+    async fn authenticate(&self) -> Result<(), SyncError> {
+        Ok(())
+    }
+}
+
+async fn sync_manager(dir: &Path, power_policy: Arc<PowerPolicy>) -> (SyncManager, Arc<CountingProvider>) {
+    let mut config = Config::default();
+    config.sync.enabled = true;
+    let resolver = PathResolver::with_roots(dir.join("config"), dir.join("data"), None);
+    let provider = Arc::new(CountingProvider(AtomicUsize::new(0)));
+    let manager = SyncManager::new(Arc::new(RwLock::new(config)), &resolver).await.unwrap()
+        .with_cloud_provider(provider.clone())
+        .with_power_policy(power_policy);
+    (manager, provider)
+}
+
+#[tokio::test]
+async fn auto_sync_is_deferred_but_a_manual_sync_uploads() {
+    let dir = tempfile::tempdir().unwrap();
+    let (manager, provider) = sync_manager(dir.path(), policy(platform(18, false), Config::default())).await;
+    assert!(!manager.auto_sync_round().await);
+    assert_eq!(provider.0.load(Ordering::SeqCst), 0);
+
+    manager.sync_up().await.unwrap();
+    assert_eq!(provider.0.load(Ordering::SeqCst), 1);
+
+    let dir = tempfile::tempdir().unwrap();
+    let (manager, provider) = sync_manager(dir.path(), policy(platform(18, true), Config::default())).await;
+    assert!(manager.auto_sync_round().await);
+    assert_eq!(provider.0.load(Ordering::SeqCst), 1);
+}
+
+// The retention pass keeps old history while deferred; pruning on request still works
+#[tokio::test]
+async fn history_maintenance_is_deferred_but_explicit_pruning_is_not() {
+    let dir = tempfile::tempdir().unwrap();
+    let clock = Arc::new(ManualClock::new(chrono::Utc::now()));
+    let config = Arc::new(RwLock::new(Config::default()));
+    let index = Arc::new(IndexManager::new(config.clone()).await.unwrap());
+    let history = Arc::new(SearchHistory::with_storage_path(dir.path().join("history.json"), clock.clone()).await.unwrap());
+    let usage = Arc::new(UsageStore::with_storage_path(dir.path().join("usage.json"), clock.clone()).await.unwrap());
+    let engine = SearchEngine::new(config.clone(), index, history.clone(), usage).await.unwrap();
+
+    history.record("firefox", "Firefox").await.unwrap();
+    clock.advance(chrono::Duration::days(91));
+
+    let power_policy = PowerPolicy::new(platform(18, false), config.clone());
+    assert!(!engine.history_maintenance_round(&power_policy).await);
+    assert_eq!(history.entries().await.len(), 1);
+
+    engine.apply_history_retention().await;
+    assert!(history.entries().await.is_empty());
+
+    let on_ac = PowerPolicy::new(platform(18, true), config);
+    assert!(engine.history_maintenance_round(&on_ac).await);
+}
+
+// Deferred probes leave the last known state alone without touching the network
+#[tokio::test]
+async fn connectivity_probes_are_deferred() {
+    let monitor = ConnectivityMonitor::new().with_power_policy(policy(platform(18, false), Config::default()));
+    monitor.set_online(false);
+    assert!(!monitor.probe_round().await);
+    assert!(!monitor.is_online());
+}
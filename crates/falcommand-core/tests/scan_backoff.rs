@@ -0,0 +1,54 @@
+// Include paths that fail to scan are backed off across rebuilds and restarts until a forced
+// rebuild
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use falcommand_config::Config;
+use falcommand_core::IndexManager;
+use falcommand_plugin_testkit::RecordingPlatform;
+
+mod common;
+use common::os_key;
+
+async fn index(config: &Config, dir: &Path) -> IndexManager {
+    IndexManager::new(Arc::new(RwLock::new(config.clone()))).await.unwrap()
+        .with_cache_path(dir.join("index.json"))
+}
+
+#[tokio::test]
+async fn failed_scans_stay_backed_off_across_restarts_until_forced() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = std::fs::canonicalize(dir.path()).unwrap();
+    let documents = root.join("Documents");
+    let offline = root.join("share").join("projects");
+    std::fs::create_dir_all(&documents).unwrap();
+    std::fs::create_dir_all(offline.parent().unwrap()).unwrap();
+    std::fs::write(documents.join("notes.txt"), "").unwrap();
+
+    let mut config = Config::default();
+    let include = [&documents, &offline].map(|path| path.to_string_lossy().into_owned());
+    config.search.include_paths = HashMap::from([(os_key().to_string(), include.to_vec())]);
+    let platform = Arc::new(RecordingPlatform::new());
+
+    let first = index(&config, &root).await;
+    first.rebuild_index(platform.clone()).await.unwrap();
+    let backed_off = first.get_index_stats().await.backed_off_dirs;
+    assert_eq!(backed_off.iter().map(|(path, _)| path).collect::<Vec<_>>(), [&offline]);
+
+    // After a restart the share is back, but its backoff came along from the cache
+    std::fs::create_dir_all(&offline).unwrap();
+    std::fs::write(offline.join("plan.txt"), "").unwrap();
+    let restarted = index(&config, &root).await;
+    assert!(restarted.load_cache().await.is_some());
+    assert_eq!(restarted.get_index_stats().await.backed_off_dirs, backed_off);
+    restarted.rebuild_index(platform.clone()).await.unwrap();
+    assert!(restarted.search_files("plan").await.unwrap().is_empty());
+    assert_eq!(restarted.search_files("notes").await.unwrap().len(), 1);
+
+    // `index rebuild --force` tries it again right away
+    restarted.force_rebuild_index(platform).await.unwrap();
+    assert_eq!(restarted.search_files("plan").await.unwrap().len(), 1);
+    assert!(restarted.get_index_stats().await.backed_off_dirs.is_empty());
+}
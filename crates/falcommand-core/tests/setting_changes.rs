@@ -0,0 +1,81 @@
+// Inline setting changes written through the ActionExecutor and the ConfigStore
+use std::sync::Arc;
+use chrono::Utc;
+use serde_json::Value;
+
+use falcommand_config::{Action, Config, ResultRowLayout};
+use falcommand_core::{ActionExecutor, ActionScheduler, ConfigStore, ManualClock, SearchSource, SettingsSource};
+use falcommand_plugin_testkit::RecordingPlatform;
+
+async fn executor(dir: &std::path::Path) -> (ActionExecutor, Arc<ConfigStore>) {
+    let clock = Arc::new(ManualClock::new(Utc::now()));
+    let scheduler = Arc::new(ActionScheduler::with_storage_path(dir.join("scheduled.json"), clock).await.unwrap());
+    let config_store = Arc::new(ConfigStore::new(Config::default(), dir.join("config.json")));
+    let executor = ActionExecutor::new(Arc::new(RecordingPlatform::new()), scheduler).with_config_store(config_store.clone());
+    (executor, config_store)
+}
+
+// The Toggle offered by a boolean setting's result is saved and shows in the next search
+#[tokio::test]
+async fn toggling_a_setting_saves_it_and_updates_its_result() {
+    let dir = tempfile::tempdir().unwrap();
+    let (executor, config_store) = executor(dir.path()).await;
+    let source = SettingsSource::new(config_store.shared());
+
+    let result = source.search("auto hide").await.into_iter()
+        .find(|result| result.title.starts_with("Setting: Auto hide"))
+        .unwrap();
+    assert_eq!(result.title, "Setting: Auto hide (On)");
+    executor.execute(&result.secondary_actions[0].action).await.unwrap();
+
+    assert!(!config_store.snapshot().await.behavior.auto_hide);
+    assert!(!Config::load_from_file(dir.path().join("config.json")).await.unwrap().behavior.auto_hide);
+    let titles: Vec<String> = source.search("auto hide").await.into_iter().map(|result| result.title).collect();
+    assert!(titles.contains(&"Setting: Auto hide (Off)".to_string()), "{:?}", titles);
+}
+
+#[tokio::test]
+async fn enum_settings_are_written_by_name() {
+    let dir = tempfile::tempdir().unwrap();
+    let (executor, config_store) = executor(dir.path()).await;
+    let action = Action::SetSetting { path: "appearance.result_row_layout".to_string(), value: Value::from("Detailed") };
+    executor.execute(&action).await.unwrap();
+    assert_eq!(config_store.snapshot().await.appearance.result_row_layout, ResultRowLayout::Detailed);
+}
+
+// Rejected values fail the action with the reason and leave the configuration alone
+#[tokio::test]
+async fn invalid_values_are_reported_and_not_saved() {
+    let dir = tempfile::tempdir().unwrap();
+    let (executor, config_store) = executor(dir.path()).await;
+
+    let out_of_range = Action::SetSetting { path: "behavior.max_results".to_string(), value: Value::from(0) };
+    assert!(executor.execute(&out_of_range).await.is_err());
+    let wrong_type = Action::SetSetting { path: "behavior.auto_hide".to_string(), value: Value::from("yes") };
+    let error = executor.execute(&wrong_type).await.unwrap_err().to_string();
+    assert!(error.contains("Invalid value for behavior.auto_hide"), "{}", error);
+    let unknown = Action::SetSetting { path: "behavior.autohide".to_string(), value: Value::Bool(false) };
+    assert!(executor.execute(&unknown).await.unwrap_err().to_string().contains("Unknown setting: behavior.autohide"));
+
+    let config = config_store.snapshot().await;
+    assert_eq!((config.behavior.max_results, config.behavior.auto_hide), (10, true));
+    assert!(!dir.path().join("config.json").exists());
+}
+
+// A saved macro replaces one spelled differently; one using another macro is refused
+#[tokio::test]
+async fn query_macros_are_saved_unless_they_use_macros() {
+    let dir = tempfile::tempdir().unwrap();
+    let (executor, config_store) = executor(dir.path()).await;
+    let save = |name: &str, template: &str| Action::SaveQueryMacro { name: name.to_string(), template: template.to_string() };
+
+    executor.execute(&save("Inv", "ext:pdf {args}")).await.unwrap();
+    executor.execute(&save("inv", "in:~/work ext:pdf invoice {args}")).await.unwrap();
+    let error = executor.execute(&save("tax", "@inv tax")).await.unwrap_err().to_string();
+    assert!(error.contains("macros can't use other macros"), "{}", error);
+
+    let saved = Config::load_from_file(dir.path().join("config.json")).await.unwrap().search.query_macros;
+    assert_eq!(saved.len(), 1);
+    assert_eq!(saved["inv"], "in:~/work ext:pdf invoice {args}");
+    assert_eq!(config_store.snapshot().await.search.query_macros, saved);
+}
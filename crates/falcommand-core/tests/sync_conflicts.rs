@@ -0,0 +1,45 @@
+// Sync conflicts answered from their search results, through the ActionExecutor as the window runs them
+use std::path::Path;
+use std::sync::Arc;
+use chrono::Utc;
+
+use falcommand_config::{Config, PathResolver};
+use falcommand_core::{
+    ActionExecutor, ActionScheduler, ConfigStore, FolderProvider, ManualClock, SearchSource, SyncCategory,
+    SyncConflictSource, SyncManager,
+};
+use falcommand_plugin_testkit::RecordingPlatform;
+
+async fn machine(dir: &Path, folder: &Path, font_size: u32) -> (Arc<SyncManager>, Arc<ConfigStore>) {
+    let mut config = Config::default();
+    config.sync.enabled = true;
+    config.appearance.font_size = font_size;
+    let config_store = Arc::new(ConfigStore::new(config, dir.join("config.json")));
+    let resolver = PathResolver::with_roots(dir.join("config"), dir.join("data"), None);
+    let manager = SyncManager::new(config_store.shared(), &resolver).await.unwrap()
+        .with_config_store(config_store.clone())
+        .with_cloud_provider(Arc::new(FolderProvider::new(folder.to_path_buf())));
+    (Arc::new(manager), config_store)
+}
+
+#[tokio::test]
+async fn keeping_the_cloud_copy_from_its_result_resolves_the_conflict() {
+    let (a_dir, b_dir, folder) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+    let (a, _) = machine(a_dir.path(), folder.path(), 18).await;
+    a.mark_local_change(SyncCategory::Settings).await;
+    a.sync_up().await.unwrap();
+
+    let (b, config_store) = machine(b_dir.path(), folder.path(), 14).await;
+    b.mark_local_change(SyncCategory::Settings).await;
+    b.sync_down().await.unwrap();
+
+    let scheduler = Arc::new(ActionScheduler::with_storage_path(b_dir.path().join("scheduled.json"), Arc::new(ManualClock::new(Utc::now()))).await.unwrap());
+    let executor = ActionExecutor::new(Arc::new(RecordingPlatform::new()), scheduler).with_sync_manager(b.clone());
+    let result = SyncConflictSource::new(b.clone()).search("sync conflicts").await.remove(0);
+    executor.execute(&result.secondary_actions[0].action).await.unwrap();
+
+    assert!(b.get_sync_status().await.pending_conflicts.is_empty());
+    assert_eq!(config_store.snapshot().await.appearance.font_size, 18);
+    // Nothing left to answer, so the same action now fails
+    assert!(executor.execute(&result.action).await.is_err());
+}
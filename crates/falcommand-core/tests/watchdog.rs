@@ -0,0 +1,64 @@
+// The running UI watchdog against a fake heartbeat: notifications, stall metrics and the restart
+// callback
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use falcommand_config::WatchdogConfig;
+use falcommand_core::{HeartbeatSource, LatencyMetrics, UiWatchdog, UI_STALL};
+use falcommand_plugin_testkit::RecordingPlatform;
+
+#[derive(Default)]
+struct FakeHeartbeat {
+    silence: Mutex<Duration>,
+}
+
+impl FakeHeartbeat {
+    fn silent_for(&self, millis: u64) {
+        *self.silence.lock().unwrap() = Duration::from_millis(millis);
+    }
+}
+
+impl HeartbeatSource for FakeHeartbeat {
+    fn since_last_beat(&self) -> Duration {
+        *self.silence.lock().unwrap()
+    }
+}
+
+async fn wait_for(condition: impl Fn() -> bool) {
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while !condition() {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    }).await.expect("condition not reached");
+}
+
+#[tokio::test]
+async fn repeated_stalls_notify_record_and_restart_the_window() {
+    let heartbeat = Arc::new(FakeHeartbeat::default());
+    let platform = Arc::new(RecordingPlatform::new());
+    let metrics = Arc::new(LatencyMetrics::new());
+    let restarts = Arc::new(AtomicUsize::new(0));
+    let config = WatchdogConfig { stall_threshold_ms: 40, auto_restart_ui: true };
+
+    let counted = restarts.clone();
+    let watchdog = UiWatchdog::new(heartbeat.clone(), platform.clone(), metrics.clone(), config)
+        .with_restart(Arc::new(move || {
+            counted.fetch_add(1, Ordering::SeqCst);
+        }));
+    Arc::new(watchdog).start();
+
+    for stall in 1..=3 {
+        heartbeat.silent_for(1500);
+        wait_for(|| platform.notifications().len() == stall).await;
+        heartbeat.silent_for(0);
+        wait_for(|| metrics.samples(UI_STALL).len() == stall).await;
+        // Only the third stall in the window restarts the window
+        assert_eq!(restarts.load(Ordering::SeqCst), usize::from(stall == 3), "after stall {}", stall);
+    }
+
+    let notification = &platform.notifications()[0];
+    assert_eq!(notification.title, "FalCommand is not responding");
+    assert_eq!(notification.message, "The window has not responded for 1 seconds");
+    assert_eq!(metrics.samples(UI_STALL), vec![Duration::from_millis(1500); 3]);
+}
@@ -0,0 +1,42 @@
+/*
+ * Searches the FalCommand index from C.
+ *
+ *   cargo build -p falcommand-ffi --release
+ *   cc examples/ffi_search.c -Iinclude -L../../target/release -lfalcommand_ffi -o ffi_search
+ *   ./ffi_search firefox
+ *
+ * Linking the static library may also need -lpthread -ldl -lm on Linux.
+ */
+#include <stdio.h>
+
+#include "falcommand.h"
+
+int main(int argc, char **argv) {
+    const char *query = argc > 1 ? argv[1] : "terminal";
+
+    /* Default config; index, history and usage in ./ffi-data */
+    FcEngine *engine = fc_engine_new(NULL, "ffi-data");
+    if (engine == NULL) {
+        fprintf(stderr, "could not create the engine\n");
+        return 1;
+    }
+
+    FcStatus status = fc_engine_rebuild_index(engine);
+    if (status != FC_STATUS_OK) {
+        fprintf(stderr, "index rebuild failed (%d)\n", status);
+        fc_engine_free(engine);
+        return 1;
+    }
+
+    char *json = NULL;
+    status = fc_engine_search(engine, query, &json);
+    if (status == FC_STATUS_OK) {
+        printf("%s\n", json);
+    } else {
+        fprintf(stderr, "search failed (%d)\n", status);
+    }
+
+    fc_string_free(json);
+    fc_engine_free(engine);
+    return status == FC_STATUS_OK ? 0 : 1;
+}
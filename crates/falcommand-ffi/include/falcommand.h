@@ -0,0 +1,65 @@
+#ifndef FALCOMMAND_H
+#define FALCOMMAND_H
+
+/* Generated by cbindgen from src/lib.rs; do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef enum FcStatus {
+  FC_STATUS_OK = 0,
+  // A null handle or output pointer, or a string that isn't UTF-8
+  FC_STATUS_INVALID_ARGUMENT = 1,
+  FC_STATUS_FAILED = 2,
+  // The call panicked; the handle stays usable
+  FC_STATUS_PANICKED = 3,
+} FcStatus;
+
+typedef struct FcEngine FcEngine;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Creates an engine. `config_json` is a FalCommand config; null uses the defaults. `data_dir`
+// holds the index cache, history and usage; null keeps them in the "embedded" profile of the
+// FalCommand data directory. Returns null on failure.
+//
+// # Safety
+// Both arguments must be null or point to NUL-terminated strings.
+struct FcEngine *fc_engine_new(const char *config_json, const char *data_dir);
+
+// Rebuilds the application and file index, blocking until it is done.
+//
+// # Safety
+// `engine` must be null or a handle from fc_engine_new that hasn't been freed.
+enum FcStatus fc_engine_rebuild_index(const struct FcEngine *engine);
+
+// Searches `query` and stores the results as a JSON array in `*out_json`, to be freed with
+// fc_string_free. `*out_json` is set to null when the call fails.
+//
+// # Safety
+// `engine` must be null or a live handle, `query` null or a NUL-terminated string, and
+// `out_json` null or writable.
+enum FcStatus fc_engine_search(const struct FcEngine *engine, const char *query, char **out_json);
+
+// Frees a string returned by the library. Null is ignored.
+//
+// # Safety
+// `text` must be null or a string from this library that hasn't been freed.
+void fc_string_free(char *text);
+
+// Frees an engine. Null is ignored.
+//
+// # Safety
+// `engine` must be null or a handle from fc_engine_new that hasn't been freed, and no other
+// thread may use it afterwards.
+void fc_engine_free(struct FcEngine *engine);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* FALCOMMAND_H */
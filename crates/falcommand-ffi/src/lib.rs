@@ -0,0 +1,319 @@
+// C ABI for embedding indexing and search without the launcher. The header is generated from this
+// file: cbindgen --config cbindgen.toml --output include/falcommand.h
+//
+// Threads: an engine handle may be moved to and used from any thread, and from several at once;
+// calls on one handle are serialized, each blocking until the previous one returns. A handle must
+// not be used after fc_engine_free. Strings returned by the library are freed with fc_string_free
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use log::{error, warn};
+
+use falcommand_config::{Config, PathResolver};
+use falcommand_platform::{create_platform_provider, PlatformProvider};
+use falcommand_core::{IndexManager, SearchEngine, SearchHistory, SystemClock, UsageStore};
+
+// Returned by every call that can fail
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FcStatus {
+    Ok = 0,
+    // A null handle or output pointer, or a string that isn't UTF-8
+    InvalidArgument = 1,
+    Failed = 2,
+    // The call panicked; the handle stays usable
+    Panicked = 3,
+}
+
+struct Engine {
+    search_engine: Arc<SearchEngine>,
+    index_manager: Arc<IndexManager>,
+    platform_provider: Arc<dyn PlatformProvider>,
+}
+
+// Owns the runtime the async internals run on, so callers never see them
+pub struct FcEngine {
+    runtime: tokio::runtime::Runtime,
+    engine: Mutex<Engine>,
+}
+
+impl FcEngine {
+    // Profile whose data the engine keeps when no data directory is given, away from the launcher's
+    const PROFILE: &'static str = "embedded";
+
+    fn new(config: Config, data_dir: Option<PathBuf>) -> Result<Self, String> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .thread_name("falcommand-ffi")
+            .enable_all()
+            .build()
+            .map_err(|e| format!("Failed to start the runtime: {}", e))?;
+        let resolver = match data_dir {
+            Some(dir) => PathResolver::with_roots(dir.clone(), dir, None),
+            None => PathResolver::new(Some(Self::PROFILE.to_string())).map_err(|e| e.to_string())?,
+        };
+
+        let engine = runtime.block_on(async {
+            let config = Arc::new(tokio::sync::RwLock::new(config));
+            let clock = Arc::new(SystemClock);
+            let index_manager = Arc::new(
+                IndexManager::new(config.clone()).await.map_err(|e| e.to_string())?
+                    .with_cache_path(resolver.data_file("index.json"))
+            );
+            index_manager.load_cache().await;
+            let history = Arc::new(SearchHistory::new(&resolver, clock.clone()).await.map_err(|e| e.to_string())?);
+            let usage = Arc::new(UsageStore::new(&resolver, clock).await.map_err(|e| e.to_string())?);
+            let search_engine = SearchEngine::new(config, index_manager.clone(), history, usage).await
+                .map_err(|e| e.to_string())?;
+            Ok::<_, String>(Engine {
+                search_engine: Arc::new(search_engine),
+                index_manager,
+                platform_provider: create_platform_provider(),
+            })
+        })?;
+        Ok(Self { runtime, engine: Mutex::new(engine) })
+    }
+
+    // A call that panicked leaves the lock poisoned; the engine itself is still consistent
+    fn engine(&self) -> std::sync::MutexGuard<'_, Engine> {
+        self.engine.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn rebuild_index(&self) -> Result<(), String> {
+        let engine = self.engine();
+        self.runtime.block_on(engine.index_manager.rebuild_index(engine.platform_provider.clone()))
+            .map_err(|e| e.to_string())
+    }
+
+    fn search(&self, query: &str) -> Result<String, String> {
+        let engine = self.engine();
+        let results = self.runtime.block_on(engine.search_engine.search(query));
+        serde_json::to_string(&results).map_err(|e| e.to_string())
+    }
+}
+
+// Runs `call` with panics turned into FcStatus::Panicked
+fn guarded(name: &str, call: impl FnOnce() -> FcStatus) -> FcStatus {
+    catch_unwind(AssertUnwindSafe(call)).unwrap_or_else(|_| {
+        error!("{} panicked", name);
+        FcStatus::Panicked
+    })
+}
+
+// Read the way the launcher reads its config file, legacy fields included
+fn parse_config(json: &str) -> Result<Config, String> {
+    let mut config: Config = serde_json::from_str(json).map_err(|e| format!("Invalid config: {}", e))?;
+    config.migrate();
+    config.validate().map_err(|e| e.to_string())?;
+    Ok(config)
+}
+
+// A borrowed C string; None for null or non-UTF-8
+unsafe fn str_arg<'a>(text: *const c_char) -> Option<&'a str> {
+    if text.is_null() {
+        return None;
+    }
+    CStr::from_ptr(text).to_str().ok()
+}
+
+/// Creates an engine. `config_json` is a FalCommand config; null uses the defaults. `data_dir`
+/// holds the index cache, history and usage; null keeps them in the "embedded" profile of the
+/// FalCommand data directory. Returns null on failure.
+///
+/// # Safety
+/// Both arguments must be null or point to NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn fc_engine_new(config_json: *const c_char, data_dir: *const c_char) -> *mut FcEngine {
+    let created = catch_unwind(AssertUnwindSafe(|| {
+        let config = if config_json.is_null() {
+            Config::default()
+        } else {
+            parse_config(str_arg(config_json).ok_or("The config is not UTF-8")?)?
+        };
+        let data_dir = if data_dir.is_null() {
+            None
+        } else {
+            Some(PathBuf::from(str_arg(data_dir).ok_or("The data directory is not UTF-8")?))
+        };
+        FcEngine::new(config, data_dir)
+    }));
+
+    match created {
+        Ok(Ok(engine)) => Box::into_raw(Box::new(engine)),
+        Ok(Err(e)) => {
+            warn!("Failed to create the engine: {}", e);
+            std::ptr::null_mut()
+        }
+        Err(_) => {
+            error!("fc_engine_new panicked");
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// Rebuilds the application and file index, blocking until it is done.
+///
+/// # Safety
+/// `engine` must be null or a handle from fc_engine_new that hasn't been freed.
+#[no_mangle]
+pub unsafe extern "C" fn fc_engine_rebuild_index(engine: *const FcEngine) -> FcStatus {
+    let Some(engine) = engine.as_ref() else {
+        return FcStatus::InvalidArgument;
+    };
+    guarded("fc_engine_rebuild_index", || match engine.rebuild_index() {
+        Ok(()) => FcStatus::Ok,
+        Err(e) => {
+            warn!("Index rebuild failed: {}", e);
+            FcStatus::Failed
+        }
+    })
+}
+
+/// Searches `query` and stores the results as a JSON array in `*out_json`, to be freed with
+/// fc_string_free. `*out_json` is set to null when the call fails.
+///
+/// # Safety
+/// `engine` must be null or a live handle, `query` null or a NUL-terminated string, and
+/// `out_json` null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn fc_engine_search(engine: *const FcEngine, query: *const c_char, out_json: *mut *mut c_char) -> FcStatus {
+    if out_json.is_null() {
+        return FcStatus::InvalidArgument;
+    }
+    *out_json = std::ptr::null_mut();
+    let (Some(engine), Some(query)) = (engine.as_ref(), str_arg(query)) else {
+        return FcStatus::InvalidArgument;
+    };
+
+    guarded("fc_engine_search", || match engine.search(query).map(CString::new) {
+        Ok(Ok(json)) => {
+            *out_json = json.into_raw();
+            FcStatus::Ok
+        }
+        Ok(Err(e)) => {
+            warn!("Search results contain a NUL byte: {}", e);
+            FcStatus::Failed
+        }
+        Err(e) => {
+            warn!("Search failed: {}", e);
+            FcStatus::Failed
+        }
+    })
+}
+
+/// Frees a string returned by the library. Null is ignored.
+///
+/// # Safety
+/// `text` must be null or a string from this library that hasn't been freed.
+#[no_mangle]
+pub unsafe extern "C" fn fc_string_free(text: *mut c_char) {
+    if !text.is_null() {
+        drop(CString::from_raw(text));
+    }
+}
+
+/// Frees an engine. Null is ignored.
+///
+/// # Safety
+/// `engine` must be null or a handle from fc_engine_new that hasn't been freed, and no other
+/// thread may use it afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn fc_engine_free(engine: *mut FcEngine) {
+    if engine.is_null() {
+        return;
+    }
+    let engine = Box::from_raw(engine);
+    // Dropping the runtime inside another runtime panics, so it never unwinds into C
+    if catch_unwind(AssertUnwindSafe(|| drop(engine))).is_err() {
+        error!("fc_engine_free panicked");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use falcommand_config::SearchResult;
+    use falcommand_core::SearchSource;
+
+    // Stands in for a bug anywhere below the boundary
+    struct PanickingSource;
+
+    #[async_trait]
+    impl SearchSource for PanickingSource {
+        fn name(&self) -> &str {
+            "Panicking"
+        }
+
+        async fn search(&self, query: &str) -> Vec<SearchResult> {
+            if query == "boom" {
+                panic!("search source bug");
+            }
+            Vec::new()
+        }
+    }
+
+    fn new_engine(data_dir: &tempfile::TempDir) -> *mut FcEngine {
+        let data_dir = CString::new(data_dir.path().to_str().unwrap()).unwrap();
+        let engine = unsafe { fc_engine_new(std::ptr::null(), data_dir.as_ptr()) };
+        assert!(!engine.is_null());
+        engine
+    }
+
+    unsafe fn search(engine: *const FcEngine, query: &str) -> (FcStatus, Option<String>) {
+        let query = CString::new(query).unwrap();
+        let mut json = std::ptr::null_mut();
+        let status = fc_engine_search(engine, query.as_ptr(), &mut json);
+        let text = (!json.is_null()).then(|| CStr::from_ptr(json).to_str().unwrap().to_string());
+        fc_string_free(json);
+        (status, text)
+    }
+
+    #[test]
+    fn a_panic_in_search_becomes_an_error_code() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let engine = new_engine(&data_dir);
+        unsafe {
+            let handle = &*engine;
+            handle.runtime.block_on(handle.engine().search_engine.register_source(Arc::new(PanickingSource)));
+
+            assert_eq!(search(engine, "boom"), (FcStatus::Panicked, None));
+            // The handle stays usable after the panic
+            let (status, json) = search(engine, "calm");
+            assert_eq!(status, FcStatus::Ok);
+            assert!(json.unwrap().starts_with('['));
+            fc_engine_free(engine);
+        }
+    }
+
+    #[test]
+    fn null_and_invalid_arguments_are_rejected() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let engine = new_engine(&data_dir);
+        unsafe {
+            let mut json = std::ptr::null_mut();
+            assert_eq!(fc_engine_search(std::ptr::null(), c"x".as_ptr(), &mut json), FcStatus::InvalidArgument);
+            assert_eq!(fc_engine_search(engine, std::ptr::null(), &mut json), FcStatus::InvalidArgument);
+            assert_eq!(fc_engine_search(engine, c"x".as_ptr(), std::ptr::null_mut()), FcStatus::InvalidArgument);
+            assert!(json.is_null());
+            let not_utf8 = [0xffu8, 0];
+            assert_eq!(fc_engine_search(engine, not_utf8.as_ptr().cast(), &mut json), FcStatus::InvalidArgument);
+            assert_eq!(fc_engine_rebuild_index(std::ptr::null()), FcStatus::InvalidArgument);
+            assert!(fc_engine_new(c"{not json".as_ptr(), std::ptr::null()).is_null());
+
+            fc_string_free(std::ptr::null_mut());
+            fc_engine_free(std::ptr::null_mut());
+            fc_engine_free(engine);
+        }
+    }
+
+    // appearance.max_results from an older config still sets the rows
+    #[test]
+    fn legacy_configs_are_migrated() {
+        let mut saved = serde_json::to_value(Config::default()).unwrap();
+        saved["appearance"].as_object_mut().unwrap().remove("visible_result_rows");
+        saved["appearance"]["max_results"] = serde_json::json!(6);
+        assert_eq!(parse_config(&saved.to_string()).unwrap().appearance.visible_result_rows, 6);
+    }
+}
@@ -0,0 +1,82 @@
+// Builds examples/ffi_search.c against the cdylib with the cc crate and runs it, so the header, the
+// exported symbols and the example stay in step. Needs a C compiler; without one it is skipped.
+// Unix only: the link line below is the cc/clang one
+#![cfg(unix)]
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const MANIFEST_DIR: &str = env!("CARGO_MANIFEST_DIR");
+
+// cc only reads TARGET and HOST from a build script's environment; tests have to name it
+fn target_triple() -> String {
+    let arch = std::env::consts::ARCH;
+    if cfg!(target_os = "macos") {
+        format!("{}-apple-darwin", arch)
+    } else {
+        format!("{}-unknown-linux-gnu", arch)
+    }
+}
+
+// The cdylib cargo built next to this test's dependencies
+fn library_dir() -> PathBuf {
+    let deps = std::env::current_exe().unwrap().parent().unwrap().to_path_buf();
+    let name = format!("{}falcommand_ffi{}", std::env::consts::DLL_PREFIX, std::env::consts::DLL_SUFFIX);
+    [deps.clone(), deps.parent().unwrap().to_path_buf()]
+        .into_iter()
+        .find(|dir| dir.join(&name).exists())
+        .unwrap_or_else(|| panic!("{} was not built next to {}", name, deps.display()))
+}
+
+fn compile_example(output: &Path) -> Option<()> {
+    let target = target_triple();
+    let compiler = cc::Build::new()
+        .target(&target)
+        .host(&target)
+        .opt_level(0)
+        .cargo_metadata(false)
+        .try_get_compiler()
+        .ok()?;
+    let library_dir = library_dir();
+
+    let status = compiler.to_command()
+        .arg(Path::new(MANIFEST_DIR).join("examples/ffi_search.c"))
+        .arg("-I")
+        .arg(Path::new(MANIFEST_DIR).join("include"))
+        .arg("-L")
+        .arg(&library_dir)
+        .arg(format!("-Wl,-rpath,{}", library_dir.display()))
+        .arg("-lfalcommand_ffi")
+        .arg("-o")
+        .arg(output)
+        .status()
+        .ok()?;
+    assert!(status.success(), "examples/ffi_search.c failed to compile or link");
+    Some(())
+}
+
+#[test]
+fn c_example_searches_through_the_c_abi() {
+    let work_dir = tempfile::tempdir().unwrap();
+    let example = work_dir.path().join("ffi_search");
+    if compile_example(&example).is_none() {
+        eprintln!("No C compiler found; skipping the C example");
+        return;
+    }
+
+    // The example keeps its data in ./ffi-data; HOME points the default include paths at the
+    // empty work dir so the rebuild stays small. The rpath finds the cdylib, not whatever cargo's
+    // library path lists first
+    let output = Command::new(&example)
+        .arg("no-such-app")
+        .current_dir(work_dir.path())
+        .env("HOME", work_dir.path())
+        .env_remove("LD_LIBRARY_PATH")
+        .env_remove("DYLD_LIBRARY_PATH")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "ffi_search failed: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(stdout.trim_start().starts_with('['), "expected a JSON array, got {}", stdout);
+    assert!(work_dir.path().join("ffi-data").is_dir());
+}
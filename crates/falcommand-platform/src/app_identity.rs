@@ -0,0 +1,98 @@
+use std::path::Path;
+
+// Applications known by a different id on each platform, under one portable identity. Each
+// platform id is "<kind>:<id>" in lowercase, as built by the functions below
+const WELL_KNOWN_APPS: &[(&str, &[&str])] = &[
+    ("firefox", &[
+        "desktop:firefox",
+        "desktop:org.mozilla.firefox",
+        "desktop:firefox_firefox",
+        "bundle:org.mozilla.firefox",
+        "aumid:308046b0af4a39cb",
+        "product:mozilla firefox|mozilla",
+    ]),
+    ("thunderbird", &[
+        "desktop:thunderbird",
+        "desktop:org.mozilla.thunderbird",
+        "bundle:org.mozilla.thunderbird",
+        "product:mozilla thunderbird|mozilla",
+    ]),
+    ("google-chrome", &[
+        "desktop:google-chrome",
+        "bundle:com.google.chrome",
+        "aumid:chrome",
+        "product:google chrome|google llc",
+    ]),
+    ("vscode", &[
+        "desktop:code",
+        "desktop:com.visualstudio.code",
+        "desktop:code_code",
+        "bundle:com.microsoft.vscode",
+        "product:microsoft visual studio code|microsoft corporation",
+    ]),
+    ("vlc", &[
+        "desktop:vlc",
+        "desktop:org.videolan.vlc",
+        "bundle:org.videolan.vlc",
+        "product:vlc media player|videolan",
+    ]),
+    ("slack", &[
+        "desktop:slack",
+        "desktop:com.slack.slack",
+        "bundle:com.tinyspeck.slackmacgap",
+        "aumid:com.squirrel.slack.slack",
+        "product:slack|slack technologies inc.",
+    ]),
+    ("spotify", &[
+        "desktop:spotify",
+        "desktop:com.spotify.client",
+        "bundle:com.spotify.client",
+        "aumid:spotify",
+        "product:spotify|spotify ab",
+    ]),
+];
+
+// The portable identity for a platform id, or the platform id itself when the app isn't well known.
+// Either way the same app on another machine of the same platform gets the same identity
+fn portable_identity(platform_id: String) -> String {
+    WELL_KNOWN_APPS.iter()
+        .find(|(_, ids)| ids.contains(&platform_id.as_str()))
+        .map(|(identity, _)| identity.to_string())
+        .unwrap_or(platform_id)
+}
+
+// Linux: the desktop file id, "firefox" for firefox.desktop
+pub fn desktop_identity(desktop_file_id: &str) -> String {
+    portable_identity(format!("desktop:{}", desktop_file_id.to_lowercase()))
+}
+
+// macOS: the bundle identifier, "org.mozilla.firefox"
+pub fn bundle_identity(bundle_id: &str) -> String {
+    portable_identity(format!("bundle:{}", bundle_id.to_lowercase()))
+}
+
+// Windows: the AppUserModelID when the app has one, otherwise its product name and publisher
+pub fn windows_identity(app_user_model_id: Option<&str>, product: &str, publisher: &str) -> Option<String> {
+    match app_user_model_id {
+        Some(id) if !id.is_empty() => Some(portable_identity(format!("aumid:{}", id.to_lowercase()))),
+        _ if !product.is_empty() && !publisher.is_empty() => Some(portable_identity(format!(
+            "product:{}|{}",
+            product.to_lowercase(),
+            publisher.to_lowercase(),
+        ))),
+        _ => None,
+    }
+}
+
+// CFBundleIdentifier from an XML Info.plist; binary plists aren't read
+pub fn plist_bundle_id(content: &str) -> Option<String> {
+    let after_key = &content[content.find("<key>CFBundleIdentifier</key>")? + "<key>CFBundleIdentifier</key>".len()..];
+    let value = after_key.trim_start().strip_prefix("<string>")?;
+    let id = value[..value.find("</string>")?].trim();
+    (!id.is_empty()).then(|| id.to_string())
+}
+
+pub fn read_bundle_id(bundle: &Path) -> Option<String> {
+    let content = std::fs::read_to_string(bundle.join("Contents").join("Info.plist")).ok()?;
+    plist_bundle_id(&content)
+}
@@ -0,0 +1,151 @@
+use std::sync::RwLock;
+use serde::{Deserialize, Serialize};
+use log::info;
+
+use crate::platform::{AppInfo, PlatformError};
+
+// One sub-source of an application scan that worked, and how many apps it found
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceScan {
+    pub source: String,
+    pub app_count: usize,
+}
+
+// A sub-source that failed; the apps of the others are still used
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanWarning {
+    pub source: String,
+    pub error: String,
+}
+
+impl std::fmt::Display for ScanWarning {
+    // "Start Menu scan failed: access denied"
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} scan failed: {}", self.source, self.error)
+    }
+}
+
+// Per-source outcome of the latest application scan
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanReport {
+    pub scanned: Vec<SourceScan>,
+    pub warnings: Vec<ScanWarning>,
+}
+
+impl ScanReport {
+    // Nothing to index; callers keep the apps they had rather than emptying the index
+    pub fn all_failed(&self) -> bool {
+        self.scanned.is_empty() && !self.warnings.is_empty()
+    }
+
+    pub fn summary(&self) -> String {
+        self.warnings.iter().map(ToString::to_string).collect::<Vec<_>>().join("; ")
+    }
+}
+
+pub type ScanFn<'a, T> = Box<dyn FnOnce() -> Result<Vec<T>, PlatformError> + Send + 'a>;
+
+// Runs each named scan on its own thread. Returns the items of every source in the order given,
+// empty for sources that failed, and the report. A panicking source counts as failed
+pub fn scan_sources<'a, T: Send>(sources: Vec<(String, ScanFn<'a, T>)>) -> (Vec<Vec<T>>, ScanReport) {
+    let outcomes: Vec<(String, Result<Vec<T>, String>)> = std::thread::scope(|scope| {
+        let handles: Vec<_> = sources.into_iter()
+            .map(|(name, scan)| (name, scope.spawn(scan)))
+            .collect();
+        handles.into_iter()
+            .map(|(name, handle)| {
+                let outcome = match handle.join() {
+                    Ok(result) => result.map_err(|e| e.to_string()),
+                    Err(_) => Err("the scan panicked".to_string()),
+                };
+                (name, outcome)
+            })
+            .collect()
+    });
+
+    let mut report = ScanReport::default();
+    let mut items = Vec::with_capacity(outcomes.len());
+    for (source, outcome) in outcomes {
+        match outcome {
+            Ok(found) => {
+                info!("{}: {} applications", source, found.len());
+                report.scanned.push(SourceScan { source, app_count: found.len() });
+                items.push(found);
+            }
+            Err(error) => {
+                report.warnings.push(ScanWarning { source, error });
+                items.push(Vec::new());
+            }
+        }
+    }
+    (items, report)
+}
+
+// Keeps `report` for last_scan_report(). Fails only when no source worked, so the caller keeps
+// the apps it already had instead of indexing none
+pub fn finish_scan(slot: &RwLock<ScanReport>, apps: Vec<AppInfo>, report: ScanReport) -> Result<Vec<AppInfo>, PlatformError> {
+    let all_failed = report.all_failed();
+    let summary = report.summary();
+    *slot.write().unwrap() = report;
+    if all_failed {
+        return Err(PlatformError::ApplicationScanError(summary));
+    }
+    Ok(apps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn apps(names: &[&str]) -> Vec<AppInfo> {
+        names.iter().map(|name| AppInfo::new(*name, PathBuf::from(format!("C:\\Apps\\{}.exe", name)))).collect()
+    }
+
+    fn names(apps: &[AppInfo]) -> Vec<&str> {
+        apps.iter().map(|app| app.name.as_str()).collect()
+    }
+
+    #[test]
+    fn failing_sources_are_reported_and_the_others_kept() {
+        let sources: Vec<(String, ScanFn<'_, AppInfo>)> = vec![
+            ("Registry".to_string(), Box::new(|| Ok(apps(&["Firefox", "Slack"])))),
+            ("Start Menu".to_string(), Box::new(|| Err(PlatformError::FileSystemError("access denied".to_string())))),
+            ("Program Files".to_string(), Box::new(|| panic!("malformed shortcut"))),
+            ("Store".to_string(), Box::new(|| Ok(apps(&["Notepad"])))),
+        ];
+        let (found, report) = scan_sources(sources);
+
+        // One list per source, in order, empty where it failed
+        assert_eq!(found.iter().map(|apps| names(apps)).collect::<Vec<_>>(), [vec!["Firefox", "Slack"], vec![], vec![], vec!["Notepad"]]);
+        assert_eq!(report.scanned, [
+            SourceScan { source: "Registry".to_string(), app_count: 2 },
+            SourceScan { source: "Store".to_string(), app_count: 1 },
+        ]);
+        assert_eq!(report.summary(), "Start Menu scan failed: File system error: access denied; Program Files scan failed: the scan panicked");
+        assert!(!report.all_failed());
+
+        let slot = RwLock::new(ScanReport::default());
+        let kept = finish_scan(&slot, found.concat(), report.clone()).unwrap();
+        assert_eq!(names(&kept), ["Firefox", "Slack", "Notepad"]);
+        assert_eq!(*slot.read().unwrap(), report);
+    }
+
+    #[test]
+    fn a_scan_fails_only_when_every_source_did() {
+        let sources: Vec<(String, ScanFn<'_, AppInfo>)> = vec![
+            ("Registry".to_string(), Box::new(|| Err(PlatformError::ApplicationScanError("no access".to_string())))),
+        ];
+        let (found, report) = scan_sources(sources);
+        assert!(report.all_failed());
+
+        let slot = RwLock::new(ScanReport::default());
+        assert!(matches!(finish_scan(&slot, found.concat(), report), Err(PlatformError::ApplicationScanError(_))));
+        assert_eq!(slot.read().unwrap().warnings.len(), 1);
+
+        // No sources at all is an empty scan, not a failure
+        let (found, report) = scan_sources::<AppInfo>(Vec::new());
+        assert!(finish_scan(&slot, found.concat(), report).unwrap().is_empty());
+        assert_eq!(*slot.read().unwrap(), ScanReport::default());
+    }
+}
@@ -0,0 +1,339 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use log::{debug, info};
+
+use crate::platform::{AppInfo, PlatformError};
+use crate::app_scan::{scan_sources, ScanFn, ScanReport};
+use crate::app_identity::desktop_identity;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sandbox {
+    None,
+    Flatpak,
+    Snap,
+}
+
+impl Sandbox {
+    // FLATPAK_ID is set inside every Flatpak; SNAP points at the mounted snap
+    pub fn detect_with<F>(env: F, flatpak_info_exists: bool) -> Self
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        if env("FLATPAK_ID").is_some() || flatpak_info_exists {
+            Sandbox::Flatpak
+        } else if env("SNAP").is_some() && env("SNAP_NAME").is_some() {
+            Sandbox::Snap
+        } else {
+            Sandbox::None
+        }
+    }
+
+    pub fn detect() -> Self {
+        Self::detect_with(|name| std::env::var(name).ok(), Path::new("/.flatpak-info").exists())
+    }
+
+    // Inside a Flatpak the host's /usr and /etc are only visible under /run/host
+    pub fn host_path(&self, path: &str) -> PathBuf {
+        match self {
+            Sandbox::Flatpak if path.starts_with("/usr") || path.starts_with("/etc") => {
+                PathBuf::from("/run/host").join(path.trim_start_matches('/'))
+            }
+            _ => PathBuf::from(path),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DesktopEntry {
+    pub id: String,
+    pub name: String,
+    pub exec: Vec<String>,
+    pub icon: Option<String>,
+    pub comment: Option<String>,
+    pub keywords: Vec<String>,
+}
+
+impl DesktopEntry {
+    pub fn to_app_info(&self, sandbox: Sandbox) -> Option<AppInfo> {
+        let mut command = self.exec.clone();
+        // Host applications have to be started outside the sandbox
+        if sandbox == Sandbox::Flatpak {
+            command.splice(0..0, ["flatpak-spawn".to_string(), "--host".to_string()]);
+        }
+
+        let (program, args) = command.split_first()?;
+        let mut app = AppInfo::new(self.name.clone(), PathBuf::from(program))
+            .with_args(args.to_vec())
+            .with_keywords(self.keywords.clone())
+            .with_identity(desktop_identity(&self.id));
+        if let Some(ref comment) = self.comment {
+            app = app.with_description(comment.clone());
+        }
+        if let Some(ref icon) = self.icon {
+            app = app.with_icon(PathBuf::from(icon));
+        }
+        Some(app)
+    }
+}
+
+// Applications directories in XDG precedence order, followed by the Flatpak and Snap export dirs
+pub fn application_dirs<F>(env: F, home: Option<&Path>, sandbox: Sandbox) -> Vec<PathBuf>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut dirs = Vec::new();
+
+    let data_home = env("XDG_DATA_HOME")
+        .filter(|d| !d.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| home.map(|h| h.join(".local/share")));
+    if let Some(data_home) = data_home {
+        dirs.push(data_home.join("applications"));
+        dirs.push(data_home.join("flatpak/exports/share/applications"));
+    }
+
+    // Inside a sandbox XDG_DATA_DIRS describes the sandbox, not the host
+    let data_dirs = match sandbox {
+        Sandbox::None => env("XDG_DATA_DIRS").filter(|d| !d.is_empty()),
+        Sandbox::Flatpak | Sandbox::Snap => None,
+    }
+    .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string());
+    for dir in data_dirs.split(':').filter(|d| !d.is_empty()) {
+        dirs.push(sandbox.host_path(dir).join("applications"));
+    }
+
+    dirs.push(PathBuf::from("/var/lib/flatpak/exports/share/applications"));
+    dirs.push(PathBuf::from("/var/lib/snapd/desktop/applications"));
+
+    let mut seen = HashSet::new();
+    dirs.retain(|dir| seen.insert(dir.clone()));
+    dirs
+}
+
+// Splits an Exec line on whitespace, honouring double quotes and backslash escapes inside them
+fn split_exec(exec: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut has_token = false;
+    let mut chars = exec.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                has_token = true;
+            }
+            '\\' if in_quotes => {
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if has_token {
+                    args.push(std::mem::take(&mut current));
+                    has_token = false;
+                }
+            }
+            c => {
+                current.push(c);
+                has_token = true;
+            }
+        }
+    }
+    if has_token {
+        args.push(current);
+    }
+    args
+}
+
+// Drops %f/%U-style field codes and the @@/@@u file-forwarding markers Flatpak exports add
+pub fn parse_exec(exec: &str) -> Vec<String> {
+    split_exec(exec)
+        .into_iter()
+        .filter(|arg| !matches!(arg.as_str(), "@@" | "@@u" | "@@f"))
+        .filter(|arg| !(arg.len() == 2 && arg.starts_with('%') && arg != "%%"))
+        .map(|arg| arg.replace("%%", "%"))
+        .collect()
+}
+
+// Exported files are named after the app id ("org.mozilla.firefox.desktop", "firefox_firefox.desktop")
+pub fn desktop_file_id(path: &Path) -> Option<String> {
+    let name = path.file_name()?.to_str()?;
+    name.strip_suffix(".desktop").map(|id| id.to_string())
+}
+
+pub fn parse_desktop_entry(content: &str, id: &str) -> Option<DesktopEntry> {
+    let mut in_entry = false;
+    let mut name = None;
+    let mut exec = None;
+    let mut icon = None;
+    let mut comment = None;
+    let mut keywords = Vec::new();
+    let mut is_application = false;
+    let mut hidden = false;
+
+    for line in content.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_entry = line == "[Desktop Entry]";
+            continue;
+        }
+        if !in_entry || line.starts_with('#') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+
+        // Localized keys such as Name[ja] are skipped in favour of the untranslated value
+        match key.trim() {
+            "Type" => is_application = value.trim() == "Application",
+            "Name" => name = Some(value.trim().to_string()),
+            "Exec" => exec = Some(parse_exec(value.trim())),
+            "Icon" => icon = Some(value.trim().to_string()),
+            "Comment" => comment = Some(value.trim().to_string()),
+            "Keywords" => keywords = value.split(';').map(str::trim).filter(|k| !k.is_empty()).map(String::from).collect(),
+            "NoDisplay" | "Hidden" => hidden |= value.trim() == "true",
+            _ => {}
+        }
+    }
+
+    if !is_application || hidden {
+        return None;
+    }
+
+    let exec = exec.filter(|e| !e.is_empty())?;
+    Some(DesktopEntry {
+        id: id.to_string(),
+        name: name?,
+        exec,
+        icon,
+        comment,
+        keywords,
+    })
+}
+
+// Applications of one directory with their desktop file ids. A missing directory has none;
+// one that can't be read is an error
+pub fn scan_application_dir(dir: &Path, sandbox: Sandbox) -> Result<Vec<(String, AppInfo)>, PlatformError> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(PlatformError::FileSystemError(e.to_string())),
+    };
+    debug!("Scanning desktop entries in {}", dir.display());
+
+    let mut paths: Vec<PathBuf> = entries.filter_map(|e| e.ok()).map(|e| e.path()).collect();
+    paths.sort();
+
+    let mut apps = Vec::new();
+    for path in paths {
+        let Some(id) = desktop_file_id(&path) else {
+            continue;
+        };
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        if let Some(app) = parse_desktop_entry(&content, &id).and_then(|entry| entry.to_app_info(sandbox)) {
+            apps.push((id, app));
+        }
+    }
+    Ok(apps)
+}
+
+// Earlier directories win when the same desktop file id appears more than once
+pub fn merge_desktop_apps(dirs: Vec<Vec<(String, AppInfo)>>) -> Vec<AppInfo> {
+    let mut seen_ids = HashSet::new();
+    dirs.into_iter()
+        .flatten()
+        .filter(|(id, _)| seen_ids.insert(id.clone()))
+        .map(|(_, app)| app)
+        .collect()
+}
+
+// Every directory is scanned concurrently; an unreadable one is reported and the others still count
+pub fn scan_application_dirs(dirs: &[PathBuf], sandbox: Sandbox) -> (Vec<AppInfo>, ScanReport) {
+    let sources: Vec<(String, ScanFn<'_, (String, AppInfo)>)> = dirs.iter()
+        .map(|dir| {
+            let scan: ScanFn<'_, (String, AppInfo)> = Box::new(move || scan_application_dir(dir, sandbox));
+            (dir.display().to_string(), scan)
+        })
+        .collect();
+    let (found, report) = scan_sources(sources);
+
+    let apps = merge_desktop_apps(found);
+    info!("Found {} desktop applications", apps.len());
+    (apps, report)
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    #[test]
+    fn sandbox_detection() {
+        assert_eq!(Sandbox::detect_with(env(&[]), false), Sandbox::None);
+        assert_eq!(Sandbox::detect_with(env(&[("FLATPAK_ID", "io.github.falcommand")]), false), Sandbox::Flatpak);
+        assert_eq!(Sandbox::detect_with(env(&[]), true), Sandbox::Flatpak);
+        assert_eq!(Sandbox::detect_with(env(&[("SNAP", "/snap/falcommand/12"), ("SNAP_NAME", "falcommand")]), false), Sandbox::Snap);
+        // SNAP alone is left over from a parent shell, not a snap of ours
+        assert_eq!(Sandbox::detect_with(env(&[("SNAP", "/snap/x/1")]), false), Sandbox::None);
+    }
+
+    #[test]
+    fn application_dirs_follow_the_xdg_overrides() {
+        let vars = env(&[("XDG_DATA_HOME", "/xdg/data"), ("XDG_DATA_DIRS", "/opt/share::/usr/share")]);
+        assert_eq!(application_dirs(vars, Some(Path::new("/home/me")), Sandbox::None), [
+            PathBuf::from("/xdg/data/applications"),
+            PathBuf::from("/xdg/data/flatpak/exports/share/applications"),
+            PathBuf::from("/opt/share/applications"),
+            PathBuf::from("/usr/share/applications"),
+            PathBuf::from("/var/lib/flatpak/exports/share/applications"),
+            PathBuf::from("/var/lib/snapd/desktop/applications"),
+        ]);
+
+        // An empty XDG_DATA_HOME means unset
+        let dirs = application_dirs(env(&[("XDG_DATA_HOME", "")]), Some(Path::new("/home/me")), Sandbox::None);
+        assert_eq!(dirs[0], PathBuf::from("/home/me/.local/share/applications"));
+        assert_eq!(dirs[2], PathBuf::from("/usr/local/share/applications"));
+    }
+
+    // Inside a Flatpak the sandbox's XDG_DATA_DIRS is ignored and host dirs are read through /run/host
+    #[test]
+    fn flatpak_reads_the_host_dirs() {
+        let vars = env(&[("XDG_DATA_DIRS", "/app/share")]);
+        let dirs = application_dirs(vars, None, Sandbox::Flatpak);
+        assert_eq!(dirs[..2], [
+            PathBuf::from("/run/host/usr/local/share/applications"),
+            PathBuf::from("/run/host/usr/share/applications"),
+        ]);
+        assert_eq!(Sandbox::Snap.host_path("/usr/share"), PathBuf::from("/usr/share"));
+    }
+
+    #[test]
+    fn exported_entries_launch_on_the_host() {
+        let content = "[Desktop Entry]\nType=Application\nName=Firefox\nName[ja]=ファイアフォックス\n\
+            Exec=/usr/bin/flatpak run --branch=stable org.mozilla.firefox @@u %U @@\nKeywords=web;browser;\n\
+            [Desktop Action new-window]\nName=New Window\n";
+        let entry = parse_desktop_entry(content, "org.mozilla.firefox").unwrap();
+        assert_eq!(entry.name, "Firefox");
+        assert_eq!(entry.exec, ["/usr/bin/flatpak", "run", "--branch=stable", "org.mozilla.firefox"]);
+        assert_eq!(entry.keywords, ["web", "browser"]);
+
+        let app = entry.to_app_info(Sandbox::Flatpak).unwrap();
+        assert_eq!(app.executable_path, PathBuf::from("flatpak-spawn"));
+        assert_eq!(app.args[..2], ["--host", "/usr/bin/flatpak"]);
+        assert_eq!(entry.to_app_info(Sandbox::None).unwrap().executable_path, PathBuf::from("/usr/bin/flatpak"));
+    }
+
+    #[test]
+    fn exec_lines_keep_quoted_arguments_whole() {
+        assert_eq!(parse_exec(r#""/opt/My App/app" --title "a \"b\"" 100%% %f"#), ["/opt/My App/app", "--title", "a \"b\"", "100%"]);
+        assert!(parse_desktop_entry("[Desktop Entry]\nType=Application\nName=Hidden\nExec=x\nNoDisplay=true\n", "x").is_none());
+    }
+}
@@ -0,0 +1,264 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::platform::PlatformError;
+
+// One entry in the taskbar jump list; it starts `executable` with `arguments`
+#[derive(Debug, Clone, PartialEq)]
+pub struct JumpListItem {
+    pub title: String,
+    pub description: String,
+    pub executable: PathBuf,
+    pub arguments: Vec<String>,
+    pub icon: Option<PathBuf>,
+}
+
+// Publishes the recent items shown when the taskbar icon is right-clicked
+pub trait JumpList: Send + Sync {
+    // Replaces the whole list; an empty list clears it
+    fn update_jump_list(&self, items: Vec<JumpListItem>) -> Result<(), PlatformError>;
+}
+
+// None on platforms without a jump list
+pub fn jump_list() -> Option<Arc<dyn JumpList>> {
+    #[cfg(target_os = "windows")]
+    {
+        Some(Arc::new(windows::ComJumpList))
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        None
+    }
+}
+
+// Command line for CreateProcess: arguments with spaces or quotes are quoted
+pub fn command_line_arguments(arguments: &[String]) -> String {
+    arguments.iter()
+        .map(|arg| {
+            if !arg.is_empty() && !arg.contains([' ', '\t', '"']) {
+                arg.clone()
+            } else {
+                format!("\"{}\"", arg.replace('"', "\\\""))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(target_os = "windows")]
+#[allow(non_upper_case_globals, non_snake_case)]
+mod windows {
+    use std::ffi::OsStr;
+    use std::os::windows::ffi::OsStrExt;
+    use std::ptr::null_mut;
+    use winapi::ctypes::{c_int, c_void};
+    use winapi::shared::guiddef::REFIID;
+    use winapi::shared::minwindef::UINT;
+    use winapi::shared::winerror::{FAILED, HRESULT, RPC_E_CHANGED_MODE};
+    use winapi::shared::wtypes::{PROPERTYKEY, VT_LPWSTR};
+    use winapi::shared::wtypesbase::CLSCTX_INPROC_SERVER;
+    use winapi::um::combaseapi::{CoCreateInstance, CoInitializeEx, CoTaskMemAlloc, CoUninitialize};
+    use winapi::um::objbase::COINIT_APARTMENTTHREADED;
+    use winapi::um::propidl::{PropVariantClear, PROPVARIANT};
+    use winapi::um::propsys::IPropertyStore;
+    use winapi::um::shobjidl_core::IShellLinkW;
+    use winapi::um::unknwnbase::{IUnknown, IUnknownVtbl};
+    use winapi::um::winnt::LPCWSTR;
+    use winapi::{Interface, DEFINE_GUID, DEFINE_PROPERTYKEY, RIDL};
+
+    use super::{command_line_arguments, JumpList, JumpListItem};
+    use crate::platform::PlatformError;
+
+    // winapi 0.3 has no bindings for the destination list API (objectarray.h, shobjidl_core.h)
+    // or for the property keys in propkey.h, so the parts used here are declared from the SDK headers
+    DEFINE_GUID!{CLSID_ShellLink, 0x00021401, 0x0000, 0x0000, 0xc0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x46}
+    DEFINE_GUID!{CLSID_DestinationList, 0x77f10cf0, 0x3db5, 0x4966, 0xb5, 0x20, 0xb7, 0xc5, 0x4f, 0xd3, 0x5e, 0xd6}
+    DEFINE_GUID!{CLSID_EnumerableObjectCollection, 0x2d3468c1, 0x36a7, 0x43b6, 0xac, 0x24, 0xd3, 0xf0, 0x2f, 0xd9, 0x60, 0x7a}
+    DEFINE_PROPERTYKEY!{PKEY_Title, 0xf29f85e0, 0x4ff9, 0x1068, 0xab, 0x91, 0x08, 0x00, 0x2b, 0x27, 0xb3, 0xd9, 2}
+
+    RIDL!{#[uuid(0x92ca9dcd, 0x5622, 0x4bba, 0xa8, 0x05, 0x5e, 0x9f, 0x54, 0x1b, 0xd8, 0xc9)]
+    interface IObjectArray(IObjectArrayVtbl): IUnknown(IUnknownVtbl) {
+        fn GetCount(pcObjects: *mut UINT,) -> HRESULT,
+        fn GetAt(uiIndex: UINT, riid: REFIID, ppv: *mut *mut c_void,) -> HRESULT,
+    }}
+
+    RIDL!{#[uuid(0x5632b1a4, 0xe38a, 0x400a, 0x92, 0x8a, 0xd4, 0xcd, 0x63, 0x23, 0x02, 0x95)]
+    interface IObjectCollection(IObjectCollectionVtbl): IObjectArray(IObjectArrayVtbl) {
+        fn AddObject(punk: *mut IUnknown,) -> HRESULT,
+        fn AddFromArray(poaSource: *mut IObjectArray,) -> HRESULT,
+        fn RemoveObjectAt(uiIndex: UINT,) -> HRESULT,
+        fn Clear() -> HRESULT,
+    }}
+
+    RIDL!{#[uuid(0x6332debf, 0x87b5, 0x4670, 0x90, 0xc0, 0x5e, 0x57, 0xb4, 0x08, 0xa4, 0x9e)]
+    interface ICustomDestinationList(ICustomDestinationListVtbl): IUnknown(IUnknownVtbl) {
+        fn SetAppID(pszAppID: LPCWSTR,) -> HRESULT,
+        fn BeginList(pcMinSlots: *mut UINT, riid: REFIID, ppv: *mut *mut c_void,) -> HRESULT,
+        fn AppendCategory(pszCategory: LPCWSTR, poa: *mut IObjectArray,) -> HRESULT,
+        fn AppendKnownCategory(category: c_int,) -> HRESULT,
+        fn AddUserTasks(poa: *mut IObjectArray,) -> HRESULT,
+        fn CommitList() -> HRESULT,
+        fn GetRemovedDestinations(riid: REFIID, ppv: *mut *mut c_void,) -> HRESULT,
+        fn DeleteList(pszAppID: LPCWSTR,) -> HRESULT,
+        fn AbortList() -> HRESULT,
+    }}
+
+    const CATEGORY: &str = "Recent";
+
+    fn wide(text: &OsStr) -> Vec<u16> {
+        text.encode_wide().chain(std::iter::once(0)).collect()
+    }
+
+    fn check(result: HRESULT, call: &str) -> Result<(), PlatformError> {
+        if FAILED(result) {
+            return Err(PlatformError::Other(format!("{} failed with HRESULT {:#010x}", call, result)));
+        }
+        Ok(())
+    }
+
+    // Owned COM interface pointer, released on drop
+    struct Com<T: Interface>(*mut T);
+
+    impl<T: Interface> Com<T> {
+        fn create(clsid: &winapi::shared::guiddef::GUID, call: &str) -> Result<Self, PlatformError> {
+            let mut pointer: *mut T = null_mut();
+            let result = unsafe {
+                CoCreateInstance(clsid, null_mut(), CLSCTX_INPROC_SERVER, &T::uuidof(), &mut pointer as *mut *mut T as *mut _)
+            };
+            check(result, call)?;
+            Ok(Self(pointer))
+        }
+
+        fn query<U: Interface>(&self, call: &str) -> Result<Com<U>, PlatformError> {
+            let mut pointer: *mut U = null_mut();
+            let result = unsafe {
+                (*(self.0 as *mut IUnknown)).QueryInterface(&U::uuidof(), &mut pointer as *mut *mut U as *mut _)
+            };
+            check(result, call)?;
+            Ok(Com(pointer))
+        }
+    }
+
+    impl<T: Interface> Drop for Com<T> {
+        fn drop(&mut self) {
+            unsafe { (*(self.0 as *mut IUnknown)).Release() };
+        }
+    }
+
+    // Balances CoInitializeEx on the publishing thread
+    struct Apartment(bool);
+
+    impl Apartment {
+        fn enter() -> Result<Self, PlatformError> {
+            let result = unsafe { CoInitializeEx(null_mut(), COINIT_APARTMENTTHREADED) };
+            // Already initialized as MTA by someone else: usable, but not ours to uninitialize
+            if result == RPC_E_CHANGED_MODE {
+                return Ok(Self(false));
+            }
+            check(result, "CoInitializeEx")?;
+            Ok(Self(true))
+        }
+    }
+
+    impl Drop for Apartment {
+        fn drop(&mut self) {
+            if self.0 {
+                unsafe { CoUninitialize() };
+            }
+        }
+    }
+
+    // Jump list links need PKEY_Title; the path alone is not shown
+    fn shell_link(item: &JumpListItem) -> Result<Com<IShellLinkW>, PlatformError> {
+        let link: Com<IShellLinkW> = Com::create(&CLSID_ShellLink, "CoCreateInstance(ShellLink)")?;
+        unsafe {
+            check((*link.0).SetPath(wide(item.executable.as_os_str()).as_ptr()), "IShellLinkW::SetPath")?;
+            let arguments = command_line_arguments(&item.arguments);
+            check((*link.0).SetArguments(wide(OsStr::new(&arguments)).as_ptr()), "IShellLinkW::SetArguments")?;
+            check((*link.0).SetDescription(wide(OsStr::new(&item.description)).as_ptr()), "IShellLinkW::SetDescription")?;
+            if let Some(ref icon) = item.icon {
+                check((*link.0).SetIconLocation(wide(icon.as_os_str()).as_ptr(), 0), "IShellLinkW::SetIconLocation")?;
+            }
+
+            let store: Com<IPropertyStore> = link.query("QueryInterface(IPropertyStore)")?;
+            let title = wide(OsStr::new(&item.title));
+            let buffer = CoTaskMemAlloc(title.len() * 2) as *mut u16;
+            if buffer.is_null() {
+                return Err(PlatformError::Other("Out of memory for the jump list title".to_string()));
+            }
+            std::ptr::copy_nonoverlapping(title.as_ptr(), buffer, title.len());
+            let mut value: PROPVARIANT = std::mem::zeroed();
+            value.vt = VT_LPWSTR as u16;
+            *value.data.pwszVal_mut() = buffer;
+            let result = (*store.0).SetValue(&PKEY_Title, &value);
+            PropVariantClear(&mut value);
+            check(result, "IPropertyStore::SetValue")?;
+            check((*store.0).Commit(), "IPropertyStore::Commit")?;
+        }
+        Ok(link)
+    }
+
+    // Windows rejects a category holding an item the user removed from the jump list,
+    // so those are recognised by their arguments and left out
+    unsafe fn removed_arguments(removed: &Com<IObjectArray>) -> Vec<String> {
+        let mut count = 0u32;
+        if FAILED((*removed.0).GetCount(&mut count)) {
+            return Vec::new();
+        }
+
+        let mut arguments = Vec::new();
+        for index in 0..count {
+            let mut link: *mut IShellLinkW = null_mut();
+            if FAILED((*removed.0).GetAt(index, &IShellLinkW::uuidof(), &mut link as *mut *mut IShellLinkW as *mut _)) {
+                continue;
+            }
+            let link = Com(link);
+            let mut buffer = [0u16; 1024];
+            if FAILED((*link.0).GetArguments(buffer.as_mut_ptr(), buffer.len() as i32)) {
+                continue;
+            }
+            let length = buffer.iter().position(|c| *c == 0).unwrap_or(buffer.len());
+            arguments.push(String::from_utf16_lossy(&buffer[..length]));
+        }
+        arguments
+    }
+
+    pub struct ComJumpList;
+
+    impl JumpList for ComJumpList {
+        fn update_jump_list(&self, items: Vec<JumpListItem>) -> Result<(), PlatformError> {
+            let _apartment = Apartment::enter()?;
+            let list: Com<ICustomDestinationList> = Com::create(&CLSID_DestinationList, "CoCreateInstance(DestinationList)")?;
+
+            unsafe {
+                let mut min_slots = 0u32;
+                let mut removed: *mut IObjectArray = null_mut();
+                check(
+                    (*list.0).BeginList(&mut min_slots, &IObjectArray::uuidof(), &mut removed as *mut *mut IObjectArray as *mut _),
+                    "ICustomDestinationList::BeginList",
+                )?;
+                let removed = removed_arguments(&Com(removed));
+                let items: Vec<&JumpListItem> = items.iter()
+                    .filter(|item| !removed.contains(&command_line_arguments(&item.arguments)))
+                    .take(min_slots.max(1) as usize)
+                    .collect();
+
+                if !items.is_empty() {
+                    let collection: Com<IObjectCollection> =
+                        Com::create(&CLSID_EnumerableObjectCollection, "CoCreateInstance(EnumerableObjectCollection)")?;
+                    for item in items {
+                        let link = shell_link(item)?;
+                        check((*collection.0).AddObject(link.0 as *mut IUnknown), "IObjectCollection::AddObject")?;
+                    }
+                    let array: Com<IObjectArray> = collection.query("QueryInterface(IObjectArray)")?;
+                    let result = (*list.0).AppendCategory(wide(OsStr::new(CATEGORY)).as_ptr(), array.0);
+                    if FAILED(result) {
+                        (*list.0).AbortList();
+                        return check(result, "ICustomDestinationList::AppendCategory");
+                    }
+                }
+                check((*list.0).CommitList(), "ICustomDestinationList::CommitList")
+            }
+        }
+    }
+}
@@ -1,3 +1,22 @@
 pub mod platform;
+mod tray;
+pub mod system;
+pub mod network;
+pub mod desktop;
+pub mod trash;
+pub mod menus;
+pub mod jump_list;
+pub mod app_scan;
+pub mod app_identity;
+pub mod uninstall;
 
-pub use platform::*;
\ No newline at end of file
+pub use platform::*;
+pub use system::*;
+pub use network::*;
+pub use desktop::*;
+pub use trash::*;
+pub use menus::*;
+pub use jump_list::*;
+pub use app_scan::*;
+pub use app_identity::*;
+pub use uninstall::*;
\ No newline at end of file
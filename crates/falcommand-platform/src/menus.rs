@@ -0,0 +1,356 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::platform::PlatformError;
+
+// The app that was in front when the launcher opened
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForegroundApp {
+    pub pid: i32,
+    pub name: String,
+}
+
+// A menu bar entry as read from the accessibility tree; separators are left out
+#[derive(Debug, Clone, PartialEq)]
+pub struct MenuNode {
+    pub title: String,
+    pub enabled: bool,
+    pub children: Vec<MenuNode>,
+}
+
+impl MenuNode {
+    pub fn new(title: impl Into<String>, children: Vec<MenuNode>) -> Self {
+        Self {
+            title: title.into(),
+            enabled: true,
+            children,
+        }
+    }
+
+    pub fn disabled(mut self) -> Self {
+        self.enabled = false;
+        self
+    }
+}
+
+// A leaf menu item with the titles leading to it, e.g. ["File", "Export", "PDF…"]
+#[derive(Debug, Clone, PartialEq)]
+pub struct MenuItem {
+    pub path: Vec<String>,
+}
+
+impl MenuItem {
+    pub fn title(&self) -> &str {
+        self.path.last().map(String::as_str).unwrap_or_default()
+    }
+
+    pub fn breadcrumb(&self) -> String {
+        self.path.join(" › ")
+    }
+}
+
+// Leaf items that can be pressed; disabled items and their submenus are skipped.
+// Top-level entries ("File", "Edit") sit at depth 1, so `max_depth` 3 reaches "File › Export › PDF…"
+pub fn flatten_menu(roots: &[MenuNode], max_depth: usize) -> Vec<MenuItem> {
+    fn walk(nodes: &[MenuNode], prefix: &mut Vec<String>, depth: usize, max_depth: usize, items: &mut Vec<MenuItem>) {
+        if depth > max_depth {
+            return;
+        }
+        for node in nodes.iter().filter(|node| node.enabled && !node.title.trim().is_empty()) {
+            prefix.push(node.title.trim().to_string());
+            if node.children.is_empty() {
+                // A top-level entry without a menu is not a command
+                if depth > 1 {
+                    items.push(MenuItem { path: prefix.clone() });
+                }
+            } else {
+                walk(&node.children, prefix, depth + 1, max_depth, items);
+            }
+            prefix.pop();
+        }
+    }
+
+    let mut items = Vec::new();
+    walk(roots, &mut Vec::new(), 1, max_depth, &mut items);
+    items
+}
+
+// Reads and presses the menu bar of another app
+pub trait MenuAccess: Send + Sync {
+    fn foreground_app(&self) -> Option<ForegroundApp>;
+    // False until the user grants Accessibility access
+    fn is_trusted(&self) -> bool;
+    // Stops descending once `budget` is spent and returns what was read so far
+    fn menu_tree(&self, app: &ForegroundApp, max_depth: usize, budget: Duration) -> Result<Vec<MenuNode>, PlatformError>;
+    fn press(&self, app: &ForegroundApp, path: &[String]) -> Result<(), PlatformError>;
+    fn activate(&self, app: &ForegroundApp) -> Result<(), PlatformError>;
+}
+
+// System Settings › Privacy & Security › Accessibility
+pub const ACCESSIBILITY_SETTINGS_URL: &str = "x-apple.systempreferences:com.apple.preference.security?Privacy_Accessibility";
+
+// None where there is no menu bar access: other platforms, or macOS builds without `ax-integration`
+pub fn menu_access() -> Option<Arc<dyn MenuAccess>> {
+    #[cfg(all(target_os = "macos", feature = "ax-integration"))]
+    {
+        Some(Arc::new(ax::AxMenuAccess))
+    }
+    #[cfg(not(all(target_os = "macos", feature = "ax-integration")))]
+    {
+        None
+    }
+}
+
+#[cfg(all(target_os = "macos", feature = "ax-integration"))]
+mod ax {
+    use std::ffi::{c_void, CStr};
+    use std::os::raw::c_char;
+    use std::time::{Duration, Instant};
+    use core_foundation::array::{CFArray, CFArrayRef};
+    use core_foundation::base::{CFGetTypeID, CFRelease, CFRetain, CFTypeRef, TCFType};
+    use core_foundation::boolean::{CFBoolean, CFBooleanRef};
+    use core_foundation::string::{CFString, CFStringRef};
+    use objc::runtime::Object;
+    use objc::{class, msg_send, sel, sel_impl};
+
+    use super::{ForegroundApp, MenuAccess, MenuNode};
+    use crate::platform::PlatformError;
+
+    type AXUIElementRef = *const c_void;
+    type AXError = i32;
+    const AX_SUCCESS: AXError = 0;
+    // Per-call limit so one unresponsive app can't stall the launcher
+    const MESSAGING_TIMEOUT_SECS: f32 = 0.25;
+    // NSApplicationActivateIgnoringOtherApps
+    const ACTIVATE_IGNORING_OTHER_APPS: u64 = 1 << 1;
+
+    #[link(name = "ApplicationServices", kind = "framework")]
+    extern "C" {
+        fn AXIsProcessTrusted() -> bool;
+        fn AXUIElementCreateApplication(pid: i32) -> AXUIElementRef;
+        fn AXUIElementCopyAttributeValue(element: AXUIElementRef, attribute: CFStringRef, value: *mut CFTypeRef) -> AXError;
+        fn AXUIElementPerformAction(element: AXUIElementRef, action: CFStringRef) -> AXError;
+        fn AXUIElementSetMessagingTimeout(element: AXUIElementRef, timeout: f32) -> AXError;
+    }
+
+    // Owned AXUIElementRef, released on drop
+    struct Element(AXUIElementRef);
+
+    impl Drop for Element {
+        fn drop(&mut self) {
+            unsafe { CFRelease(self.0) };
+        }
+    }
+
+    impl Element {
+        fn application(pid: i32) -> Option<Self> {
+            let element = unsafe { AXUIElementCreateApplication(pid) };
+            if element.is_null() {
+                return None;
+            }
+            unsafe { AXUIElementSetMessagingTimeout(element, MESSAGING_TIMEOUT_SECS) };
+            Some(Self(element))
+        }
+
+        fn attribute(&self, name: &str) -> Option<CFTypeRef> {
+            let name = CFString::new(name);
+            let mut value: CFTypeRef = std::ptr::null();
+            let error = unsafe { AXUIElementCopyAttributeValue(self.0, name.as_concrete_TypeRef(), &mut value) };
+            (error == AX_SUCCESS && !value.is_null()).then_some(value)
+        }
+
+        fn string(&self, name: &str) -> Option<String> {
+            let value = self.attribute(name)?;
+            if unsafe { CFGetTypeID(value) } != CFString::type_id() {
+                unsafe { CFRelease(value) };
+                return None;
+            }
+            Some(unsafe { CFString::wrap_under_create_rule(value as CFStringRef) }.to_string())
+        }
+
+        fn flag(&self, name: &str) -> Option<bool> {
+            let value = self.attribute(name)?;
+            if unsafe { CFGetTypeID(value) } != CFBoolean::type_id() {
+                unsafe { CFRelease(value) };
+                return None;
+            }
+            Some(unsafe { CFBoolean::wrap_under_create_rule(value as CFBooleanRef) }.into())
+        }
+
+        fn element(&self, name: &str) -> Option<Element> {
+            self.attribute(name).map(Element)
+        }
+
+        fn children(&self) -> Vec<Element> {
+            let Some(value) = self.attribute("AXChildren") else {
+                return Vec::new();
+            };
+            if unsafe { CFGetTypeID(value) } != CFArray::<*const c_void>::type_id() {
+                unsafe { CFRelease(value) };
+                return Vec::new();
+            }
+
+            let array: CFArray<*const c_void> = unsafe { CFArray::wrap_under_create_rule(value as CFArrayRef) };
+            array.iter()
+                .map(|child| {
+                    // The array owns its items; each child is retained before the array is released
+                    unsafe { CFRetain(*child) };
+                    Element(*child)
+                })
+                .collect()
+        }
+
+        // Menu items of this element; AXMenu containers are transparent
+        fn menu_items(&self) -> Vec<Element> {
+            let mut items = Vec::new();
+            for child in self.children() {
+                if child.string("AXRole").as_deref() == Some("AXMenu") {
+                    items.extend(child.menu_items());
+                } else {
+                    items.push(child);
+                }
+            }
+            items
+        }
+
+        fn press(&self) -> Result<(), PlatformError> {
+            let action = CFString::new("AXPress");
+            match unsafe { AXUIElementPerformAction(self.0, action.as_concrete_TypeRef()) } {
+                AX_SUCCESS => Ok(()),
+                error => Err(PlatformError::Other(format!("AXPress failed with error {}", error))),
+            }
+        }
+    }
+
+    fn read_nodes(element: &Element, depth: usize, max_depth: usize, deadline: Instant) -> Vec<MenuNode> {
+        let mut nodes = Vec::new();
+        for item in element.menu_items() {
+            if Instant::now() > deadline {
+                break;
+            }
+            let Some(title) = item.string("AXTitle").filter(|title| !title.trim().is_empty()) else {
+                continue;
+            };
+            let children = if depth < max_depth {
+                read_nodes(&item, depth + 1, max_depth, deadline)
+            } else {
+                Vec::new()
+            };
+            nodes.push(MenuNode {
+                title,
+                enabled: item.flag("AXEnabled").unwrap_or(true),
+                children,
+            });
+        }
+        nodes
+    }
+
+    fn menu_bar(app: &ForegroundApp) -> Result<Element, PlatformError> {
+        let application = Element::application(app.pid)
+            .ok_or_else(|| PlatformError::Other(format!("Cannot access {}", app.name)))?;
+        application.element("AXMenuBar")
+            .ok_or_else(|| PlatformError::Other(format!("{} has no menu bar", app.name)))
+    }
+
+    unsafe fn ns_string(string: *mut Object) -> Option<String> {
+        if string.is_null() {
+            return None;
+        }
+        let utf8: *const c_char = msg_send![string, UTF8String];
+        (!utf8.is_null()).then(|| CStr::from_ptr(utf8).to_string_lossy().into_owned())
+    }
+
+    pub struct AxMenuAccess;
+
+    impl MenuAccess for AxMenuAccess {
+        fn foreground_app(&self) -> Option<ForegroundApp> {
+            unsafe {
+                let workspace: *mut Object = msg_send![class!(NSWorkspace), sharedWorkspace];
+                let app: *mut Object = msg_send![workspace, frontmostApplication];
+                if app.is_null() {
+                    return None;
+                }
+                let pid: i32 = msg_send![app, processIdentifier];
+                let name: *mut Object = msg_send![app, localizedName];
+                Some(ForegroundApp {
+                    pid,
+                    name: ns_string(name).unwrap_or_default(),
+                })
+            }
+        }
+
+        fn is_trusted(&self) -> bool {
+            unsafe { AXIsProcessTrusted() }
+        }
+
+        fn menu_tree(&self, app: &ForegroundApp, max_depth: usize, budget: Duration) -> Result<Vec<MenuNode>, PlatformError> {
+            let deadline = Instant::now() + budget;
+            Ok(read_nodes(&menu_bar(app)?, 1, max_depth, deadline))
+        }
+
+        fn press(&self, app: &ForegroundApp, path: &[String]) -> Result<(), PlatformError> {
+            let mut current = menu_bar(app)?;
+            for title in path {
+                current = current.menu_items()
+                    .into_iter()
+                    .find(|item| item.string("AXTitle").is_some_and(|t| t.trim() == title))
+                    .ok_or_else(|| PlatformError::Other(format!("Menu item '{}' is gone", path.join(" › "))))?;
+            }
+            current.press()
+        }
+
+        fn activate(&self, app: &ForegroundApp) -> Result<(), PlatformError> {
+            unsafe {
+                let running: *mut Object = msg_send![
+                    class!(NSRunningApplication),
+                    runningApplicationWithProcessIdentifier: app.pid
+                ];
+                if running.is_null() {
+                    return Err(PlatformError::Other(format!("{} is no longer running", app.name)));
+                }
+                let _: objc::runtime::BOOL = msg_send![running, activateWithOptions: ACTIVATE_IGNORING_OTHER_APPS];
+            }
+            Ok(())
+        }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaf(title: &str) -> MenuNode {
+        MenuNode::new(title, Vec::new())
+    }
+
+    fn paths(items: &[MenuItem]) -> Vec<String> {
+        items.iter().map(MenuItem::breadcrumb).collect()
+    }
+
+    #[test]
+    fn flattening_keeps_enabled_leaves_within_the_depth() {
+        let menu_bar = vec![
+            leaf("Apple"),
+            MenuNode::new("File", vec![
+                leaf("New Window"),
+                leaf(" "),
+                leaf("Close").disabled(),
+                MenuNode::new("Export", vec![
+                    leaf("PDF…"),
+                    MenuNode::new("Image", vec![leaf("PNG")]),
+                ]),
+                MenuNode::new("Share", vec![leaf("Mail")]).disabled(),
+            ]),
+            MenuNode::new("Edit", vec![leaf("  Undo  ")]),
+        ];
+
+        assert_eq!(paths(&flatten_menu(&menu_bar, 4)), [
+            "File › New Window",
+            "File › Export › PDF…",
+            "File › Export › Image › PNG",
+            "Edit › Undo",
+        ]);
+        // Deeper items are left out, not cut short
+        assert_eq!(paths(&flatten_menu(&menu_bar, 3)), ["File › New Window", "File › Export › PDF…", "Edit › Undo"]);
+        assert!(flatten_menu(&menu_bar, 1).is_empty());
+    }
+}
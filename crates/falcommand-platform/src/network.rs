@@ -0,0 +1,242 @@
+use std::net::{IpAddr, UdpSocket};
+use serde::{Deserialize, Serialize};
+
+use crate::system::{ControlCommand, TargetOs};
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NetworkInfo {
+    pub local_ip: Option<IpAddr>,
+    pub ssid: Option<String>,
+    // Signal quality in percent where the platform reports it
+    pub signal: Option<u8>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PingStats {
+    pub min_ms: f64,
+    pub avg_ms: f64,
+    pub max_ms: f64,
+}
+
+// Address of the interface holding the default route; connecting a UDP socket sends no packets
+pub fn local_ip() -> Option<IpAddr> {
+    let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect("192.0.2.1:80").ok()?;
+    let ip = socket.local_addr().ok()?.ip();
+    (!ip.is_unspecified()).then_some(ip)
+}
+
+pub fn wifi_command(os: TargetOs) -> ControlCommand {
+    match os {
+        TargetOs::Windows => ControlCommand::new("netsh", &["wlan", "show", "interfaces"]),
+        TargetOs::MacOS => ControlCommand::new(
+            "/System/Library/PrivateFrameworks/Apple80211.framework/Versions/Current/Resources/airport",
+            &["-I"],
+        ),
+        TargetOs::Linux => ControlCommand::new("nmcli", &["-t", "-f", "active,ssid,signal", "dev", "wifi"]),
+    }
+}
+
+// "yes:HomeNetwork:72" for the active access point
+pub fn parse_nmcli_wifi(output: &str) -> Option<(String, Option<u8>)> {
+    output.lines().find_map(|line| {
+        let rest = line.strip_prefix("yes:")?;
+        // SSIDs may contain escaped colons, so the signal is taken from the end
+        let (ssid, signal) = rest.rsplit_once(':')?;
+        Some((ssid.replace("\\:", ":"), signal.trim().parse::<u8>().ok().map(|s| s.min(100))))
+    })
+}
+
+fn field_value<'a>(output: &'a str, name: &str) -> Option<&'a str> {
+    output.lines().find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        (key.trim() == name).then(|| value.trim())
+    })
+}
+
+// "    SSID                   : HomeNetwork" / "    Signal                 : 72%"
+pub fn parse_netsh_wifi(output: &str) -> Option<(String, Option<u8>)> {
+    let ssid = field_value(output, "SSID").filter(|s| !s.is_empty())?;
+    let signal = field_value(output, "Signal")
+        .and_then(|s| s.trim_end_matches('%').parse::<u8>().ok())
+        .map(|s| s.min(100));
+    Some((ssid.to_string(), signal))
+}
+
+// "     agrCtlRSSI: -55" / "           SSID: HomeNetwork"; RSSI is mapped onto 0-100
+pub fn parse_airport_wifi(output: &str) -> Option<(String, Option<u8>)> {
+    let ssid = field_value(output, "SSID").filter(|s| !s.is_empty())?;
+    let signal = field_value(output, "agrCtlRSSI")
+        .and_then(|s| s.parse::<i32>().ok())
+        .map(|rssi| (2 * (rssi + 100)).clamp(0, 100) as u8);
+    Some((ssid.to_string(), signal))
+}
+
+pub fn parse_wifi(os: TargetOs, output: &str) -> Option<(String, Option<u8>)> {
+    match os {
+        TargetOs::Windows => parse_netsh_wifi(output),
+        TargetOs::MacOS => parse_airport_wifi(output),
+        TargetOs::Linux => parse_nmcli_wifi(output),
+    }
+}
+
+// Wired machines or missing Wi-Fi tools simply leave the SSID empty
+pub fn query_network_info(os: TargetOs) -> NetworkInfo {
+    let wifi = wifi_command(os).run()
+        .ok()
+        .and_then(|output| parse_wifi(os, &output));
+
+    NetworkInfo {
+        local_ip: local_ip(),
+        signal: wifi.as_ref().and_then(|(_, signal)| *signal),
+        ssid: wifi.map(|(ssid, _)| ssid),
+    }
+}
+
+pub const PING_PROBES: u32 = 3;
+pub const PING_PROBE_TIMEOUT_SECS: u32 = 1;
+
+// Bounded to three probes with a one second wait each
+pub fn ping_command(os: TargetOs, host: &str) -> ControlCommand {
+    let probes = PING_PROBES.to_string();
+    match os {
+        TargetOs::Windows => ControlCommand::new(
+            "ping",
+            &["-n", &probes, "-w", &(PING_PROBE_TIMEOUT_SECS * 1000).to_string(), host],
+        ),
+        // macOS takes the wait in milliseconds, Linux in seconds
+        TargetOs::MacOS => ControlCommand::new(
+            "ping",
+            &["-c", &probes, "-W", &(PING_PROBE_TIMEOUT_SECS * 1000).to_string(), host],
+        ),
+        TargetOs::Linux => ControlCommand::new(
+            "ping",
+            &["-c", &probes, "-W", &PING_PROBE_TIMEOUT_SECS.to_string(), host],
+        ),
+    }
+}
+
+// "rtt min/avg/max/mdev = 10.1/11.2/12.3/0.5 ms" (Linux) or "round-trip min/avg/max/stddev = ..." (macOS)
+pub fn parse_unix_ping(output: &str) -> Option<PingStats> {
+    let line = output.lines().find(|line| line.contains("min/avg/max"))?;
+    let values = line.split('=').nth(1)?.trim();
+    let mut parts = values.split_whitespace().next()?.split('/').map(|v| v.parse::<f64>());
+    Some(PingStats {
+        min_ms: parts.next()?.ok()?,
+        avg_ms: parts.next()?.ok()?,
+        max_ms: parts.next()?.ok()?,
+    })
+}
+
+// "    Minimum = 10ms, Maximum = 12ms, Average = 11ms"
+pub fn parse_windows_ping(output: &str) -> Option<PingStats> {
+    let line = output.lines().find(|line| line.contains("Minimum") && line.contains("Average"))?;
+    let value = |name: &str| -> Option<f64> {
+        line.split(',')
+            .find_map(|part| {
+                let (key, value) = part.split_once('=')?;
+                (key.trim() == name).then(|| value.trim().trim_end_matches("ms").trim().parse().ok())
+            })
+            .flatten()
+    };
+    Some(PingStats {
+        min_ms: value("Minimum")?,
+        avg_ms: value("Average")?,
+        max_ms: value("Maximum")?,
+    })
+}
+
+pub fn parse_ping(os: TargetOs, output: &str) -> Option<PingStats> {
+    match os {
+        TargetOs::Windows => parse_windows_ping(output),
+        TargetOs::MacOS | TargetOs::Linux => parse_unix_ping(output),
+    }
+}
+
+// Hostnames and literal addresses only, so queries can't smuggle extra ping flags
+pub fn is_valid_host(host: &str) -> bool {
+    !host.is_empty()
+        && host.len() <= 253
+        && !host.starts_with('-')
+        && host.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | ':'))
+}
+
+// Runs the platform's ping; unreachable hosts come back as None
+pub fn ping(host: &str) -> Option<PingStats> {
+    if !is_valid_host(host) {
+        return None;
+    }
+    let os = TargetOs::current();
+    let output = ping_command(os, host).run().ok()?;
+    parse_ping(os, &output)
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LINUX_PING: &str = "\
+PING example.com (93.184.216.34) 56(84) bytes of data.
+--- example.com ping statistics ---
+3 packets transmitted, 3 received, 0% packet loss, time 2003ms
+rtt min/avg/max/mdev = 10.104/11.250/12.300/0.897 ms";
+
+    const MACOS_PING: &str = "\
+--- example.com ping statistics ---
+3 packets transmitted, 3 packets received, 0.0% packet loss
+round-trip min/avg/max/stddev = 9.5/10.0/10.5/0.4 ms";
+
+    const WINDOWS_PING: &str = "\
+Ping statistics for 93.184.216.34:
+    Packets: Sent = 3, Received = 3, Lost = 0 (0% loss),
+Approximate round trip times in milli-seconds:
+    Minimum = 10ms, Maximum = 14ms, Average = 12ms";
+
+    fn stats(min_ms: f64, avg_ms: f64, max_ms: f64) -> PingStats {
+        PingStats { min_ms, avg_ms, max_ms }
+    }
+
+    #[test]
+    fn ping_output_is_parsed_per_os() {
+        assert_eq!(parse_ping(TargetOs::Linux, LINUX_PING), Some(stats(10.104, 11.25, 12.3)));
+        assert_eq!(parse_ping(TargetOs::MacOS, MACOS_PING), Some(stats(9.5, 10.0, 10.5)));
+        assert_eq!(parse_ping(TargetOs::Windows, WINDOWS_PING), Some(stats(10.0, 12.0, 14.0)));
+    }
+
+    #[test]
+    fn unanswered_pings_have_no_stats() {
+        let linux = "3 packets transmitted, 0 received, 100% packet loss, time 2040ms";
+        let windows = "    Packets: Sent = 3, Received = 0, Lost = 3 (100% loss),";
+        assert_eq!(parse_ping(TargetOs::Linux, linux), None);
+        assert_eq!(parse_ping(TargetOs::Windows, windows), None);
+    }
+
+    #[test]
+    fn ping_waits_are_in_each_os_unit() {
+        assert_eq!(ping_command(TargetOs::Linux, "example.com").args, ["-c", "3", "-W", "1", "example.com"]);
+        assert_eq!(ping_command(TargetOs::MacOS, "example.com").args, ["-c", "3", "-W", "1000", "example.com"]);
+        assert_eq!(ping_command(TargetOs::Windows, "example.com").args, ["-n", "3", "-w", "1000", "example.com"]);
+    }
+
+    #[test]
+    fn hosts_cannot_carry_flags() {
+        assert!(is_valid_host("example.com"));
+        assert!(is_valid_host("::1"));
+        assert!(!is_valid_host("-f example.com"));
+        assert!(!is_valid_host("example.com; rm"));
+        assert!(!is_valid_host(""));
+    }
+
+    #[test]
+    fn wifi_output_is_parsed_per_os() {
+        assert_eq!(parse_wifi(TargetOs::Linux, "no:Other:40\nyes:Cafe\\:5G:72\n"), Some(("Cafe:5G".to_string(), Some(72))));
+        assert_eq!(
+            parse_wifi(TargetOs::Windows, "    SSID                   : HomeNetwork\n    Signal                 : 88%\n"),
+            Some(("HomeNetwork".to_string(), Some(88))),
+        );
+        assert_eq!(
+            parse_wifi(TargetOs::MacOS, "     agrCtlRSSI: -55\n           SSID: HomeNetwork\n"),
+            Some(("HomeNetwork".to_string(), Some(90))),
+        );
+        assert_eq!(parse_wifi(TargetOs::Linux, "no:Other:40\n"), None);
+    }
+}
@@ -3,10 +3,25 @@ use std::sync::Arc;
 use std::time::SystemTime;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
-use log::{info, error};
-use tray_icon::{TrayIcon, TrayIconBuilder, menu::{Menu, MenuItem}};
+use log::info;
+#[cfg(target_os = "macos")]
+use log::error;
 
 use falcommand_config::{Theme, SearchResult, Action, Category};
+use crate::system::{self, BatteryStatus, PowerStatus, SystemControls, TargetOs};
+use crate::network::{self, NetworkInfo};
+use crate::trash;
+use crate::tray;
+use crate::menus::ForegroundApp;
+use crate::app_scan::{finish_scan, ScanReport};
+#[cfg(target_os = "macos")]
+use crate::app_identity::{bundle_identity, read_bundle_id};
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+use crate::app_scan::{scan_sources, ScanFn};
+#[cfg(target_os = "linux")]
+use crate::desktop::{self, Sandbox};
+#[cfg(target_os = "windows")]
+use crate::uninstall;
 
 #[derive(Debug, thiserror::Error)]
 pub enum PlatformError {
@@ -41,6 +56,15 @@ pub struct AppInfo {
     pub keywords: Vec<String>,
     pub usage_count: u32,
     pub last_used: Option<SystemTime>,
+    #[serde(default)]
+    pub args: Vec<String>,
+    // Older or alternate builds grouped under this app, offered as secondary actions
+    #[serde(default)]
+    pub other_versions: Vec<AppInfo>,
+    // Names the same app across machines and platforms, e.g. "firefox"; set by the scanner that
+    // found it. Usage stats sync merges launches by it, as paths differ from machine to machine
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity: Option<String>,
 }
 
 impl AppInfo {
@@ -53,9 +77,17 @@ impl AppInfo {
             keywords: Vec::new(),
             usage_count: 0,
             last_used: None,
+            args: Vec::new(),
+            other_versions: Vec::new(),
+            identity: None,
         }
     }
     
+    pub fn with_args(mut self, args: Vec<String>) -> Self {
+        self.args = args;
+        self
+    }
+    
     pub fn with_description(mut self, description: impl Into<String>) -> Self {
         self.description = Some(description.into());
         self
@@ -71,20 +103,37 @@ impl AppInfo {
         self
     }
     
+    pub fn with_identity(mut self, identity: impl Into<String>) -> Self {
+        self.identity = Some(identity.into());
+        self
+    }
+    
     pub fn increment_usage(&mut self) {
         self.usage_count += 1;
         self.last_used = Some(SystemTime::now());
     }
     
     pub fn to_search_result(&self) -> SearchResult {
-        SearchResult::new(&self.name, self.description.as_deref().unwrap_or(""))
+        let mut result = SearchResult::new(&self.name, self.description.as_deref().unwrap_or(""))
             .with_action(Action::ExecuteApplication {
                 path: self.executable_path.clone(),
-                args: Vec::new(),
+                args: self.args.clone(),
             })
             .with_category(Category::Application)
             .with_path(self.executable_path.clone())
             .with_score(self.calculate_score())
+            .with_open_later_actions();
+        
+        for version in &self.other_versions {
+            result = result.with_secondary_action(
+                format!("Launch {}", version.name),
+                Action::ExecuteApplication {
+                    path: version.executable_path.clone(),
+                    args: version.args.clone(),
+                },
+            );
+        }
+        result
     }
     
     fn calculate_score(&self) -> f64 {
@@ -106,27 +155,94 @@ impl AppInfo {
 
 #[async_trait]
 pub trait PlatformProvider: Send + Sync {
+    // Apps from every source that could be scanned; fails only when none could
     async fn get_installed_applications(&self) -> Result<Vec<AppInfo>, PlatformError>;
+    
+    // Which sources the last get_installed_applications scanned and which failed
+    fn last_scan_report(&self) -> ScanReport {
+        ScanReport::default()
+    }
+    
+    // Folders where installing or removing an app shows up, watched to refresh the app index
+    fn app_install_locations(&self) -> Vec<PathBuf> {
+        Vec::new()
+    }
+    
+    // Names of apps most people use (browsers, a terminal, the file manager), seeded into the
+    // usage stats of a fresh install when they are in the app index
+    fn common_app_names(&self) -> Vec<&'static str> {
+        Vec::new()
+    }
+    
+    // The app in front, for ranking by what the user was doing; call before the launcher takes
+    // focus. None where it can't be detected
+    fn get_foreground_window(&self) -> Option<ForegroundApp> {
+        None
+    }
+    
     fn register_global_hotkey(&self, hotkey: &str, callback: Box<dyn Fn() + Send>) -> Result<(), PlatformError>;
     fn unregister_global_hotkey(&self, hotkey: &str) -> Result<(), PlatformError>;
     fn show_notification(&self, title: &str, message: &str) -> Result<(), PlatformError>;
+    // A notification with one button; `on_action` runs, on any thread, when it is clicked. Where
+    // notifications can't carry buttons this shows the plain notification and returns false, so the
+    // caller can say how else to get there
+    fn show_notification_with_action(
+        &self,
+        title: &str,
+        message: &str,
+        _action_label: &str,
+        _on_action: Box<dyn FnOnce() + Send>,
+    ) -> Result<bool, PlatformError> {
+        self.show_notification(title, message)?;
+        Ok(false)
+    }
     fn get_system_theme(&self) -> Theme;
     async fn open_with_default_app(&self, path: &std::path::Path) -> Result<(), PlatformError>;
     fn copy_to_clipboard(&self, text: &str) -> Result<(), PlatformError>;
     fn paste_from_clipboard(&self) -> Result<String, PlatformError>;
     
     // System tray methods
+    // Whether this desktop can show a tray icon at all; blocking, so call it off the async runtime
+    fn system_tray_available(&self) -> bool {
+        tray::TRAY_SUPPORTED
+    }
     fn create_system_tray(&self, title: &str, tooltip: &str, icon_data: Option<&[u8]>) -> Result<(), PlatformError>;
     fn show_system_tray(&self) -> Result<(), PlatformError>;
     fn hide_system_tray(&self) -> Result<(), PlatformError>;
     fn update_system_tray_menu(&self, show_callback: Box<dyn Fn() + Send>, quit_callback: Box<dyn Fn() + Send>) -> Result<(), PlatformError>;
+    fn set_tray_tooltip(&self, text: &str) -> Result<(), PlatformError>;
+    fn set_tray_icon(&self, icon_data: &[u8]) -> Result<(), PlatformError>;
+    
+    // System quick controls
+    fn system_controls(&self) -> SystemControls;
+    fn get_battery_status(&self) -> Result<Option<BatteryStatus>, PlatformError>;
+    // AC or battery, for deferring background work; blocking, so call it off the async runtime
+    fn get_power_status(&self) -> Result<PowerStatus, PlatformError> {
+        Ok(PowerStatus::from_battery(self.get_battery_status()?.as_ref()))
+    }
+    fn set_volume(&self, percent: u8) -> Result<(), PlatformError>;
+    fn toggle_mute(&self) -> Result<(), PlatformError>;
+    fn set_brightness(&self, percent: u8) -> Result<(), PlatformError>;
+    
+    // Network information
+    fn get_network_info(&self) -> Result<NetworkInfo, PlatformError>;
+    
+    // Moves a file or folder to the OS trash; blocking, so call it off the async runtime
+    fn trash_file(&self, path: &std::path::Path) -> Result<(), PlatformError>;
 }
 
 // Windows implementation
 #[cfg(target_os = "windows")]
 pub struct WindowsPlatform {
     app_cache: std::sync::RwLock<Vec<AppInfo>>,
-    tray_icon: std::sync::RwLock<Option<TrayIcon>>,
+    scan_report: std::sync::RwLock<ScanReport>,
+}
+
+#[cfg(target_os = "windows")]
+impl Default for WindowsPlatform {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 #[cfg(target_os = "windows")]
@@ -134,40 +250,65 @@ impl WindowsPlatform {
     pub fn new() -> Self {
         Self {
             app_cache: std::sync::RwLock::new(Vec::new()),
-            tray_icon: std::sync::RwLock::new(None),
+            scan_report: std::sync::RwLock::new(ScanReport::default()),
         }
     }
     
-    async fn scan_registry(&self) -> Result<Vec<AppInfo>, PlatformError> {
-        // Registry scanning implementation would go here
+    // Programs and Features entries, identified across machines by product and publisher
+    fn scan_registry() -> Result<Vec<AppInfo>, PlatformError> {
         info!("Scanning Windows registry for applications");
-        Ok(Vec::new())
+        let mut seen = std::collections::HashSet::new();
+        Ok(uninstall::installed_programs().iter()
+            .filter_map(|entry| entry.to_app_info(|name| std::env::var(name).ok()))
+            // 32-bit and per-user keys often list the same program again
+            .filter(|app| seen.insert(app.executable_path.to_string_lossy().to_lowercase()))
+            .collect())
     }
     
-    async fn scan_start_menu(&self) -> Result<Vec<AppInfo>, PlatformError> {
+    fn scan_start_menu() -> Result<Vec<AppInfo>, PlatformError> {
         // Start menu scanning implementation would go here
         info!("Scanning Windows Start Menu");
         Ok(Vec::new())
     }
     
-    async fn scan_program_files(&self) -> Result<Vec<AppInfo>, PlatformError> {
+    fn scan_program_files() -> Result<Vec<AppInfo>, PlatformError> {
         // Program Files scanning implementation would go here
         info!("Scanning Program Files directories");
         Ok(Vec::new())
     }
+    
+    // The per-user and all-users Start Menu program folders
+    fn start_menu_dirs() -> Vec<PathBuf> {
+        ["APPDATA", "PROGRAMDATA"].iter()
+            .filter_map(|var| std::env::var_os(var))
+            .map(|base| PathBuf::from(base).join(r"Microsoft\Windows\Start Menu\Programs"))
+            .collect()
+    }
 }
 
 #[cfg(target_os = "windows")]
 #[async_trait]
 impl PlatformProvider for WindowsPlatform {
     async fn get_installed_applications(&self) -> Result<Vec<AppInfo>, PlatformError> {
-        let mut apps = Vec::new();
-        
-        apps.extend(self.scan_registry().await?);
-        apps.extend(self.scan_start_menu().await?);
-        apps.extend(self.scan_program_files().await?);
-        
-        Ok(apps)
+        let sources: Vec<(String, ScanFn<'_, AppInfo>)> = vec![
+            ("Registry".to_string(), Box::new(Self::scan_registry)),
+            ("Start Menu".to_string(), Box::new(Self::scan_start_menu)),
+            ("Program Files".to_string(), Box::new(Self::scan_program_files)),
+        ];
+        let (found, report) = scan_sources(sources);
+        finish_scan(&self.scan_report, found.concat(), report)
+    }
+    
+    fn last_scan_report(&self) -> ScanReport {
+        self.scan_report.read().unwrap().clone()
+    }
+    
+    fn app_install_locations(&self) -> Vec<PathBuf> {
+        Self::start_menu_dirs()
+    }
+    
+    fn common_app_names(&self) -> Vec<&'static str> {
+        vec!["Microsoft Edge", "Google Chrome", "Firefox", "Windows Terminal", "Command Prompt", "File Explorer"]
     }
     
     fn register_global_hotkey(&self, hotkey: &str, _callback: Box<dyn Fn() + Send>) -> Result<(), PlatformError> {
@@ -208,49 +349,17 @@ impl PlatformProvider for WindowsPlatform {
     
     fn create_system_tray(&self, title: &str, tooltip: &str, icon_data: Option<&[u8]>) -> Result<(), PlatformError> {
         info!("Creating Windows system tray: {}", title);
-        
-        let mut tray_builder = TrayIconBuilder::new()
-            .with_tooltip(tooltip);
-            
-        // Set icon if provided
-        if let Some(data) = icon_data {
-            if let Ok(icon) = tray_icon::Icon::from_rgba(data.to_vec(), 32, 32) {
-                tray_builder = tray_builder.with_icon(icon);
-            }
-        }
-        
-        // Create menu
-        let show_item = MenuItem::new("Show", true, None);
-        let quit_item = MenuItem::new("Quit", true, None);
-        let menu = Menu::new();
-        menu.append_items(&[&show_item, &quit_item])
-            .map_err(|e| PlatformError::SystemTrayError(e.to_string()))?;
-        
-        tray_builder = tray_builder.with_menu(Box::new(menu));
-        
-        let tray = tray_builder.build()
-            .map_err(|e| PlatformError::SystemTrayError(e.to_string()))?;
-            
-        *self.tray_icon.write().unwrap() = Some(tray);
-        Ok(())
+        tray::create_tray(tooltip, icon_data)
     }
     
     fn show_system_tray(&self) -> Result<(), PlatformError> {
         info!("Showing Windows system tray");
-        if let Some(ref tray) = *self.tray_icon.read().unwrap() {
-            tray.set_visible(true)
-                .map_err(|e| PlatformError::SystemTrayError(e.to_string()))?;
-        }
-        Ok(())
+        tray::set_visible(true)
     }
     
     fn hide_system_tray(&self) -> Result<(), PlatformError> {
         info!("Hiding Windows system tray");
-        if let Some(ref tray) = *self.tray_icon.read().unwrap() {
-            tray.set_visible(false)
-                .map_err(|e| PlatformError::SystemTrayError(e.to_string()))?;
-        }
-        Ok(())
+        tray::set_visible(false)
     }
     
     fn update_system_tray_menu(&self, _show_callback: Box<dyn Fn() + Send>, _quit_callback: Box<dyn Fn() + Send>) -> Result<(), PlatformError> {
@@ -258,21 +367,107 @@ impl PlatformProvider for WindowsPlatform {
         // Menu event handling would be implemented here
         Ok(())
     }
+    
+    fn set_tray_tooltip(&self, text: &str) -> Result<(), PlatformError> {
+        tray::set_tooltip(text)
+    }
+    
+    fn set_tray_icon(&self, icon_data: &[u8]) -> Result<(), PlatformError> {
+        tray::set_icon(icon_data)
+    }
+    
+    fn system_controls(&self) -> SystemControls {
+        SystemControls {
+            battery: true,
+            volume: false,
+            mute: false,
+            brightness: true,
+        }
+    }
+    
+    fn get_battery_status(&self) -> Result<Option<BatteryStatus>, PlatformError> {
+        let Some(command) = system::battery_command(TargetOs::Windows) else {
+            return Ok(None);
+        };
+        Ok(system::parse_windows_battery(&command.run()?))
+    }
+    
+    fn set_volume(&self, _percent: u8) -> Result<(), PlatformError> {
+        Err(PlatformError::Other("Volume control is not supported on Windows in this build".to_string()))
+    }
+    
+    fn toggle_mute(&self) -> Result<(), PlatformError> {
+        Err(PlatformError::Other("Mute is not supported on Windows in this build".to_string()))
+    }
+    
+    fn set_brightness(&self, percent: u8) -> Result<(), PlatformError> {
+        info!("Setting brightness to {}%", percent);
+        let command = system::brightness_command(TargetOs::Windows, percent)
+            .ok_or_else(|| PlatformError::Other("Brightness control is not supported".to_string()))?;
+        command.run().map(|_| ())
+    }
+    
+    fn get_network_info(&self) -> Result<NetworkInfo, PlatformError> {
+        Ok(network::query_network_info(TargetOs::Windows))
+    }
+    
+    fn trash_file(&self, path: &std::path::Path) -> Result<(), PlatformError> {
+        info!("Moving to trash: {:?}", path);
+        trash::trash_file(TargetOs::Windows, path)
+    }
 }
 
 // macOS implementation
 #[cfg(target_os = "macos")]
 pub struct MacOSPlatform {
-    tray_icon: std::sync::RwLock<Option<TrayIcon>>,
+    scan_report: std::sync::RwLock<ScanReport>,
+}
+
+#[cfg(target_os = "macos")]
+impl Default for MacOSPlatform {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 #[cfg(target_os = "macos")]
 impl MacOSPlatform {
     pub fn new() -> Self {
         Self {
-            tray_icon: std::sync::RwLock::new(None),
+            scan_report: std::sync::RwLock::new(ScanReport::default()),
         }
     }
+    
+    // Folders holding .app bundles: the system ones and the user's own
+    fn bundle_roots() -> Vec<PathBuf> {
+        let mut roots = vec![PathBuf::from("/Applications"), PathBuf::from("/System/Applications")];
+        roots.extend(dirs::home_dir().map(|home| home.join("Applications")));
+        roots
+    }
+    
+    // The .app bundles directly inside `root`; a root that doesn't exist has none
+    fn scan_bundle_root(root: &std::path::Path) -> Result<Vec<AppInfo>, PlatformError> {
+        let entries = match std::fs::read_dir(root) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(PlatformError::FileSystemError(e.to_string())),
+        };
+        
+        let mut apps: Vec<AppInfo> = entries.filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|extension| extension == "app"))
+            .filter_map(|path| {
+                let name = path.file_stem()?.to_string_lossy().into_owned();
+                let app = match read_bundle_id(&path) {
+                    Some(bundle_id) => AppInfo::new(name, path).with_identity(bundle_identity(&bundle_id)),
+                    None => AppInfo::new(name, path),
+                };
+                Some(app)
+            })
+            .collect();
+        apps.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(apps)
+    }
 }
 
 #[cfg(target_os = "macos")]
@@ -286,8 +481,33 @@ unsafe impl Sync for MacOSPlatform {}
 impl PlatformProvider for MacOSPlatform {
     async fn get_installed_applications(&self) -> Result<Vec<AppInfo>, PlatformError> {
         info!("Scanning macOS applications");
-        // macOS application scanning implementation would go here
-        Ok(Vec::new())
+        let roots = Self::bundle_roots();
+        
+        let sources: Vec<(String, ScanFn<'_, AppInfo>)> = roots.iter()
+            .map(|root| {
+                let scan: ScanFn<'_, AppInfo> = Box::new(move || Self::scan_bundle_root(root));
+                (root.display().to_string(), scan)
+            })
+            .collect();
+        let (found, report) = scan_sources(sources);
+        finish_scan(&self.scan_report, found.concat(), report)
+    }
+    
+    fn last_scan_report(&self) -> ScanReport {
+        self.scan_report.read().unwrap().clone()
+    }
+    
+    // Read through the menu bar access, so only builds with `ax-integration` know it
+    fn get_foreground_window(&self) -> Option<ForegroundApp> {
+        crate::menus::menu_access()?.foreground_app()
+    }
+    
+    fn app_install_locations(&self) -> Vec<PathBuf> {
+        Self::bundle_roots()
+    }
+    
+    fn common_app_names(&self) -> Vec<&'static str> {
+        vec!["Safari", "Google Chrome", "Firefox", "Terminal", "iTerm", "Finder"]
     }
     
     fn register_global_hotkey(&self, hotkey: &str, _callback: Box<dyn Fn() + Send>) -> Result<(), PlatformError> {
@@ -338,20 +558,12 @@ impl PlatformProvider for MacOSPlatform {
     
     fn show_system_tray(&self) -> Result<(), PlatformError> {
         info!("Showing macOS system tray");
-        if let Some(ref tray) = *self.tray_icon.read().unwrap() {
-            tray.set_visible(true)
-                .map_err(|e| PlatformError::SystemTrayError(e.to_string()))?;
-        }
-        Ok(())
+        tray::set_visible(true)
     }
     
     fn hide_system_tray(&self) -> Result<(), PlatformError> {
         info!("Hiding macOS system tray");
-        if let Some(ref tray) = *self.tray_icon.read().unwrap() {
-            tray.set_visible(false)
-                .map_err(|e| PlatformError::SystemTrayError(e.to_string()))?;
-        }
-        Ok(())
+        tray::set_visible(false)
     }
     
     fn update_system_tray_menu(&self, _show_callback: Box<dyn Fn() + Send>, _quit_callback: Box<dyn Fn() + Send>) -> Result<(), PlatformError> {
@@ -359,19 +571,85 @@ impl PlatformProvider for MacOSPlatform {
         // Menu event handling would be implemented here
         Ok(())
     }
+    
+    fn set_tray_tooltip(&self, text: &str) -> Result<(), PlatformError> {
+        tray::set_tooltip(text)
+    }
+    
+    fn set_tray_icon(&self, icon_data: &[u8]) -> Result<(), PlatformError> {
+        tray::set_icon(icon_data)
+    }
+    
+    fn system_controls(&self) -> SystemControls {
+        SystemControls {
+            battery: true,
+            volume: true,
+            mute: true,
+            brightness: false,
+        }
+    }
+    
+    fn get_battery_status(&self) -> Result<Option<BatteryStatus>, PlatformError> {
+        let Some(command) = system::battery_command(TargetOs::MacOS) else {
+            return Ok(None);
+        };
+        Ok(system::parse_pmset_battery(&command.run()?))
+    }
+    
+    fn get_power_status(&self) -> Result<PowerStatus, PlatformError> {
+        let Some(command) = system::battery_command(TargetOs::MacOS) else {
+            return Ok(PowerStatus::AC);
+        };
+        Ok(system::parse_pmset_power(&command.run()?))
+    }
+    
+    fn set_volume(&self, percent: u8) -> Result<(), PlatformError> {
+        info!("Setting volume to {}%", percent);
+        let command = system::volume_command(TargetOs::MacOS, percent)
+            .ok_or_else(|| PlatformError::Other("Volume control is not supported".to_string()))?;
+        command.run().map(|_| ())
+    }
+    
+    fn toggle_mute(&self) -> Result<(), PlatformError> {
+        let command = system::mute_command(TargetOs::MacOS)
+            .ok_or_else(|| PlatformError::Other("Mute is not supported".to_string()))?;
+        command.run().map(|_| ())
+    }
+    
+    fn set_brightness(&self, _percent: u8) -> Result<(), PlatformError> {
+        // Display brightness needs IOKit bindings this build doesn't include
+        Err(PlatformError::Other("Brightness control is not supported on macOS in this build".to_string()))
+    }
+    
+    fn get_network_info(&self) -> Result<NetworkInfo, PlatformError> {
+        Ok(network::query_network_info(TargetOs::MacOS))
+    }
+    
+    fn trash_file(&self, path: &std::path::Path) -> Result<(), PlatformError> {
+        info!("Moving to trash: {:?}", path);
+        trash::trash_file(TargetOs::MacOS, path)
+    }
 }
 
 // Linux implementation
+
 #[cfg(target_os = "linux")]
 pub struct LinuxPlatform {
-    tray_icon: std::sync::RwLock<Option<TrayIcon>>,
+    scan_report: std::sync::RwLock<ScanReport>,
+}
+
+#[cfg(target_os = "linux")]
+impl Default for LinuxPlatform {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 #[cfg(target_os = "linux")]
 impl LinuxPlatform {
     pub fn new() -> Self {
         Self {
-            tray_icon: std::sync::RwLock::new(None),
+            scan_report: std::sync::RwLock::new(ScanReport::default()),
         }
     }
 }
@@ -380,9 +658,24 @@ impl LinuxPlatform {
 #[async_trait]
 impl PlatformProvider for LinuxPlatform {
     async fn get_installed_applications(&self) -> Result<Vec<AppInfo>, PlatformError> {
-        info!("Scanning Linux applications");
-        // Linux application scanning implementation would go here
-        Ok(Vec::new())
+        let sandbox = Sandbox::detect();
+        info!("Scanning Linux applications (sandbox: {:?})", sandbox);
+        
+        let dirs = desktop::application_dirs(|name| std::env::var(name).ok(), dirs::home_dir().as_deref(), sandbox);
+        let (apps, report) = desktop::scan_application_dirs(&dirs, sandbox);
+        finish_scan(&self.scan_report, apps, report)
+    }
+    
+    fn last_scan_report(&self) -> ScanReport {
+        self.scan_report.read().unwrap().clone()
+    }
+    
+    fn app_install_locations(&self) -> Vec<PathBuf> {
+        desktop::application_dirs(|name| std::env::var(name).ok(), dirs::home_dir().as_deref(), Sandbox::detect())
+    }
+    
+    fn common_app_names(&self) -> Vec<&'static str> {
+        vec!["Firefox", "Google Chrome", "Chromium", "Terminal", "Konsole", "Files", "Dolphin"]
     }
     
     fn register_global_hotkey(&self, hotkey: &str, _callback: Box<dyn Fn() + Send>) -> Result<(), PlatformError> {
@@ -419,51 +712,32 @@ impl PlatformProvider for LinuxPlatform {
         Ok(String::new())
     }
     
+    // GNOME without the AppIndicator extension has no StatusNotifier host
+    fn system_tray_available(&self) -> bool {
+        if !tray::TRAY_SUPPORTED {
+            return false;
+        }
+        if !system::command_exists("dbus-send") {
+            return true;
+        }
+        system::status_notifier_available(|name| {
+            system::name_has_owner_command(name).run().ok().and_then(|output| system::parse_name_has_owner(&output))
+        })
+    }
+    
     fn create_system_tray(&self, title: &str, tooltip: &str, icon_data: Option<&[u8]>) -> Result<(), PlatformError> {
         info!("Creating Linux system tray: {}", title);
-        
-        let mut tray_builder = TrayIconBuilder::new()
-            .with_tooltip(tooltip);
-            
-        // Set icon if provided
-        if let Some(data) = icon_data {
-            if let Ok(icon) = tray_icon::Icon::from_rgba(data.to_vec(), 32, 32) {
-                tray_builder = tray_builder.with_icon(icon);
-            }
-        }
-        
-        // Create menu
-        let show_item = MenuItem::new("Show", true, None);
-        let quit_item = MenuItem::new("Quit", true, None);
-        let menu = Menu::new();
-        menu.append_items(&[&show_item, &quit_item])
-            .map_err(|e| PlatformError::SystemTrayError(e.to_string()))?;
-        
-        tray_builder = tray_builder.with_menu(Box::new(menu));
-        
-        let tray = tray_builder.build()
-            .map_err(|e| PlatformError::SystemTrayError(e.to_string()))?;
-            
-        *self.tray_icon.write().unwrap() = Some(tray);
-        Ok(())
+        tray::create_tray(tooltip, icon_data)
     }
     
     fn show_system_tray(&self) -> Result<(), PlatformError> {
         info!("Showing Linux system tray");
-        if let Some(ref tray) = *self.tray_icon.read().unwrap() {
-            tray.set_visible(true)
-                .map_err(|e| PlatformError::SystemTrayError(e.to_string()))?;
-        }
-        Ok(())
+        tray::set_visible(true)
     }
     
     fn hide_system_tray(&self) -> Result<(), PlatformError> {
         info!("Hiding Linux system tray");
-        if let Some(ref tray) = *self.tray_icon.read().unwrap() {
-            tray.set_visible(false)
-                .map_err(|e| PlatformError::SystemTrayError(e.to_string()))?;
-        }
-        Ok(())
+        tray::set_visible(false)
     }
     
     fn update_system_tray_menu(&self, _show_callback: Box<dyn Fn() + Send>, _quit_callback: Box<dyn Fn() + Send>) -> Result<(), PlatformError> {
@@ -471,6 +745,67 @@ impl PlatformProvider for LinuxPlatform {
         // Menu event handling would be implemented here
         Ok(())
     }
+    
+    fn set_tray_tooltip(&self, text: &str) -> Result<(), PlatformError> {
+        tray::set_tooltip(text)
+    }
+    
+    fn set_tray_icon(&self, icon_data: &[u8]) -> Result<(), PlatformError> {
+        tray::set_icon(icon_data)
+    }
+    
+    fn system_controls(&self) -> SystemControls {
+        let has_mixer = system::command_exists("pactl") || system::command_exists("amixer");
+        SystemControls {
+            battery: system::read_sysfs_battery(std::path::Path::new(system::SYSFS_POWER_SUPPLY)).is_some(),
+            volume: has_mixer,
+            mute: has_mixer,
+            brightness: system::sysfs_backlight(std::path::Path::new(system::SYSFS_BACKLIGHT)).is_some(),
+        }
+    }
+    
+    fn get_battery_status(&self) -> Result<Option<BatteryStatus>, PlatformError> {
+        Ok(system::read_sysfs_battery(std::path::Path::new(system::SYSFS_POWER_SUPPLY)))
+    }
+    
+    fn get_power_status(&self) -> Result<PowerStatus, PlatformError> {
+        Ok(system::read_sysfs_power(std::path::Path::new(system::SYSFS_POWER_SUPPLY)))
+    }
+    
+    fn set_volume(&self, percent: u8) -> Result<(), PlatformError> {
+        info!("Setting volume to {}%", percent);
+        if system::command_exists("pactl") {
+            if let Some(command) = system::volume_command(TargetOs::Linux, percent) {
+                return command.run().map(|_| ());
+            }
+        }
+        system::amixer_volume_command(percent).run().map(|_| ())
+    }
+    
+    fn toggle_mute(&self) -> Result<(), PlatformError> {
+        if system::command_exists("pactl") {
+            if let Some(command) = system::mute_command(TargetOs::Linux) {
+                return command.run().map(|_| ());
+            }
+        }
+        system::amixer_mute_command().run().map(|_| ())
+    }
+    
+    fn set_brightness(&self, percent: u8) -> Result<(), PlatformError> {
+        info!("Setting brightness to {}%", percent);
+        let backlight = system::sysfs_backlight(std::path::Path::new(system::SYSFS_BACKLIGHT))
+            .ok_or_else(|| PlatformError::Other("No backlight device found".to_string()))?;
+        system::write_sysfs_brightness(&backlight, percent)
+    }
+    
+    fn get_network_info(&self) -> Result<NetworkInfo, PlatformError> {
+        Ok(network::query_network_info(TargetOs::Linux))
+    }
+    
+    fn trash_file(&self, path: &std::path::Path) -> Result<(), PlatformError> {
+        info!("Moving to trash: {:?}", path);
+        trash::trash_file(TargetOs::Linux, path)
+    }
 }
 
 // Platform provider factory
@@ -0,0 +1,385 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use serde::{Deserialize, Serialize};
+
+use crate::platform::PlatformError;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BatteryStatus {
+    pub percent: u8,
+    pub charging: bool,
+}
+
+// Where power comes from; machines without a battery are always on AC
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PowerStatus {
+    pub on_ac: bool,
+    pub battery_percent: Option<u8>,
+}
+
+impl PowerStatus {
+    pub const AC: PowerStatus = PowerStatus { on_ac: true, battery_percent: None };
+
+    // A full battery on AC isn't charging, so charging only tells AC apart where nothing better is known
+    pub fn from_battery(battery: Option<&BatteryStatus>) -> Self {
+        match battery {
+            Some(battery) => Self { on_ac: battery.charging, battery_percent: Some(battery.percent) },
+            None => Self::AC,
+        }
+    }
+
+    // "on AC", "on battery (45%)"
+    pub fn describe(&self) -> String {
+        match (self.on_ac, self.battery_percent) {
+            (true, _) => "on AC".to_string(),
+            (false, Some(percent)) => format!("on battery ({}%)", percent),
+            (false, None) => "on battery".to_string(),
+        }
+    }
+}
+
+// Which quick controls the current platform can actually perform
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SystemControls {
+    pub battery: bool,
+    pub volume: bool,
+    pub mute: bool,
+    pub brightness: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TargetOs {
+    Windows,
+    MacOS,
+    Linux,
+}
+
+impl TargetOs {
+    pub fn current() -> Self {
+        if cfg!(target_os = "windows") {
+            TargetOs::Windows
+        } else if cfg!(target_os = "macos") {
+            TargetOs::MacOS
+        } else {
+            TargetOs::Linux
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ControlCommand {
+    pub program: String,
+    pub args: Vec<String>,
+}
+
+impl ControlCommand {
+    pub(crate) fn new(program: &str, args: &[&str]) -> Self {
+        Self {
+            program: program.to_string(),
+            args: args.iter().map(|a| a.to_string()).collect(),
+        }
+    }
+
+    pub fn run(&self) -> Result<String, PlatformError> {
+        let output = Command::new(&self.program)
+            .args(&self.args)
+            .output()
+            .map_err(|e| PlatformError::Other(format!("Failed to run {}: {}", self.program, e)))?;
+
+        if !output.status.success() {
+            return Err(PlatformError::Other(format!(
+                "{} failed: {}",
+                self.program,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
+
+pub fn volume_command(os: TargetOs, percent: u8) -> Option<ControlCommand> {
+    let percent = percent.min(100);
+    match os {
+        TargetOs::Linux => Some(ControlCommand::new(
+            "pactl",
+            &["set-sink-volume", "@DEFAULT_SINK@", &format!("{}%", percent)],
+        )),
+        TargetOs::MacOS => Some(ControlCommand::new(
+            "osascript",
+            &["-e", &format!("set volume output volume {}", percent)],
+        )),
+        // Core Audio needs COM bindings this build doesn't include
+        TargetOs::Windows => None,
+    }
+}
+
+// ALSA fallback for systems without PulseAudio/PipeWire
+pub fn amixer_volume_command(percent: u8) -> ControlCommand {
+    ControlCommand::new("amixer", &["-q", "set", "Master", &format!("{}%", percent.min(100))])
+}
+
+pub fn mute_command(os: TargetOs) -> Option<ControlCommand> {
+    match os {
+        TargetOs::Linux => Some(ControlCommand::new("pactl", &["set-sink-mute", "@DEFAULT_SINK@", "toggle"])),
+        TargetOs::MacOS => Some(ControlCommand::new(
+            "osascript",
+            &["-e", "set volume output muted not (output muted of (get volume settings))"],
+        )),
+        TargetOs::Windows => None,
+    }
+}
+
+pub fn amixer_mute_command() -> ControlCommand {
+    ControlCommand::new("amixer", &["-q", "set", "Master", "toggle"])
+}
+
+// Linux brightness goes through sysfs instead of a command
+pub fn brightness_command(os: TargetOs, percent: u8) -> Option<ControlCommand> {
+    match os {
+        TargetOs::Windows => Some(ControlCommand::new(
+            "powershell",
+            &[
+                "-NoProfile",
+                "-Command",
+                &format!(
+                    "(Get-WmiObject -Namespace root/WMI -Class WmiMonitorBrightnessMethods).WmiSetBrightness(1,{})",
+                    percent.min(100)
+                ),
+            ],
+        )),
+        TargetOs::MacOS | TargetOs::Linux => None,
+    }
+}
+
+pub fn battery_command(os: TargetOs) -> Option<ControlCommand> {
+    match os {
+        TargetOs::Windows => Some(ControlCommand::new(
+            "powershell",
+            &[
+                "-NoProfile",
+                "-Command",
+                "$b = Get-CimInstance Win32_Battery; if ($b) { \"$($b.EstimatedChargeRemaining);$($b.BatteryStatus)\" }",
+            ],
+        )),
+        TargetOs::MacOS => Some(ControlCommand::new("pmset", &["-g", "batt"])),
+        TargetOs::Linux => None,
+    }
+}
+
+// "... 85%; charging; 1:05 remaining ..."
+pub fn parse_pmset_battery(output: &str) -> Option<BatteryStatus> {
+    let line = output.lines().find(|line| line.contains('%'))?;
+    let percent_end = line.find('%')?;
+    let digits: String = line[..percent_end].chars().rev()
+        .take_while(|c| c.is_ascii_digit())
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .collect();
+
+    Some(BatteryStatus {
+        percent: digits.parse::<u8>().ok()?.min(100),
+        charging: line.contains("charging") && !line.contains("discharging"),
+    })
+}
+
+// pmset's first line names the source: "Now drawing from 'AC Power'" or "'Battery Power'"
+pub fn parse_pmset_power(output: &str) -> PowerStatus {
+    let battery = parse_pmset_battery(output);
+    PowerStatus {
+        on_ac: !output.contains("'Battery Power'"),
+        battery_percent: battery.map(|battery| battery.percent),
+    }
+}
+
+// "85;2" where Win32_Battery.BatteryStatus 2 means on AC power
+pub fn parse_windows_battery(output: &str) -> Option<BatteryStatus> {
+    let (percent, status) = output.trim().split_once(';')?;
+    Some(BatteryStatus {
+        percent: percent.trim().parse::<u8>().ok()?.min(100),
+        charging: matches!(status.trim(), "2" | "6" | "7" | "8" | "9"),
+    })
+}
+
+pub const SYSFS_POWER_SUPPLY: &str = "/sys/class/power_supply";
+pub const SYSFS_BACKLIGHT: &str = "/sys/class/backlight";
+
+fn first_entry(root: &Path, prefix: &str) -> Option<PathBuf> {
+    let mut entries: Vec<PathBuf> = std::fs::read_dir(root).ok()?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.file_name().map(|n| n.to_string_lossy().starts_with(prefix)).unwrap_or(false))
+        .collect();
+    entries.sort();
+    entries.into_iter().next()
+}
+
+pub fn read_sysfs_battery(root: &Path) -> Option<BatteryStatus> {
+    let battery = first_entry(root, "BAT")?;
+    let capacity = std::fs::read_to_string(battery.join("capacity")).ok()?;
+    let status = std::fs::read_to_string(battery.join("status")).unwrap_or_default();
+
+    Some(BatteryStatus {
+        percent: capacity.trim().parse::<u8>().ok()?.min(100),
+        charging: status.trim() == "Charging" || status.trim() == "Full",
+    })
+}
+
+// On AC when any mains supply is online; a laptop on AC with a full battery reports "Not charging"
+pub fn read_sysfs_power(root: &Path) -> PowerStatus {
+    let battery = read_sysfs_battery(root);
+    let mains: Vec<PathBuf> = std::fs::read_dir(root).into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|supply| std::fs::read_to_string(supply.join("type")).is_ok_and(|kind| kind.trim() == "Mains"))
+        .collect();
+
+    if mains.is_empty() {
+        return PowerStatus::from_battery(battery.as_ref());
+    }
+    PowerStatus {
+        on_ac: mains.iter().any(|supply| std::fs::read_to_string(supply.join("online")).is_ok_and(|online| online.trim() == "1")),
+        battery_percent: battery.map(|battery| battery.percent),
+    }
+}
+
+pub fn sysfs_backlight(root: &Path) -> Option<PathBuf> {
+    first_entry(root, "")
+}
+
+pub fn write_sysfs_brightness(backlight: &Path, percent: u8) -> Result<(), PlatformError> {
+    let max: u64 = std::fs::read_to_string(backlight.join("max_brightness"))
+        .map_err(|e| PlatformError::FileSystemError(e.to_string()))?
+        .trim()
+        .parse()
+        .map_err(|_| PlatformError::Other("Invalid max_brightness value".to_string()))?;
+
+    let value = max * u64::from(percent.min(100)) / 100;
+    let brightness_path = backlight.join("brightness");
+    std::fs::write(&brightness_path, value.to_string()).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::PermissionDenied {
+            PlatformError::Other(format!(
+                "Permission denied writing {}. Add your user to the 'video' group or install a udev rule for the backlight.",
+                brightness_path.display()
+            ))
+        } else {
+            PlatformError::FileSystemError(e.to_string())
+        }
+    })
+}
+
+// Whether a command-line tool is on PATH, used to decide which controls to offer
+pub fn command_exists(program: &str) -> bool {
+    let Some(paths) = std::env::var_os("PATH") else {
+        return false;
+    };
+    std::env::split_paths(&paths).any(|dir| {
+        dir.join(program).is_file() || dir.join(format!("{}.exe", program)).is_file()
+    })
+}
+// Bus names a StatusNotifier host registers; tray icons only show on Linux when one of them is owned
+pub const STATUS_NOTIFIER_WATCHERS: &[&str] = &["org.kde.StatusNotifierWatcher", "org.freedesktop.StatusNotifierWatcher"];
+
+// Asks the session bus whether `name` has an owner
+pub fn name_has_owner_command(name: &str) -> ControlCommand {
+    ControlCommand::new(
+        "dbus-send",
+        &[
+            "--session",
+            "--print-reply",
+            "--dest=org.freedesktop.DBus",
+            "/org/freedesktop/DBus",
+            "org.freedesktop.DBus.NameHasOwner",
+            &format!("string:{}", name),
+        ],
+    )
+}
+
+// The answer in a NameHasOwner reply: "method return ... \n   boolean true"
+pub fn parse_name_has_owner(output: &str) -> Option<bool> {
+    output.lines()
+        .filter_map(|line| line.trim().strip_prefix("boolean "))
+        .find_map(|value| match value.trim() {
+            "true" => Some(true),
+            "false" => Some(false),
+            _ => None,
+        })
+}
+
+// Whether tray icons can show. `has_owner` tells whether a bus name is owned, or None when the bus
+// can't be asked; then the tray is tried as before, as only a definite no is worth a fallback
+pub fn status_notifier_available(has_owner: impl Fn(&str) -> Option<bool>) -> bool {
+    let answers: Vec<Option<bool>> = STATUS_NOTIFIER_WATCHERS.iter().map(|name| has_owner(name)).collect();
+    answers.contains(&Some(true)) || answers.contains(&None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn volume_commands_per_os() {
+        assert_eq!(volume_command(TargetOs::Linux, 40), Some(ControlCommand::new("pactl", &["set-sink-volume", "@DEFAULT_SINK@", "40%"])));
+        assert_eq!(volume_command(TargetOs::MacOS, 250), Some(ControlCommand::new("osascript", &["-e", "set volume output volume 100"])));
+        assert_eq!(volume_command(TargetOs::Windows, 40), None);
+        assert_eq!(amixer_volume_command(120).args, ["-q", "set", "Master", "100%"]);
+    }
+
+    #[test]
+    fn mute_brightness_and_battery_commands_per_os() {
+        assert_eq!(mute_command(TargetOs::Linux).unwrap().args, ["set-sink-mute", "@DEFAULT_SINK@", "toggle"]);
+        assert_eq!(mute_command(TargetOs::MacOS).unwrap().program, "osascript");
+        assert_eq!(mute_command(TargetOs::Windows), None);
+
+        let brightness = brightness_command(TargetOs::Windows, 150).unwrap();
+        assert_eq!(brightness.program, "powershell");
+        assert!(brightness.args[2].ends_with("WmiSetBrightness(1,100)"));
+        assert_eq!(brightness_command(TargetOs::Linux, 50), None);
+        assert_eq!(brightness_command(TargetOs::MacOS, 50), None);
+
+        assert_eq!(battery_command(TargetOs::MacOS), Some(ControlCommand::new("pmset", &["-g", "batt"])));
+        assert_eq!(battery_command(TargetOs::Windows).unwrap().program, "powershell");
+        assert_eq!(battery_command(TargetOs::Linux), None);
+    }
+
+    #[test]
+    fn battery_output_parsing() {
+        let charging = "Now drawing from 'AC Power'\n -InternalBattery-0 (id=1234)\t85%; charging; 1:05 remaining present: true";
+        assert_eq!(parse_pmset_battery(charging), Some(BatteryStatus { percent: 85, charging: true }));
+        assert_eq!(parse_pmset_power(charging), PowerStatus { on_ac: true, battery_percent: Some(85) });
+
+        let discharging = "Now drawing from 'Battery Power'\n -InternalBattery-0 (id=1234)\t7%; discharging; 0:20 remaining";
+        assert_eq!(parse_pmset_battery(discharging), Some(BatteryStatus { percent: 7, charging: false }));
+        assert_eq!(parse_pmset_power(discharging).describe(), "on battery (7%)");
+        assert_eq!(parse_pmset_battery("Now drawing from 'AC Power'"), None);
+
+        assert_eq!(parse_windows_battery("85;2\r\n"), Some(BatteryStatus { percent: 85, charging: true }));
+        assert_eq!(parse_windows_battery("40;1"), Some(BatteryStatus { percent: 40, charging: false }));
+        assert_eq!(parse_windows_battery(""), None);
+    }
+
+    #[test]
+    fn name_has_owner_replies() {
+        let command = name_has_owner_command("org.kde.StatusNotifierWatcher");
+        assert_eq!(command.program, "dbus-send");
+        assert_eq!(command.args.last().unwrap(), "string:org.kde.StatusNotifierWatcher");
+
+        assert_eq!(parse_name_has_owner("method return time=1.2 sender=org.freedesktop.DBus\n   boolean true\n"), Some(true));
+        assert_eq!(parse_name_has_owner("method return time=1.2\n   boolean false"), Some(false));
+        assert_eq!(parse_name_has_owner("Error org.freedesktop.DBus.Error.ServiceUnknown"), None);
+        assert_eq!(parse_name_has_owner(""), None);
+    }
+
+    // Either watcher is enough; only a definite no from both rules the tray out
+    #[test]
+    fn status_notifier_detection() {
+        assert!(status_notifier_available(|name| Some(name == "org.kde.StatusNotifierWatcher")));
+        assert!(status_notifier_available(|name| Some(name == "org.freedesktop.StatusNotifierWatcher")));
+        assert!(!status_notifier_available(|_| Some(false)));
+        // No bus to ask, or only one answer: try the tray
+        assert!(status_notifier_available(|_| None));
+        assert!(status_notifier_available(|name| (name == "org.kde.StatusNotifierWatcher").then_some(false)));
+    }
+}
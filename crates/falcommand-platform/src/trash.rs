@@ -0,0 +1,75 @@
+use std::path::Path;
+
+use crate::platform::PlatformError;
+use crate::system::{ControlCommand, TargetOs};
+
+// AppleScript string literal: backslashes and quotes escaped
+pub fn applescript_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+// Command that moves `path` to the desktop trash; Windows goes through the shell API instead
+pub fn trash_command(os: TargetOs, path: &Path) -> Option<ControlCommand> {
+    let path = path.to_string_lossy();
+    match os {
+        // gio refuses rather than deleting when the filesystem has no trash
+        TargetOs::Linux => Some(ControlCommand::new("gio", &["trash", "--", &path])),
+        TargetOs::MacOS => Some(ControlCommand::new(
+            "osascript",
+            &["-e", &format!("tell application \"Finder\" to delete POSIX file {}", applescript_string(&path))],
+        )),
+        TargetOs::Windows => None,
+    }
+}
+
+// FOF_ALLOWUNDO sends the file to the Recycle Bin. On volumes without one FOF_WANTNUKEWARNING
+// asks before a permanent delete, and declining aborts the operation
+#[cfg(target_os = "windows")]
+fn recycle(path: &Path) -> Result<(), PlatformError> {
+    use std::os::windows::ffi::OsStrExt;
+    use winapi::um::shellapi::{
+        SHFileOperationW, SHFILEOPSTRUCTW, FO_DELETE, FOF_ALLOWUNDO, FOF_NOCONFIRMATION, FOF_NOERRORUI,
+        FOF_SILENT, FOF_WANTNUKEWARNING,
+    };
+
+    // The source list is double-null terminated
+    let from: Vec<u16> = path.as_os_str().encode_wide().chain([0, 0]).collect();
+    let mut operation = SHFILEOPSTRUCTW {
+        hwnd: std::ptr::null_mut(),
+        wFunc: FO_DELETE as u32,
+        pFrom: from.as_ptr(),
+        pTo: std::ptr::null(),
+        fFlags: FOF_ALLOWUNDO | FOF_NOCONFIRMATION | FOF_NOERRORUI | FOF_SILENT | FOF_WANTNUKEWARNING,
+        fAnyOperationsAborted: 0,
+        hNameMappings: std::ptr::null_mut(),
+        lpszProgressTitle: std::ptr::null(),
+    };
+
+    let result = unsafe { SHFileOperationW(&mut operation) };
+    if result != 0 || operation.fAnyOperationsAborted != 0 {
+        return Err(PlatformError::FileSystemError(format!(
+            "Could not move {} to the Recycle Bin (error {})",
+            path.display(),
+            result
+        )));
+    }
+    Ok(())
+}
+
+// Always uses the OS trash; a missing or unsupported trash is reported, never worked around by unlinking
+pub fn trash_file(os: TargetOs, path: &Path) -> Result<(), PlatformError> {
+    if !path.exists() {
+        return Err(PlatformError::FileSystemError(format!("{} does not exist", path.display())));
+    }
+
+    #[cfg(target_os = "windows")]
+    if os == TargetOs::Windows {
+        return recycle(path);
+    }
+
+    let command = trash_command(os, path)
+        .ok_or_else(|| PlatformError::Other("Moving files to the trash is not supported on this platform".to_string()))?;
+    command.run()
+        .map(|_| ())
+        .map_err(|e| PlatformError::FileSystemError(format!("Could not move {} to the trash: {}", path.display(), e)))
+}
\ No newline at end of file
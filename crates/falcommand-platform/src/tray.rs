@@ -0,0 +1,93 @@
+// The tray icon behind the `tray` feature. On Linux tray-icon links GTK, so the library crates
+// build without it and only the launcher binary turns it on
+use crate::platform::PlatformError;
+
+#[cfg(feature = "tray")]
+mod imp {
+    use super::PlatformError;
+    use tray_icon::{TrayIcon, TrayIconBuilder, menu::{Menu, MenuItem}};
+
+    // tray-icon's tray is neither Send nor Sync, so it can't be a field of a shared provider. It lives
+    // on the thread that created it; elsewhere there is no tray to change
+    thread_local! {
+        static TRAY_ICON: std::cell::RefCell<Option<TrayIcon>> = const { std::cell::RefCell::new(None) };
+    }
+
+    fn tray_error(error: impl std::fmt::Display) -> PlatformError {
+        PlatformError::SystemTrayError(error.to_string())
+    }
+
+    fn with_tray(update: impl FnOnce(&TrayIcon) -> Result<(), PlatformError>) -> Result<(), PlatformError> {
+        TRAY_ICON.with(|tray| match *tray.borrow() {
+            Some(ref tray) => update(tray),
+            None => Ok(()),
+        })
+    }
+
+    pub fn create_tray(tooltip: &str, icon_data: Option<&[u8]>) -> Result<(), PlatformError> {
+        let mut tray_builder = TrayIconBuilder::new()
+            .with_tooltip(tooltip);
+
+        // Set icon if provided
+        if let Some(data) = icon_data {
+            if let Ok(icon) = tray_icon::Icon::from_rgba(data.to_vec(), 32, 32) {
+                tray_builder = tray_builder.with_icon(icon);
+            }
+        }
+
+        // Create menu
+        let show_item = MenuItem::new("Show", true, None);
+        let quit_item = MenuItem::new("Quit", true, None);
+        let menu = Menu::new();
+        menu.append_items(&[&show_item, &quit_item]).map_err(tray_error)?;
+
+        tray_builder = tray_builder.with_menu(Box::new(menu));
+
+        let tray = tray_builder.build().map_err(tray_error)?;
+        TRAY_ICON.with(|current| *current.borrow_mut() = Some(tray));
+        Ok(())
+    }
+
+    pub fn set_visible(visible: bool) -> Result<(), PlatformError> {
+        with_tray(|tray| tray.set_visible(visible).map_err(tray_error))
+    }
+
+    pub fn set_tooltip(text: &str) -> Result<(), PlatformError> {
+        with_tray(|tray| tray.set_tooltip(Some(text)).map_err(tray_error))
+    }
+
+    pub fn set_icon(icon_data: &[u8]) -> Result<(), PlatformError> {
+        with_tray(|tray| {
+            let icon = tray_icon::Icon::from_rgba(icon_data.to_vec(), 32, 32).map_err(tray_error)?;
+            tray.set_icon(Some(icon)).map_err(tray_error)
+        })
+    }
+}
+
+// Without the feature there is never a tray, so creating one fails and the callers fall back to
+// their tray-less behaviour; changing the missing tray is a no-op like before creation
+#[cfg(not(feature = "tray"))]
+mod imp {
+    use super::PlatformError;
+
+    pub fn create_tray(_tooltip: &str, _icon_data: Option<&[u8]>) -> Result<(), PlatformError> {
+        Err(PlatformError::SystemTrayError("Built without the tray feature".to_string()))
+    }
+
+    pub fn set_visible(_visible: bool) -> Result<(), PlatformError> {
+        Ok(())
+    }
+
+    pub fn set_tooltip(_text: &str) -> Result<(), PlatformError> {
+        Ok(())
+    }
+
+    pub fn set_icon(_icon_data: &[u8]) -> Result<(), PlatformError> {
+        Ok(())
+    }
+}
+
+pub(crate) use imp::{create_tray, set_icon, set_tooltip, set_visible};
+
+// Whether this build can show a tray icon at all
+pub(crate) const TRAY_SUPPORTED: bool = cfg!(feature = "tray");
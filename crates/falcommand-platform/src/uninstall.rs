@@ -0,0 +1,266 @@
+use std::path::PathBuf;
+
+use crate::platform::AppInfo;
+use crate::app_identity::windows_identity;
+
+// Where Windows lists installed programs, under HKEY_LOCAL_MACHINE (all users) and
+// HKEY_CURRENT_USER (per-user installs); WOW6432Node holds the 32-bit programs on 64-bit Windows
+pub const UNINSTALL_KEYS: &[&str] = &[
+    r"SOFTWARE\Microsoft\Windows\CurrentVersion\Uninstall",
+    r"SOFTWARE\WOW6432Node\Microsoft\Windows\CurrentVersion\Uninstall",
+];
+
+// The values of one program's Uninstall subkey that describe it
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UninstallEntry {
+    pub display_name: Option<String>,
+    // "<path>[,<icon index>]", usually the program's main executable
+    pub display_icon: Option<String>,
+    pub publisher: Option<String>,
+    // Updates and shared components hide themselves from Programs and Features with one of these
+    pub system_component: bool,
+    pub parent_key_name: Option<String>,
+}
+
+impl UninstallEntry {
+    // None for hidden entries and ones without an .exe to launch. `env` expands %VARIABLES% in
+    // REG_EXPAND_SZ paths
+    pub fn to_app_info<F>(&self, env: F) -> Option<AppInfo>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        if self.system_component || self.parent_key_name.is_some() {
+            return None;
+        }
+        let name = self.display_name.as_deref().map(str::trim).filter(|name| !name.is_empty())?;
+        let executable = icon_executable(&expand_env(self.display_icon.as_deref()?, env))?;
+
+        let mut app = AppInfo::new(name, executable.clone()).with_icon(executable);
+        // Uninstall entries carry no AppUserModelID, so product and publisher name the app
+        if let Some(identity) = windows_identity(None, name, self.publisher.as_deref().unwrap_or("").trim()) {
+            app = app.with_identity(identity);
+        }
+        Some(app)
+    }
+}
+
+// DisplayIcon is a path, possibly quoted and followed by ",<icon index>"; only an .exe is launchable
+pub fn icon_executable(display_icon: &str) -> Option<PathBuf> {
+    let path = display_icon.trim();
+    let path = match path.rsplit_once(',') {
+        Some((path, index)) if index.trim().trim_start_matches('-').parse::<u32>().is_ok() => path,
+        _ => path,
+    };
+    let path = path.trim().trim_matches('"');
+    path.to_lowercase().ends_with(".exe").then(|| PathBuf::from(path))
+}
+
+// "%ProgramFiles%\App\app.exe" with the variables `env` knows replaced; unknown ones are left as they are
+pub fn expand_env<F>(text: &str, env: F) -> String
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut expanded = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('%') {
+        let Some(length) = rest[start + 1..].find('%') else {
+            break;
+        };
+        let name = &rest[start + 1..start + 1 + length];
+        expanded.push_str(&rest[..start]);
+        match env(name) {
+            Some(value) if !name.is_empty() => expanded.push_str(&value),
+            _ => expanded.push_str(&rest[start..start + length + 2]),
+        }
+        rest = &rest[start + length + 2..];
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+// Every program listed under UNINSTALL_KEYS, for both users and machine
+#[cfg(target_os = "windows")]
+pub fn installed_programs() -> Vec<UninstallEntry> {
+    use winapi::um::winreg::{HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE};
+    use registry::Key;
+
+    let mut entries = Vec::new();
+    for root in [HKEY_LOCAL_MACHINE, HKEY_CURRENT_USER] {
+        for path in UNINSTALL_KEYS {
+            let Some(uninstall) = Key::open(root, path) else {
+                continue;
+            };
+            for name in uninstall.subkey_names() {
+                let Some(program) = uninstall.subkey(&name) else {
+                    continue;
+                };
+                entries.push(UninstallEntry {
+                    display_name: program.string("DisplayName"),
+                    display_icon: program.string("DisplayIcon"),
+                    publisher: program.string("Publisher"),
+                    system_component: program.dword("SystemComponent") == Some(1),
+                    parent_key_name: program.string("ParentKeyName"),
+                });
+            }
+        }
+    }
+    entries
+}
+
+#[cfg(target_os = "windows")]
+mod registry {
+    use std::ffi::OsStr;
+    use std::os::windows::ffi::OsStrExt;
+    use std::ptr;
+    use winapi::shared::minwindef::{DWORD, HKEY};
+    use winapi::shared::winerror::ERROR_SUCCESS;
+    use winapi::um::winnt::{KEY_READ, REG_DWORD, REG_EXPAND_SZ, REG_SZ};
+    use winapi::um::winreg::{RegCloseKey, RegEnumKeyExW, RegOpenKeyExW, RegQueryValueExW};
+
+    fn wide(text: &str) -> Vec<u16> {
+        OsStr::new(text).encode_wide().chain(Some(0)).collect()
+    }
+
+    // An open registry key, closed on drop
+    pub struct Key(HKEY);
+
+    impl Drop for Key {
+        fn drop(&mut self) {
+            unsafe {
+                RegCloseKey(self.0);
+            }
+        }
+    }
+
+    impl Key {
+        pub fn open(parent: HKEY, path: &str) -> Option<Key> {
+            let mut key = ptr::null_mut();
+            let status = unsafe { RegOpenKeyExW(parent, wide(path).as_ptr(), 0, KEY_READ, &mut key) };
+            (status as DWORD == ERROR_SUCCESS).then_some(Key(key))
+        }
+
+        pub fn subkey(&self, name: &str) -> Option<Key> {
+            Self::open(self.0, name)
+        }
+
+        pub fn subkey_names(&self) -> Vec<String> {
+            // Key names are at most 255 characters
+            let mut names = Vec::new();
+            let mut name = [0u16; 256];
+            for index in 0.. {
+                let mut length = name.len() as DWORD;
+                let status = unsafe {
+                    RegEnumKeyExW(
+                        self.0,
+                        index,
+                        name.as_mut_ptr(),
+                        &mut length,
+                        ptr::null_mut(),
+                        ptr::null_mut(),
+                        ptr::null_mut(),
+                        ptr::null_mut(),
+                    )
+                };
+                if status as DWORD != ERROR_SUCCESS {
+                    break;
+                }
+                names.push(String::from_utf16_lossy(&name[..length as usize]));
+            }
+            names
+        }
+
+        // REG_SZ and REG_EXPAND_SZ values, unexpanded
+        pub fn string(&self, name: &str) -> Option<String> {
+            let name = wide(name);
+            let (mut kind, mut size): (DWORD, DWORD) = (0, 0);
+            let status = unsafe {
+                RegQueryValueExW(self.0, name.as_ptr(), ptr::null_mut(), &mut kind, ptr::null_mut(), &mut size)
+            };
+            if status as DWORD != ERROR_SUCCESS || (kind != REG_SZ && kind != REG_EXPAND_SZ) {
+                return None;
+            }
+
+            let mut buffer = vec![0u16; (size as usize).div_ceil(2)];
+            let status = unsafe {
+                RegQueryValueExW(self.0, name.as_ptr(), ptr::null_mut(), &mut kind, buffer.as_mut_ptr().cast(), &mut size)
+            };
+            if status as DWORD != ERROR_SUCCESS {
+                return None;
+            }
+            buffer.truncate(size as usize / 2);
+            // Stored strings may or may not end in a terminator
+            while buffer.last() == Some(&0) {
+                buffer.pop();
+            }
+            Some(String::from_utf16_lossy(&buffer))
+        }
+
+        pub fn dword(&self, name: &str) -> Option<u32> {
+            let name = wide(name);
+            let mut kind: DWORD = 0;
+            let mut value: DWORD = 0;
+            let mut size = std::mem::size_of::<DWORD>() as DWORD;
+            let status = unsafe {
+                RegQueryValueExW(
+                    self.0,
+                    name.as_ptr(),
+                    ptr::null_mut(),
+                    &mut kind,
+                    (&mut value as *mut DWORD).cast(),
+                    &mut size,
+                )
+            };
+            (status as DWORD == ERROR_SUCCESS && kind == REG_DWORD).then_some(value)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env(name: &str) -> Option<String> {
+        (name == "ProgramFiles").then(|| r"C:\Program Files".to_string())
+    }
+
+    #[test]
+    fn icon_index_and_quotes_are_stripped() {
+        assert_eq!(icon_executable(r#""C:\Apps\app.exe",0"#), Some(PathBuf::from(r"C:\Apps\app.exe")));
+        assert_eq!(icon_executable(r"C:\Apps\app.exe,-101"), Some(PathBuf::from(r"C:\Apps\app.exe")));
+        assert_eq!(icon_executable(r"C:\Apps\app.ico"), None);
+    }
+
+    #[test]
+    fn environment_variables_expand() {
+        assert_eq!(expand_env(r"%ProgramFiles%\VLC\vlc.exe", env), r"C:\Program Files\VLC\vlc.exe");
+        assert_eq!(expand_env(r"%Unknown%\a.exe", env), r"%Unknown%\a.exe");
+        assert_eq!(expand_env("100% done", env), "100% done");
+    }
+
+    #[test]
+    fn apps_carry_the_windows_identity() {
+        let entry = UninstallEntry {
+            display_name: Some("VLC media player".to_string()),
+            display_icon: Some(r"%ProgramFiles%\VideoLAN\VLC\vlc.exe,0".to_string()),
+            publisher: Some("VideoLAN".to_string()),
+            ..UninstallEntry::default()
+        };
+
+        let app = entry.to_app_info(env).unwrap();
+        assert_eq!(app.executable_path, PathBuf::from(r"C:\Program Files\VideoLAN\VLC\vlc.exe"));
+        assert_eq!(app.identity.as_deref(), Some("vlc"));
+    }
+
+    #[test]
+    fn hidden_entries_are_skipped() {
+        let entry = UninstallEntry {
+            display_name: Some("Update for App".to_string()),
+            display_icon: Some(r"C:\Apps\app.exe".to_string()),
+            parent_key_name: Some("App".to_string()),
+            ..UninstallEntry::default()
+        };
+
+        assert!(entry.to_app_info(env).is_none());
+        assert!(UninstallEntry { system_component: true, parent_key_name: None, ..entry }.to_app_info(env).is_none());
+    }
+}
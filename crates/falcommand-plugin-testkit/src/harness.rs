@@ -0,0 +1,168 @@
+use std::future::Future;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+
+use falcommand_config::{Config, SearchResult};
+use falcommand_plugins::{Plugin, PluginContext, PluginSystem};
+use crate::http::ScriptedHttp;
+use crate::platform::RecordingPlatform;
+
+// Set to 1 to rewrite golden files instead of comparing against them
+pub const UPDATE_GOLDEN_ENV: &str = "FALCOMMAND_UPDATE_GOLDEN";
+
+// In-memory config, recording platform and scripted HTTP shared by every context it hands out
+pub struct PluginHarness {
+    config: Arc<RwLock<Config>>,
+    platform: Arc<RecordingPlatform>,
+    http: Arc<ScriptedHttp>,
+}
+
+impl Default for PluginHarness {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PluginHarness {
+    pub fn new() -> Self {
+        Self {
+            config: Arc::new(RwLock::new(Config::default())),
+            platform: Arc::new(RecordingPlatform::new()),
+            http: Arc::new(ScriptedHttp::new()),
+        }
+    }
+
+    pub fn with_config(mut self, config: Config) -> Self {
+        self.config = Arc::new(RwLock::new(config));
+        self
+    }
+
+    // Settings the plugin reads from `plugins.plugin_settings.<plugin_id>`
+    pub fn with_plugin_settings(self, plugin_id: &str, settings: serde_json::Value) -> Self {
+        self.config.try_write()
+            .expect("the harness config is not shared until context() is called")
+            .plugins.plugin_settings.insert(plugin_id.to_lowercase(), settings);
+        self
+    }
+
+    // Pass this to the plugin's constructor
+    pub fn context(&self) -> PluginContext {
+        PluginContext::new(self.config.clone())
+            .with_platform(self.platform.clone())
+            .with_http_client(self.http.clone())
+    }
+
+    pub fn config(&self) -> &Arc<RwLock<Config>> {
+        &self.config
+    }
+
+    pub fn platform(&self) -> &RecordingPlatform {
+        &self.platform
+    }
+
+    pub fn http(&self) -> &ScriptedHttp {
+        &self.http
+    }
+
+    // Each query goes through can_handle and, if handled, search, as PluginSystem::search_all does
+    pub async fn run_queries(&self, plugin: &dyn Plugin, queries: &[&str]) -> Vec<QueryOutcome> {
+        let mut outcomes = Vec::new();
+        for query in queries {
+            let handled = plugin.can_handle(query);
+            let (results, error) = if handled {
+                match tokio::time::timeout(PluginSystem::PLUGIN_TIMEOUT, plugin.search(query)).await {
+                    Ok(Ok(results)) => (results, None),
+                    Ok(Err(e)) => (Vec::new(), Some(e.to_string())),
+                    Err(_) => (Vec::new(), Some("timed out".to_string())),
+                }
+            } else {
+                (Vec::new(), None)
+            };
+            outcomes.push(QueryOutcome {
+                query: query.to_string(),
+                handled,
+                results,
+                error,
+            });
+        }
+        outcomes
+    }
+}
+
+// What one query produced, in the shape golden files store
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueryOutcome {
+    pub query: String,
+    pub handled: bool,
+    pub results: Vec<SearchResult>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+// Compares the pretty JSON of `value` with the file at `path`. A missing file is written, as is
+// every file while FALCOMMAND_UPDATE_GOLDEN=1; review the diff before committing it
+pub fn assert_golden<T: Serialize>(path: impl AsRef<Path>, value: &T) {
+    let path = path.as_ref();
+    let actual = serde_json::to_string_pretty(value).expect("golden values serialize to JSON") + "\n";
+    let update = std::env::var(UPDATE_GOLDEN_ENV).is_ok_and(|value| value == "1");
+
+    if update || !path.exists() {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).expect("golden directory can be created");
+        }
+        std::fs::write(path, &actual).expect("golden file can be written");
+        return;
+    }
+
+    let expected = std::fs::read_to_string(path).expect("golden file can be read");
+    if expected.replace("\r\n", "\n") != actual {
+        let line = expected.lines()
+            .zip(actual.lines())
+            .position(|(expected, actual)| expected != actual)
+            .unwrap_or_else(|| expected.lines().count().min(actual.lines().count()));
+        panic!(
+            "{} does not match (first difference at line {}); rerun with {}=1 to update it\n--- expected\n{}\n--- actual\n{}",
+            path.display(),
+            line + 1,
+            UPDATE_GOLDEN_ENV,
+            expected,
+            actual,
+        );
+    }
+}
+
+// Runs `future` and returns its output with how long it took
+pub async fn timed<F: Future>(future: F) -> (F::Output, Duration) {
+    let started = Instant::now();
+    let output = future.await;
+    (output, started.elapsed())
+}
+
+// Fails when `future` takes longer than the limit PluginSystem enforces on every plugin call
+pub async fn assert_within_plugin_timeout<F: Future>(label: &str, future: F) -> F::Output {
+    let (output, elapsed) = timed(future).await;
+    assert!(
+        elapsed <= PluginSystem::PLUGIN_TIMEOUT,
+        "{} took {:?}; plugin calls are cut off after {:?}",
+        label,
+        elapsed,
+        PluginSystem::PLUGIN_TIMEOUT,
+    );
+    output
+}
+
+pub async fn assert_search_in_time(plugin: &dyn Plugin, query: &str) -> Vec<SearchResult> {
+    assert_within_plugin_timeout(&format!("search({:?})", query), plugin.search(query))
+        .await
+        .unwrap_or_else(|e| panic!("search({:?}) failed: {}", query, e))
+}
+
+// execute must return quickly; slow work belongs in PluginContext::spawn_tracked
+pub async fn assert_execute_in_time(plugin: &dyn Plugin, result: &SearchResult) {
+    assert_within_plugin_timeout(&format!("execute({:?})", result.title), plugin.execute(result))
+        .await
+        .unwrap_or_else(|e| panic!("execute({:?}) failed: {}", result.title, e));
+}
\ No newline at end of file
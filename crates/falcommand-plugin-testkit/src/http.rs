@@ -0,0 +1,115 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use async_trait::async_trait;
+
+use falcommand_plugins::{HttpClient, PluginError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HttpMethod {
+    Get,
+    Post,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpRequest {
+    pub method: HttpMethod,
+    pub url: String,
+    pub body: Option<String>,
+}
+
+#[derive(Debug)]
+struct Expectation {
+    method: HttpMethod,
+    url: String,
+    response: std::result::Result<String, String>,
+    // Answered every time instead of once
+    repeat: bool,
+}
+
+// HttpClient answering from a script. Expectations are matched by method and URL in the order
+// they were added; a request nothing matches fails with a PluginError naming it
+#[derive(Debug, Default)]
+pub struct ScriptedHttp {
+    expectations: Mutex<VecDeque<Expectation>>,
+    requests: Mutex<Vec<HttpRequest>>,
+}
+
+impl ScriptedHttp {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn expect(&self, method: HttpMethod, url: &str, response: std::result::Result<String, String>, repeat: bool) -> &Self {
+        self.expectations.lock().unwrap().push_back(Expectation {
+            method,
+            url: url.to_string(),
+            response,
+            repeat,
+        });
+        self
+    }
+
+    pub fn expect_get(&self, url: &str, response: impl Into<String>) -> &Self {
+        self.expect(HttpMethod::Get, url, Ok(response.into()), false)
+    }
+
+    pub fn expect_post(&self, url: &str, response: impl Into<String>) -> &Self {
+        self.expect(HttpMethod::Post, url, Ok(response.into()), false)
+    }
+
+    // The request fails with `error`, e.g. to test offline handling
+    pub fn fail_get(&self, url: &str, error: impl Into<String>) -> &Self {
+        self.expect(HttpMethod::Get, url, Err(error.into()), false)
+    }
+
+    pub fn always_get(&self, url: &str, response: impl Into<String>) -> &Self {
+        self.expect(HttpMethod::Get, url, Ok(response.into()), true)
+    }
+
+    pub fn requests(&self) -> Vec<HttpRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    // Scripted one-off responses nothing asked for
+    pub fn unused(&self) -> Vec<String> {
+        self.expectations.lock().unwrap()
+            .iter()
+            .filter(|expectation| !expectation.repeat)
+            .map(|expectation| format!("{:?} {}", expectation.method, expectation.url))
+            .collect()
+    }
+
+    pub fn assert_all_used(&self) {
+        let unused = self.unused();
+        assert!(unused.is_empty(), "scripted HTTP responses were never requested: {:?}", unused);
+    }
+
+    fn respond(&self, method: HttpMethod, url: &str, body: Option<&str>) -> std::result::Result<String, PluginError> {
+        self.requests.lock().unwrap().push(HttpRequest {
+            method,
+            url: url.to_string(),
+            body: body.map(str::to_string),
+        });
+
+        let mut expectations = self.expectations.lock().unwrap();
+        let position = expectations.iter()
+            .position(|expectation| expectation.method == method && expectation.url == url)
+            .ok_or_else(|| PluginError::Other(format!("Unexpected HTTP {:?} {}", method, url)))?;
+        let response = expectations[position].response.clone();
+        if !expectations[position].repeat {
+            expectations.remove(position);
+        }
+        response.map_err(PluginError::Other)
+    }
+}
+
+#[async_trait]
+impl HttpClient for ScriptedHttp {
+    async fn get(&self, url: &str) -> std::result::Result<String, PluginError> {
+        self.respond(HttpMethod::Get, url, None)
+    }
+
+    async fn post(&self, url: &str, body: &str) -> std::result::Result<String, PluginError> {
+        self.respond(HttpMethod::Post, url, Some(body))
+    }
+}
\ No newline at end of file
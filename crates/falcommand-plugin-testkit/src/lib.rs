@@ -0,0 +1,20 @@
+// Test harness for FalCommand plugins, usable from plugin crates outside this workspace:
+//
+//     let harness = PluginHarness::new();
+//     let plugin = CalculatorPlugin::new(harness.context());
+//     let outcomes = harness.run_queries(&plugin, &["12+30", "hello"]).await;
+//     assert_golden("tests/golden/calculator.json", &outcomes);
+//
+//     let results = assert_search_in_time(&plugin, "12+30").await;
+//     assert_execute_in_time(&plugin, &results[0]).await;
+//     assert_eq!(harness.platform().clipboard().as_deref(), Some("42"));
+//
+// tests/ holds the Calculator, Translator and Network tests; examples/plugin-testkit-example is a plugin crate
+// outside the workspace tested the same way
+pub mod harness;
+pub mod http;
+pub mod platform;
+
+pub use harness::*;
+pub use http::*;
+pub use platform::*;
\ No newline at end of file
@@ -0,0 +1,264 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, RwLock};
+use std::sync::atomic::{AtomicBool, Ordering};
+use async_trait::async_trait;
+
+use falcommand_config::Theme;
+use falcommand_platform::{
+    finish_scan, scan_sources, AppInfo, BatteryStatus, NetworkInfo, PlatformError, PlatformProvider, ScanFn, ScanReport, SystemControls,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    pub title: String,
+    pub message: String,
+    // Label of the notification's button, if it has one
+    pub action: Option<String>,
+}
+
+type NotificationAction = Box<dyn FnOnce() + Send>;
+
+// A named sub-source of the application scan and what it returns
+type AppSource = (String, Result<Vec<AppInfo>, String>);
+
+// PlatformProvider that records notifications, clipboard writes and quick-control calls instead of
+// touching the desktop. Notification buttons are kept until a test clicks them. The quick controls
+// and battery it reports are whatever the test sets, and so are the application sources it scans.
+// Global hotkeys and notifications can be refused like on a desktop that doesn't allow them.
+// Everything else succeeds and does nothing
+#[derive(Default)]
+pub struct RecordingPlatform {
+    notifications: Mutex<Vec<Notification>>,
+    // Keyed by the notification's position in `notifications`
+    notification_actions: Mutex<HashMap<usize, NotificationAction>>,
+    clipboard: Mutex<Vec<String>>,
+    opened: Mutex<Vec<std::path::PathBuf>>,
+    system_controls: Mutex<SystemControls>,
+    battery: Mutex<Option<BatteryStatus>>,
+    // "volume 40", "mute", "brightness 70"
+    control_calls: Mutex<Vec<String>>,
+    app_sources: Mutex<Vec<AppSource>>,
+    scan_report: RwLock<ScanReport>,
+    refuse_hotkeys: AtomicBool,
+    refuse_notifications: AtomicBool,
+}
+
+impl std::fmt::Debug for RecordingPlatform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RecordingPlatform")
+            .field("notifications", &self.notifications)
+            .field("clipboard", &self.clipboard)
+            .field("opened", &self.opened)
+            .field("control_calls", &self.control_calls)
+            .finish_non_exhaustive()
+    }
+}
+
+impl RecordingPlatform {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn notifications(&self) -> Vec<Notification> {
+        self.notifications.lock().unwrap().clone()
+    }
+
+    // Every text copied, oldest first
+    pub fn clipboard_history(&self) -> Vec<String> {
+        self.clipboard.lock().unwrap().clone()
+    }
+
+    pub fn clipboard(&self) -> Option<String> {
+        self.clipboard.lock().unwrap().last().cloned()
+    }
+
+    // Clicks the button of the `index`th notification, oldest first. False when it has none or it
+    // was already clicked
+    pub fn click_notification_action(&self, index: usize) -> bool {
+        let action = self.notification_actions.lock().unwrap().remove(&index);
+        match action {
+            Some(action) => {
+                action();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn opened_paths(&self) -> Vec<std::path::PathBuf> {
+        self.opened.lock().unwrap().clone()
+    }
+
+    pub fn set_system_controls(&self, controls: SystemControls) {
+        *self.system_controls.lock().unwrap() = controls;
+    }
+
+    pub fn set_battery(&self, battery: Option<BatteryStatus>) {
+        *self.battery.lock().unwrap() = battery;
+    }
+
+    // Sub-sources the application scan runs, like the registry and Start Menu scans on Windows.
+    // An `Err` source fails with that message and the others are still returned
+    pub fn set_app_sources(&self, sources: Vec<(&str, Result<Vec<AppInfo>, String>)>) {
+        *self.app_sources.lock().unwrap() = sources.into_iter()
+            .map(|(name, outcome)| (name.to_string(), outcome))
+            .collect();
+    }
+
+    // Registering a global hotkey fails from now on
+    pub fn refuse_hotkeys(&self) {
+        self.refuse_hotkeys.store(true, Ordering::Relaxed);
+    }
+
+    // Showing a notification fails from now on, with or without a button
+    pub fn refuse_notifications(&self) {
+        self.refuse_notifications.store(true, Ordering::Relaxed);
+    }
+
+    // Quick controls performed, oldest first: "volume 40", "mute", "brightness 70"
+    pub fn control_calls(&self) -> Vec<String> {
+        self.control_calls.lock().unwrap().clone()
+    }
+
+    pub fn clear(&self) {
+        self.notifications.lock().unwrap().clear();
+        self.notification_actions.lock().unwrap().clear();
+        self.clipboard.lock().unwrap().clear();
+        self.opened.lock().unwrap().clear();
+        self.control_calls.lock().unwrap().clear();
+    }
+}
+
+#[async_trait]
+impl PlatformProvider for RecordingPlatform {
+    async fn get_installed_applications(&self) -> Result<Vec<AppInfo>, PlatformError> {
+        let sources: Vec<(String, ScanFn<'_, AppInfo>)> = self.app_sources.lock().unwrap().clone().into_iter()
+            .map(|(name, outcome)| {
+                let scan: ScanFn<'_, AppInfo> = Box::new(move || outcome.map_err(PlatformError::ApplicationScanError));
+                (name, scan)
+            })
+            .collect();
+        let (found, report) = scan_sources(sources);
+        finish_scan(&self.scan_report, found.concat(), report)
+    }
+
+    fn last_scan_report(&self) -> ScanReport {
+        self.scan_report.read().unwrap().clone()
+    }
+
+    fn register_global_hotkey(&self, hotkey: &str, _callback: Box<dyn Fn() + Send>) -> Result<(), PlatformError> {
+        if self.refuse_hotkeys.load(Ordering::Relaxed) {
+            return Err(PlatformError::HotkeyError(format!("{} is not allowed", hotkey)));
+        }
+        Ok(())
+    }
+
+    fn unregister_global_hotkey(&self, _hotkey: &str) -> Result<(), PlatformError> {
+        Ok(())
+    }
+
+    fn show_notification(&self, title: &str, message: &str) -> Result<(), PlatformError> {
+        if self.refuse_notifications.load(Ordering::Relaxed) {
+            return Err(PlatformError::NotificationError("notifications are turned off".to_string()));
+        }
+        self.notifications.lock().unwrap().push(Notification {
+            title: title.to_string(),
+            message: message.to_string(),
+            action: None,
+        });
+        Ok(())
+    }
+
+    fn show_notification_with_action(
+        &self,
+        title: &str,
+        message: &str,
+        action_label: &str,
+        on_action: Box<dyn FnOnce() + Send>,
+    ) -> Result<bool, PlatformError> {
+        if self.refuse_notifications.load(Ordering::Relaxed) {
+            return Err(PlatformError::NotificationError("notifications are turned off".to_string()));
+        }
+        let mut notifications = self.notifications.lock().unwrap();
+        notifications.push(Notification {
+            title: title.to_string(),
+            message: message.to_string(),
+            action: Some(action_label.to_string()),
+        });
+        self.notification_actions.lock().unwrap().insert(notifications.len() - 1, on_action);
+        Ok(true)
+    }
+
+    fn get_system_theme(&self) -> Theme {
+        Theme::System
+    }
+
+    async fn open_with_default_app(&self, path: &std::path::Path) -> Result<(), PlatformError> {
+        self.opened.lock().unwrap().push(path.to_path_buf());
+        Ok(())
+    }
+
+    fn copy_to_clipboard(&self, text: &str) -> Result<(), PlatformError> {
+        self.clipboard.lock().unwrap().push(text.to_string());
+        Ok(())
+    }
+
+    fn paste_from_clipboard(&self) -> Result<String, PlatformError> {
+        Ok(self.clipboard().unwrap_or_default())
+    }
+
+    fn create_system_tray(&self, _title: &str, _tooltip: &str, _icon_data: Option<&[u8]>) -> Result<(), PlatformError> {
+        Ok(())
+    }
+
+    fn show_system_tray(&self) -> Result<(), PlatformError> {
+        Ok(())
+    }
+
+    fn hide_system_tray(&self) -> Result<(), PlatformError> {
+        Ok(())
+    }
+
+    fn update_system_tray_menu(&self, _show_callback: Box<dyn Fn() + Send>, _quit_callback: Box<dyn Fn() + Send>) -> Result<(), PlatformError> {
+        Ok(())
+    }
+
+    fn set_tray_tooltip(&self, _text: &str) -> Result<(), PlatformError> {
+        Ok(())
+    }
+
+    fn set_tray_icon(&self, _icon_data: &[u8]) -> Result<(), PlatformError> {
+        Ok(())
+    }
+
+    fn system_controls(&self) -> SystemControls {
+        *self.system_controls.lock().unwrap()
+    }
+
+    fn get_battery_status(&self) -> Result<Option<BatteryStatus>, PlatformError> {
+        Ok(self.battery.lock().unwrap().clone())
+    }
+
+    fn set_volume(&self, percent: u8) -> Result<(), PlatformError> {
+        self.control_calls.lock().unwrap().push(format!("volume {}", percent));
+        Ok(())
+    }
+
+    fn toggle_mute(&self) -> Result<(), PlatformError> {
+        self.control_calls.lock().unwrap().push("mute".to_string());
+        Ok(())
+    }
+
+    fn set_brightness(&self, percent: u8) -> Result<(), PlatformError> {
+        self.control_calls.lock().unwrap().push(format!("brightness {}", percent));
+        Ok(())
+    }
+
+    fn get_network_info(&self) -> Result<NetworkInfo, PlatformError> {
+        Ok(NetworkInfo::default())
+    }
+
+    fn trash_file(&self, _path: &std::path::Path) -> Result<(), PlatformError> {
+        Ok(())
+    }
+}
\ No newline at end of file
@@ -0,0 +1,35 @@
+use falcommand_config::Action;
+use falcommand_plugin_testkit::{assert_execute_in_time, assert_golden, assert_search_in_time, PluginHarness};
+use falcommand_plugins::CalculatorPlugin;
+
+#[tokio::test]
+async fn queries_match_the_golden_file() {
+    let harness = PluginHarness::new();
+    let plugin = CalculatorPlugin::new(harness.context());
+
+    let outcomes = harness.run_queries(&plugin, &["12+30", "7.5-2", "42", "1*2", "hello"]).await;
+    assert_golden(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden/calculator.json"), &outcomes);
+}
+
+#[tokio::test]
+async fn execute_copies_the_plain_value_and_notifies() {
+    let harness = PluginHarness::new();
+    let plugin = CalculatorPlugin::new(harness.context());
+
+    let results = assert_search_in_time(&plugin, "12+30").await;
+    assert_eq!(results[0].action, Action::CopyToClipboard("42".to_string()));
+    assert_execute_in_time(&plugin, &results[0]).await;
+
+    assert_eq!(harness.platform().clipboard().as_deref(), Some("42"));
+    assert_eq!(harness.platform().notifications()[0].title, "Calculator");
+}
+
+#[tokio::test]
+async fn file_names_with_digits_are_not_handled() {
+    let harness = PluginHarness::new();
+    let plugin = CalculatorPlugin::new(harness.context());
+
+    let outcomes = harness.run_queries(&plugin, &["hello"]).await;
+    assert!(!outcomes[0].handled);
+    assert!(assert_search_in_time(&plugin, "file2.txt").await.is_empty());
+}
@@ -0,0 +1,62 @@
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+
+use falcommand_config::{Config, SearchResult};
+use falcommand_plugin_testkit::{assert_search_in_time, PluginHarness};
+use falcommand_plugins::{NetworkPlugin, Plugin, PluginContext};
+
+const PUBLIC_IP_URL: &str = "https://api.ipify.org";
+
+// Refreshes the Public IP row until its lookup has finished
+async fn refreshed_public_ip(plugin: &NetworkPlugin, result: &SearchResult) -> SearchResult {
+    for _ in 0..100 {
+        let refreshed = plugin.refresh(result).await.unwrap().unwrap();
+        if refreshed.description != "Looking up…" {
+            return refreshed;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("the public IP lookup never finished");
+}
+
+fn public_ip_row(results: &[SearchResult]) -> &SearchResult {
+    results.iter().find(|result| result.title.starts_with("Public IP")).unwrap()
+}
+
+#[tokio::test]
+async fn public_ip_is_fetched_through_the_http_client() {
+    let harness = PluginHarness::new();
+    harness.http().expect_get(PUBLIC_IP_URL, "203.0.113.7\n");
+    let plugin = NetworkPlugin::new(harness.context());
+
+    let results = assert_search_in_time(&plugin, "ip").await;
+    assert_eq!(public_ip_row(&results).description, "Looking up…");
+
+    let refreshed = refreshed_public_ip(&plugin, public_ip_row(&results)).await;
+    assert_eq!(refreshed.title, "Public IP 203.0.113.7");
+    harness.http().assert_all_used();
+
+    // Cached: the next search answers without another request
+    let results = assert_search_in_time(&plugin, "ip").await;
+    assert_eq!(public_ip_row(&results).title, "Public IP 203.0.113.7");
+    assert_eq!(harness.http().requests().len(), 1);
+}
+
+#[tokio::test]
+async fn failed_lookups_show_as_unavailable() {
+    let harness = PluginHarness::new();
+    harness.http().fail_get(PUBLIC_IP_URL, "connection refused");
+    let plugin = NetworkPlugin::new(harness.context());
+
+    let results = assert_search_in_time(&plugin, "ip").await;
+    let refreshed = refreshed_public_ip(&plugin, public_ip_row(&results)).await;
+    assert_eq!((refreshed.title.as_str(), refreshed.description.as_str()), ("Public IP", "Unavailable"));
+}
+
+#[tokio::test]
+async fn requests_fail_without_an_http_client() {
+    let context = PluginContext::new(Arc::new(RwLock::new(Config::default())));
+    assert!(context.http_get(PUBLIC_IP_URL).await.is_err());
+    assert!(context.http_post(PUBLIC_IP_URL, "{}").await.is_err());
+}
@@ -0,0 +1,65 @@
+use falcommand_platform::{BatteryStatus, SystemControls};
+use falcommand_plugin_testkit::{assert_execute_in_time, assert_search_in_time, PluginHarness};
+use falcommand_plugins::{Plugin, SystemCommand, SystemControlsPlugin};
+
+const ALL_CONTROLS: SystemControls = SystemControls { battery: true, volume: true, mute: true, brightness: true };
+
+#[test]
+fn queries_parse_and_levels_are_clamped() {
+    assert_eq!(SystemCommand::parse("volume 40"), Some(SystemCommand::Volume(40)));
+    assert_eq!(SystemCommand::parse(" VOL 40% "), Some(SystemCommand::Volume(40)));
+    assert_eq!(SystemCommand::parse("volume 250"), Some(SystemCommand::Volume(100)));
+    assert_eq!(SystemCommand::parse("brightness 0"), Some(SystemCommand::Brightness(0)));
+    assert_eq!(SystemCommand::parse("unmute"), Some(SystemCommand::Mute));
+    assert_eq!(SystemCommand::parse("battery"), Some(SystemCommand::Battery));
+
+    assert_eq!(SystemCommand::parse("volume"), None);
+    assert_eq!(SystemCommand::parse("volume -5"), None);
+    assert_eq!(SystemCommand::parse("mute now"), None);
+    assert_eq!(SystemCommand::parse("battery saver"), None);
+}
+
+#[tokio::test]
+async fn results_run_the_control_on_the_platform() {
+    let harness = PluginHarness::new();
+    harness.platform().set_system_controls(ALL_CONTROLS);
+    let plugin = SystemControlsPlugin::new(harness.context());
+
+    for query in ["volume 140", "mute", "brightness 70"] {
+        let results = assert_search_in_time(&plugin, query).await;
+        assert_execute_in_time(&plugin, &results[0]).await;
+    }
+
+    assert_eq!(harness.platform().control_calls(), ["volume 100", "mute", "brightness 70"]);
+}
+
+#[tokio::test]
+async fn unsupported_controls_give_no_result() {
+    let harness = PluginHarness::new();
+    harness.platform().set_system_controls(SystemControls { volume: true, ..SystemControls::default() });
+    let plugin = SystemControlsPlugin::new(harness.context());
+
+    assert_eq!(assert_search_in_time(&plugin, "volume 40").await[0].title, "Set volume to 40%");
+    assert!(assert_search_in_time(&plugin, "brightness 40").await.is_empty());
+    assert!(assert_search_in_time(&plugin, "mute").await.is_empty());
+}
+
+#[tokio::test]
+async fn battery_result_refreshes_with_the_platform_status() {
+    let harness = PluginHarness::new();
+    harness.platform().set_system_controls(ALL_CONTROLS);
+    harness.platform().set_battery(Some(BatteryStatus { percent: 80, charging: true }));
+    let plugin = SystemControlsPlugin::new(harness.context());
+
+    let results = assert_search_in_time(&plugin, "battery").await;
+    assert_eq!(results[0].description, "80% · charging");
+
+    harness.platform().set_battery(Some(BatteryStatus { percent: 79, charging: false }));
+    let refreshed = plugin.refresh(&results[0]).await.unwrap().unwrap();
+    assert_eq!(refreshed.description, "79% · on battery");
+    assert_eq!(refreshed.id, results[0].id);
+
+    // A machine without a battery has nothing to show
+    harness.platform().set_battery(None);
+    assert!(assert_search_in_time(&plugin, "battery").await.is_empty());
+}
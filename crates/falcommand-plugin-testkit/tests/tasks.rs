@@ -0,0 +1,114 @@
+use std::sync::Arc;
+use std::time::Duration;
+use async_trait::async_trait;
+use tokio::sync::{mpsc, Mutex, RwLock};
+
+use falcommand_config::{Config, SearchResult};
+use falcommand_plugin_testkit::{Notification, RecordingPlatform};
+use falcommand_plugins::{Plugin, PluginContext, PluginError, PluginSystem, TaskProgress, TaskState};
+
+// Executing starts an "Export" task that takes one step per message on `steps`: two progress
+// reports out of three, then it fails before the last one
+#[derive(Debug)]
+struct ExportPlugin {
+    context: PluginContext,
+    steps: Arc<Mutex<mpsc::UnboundedReceiver<()>>>,
+}
+
+#[async_trait]
+impl Plugin for ExportPlugin {
+    fn name(&self) -> &str {
+        "Export"
+    }
+
+    fn version(&self) -> &str {
+        "1.0.0"
+    }
+
+    fn description(&self) -> &str {
+        "Exports in three steps"
+    }
+
+    fn can_handle(&self, _query: &str) -> bool {
+        false
+    }
+
+    async fn search(&self, _query: &str) -> Result<Vec<SearchResult>, PluginError> {
+        Ok(Vec::new())
+    }
+
+    async fn execute(&self, _result: &SearchResult) -> Result<(), PluginError> {
+        let (id_sender, id_receiver) = tokio::sync::oneshot::channel();
+        let context = self.context.clone();
+        let steps = self.steps.clone();
+        let task_id = self.context.spawn_tracked("Export", async move {
+            let task_id = id_receiver.await.expect("the task id is sent after spawning");
+            let mut steps = steps.lock().await;
+            for (done, message) in [(1.0, "reading files"), (2.0, "writing files")] {
+                steps.recv().await;
+                context.report_progress(task_id, done / 3.0, message);
+            }
+            steps.recv().await;
+            Err(PluginError::ExecutionError("disk full".to_string()))
+        });
+        let _ = id_sender.send(task_id);
+        Ok(())
+    }
+}
+
+async fn next_update(updates: &mut tokio::sync::broadcast::Receiver<TaskProgress>) -> TaskProgress {
+    tokio::time::timeout(Duration::from_secs(5), updates.recv()).await
+        .expect("the task reports in time")
+        .expect("the tracker is alive")
+}
+
+fn summaries(system: &PluginSystem) -> Vec<String> {
+    system.running_tasks().iter().map(|task| task.summary()).collect()
+}
+
+#[tokio::test]
+async fn a_task_failing_midway_is_reported_and_leaves_the_tooltip() {
+    let platform = Arc::new(RecordingPlatform::new());
+    let system = PluginSystem::new(Arc::new(RwLock::new(Config::default()))).await.unwrap()
+        .with_platform_provider(platform.clone());
+    let (steps, receiver) = mpsc::unbounded_channel();
+    system.register_plugin(Arc::new(ExportPlugin {
+        context: system.context().clone(),
+        steps: Arc::new(Mutex::new(receiver)),
+    })).await;
+    system.start_progress_notifications();
+    // Let the forwarding loop subscribe before the task starts
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let mut updates = system.context().tasks().subscribe();
+    system.execute_plugin_action("Export", &SearchResult::new("Export", "")).await.unwrap();
+    assert_eq!(next_update(&mut updates).await.summary(), "Export started");
+    assert_eq!(summaries(&system), ["Export started"]);
+
+    steps.send(()).unwrap();
+    assert_eq!(next_update(&mut updates).await.summary(), "Export 33% · reading files");
+    assert_eq!(summaries(&system), ["Export 33% · reading files"]);
+
+    steps.send(()).unwrap();
+    assert_eq!(next_update(&mut updates).await.summary(), "Export 67% · writing files");
+    assert_eq!(system.active_task_count(), 1);
+
+    steps.send(()).unwrap();
+    let failed = next_update(&mut updates).await;
+    assert_eq!(failed.state, TaskState::Failed(PluginError::ExecutionError("disk full".to_string()).to_string()));
+    assert!(summaries(&system).is_empty());
+    assert_eq!(system.active_task_count(), 0);
+
+    // The throttle holds the quick progress updates back but never the start or the failure
+    for _ in 0..100 {
+        if platform.notifications().len() >= 2 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    let notification = |message: String| Notification { title: "Export".to_string(), message, action: None };
+    assert_eq!(platform.notifications(), [
+        notification("Export started".to_string()),
+        notification(failed.summary()),
+    ]);
+}
@@ -0,0 +1,57 @@
+use falcommand_config::Action;
+use falcommand_plugin_testkit::{assert_execute_in_time, assert_search_in_time, HttpMethod, PluginHarness};
+use falcommand_plugins::TranslatorPlugin;
+use serde_json::json;
+
+const ENDPOINT: &str = "https://translate.example/translate";
+
+fn harness_with_endpoint() -> PluginHarness {
+    PluginHarness::new().with_plugin_settings("translator", json!({ "endpoint": ENDPOINT, "target_language": "ja" }))
+}
+
+#[tokio::test]
+async fn translates_through_the_configured_endpoint() {
+    let harness = harness_with_endpoint();
+    harness.http().expect_post(ENDPOINT, r#"{"translatedText":"おはよう"}"#);
+    let plugin = TranslatorPlugin::new(harness.context());
+
+    let results = assert_search_in_time(&plugin, "translate good morning").await;
+    assert_eq!(results[0].action, Action::CopyToClipboard("おはよう".to_string()));
+
+    let requests = harness.http().requests();
+    assert_eq!(requests[0].method, HttpMethod::Post);
+    let body: serde_json::Value = serde_json::from_str(requests[0].body.as_deref().unwrap()).unwrap();
+    assert_eq!(body, json!({ "q": "good morning", "source": "auto", "target": "ja" }));
+    harness.http().assert_all_used();
+}
+
+#[tokio::test]
+async fn execute_copies_the_translation() {
+    let harness = harness_with_endpoint();
+    harness.http().expect_post(ENDPOINT, r#"{"translatedText":"good morning"}"#);
+    let plugin = TranslatorPlugin::new(harness.context());
+
+    let results = assert_search_in_time(&plugin, "翻訳 おはよう").await;
+    assert_execute_in_time(&plugin, &results[0]).await;
+    assert_eq!(harness.platform().clipboard().as_deref(), Some("good morning"));
+}
+
+#[tokio::test]
+async fn endpoint_errors_fail_the_search() {
+    let harness = harness_with_endpoint();
+    harness.http().expect_post(ENDPOINT, "<html>Bad gateway</html>");
+    let plugin = TranslatorPlugin::new(harness.context());
+
+    let outcomes = harness.run_queries(&plugin, &["translate hello"]).await;
+    assert!(outcomes[0].error.as_deref().unwrap().contains("Unexpected translation response"));
+}
+
+#[tokio::test]
+async fn no_request_is_made_without_an_endpoint() {
+    let harness = PluginHarness::new();
+    let plugin = TranslatorPlugin::new(harness.context());
+
+    let outcomes = harness.run_queries(&plugin, &["translate hello", "hello"]).await;
+    assert!(outcomes[0].handled && !outcomes[1].handled);
+    assert!(harness.http().requests().is_empty());
+}
@@ -0,0 +1,382 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use async_trait::async_trait;
+
+use falcommand_config::{SearchResult, SyntaxHint, Action, Category};
+use falcommand_config::i18n::tr;
+use crate::plugins::{Plugin, PluginContext, PluginError};
+use crate::icons::PluginIcon;
+use crate::routing::Trigger;
+
+const HTTP_STATUS_DATA: &str = include_str!("../data/http_status.tsv");
+const COMMANDS_DATA: &str = include_str!("../data/commands.tsv");
+const MIME_TYPES_DATA: &str = include_str!("../data/mime_types.tsv");
+const ASCII_NAMES_DATA: &str = include_str!("../data/ascii_names.tsv");
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpStatus {
+    pub code: u16,
+    pub phrase: String,
+    pub description: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandSnippet {
+    pub tool: String,
+    pub subcommand: String,
+    pub command: String,
+    pub description: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MimeType {
+    pub extension: String,
+    pub mime: String,
+}
+
+// Tab-separated rows, skipping blank lines and `#` comments
+fn tsv_rows(data: &str) -> impl Iterator<Item = Vec<&str>> {
+    data.lines()
+        .map(|line| line.trim_end_matches('\r'))
+        .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+        .map(|line| line.split('\t').collect())
+}
+
+pub fn parse_http_statuses(data: &str) -> Vec<HttpStatus> {
+    tsv_rows(data)
+        .filter_map(|row| match row.as_slice() {
+            [code, phrase, description] => Some(HttpStatus {
+                code: code.parse().ok()?,
+                phrase: phrase.to_string(),
+                description: description.to_string(),
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+pub fn parse_commands(data: &str) -> Vec<CommandSnippet> {
+    tsv_rows(data)
+        .filter_map(|row| match row.as_slice() {
+            [tool, subcommand, command, description] => Some(CommandSnippet {
+                tool: tool.to_string(),
+                subcommand: subcommand.to_string(),
+                command: command.to_string(),
+                description: description.to_string(),
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+pub fn parse_mime_types(data: &str) -> Vec<MimeType> {
+    tsv_rows(data)
+        .filter_map(|row| match row.as_slice() {
+            [extension, mime] => Some(MimeType {
+                extension: extension.to_string(),
+                mime: mime.to_string(),
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+pub fn parse_ascii_names(data: &str) -> HashMap<u32, (String, String)> {
+    tsv_rows(data)
+        .filter_map(|row| match row.as_slice() {
+            [code, abbreviation, name] => Some((code.parse().ok()?, (abbreviation.to_string(), name.to_string()))),
+            _ => None,
+        })
+        .collect()
+}
+
+// "65", "0x41", "U+0041" or a single character
+pub fn parse_codepoint(text: &str) -> Option<u32> {
+    let text = text.trim();
+    let hex = text.strip_prefix("0x")
+        .or_else(|| text.strip_prefix("0X"))
+        .or_else(|| text.strip_prefix("U+"))
+        .or_else(|| text.strip_prefix("u+"));
+
+    if let Some(hex) = hex {
+        return u32::from_str_radix(hex, 16).ok();
+    }
+    if !text.is_empty() && text.chars().all(|c| c.is_ascii_digit()) {
+        return text.parse().ok();
+    }
+
+    let mut chars = text.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(c as u32),
+        _ => None,
+    }
+}
+
+// Offline reference tables: HTTP statuses, CLI one-liners, character codes and MIME types
+#[derive(Debug)]
+pub struct DevDocsPlugin {
+    context: PluginContext,
+    // Each table is parsed on first use so startup stays cheap
+    http_statuses: OnceLock<Vec<HttpStatus>>,
+    commands: OnceLock<Vec<CommandSnippet>>,
+    mime_types: OnceLock<Vec<MimeType>>,
+    ascii_names: OnceLock<HashMap<u32, (String, String)>>,
+}
+
+impl DevDocsPlugin {
+    const MAX_RESULTS: usize = 8;
+    const COMMAND_TOOLS: [&'static str; 3] = ["git", "docker", "kubectl"];
+    const KEYWORDS: [&'static str; 7] = ["http", "git", "docker", "kubectl", "ascii", "unicode", "mime"];
+
+    pub fn new(context: PluginContext) -> Self {
+        Self {
+            context,
+            http_statuses: OnceLock::new(),
+            commands: OnceLock::new(),
+            mime_types: OnceLock::new(),
+            ascii_names: OnceLock::new(),
+        }
+    }
+
+    fn split_query(query: &str) -> Option<(&str, &str)> {
+        let query = query.trim();
+        let (keyword, rest) = query.split_once(char::is_whitespace).unwrap_or((query, ""));
+        let keyword = Self::KEYWORDS.iter().find(|k| k.eq_ignore_ascii_case(keyword))?;
+        Some((keyword, rest.trim()))
+    }
+
+    fn result(title: impl Into<String>, description: impl Into<String>, copy: impl Into<String>, score: f64) -> SearchResult {
+        SearchResult::new(title, description)
+            .with_action(Action::CopyToClipboard(copy.into()))
+            .with_category(Category::Plugin("DevDocs".to_string()))
+            .with_score(score)
+    }
+
+    fn search_http(&self, argument: &str) -> Vec<SearchResult> {
+        let statuses = self.http_statuses.get_or_init(|| parse_http_statuses(HTTP_STATUS_DATA));
+        let argument_lower = argument.to_lowercase();
+
+        statuses.iter()
+            .filter(|status| {
+                let code = status.code.to_string();
+                argument.is_empty()
+                    || code.starts_with(argument)
+                    || status.phrase.to_lowercase().contains(&argument_lower)
+            })
+            .take(Self::MAX_RESULTS)
+            .map(|status| {
+                let score = if status.code.to_string() == argument { 1.0 } else { 0.8 };
+                Self::result(
+                    format!("{} {}", status.code, status.phrase),
+                    status.description.clone(),
+                    format!("{} {}", status.code, status.phrase),
+                    score,
+                )
+                .with_secondary_action(
+                    "Open on MDN",
+                    Action::OpenUrl(format!("https://developer.mozilla.org/en-US/docs/Web/HTTP/Status/{}", status.code)),
+                )
+            })
+            .collect()
+    }
+
+    fn command_url(snippet: &CommandSnippet) -> Option<String> {
+        match snippet.tool.as_str() {
+            "git" => Some(format!("https://git-scm.com/docs/git-{}", snippet.subcommand)),
+            "docker" => Some(format!("https://docs.docker.com/reference/cli/docker/{}/", snippet.subcommand)),
+            "kubectl" => Some("https://kubernetes.io/docs/reference/kubectl/".to_string()),
+            _ => None,
+        }
+    }
+
+    fn search_commands(&self, tool: &str, argument: &str) -> Vec<SearchResult> {
+        let commands = self.commands.get_or_init(|| parse_commands(COMMANDS_DATA));
+        let argument_lower = argument.to_lowercase();
+
+        commands.iter()
+            .filter(|snippet| snippet.tool == tool)
+            .filter(|snippet| {
+                argument.is_empty()
+                    || snippet.subcommand.starts_with(&argument_lower)
+                    || snippet.description.to_lowercase().contains(&argument_lower)
+            })
+            .take(Self::MAX_RESULTS)
+            .map(|snippet| {
+                let score = if snippet.subcommand == argument_lower { 0.95 } else { 0.8 };
+                let mut result = Self::result(snippet.command.clone(), snippet.description.clone(), snippet.command.clone(), score);
+                if let Some(url) = Self::command_url(snippet) {
+                    result = result.with_secondary_action("Open documentation", Action::OpenUrl(url));
+                }
+                result
+            })
+            .collect()
+    }
+
+    fn search_codepoint(&self, argument: &str) -> Vec<SearchResult> {
+        let Some(code) = parse_codepoint(argument) else {
+            return Vec::new();
+        };
+        let names = self.ascii_names.get_or_init(|| parse_ascii_names(ASCII_NAMES_DATA));
+
+        let (display, name) = match (names.get(&code), char::from_u32(code)) {
+            (Some((abbreviation, name)), _) => (abbreviation.clone(), name.clone()),
+            (None, Some(c)) => (c.to_string(), String::new()),
+            (None, None) => return Vec::new(),
+        };
+
+        let mut description = format!("dec {} · hex 0x{:X} · U+{:04X}", code, code, code);
+        if !name.is_empty() {
+            description = format!("{} · {}", name, description);
+        }
+
+        let copy = char::from_u32(code).map(|c| c.to_string()).unwrap_or_default();
+        vec![
+            Self::result(format!("{} = {}", argument, display), description, copy, 1.0)
+                .with_secondary_action("Copy decimal", Action::CopyToClipboard(code.to_string()))
+                .with_secondary_action("Copy hex", Action::CopyToClipboard(format!("0x{:X}", code)))
+        ]
+    }
+
+    fn search_mime(&self, argument: &str) -> Vec<SearchResult> {
+        let mime_types = self.mime_types.get_or_init(|| parse_mime_types(MIME_TYPES_DATA));
+        let argument = argument.trim_start_matches('.').to_lowercase();
+        if argument.is_empty() {
+            return Vec::new();
+        }
+
+        // "image/png" looks up extensions, anything else is treated as an extension
+        let reverse = argument.contains('/');
+        mime_types.iter()
+            .filter(|entry| {
+                if reverse {
+                    entry.mime.starts_with(&argument)
+                } else {
+                    entry.extension.starts_with(&argument)
+                }
+            })
+            .take(Self::MAX_RESULTS)
+            .map(|entry| {
+                let exact = entry.extension == argument || entry.mime == argument;
+                let score = if exact { 1.0 } else { 0.8 };
+                if reverse {
+                    Self::result(format!(".{}", entry.extension), entry.mime.clone(), entry.extension.clone(), score)
+                } else {
+                    Self::result(entry.mime.clone(), format!(".{}", entry.extension), entry.mime.clone(), score)
+                }
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl Plugin for DevDocsPlugin {
+    fn name(&self) -> &str {
+        "DevDocs"
+    }
+
+    fn version(&self) -> &str {
+        "1.0.0"
+    }
+
+    fn description(&self) -> &str {
+        "Offline developer references"
+    }
+
+    fn can_handle(&self, query: &str) -> bool {
+        Self::split_query(query).is_some_and(|(_, rest)| !rest.is_empty())
+    }
+
+    async fn search(&self, query: &str) -> std::result::Result<Vec<SearchResult>, PluginError> {
+        let Some((keyword, argument)) = Self::split_query(query) else {
+            return Ok(vec![]);
+        };
+
+        let results = match keyword {
+            "http" => self.search_http(argument),
+            "ascii" | "unicode" => self.search_codepoint(argument),
+            "mime" => self.search_mime(argument),
+            tool if Self::COMMAND_TOOLS.contains(&tool) => self.search_commands(tool, argument),
+            _ => Vec::new(),
+        };
+
+        Ok(results)
+    }
+
+    async fn execute(&self, result: &SearchResult) -> std::result::Result<(), PluginError> {
+        if let Action::CopyToClipboard(_) = result.action {
+            self.context.show_notification("DevDocs", "Copied to clipboard")?;
+        }
+        Ok(())
+    }
+
+    fn syntax_hints(&self) -> Vec<SyntaxHint> {
+        vec![
+            SyntaxHint::new("http <code>", tr("hint.devdocs_http", "Look up an HTTP status code"), "http 404"),
+            SyntaxHint::new("git|docker|kubectl <command>", tr("hint.devdocs_commands", "Common command one-liners"), "git rebase"),
+            SyntaxHint::new("ascii|unicode <code or char>", tr("hint.devdocs_codepoint", "Look up a character code"), "ascii 65"),
+            SyntaxHint::new("mime <extension>", tr("hint.devdocs_mime", "Look up a MIME type"), "mime pdf"),
+        ]
+    }
+
+    fn icon(&self) -> Option<PluginIcon> {
+        Some(PluginIcon::Glyph("book"))
+    }
+
+    fn triggers(&self) -> Vec<Trigger> {
+        Self::KEYWORDS.iter().map(|keyword| Trigger::keyword(*keyword)).collect()
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use tokio::sync::RwLock;
+    use falcommand_config::Config;
+
+    fn plugin() -> DevDocsPlugin {
+        DevDocsPlugin::new(PluginContext::new(Arc::new(RwLock::new(Config::default()))))
+    }
+
+    async fn titles(query: &str) -> Vec<String> {
+        plugin().search(query).await.unwrap().into_iter().map(|result| result.title).collect()
+    }
+
+    // A malformed row would be dropped silently, so every data row has to come out of its parser
+    #[test]
+    fn every_table_row_parses() {
+        assert_eq!(parse_http_statuses(HTTP_STATUS_DATA).len(), tsv_rows(HTTP_STATUS_DATA).count());
+        assert_eq!(parse_commands(COMMANDS_DATA).len(), tsv_rows(COMMANDS_DATA).count());
+        assert_eq!(parse_mime_types(MIME_TYPES_DATA).len(), tsv_rows(MIME_TYPES_DATA).count());
+        assert_eq!(parse_ascii_names(ASCII_NAMES_DATA).len(), tsv_rows(ASCII_NAMES_DATA).count());
+        assert!(parse_http_statuses("# code\tphrase\tdescription\n\nabc\tBad\tNot a code\r\n").is_empty());
+    }
+
+    #[test]
+    fn codepoints_in_every_notation() {
+        assert_eq!(parse_codepoint("65"), Some(65));
+        assert_eq!(parse_codepoint("0x41"), Some(65));
+        assert_eq!(parse_codepoint("U+00e9"), Some(0xE9));
+        assert_eq!(parse_codepoint("é"), Some(0xE9));
+        assert_eq!(parse_codepoint("ab"), None);
+    }
+
+    #[tokio::test]
+    async fn representative_queries_per_table() {
+        assert_eq!(titles("http 404").await, ["404 Not Found"]);
+        assert!(titles("http teapot").await.contains(&"418 I'm a teapot".to_string()));
+        assert_eq!(titles("git rebase").await, ["git rebase -i HEAD~3", "git rebase --onto main feature~2 feature"]);
+        assert_eq!(titles("ascii 0").await, ["0 = NUL"]);
+        assert_eq!(titles("unicode U+00E9").await, ["U+00E9 = é"]);
+        assert_eq!(titles("mime .PDF").await, ["application/pdf"]);
+        assert_eq!(titles("mime image/png").await, [".png"]);
+        assert!(!plugin().can_handle("git") && !plugin().can_handle("http "));
+    }
+
+    #[tokio::test]
+    async fn exact_matches_score_highest() {
+        let results = plugin().search("mime json").await.unwrap();
+        assert_eq!(results[0].title, "application/json");
+        assert_eq!(results[0].score, 1.0);
+        assert!(results[1..].iter().all(|result| result.score < 1.0));
+    }
+}
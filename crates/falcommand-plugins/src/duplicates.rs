@@ -0,0 +1,220 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use async_trait::async_trait;
+use log::{info, warn};
+
+use falcommand_config::{SearchResult, SyntaxHint, Action, Category};
+use falcommand_config::i18n::{current_language, tr};
+use falcommand_config::launch::display_path;
+use falcommand_core::{DuplicateFinder, DuplicateGroup, DuplicateScan, IndexManager, expand_home, format_size, looks_like_path};
+use crate::plugins::{Plugin, PluginContext, PluginError};
+use crate::icons::PluginIcon;
+use crate::routing::Trigger;
+
+// "duplicates ~/Downloads" or "duplicates invoice": indexed files with the same contents. Opt-in,
+// since comparing means reading files
+pub struct DuplicatesPlugin {
+    context: PluginContext,
+    index: Option<Arc<IndexManager>>,
+    // Keeps its hash cache for as long as the plugin is loaded
+    finder: Option<DuplicateFinder>,
+}
+
+impl std::fmt::Debug for DuplicatesPlugin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DuplicatesPlugin")
+            .field("index", &self.index.is_some())
+            .field("finder", &self.finder.is_some())
+            .finish()
+    }
+}
+
+// What the argument narrows the candidates to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DuplicateScope {
+    Folder(PathBuf),
+    Name(String),
+}
+
+impl DuplicateScope {
+    pub fn parse(query: &str) -> Option<Self> {
+        let (keyword, argument) = query.trim().split_once(char::is_whitespace)?;
+        let argument = argument.trim();
+        if keyword != DuplicatesPlugin::KEYWORD || argument.is_empty() {
+            return None;
+        }
+        Some(if looks_like_path(argument) {
+            DuplicateScope::Folder(PathBuf::from(expand_home(argument)))
+        } else {
+            DuplicateScope::Name(argument.to_string())
+        })
+    }
+}
+
+impl DuplicatesPlugin {
+    const KEYWORD: &'static str = "duplicates";
+    // Kept under PluginSystem::PLUGIN_TIMEOUT so a scan returns what it found instead of timing out
+    pub const SCAN_BUDGET: Duration = Duration::from_millis(1500);
+    pub const MAX_GROUPS: usize = 20;
+
+    pub fn new(context: PluginContext) -> Self {
+        let index = context.index_manager().cloned();
+        let finder = context.extractor().cloned().map(DuplicateFinder::new);
+        Self { context, index, finder }
+    }
+
+    fn group_result(group: &DuplicateGroup) -> SearchResult {
+        let language = current_language();
+        let newest = group.newest();
+        let copies = group.files.len();
+        let description = format!(
+            "{} duplicates of {} · {} wasted",
+            copies - 1,
+            format_size(group.size, language),
+            format_size(group.wasted_bytes(), language),
+        );
+
+        let mut result = SearchResult::new(format!("{} copies of {}", copies, newest.name), description)
+            .with_action(Action::OpenFile(newest.path.clone()))
+            .with_category(Category::Plugin("Duplicates".to_string()))
+            .with_path(newest.path.clone())
+            .with_score(0.9);
+        for file in &group.files {
+            if let Some(parent) = file.path.parent() {
+                result = result.with_secondary_action(
+                    format!("Open folder of {}", display_path(&file.path)),
+                    Action::OpenFile(parent.to_path_buf()),
+                );
+            }
+        }
+        result.with_destructive_action("Move all but the newest to trash", Action::PluginAction {
+            plugin_id: "Duplicates".to_string(),
+            action_data: serde_json::json!({
+                "keep": newest.path,
+                "trash": group.files[1..].iter()
+                    .map(|file| serde_json::json!({ "path": file.path, "size": file.size }))
+                    .collect::<Vec<_>>(),
+            }),
+        })
+    }
+
+    fn summary_result(query: &str, scan: &DuplicateScan) -> Option<SearchResult> {
+        let category = Category::Plugin("Duplicates".to_string());
+        if !scan.is_complete() {
+            // Hashes done so far are cached, so searching again continues rather than restarts
+            let title = format!("Still comparing: {} of {} files left", scan.unhashed, scan.candidates);
+            return Some(SearchResult::new(title, "Select to continue comparing")
+                .with_action(Action::SetQuery(query.to_string()))
+                .with_category(category)
+                .with_score(0.95));
+        }
+        scan.groups.is_empty().then(|| {
+            SearchResult::new("No duplicates found", format!("Compared {} files of matching size", scan.candidates))
+                .with_category(category)
+                .with_score(0.5)
+        })
+    }
+
+    async fn trash_copy(&self, path: &Path, size: u64) -> std::result::Result<bool, PluginError> {
+        // A copy that changed since it was compared may no longer be a duplicate
+        match tokio::fs::metadata(path).await {
+            Ok(metadata) if metadata.len() == size => {}
+            _ => {
+                warn!("Not trashing {}: it changed since it was compared", path.display());
+                return Ok(false);
+            }
+        }
+        let platform = self.context.platform().cloned()
+            .ok_or_else(|| PluginError::ExecutionError("Trash is not available".to_string()))?;
+        let target = path.to_path_buf();
+        tokio::task::spawn_blocking(move || platform.trash_file(&target))
+            .await
+            .map_err(|e| PluginError::ExecutionError(e.to_string()))?
+            .map_err(|e| PluginError::ExecutionError(e.to_string()))?;
+        if let Some(ref index) = self.index {
+            index.remove_file(path).await;
+        }
+        Ok(true)
+    }
+}
+
+#[async_trait]
+impl Plugin for DuplicatesPlugin {
+    fn name(&self) -> &str {
+        "Duplicates"
+    }
+
+    fn version(&self) -> &str {
+        "1.0.0"
+    }
+
+    fn description(&self) -> &str {
+        "Find indexed files with identical contents"
+    }
+
+    fn can_handle(&self, query: &str) -> bool {
+        DuplicateScope::parse(query).is_some()
+    }
+
+    async fn search(&self, query: &str) -> std::result::Result<Vec<SearchResult>, PluginError> {
+        let Some(scope) = DuplicateScope::parse(query) else {
+            return Ok(vec![]);
+        };
+        let (Some(index), Some(finder)) = (self.index.as_ref(), self.finder.as_ref()) else {
+            return Ok(vec![]);
+        };
+
+        let files = match scope {
+            DuplicateScope::Folder(ref folder) => index.files_in("", Some(folder)).await,
+            DuplicateScope::Name(ref name) => index.files_in(name, None).await,
+        };
+        let scan = finder.find(files, Self::SCAN_BUDGET).await;
+
+        let mut results: Vec<SearchResult> = Self::summary_result(query, &scan).into_iter().collect();
+        results.extend(scan.groups.iter().take(Self::MAX_GROUPS).map(Self::group_result));
+        Ok(results)
+    }
+
+    async fn execute(&self, result: &SearchResult) -> std::result::Result<(), PluginError> {
+        let Action::PluginAction { ref action_data, .. } = result.action else {
+            return Ok(());
+        };
+        let Some(copies) = action_data.get("trash").and_then(|v| v.as_array()) else {
+            return Ok(());
+        };
+        // Never trash the copies unless the one being kept is still there
+        let keep = action_data.get("keep").and_then(|v| v.as_str()).unwrap_or_default();
+        if keep.is_empty() || !tokio::fs::try_exists(keep).await.unwrap_or(false) {
+            return Err(PluginError::ExecutionError("The newest copy is gone; nothing was moved to trash".to_string()));
+        }
+
+        let mut trashed = 0;
+        for copy in copies {
+            let (Some(path), Some(size)) = (copy.get("path").and_then(|v| v.as_str()), copy.get("size").and_then(|v| v.as_u64())) else {
+                continue;
+            };
+            if self.trash_copy(Path::new(path), size).await? {
+                trashed += 1;
+            }
+        }
+        info!("Moved {} duplicates of {} to trash", trashed, keep);
+        self.context.show_notification("Duplicates", &format!("Moved {} duplicates to trash", trashed))
+    }
+
+    fn syntax_hints(&self) -> Vec<SyntaxHint> {
+        vec![SyntaxHint::new(
+            "duplicates <folder or name>",
+            tr("hint.duplicates", "Find files with identical contents"),
+            "duplicates ~/Downloads",
+        )]
+    }
+
+    fn icon(&self) -> Option<PluginIcon> {
+        Some(PluginIcon::Glyph("duplicate"))
+    }
+
+    fn triggers(&self) -> Vec<Trigger> {
+        vec![Trigger::keyword(Self::KEYWORD)]
+    }
+}
@@ -0,0 +1,216 @@
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+use log::warn;
+
+use falcommand_config::SearchResult;
+use crate::plugins::PluginError;
+
+// How a plugin brands its results
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PluginIcon {
+    // A PNG image, written to the icon cache before results point at it
+    Png(Vec<u8>),
+    // One of the UI's own glyphs, by name ("calculator", "timer"); nothing is cached
+    Glyph(&'static str),
+}
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+// Limits for icon files external plugins ship
+pub const MAX_ICON_BYTES: u64 = 256 * 1024;
+pub const MAX_ICON_DIMENSION: u32 = 256;
+
+// Width and height from the IHDR chunk, which always comes first
+pub fn png_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    if data.len() < 24 || data[..8] != PNG_SIGNATURE || &data[12..16] != b"IHDR" {
+        return None;
+    }
+    let width = u32::from_be_bytes(data[16..20].try_into().ok()?);
+    let height = u32::from_be_bytes(data[20..24].try_into().ok()?);
+    Some((width, height))
+}
+
+// The icon a plugin manifest declares, relative to the plugin's directory. The path has to stay
+// inside that directory, and the file has to be a PNG of at most MAX_ICON_BYTES and
+// MAX_ICON_DIMENSION pixels a side
+pub fn load_manifest_icon(plugin_dir: &Path, relative: &Path) -> std::result::Result<PluginIcon, PluginError> {
+    let escapes = relative.components().any(|component| !matches!(component, Component::Normal(_) | Component::CurDir));
+    if escapes {
+        return Err(PluginError::ConfigurationError(format!(
+            "Icon {} must be a path inside the plugin directory", relative.display()
+        )));
+    }
+
+    let path = plugin_dir.join(relative);
+    let size = std::fs::metadata(&path)?.len();
+    if size > MAX_ICON_BYTES {
+        return Err(PluginError::ConfigurationError(format!(
+            "Icon {} is {} bytes, more than the {} allowed", relative.display(), size, MAX_ICON_BYTES
+        )));
+    }
+
+    let data = std::fs::read(&path)?;
+    let (width, height) = png_dimensions(&data).ok_or_else(|| {
+        PluginError::ConfigurationError(format!("Icon {} is not a PNG image", relative.display()))
+    })?;
+    if width == 0 || height == 0 || width > MAX_ICON_DIMENSION || height > MAX_ICON_DIMENSION {
+        return Err(PluginError::ConfigurationError(format!(
+            "Icon {} is {}x{}, icons can be at most {}x{}",
+            relative.display(), width, height, MAX_ICON_DIMENSION, MAX_ICON_DIMENSION
+        )));
+    }
+    Ok(PluginIcon::Png(data))
+}
+
+// Plugin icons written out as files, one per plugin id, so results can point at them
+#[derive(Debug)]
+pub struct PluginIconCache {
+    dir: PathBuf,
+    // Plugin ids already resolved this run; None when the plugin's icon has no file
+    resolved: Mutex<HashMap<String, Option<PathBuf>>>,
+}
+
+impl PluginIconCache {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir, resolved: Mutex::new(HashMap::new()) }
+    }
+
+    pub fn path_for(&self, plugin_id: &str) -> PathBuf {
+        self.dir.join(format!("{}.png", plugin_id))
+    }
+
+    // The file results of `plugin_id` use for `icon`. PNG bytes are written once per run, and
+    // again only when they differ from what the file holds
+    pub fn resolve(&self, plugin_id: &str, icon: &PluginIcon) -> Option<PathBuf> {
+        if let Some(path) = self.resolved.lock().unwrap().get(plugin_id) {
+            return path.clone();
+        }
+
+        let path = match icon {
+            PluginIcon::Png(data) => {
+                let path = self.path_for(plugin_id);
+                match self.materialize(&path, data) {
+                    Ok(()) => Some(path),
+                    Err(e) => {
+                        warn!("Failed to cache the icon of plugin '{}': {}", plugin_id, e);
+                        None
+                    }
+                }
+            }
+            PluginIcon::Glyph(_) => None,
+        };
+        self.resolved.lock().unwrap().insert(plugin_id.to_string(), path.clone());
+        path
+    }
+
+    fn materialize(&self, path: &Path, data: &[u8]) -> std::io::Result<()> {
+        if std::fs::read(path).is_ok_and(|existing| existing == data) {
+            return Ok(());
+        }
+        std::fs::create_dir_all(&self.dir)?;
+        std::fs::write(path, data)
+    }
+}
+
+// Gives results without an icon of their own the plugin's icon
+pub fn attach_plugin_icon(results: &mut [SearchResult], icon: &Path) {
+    for result in results.iter_mut().filter(|result| result.icon.is_none()) {
+        result.icon = Some(icon.to_path_buf());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The signature and IHDR chunk, which is all the checks read
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut data = PNG_SIGNATURE.to_vec();
+        data.extend_from_slice(&13u32.to_be_bytes());
+        data.extend_from_slice(b"IHDR");
+        data.extend_from_slice(&width.to_be_bytes());
+        data.extend_from_slice(&height.to_be_bytes());
+        data.extend_from_slice(&[8, 6, 0, 0, 0]);
+        data
+    }
+
+    #[test]
+    fn png_dimensions_come_from_the_header() {
+        assert_eq!(png_dimensions(&png(32, 48)), Some((32, 48)));
+        assert_eq!(png_dimensions(b"GIF89a not a png at all"), None);
+        assert_eq!(png_dimensions(&png(32, 48)[..20]), None);
+    }
+
+    #[test]
+    fn manifest_icons_load_from_the_plugin_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("assets")).unwrap();
+        std::fs::write(dir.path().join("assets").join("icon.png"), png(64, 64)).unwrap();
+
+        let icon = load_manifest_icon(dir.path(), Path::new("./assets/icon.png")).unwrap();
+        assert_eq!(icon, PluginIcon::Png(png(64, 64)));
+        assert!(load_manifest_icon(dir.path(), Path::new("assets/missing.png")).is_err());
+    }
+
+    #[test]
+    fn manifest_icons_are_validated() {
+        let dir = tempfile::tempdir().unwrap();
+        let error = |relative: &str| load_manifest_icon(dir.path(), Path::new(relative)).unwrap_err().to_string();
+
+        std::fs::write(dir.path().join("huge.png"), png(512, 64)).unwrap();
+        assert!(error("huge.png").contains("is 512x64, icons can be at most 256x256"), "{}", error("huge.png"));
+        std::fs::write(dir.path().join("empty.png"), png(0, 16)).unwrap();
+        assert!(error("empty.png").contains("is 0x16"));
+        std::fs::write(dir.path().join("icon.svg"), "<svg/>").unwrap();
+        assert!(error("icon.svg").contains("is not a PNG image"));
+
+        let mut heavy = png(16, 16);
+        heavy.resize(MAX_ICON_BYTES as usize + 1, 0);
+        std::fs::write(dir.path().join("heavy.png"), heavy).unwrap();
+        assert!(error("heavy.png").contains("more than the 262144 allowed"));
+
+        // Nothing outside the plugin's own directory
+        assert!(error("../other/icon.png").contains("must be a path inside the plugin directory"));
+        let absolute = dir.path().join("huge.png");
+        assert!(error(&absolute.to_string_lossy()).contains("must be a path inside the plugin directory"));
+    }
+
+    // One file per plugin id, written once per run and rewritten only when the bytes changed
+    #[test]
+    fn cache_files_are_keyed_by_plugin() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = PluginIconCache::new(dir.path().join("icons"));
+
+        let weather = cache.resolve("weather", &PluginIcon::Png(png(16, 16))).unwrap();
+        let emoji = cache.resolve("emoji", &PluginIcon::Png(png(32, 32))).unwrap();
+        assert_eq!(weather, dir.path().join("icons").join("weather.png"));
+        assert_ne!(weather, emoji);
+        assert_eq!(std::fs::read(&weather).unwrap(), png(16, 16));
+        assert_eq!(std::fs::read(&emoji).unwrap(), png(32, 32));
+
+        // Resolved once per run
+        std::fs::remove_file(&weather).unwrap();
+        assert_eq!(cache.resolve("weather", &PluginIcon::Png(png(16, 16))), Some(weather.clone()));
+        assert!(!weather.exists());
+
+        let restarted = PluginIconCache::new(dir.path().join("icons"));
+        restarted.resolve("emoji", &PluginIcon::Png(png(48, 48))).unwrap();
+        assert_eq!(std::fs::read(&emoji).unwrap(), png(48, 48));
+
+        // Glyphs are the UI's own, so nothing is written
+        assert_eq!(cache.resolve("calculator", &PluginIcon::Glyph("calculator")), None);
+        assert!(!cache.path_for("calculator").exists());
+    }
+
+    #[test]
+    fn results_keep_an_icon_of_their_own() {
+        let own = PathBuf::from("/icons/sunny.png");
+        let mut results = vec![SearchResult::new("Weather", "cloudy"), SearchResult::new("Weather", "sunny")];
+        results[1].icon = Some(own.clone());
+
+        attach_plugin_icon(&mut results, Path::new("/cache/weather.png"));
+        assert_eq!(results[0].icon, Some(PathBuf::from("/cache/weather.png")));
+        assert_eq!(results[1].icon, Some(own));
+    }
+}
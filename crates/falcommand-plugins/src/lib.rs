@@ -1,3 +1,21 @@
 pub mod plugins;
+pub mod devdocs;
+pub mod system;
+pub mod network;
+pub mod tasks;
+pub mod notes;
+pub mod icons;
+pub mod routing;
+pub mod settings_form;
+pub mod duplicates;
 
-pub use plugins::*;
\ No newline at end of file
+pub use plugins::*;
+pub use devdocs::*;
+pub use system::*;
+pub use network::*;
+pub use tasks::*;
+pub use notes::*;
+pub use icons::*;
+pub use routing::*;
+pub use settings_form::*;
+pub use duplicates::*;
\ No newline at end of file
@@ -0,0 +1,372 @@
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use async_trait::async_trait;
+use log::{info, warn};
+
+use falcommand_config::{SearchResult, SyntaxHint, Action, Category};
+use falcommand_config::i18n::tr;
+use falcommand_platform::{NetworkInfo, PingStats, PlatformProvider};
+use crate::plugins::{Plugin, PluginContext, PluginError};
+use crate::icons::PluginIcon;
+use crate::routing::Trigger;
+
+// Values expire individually so failed lookups can be retried sooner than successful ones
+#[derive(Debug)]
+pub struct TtlCache<V> {
+    entries: HashMap<String, (V, Instant)>,
+}
+
+impl<V: Clone> TtlCache<V> {
+    pub fn new() -> Self {
+        Self { entries: HashMap::new() }
+    }
+
+    pub fn get(&self, key: &str, now: Instant) -> Option<V> {
+        self.entries.get(key)
+            .filter(|(_, expires)| now < *expires)
+            .map(|(value, _)| value.clone())
+    }
+
+    pub fn insert(&mut self, key: impl Into<String>, value: V, ttl: Duration, now: Instant) {
+        self.entries.retain(|_, (_, expires)| now < *expires);
+        self.entries.insert(key.into(), (value, now + ttl));
+    }
+}
+
+impl<V: Clone> Default for TtlCache<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NetworkQuery {
+    Ip,
+    Wifi,
+    Ping(String),
+}
+
+impl NetworkQuery {
+    pub fn parse(query: &str) -> Option<Self> {
+        let query = query.trim();
+        if query.eq_ignore_ascii_case("ip") {
+            return Some(NetworkQuery::Ip);
+        }
+        if query.eq_ignore_ascii_case("wifi") {
+            return Some(NetworkQuery::Wifi);
+        }
+
+        let (keyword, host) = query.split_once(char::is_whitespace)?;
+        let host = host.trim();
+        (keyword.eq_ignore_ascii_case("ping") && falcommand_platform::is_valid_host(host))
+            .then(|| NetworkQuery::Ping(host.to_lowercase()))
+    }
+}
+
+pub fn format_ping_stats(stats: &PingStats) -> String {
+    format!("min {:.1} / avg {:.1} / max {:.1} ms", stats.min_ms, stats.avg_ms, stats.max_ms)
+}
+
+pub fn format_wifi(info: &NetworkInfo) -> Option<(String, String)> {
+    let ssid = info.ssid.as_ref()?;
+    let description = match info.signal {
+        Some(signal) => format!("Signal {}%", signal),
+        None => "Connected".to_string(),
+    };
+    Some((format!("Wi-Fi {}", ssid), description))
+}
+
+// Local IP, public IP, Wi-Fi and ping answers
+#[derive(Debug)]
+pub struct NetworkPlugin {
+    context: PluginContext,
+    // Successful lookups keep their value, failures are cached as None
+    public_ip: Arc<Mutex<TtlCache<Option<String>>>>,
+    pings: Arc<Mutex<TtlCache<Option<PingStats>>>>,
+    in_flight: Arc<Mutex<HashSet<String>>>,
+}
+
+impl NetworkPlugin {
+    const PUBLIC_IP_URL: &'static str = "https://api.ipify.org";
+    const PUBLIC_IP_KEY: &'static str = "public_ip";
+    pub const PUBLIC_IP_TTL: Duration = Duration::from_secs(600);
+    pub const PING_TTL: Duration = Duration::from_secs(30);
+    const FAILURE_TTL: Duration = Duration::from_secs(30);
+
+    pub fn new(context: PluginContext) -> Self {
+        Self {
+            context,
+            public_ip: Arc::new(Mutex::new(TtlCache::new())),
+            pings: Arc::new(Mutex::new(TtlCache::new())),
+            in_flight: Arc::new(Mutex::new(HashSet::new())),
+        }
+    }
+
+    fn category() -> Category {
+        Category::Plugin("Network".to_string())
+    }
+
+    // Lookups keep the same action while pending and resolved so live refresh can swap them in place
+    fn lookup_action(key: &str) -> Action {
+        Action::PluginAction {
+            plugin_id: "Network".to_string(),
+            action_data: serde_json::json!({ "copy": key }),
+        }
+    }
+
+    fn copy_result(title: String, description: impl Into<String>, value: String) -> SearchResult {
+        SearchResult::new(title, description)
+            .with_action(Action::CopyToClipboard(value))
+            .with_category(Self::category())
+            .with_score(1.0)
+    }
+
+    fn public_ip_result(&self) -> SearchResult {
+        let cached = self.public_ip.lock().unwrap().get(Self::PUBLIC_IP_KEY, Instant::now());
+        let (title, description) = match cached {
+            Some(Some(ip)) => (format!("Public IP {}", ip), "Select to copy".to_string()),
+            Some(None) => ("Public IP".to_string(), "Unavailable".to_string()),
+            None if !self.context.is_online() => ("Public IP".to_string(), "Offline".to_string()),
+            None => ("Public IP".to_string(), "Looking up…".to_string()),
+        };
+
+        SearchResult::new(title, description)
+            .with_action(Self::lookup_action(Self::PUBLIC_IP_KEY))
+            .with_category(Self::category())
+            .with_score(0.95)
+    }
+
+    fn ping_key(host: &str) -> String {
+        format!("ping:{}", host)
+    }
+
+    fn ping_result(&self, host: &str) -> SearchResult {
+        let cached = self.pings.lock().unwrap().get(host, Instant::now());
+        let description = match cached {
+            Some(Some(stats)) => format_ping_stats(&stats),
+            Some(None) => "No reply".to_string(),
+            None => "Pinging…".to_string(),
+        };
+
+        SearchResult::new(format!("Ping {}", host), description)
+            .with_action(Self::lookup_action(&Self::ping_key(host)))
+            .with_category(Self::category())
+            .with_score(1.0)
+    }
+
+    // Single-flight per key: a lookup already running is never started twice
+    fn begin(&self, key: &str) -> bool {
+        self.in_flight.lock().unwrap().insert(key.to_string())
+    }
+
+    fn start_public_ip_lookup(&self) {
+        if !self.context.is_online() || !self.begin(Self::PUBLIC_IP_KEY) {
+            return;
+        }
+
+        let context = self.context.clone();
+        let cache = self.public_ip.clone();
+        let in_flight = self.in_flight.clone();
+        tokio::spawn(async move {
+            let ip = match context.http_get(Self::PUBLIC_IP_URL).await {
+                Ok(body) => body.trim().parse::<IpAddr>().ok().map(|ip| ip.to_string()),
+                Err(e) => {
+                    warn!("Public IP lookup failed: {}", e);
+                    None
+                }
+            };
+
+            let ttl = if ip.is_some() { Self::PUBLIC_IP_TTL } else { Self::FAILURE_TTL };
+            cache.lock().unwrap().insert(Self::PUBLIC_IP_KEY, ip, ttl, Instant::now());
+            in_flight.lock().unwrap().remove(Self::PUBLIC_IP_KEY);
+        });
+    }
+
+    fn start_ping(&self, host: &str) {
+        let key = Self::ping_key(host);
+        if !self.begin(&key) {
+            return;
+        }
+
+        info!("Pinging {}", host);
+        let host = host.to_string();
+        let cache = self.pings.clone();
+        let in_flight = self.in_flight.clone();
+        tokio::spawn(async move {
+            let target = host.clone();
+            let stats = tokio::task::spawn_blocking(move || falcommand_platform::ping(&target))
+                .await
+                .unwrap_or_default();
+
+            cache.lock().unwrap().insert(host, stats, Self::PING_TTL, Instant::now());
+            in_flight.lock().unwrap().remove(&key);
+        });
+    }
+
+    async fn network_info(&self) -> Option<NetworkInfo> {
+        let platform: Arc<dyn PlatformProvider> = self.context.platform()?.clone();
+        tokio::task::spawn_blocking(move || platform.get_network_info())
+            .await
+            .ok()?
+            .map_err(|e| warn!("Failed to read network info: {}", e))
+            .ok()
+    }
+
+    fn copy_value(&self, key: &str) -> Option<String> {
+        let now = Instant::now();
+        if key == Self::PUBLIC_IP_KEY {
+            return self.public_ip.lock().unwrap().get(key, now).flatten();
+        }
+        let host = key.strip_prefix("ping:")?;
+        self.pings.lock().unwrap().get(host, now).flatten().map(|stats| format_ping_stats(&stats))
+    }
+}
+
+#[async_trait]
+impl Plugin for NetworkPlugin {
+    fn name(&self) -> &str {
+        "Network"
+    }
+
+    fn version(&self) -> &str {
+        "1.0.0"
+    }
+
+    fn description(&self) -> &str {
+        "IP addresses, Wi-Fi and ping"
+    }
+
+    fn can_handle(&self, query: &str) -> bool {
+        NetworkQuery::parse(query).is_some()
+    }
+
+    // Online lookups are only started from refresh, which runs once a result has stayed on
+    // screen for a tick, so typing through "ip" or a hostname never fires a request per keystroke
+    async fn search(&self, query: &str) -> std::result::Result<Vec<SearchResult>, PluginError> {
+        let Some(query) = NetworkQuery::parse(query) else {
+            return Ok(vec![]);
+        };
+
+        let results = match query {
+            NetworkQuery::Ip => {
+                let mut results = Vec::new();
+                if let Some(ip) = self.network_info().await.and_then(|info| info.local_ip) {
+                    results.push(Self::copy_result(format!("Local IP {}", ip), "Select to copy", ip.to_string()));
+                }
+                results.push(self.public_ip_result());
+                results
+            }
+            NetworkQuery::Wifi => self.network_info().await
+                .as_ref()
+                .and_then(format_wifi)
+                .map(|(title, description)| {
+                    let ssid = title.trim_start_matches("Wi-Fi ").to_string();
+                    vec![Self::copy_result(title, description, ssid)]
+                })
+                .unwrap_or_default(),
+            NetworkQuery::Ping(host) => vec![self.ping_result(&host)],
+        };
+
+        Ok(results)
+    }
+
+    async fn execute(&self, result: &SearchResult) -> std::result::Result<(), PluginError> {
+        let Action::PluginAction { ref action_data, .. } = result.action else {
+            return Ok(());
+        };
+        let Some(key) = action_data.get("copy").and_then(|v| v.as_str()) else {
+            return Ok(());
+        };
+        let Some(value) = self.copy_value(key) else {
+            return Err(PluginError::ExecutionError("Nothing to copy yet".to_string()));
+        };
+
+        self.context.copy_to_clipboard(&value)?;
+        self.context.show_notification("Network", "Copied to clipboard")?;
+        Ok(())
+    }
+
+    fn syntax_hints(&self) -> Vec<SyntaxHint> {
+        vec![
+            SyntaxHint::new("ip", tr("hint.network_ip", "Show local and public IP addresses"), "ip"),
+            SyntaxHint::new("wifi", tr("hint.network_wifi", "Show the connected Wi-Fi network"), "wifi"),
+            SyntaxHint::new("ping <host>", tr("hint.network_ping", "Ping a host"), "ping example.com"),
+        ]
+    }
+
+    fn icon(&self) -> Option<PluginIcon> {
+        Some(PluginIcon::Glyph("network"))
+    }
+
+    fn triggers(&self) -> Vec<Trigger> {
+        ["ip", "wifi", "ping"].into_iter().map(Trigger::keyword).collect()
+    }
+
+    fn refresh_interval(&self) -> Option<Duration> {
+        Some(Duration::from_secs(1))
+    }
+
+    async fn refresh(&self, result: &SearchResult) -> std::result::Result<Option<SearchResult>, PluginError> {
+        let Action::PluginAction { ref action_data, .. } = result.action else {
+            return Ok(None);
+        };
+        let Some(key) = action_data.get("copy").and_then(|v| v.as_str()) else {
+            return Ok(None);
+        };
+
+        if key == Self::PUBLIC_IP_KEY {
+            if self.public_ip.lock().unwrap().get(key, Instant::now()).is_none() {
+                self.start_public_ip_lookup();
+            }
+            return Ok(Some(self.public_ip_result()));
+        }
+
+        let Some(host) = key.strip_prefix("ping:") else {
+            return Ok(None);
+        };
+        if self.pings.lock().unwrap().get(host, Instant::now()).is_none() {
+            self.start_ping(host);
+        }
+        Ok(Some(self.ping_result(host)))
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cached_values_expire_after_their_ttl() {
+        let now = Instant::now();
+        let mut cache = TtlCache::new();
+        cache.insert("public_ip", Some("203.0.113.7".to_string()), Duration::from_secs(600), now);
+        cache.insert("ping:example.com", None, Duration::from_secs(30), now);
+
+        let later = now + Duration::from_secs(31);
+        assert_eq!(cache.get("public_ip", later), Some(Some("203.0.113.7".to_string())));
+        assert_eq!(cache.get("ping:example.com", later), None);
+        assert_eq!(cache.get("public_ip", now + Duration::from_secs(600)), None);
+    }
+
+    #[test]
+    fn queries_are_recognized() {
+        assert_eq!(NetworkQuery::parse(" IP "), Some(NetworkQuery::Ip));
+        assert_eq!(NetworkQuery::parse("wifi"), Some(NetworkQuery::Wifi));
+        assert_eq!(NetworkQuery::parse("ping Example.COM"), Some(NetworkQuery::Ping("example.com".to_string())));
+        assert_eq!(NetworkQuery::parse("ping -f host"), None);
+        assert_eq!(NetworkQuery::parse("ipconfig"), None);
+    }
+
+    #[test]
+    fn results_are_formatted() {
+        let stats = PingStats { min_ms: 10.04, avg_ms: 11.25, max_ms: 12.3 };
+        assert_eq!(format_ping_stats(&stats), "min 10.0 / avg 11.2 / max 12.3 ms");
+
+        let info = NetworkInfo { ssid: Some("HomeNetwork".to_string()), signal: Some(72), ..NetworkInfo::default() };
+        assert_eq!(format_wifi(&info), Some(("Wi-Fi HomeNetwork".to_string(), "Signal 72%".to_string())));
+        let info = NetworkInfo { ssid: Some("HomeNetwork".to_string()), ..NetworkInfo::default() };
+        assert_eq!(format_wifi(&info).unwrap().1, "Connected");
+        assert_eq!(format_wifi(&NetworkInfo::default()), None);
+    }
+}
@@ -0,0 +1,413 @@
+use std::path::{Path, PathBuf};
+use async_trait::async_trait;
+use chrono::{DateTime, Local, NaiveDate};
+use serde::Deserialize;
+use log::info;
+
+use falcommand_config::{SearchResult, SyntaxHint, Action, Category};
+use falcommand_config::i18n::tr;
+use crate::plugins::{Plugin, PluginContext, PluginError};
+use crate::icons::PluginIcon;
+use crate::routing::Trigger;
+
+// plugin_settings.notes
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct NotesSettings {
+    // Defaults to notes.md in the data directory
+    pub file: Option<PathBuf>,
+    // One file per day, notes-YYYY-MM-DD.md next to `file`
+    pub daily: bool,
+}
+
+impl NotesSettings {
+    pub fn target_file(&self, data_dir: &Path) -> PathBuf {
+        self.file.clone().unwrap_or_else(|| data_dir.join(NotesPlugin::DEFAULT_FILE))
+    }
+
+    // The file a note taken on `date` goes to
+    pub fn file_for(&self, data_dir: &Path, date: NaiveDate) -> PathBuf {
+        let target = self.target_file(data_dir);
+        if !self.daily {
+            return target;
+        }
+        let dir = target.parent().map(Path::to_path_buf).unwrap_or_default();
+        dir.join(daily_file_name(date))
+    }
+}
+
+pub fn daily_file_name(date: NaiveDate) -> String {
+    format!("notes-{}.md", date.format("%Y-%m-%d"))
+}
+
+fn is_daily_file_name(name: &str) -> bool {
+    name.strip_prefix("notes-")
+        .and_then(|rest| rest.strip_suffix(".md"))
+        .is_some_and(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").is_ok())
+}
+
+// "- 2024-06-01 14:05 buy milk"; None for blank text, which is never written
+pub fn note_line(text: &str, now: DateTime<Local>) -> Option<String> {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.is_empty() {
+        return None;
+    }
+    Some(format!("- {} {}", now.format("%Y-%m-%d %H:%M"), text))
+}
+
+// The text of a note line, without its timestamp
+pub fn note_text(line: &str) -> &str {
+    let Some(rest) = line.strip_prefix("- ") else {
+        return line;
+    };
+    // "YYYY-MM-DD HH:MM " is 17 characters
+    match rest.get(..17) {
+        Some(stamp) if chrono::NaiveDateTime::parse_from_str(stamp.trim_end(), "%Y-%m-%d %H:%M").is_ok() => &rest[17..],
+        _ => rest,
+    }
+}
+
+// Writes the whole file next to itself and renames it over, so a crash leaves the old or new content
+async fn write_atomically(path: &Path, content: &str) -> std::result::Result<(), PluginError> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let tmp_path = path.with_extension("md.tmp");
+    tokio::fs::write(&tmp_path, content).await?;
+    tokio::fs::rename(&tmp_path, path).await?;
+    Ok(())
+}
+
+async fn read_notes(path: &Path) -> std::result::Result<String, PluginError> {
+    match tokio::fs::read_to_string(path).await {
+        Ok(content) => Ok(content),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(String::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn append_note(path: &Path, line: &str) -> std::result::Result<(), PluginError> {
+    let mut content = read_notes(path).await?;
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    content.push_str(line);
+    content.push('\n');
+    write_atomically(path, &content).await
+}
+
+// Removes line `index` (zero-based) from `path` if it still reads `expected`; the file may have been
+// edited since the list was shown. Returns whether a line was removed
+pub async fn delete_note(path: &Path, index: usize, expected: &str) -> std::result::Result<bool, PluginError> {
+    let content = read_notes(path).await?;
+    let mut lines: Vec<&str> = content.lines().collect();
+    if lines.get(index) != Some(&expected) {
+        return Ok(false);
+    }
+    lines.remove(index);
+
+    let mut updated = lines.join("\n");
+    if !updated.is_empty() {
+        updated.push('\n');
+    }
+    write_atomically(path, &updated).await?;
+    Ok(true)
+}
+
+// A captured line and where it is, so it can be deleted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NoteEntry {
+    pub file: PathBuf,
+    pub index: usize,
+    pub line: String,
+}
+
+// Note lines of `content`, newest (last) first
+fn entries_in(file: &Path, content: &str) -> Vec<NoteEntry> {
+    let mut entries: Vec<NoteEntry> = content.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| NoteEntry { file: file.to_path_buf(), index, line: line.to_string() })
+        .collect();
+    entries.reverse();
+    entries
+}
+
+// The newest `limit` notes; in daily mode, from the newest files back
+pub async fn recent_notes(settings: &NotesSettings, data_dir: &Path, limit: usize) -> std::result::Result<Vec<NoteEntry>, PluginError> {
+    let target = settings.target_file(data_dir);
+    let files = if settings.daily {
+        let dir = target.parent().map(Path::to_path_buf).unwrap_or_default();
+        let mut names = Vec::new();
+        match tokio::fs::read_dir(&dir).await {
+            Ok(mut entries) => {
+                while let Some(entry) = entries.next_entry().await? {
+                    let name = entry.file_name().to_string_lossy().into_owned();
+                    if is_daily_file_name(&name) {
+                        names.push(name);
+                    }
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+        // The date in the name sorts newest last
+        names.sort();
+        names.into_iter().rev().map(|name| dir.join(name)).collect()
+    } else {
+        vec![target]
+    };
+
+    let mut notes = Vec::new();
+    for file in files {
+        notes.extend(entries_in(&file, &read_notes(&file).await?));
+        if notes.len() >= limit {
+            break;
+        }
+    }
+    notes.truncate(limit);
+    Ok(notes)
+}
+
+// `note <text>` appends a timestamped line to the notes file; `notes` lists the latest ones
+#[derive(Debug)]
+pub struct NotesPlugin {
+    context: PluginContext,
+}
+
+impl NotesPlugin {
+    const KEYWORD: &'static str = "note";
+    const LIST_KEYWORD: &'static str = "notes";
+    pub const DEFAULT_FILE: &'static str = "notes.md";
+    pub const LIST_LIMIT: usize = 10;
+
+    pub fn new(context: PluginContext) -> Self {
+        Self { context }
+    }
+
+    async fn settings(&self) -> NotesSettings {
+        self.context.plugin_settings("notes").await
+            .and_then(|settings| serde_json::from_value(settings).ok())
+            .unwrap_or_default()
+    }
+
+    fn data_dir(&self) -> std::result::Result<PathBuf, PluginError> {
+        self.context.data_dir()
+            .map(Path::to_path_buf)
+            .ok_or_else(|| PluginError::ConfigurationError("No data directory for notes".to_string()))
+    }
+
+    fn entry_result(entry: &NoteEntry, position: usize) -> SearchResult {
+        let text = note_text(&entry.line);
+        let file_name = entry.file.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+
+        SearchResult::new(text, format!("{} · select to copy", file_name))
+            .with_action(Action::CopyToClipboard(text.to_string()))
+            .with_category(Category::Plugin("Notes".to_string()))
+            .with_score(0.9 - position as f64 * 0.01)
+            .with_destructive_action("Delete line", Action::PluginAction {
+                plugin_id: "Notes".to_string(),
+                action_data: serde_json::json!({
+                    "delete": entry.file,
+                    "line": entry.index,
+                    "text": entry.line,
+                }),
+            })
+    }
+}
+
+#[async_trait]
+impl Plugin for NotesPlugin {
+    fn name(&self) -> &str {
+        "Notes"
+    }
+
+    fn version(&self) -> &str {
+        "1.0.0"
+    }
+
+    fn description(&self) -> &str {
+        "Capture quick notes into a Markdown file"
+    }
+
+    fn can_handle(&self, query: &str) -> bool {
+        let query = query.trim_start();
+        query.trim_end() == Self::LIST_KEYWORD || query.starts_with("note ")
+    }
+
+    fn trigger_prefix(&self) -> Option<&str> {
+        Some("note ")
+    }
+
+    fn has_configuration(&self) -> bool {
+        true
+    }
+
+    fn get_configuration_ui(&self) -> Option<serde_json::Value> {
+        Some(serde_json::json!({
+            "fields": [
+                { "key": "file", "label": "Notes file", "type": "string", "description": "Defaults to notes.md in the data directory" },
+                { "key": "daily", "label": "Daily files", "type": "bool", "default": false, "description": "Write one file per day, notes-YYYY-MM-DD.md next to the notes file" },
+            ],
+        }))
+    }
+
+    async fn search(&self, query: &str) -> std::result::Result<Vec<SearchResult>, PluginError> {
+        let query = query.trim_start();
+        if query.trim_end() == Self::LIST_KEYWORD {
+            let notes = recent_notes(&self.settings().await, &self.data_dir()?, Self::LIST_LIMIT).await?;
+            return Ok(notes.iter().enumerate().map(|(position, entry)| Self::entry_result(entry, position)).collect());
+        }
+
+        let text = query.strip_prefix(Self::KEYWORD).unwrap_or_default().trim();
+        if text.is_empty() {
+            return Ok(Vec::new());
+        }
+        Ok(vec![
+            SearchResult::new(format!("Note: {}", text), "Append to your notes")
+                .with_action(Action::PluginAction {
+                    plugin_id: "Notes".to_string(),
+                    action_data: serde_json::json!({ "append": text }),
+                })
+                .with_category(Category::Plugin("Notes".to_string()))
+                .with_score(1.0)
+        ])
+    }
+
+    async fn execute(&self, result: &SearchResult) -> std::result::Result<(), PluginError> {
+        let Action::PluginAction { ref action_data, .. } = result.action else {
+            return Ok(());
+        };
+
+        if let Some(text) = action_data.get("append").and_then(|v| v.as_str()) {
+            let Some(line) = note_line(text, Local::now()) else {
+                return Ok(());
+            };
+            let path = self.settings().await.file_for(&self.data_dir()?, Local::now().date_naive());
+            append_note(&path, &line).await?;
+            info!("Appended a note to {}", path.display());
+            self.context.show_notification("Note saved", note_text(&line))?;
+        } else if let Some(path) = action_data.get("delete").and_then(|v| v.as_str()) {
+            let index = action_data.get("line").and_then(|v| v.as_u64()).unwrap_or_default() as usize;
+            let expected = action_data.get("text").and_then(|v| v.as_str()).unwrap_or_default();
+            if !delete_note(Path::new(path), index, expected).await? {
+                return Err(PluginError::ExecutionError("The note changed since it was listed".to_string()));
+            }
+            info!("Deleted a note from {}", path);
+        }
+        Ok(())
+    }
+
+    fn syntax_hints(&self) -> Vec<SyntaxHint> {
+        vec![
+            SyntaxHint::new("note <text>", tr("hint.note", "Append a note to your notes file"), "note buy milk before 6"),
+            SyntaxHint::new("notes", tr("hint.notes", "Show the latest notes"), "notes"),
+        ]
+    }
+
+    fn icon(&self) -> Option<PluginIcon> {
+        Some(PluginIcon::Glyph("note"))
+    }
+
+    fn triggers(&self) -> Vec<Trigger> {
+        vec![Trigger::keyword("note"), Trigger::keyword(Self::LIST_KEYWORD)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 6, day).unwrap()
+    }
+
+    fn texts(entries: &[NoteEntry]) -> Vec<&str> {
+        entries.iter().map(|entry| note_text(&entry.line)).collect()
+    }
+
+    #[test]
+    fn lines_are_timestamped_and_blank_notes_are_dropped() {
+        let now = Local.with_ymd_and_hms(2024, 6, 1, 14, 5, 0).unwrap();
+        let line = note_line("  buy milk\tbefore 6 ", now).unwrap();
+        assert_eq!(line, "- 2024-06-01 14:05 buy milk before 6");
+        assert_eq!(note_text(&line), "buy milk before 6");
+        assert_eq!(note_line(" \n\t", now), None);
+        // Lines written by hand keep whatever they say
+        assert_eq!(note_text("- call back"), "call back");
+        assert_eq!(note_text("plain line"), "plain line");
+    }
+
+    #[test]
+    fn daily_mode_rotates_files_next_to_the_notes_file() {
+        let data_dir = Path::new("/data/falcommand");
+        let settings = NotesSettings::default();
+        assert_eq!(settings.file_for(data_dir, date(1)), data_dir.join("notes.md"));
+
+        let daily = NotesSettings { file: Some(PathBuf::from("/home/me/notes/inbox.md")), daily: true };
+        assert_eq!(daily.file_for(data_dir, date(1)), PathBuf::from("/home/me/notes/notes-2024-06-01.md"));
+        assert_eq!(daily.file_for(data_dir, date(2)), PathBuf::from("/home/me/notes/notes-2024-06-02.md"));
+        assert!(is_daily_file_name("notes-2024-06-01.md"));
+        assert!(!is_daily_file_name("notes-2024-13-01.md") && !is_daily_file_name("notes.md"));
+    }
+
+    #[tokio::test]
+    async fn appending_creates_the_file_and_keeps_lines_whole() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("notes.md");
+        append_note(&path, "- first").await.unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "- first\n");
+
+        // A file edited by hand without a final newline
+        std::fs::write(&path, "- first\n- edited").unwrap();
+        append_note(&path, "- second").await.unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "- first\n- edited\n- second\n");
+        assert_eq!(std::fs::read_dir(path.parent().unwrap()).unwrap().count(), 1, "no temporary file is left behind");
+    }
+
+    #[tokio::test]
+    async fn recent_notes_are_listed_newest_first_across_daily_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let settings = NotesSettings { file: None, daily: true };
+        for (day, notes) in [(1, ["a1", "a2"]), (3, ["c1", "c2"]), (2, ["b1", "b2"])] {
+            for note in notes {
+                append_note(&settings.file_for(dir.path(), date(day)), &format!("- {}", note)).await.unwrap();
+            }
+        }
+        std::fs::write(dir.path().join("notes.md"), "- not a daily file\n").unwrap();
+
+        let notes = recent_notes(&settings, dir.path(), 5).await.unwrap();
+        assert_eq!(texts(&notes), ["c2", "c1", "b2", "b1", "a2"]);
+        assert_eq!(notes[0].file, dir.path().join("notes-2024-06-03.md"));
+        assert_eq!(notes[0].index, 1);
+
+        // A single file skips blank lines but keeps their line numbers
+        let single = NotesSettings::default();
+        std::fs::write(single.target_file(dir.path()), "- one\n\n- two\n").unwrap();
+        let notes = recent_notes(&single, dir.path(), 10).await.unwrap();
+        assert_eq!(texts(&notes), ["two", "one"]);
+        assert_eq!(notes[0].index, 2);
+        assert!(recent_notes(&settings, &dir.path().join("missing"), 10).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn deleting_removes_only_the_listed_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.md");
+        std::fs::write(&path, "- one\n- two\n- three\n").unwrap();
+
+        assert!(delete_note(&path, 1, "- two").await.unwrap());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "- one\n- three\n");
+
+        // The file changed since the list was shown: nothing is touched
+        assert!(!delete_note(&path, 1, "- two").await.unwrap());
+        assert!(!delete_note(&path, 9, "- one").await.unwrap());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "- one\n- three\n");
+
+        assert!(delete_note(&path, 0, "- one").await.unwrap());
+        assert!(delete_note(&path, 0, "- three").await.unwrap());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "");
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+}
@@ -1,10 +1,26 @@
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
 use async_trait::async_trait;
 use tokio::sync::RwLock;
+use serde::{Deserialize, Serialize};
 use serde_json;
 use log::{info, warn, error};
 
-use falcommand_config::{Config, SearchResult, Action, Category};
+use falcommand_config::{Config, SearchResult, SyntaxHint, Action, ActionError, Category};
+use falcommand_config::i18n::{current_language, tr};
+use crate::devdocs::DevDocsPlugin;
+use crate::system::SystemControlsPlugin;
+use crate::network::NetworkPlugin;
+use crate::notes::NotesPlugin;
+use crate::duplicates::DuplicatesPlugin;
+use crate::tasks::{TaskTracker, TaskProgress, ProgressThrottle};
+use crate::icons::{PluginIcon, PluginIconCache, attach_plugin_icon};
+use crate::routing::{RoutingTable, Trigger, trigger_hints};
+use crate::settings_form::{SettingsForm, SettingsSchema, plugin_secret_key};
+use falcommand_core::{HintGroup, SyntaxHintProvider, SearchSource, ResultRefresher, PluginActionHandler, ConnectivityMonitor, ConfigStore, BackgroundTasks, TaskSupervisor, SecretStore, IndexManager, ExtractionWorker, WebClient, arithmetic_pattern, format_number};
+use falcommand_platform::PlatformProvider;
 
 #[derive(Debug, thiserror::Error)]
 pub enum PluginError {
@@ -53,33 +69,215 @@ pub trait Plugin: Send + Sync + std::fmt::Debug {
         Ok(())
     }
     
+    // A cheap final check, asked only when the routing table picked this plugin for the query
     fn can_handle(&self, query: &str) -> bool;
     async fn search(&self, query: &str) -> std::result::Result<Vec<SearchResult>, PluginError>;
+    
+    // Must return quickly; slow work belongs in PluginContext::spawn_tracked
     async fn execute(&self, result: &SearchResult) -> std::result::Result<(), PluginError>;
     
     fn has_configuration(&self) -> bool {
         false
     }
     
+    // A SettingsSchema as JSON. The settings window falls back to a raw JSON editor for anything else
     fn get_configuration_ui(&self) -> Option<serde_json::Value> {
         None
     }
+    
+    fn syntax_hints(&self) -> Vec<SyntaxHint> {
+        Vec::new()
+    }
+    
+    // Query a per-plugin hotkey opens the window with, e.g. "timer "
+    fn trigger_prefix(&self) -> Option<&str> {
+        None
+    }
+    
+    // Results from plugins declaring an interval are refreshed while they stay on screen
+    fn refresh_interval(&self) -> Option<Duration> {
+        None
+    }
+    
+    async fn refresh(&self, _result: &SearchResult) -> std::result::Result<Option<SearchResult>, PluginError> {
+        Ok(None)
+    }
+    
+    // Shown with results that don't set an icon themselves
+    fn icon(&self) -> Option<PluginIcon> {
+        None
+    }
+    
+    // Queries this plugin answers. Without any, it is asked about every query
+    fn triggers(&self) -> Vec<Trigger> {
+        Vec::new()
+    }
 }
 
-#[derive(Debug, Clone)]
+// Backs PluginContext::http_get and http_post, so tests can script responses
+#[async_trait]
+pub trait HttpClient: Send + Sync {
+    async fn get(&self, url: &str) -> std::result::Result<String, PluginError>;
+    async fn post(&self, url: &str, body: &str) -> std::result::Result<String, PluginError>;
+}
+
+#[async_trait]
+impl HttpClient for WebClient {
+    async fn get(&self, url: &str) -> std::result::Result<String, PluginError> {
+        WebClient::get(self, url).await.map_err(|e| PluginError::Other(e.to_string()))
+    }
+    
+    async fn post(&self, url: &str, body: &str) -> std::result::Result<String, PluginError> {
+        self.post_json(url, body).await.map_err(|e| PluginError::Other(e.to_string()))
+    }
+}
+
+#[derive(Clone)]
 pub struct PluginContext {
     config: Arc<RwLock<Config>>,
+    platform: Option<Arc<dyn PlatformProvider>>,
+    connectivity: Option<Arc<ConnectivityMonitor>>,
+    http: Option<Arc<dyn HttpClient>>,
+    tasks: Arc<TaskTracker>,
+    data_dir: Option<std::path::PathBuf>,
+    secrets: Option<Arc<dyn SecretStore>>,
+    index: Option<Arc<IndexManager>>,
+    extractor: Option<Arc<ExtractionWorker>>,
+}
+
+impl std::fmt::Debug for PluginContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PluginContext")
+            .field("config", &self.config)
+            .field("platform", &self.platform.is_some())
+            .field("connectivity", &self.connectivity)
+            .field("http", &self.http.is_some())
+            .field("tasks", &self.tasks.running_count())
+            .field("data_dir", &self.data_dir)
+            .field("secrets", &self.secrets.as_ref().map(|store| store.name()))
+            .field("index", &self.index.is_some())
+            .field("extractor", &self.extractor.is_some())
+            .finish()
+    }
 }
 
 impl PluginContext {
     pub fn new(config: Arc<RwLock<Config>>) -> Self {
-        Self { config }
+        Self {
+            config,
+            platform: None,
+            connectivity: None,
+            http: None,
+            tasks: Arc::new(TaskTracker::new()),
+            data_dir: None,
+            secrets: None,
+            index: None,
+            extractor: None,
+        }
+    }
+    
+    pub fn with_platform(mut self, platform: Arc<dyn PlatformProvider>) -> Self {
+        self.platform = Some(platform);
+        self
+    }
+    
+    pub fn platform(&self) -> Option<&Arc<dyn PlatformProvider>> {
+        self.platform.as_ref()
+    }
+    
+    pub fn with_connectivity(mut self, connectivity: Arc<ConnectivityMonitor>) -> Self {
+        self.connectivity = Some(connectivity);
+        self
+    }
+    
+    pub fn with_http_client(mut self, http: Arc<dyn HttpClient>) -> Self {
+        self.http = Some(http);
+        self
+    }
+    
+    // Where plugins keep files of their own, such as notes
+    pub fn with_data_dir(mut self, data_dir: std::path::PathBuf) -> Self {
+        self.data_dir = Some(data_dir);
+        self
+    }
+    
+    pub fn data_dir(&self) -> Option<&std::path::Path> {
+        self.data_dir.as_deref()
+    }
+    
+    // Holds the secret fields of plugin settings forms
+    pub fn with_secret_store(mut self, secrets: Arc<dyn SecretStore>) -> Self {
+        self.secrets = Some(secrets);
+        self
+    }
+    
+    pub fn secret_store(&self) -> Option<&Arc<dyn SecretStore>> {
+        self.secrets.as_ref()
+    }
+    
+    // Read access to indexed files, for plugins that work on them
+    pub fn with_index_manager(mut self, index: Arc<IndexManager>) -> Self {
+        self.index = Some(index);
+        self
+    }
+    
+    pub fn index_manager(&self) -> Option<&Arc<IndexManager>> {
+        self.index.as_ref()
+    }
+    
+    // Runs file reads that may hang or crash out of process
+    pub fn with_extractor(mut self, extractor: Arc<ExtractionWorker>) -> Self {
+        self.extractor = Some(extractor);
+        self
+    }
+    
+    pub fn extractor(&self) -> Option<&Arc<ExtractionWorker>> {
+        self.extractor.as_ref()
+    }
+    
+    // Without a monitor the network is assumed reachable
+    pub fn is_online(&self) -> bool {
+        self.connectivity.as_ref().map(|c| c.is_online()).unwrap_or(true)
+    }
+    
+    pub fn tasks(&self) -> &Arc<TaskTracker> {
+        &self.tasks
+    }
+    
+    // Runs work in the background so execute can return; the task can report progress
+    // with the returned id, ends with a notification and is cancelled at shutdown
+    pub fn spawn_tracked<F>(&self, label: &str, future: F) -> u64
+    where
+        F: std::future::Future<Output = std::result::Result<(), PluginError>> + Send + 'static,
+    {
+        self.tasks.spawn(label, future)
+    }
+    
+    pub fn report_progress(&self, task_id: u64, fraction: f32, message: &str) {
+        self.tasks.report(task_id, fraction, message);
     }
     
     pub async fn get_config(&self) -> std::result::Result<Config, PluginError> {
         Ok(self.config.read().await.clone())
     }
     
+    // plugins.plugin_settings.<id>, where the id is the lowercased plugin name
+    pub async fn plugin_settings(&self, id: &str) -> Option<serde_json::Value> {
+        self.config.read().await.plugins.plugin_settings.get(id).cloned()
+    }
+    
+    // A secret field of plugin `id`'s settings; None when unset or there is no secret store
+    pub async fn plugin_secret(&self, id: &str, field: &str) -> Option<String> {
+        let store = self.secrets.as_ref()?;
+        match store.get(&plugin_secret_key(id, field)).await {
+            Ok(secret) => secret,
+            Err(e) => {
+                warn!("Failed to read secret '{}' of plugin '{}': {}", field, id, e);
+                None
+            }
+        }
+    }
+    
     pub fn log(&self, level: LogLevel, message: &str) {
         match level {
             LogLevel::Error => error!("{}", message),
@@ -93,15 +291,34 @@ impl PluginContext {
         tokio::fs::read(path).await.map_err(Into::into)
     }
     
+    // A context without a client can't reach the network, so requests fail instead of coming back empty
+    fn http_client(&self) -> std::result::Result<&Arc<dyn HttpClient>, PluginError> {
+        self.http.as_ref().ok_or_else(|| PluginError::Other("No HTTP client is available".to_string()))
+    }
+    
     pub async fn http_get(&self, url: &str) -> std::result::Result<String, PluginError> {
-        // HTTP client implementation would go here
         info!("HTTP GET request to: {}", url);
-        Ok(String::new())
+        self.http_client()?.get(url).await
+    }
+    
+    pub async fn http_post(&self, url: &str, body: &str) -> std::result::Result<String, PluginError> {
+        info!("HTTP POST request to: {}", url);
+        self.http_client()?.post(url, body).await
+    }
+    
+    pub fn copy_to_clipboard(&self, text: &str) -> std::result::Result<(), PluginError> {
+        let platform = self.platform.as_ref()
+            .ok_or_else(|| PluginError::ExecutionError("Clipboard is not available".to_string()))?;
+        platform.copy_to_clipboard(text)
+            .map_err(|e| PluginError::ExecutionError(e.to_string()))
     }
     
     pub fn show_notification(&self, title: &str, message: &str) -> std::result::Result<(), PluginError> {
         info!("Plugin notification: {} - {}", title, message);
-        // This would delegate to platform provider
+        if let Some(ref platform) = self.platform {
+            platform.show_notification(title, message)
+                .map_err(|e| PluginError::ExecutionError(e.to_string()))?;
+        }
         Ok(())
     }
 }
@@ -114,13 +331,65 @@ pub enum LogLevel {
     Debug,
 }
 
+// What the settings UI needs to render one plugin row and its settings editor
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginDescriptor {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    pub description: String,
+    pub enabled: bool,
+    pub has_configuration: bool,
+    pub configuration_ui: Option<serde_json::Value>,
+    // What the settings window builds from configuration_ui
+    pub settings_form: Option<SettingsForm>,
+    pub capabilities: Vec<PluginCapability>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PluginCapability {
+    Search,
+    SyntaxHints,
+    LiveRefresh,
+    Configuration,
+}
+
+// Config ids are the lowercased plugin names
+pub fn plugin_id(plugin: &dyn Plugin) -> String {
+    plugin.name().to_lowercase()
+}
+
 pub struct PluginSystem {
     plugins: RwLock<Vec<Arc<dyn Plugin>>>,
     config: Arc<RwLock<Config>>,
     context: PluginContext,
+    config_store: Option<Arc<ConfigStore>>,
+    // Without it, plugin images aren't attached; glyphs need no file either way
+    icon_cache: Option<PluginIconCache>,
+    // Compiled from the loaded plugins' triggers whenever they change
+    routes: std::sync::RwLock<Arc<RoutingTable>>,
+    // Plugin searches since start, and how many of them failed or timed out
+    searches: AtomicU64,
+    failed_searches: AtomicU64,
+}
+
+// Plugin search calls since start; a soak run reports the failure rate
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PluginSearchStats {
+    pub searches: u64,
+    pub failures: u64,
 }
 
 impl PluginSystem {
+    // Upper bound for a single plugin call so a hung plugin can't stall searching or refreshing
+    pub const PLUGIN_TIMEOUT: Duration = Duration::from_secs(2);
+    
+    // Progress notifications per task are limited to one per interval
+    pub const PROGRESS_NOTIFICATION_INTERVAL: Duration = Duration::from_secs(5);
+    
+    // Config ids of the built-in plugins, in load order
+    pub const BUILTIN_PLUGINS: [&'static str; 8] = ["calculator", "timer", "devdocs", "system", "network", "translator", "notes", "duplicates"];
+    
     pub async fn new(config: Arc<RwLock<Config>>) -> std::result::Result<Self, PluginError> {
         info!("Initializing plugin system...");
         
@@ -130,9 +399,67 @@ impl PluginSystem {
             plugins: RwLock::new(Vec::new()),
             config,
             context,
+            config_store: None,
+            icon_cache: None,
+            routes: std::sync::RwLock::new(Arc::new(RoutingTable::default())),
+            searches: AtomicU64::new(0),
+            failed_searches: AtomicU64::new(0),
         })
     }
     
+    pub fn with_platform_provider(mut self, platform: Arc<dyn PlatformProvider>) -> Self {
+        self.context = self.context.with_platform(platform);
+        self
+    }
+    
+    pub fn with_connectivity(mut self, connectivity: Arc<ConnectivityMonitor>) -> Self {
+        self.context = self.context.with_connectivity(connectivity);
+        self
+    }
+    
+    pub fn with_http_client(mut self, http: Arc<dyn HttpClient>) -> Self {
+        self.context = self.context.with_http_client(http);
+        self
+    }
+    
+    pub fn with_data_dir(mut self, data_dir: std::path::PathBuf) -> Self {
+        self.context = self.context.with_data_dir(data_dir);
+        self
+    }
+    
+    // The context built-in plugins get; build plugins passed to register_plugin with it so their
+    // tracked tasks are shown and notified like the built-in ones
+    pub fn context(&self) -> &PluginContext {
+        &self.context
+    }
+    
+    // Used to persist plugin toggles and settings to the active profile
+    pub fn with_config_store(mut self, config_store: Arc<ConfigStore>) -> Self {
+        self.config_store = Some(config_store);
+        self
+    }
+    
+    pub fn with_secret_store(mut self, secrets: Arc<dyn SecretStore>) -> Self {
+        self.context = self.context.with_secret_store(secrets);
+        self
+    }
+    
+    pub fn with_index_manager(mut self, index: Arc<IndexManager>, extractor: Arc<ExtractionWorker>) -> Self {
+        self.context = self.context.with_index_manager(index).with_extractor(extractor);
+        self
+    }
+    
+    pub fn with_icon_cache_dir(mut self, dir: std::path::PathBuf) -> Self {
+        self.icon_cache = Some(PluginIconCache::new(dir));
+        self
+    }
+    
+    // The icon file for results of `plugin`, if it has an image icon
+    fn icon_path(&self, plugin: &dyn Plugin) -> Option<std::path::PathBuf> {
+        let icon = plugin.icon()?;
+        self.icon_cache.as_ref()?.resolve(&plugin_id(plugin), &icon)
+    }
+    
     pub async fn load_plugins(&self) -> std::result::Result<(), PluginError> {
         info!("Loading plugins...");
         
@@ -145,23 +472,34 @@ impl PluginSystem {
         Ok(())
     }
     
+    fn create_builtin_plugin(&self, id: &str) -> Option<Arc<dyn Plugin>> {
+        let context = self.context.clone();
+        let plugin: Arc<dyn Plugin> = match id {
+            "calculator" => Arc::new(CalculatorPlugin::new(context)),
+            "timer" => Arc::new(TimerPlugin::new(context)),
+            "devdocs" => Arc::new(DevDocsPlugin::new(context)),
+            "system" => Arc::new(SystemControlsPlugin::new(context)),
+            "network" => Arc::new(NetworkPlugin::new(context)),
+            "translator" => Arc::new(TranslatorPlugin::new(context)),
+            "notes" => Arc::new(NotesPlugin::new(context)),
+            "duplicates" => Arc::new(DuplicatesPlugin::new(context)),
+            _ => return None,
+        };
+        Some(plugin)
+    }
+    
     async fn load_builtin_plugins(&self) -> std::result::Result<(), PluginError> {
-        let config = self.config.read().await;
+        let plugin_config = self.config.read().await.plugins.clone();
         
-        // Load calculator plugin if enabled
-        if config.plugins.enabled.contains(&"calculator".to_string()) {
-            let calculator_plugin = Arc::new(CalculatorPlugin::new(self.context.clone()));
-            calculator_plugin.initialize().await?;
-            self.register_plugin(calculator_plugin).await;
-            info!("Loaded calculator plugin");
-        }
-        
-        // Load translator plugin if enabled
-        if config.plugins.enabled.contains(&"translator".to_string()) {
-            let translator_plugin = Arc::new(TranslatorPlugin::new(self.context.clone()));
-            translator_plugin.initialize().await?;
-            self.register_plugin(translator_plugin).await;
-            info!("Loaded translator plugin");
+        for id in Self::BUILTIN_PLUGINS {
+            if !plugin_config.is_enabled(id) {
+                continue;
+            }
+            if let Some(plugin) = self.create_builtin_plugin(id) {
+                plugin.initialize().await?;
+                self.register_plugin(plugin).await;
+                info!("Loaded {} plugin", id);
+            }
         }
         
         Ok(())
@@ -171,39 +509,394 @@ impl PluginSystem {
         let mut plugins = self.plugins.write().await;
         info!("Registering plugin: {}", plugin.name());
         plugins.push(plugin);
+        self.compile_routes(&plugins);
+    }
+    
+    // Called with the plugin list locked for writing, so searches never see a table for other plugins
+    fn compile_routes(&self, plugins: &[Arc<dyn Plugin>]) {
+        let table = RoutingTable::compile(plugins.iter().map(|plugin| (plugin_id(plugin.as_ref()), plugin.triggers())));
+        *self.routes.write().unwrap() = Arc::new(table);
     }
     
     pub async fn search_all(&self, query: &str) -> std::result::Result<Vec<SearchResult>, PluginError> {
-        let plugins = self.plugins.read().await;
-        let mut all_results = Vec::new();
+        // Searching works on a snapshot so toggling a plugin never waits for, or cancels, a search
+        let plugins: Vec<Arc<dyn Plugin>> = self.plugins.read().await.clone();
+        let routed = self.routes.read().unwrap().route(query);
+        let mut plugin_results = Vec::new();
         
-        for plugin in plugins.iter() {
+        for plugin in plugins.iter().filter(|plugin| routed.contains(&plugin_id(plugin.as_ref()))) {
             if plugin.can_handle(query) {
-                match plugin.search(query).await {
-                    Ok(mut results) => {
-                        all_results.append(&mut results);
+                self.searches.fetch_add(1, Ordering::Relaxed);
+                match tokio::time::timeout(Self::PLUGIN_TIMEOUT, plugin.search(query)).await {
+                    Ok(Ok(mut results)) => {
+                        if let Some(icon) = self.icon_path(plugin.as_ref()) {
+                            attach_plugin_icon(&mut results, &icon);
+                        }
+                        plugin_results.push((plugin.clone(), results));
                     }
-                    Err(e) => {
+                    Ok(Err(e)) => {
+                        self.failed_searches.fetch_add(1, Ordering::Relaxed);
                         warn!("Plugin '{}' search failed: {}", plugin.name(), e);
                     }
+                    Err(_) => {
+                        self.failed_searches.fetch_add(1, Ordering::Relaxed);
+                        warn!("Plugin '{}' search timed out", plugin.name());
+                    }
                 }
             }
         }
         
+        // Plugins disabled while the search ran still finish, but their results are dropped here
+        let active = self.plugins.read().await;
+        let all_results = plugin_results.into_iter()
+            .filter(|(plugin, _)| active.iter().any(|p| Arc::ptr_eq(p, plugin)))
+            .flat_map(|(_, results)| results)
+            .collect();
+        
         Ok(all_results)
     }
     
+    pub fn search_stats(&self) -> PluginSearchStats {
+        PluginSearchStats {
+            searches: self.searches.load(Ordering::Relaxed),
+            failures: self.failed_searches.load(Ordering::Relaxed),
+        }
+    }
+    
+    pub async fn set_plugin_enabled(&self, name: &str, enabled: bool) -> std::result::Result<(), PluginError> {
+        let id = name.to_lowercase();
+        let loaded = self.plugins.read().await
+            .iter()
+            .find(|plugin| plugin_id(plugin.as_ref()) == id)
+            .cloned();
+        
+        match (enabled, loaded) {
+            (true, None) => {
+                let plugin = self.create_builtin_plugin(&id)
+                    .ok_or_else(|| PluginError::ConfigurationError(format!("Unknown plugin '{}'", name)))?;
+                plugin.initialize().await?;
+                self.register_plugin(plugin).await;
+            }
+            (false, Some(plugin)) => {
+                let mut plugins = self.plugins.write().await;
+                plugins.retain(|p| !Arc::ptr_eq(p, &plugin));
+                self.compile_routes(&plugins);
+                drop(plugins);
+                info!("Unregistered plugin: {}", plugin.name());
+                // In-flight calls hold their own Arc and finish normally before the plugin is dropped
+                if let Err(e) = plugin.shutdown().await {
+                    warn!("Plugin '{}' failed to shut down cleanly: {}", plugin.name(), e);
+                }
+            }
+            _ => {}
+        }
+        
+        self.update_config(move |config| config.plugins.set_enabled(&id, enabled)).await
+    }
+    
+    pub async fn set_plugin_settings(&self, name: &str, settings: serde_json::Value) -> std::result::Result<(), PluginError> {
+        let id = name.to_lowercase();
+        self.update_config(move |config| {
+            config.plugins.plugin_settings.insert(id, settings);
+        }).await
+    }
+    
+    // Saves what was edited in a plugin's settings form: secret fields go to the secret store,
+    // the rest to the config like set_plugin_settings. Secrets are only written to a store that
+    // encrypts them; without an OS keyring they can be cleared but not set
+    pub async fn save_plugin_settings(&self, name: &str, values: serde_json::Map<String, serde_json::Value>) -> std::result::Result<(), PluginError> {
+        let id = name.to_lowercase();
+        let schema = self.settings_schema(&id).await?;
+        let validated = schema.validate(&values)
+            .map_err(|e| PluginError::ConfigurationError(e.to_string()))?;
+        
+        if !validated.secrets.is_empty() {
+            let store = self.context.secret_store()
+                .ok_or_else(|| PluginError::ConfigurationError("No secret store to keep the plugin's secrets in".to_string()))?;
+            if !store.encrypts_at_rest() && validated.secrets.iter().any(|(_, secret)| secret.is_some()) {
+                return Err(PluginError::ConfigurationError(format!(
+                    "No OS keyring is available, and plugin secrets are not kept in the {} store", store.name()
+                )));
+            }
+            for (field, secret) in &validated.secrets {
+                let key = plugin_secret_key(&id, field);
+                let stored = match secret {
+                    Some(secret) => store.set(&key, secret).await,
+                    None => store.delete(&key).await,
+                };
+                stored.map_err(|e| PluginError::ConfigurationError(e.to_string()))?;
+            }
+        }
+        self.set_plugin_settings(&id, serde_json::Value::Object(validated.settings)).await
+    }
+    
+    // Saves the raw JSON editor that stands in for a schema the form doesn't understand. Nothing
+    // can be checked against the schema, but the settings have to be a JSON object
+    pub async fn save_raw_plugin_settings(&self, name: &str, json: &str) -> std::result::Result<(), PluginError> {
+        let settings: serde_json::Value = serde_json::from_str(json)
+            .map_err(|e| PluginError::ConfigurationError(format!("Not valid JSON: {}", e)))?;
+        if !settings.is_object() {
+            return Err(PluginError::ConfigurationError("Plugin settings must be a JSON object".to_string()));
+        }
+        self.set_plugin_settings(name, settings).await
+    }
+    
+    // The settings form of a loaded or built-in plugin, when it declares one the form understands
+    async fn settings_schema(&self, id: &str) -> std::result::Result<SettingsSchema, PluginError> {
+        let loaded = self.plugins.read().await.iter().find(|plugin| plugin_id(plugin.as_ref()) == id).cloned();
+        let plugin = loaded.or_else(|| self.create_builtin_plugin(id))
+            .ok_or_else(|| PluginError::ConfigurationError(format!("Unknown plugin '{}'", id)))?;
+        let ui = plugin.get_configuration_ui()
+            .ok_or_else(|| PluginError::ConfigurationError(format!("Plugin '{}' has no settings", id)))?;
+        SettingsSchema::parse(&ui).map_err(|e| PluginError::ConfigurationError(e.to_string()))
+    }
+    
+    async fn update_config<F>(&self, mutate: F) -> std::result::Result<(), PluginError>
+    where
+        F: FnOnce(&mut Config) + Send + 'static,
+    {
+        match self.config_store {
+            Some(ref store) => store.update(mutate).await
+                .map_err(|e| PluginError::ConfigurationError(e.to_string())),
+            None => {
+                warn!("No config store; plugin configuration changes are not persisted");
+                mutate(&mut *self.config.write().await);
+                Ok(())
+            }
+        }
+    }
+    
+    // Loaded plugins plus the built-ins that are currently disabled
+    pub async fn plugin_descriptors(&self) -> Vec<PluginDescriptor> {
+        let loaded: Vec<Arc<dyn Plugin>> = self.plugins.read().await.clone();
+        let mut descriptors: Vec<PluginDescriptor> = loaded.iter()
+            .map(|plugin| Self::describe(plugin.as_ref(), true))
+            .collect();
+        
+        for id in Self::BUILTIN_PLUGINS {
+            if descriptors.iter().any(|d| d.id == id) {
+                continue;
+            }
+            if let Some(plugin) = self.create_builtin_plugin(id) {
+                descriptors.push(Self::describe(plugin.as_ref(), false));
+            }
+        }
+        
+        descriptors
+    }
+    
+    fn describe(plugin: &dyn Plugin, enabled: bool) -> PluginDescriptor {
+        let mut capabilities = vec![PluginCapability::Search];
+        if !plugin.syntax_hints().is_empty() {
+            capabilities.push(PluginCapability::SyntaxHints);
+        }
+        if plugin.refresh_interval().is_some() {
+            capabilities.push(PluginCapability::LiveRefresh);
+        }
+        if plugin.has_configuration() {
+            capabilities.push(PluginCapability::Configuration);
+        }
+        
+        let configuration_ui = plugin.get_configuration_ui();
+        PluginDescriptor {
+            id: plugin_id(plugin),
+            name: plugin.name().to_string(),
+            version: plugin.version().to_string(),
+            description: plugin.description().to_string(),
+            enabled,
+            has_configuration: plugin.has_configuration(),
+            settings_form: configuration_ui.as_ref().map(SettingsForm::from_configuration_ui),
+            configuration_ui,
+            capabilities,
+        }
+    }
+    
     pub async fn execute_plugin_action(&self, plugin_id: &str, result: &SearchResult) -> std::result::Result<(), PluginError> {
         let plugins = self.plugins.read().await;
         
         for plugin in plugins.iter() {
             if plugin.name() == plugin_id {
-                return plugin.execute(result).await;
+                return match tokio::time::timeout(Self::PLUGIN_TIMEOUT, plugin.execute(result)).await {
+                    Ok(outcome) => outcome,
+                    Err(_) => {
+                        warn!("Plugin '{}' execute timed out; slow work should use spawn_tracked", plugin.name());
+                        Err(PluginError::ExecutionError("timed out".to_string()))
+                    }
+                };
             }
         }
         
         Err(PluginError::Other(format!("Plugin '{}' not found", plugin_id)))
     }
+    
+    // Plugin id -> trigger prefix, for plugin-targeted hotkeys
+    pub async fn trigger_prefixes(&self) -> HashMap<String, String> {
+        self.plugins.read().await
+            .iter()
+            .filter_map(|plugin| plugin.trigger_prefix().map(|prefix| (plugin_id(plugin.as_ref()), prefix.to_string())))
+            .collect()
+    }
+    
+    pub fn running_tasks(&self) -> Vec<TaskProgress> {
+        self.context.tasks().running()
+    }
+    
+    pub fn active_task_count(&self) -> usize {
+        self.context.tasks().running_count()
+    }
+    
+    // Forwards task progress to desktop notifications, throttled per task
+    pub fn start_progress_notifications(&self) {
+        let context = self.context.clone();
+        
+        TaskSupervisor::default().spawn("plugin-progress", move || {
+            let context = context.clone();
+            async move {
+                let mut updates = context.tasks().subscribe();
+                let mut throttle = ProgressThrottle::new(Self::PROGRESS_NOTIFICATION_INTERVAL);
+                
+                loop {
+                    let progress = match updates.recv().await {
+                        Ok(progress) => progress,
+                        Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                            warn!("Skipped {} plugin progress updates", skipped);
+                            continue;
+                        }
+                        Err(tokio::sync::broadcast::error::RecvError::Closed) => return,
+                    };
+                    
+                    if throttle.should_notify(&progress, Instant::now()) {
+                        if let Err(e) = context.show_notification(&progress.label, &progress.summary()) {
+                            warn!("Failed to show task notification: {}", e);
+                        }
+                    }
+                }
+            }
+        });
+    }
+    
+    // Cancels plugin tasks and lets every loaded plugin clean up
+    pub async fn shutdown(&self) {
+        self.context.tasks().cancel_all();
+        
+        for plugin in self.plugins.read().await.iter() {
+            if let Err(e) = plugin.shutdown().await {
+                warn!("Plugin '{}' failed to shut down cleanly: {}", plugin.name(), e);
+            }
+        }
+    }
+    
+    // Shuts every plugin down and loads them again, picking up changed settings and toggles
+    pub async fn reload_plugins(&self) -> std::result::Result<(), PluginError> {
+        self.shutdown().await;
+        let mut plugins = self.plugins.write().await;
+        plugins.clear();
+        self.compile_routes(&plugins);
+        drop(plugins);
+        self.load_plugins().await
+    }
+    
+    async fn plugin_for(&self, result: &SearchResult) -> Option<Arc<dyn Plugin>> {
+        let Category::Plugin(ref name) = result.category else {
+            return None;
+        };
+        
+        self.plugins.read().await
+            .iter()
+            .find(|plugin| plugin.name() == name)
+            .cloned()
+    }
+}
+
+#[async_trait]
+impl SearchSource for PluginSystem {
+    fn name(&self) -> &str {
+        "Plugins"
+    }
+    
+    async fn search(&self, query: &str) -> Vec<SearchResult> {
+        self.search_all(query).await.unwrap_or_else(|e| {
+            warn!("Plugin search failed: {}", e);
+            Vec::new()
+        })
+    }
+}
+
+#[async_trait]
+impl ResultRefresher for PluginSystem {
+    fn refresh_interval(&self, result: &SearchResult) -> Option<Duration> {
+        let Category::Plugin(ref name) = result.category else {
+            return None;
+        };
+        
+        // Called on every refresh tick; skip the check rather than wait on a writer
+        let plugins = self.plugins.try_read().ok()?;
+        plugins.iter()
+            .find(|plugin| plugin.name() == name)
+            .and_then(|plugin| plugin.refresh_interval())
+    }
+    
+    async fn refresh(&self, result: &SearchResult) -> Option<SearchResult> {
+        let plugin = self.plugin_for(result).await?;
+        
+        match tokio::time::timeout(Self::PLUGIN_TIMEOUT, plugin.refresh(result)).await {
+            Ok(Ok(Some(mut refreshed))) => {
+                if let Some(icon) = self.icon_path(plugin.as_ref()) {
+                    attach_plugin_icon(std::slice::from_mut(&mut refreshed), &icon);
+                }
+                Some(refreshed)
+            }
+            Ok(Ok(None)) => None,
+            Ok(Err(e)) => {
+                warn!("Plugin '{}' refresh failed: {}", plugin.name(), e);
+                None
+            }
+            Err(_) => {
+                warn!("Plugin '{}' refresh timed out", plugin.name());
+                None
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl PluginActionHandler for PluginSystem {
+    async fn execute_plugin_action(
+        &self,
+        plugin_id: &str,
+        action: &Action,
+    ) -> std::result::Result<(), ActionError> {
+        let result = SearchResult::new(plugin_id, "")
+            .with_action(action.clone())
+            .with_category(Category::Plugin(plugin_id.to_string()));
+        
+        self.execute_plugin_action(plugin_id, &result).await
+            .map_err(|e| ActionError::Other(e.to_string()))
+    }
+}
+
+impl BackgroundTasks for PluginSystem {
+    fn task_summaries(&self) -> Vec<String> {
+        self.running_tasks().iter().map(|task| task.summary()).collect()
+    }
+}
+
+#[async_trait]
+impl SyntaxHintProvider for PluginSystem {
+    // Only loaded plugins are registered, so disabled plugins contribute no hints. Plugins that
+    // list no syntax are described by their triggers
+    async fn syntax_hints(&self) -> Vec<HintGroup> {
+        self.plugins.read().await
+            .iter()
+            .map(|plugin| {
+                let hints = match plugin.syntax_hints() {
+                    hints if hints.is_empty() => trigger_hints(&plugin.triggers(), plugin.description()),
+                    hints => hints,
+                };
+                HintGroup::new(plugin.name(), hints)
+            })
+            .collect()
+    }
 }
 
 // Built-in Calculator Plugin
@@ -264,11 +957,25 @@ impl Plugin for CalculatorPlugin {
         query.chars().any(|c| c.is_ascii_digit())
     }
     
+    fn syntax_hints(&self) -> Vec<SyntaxHint> {
+        vec![SyntaxHint::new("<expression>", tr("hint.calculator", "Evaluate an arithmetic expression"), "12+30")]
+    }
+    
+    fn icon(&self) -> Option<PluginIcon> {
+        Some(PluginIcon::Glyph("calculator"))
+    }
+    
+    // Numbers joined by operators, optionally parenthesized, so "file2.txt" no longer reaches the calculator
+    fn triggers(&self) -> Vec<Trigger> {
+        vec![Trigger::regex(arithmetic_pattern())]
+    }
+    
     async fn search(&self, query: &str) -> std::result::Result<Vec<SearchResult>, PluginError> {
         match self.evaluate_expression(query) {
             Ok(result) => {
+                // The title is formatted for the locale; the copied value stays plain so it pastes anywhere
                 let search_result = SearchResult::new(
-                    format!("{} = {}", query, result),
+                    format!("{} = {}", query, format_number(result, current_language())),
                     "Mathematical calculation"
                 )
                 .with_action(Action::CopyToClipboard(result.to_string()))
@@ -285,6 +992,7 @@ impl Plugin for CalculatorPlugin {
         use falcommand_config::Action;
         
         if let Action::CopyToClipboard(ref text) = result.action {
+            self.context.copy_to_clipboard(text)?;
             self.context.show_notification("Calculator", "Result copied to clipboard")?;
             info!("Calculator result copied: {}", text);
         }
@@ -293,15 +1001,63 @@ impl Plugin for CalculatorPlugin {
 }
 
 // Built-in Translator Plugin
+// plugin_settings.translator; the API key is kept in the secret store
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct TranslatorSettings {
+    // A LibreTranslate-compatible /translate endpoint; without one, no request is made
+    pub endpoint: Option<String>,
+    pub target_language: String,
+}
+
+impl Default for TranslatorSettings {
+    fn default() -> Self {
+        Self { endpoint: None, target_language: "en".to_string() }
+    }
+}
+
 #[derive(Debug)]
 pub struct TranslatorPlugin {
     context: PluginContext,
 }
 
 impl TranslatorPlugin {
+    const ID: &'static str = "translator";
+    const TARGET_LANGUAGES: [&'static str; 8] = ["en", "ja", "zh", "ko", "de", "fr", "es", "pt"];
+    
     pub fn new(context: PluginContext) -> Self {
         Self { context }
     }
+    
+    async fn settings(&self) -> TranslatorSettings {
+        self.context.plugin_settings(Self::ID).await
+            .and_then(|settings| serde_json::from_value(settings).ok())
+            .unwrap_or_default()
+    }
+    
+    async fn translate(&self, text: &str) -> std::result::Result<String, PluginError> {
+        let settings = self.settings().await;
+        let Some(endpoint) = settings.endpoint else {
+            // Placeholder translation until an endpoint is configured
+            return Ok(format!("Translation of: {}", text));
+        };
+        
+        let mut request = serde_json::json!({
+            "q": text,
+            "source": "auto",
+            "target": settings.target_language,
+        });
+        if let Some(api_key) = self.context.plugin_secret(Self::ID, "api_key").await {
+            request["api_key"] = serde_json::Value::String(api_key);
+        }
+        let response = self.context.http_post(&endpoint, &request.to_string()).await?;
+        let response: serde_json::Value = serde_json::from_str(&response)
+            .map_err(|e| PluginError::SearchError(format!("Unexpected translation response: {}", e)))?;
+        response.get("translatedText")
+            .and_then(|text| text.as_str())
+            .map(str::to_string)
+            .ok_or_else(|| PluginError::SearchError("The translation response has no translatedText".to_string()))
+    }
 }
 
 #[async_trait]
@@ -322,6 +1078,40 @@ impl Plugin for TranslatorPlugin {
         query.starts_with("translate ") || query.starts_with("翻訳 ")
     }
     
+    fn trigger_prefix(&self) -> Option<&str> {
+        Some("translate ")
+    }
+    
+    fn syntax_hints(&self) -> Vec<SyntaxHint> {
+        let description = tr("hint.translator", "Translate text");
+        vec![
+            SyntaxHint::new("translate <text>", description.clone(), "translate good morning"),
+            SyntaxHint::new("翻訳 <text>", description, "翻訳 おはよう"),
+        ]
+    }
+    
+    fn icon(&self) -> Option<PluginIcon> {
+        Some(PluginIcon::Glyph("translate"))
+    }
+    
+    fn triggers(&self) -> Vec<Trigger> {
+        vec![Trigger::prefix("translate "), Trigger::prefix("翻訳 ")]
+    }
+    
+    fn has_configuration(&self) -> bool {
+        true
+    }
+    
+    fn get_configuration_ui(&self) -> Option<serde_json::Value> {
+        Some(serde_json::json!({
+            "fields": [
+                { "key": "endpoint", "label": "Endpoint", "type": "string", "description": "LibreTranslate-compatible URL, e.g. https://libretranslate.com/translate" },
+                { "key": "target_language", "label": "Translate to", "type": "enum", "options": Self::TARGET_LANGUAGES, "default": "en" },
+                { "key": "api_key", "label": "API key", "type": "secret" },
+            ],
+        }))
+    }
+    
     async fn search(&self, query: &str) -> std::result::Result<Vec<SearchResult>, PluginError> {
         let text = if let Some(text) = query.strip_prefix("translate ") {
             text
@@ -331,8 +1121,7 @@ impl Plugin for TranslatorPlugin {
             return Ok(vec![]);
         };
         
-        // Placeholder translation (in real implementation, this would call translation API)
-        let translated = format!("Translation of: {}", text);
+        let translated = self.translate(text).await?;
         
         let search_result = SearchResult::new(
             format!("Translation: {}", text),
@@ -349,9 +1138,489 @@ impl Plugin for TranslatorPlugin {
         use falcommand_config::Action;
         
         if let Action::CopyToClipboard(ref text) = result.action {
+            self.context.copy_to_clipboard(text)?;
             self.context.show_notification("Translator", "Translation copied to clipboard")?;
             info!("Translation copied: {}", text);
         }
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+// Built-in Timer Plugin
+#[derive(Debug)]
+struct PendingTimer {
+    id: u64,
+    task_id: Option<u64>,
+    label: String,
+    due: Instant,
+}
+
+#[derive(Debug)]
+pub struct TimerPlugin {
+    context: PluginContext,
+    timers: Arc<std::sync::Mutex<Vec<PendingTimer>>>,
+    next_id: AtomicU64,
+}
+
+impl TimerPlugin {
+    const KEYWORD: &'static str = "timer";
+    
+    pub fn new(context: PluginContext) -> Self {
+        Self {
+            context,
+            timers: Arc::new(std::sync::Mutex::new(Vec::new())),
+            next_id: AtomicU64::new(1),
+        }
+    }
+    
+    // Parses "90s", "5m", "1h30m" or a bare number of minutes
+    fn parse_duration(text: &str) -> Option<Duration> {
+        let text = text.trim();
+        if let Ok(minutes) = text.parse::<u64>() {
+            return Some(Duration::from_secs(minutes * 60));
+        }
+        
+        let mut total = 0u64;
+        let mut number = String::new();
+        for c in text.chars() {
+            if c.is_ascii_digit() {
+                number.push(c);
+                continue;
+            }
+            let value: u64 = number.parse().ok()?;
+            number.clear();
+            total += match c {
+                'h' => value * 3600,
+                'm' => value * 60,
+                's' => value,
+                _ => return None,
+            };
+        }
+        
+        (number.is_empty() && total > 0).then(|| Duration::from_secs(total))
+    }
+    
+    fn format_remaining(remaining: Duration) -> String {
+        let secs = remaining.as_secs();
+        if secs >= 3600 {
+            format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+        } else {
+            format!("{}:{:02}", secs / 60, secs % 60)
+        }
+    }
+    
+    fn pending_result(timer: &PendingTimer) -> SearchResult {
+        let remaining = timer.due.saturating_duration_since(Instant::now());
+        let description = if remaining.is_zero() {
+            "Finished".to_string()
+        } else {
+            format!("{} left · select to cancel", Self::format_remaining(remaining))
+        };
+        
+        SearchResult::new(format!("Timer {}", timer.label), description)
+            .with_action(Action::PluginAction {
+                plugin_id: "Timer".to_string(),
+                action_data: serde_json::json!({ "cancel": timer.id }),
+            })
+            .with_category(Category::Plugin("Timer".to_string()))
+            .with_score(0.9)
+    }
+    
+    fn start_timer(&self, label: String, duration: Duration) {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        self.timers.lock().unwrap().push(PendingTimer {
+            id,
+            task_id: None,
+            label: label.clone(),
+            due: Instant::now() + duration,
+        });
+        info!("Started timer {} ({})", id, label);
+        
+        // The tracked task's final notification announces the finished or cancelled timer
+        let timers = self.timers.clone();
+        let task_id = self.context.spawn_tracked(&format!("{} timer", label), async move {
+            tokio::time::sleep(duration).await;
+            timers.lock().unwrap().retain(|t| t.id != id);
+            Ok(())
+        });
+        
+        if let Some(timer) = self.timers.lock().unwrap().iter_mut().find(|t| t.id == id) {
+            timer.task_id = Some(task_id);
+        }
+    }
+}
+
+#[async_trait]
+impl Plugin for TimerPlugin {
+    fn name(&self) -> &str {
+        "Timer"
+    }
+    
+    fn version(&self) -> &str {
+        "1.0.0"
+    }
+    
+    fn description(&self) -> &str {
+        "Countdown timers with live remaining time"
+    }
+    
+    fn can_handle(&self, query: &str) -> bool {
+        let query = query.trim();
+        query == Self::KEYWORD || query.starts_with("timer ")
+    }
+    
+    fn trigger_prefix(&self) -> Option<&str> {
+        Some("timer ")
+    }
+    
+    async fn search(&self, query: &str) -> std::result::Result<Vec<SearchResult>, PluginError> {
+        let mut results = Vec::new();
+        
+        let argument = query.trim().strip_prefix(Self::KEYWORD).unwrap_or_default().trim();
+        if let Some(duration) = Self::parse_duration(argument) {
+            results.push(
+                SearchResult::new(format!("Start a {} timer", argument), "Notifies when the time is up")
+                    .with_action(Action::PluginAction {
+                        plugin_id: "Timer".to_string(),
+                        action_data: serde_json::json!({ "start": argument, "seconds": duration.as_secs() }),
+                    })
+                    .with_category(Category::Plugin("Timer".to_string()))
+                    .with_score(1.0)
+            );
+        }
+        
+        let timers = self.timers.lock().unwrap();
+        results.extend(timers.iter().map(Self::pending_result));
+        
+        Ok(results)
+    }
+    
+    async fn execute(&self, result: &SearchResult) -> std::result::Result<(), PluginError> {
+        let Action::PluginAction { ref action_data, .. } = result.action else {
+            return Ok(());
+        };
+        
+        if let Some(id) = action_data.get("cancel").and_then(|v| v.as_u64()) {
+            let task_id = {
+                let mut timers = self.timers.lock().unwrap();
+                let task_id = timers.iter().find(|t| t.id == id).and_then(|t| t.task_id);
+                timers.retain(|t| t.id != id);
+                task_id
+            };
+            if let Some(task_id) = task_id {
+                self.context.tasks().cancel(task_id);
+            }
+            info!("Cancelled timer {}", id);
+        } else if let Some(seconds) = action_data.get("seconds").and_then(|v| v.as_u64()) {
+            let label = action_data.get("start")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string();
+            self.start_timer(label, Duration::from_secs(seconds));
+        }
+        Ok(())
+    }
+    
+    fn syntax_hints(&self) -> Vec<SyntaxHint> {
+        vec![SyntaxHint::new("timer <duration>", tr("hint.timer", "Start a countdown timer"), "timer 5m")]
+    }
+    
+    fn icon(&self) -> Option<PluginIcon> {
+        Some(PluginIcon::Glyph("timer"))
+    }
+    
+    fn triggers(&self) -> Vec<Trigger> {
+        vec![Trigger::keyword(Self::KEYWORD)]
+    }
+    
+    fn refresh_interval(&self) -> Option<Duration> {
+        Some(Duration::from_secs(1))
+    }
+    
+    async fn refresh(&self, result: &SearchResult) -> std::result::Result<Option<SearchResult>, PluginError> {
+        let Action::PluginAction { ref action_data, .. } = result.action else {
+            return Ok(None);
+        };
+        let Some(id) = action_data.get("cancel").and_then(|v| v.as_u64()) else {
+            return Ok(None);
+        };
+        
+        let timers = self.timers.lock().unwrap();
+        let refreshed = match timers.iter().find(|t| t.id == id) {
+            Some(timer) => Self::pending_result(timer),
+            None => {
+                let mut finished = result.clone();
+                finished.description = "Finished".to_string();
+                finished
+            }
+        };
+        
+        Ok(Some(refreshed))
+    }
+}
+#[cfg(test)]
+mod tests {
+    use falcommand_core::HotkeyIntent;
+    use super::*;
+
+    // PluginSystem is also a SearchSource with its own syntax_hints
+    async fn hint_groups(system: &PluginSystem) -> Vec<HintGroup> {
+        SyntaxHintProvider::syntax_hints(system).await
+    }
+
+    fn group_names(groups: &[HintGroup]) -> Vec<&str> {
+        groups.iter().map(|group| group.source.as_str()).collect()
+    }
+    
+    // Searches wait for `release` after announcing themselves on `started`; counts shutdowns
+    #[derive(Debug, Default)]
+    struct GatedPlugin {
+        started: tokio::sync::Notify,
+        release: tokio::sync::Notify,
+        shutdowns: AtomicU64,
+    }
+    
+    #[async_trait]
+    impl Plugin for GatedPlugin {
+        fn name(&self) -> &str {
+            "Gated"
+        }
+        
+        fn version(&self) -> &str {
+            "1.0.0"
+        }
+        
+        fn description(&self) -> &str {
+            "Answers once released"
+        }
+        
+        async fn shutdown(&self) -> std::result::Result<(), PluginError> {
+            self.shutdowns.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+        
+        fn can_handle(&self, _query: &str) -> bool {
+            true
+        }
+        
+        async fn search(&self, query: &str) -> std::result::Result<Vec<SearchResult>, PluginError> {
+            self.started.notify_one();
+            self.release.notified().await;
+            Ok(vec![SearchResult::new(query, "gated")])
+        }
+        
+        async fn execute(&self, _result: &SearchResult) -> std::result::Result<(), PluginError> {
+            Ok(())
+        }
+    }
+    
+    #[tokio::test]
+    async fn toggling_keeps_the_config_lists_disjoint() {
+        let config = Arc::new(RwLock::new(Config::default()));
+        let system = PluginSystem::new(config.clone()).await.unwrap();
+        
+        for enabled in [true, false, true, true] {
+            system.set_plugin_enabled("Timer", enabled).await.unwrap();
+        }
+        let plugins = config.read().await.plugins.clone();
+        assert_eq!(plugins.enabled.iter().filter(|name| *name == "timer").count(), 1);
+        assert!(!plugins.disabled.contains(&"timer".to_string()));
+        assert!(system.plugin_descriptors().await.iter().any(|plugin| plugin.id == "timer" && plugin.enabled));
+        
+        system.set_plugin_enabled("timer", false).await.unwrap();
+        let plugins = config.read().await.plugins.clone();
+        assert!(!plugins.enabled.contains(&"timer".to_string()));
+        assert_eq!(plugins.disabled.iter().filter(|name| *name == "timer").count(), 1);
+        assert!(system.plugin_descriptors().await.iter().any(|plugin| plugin.id == "timer" && !plugin.enabled));
+        
+        // Unknown plugins can't be turned on and leave the config alone
+        assert!(system.set_plugin_enabled("nonexistent", true).await.is_err());
+        assert!(!config.read().await.plugins.enabled.contains(&"nonexistent".to_string()));
+    }
+    
+    // A plugin disabled while its search runs is shut down, and what it returns late is dropped
+    #[tokio::test]
+    async fn results_of_a_plugin_disabled_mid_search_are_dropped() {
+        let system = Arc::new(PluginSystem::new(Arc::new(RwLock::new(Config::default()))).await.unwrap());
+        let plugin = Arc::new(GatedPlugin::default());
+        system.register_plugin(plugin.clone()).await;
+        
+        let search = tokio::spawn({
+            let system = system.clone();
+            async move { system.search_all("query").await.unwrap() }
+        });
+        plugin.started.notified().await;
+        system.set_plugin_enabled("gated", false).await.unwrap();
+        assert_eq!(plugin.shutdowns.load(Ordering::SeqCst), 1);
+        plugin.release.notify_one();
+        
+        assert!(search.await.unwrap().is_empty());
+        assert!(system.search_all("query").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn plugin_hotkeys_prefill_the_loaded_plugins_prefix() {
+        let system = PluginSystem::new(Arc::new(RwLock::new(Config::default()))).await.unwrap();
+        system.set_plugin_enabled("timer", true).await.unwrap();
+        system.set_plugin_enabled("translator", false).await.unwrap();
+        let prefixes = system.trigger_prefixes().await;
+
+        assert_eq!(falcommand_core::resolve_intent("timer", &prefixes), Some(HotkeyIntent::Prefill("timer ".to_string())));
+        assert_eq!(falcommand_core::resolve_intent("translator", &prefixes), None);
+        // Loaded plugins without a prefix can't be targeted either
+        assert_eq!(falcommand_core::resolve_intent("calculator", &prefixes), None);
+    }
+
+    #[tokio::test]
+    async fn disabled_plugins_contribute_no_hints() {
+        let system = PluginSystem::new(Arc::new(RwLock::new(Config::default()))).await.unwrap();
+        system.set_plugin_enabled("calculator", true).await.unwrap();
+        system.set_plugin_enabled("translator", true).await.unwrap();
+        assert_eq!(group_names(&hint_groups(&system).await), ["Calculator", "Translator"]);
+
+        system.set_plugin_enabled("Calculator", false).await.unwrap();
+        let groups = hint_groups(&system).await;
+        assert_eq!(group_names(&groups), ["Translator"]);
+        assert!(groups[0].hints.iter().all(|hint| hint.pattern != "<expression>"));
+
+        system.set_plugin_enabled("calculator", true).await.unwrap();
+        assert_eq!(group_names(&hint_groups(&system).await), ["Translator", "Calculator"]);
+    }
+
+    // One result with its own icon and one without
+    #[derive(Debug)]
+    struct BrandedPlugin(PluginIcon);
+
+    #[async_trait]
+    impl Plugin for BrandedPlugin {
+        fn name(&self) -> &str {
+            "Branded"
+        }
+
+        fn version(&self) -> &str {
+            "1.0.0"
+        }
+
+        fn description(&self) -> &str {
+            "Brands its results"
+        }
+
+        fn can_handle(&self, _query: &str) -> bool {
+            true
+        }
+
+        async fn search(&self, _query: &str) -> std::result::Result<Vec<SearchResult>, PluginError> {
+            let mut own = SearchResult::new("Own icon", "branded");
+            own.icon = Some(std::path::PathBuf::from("/icons/own.png"));
+            Ok(vec![SearchResult::new("Plugin icon", "branded"), own])
+        }
+
+        async fn execute(&self, _result: &SearchResult) -> std::result::Result<(), PluginError> {
+            Ok(())
+        }
+
+        fn icon(&self) -> Option<PluginIcon> {
+            Some(self.0.clone())
+        }
+    }
+
+    fn icons(results: &[SearchResult]) -> Vec<Option<std::path::PathBuf>> {
+        results.iter().filter(|result| result.description == "branded").map(|result| result.icon.clone()).collect()
+    }
+
+    #[tokio::test]
+    async fn plugin_icons_fill_in_for_results_without_one() {
+        let dir = tempfile::tempdir().unwrap();
+        let system = PluginSystem::new(Arc::new(RwLock::new(Config::default()))).await.unwrap()
+            .with_icon_cache_dir(dir.path().to_path_buf());
+        let png = b"\x89PNG\r\n\x1a\n branded".to_vec();
+        system.register_plugin(Arc::new(BrandedPlugin(PluginIcon::Png(png.clone())))).await;
+
+        let results = system.search_all("query").await.unwrap();
+        let cached = dir.path().join("branded.png");
+        assert_eq!(icons(&results), [Some(cached.clone()), Some(std::path::PathBuf::from("/icons/own.png"))]);
+        assert_eq!(std::fs::read(cached).unwrap(), png);
+    }
+
+    // A glyph has no file to point at, and without a cache directory nothing is written
+    #[tokio::test]
+    async fn glyphs_and_uncached_icons_leave_results_alone() {
+        let system = PluginSystem::new(Arc::new(RwLock::new(Config::default()))).await.unwrap();
+        system.register_plugin(Arc::new(BrandedPlugin(PluginIcon::Png(b"\x89PNG".to_vec())))).await;
+        let results = system.search_all("query").await.unwrap();
+        assert_eq!(icons(&results), [None, Some(std::path::PathBuf::from("/icons/own.png"))]);
+
+        let dir = tempfile::tempdir().unwrap();
+        let system = PluginSystem::new(Arc::new(RwLock::new(Config::default()))).await.unwrap()
+            .with_icon_cache_dir(dir.path().to_path_buf());
+        system.register_plugin(Arc::new(BrandedPlugin(PluginIcon::Glyph("calculator")))).await;
+        let results = system.search_all("query").await.unwrap();
+        assert_eq!(icons(&results)[0], None);
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    // Answers every query it is asked about with one result named after itself
+    #[derive(Debug)]
+    struct RoutedPlugin {
+        name: &'static str,
+        triggers: Vec<Trigger>,
+    }
+
+    #[async_trait]
+    impl Plugin for RoutedPlugin {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn version(&self) -> &str {
+            "1.0.0"
+        }
+
+        fn description(&self) -> &str {
+            "Routed"
+        }
+
+        fn can_handle(&self, _query: &str) -> bool {
+            true
+        }
+
+        async fn search(&self, _query: &str) -> std::result::Result<Vec<SearchResult>, PluginError> {
+            Ok(vec![SearchResult::new(self.name, "routed")])
+        }
+
+        async fn execute(&self, _result: &SearchResult) -> std::result::Result<(), PluginError> {
+            Ok(())
+        }
+
+        fn triggers(&self) -> Vec<Trigger> {
+            self.triggers.clone()
+        }
+    }
+
+    #[tokio::test]
+    async fn fallback_plugins_answer_what_no_trigger_claimed() {
+        let system = PluginSystem::new(Arc::new(RwLock::new(Config::default()))).await.unwrap();
+        system.register_plugin(Arc::new(RoutedPlugin { name: "Web", triggers: vec![Trigger::Fallback] })).await;
+        system.register_plugin(Arc::new(RoutedPlugin { name: "Emoji", triggers: vec![Trigger::keyword("emoji")] })).await;
+        system.register_plugin(Arc::new(RoutedPlugin { name: "Hex", triggers: vec![Trigger::regex("0x[0-9a-f]+")] })).await;
+        let answered = |results: Vec<SearchResult>| -> Vec<String> {
+            results.into_iter().filter(|result| result.description == "routed").map(|result| result.title).collect()
+        };
+
+        assert_eq!(answered(system.search_all("emoji smile").await.unwrap()), ["Emoji"]);
+        assert_eq!(answered(system.search_all("zebra crossing").await.unwrap()), ["Web"]);
+        assert_eq!(answered(system.search_all("0xff").await.unwrap()), ["Hex"]);
+    }
+
+    // The calculator's arithmetic regex fits within the trigger limits and skips file names
+    #[test]
+    fn calculator_routes_only_arithmetic() {
+        let table = RoutingTable::compile([("calculator".to_string(), vec![Trigger::regex(arithmetic_pattern())])]);
+        for query in ["12+30", "(2.5 * 4) / 2", " 7 - 3 "] {
+            assert!(table.route(query).contains("calculator"), "{}", query);
+        }
+        for query in ["file2.txt", "2 apples", "firefox"] {
+            assert!(!table.route(query).contains("calculator"), "{}", query);
+        }
+    }
+}
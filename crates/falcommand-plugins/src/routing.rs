@@ -0,0 +1,249 @@
+use std::collections::{HashMap, HashSet};
+use regex::{Regex, RegexBuilder};
+use log::warn;
+
+use falcommand_config::SyntaxHint;
+
+// Queries a plugin declares it answers, so only plugins that can answer are asked
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Trigger {
+    // The query starts with it, e.g. "translate "; case-insensitive
+    Prefix(String),
+    // The query's first word is it, e.g. "ip"; case-insensitive
+    Keyword(String),
+    // The whole query matches it; checked against queries of at most MAX_REGEX_QUERY_LEN bytes
+    Regex(String),
+    // Asked only when no plugin's trigger matched
+    Fallback,
+}
+
+impl Trigger {
+    pub fn prefix(prefix: impl Into<String>) -> Self {
+        Trigger::Prefix(prefix.into())
+    }
+
+    pub fn keyword(keyword: impl Into<String>) -> Self {
+        Trigger::Keyword(keyword.into())
+    }
+
+    pub fn regex(pattern: impl Into<String>) -> Self {
+        Trigger::Regex(pattern.into())
+    }
+}
+
+// Limits on regex triggers. The regex crate matches in linear time, so bounding the compiled
+// program and the input bounds the time a trigger takes
+pub const MAX_REGEX_PATTERN_LEN: usize = 256;
+pub const MAX_REGEX_PROGRAM_SIZE: usize = 64 * 1024;
+pub const MAX_REGEX_QUERY_LEN: usize = 512;
+
+pub fn compile_trigger_regex(pattern: &str) -> Result<Regex, String> {
+    if pattern.len() > MAX_REGEX_PATTERN_LEN {
+        return Err(format!("pattern is longer than {} bytes", MAX_REGEX_PATTERN_LEN));
+    }
+    // Anchored so the pattern has to describe the whole query
+    RegexBuilder::new(&format!("^(?:{})$", pattern))
+        .case_insensitive(true)
+        .size_limit(MAX_REGEX_PROGRAM_SIZE)
+        .dfa_size_limit(MAX_REGEX_PROGRAM_SIZE)
+        .build()
+        .map_err(|e| e.to_string())
+}
+
+#[derive(Debug, Default)]
+struct TrieNode {
+    children: HashMap<char, TrieNode>,
+    // Plugins whose prefix ends here
+    plugins: Vec<String>,
+}
+
+// Prefixes by character, so one walk along the query finds every matching prefix
+#[derive(Debug, Default)]
+pub struct PrefixTrie {
+    root: TrieNode,
+}
+
+impl PrefixTrie {
+    pub fn insert(&mut self, prefix: &str, plugin_id: &str) {
+        let node = prefix.chars()
+            .flat_map(char::to_lowercase)
+            .fold(&mut self.root, |node, c| node.children.entry(c).or_default());
+        node.plugins.push(plugin_id.to_string());
+    }
+
+    // Plugins with a prefix the query starts with, shortest prefix first
+    pub fn matches(&self, query: &str) -> Vec<&str> {
+        let mut found: Vec<&str> = self.root.plugins.iter().map(String::as_str).collect();
+        let mut node = &self.root;
+        for c in query.chars().flat_map(char::to_lowercase) {
+            let Some(next) = node.children.get(&c) else {
+                break;
+            };
+            node = next;
+            found.extend(node.plugins.iter().map(String::as_str));
+        }
+        found
+    }
+}
+
+// Every plugin's triggers compiled together, evaluated once per query
+#[derive(Debug, Default)]
+pub struct RoutingTable {
+    prefixes: PrefixTrie,
+    keywords: HashMap<String, Vec<String>>,
+    regexes: Vec<(Regex, String)>,
+    fallbacks: Vec<String>,
+    // Plugins without triggers are asked for every query, as before triggers existed
+    unrouted: HashSet<String>,
+}
+
+impl RoutingTable {
+    // From each plugin's id and declared triggers. A regex that doesn't compile is skipped
+    pub fn compile(plugins: impl IntoIterator<Item = (String, Vec<Trigger>)>) -> Self {
+        let mut table = Self::default();
+        for (plugin_id, triggers) in plugins {
+            if triggers.is_empty() {
+                table.unrouted.insert(plugin_id);
+                continue;
+            }
+            for trigger in triggers {
+                match trigger {
+                    Trigger::Prefix(prefix) => table.prefixes.insert(&prefix, &plugin_id),
+                    Trigger::Keyword(keyword) => {
+                        table.keywords.entry(keyword.to_lowercase()).or_default().push(plugin_id.clone());
+                    }
+                    Trigger::Regex(pattern) => match compile_trigger_regex(&pattern) {
+                        Ok(regex) => table.regexes.push((regex, plugin_id.clone())),
+                        Err(e) => warn!("Ignoring trigger /{}/ of plugin '{}': {}", pattern, plugin_id, e),
+                    },
+                    Trigger::Fallback => table.fallbacks.push(plugin_id.clone()),
+                }
+            }
+        }
+        table
+    }
+
+    // Ids of the plugins to ask about `query`
+    pub fn route(&self, query: &str) -> HashSet<String> {
+        let query = query.trim_start();
+        let mut matched: HashSet<String> = self.prefixes.matches(query).into_iter().map(str::to_string).collect();
+
+        let first_word = query.split_whitespace().next().unwrap_or_default().to_lowercase();
+        if let Some(plugins) = self.keywords.get(&first_word) {
+            matched.extend(plugins.iter().cloned());
+        }
+
+        let trimmed = query.trim_end();
+        if trimmed.len() <= MAX_REGEX_QUERY_LEN {
+            matched.extend(self.regexes.iter()
+                .filter(|(regex, _)| regex.is_match(trimmed))
+                .map(|(_, plugin_id)| plugin_id.clone()));
+        }
+
+        if matched.is_empty() {
+            matched.extend(self.fallbacks.iter().cloned());
+        }
+        matched.extend(self.unrouted.iter().cloned());
+        matched
+    }
+}
+
+// Help entries for a plugin that lists no syntax of its own, from what its triggers accept
+pub fn trigger_hints(triggers: &[Trigger], description: &str) -> Vec<SyntaxHint> {
+    triggers.iter()
+        .filter_map(|trigger| match trigger {
+            Trigger::Prefix(prefix) => Some(SyntaxHint::new(format!("{}<text>", prefix), description, prefix.trim_end())),
+            Trigger::Keyword(keyword) => Some(SyntaxHint::new(keyword.clone(), description, keyword.clone())),
+            Trigger::Regex(_) | Trigger::Fallback => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn routed(table: &RoutingTable, query: &str) -> Vec<String> {
+        let mut plugins: Vec<String> = table.route(query).into_iter().collect();
+        plugins.sort();
+        plugins
+    }
+
+    // Every prefix along the query matches, whatever the case, shortest first
+    #[test]
+    fn trie_matches_each_prefix_of_the_query() {
+        let mut trie = PrefixTrie::default();
+        trie.insert("t", "short");
+        trie.insert("Translate ", "translator");
+        trie.insert("翻訳 ", "translator");
+        trie.insert("tr", "tracker");
+
+        assert_eq!(trie.matches("TRANSLATE hello"), ["short", "tracker", "translator"]);
+        assert_eq!(trie.matches("translate"), ["short", "tracker"]);
+        assert_eq!(trie.matches("翻訳 こんにちは"), ["translator"]);
+        assert_eq!(trie.matches("timer 5m"), ["short"]);
+        assert!(trie.matches("x").is_empty());
+        assert!(trie.matches("").is_empty());
+    }
+
+    #[test]
+    fn keywords_match_the_first_word() {
+        let table = RoutingTable::compile([("network".to_string(), vec![Trigger::keyword("ip")])]);
+        assert_eq!(routed(&table, "IP"), ["network"]);
+        assert_eq!(routed(&table, "  ip addresses"), ["network"]);
+        assert!(routed(&table, "ipconfig").is_empty());
+        assert!(routed(&table, "my ip").is_empty());
+    }
+
+    // The whole query has to match, in any case
+    #[test]
+    fn regex_triggers_are_anchored() {
+        let table = RoutingTable::compile([("hex".to_string(), vec![Trigger::regex("0x[0-9a-f]+")])]);
+        assert_eq!(routed(&table, "0xFF"), ["hex"]);
+        assert_eq!(routed(&table, "0xff "), ["hex"]);
+        assert!(routed(&table, "0xff and more").is_empty());
+        assert!(routed(&table, "value 0xff").is_empty());
+    }
+
+    #[test]
+    fn regex_triggers_are_limited() {
+        let long = "a".repeat(MAX_REGEX_PATTERN_LEN + 1);
+        assert!(compile_trigger_regex(&long).unwrap_err().contains("longer than 256 bytes"));
+        // Short to write but far too big once compiled
+        assert!(compile_trigger_regex(r"\w{1000}").is_err());
+        assert!(compile_trigger_regex("(unclosed").is_err());
+
+        // A pattern that fails is skipped; the plugin's other triggers still work
+        let table = RoutingTable::compile([("broken".to_string(), vec![Trigger::regex(r"\w{1000}"), Trigger::keyword("ok")])]);
+        assert_eq!(routed(&table, "ok"), ["broken"]);
+
+        // Overlong queries never reach a regex
+        let table = RoutingTable::compile([("any".to_string(), vec![Trigger::regex("a+")])]);
+        assert_eq!(routed(&table, &"a".repeat(MAX_REGEX_QUERY_LEN)), ["any"]);
+        assert!(routed(&table, &"a".repeat(MAX_REGEX_QUERY_LEN + 1)).is_empty());
+    }
+
+    // A Fallback-only plugin sees exactly the queries nothing else claimed; one without triggers sees all
+    #[test]
+    fn fallbacks_get_unmatched_queries() {
+        let table = RoutingTable::compile([
+            ("translator".to_string(), vec![Trigger::prefix("translate ")]),
+            ("web".to_string(), vec![Trigger::Fallback]),
+            ("legacy".to_string(), Vec::new()),
+        ]);
+        assert_eq!(routed(&table, "translate hello"), ["legacy", "translator"]);
+        assert_eq!(routed(&table, "weather tokyo"), ["legacy", "web"]);
+        assert_eq!(routed(&table, ""), ["legacy", "web"]);
+    }
+
+    #[test]
+    fn hints_come_from_prefixes_and_keywords() {
+        let hints = trigger_hints(
+            &[Trigger::prefix("translate "), Trigger::keyword("emoji"), Trigger::regex("x+"), Trigger::Fallback],
+            "Does things",
+        );
+        let patterns: Vec<&str> = hints.iter().map(|hint| hint.pattern.as_str()).collect();
+        assert_eq!(patterns, ["translate <text>", "emoji"]);
+        assert_eq!(hints[0].example, "translate");
+    }
+}
@@ -0,0 +1,404 @@
+use std::collections::HashSet;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+// What get_configuration_ui returns: the fields of a plugin's settings form, in display order
+//
+// {"fields": [
+//     {"key": "endpoint", "label": "Endpoint", "type": "string", "default": "https://..."},
+//     {"key": "limit", "label": "Limit", "type": "number", "min": 1, "max": 100},
+//     {"key": "daily", "label": "Daily files", "type": "bool"},
+//     {"key": "target", "label": "Language", "type": "enum", "options": ["en", "ja"]},
+//     {"key": "api_key", "label": "API key", "type": "secret"}
+// ]}
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SettingsSchema {
+    pub fields: Vec<SettingsField>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SettingsField {
+    pub key: String,
+    pub label: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(flatten)]
+    pub kind: FieldKind,
+    // Shown when nothing is stored; secrets have none
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FieldKind {
+    String,
+    Number {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        min: Option<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max: Option<f64>,
+    },
+    Bool,
+    Enum { options: Vec<String> },
+    // Kept in the secret store under plugin_secret_key, never in the config file
+    Secret,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SettingsSchemaError {
+    #[error("Not a settings schema: {0}")]
+    Malformed(String),
+    #[error("Field '{0}' is declared more than once")]
+    DuplicateKey(String),
+    #[error("Field '{key}' is invalid: {reason}")]
+    InvalidField { key: String, reason: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SettingsValueError {
+    #[error("'{0}' is not a setting of this plugin")]
+    UnknownField(String),
+    #[error("{label} must be {expected}")]
+    WrongType { label: String, expected: &'static str },
+    #[error("{label} must be between {min} and {max}")]
+    OutOfRange { label: String, min: String, max: String },
+    #[error("{label} must be one of {options}")]
+    NotAnOption { label: String, options: String },
+}
+
+// Settings split by where they are stored
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ValidatedSettings {
+    // Written to plugins.plugin_settings.<id>
+    pub settings: Map<String, Value>,
+    // Secret fields to set; None deletes the stored secret
+    pub secrets: Vec<(String, Option<String>)>,
+}
+
+// Secret store key of a secret field: "plugin.translator.api_key"
+pub fn plugin_secret_key(plugin_id: &str, field: &str) -> String {
+    format!("plugin.{}.{}", plugin_id, field)
+}
+
+impl SettingsSchema {
+    pub fn parse(value: &Value) -> Result<Self, SettingsSchemaError> {
+        let schema: Self = serde_json::from_value(value.clone())
+            .map_err(|e| SettingsSchemaError::Malformed(e.to_string()))?;
+
+        let mut keys = HashSet::new();
+        for field in &schema.fields {
+            if !keys.insert(field.key.as_str()) {
+                return Err(SettingsSchemaError::DuplicateKey(field.key.clone()));
+            }
+            field.check().map_err(|reason| SettingsSchemaError::InvalidField { key: field.key.clone(), reason })?;
+        }
+        Ok(schema)
+    }
+
+    pub fn field(&self, key: &str) -> Option<&SettingsField> {
+        self.fields.iter().find(|field| field.key == key)
+    }
+
+    // What each field shows: the stored value, else the default. Secrets show nothing; the form
+    // only says whether one is set
+    pub fn current_values(&self, stored: Option<&Value>) -> Map<String, Value> {
+        self.fields.iter()
+            .filter(|field| field.kind != FieldKind::Secret)
+            .filter_map(|field| {
+                let value = stored.and_then(|stored| stored.get(&field.key)).or(field.default.as_ref())?;
+                Some((field.key.clone(), value.clone()))
+            })
+            .collect()
+    }
+
+    // Checks values edited in the form and splits the secrets off. Missing fields are left out,
+    // as are empty strings, so the plugin's own default applies; a missing secret stays as stored
+    // and a null one is deleted
+    pub fn validate(&self, values: &Map<String, Value>) -> Result<ValidatedSettings, SettingsValueError> {
+        let mut validated = ValidatedSettings::default();
+        for (key, value) in values {
+            let field = self.field(key).ok_or_else(|| SettingsValueError::UnknownField(key.clone()))?;
+            if field.kind == FieldKind::Secret {
+                let secret = match value {
+                    Value::Null => None,
+                    Value::String(text) => Some(text.clone()),
+                    _ => return Err(field.wrong_type("text")),
+                };
+                validated.secrets.push((key.clone(), secret));
+                continue;
+            }
+            if value.is_null() || value.as_str() == Some("") {
+                continue;
+            }
+            field.check_value(value)?;
+            validated.settings.insert(key.clone(), value.clone());
+        }
+        Ok(validated)
+    }
+
+    // Reads what was typed into each widget of the form, keyed by field. Every field is checked,
+    // so each error can be shown next to its own field; the values can then go to validate
+    pub fn parse_inputs(&self, inputs: &[(String, String)]) -> Result<Map<String, Value>, Vec<(String, SettingsValueError)>> {
+        let mut values = Map::new();
+        let mut errors = Vec::new();
+        for (key, input) in inputs {
+            let parsed = self.field(key)
+                .ok_or_else(|| SettingsValueError::UnknownField(key.clone()))
+                .and_then(|field| field.parse_input(input));
+            match parsed {
+                Ok(Some(value)) => {
+                    values.insert(key.clone(), value);
+                }
+                Ok(None) => {}
+                Err(e) => errors.push((key.clone(), e)),
+            }
+        }
+        if errors.is_empty() {
+            Ok(values)
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+impl SettingsField {
+    // The value of a widget's text: a checkbox gives "true"/"false", a dropdown its option. Empty
+    // input leaves the field out, so a blank password box keeps the stored secret
+    pub fn parse_input(&self, input: &str) -> Result<Option<Value>, SettingsValueError> {
+        let input = input.trim();
+        if input.is_empty() {
+            return Ok(None);
+        }
+        let value = match self.kind {
+            FieldKind::String | FieldKind::Secret | FieldKind::Enum { .. } => Value::String(input.to_string()),
+            FieldKind::Bool => match input {
+                "true" => Value::Bool(true),
+                "false" => Value::Bool(false),
+                _ => return Err(self.wrong_type("on or off")),
+            },
+            FieldKind::Number { .. } => {
+                let number: f64 = input.parse().map_err(|_| self.wrong_type("a number"))?;
+                // Whole numbers stay integers, as plugins read them with as_u64/as_i64
+                if number.fract() == 0.0 && number.abs() < i64::MAX as f64 {
+                    Value::from(number as i64)
+                } else {
+                    serde_json::Number::from_f64(number).map(Value::Number).ok_or_else(|| self.wrong_type("a number"))?
+                }
+            }
+        };
+        self.check_value(&value)?;
+        Ok(Some(value))
+    }
+
+    // A field a form can be built from
+    fn check(&self) -> Result<(), String> {
+        if self.key.is_empty() {
+            return Err("the key is empty".to_string());
+        }
+        match self.kind {
+            FieldKind::Number { min: Some(min), max: Some(max) } if min > max => {
+                return Err(format!("min {} is above max {}", min, max));
+            }
+            FieldKind::Enum { ref options } if options.is_empty() => return Err("an enum needs options".to_string()),
+            FieldKind::Secret if self.default.is_some() => return Err("a secret can't have a default".to_string()),
+            _ => {}
+        }
+        match self.default {
+            Some(ref default) => self.check_value(default).map_err(|e| format!("default: {}", e)),
+            None => Ok(()),
+        }
+    }
+
+    fn check_value(&self, value: &Value) -> Result<(), SettingsValueError> {
+        match self.kind {
+            FieldKind::String | FieldKind::Secret => value.as_str().map(|_| ()).ok_or_else(|| self.wrong_type("text")),
+            FieldKind::Bool => value.as_bool().map(|_| ()).ok_or_else(|| self.wrong_type("on or off")),
+            FieldKind::Number { min, max } => {
+                let number = value.as_f64().ok_or_else(|| self.wrong_type("a number"))?;
+                if min.is_some_and(|min| number < min) || max.is_some_and(|max| number > max) {
+                    let bound = |bound: Option<f64>| bound.map(|b| b.to_string()).unwrap_or_else(|| "any".to_string());
+                    return Err(SettingsValueError::OutOfRange { label: self.label.clone(), min: bound(min), max: bound(max) });
+                }
+                Ok(())
+            }
+            FieldKind::Enum { ref options } => match value.as_str() {
+                Some(choice) if options.iter().any(|option| option == choice) => Ok(()),
+                _ => Err(SettingsValueError::NotAnOption { label: self.label.clone(), options: options.join(", ") }),
+            },
+        }
+    }
+
+    fn wrong_type(&self, expected: &'static str) -> SettingsValueError {
+        SettingsValueError::WrongType { label: self.label.clone(), expected }
+    }
+}
+
+// How the settings window edits a plugin's settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SettingsForm {
+    // One widget per field: text box, number box, checkbox, dropdown, password box
+    Fields(SettingsSchema),
+    // The plugin's schema isn't one the form understands; its settings are edited as raw JSON
+    RawJson { reason: String },
+}
+
+impl SettingsForm {
+    pub fn from_configuration_ui(ui: &Value) -> Self {
+        match SettingsSchema::parse(ui) {
+            Ok(schema) => SettingsForm::Fields(schema),
+            Err(e) => SettingsForm::RawJson { reason: e.to_string() },
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use async_trait::async_trait;
+    use serde_json::json;
+    use tokio::sync::{Mutex, RwLock};
+
+    use falcommand_config::Config;
+    use falcommand_core::{SecretError, SecretStore};
+    use super::*;
+    use crate::plugins::PluginSystem;
+
+    // Stands in for the OS keyring, or for a store that doesn't encrypt
+    #[derive(Debug, Default)]
+    struct MemoryKeyring {
+        secrets: Mutex<HashMap<String, String>>,
+        plaintext: bool,
+    }
+
+    #[async_trait]
+    impl SecretStore for MemoryKeyring {
+        fn name(&self) -> &str {
+            "Memory keyring"
+        }
+
+        fn encrypts_at_rest(&self) -> bool {
+            !self.plaintext
+        }
+
+        async fn is_available(&self) -> bool {
+            true
+        }
+
+        async fn get(&self, key: &str) -> Result<Option<String>, SecretError> {
+            Ok(self.secrets.lock().await.get(key).cloned())
+        }
+
+        async fn set(&self, key: &str, value: &str) -> Result<(), SecretError> {
+            self.secrets.lock().await.insert(key.to_string(), value.to_string());
+            Ok(())
+        }
+
+        async fn delete(&self, key: &str) -> Result<(), SecretError> {
+            self.secrets.lock().await.remove(key);
+            Ok(())
+        }
+    }
+
+    fn values(value: Value) -> Map<String, Value> {
+        value.as_object().cloned().unwrap()
+    }
+
+    fn schema() -> SettingsSchema {
+        SettingsSchema::parse(&json!({"fields": [
+            {"key": "limit", "label": "Limit", "type": "number", "min": 1, "max": 100, "default": 10},
+            {"key": "target", "label": "Language", "type": "enum", "options": ["en", "ja"]},
+            {"key": "api_key", "label": "API key", "type": "secret"},
+        ]})).unwrap()
+    }
+
+    #[test]
+    fn schema_violations_fall_back_to_raw_json() {
+        let unknown_type = json!({"fields": [{"key": "color", "label": "Color", "type": "color"}]});
+        assert!(matches!(SettingsForm::from_configuration_ui(&unknown_type), SettingsForm::RawJson { .. }));
+
+        let duplicate = json!({"fields": [
+            {"key": "a", "label": "A", "type": "bool"},
+            {"key": "a", "label": "A again", "type": "string"},
+        ]});
+        assert_eq!(SettingsSchema::parse(&duplicate), Err(SettingsSchemaError::DuplicateKey("a".to_string())));
+
+        let secret_default = json!({"fields": [{"key": "k", "label": "K", "type": "secret", "default": "x"}]});
+        assert!(matches!(SettingsSchema::parse(&secret_default), Err(SettingsSchemaError::InvalidField { .. })));
+    }
+
+    #[test]
+    fn values_are_checked_against_the_constraints() {
+        let schema = schema();
+        assert!(matches!(schema.validate(&values(json!({"limit": 500}))), Err(SettingsValueError::OutOfRange { .. })));
+        assert!(matches!(schema.validate(&values(json!({"target": "de"}))), Err(SettingsValueError::NotAnOption { .. })));
+        assert!(matches!(schema.validate(&values(json!({"limit": "ten"}))), Err(SettingsValueError::WrongType { .. })));
+        assert_eq!(schema.validate(&values(json!({"other": 1}))), Err(SettingsValueError::UnknownField("other".to_string())));
+    }
+
+    #[test]
+    fn secrets_are_split_off_the_settings() {
+        let validated = schema().validate(&values(json!({"limit": 20, "target": "ja", "api_key": "k"}))).unwrap();
+        assert_eq!(Value::Object(validated.settings), json!({"limit": 20, "target": "ja"}));
+        assert_eq!(validated.secrets, vec![("api_key".to_string(), Some("k".to_string()))]);
+
+        let cleared = schema().validate(&values(json!({"api_key": null}))).unwrap();
+        assert_eq!(cleared.secrets, vec![("api_key".to_string(), None)]);
+    }
+
+    #[test]
+    fn typed_inputs_are_checked_field_by_field() {
+        let inputs = |pairs: &[(&str, &str)]| pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect::<Vec<_>>();
+        let parsed = schema().parse_inputs(&inputs(&[("limit", " 20 "), ("target", "ja"), ("api_key", "")])).unwrap();
+        // The blank password box is left out, keeping the stored secret
+        assert_eq!(Value::Object(parsed), json!({"limit": 20, "target": "ja"}));
+
+        let errors = schema().parse_inputs(&inputs(&[("limit", "lots"), ("target", "de"), ("api_key", "k")])).unwrap_err();
+        let keys: Vec<_> = errors.iter().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(keys, vec!["limit", "target"]);
+        assert!(matches!(errors[0].1, SettingsValueError::WrongType { .. }));
+
+        let toggle = SettingsField { key: "daily".into(), label: "Daily".into(), description: None, kind: FieldKind::Bool, default: None };
+        assert_eq!(toggle.parse_input("false"), Ok(Some(Value::Bool(false))));
+        assert!(toggle.parse_input("yes").is_err());
+        assert_eq!(schema().fields[0].parse_input("2.5"), Ok(Some(json!(2.5))));
+    }
+
+    #[tokio::test]
+    async fn raw_json_settings_must_be_an_object() {
+        let config = Arc::new(RwLock::new(Config::default()));
+        let plugins = PluginSystem::new(config.clone()).await.unwrap();
+
+        assert!(plugins.save_raw_plugin_settings("Notes", "[1, 2]").await.is_err());
+        assert!(plugins.save_raw_plugin_settings("Notes", "{\"folder\": ").await.is_err());
+        plugins.save_raw_plugin_settings("Notes", "{\"folder\": \"~/notes\"}").await.unwrap();
+        assert_eq!(config.read().await.plugins.plugin_settings.get("notes"), Some(&json!({"folder": "~/notes"})));
+    }
+
+    #[tokio::test]
+    async fn edited_settings_round_trip_to_the_plugin() {
+        let config = Arc::new(RwLock::new(Config::default()));
+        let keyring = Arc::new(MemoryKeyring::default());
+        let plugins = PluginSystem::new(config.clone()).await.unwrap().with_secret_store(keyring.clone());
+
+        let edited = values(json!({"endpoint": "https://translate.example/translate", "target_language": "ja", "api_key": "k"}));
+        plugins.save_plugin_settings("translator", edited).await.unwrap();
+
+        let stored = config.read().await.plugins.plugin_settings.get("translator").cloned();
+        assert_eq!(stored, Some(json!({"endpoint": "https://translate.example/translate", "target_language": "ja"})));
+        assert_eq!(keyring.get(&plugin_secret_key("translator", "api_key")).await.unwrap(), Some("k".to_string()));
+    }
+
+    #[tokio::test]
+    async fn secrets_are_not_written_to_a_plaintext_store() {
+        let store = Arc::new(MemoryKeyring { plaintext: true, ..MemoryKeyring::default() });
+        let config = Arc::new(RwLock::new(Config::default()));
+        let plugins = PluginSystem::new(config).await.unwrap().with_secret_store(store.clone());
+
+        let saved = plugins.save_plugin_settings("translator", values(json!({"api_key": "k"}))).await;
+        assert!(saved.is_err());
+        assert!(store.secrets.lock().await.is_empty());
+    }
+}
@@ -0,0 +1,237 @@
+use std::sync::Arc;
+use std::time::Duration;
+use async_trait::async_trait;
+use log::info;
+
+use falcommand_config::{SearchResult, SyntaxHint, Action, Category};
+use falcommand_config::i18n::tr;
+use falcommand_platform::{BatteryStatus, PlatformProvider, SystemControls};
+use crate::plugins::{Plugin, PluginContext, PluginError};
+use crate::icons::PluginIcon;
+use crate::routing::Trigger;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SystemCommand {
+    Volume(u8),
+    Mute,
+    Brightness(u8),
+    Battery,
+}
+
+impl SystemCommand {
+    // "volume 40", "volume 40%", "mute", "brightness 70", "battery"; levels are clamped to 0-100
+    pub fn parse(query: &str) -> Option<Self> {
+        let query = query.trim().to_lowercase();
+        let (keyword, argument) = query.split_once(char::is_whitespace).unwrap_or((&query, ""));
+        let argument = argument.trim();
+
+        match keyword {
+            "volume" | "vol" => Self::parse_level(argument).map(SystemCommand::Volume),
+            "brightness" => Self::parse_level(argument).map(SystemCommand::Brightness),
+            "mute" | "unmute" if argument.is_empty() => Some(SystemCommand::Mute),
+            "battery" if argument.is_empty() => Some(SystemCommand::Battery),
+            _ => None,
+        }
+    }
+
+    fn parse_level(text: &str) -> Option<u8> {
+        let value: u32 = text.trim_end_matches('%').trim().parse().ok()?;
+        Some(value.min(100) as u8)
+    }
+
+    fn is_supported(&self, controls: &SystemControls) -> bool {
+        match self {
+            SystemCommand::Volume(_) => controls.volume,
+            SystemCommand::Mute => controls.mute,
+            SystemCommand::Brightness(_) => controls.brightness,
+            SystemCommand::Battery => controls.battery,
+        }
+    }
+}
+
+pub fn battery_description(status: &BatteryStatus) -> String {
+    if status.charging {
+        format!("{}% · charging", status.percent)
+    } else {
+        format!("{}% · on battery", status.percent)
+    }
+}
+
+// Volume, mute, brightness and battery level without leaving the launcher
+#[derive(Debug)]
+pub struct SystemControlsPlugin {
+    context: PluginContext,
+}
+
+impl SystemControlsPlugin {
+    const BATTERY_REFRESH: Duration = Duration::from_secs(30);
+
+    pub fn new(context: PluginContext) -> Self {
+        Self { context }
+    }
+
+    fn platform(&self) -> Option<Arc<dyn PlatformProvider>> {
+        self.context.platform().cloned()
+    }
+
+    fn action(command: &str, level: Option<u8>) -> Action {
+        Action::PluginAction {
+            plugin_id: "System".to_string(),
+            action_data: serde_json::json!({ "command": command, "level": level }),
+        }
+    }
+
+    fn result(title: impl Into<String>, description: impl Into<String>, action: Action) -> SearchResult {
+        SearchResult::new(title, description)
+            .with_action(action)
+            .with_category(Category::Plugin("System".to_string()))
+            .with_score(1.0)
+    }
+
+    fn battery_result(status: &BatteryStatus) -> SearchResult {
+        Self::result("Battery", battery_description(status), Self::action("battery", None))
+            .with_detail("Charge", format!("{}%", status.percent))
+            .with_detail("Power", if status.charging { "Charging" } else { "On battery" })
+    }
+
+    // Shell-outs and sysfs reads block, so they stay off the async workers
+    async fn blocking<T, F>(platform: Arc<dyn PlatformProvider>, f: F) -> std::result::Result<T, PluginError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&dyn PlatformProvider) -> std::result::Result<T, falcommand_platform::PlatformError> + Send + 'static,
+    {
+        tokio::task::spawn_blocking(move || f(platform.as_ref()))
+            .await
+            .map_err(|e| PluginError::ExecutionError(e.to_string()))?
+            .map_err(|e| PluginError::ExecutionError(e.to_string()))
+    }
+
+    async fn battery_status(&self) -> std::result::Result<Option<BatteryStatus>, PluginError> {
+        let Some(platform) = self.platform() else {
+            return Ok(None);
+        };
+        Self::blocking(platform, |platform| platform.get_battery_status()).await
+    }
+}
+
+#[async_trait]
+impl Plugin for SystemControlsPlugin {
+    fn name(&self) -> &str {
+        "System"
+    }
+
+    fn version(&self) -> &str {
+        "1.0.0"
+    }
+
+    fn description(&self) -> &str {
+        "Battery, volume and brightness quick controls"
+    }
+
+    fn can_handle(&self, query: &str) -> bool {
+        SystemCommand::parse(query).is_some()
+    }
+
+    async fn search(&self, query: &str) -> std::result::Result<Vec<SearchResult>, PluginError> {
+        let Some(command) = SystemCommand::parse(query) else {
+            return Ok(vec![]);
+        };
+        let Some(platform) = self.platform() else {
+            return Ok(vec![]);
+        };
+
+        // Controls the platform can't perform produce no result rather than a failing one
+        let controls = Self::blocking(platform, |platform| Ok(platform.system_controls())).await?;
+        if !command.is_supported(&controls) {
+            return Ok(vec![]);
+        }
+
+        let result = match command {
+            SystemCommand::Volume(level) => Self::result(
+                format!("Set volume to {}%", level),
+                "System volume",
+                Self::action("volume", Some(level)),
+            ),
+            SystemCommand::Mute => Self::result("Toggle mute", "System audio", Self::action("mute", None)),
+            SystemCommand::Brightness(level) => Self::result(
+                format!("Set brightness to {}%", level),
+                "Display brightness",
+                Self::action("brightness", Some(level)),
+            ),
+            SystemCommand::Battery => match self.battery_status().await? {
+                Some(status) => Self::battery_result(&status),
+                None => return Ok(vec![]),
+            },
+        };
+
+        Ok(vec![result])
+    }
+
+    async fn execute(&self, result: &SearchResult) -> std::result::Result<(), PluginError> {
+        let Action::PluginAction { ref action_data, .. } = result.action else {
+            return Ok(());
+        };
+        let Some(platform) = self.platform() else {
+            return Err(PluginError::ExecutionError("System controls are not available".to_string()));
+        };
+
+        let command = action_data.get("command").and_then(|v| v.as_str()).unwrap_or_default();
+        let level = action_data.get("level")
+            .and_then(|v| v.as_u64())
+            .map(|level| level.min(100) as u8);
+
+        match (command, level) {
+            ("volume", Some(level)) => {
+                Self::blocking(platform, move |platform| platform.set_volume(level)).await?;
+                info!("Volume set to {}%", level);
+            }
+            ("brightness", Some(level)) => {
+                Self::blocking(platform, move |platform| platform.set_brightness(level)).await?;
+                info!("Brightness set to {}%", level);
+            }
+            ("mute", _) => {
+                Self::blocking(platform, |platform| platform.toggle_mute()).await?;
+            }
+            ("battery", _) => {
+                if let Some(status) = self.battery_status().await? {
+                    self.context.show_notification("Battery", &battery_description(&status))?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn syntax_hints(&self) -> Vec<SyntaxHint> {
+        vec![
+            SyntaxHint::new("volume <0-100>", tr("hint.system_volume", "Set the system volume"), "volume 40"),
+            SyntaxHint::new("mute", tr("hint.system_mute", "Toggle mute"), "mute"),
+            SyntaxHint::new("brightness <0-100>", tr("hint.system_brightness", "Set the display brightness"), "brightness 70"),
+            SyntaxHint::new("battery", tr("hint.system_battery", "Show the battery level"), "battery"),
+        ]
+    }
+
+    fn icon(&self) -> Option<PluginIcon> {
+        Some(PluginIcon::Glyph("settings"))
+    }
+
+    fn triggers(&self) -> Vec<Trigger> {
+        ["volume", "vol", "brightness", "mute", "unmute", "battery"].into_iter().map(Trigger::keyword).collect()
+    }
+
+    fn refresh_interval(&self) -> Option<Duration> {
+        Some(Self::BATTERY_REFRESH)
+    }
+
+    // Only the battery result goes stale; level-setting results are static
+    async fn refresh(&self, result: &SearchResult) -> std::result::Result<Option<SearchResult>, PluginError> {
+        let Action::PluginAction { ref action_data, .. } = result.action else {
+            return Ok(None);
+        };
+        if action_data.get("command").and_then(|v| v.as_str()) != Some("battery") {
+            return Ok(None);
+        }
+
+        Ok(self.battery_status().await?.map(|status| Self::battery_result(&status)))
+    }
+}
\ No newline at end of file
@@ -0,0 +1,279 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+use tokio::task::AbortHandle;
+use log::{info, warn};
+
+use crate::plugins::PluginError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TaskState {
+    Running,
+    Completed,
+    Failed(String),
+    Cancelled,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaskProgress {
+    pub task_id: u64,
+    pub label: String,
+    // 0.0..=1.0
+    pub fraction: f32,
+    pub message: String,
+    pub state: TaskState,
+}
+
+impl TaskProgress {
+    pub fn is_final(&self) -> bool {
+        self.state != TaskState::Running
+    }
+
+    // "Export 40% · writing files", used for notifications and the tray tooltip
+    pub fn summary(&self) -> String {
+        let status = match self.state {
+            TaskState::Running if self.fraction == 0.0 && self.message.is_empty() => "started".to_string(),
+            TaskState::Running => format!("{}%", (self.fraction * 100.0).round() as u32),
+            TaskState::Completed => "finished".to_string(),
+            TaskState::Failed(ref error) => format!("failed: {}", error),
+            TaskState::Cancelled => "cancelled".to_string(),
+        };
+
+        if self.message.is_empty() || self.is_final() {
+            format!("{} {}", self.label, status)
+        } else {
+            format!("{} {} · {}", self.label, status, self.message)
+        }
+    }
+}
+
+#[derive(Debug)]
+struct TrackedTask {
+    progress: TaskProgress,
+    abort: Option<AbortHandle>,
+}
+
+// Background work started by plugins; every state change is broadcast to subscribers
+#[derive(Debug)]
+pub struct TaskTracker {
+    tasks: Mutex<HashMap<u64, TrackedTask>>,
+    sender: broadcast::Sender<TaskProgress>,
+    next_id: AtomicU64,
+}
+
+impl TaskTracker {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(64);
+        Self {
+            tasks: Mutex::new(HashMap::new()),
+            sender,
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<TaskProgress> {
+        self.sender.subscribe()
+    }
+
+    fn publish(&self, progress: TaskProgress) {
+        // No subscribers is fine; progress is still visible through running()
+        let _ = self.sender.send(progress);
+    }
+
+    // Runs the future on its own task so a panic is reported as a failure instead of
+    // taking the caller down; the returned id is what report() expects
+    pub fn spawn<F>(self: &Arc<Self>, label: &str, future: F) -> u64
+    where
+        F: Future<Output = std::result::Result<(), PluginError>> + Send + 'static,
+    {
+        let task_id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let progress = TaskProgress {
+            task_id,
+            label: label.to_string(),
+            fraction: 0.0,
+            message: String::new(),
+            state: TaskState::Running,
+        };
+        self.tasks.lock().unwrap().insert(task_id, TrackedTask { progress: progress.clone(), abort: None });
+        self.publish(progress);
+        info!("Started plugin task {} ({})", task_id, label);
+
+        let handle = tokio::spawn(future);
+        if let Some(task) = self.tasks.lock().unwrap().get_mut(&task_id) {
+            task.abort = Some(handle.abort_handle());
+        }
+
+        let tracker = self.clone();
+        tokio::spawn(async move {
+            let state = match handle.await {
+                Ok(Ok(())) => TaskState::Completed,
+                Ok(Err(e)) => TaskState::Failed(e.to_string()),
+                Err(e) if e.is_panic() => TaskState::Failed("the task panicked".to_string()),
+                Err(_) => TaskState::Cancelled,
+            };
+            tracker.finish(task_id, state);
+        });
+
+        task_id
+    }
+
+    pub fn report(&self, task_id: u64, fraction: f32, message: &str) {
+        let progress = {
+            let mut tasks = self.tasks.lock().unwrap();
+            let Some(task) = tasks.get_mut(&task_id) else {
+                return;
+            };
+            task.progress.fraction = fraction.clamp(0.0, 1.0);
+            task.progress.message = message.to_string();
+            task.progress.clone()
+        };
+        self.publish(progress);
+    }
+
+    fn finish(&self, task_id: u64, state: TaskState) {
+        let Some(task) = self.tasks.lock().unwrap().remove(&task_id) else {
+            return;
+        };
+
+        let mut progress = task.progress;
+        if state == TaskState::Completed {
+            progress.fraction = 1.0;
+        }
+        match state {
+            TaskState::Failed(ref error) => warn!("Plugin task {} ({}) failed: {}", task_id, progress.label, error),
+            _ => info!("Plugin task {} ({}) ended: {:?}", task_id, progress.label, state),
+        }
+        progress.state = state;
+        self.publish(progress);
+    }
+
+    pub fn running(&self) -> Vec<TaskProgress> {
+        let mut running: Vec<TaskProgress> = self.tasks.lock().unwrap()
+            .values()
+            .map(|task| task.progress.clone())
+            .collect();
+        running.sort_by_key(|progress| progress.task_id);
+        running
+    }
+
+    pub fn running_count(&self) -> usize {
+        self.tasks.lock().unwrap().len()
+    }
+
+    pub fn cancel(&self, task_id: u64) {
+        if let Some(abort) = self.tasks.lock().unwrap().get(&task_id).and_then(|task| task.abort.as_ref()) {
+            abort.abort();
+        }
+    }
+
+    // Called at shutdown; each task still ends with a Cancelled update
+    pub fn cancel_all(&self) {
+        let tasks = self.tasks.lock().unwrap();
+        for task in tasks.values() {
+            if let Some(ref abort) = task.abort {
+                abort.abort();
+            }
+        }
+        if !tasks.is_empty() {
+            info!("Cancelling {} plugin tasks", tasks.len());
+        }
+    }
+}
+
+impl Default for TaskTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Keeps progress notifications to one per task per interval; start and final updates always pass
+#[derive(Debug, Clone)]
+pub struct ProgressThrottle {
+    min_interval: Duration,
+    last_notified: HashMap<u64, Instant>,
+}
+
+impl ProgressThrottle {
+    pub fn new(min_interval: Duration) -> Self {
+        Self {
+            min_interval,
+            last_notified: HashMap::new(),
+        }
+    }
+
+    pub fn should_notify(&mut self, progress: &TaskProgress, now: Instant) -> bool {
+        if progress.is_final() {
+            self.last_notified.remove(&progress.task_id);
+            return true;
+        }
+
+        let due = self.last_notified.get(&progress.task_id)
+            .map(|last| now.duration_since(*last) >= self.min_interval)
+            .unwrap_or(true);
+        if due {
+            self.last_notified.insert(progress.task_id, now);
+        }
+        due
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn final_update(updates: &mut broadcast::Receiver<TaskProgress>) -> TaskProgress {
+        loop {
+            let progress = tokio::time::timeout(Duration::from_secs(5), updates.recv()).await.unwrap().unwrap();
+            if progress.is_final() {
+                return progress;
+            }
+        }
+    }
+
+    fn progress(task_id: u64, state: TaskState) -> TaskProgress {
+        TaskProgress { task_id, label: "Export".to_string(), fraction: 0.5, message: String::new(), state }
+    }
+
+    #[tokio::test]
+    async fn a_panicking_task_ends_as_failed() {
+        let tracker = Arc::new(TaskTracker::new());
+        let mut updates = tracker.subscribe();
+        tracker.spawn("Export", async { panic!("boom") });
+
+        let ended = final_update(&mut updates).await;
+        assert_eq!(ended.state, TaskState::Failed("the task panicked".to_string()));
+        assert_eq!(ended.summary(), "Export failed: the task panicked");
+        assert_eq!(tracker.running_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn cancelled_tasks_end_with_a_cancelled_update() {
+        let tracker = Arc::new(TaskTracker::new());
+        let mut updates = tracker.subscribe();
+        let task_id = tracker.spawn("Export", std::future::pending());
+        tracker.report(task_id, 1.5, "writing files");
+        assert_eq!(tracker.running()[0].fraction, 1.0);
+
+        tracker.cancel(task_id);
+        let ended = final_update(&mut updates).await;
+        assert_eq!(ended.state, TaskState::Cancelled);
+        assert!(tracker.running().is_empty());
+        // Reports for a task that ended are ignored
+        tracker.report(task_id, 0.5, "late");
+        assert!(tracker.running().is_empty());
+    }
+
+    #[test]
+    fn throttle_holds_progress_but_never_final_updates() {
+        let mut throttle = ProgressThrottle::new(Duration::from_secs(5));
+        let start = Instant::now();
+
+        assert!(throttle.should_notify(&progress(1, TaskState::Running), start));
+        assert!(!throttle.should_notify(&progress(1, TaskState::Running), start + Duration::from_secs(1)));
+        assert!(throttle.should_notify(&progress(2, TaskState::Running), start + Duration::from_secs(1)));
+        assert!(throttle.should_notify(&progress(1, TaskState::Failed("disk full".to_string())), start + Duration::from_secs(2)));
+        assert!(throttle.should_notify(&progress(1, TaskState::Running), start + Duration::from_secs(5)));
+    }
+}
@@ -1,30 +1,311 @@
 use std::sync::Arc;
-use tokio::sync::RwLock;
-use log::{info, error};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, RwLock};
+use log::{info, warn, error};
 
 // Minimal, real UI using Slint. We keep the surface very small and stable for the rest of the app.
 slint::slint! {
+    export struct HelpEntry {
+        group: string,
+        pattern: string,
+        description: string,
+        example: string,
+    }
+
+    // One result as the configured row layout shows it; empty strings are left out
+    export struct ResultRowView {
+        title: string,
+        badge: string,
+        score: string,
+        subtitle: string,
+        hints: string,
+        badge_color: color,
+        // Under the row while it is selected, and all of them in the peek overlay
+        expanded_details: [string],
+        peek_details: [string],
+    }
+
     export component LauncherWindow inherits Window {
         in property <string> placeholder: "Type to search...";
         in property <bool> visible_state: true;
+        in property <string> ghost_text: "";
+        // What a typed query macro searches, shown dimmed under the input
+        in property <string> expansion_hint: "";
+        in property <string> help_title: "Query syntax";
+        in property <string> badge: "";
+        in property <[HelpEntry]> help_entries: [];
+        in property <[ResultRowView]> rows: [];
+        in-out property <bool> help_visible: false;
+        // While Alt is held: every detail of the selected result
+        in-out property <bool> peek_visible: false;
+        in-out property <string> query: "";
+        // Row Enter runs; Up/Down move it and new results put it back on the first row
+        in-out property <int> selected: 0;
+        in property <float> content_opacity: 1;
+        // The resolved palette; the defaults are the dark theme's
+        in property <color> palette_background: #202225;
+        in property <color> palette_foreground: #dcddde;
+        in property <color> palette_muted: #8e9297;
+        in property <color> palette_faint: #6b6f76;
+        in property <color> palette_selection: #3d5a80;
+        in property <color> palette_accent: #faa61a;
+        in property <color> palette_border: #2f3136;
+        callback help_toggled(bool);
+        // Keys that arrive before the input has focus (right after show)
+        callback typed_ahead(string);
+        // The query as typed, after every edit
+        callback edited(string);
+        // Right/End while a history completion is shown
+        callback accept_suggestion();
+        // Tab: shell-style completion of a typed path
+        callback complete_path();
+        // Enter on a row
+        callback execute(int);
+        // Ctrl+1..9: that secondary action of the row
+        callback execute_secondary(int, int);
+
+        public function focus_input() {
+            input.focus();
+        }
+
+        // `offset` is in bytes, as slint counts them
+        public function place_caret(offset: int) {
+            input.focus();
+            input.set-selection-offsets(offset, offset);
+        }
 
         width: 600px;
-        height: 80px;
-        background: #202225;
-
-        VerticalLayout {
-            padding: 12px;
-            TextInput {
-                font-size: 16px;
-                height: 32px;
+        height: root.help_visible || root.peek_visible ? 420px
+            : 80px + (root.expansion_hint != "" ? 20px : 0px) + root.rows.length * 44px
+                + (root.rows.length > 0 ? root.rows[root.selected].expanded_details.length * 16px : 0px);
+        background: root.palette_background;
+
+        FocusScope {
+            key-pressed(event) => {
+                if (event.text == Key.F1) {
+                    root.help_visible = !root.help_visible;
+                    root.help_toggled(root.help_visible);
+                    return accept;
+                }
+                if (event.text == Key.Escape && root.help_visible) {
+                    root.help_visible = false;
+                    return accept;
+                }
+                if (!input.has-focus && event.text != "" && !event.modifiers.control && !event.modifiers.alt && !event.modifiers.meta) {
+                    root.typed_ahead(event.text);
+                    return accept;
+                }
+                return reject;
+            }
+
+            // Faded in on show; the input gets focus before the fade starts
+            Rectangle {
+                opacity: root.content_opacity;
+                border-width: 1px;
+                border-color: root.palette_border;
+
+                VerticalLayout {
+                    padding: 12px;
+                    spacing: 8px;
+                    Rectangle {
+                        height: 32px;
+
+                        // History suggestion rendered dimmed underneath the typed text
+                        Text {
+                            x: 0px;
+                            width: parent.width;
+                            height: parent.height;
+                            text: root.ghost_text;
+                            font-size: 16px;
+                            color: root.palette_faint;
+                        }
+
+                        input := TextInput {
+                            text <=> root.query;
+                            x: 0px;
+                            width: parent.width;
+                            height: parent.height;
+                            font-size: 16px;
+                            color: root.palette_foreground;
+                            selection-background-color: root.palette_selection;
+                            selection-foreground-color: root.palette_foreground;
+
+                            edited => {
+                                root.edited(self.text);
+                            }
+
+                            key-pressed(event) => {
+                                if ((event.text == Key.RightArrow || event.text == Key.End) && root.ghost_text != "" && !event.modifiers.shift) {
+                                    root.accept_suggestion();
+                                    return accept;
+                                }
+                                if (event.text == Key.Tab && !event.modifiers.shift) {
+                                    root.complete_path();
+                                    return accept;
+                                }
+                                if (event.text == Key.Return && root.rows.length > 0) {
+                                    root.execute(root.selected);
+                                    return accept;
+                                }
+                                if (event.text == Key.DownArrow) {
+                                    root.selected = Math.max(0, Math.min(root.selected + 1, root.rows.length - 1));
+                                    return accept;
+                                }
+                                if (event.text == Key.UpArrow) {
+                                    root.selected = Math.max(root.selected - 1, 0);
+                                    return accept;
+                                }
+                                if (event.modifiers.control && event.text.is-float() && event.text.to-float() >= 1 && root.rows.length > 0) {
+                                    root.execute_secondary(root.selected, event.text.to-float() - 1);
+                                    return accept;
+                                }
+                                if (event.text == Key.Alt && root.rows.length > 0 && root.rows[root.selected].peek_details.length > 0) {
+                                    root.peek_visible = true;
+                                    return accept;
+                                }
+                                return reject;
+                            }
+
+                            key-released(event) => {
+                                if (event.text == Key.Alt && root.peek_visible) {
+                                    root.peek_visible = false;
+                                    return accept;
+                                }
+                                return reject;
+                            }
+                        }
+
+                        // Mode the launcher is running in, e.g. safe mode
+                        if root.badge != "": Text {
+                            x: parent.width - self.width;
+                            height: parent.height;
+                            text: root.badge;
+                            font-size: 12px;
+                            font-weight: 700;
+                            color: root.palette_accent;
+                            vertical-alignment: center;
+                        }
+                    }
+
+                    if !root.help_visible && root.expansion_hint != "": Text {
+                        height: 16px;
+                        text: root.expansion_hint;
+                        font-size: 11px;
+                        color: root.palette_faint;
+                        overflow: elide;
+                    }
+
+                    if !root.help_visible && !root.peek_visible: VerticalLayout {
+                        spacing: 4px;
+                        for row[i] in root.rows: Rectangle {
+                            height: i == root.selected ? 40px + row.expanded_details.length * 16px : 40px;
+                            background: i == root.selected ? root.palette_selection : transparent;
+                            VerticalLayout {
+                                HorizontalLayout {
+                                    spacing: 8px;
+                                    Text {
+                                        text: row.title;
+                                        font-size: 14px;
+                                        color: root.palette_foreground;
+                                        overflow: elide;
+                                    }
+                                    if row.badge != "": Text {
+                                        text: row.badge;
+                                        font-size: 11px;
+                                        font-weight: 700;
+                                        color: row.badge_color;
+                                        vertical-alignment: center;
+                                    }
+                                    if row.score != "": Text {
+                                        text: row.score;
+                                        font-size: 11px;
+                                        color: root.palette_accent;
+                                        vertical-alignment: center;
+                                    }
+                                }
+                                if row.subtitle != "" || row.hints != "": Text {
+                                    text: row.subtitle != "" && row.hints != "" ? row.subtitle + "  ·  " + row.hints : row.subtitle + row.hints;
+                                    font-size: 11px;
+                                    color: root.palette_muted;
+                                    overflow: elide;
+                                }
+                                if i == root.selected: VerticalLayout {
+                                    for line in row.expanded_details: Text {
+                                        height: 16px;
+                                        text: line;
+                                        font-size: 11px;
+                                        color: root.palette_muted;
+                                        overflow: elide;
+                                    }
+                                }
+                            }
+                        }
+                    }
+
+                    // Hold-to-peek: the selected result with all of its details
+                    if root.peek_visible && !root.help_visible: VerticalLayout {
+                        spacing: 4px;
+                        Text {
+                            text: root.rows[root.selected].title;
+                            font-size: 14px;
+                            font-weight: 700;
+                            color: root.palette_foreground;
+                        }
+                        for line in root.rows[root.selected].peek_details: Text {
+                            text: line;
+                            font-size: 12px;
+                            color: root.palette_muted;
+                            overflow: elide;
+                        }
+                    }
+
+                    // F1 overlay listing every prefix, operator and trigger, grouped by source
+                    if root.help_visible: VerticalLayout {
+                        spacing: 4px;
+                        Text {
+                            text: root.help_title;
+                            font-size: 14px;
+                            font-weight: 700;
+                            color: root.palette_foreground;
+                        }
+                        for entry[i] in root.help_entries: HorizontalLayout {
+                            spacing: 8px;
+                            Text {
+                                width: 120px;
+                                text: (i == 0 || root.help_entries[i - 1].group != entry.group) ? entry.group : "";
+                                font-size: 12px;
+                                color: root.palette_muted;
+                            }
+                            Text {
+                                width: 160px;
+                                text: entry.pattern;
+                                font-size: 12px;
+                                color: root.palette_foreground;
+                            }
+                            Text {
+                                text: entry.description + "  (" + entry.example + ")";
+                                font-size: 12px;
+                                color: root.palette_muted;
+                                overflow: elide;
+                            }
+                        }
+                    }
+                }
             }
         }
     }
 }
 
-use falcommand_config::Config;
-use falcommand_core::SearchEngine;
-use falcommand_config::SearchResult;
+use falcommand_config::{AppearanceConfig, Config, Palette, Theme};
+use falcommand_config::i18n::tr;
+use falcommand_core::{
+    SearchEngine, ActionExecutor, LiveRefresh, ResultRefresher, ConfirmationGate, confirmation_result,
+    LatencyMetrics, SHOW_LATENCY, SHOW_LATENCY_BUDGET, Heartbeat,
+    ConfigChanged, ResultRow, RowFields, result_row, expansion_hint, ThemeWatch, ClipboardUndo, ClipboardSuggestions,
+    EventBus, LauncherEvent,
+};
+use falcommand_config::{Action, SearchResult, SecondaryAction};
 
 #[derive(Debug, thiserror::Error)]
 pub enum UiError {
@@ -44,19 +325,287 @@ pub enum UiError {
     Other(String),
 }
 
-// Placeholder for Slint UI components
-// In a real implementation, this would use actual Slint UI definitions
+pub const FADE_IN_DURATION: Duration = Duration::from_millis(120);
+const FADE_FRAME: Duration = Duration::from_millis(16);
+// Well under the smallest stall threshold the watchdog accepts
+const HEARTBEAT_INTERVAL: Duration = Duration::from_millis(100);
+
+// Rows for the results the window has room for, laid out as the appearance settings say
+pub fn row_views(results: &[SearchResult], appearance: &AppearanceConfig) -> Vec<ResultRow> {
+    let fields = RowFields::from_appearance(appearance);
+    results.iter()
+        .take(appearance.visible_result_rows)
+        .map(|result| result_row(result, fields))
+        .collect()
+}
+
+fn show_rows(ui: &LauncherWindow, rows: Vec<ResultRow>, palette: &Palette) {
+    let rows: Vec<ResultRowView> = rows.into_iter()
+        .map(|row| ResultRowView {
+            badge_color: slint_color(palette.badge(&row.category)),
+            title: row.title.into(),
+            badge: row.badge.unwrap_or_default().into(),
+            score: row.score.unwrap_or_default().into(),
+            subtitle: row.subtitle.unwrap_or_default().into(),
+            hints: row.hints.unwrap_or_default().into(),
+            expanded_details: string_model(row.expanded_details),
+            peek_details: string_model(row.peek_details),
+        })
+        .collect();
+    ui.set_rows(slint::ModelRc::new(slint::VecModel::from(rows)));
+}
+
+fn string_model(lines: Vec<String>) -> slint::ModelRc<slint::SharedString> {
+    let lines: Vec<slint::SharedString> = lines.into_iter().map(Into::into).collect();
+    slint::ModelRc::new(slint::VecModel::from(lines))
+}
+
+fn slint_color(color: falcommand_config::Color) -> slint::Color {
+    slint::Color::from_argb_u8(color.a, color.r, color.g, color.b)
+}
+
+fn show_palette(ui: &LauncherWindow, palette: &Palette) {
+    ui.set_palette_background(slint_color(palette.background));
+    ui.set_palette_foreground(slint_color(palette.foreground));
+    ui.set_palette_muted(slint_color(palette.muted));
+    ui.set_palette_faint(slint_color(palette.faint));
+    ui.set_palette_selection(slint_color(palette.selection));
+    ui.set_palette_accent(slint_color(palette.accent));
+    ui.set_palette_border(slint_color(palette.border));
+}
+
+// Where the palette comes from, and what it last resolved to
+struct ThemeState {
+    // Unset until `with_themes`; custom themes then fall back to their base palette
+    themes_dir: Option<std::path::PathBuf>,
+    system_dark: bool,
+    palette: Palette,
+}
+
+impl Default for ThemeState {
+    fn default() -> Self {
+        Self { themes_dir: None, system_dark: true, palette: Palette::dark() }
+    }
+}
+
+impl ThemeState {
+    // A theme file that can't be used leaves the launcher readable in its base palette
+    fn resolve(&mut self, theme: &Theme) -> Palette {
+        let resolved = match self.themes_dir {
+            Some(ref themes_dir) => Palette::resolve(theme, self.system_dark, themes_dir),
+            None => Ok(Palette::base(theme, self.system_dark)),
+        };
+        self.palette = resolved.unwrap_or_else(|e| {
+            warn!("Using the base palette instead of theme {:?}: {}", theme, e);
+            Palette::base(theme, self.system_dark)
+        });
+        self.palette.clone()
+    }
+}
+
+pub fn fade_duration(appearance: &AppearanceConfig) -> Duration {
+    if appearance.fade_in && !appearance.reduced_motion {
+        FADE_IN_DURATION
+    } else {
+        Duration::ZERO
+    }
+}
+
+// Content opacity `elapsed` into a fade of `duration`
+pub fn fade_opacity(elapsed: Duration, duration: Duration) -> f32 {
+    if duration.is_zero() {
+        return 1.0;
+    }
+    (elapsed.as_secs_f32() / duration.as_secs_f32()).clamp(0.0, 1.0)
+}
+
+// Slint reports special keys (arrows, F-keys) as private-use characters; only real text is kept
+pub fn typed_ahead_text(text: &str) -> Option<&str> {
+    let printable = !text.is_empty()
+        && text.chars().all(|c| !c.is_control() && !('\u{F700}'..='\u{F8FF}').contains(&c));
+    printable.then_some(text)
+}
+
+// What Right/End accepts: a completion that still extends what is typed, so accepting only ever
+// adds the remainder and never rewrites typed text
+pub fn accepted_completion(suggestion: Option<String>, typed: &str) -> Option<String> {
+    suggestion.filter(|suggestion| suggestion.len() > typed.len() && suggestion.starts_with(typed))
+}
+
+// The query a pivot such as "Search in this folder" types in; these stay in the window and never
+// reach the ActionExecutor
+fn query_pivot(action: &Action) -> Option<&str> {
+    match action {
+        Action::SetQuery(query) => Some(query),
+        _ => None,
+    }
+}
+
+// Send-able handle for callbacks that run off the UI thread, such as global hotkeys
+#[derive(Clone)]
+pub struct WindowHandle {
+    ui: slint::Weak<LauncherWindow>,
+    pending_paint: Arc<std::sync::Mutex<Option<Instant>>>,
+    clipboard_offer: Option<ClipboardOffer>,
+    events: Option<Arc<EventBus>>,
+}
+
+impl WindowHandle {
+    pub fn toggle(&self) {
+        let requested_at = Instant::now();
+        let pending_paint = self.pending_paint.clone();
+        let clipboard_offer = self.clipboard_offer.clone();
+        let events = self.events.clone();
+        let result = self.ui.upgrade_in_event_loop(move |ui| {
+            if ui.window().is_visible() {
+                match ui.hide() {
+                    Ok(()) => publish(events.as_deref(), LauncherEvent::WindowHidden),
+                    Err(e) => error!("Failed to hide window: {}", e),
+                }
+                return;
+            }
+            Self::present(&ui, &pending_paint, requested_at, clipboard_offer.as_ref(), events.as_deref());
+        });
+        if let Err(e) = result {
+            error!("Failed to toggle window: {}", e);
+        }
+    }
+    
+    // Opens the window with `query` typed in, replacing whatever was there
+    pub fn show_with_query(&self, query: String) {
+        let requested_at = Instant::now();
+        let pending_paint = self.pending_paint.clone();
+        let events = self.events.clone();
+        let result = self.ui.upgrade_in_event_loop(move |ui| {
+            let query: slint::SharedString = query.into();
+            ui.set_query(query.clone());
+            ui.invoke_edited(query);
+            Self::present(&ui, &pending_paint, requested_at, None, events.as_deref());
+        });
+        if let Err(e) = result {
+            error!("Failed to show window: {}", e);
+        }
+    }
+    
+    // Ends the event loop so `MainWindow::run` returns; used to replace a window that keeps stalling
+    pub fn close(&self) {
+        if let Err(e) = slint::quit_event_loop() {
+            error!("Failed to stop the event loop: {}", e);
+        }
+    }
+    
+    fn present(
+        ui: &LauncherWindow,
+        pending_paint: &std::sync::Mutex<Option<Instant>>,
+        requested_at: Instant,
+        clipboard_offer: Option<&ClipboardOffer>,
+        events: Option<&EventBus>,
+    ) {
+        *pending_paint.lock().unwrap() = Some(requested_at);
+        ui.invoke_focus_input();
+        ui.set_content_opacity(1.0);
+        match ui.show() {
+            Ok(()) => publish(events, LauncherEvent::WindowShown),
+            Err(e) => error!("Failed to show window: {}", e),
+        }
+        if let Some(offer) = clipboard_offer {
+            offer.show(ui);
+        }
+    }
+}
+
+fn publish(events: Option<&EventBus>, event: LauncherEvent) {
+    if let Some(events) = events {
+        events.publish(event);
+    }
+}
+
+// Puts the undo of a recent copy, then suggestions for what the clipboard holds, in the window
+// while nothing is typed
+#[derive(Clone)]
+struct ClipboardOffer {
+    clipboard_undo: Option<Arc<ClipboardUndo>>,
+    suggestions: Option<Arc<ClipboardSuggestions>>,
+    config: Arc<RwLock<Config>>,
+    results: Arc<RwLock<Vec<SearchResult>>>,
+    theme: Arc<std::sync::Mutex<ThemeState>>,
+}
+
+impl ClipboardOffer {
+    fn show(&self, ui: &LauncherWindow) {
+        if !ui.get_query().is_empty() {
+            return;
+        }
+        let undo = self.clipboard_undo.as_ref().and_then(|clipboard_undo| clipboard_undo.undo_result());
+        if undo.is_none() && self.suggestions.is_none() {
+            return;
+        }
+        
+        let weak_ui = ui.as_weak();
+        let suggestions = self.suggestions.clone();
+        let (config, results, theme) = (self.config.clone(), self.results.clone(), self.theme.clone());
+        tokio::spawn(async move {
+            let mut offered: Vec<SearchResult> = undo.into_iter().collect();
+            if let Some(suggestions) = suggestions {
+                offered.extend(suggestions.suggestions().await);
+            }
+            if offered.is_empty() {
+                return;
+            }
+            let rows = row_views(&offered, &config.read().await.appearance);
+            let palette = theme.lock().unwrap().palette.clone();
+            let shown = weak_ui.upgrade_in_event_loop(move |ui| {
+                // Typing while the clipboard was read wins; the results are only claimed here, on
+                // the UI thread, so Enter never runs an offer that isn't on screen
+                if !ui.get_query().is_empty() {
+                    return;
+                }
+                let Ok(mut current) = results.try_write() else {
+                    return;
+                };
+                *current = offered;
+                show_rows(&ui, rows, &palette);
+                ui.set_selected(0);
+            });
+            if let Err(e) = shown {
+                error!("Failed to offer clipboard actions: {}", e);
+            }
+        });
+    }
+}
+
+// The window is created once at startup; show and hide reuse it so the hotkey never waits on window creation
 pub struct MainWindow {
     search_engine: Arc<SearchEngine>,
+    action_executor: Arc<ActionExecutor>,
     config: Arc<RwLock<Config>>,
     is_visible: Arc<RwLock<bool>>,
     current_results: Arc<RwLock<Vec<SearchResult>>>,
+    current_query: Arc<RwLock<String>>,
+    // Counts searches started from the input; one finishing after a newer one started is dropped
+    searches: Arc<AtomicU64>,
+    suggestion: Arc<std::sync::Mutex<Option<String>>>,
+    confirmation: Arc<std::sync::Mutex<ConfirmationGate>>,
+    live_refresh: Option<Arc<LiveRefresh>>,
+    metrics: Arc<LatencyMetrics>,
+    // When the pending show was requested; cleared by the first frame rendered after it
+    pending_paint: Arc<std::sync::Mutex<Option<Instant>>>,
+    fade_timer: std::rc::Rc<slint::Timer>,
+    theme: Arc<std::sync::Mutex<ThemeState>>,
+    clipboard_offer: Option<ClipboardOffer>,
+    // Showing and hiding are published for external tools
+    events: Option<Arc<EventBus>>,
+    heartbeat_timer: slint::Timer,
+    // One-shot mode: the event loop ends after the first execution or when the window is dismissed
+    exit_after_execute: bool,
+    executed: Arc<std::sync::atomic::AtomicBool>,
     ui: LauncherWindow,
 }
 
 impl MainWindow {
     pub async fn new(
         search_engine: Arc<SearchEngine>,
+        action_executor: Arc<ActionExecutor>,
         config: Arc<RwLock<Config>>,
     ) -> Result<Self> {
         info!("Initializing main window...");
@@ -64,12 +613,94 @@ impl MainWindow {
         // Create the Slint UI window
         let slint_ui = LauncherWindow::new().map_err(|e| UiError::InitializationError(e.to_string()))?;
         slint_ui.set_visible_state(true);
+        slint_ui.set_help_title(tr("help.title", "Query syntax").into());
+        
+        // Characters typed before focus lands on the input are appended rather than lost
+        let weak_ui = slint_ui.as_weak();
+        slint_ui.on_typed_ahead(move |text| {
+            let Some(ui) = weak_ui.upgrade() else {
+                return;
+            };
+            if let Some(text) = typed_ahead_text(&text) {
+                let query: slint::SharedString = format!("{}{}", ui.get_query(), text).into();
+                ui.set_query(query.clone());
+                ui.invoke_edited(query);
+            }
+            ui.invoke_focus_input();
+        });
+        
+        let metrics = Arc::new(LatencyMetrics::new().with_budget(SHOW_LATENCY, SHOW_LATENCY_BUDGET));
+        let pending_paint: Arc<std::sync::Mutex<Option<Instant>>> = Arc::new(std::sync::Mutex::new(None));
+        let paint_metrics = metrics.clone();
+        let paint_pending = pending_paint.clone();
+        let notifier = slint_ui.window().set_rendering_notifier(move |state, _| {
+            if !matches!(state, slint::RenderingState::AfterRendering) {
+                return;
+            }
+            if let Some(requested_at) = paint_pending.lock().unwrap().take() {
+                paint_metrics.record(SHOW_LATENCY, requested_at.elapsed());
+            }
+        });
+        if let Err(e) = notifier {
+            warn!("Show latency can't be measured with this renderer: {}", e);
+        }
+        
+        // Hints are collected when the overlay opens so they reflect the currently loaded sources
+        let weak_ui = slint_ui.as_weak();
+        let help_engine = search_engine.clone();
+        slint_ui.on_help_toggled(move |visible| {
+            if !visible {
+                return;
+            }
+            let weak_ui = weak_ui.clone();
+            let search_engine = help_engine.clone();
+            tokio::spawn(async move {
+                let entries: Vec<(String, String, String, String)> = search_engine.syntax_hints().await
+                    .into_iter()
+                    .flat_map(|group| {
+                        let source = group.source;
+                        group.hints.into_iter()
+                            .map(move |hint| (source.clone(), hint.pattern, hint.description, hint.example))
+                    })
+                    .collect();
+                
+                let result = weak_ui.upgrade_in_event_loop(move |ui| {
+                    let entries: Vec<HelpEntry> = entries.into_iter()
+                        .map(|(group, pattern, description, example)| HelpEntry {
+                            group: group.into(),
+                            pattern: pattern.into(),
+                            description: description.into(),
+                            example: example.into(),
+                        })
+                        .collect();
+                    ui.set_help_entries(slint::ModelRc::new(slint::VecModel::from(entries)));
+                });
+                if let Err(e) = result {
+                    error!("Failed to show help overlay: {}", e);
+                }
+            });
+        });
 
         let window = Self {
             search_engine,
+            action_executor,
             config,
             is_visible: Arc::new(RwLock::new(false)),
             current_results: Arc::new(RwLock::new(Vec::new())),
+            current_query: Arc::new(RwLock::new(String::new())),
+            searches: Arc::new(AtomicU64::new(0)),
+            suggestion: Arc::new(std::sync::Mutex::new(None)),
+            confirmation: Arc::new(std::sync::Mutex::new(ConfirmationGate::default())),
+            live_refresh: None,
+            metrics,
+            pending_paint,
+            fade_timer: std::rc::Rc::new(slint::Timer::default()),
+            theme: Arc::new(std::sync::Mutex::new(ThemeState::default())),
+            clipboard_offer: None,
+            events: None,
+            heartbeat_timer: slint::Timer::default(),
+            exit_after_execute: false,
+            executed: Arc::new(std::sync::atomic::AtomicBool::new(false)),
             ui: slint_ui,
         };
         
@@ -77,18 +708,185 @@ impl MainWindow {
         Ok(window)
     }
     
+    pub fn with_result_refresher(mut self, refresher: Arc<dyn ResultRefresher>) -> Self {
+        self.live_refresh = Some(Arc::new(LiveRefresh::new(refresher)));
+        self
+    }
+    
+    pub fn with_exit_after_execute(mut self) -> Self {
+        self.exit_after_execute = true;
+        self
+    }
+    
+    // Beats from inside the event loop, so a blocked loop shows up as a stale heartbeat
+    pub fn with_heartbeat(self, heartbeat: Arc<Heartbeat>) -> Self {
+        heartbeat.beat();
+        self.heartbeat_timer.start(slint::TimerMode::Repeated, HEARTBEAT_INTERVAL, move || heartbeat.beat());
+        self
+    }
+    
+    // Rows and the palette are rebuilt whenever the settings change, so a new row layout or theme
+    // shows without a restart
+    pub fn with_config_changes(self, mut changes: broadcast::Receiver<ConfigChanged>) -> Self {
+        let weak_ui = self.ui.as_weak();
+        let results = self.current_results.clone();
+        let theme = self.theme.clone();
+        tokio::spawn(async move {
+            loop {
+                let config = match changes.recv().await {
+                    Ok(change) => change.config,
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                let palette = theme.lock().unwrap().resolve(&config.appearance.theme);
+                let rows = row_views(&results.read().await, &config.appearance);
+                // The window is gone, e.g. replaced after a restart
+                let repainted = weak_ui.upgrade_in_event_loop(move |ui| {
+                    show_palette(&ui, &palette);
+                    show_rows(&ui, rows, &palette);
+                });
+                if repainted.is_err() {
+                    break;
+                }
+            }
+        });
+        self
+    }
+    
+    // Custom themes are read from `themes_dir` and re-read when their file is saved. The system
+    // theme picks the base palette for System and for colors a custom theme leaves out; when the
+    // platform can't tell, the launcher stays dark
+    pub fn with_themes(self, themes_dir: std::path::PathBuf, system_theme: Theme) -> Self {
+        {
+            let mut state = self.theme.lock().unwrap();
+            state.themes_dir = Some(themes_dir.clone());
+            state.system_dark = system_theme != Theme::Light;
+        }
+        let Some(mut watch) = ThemeWatch::start(&themes_dir) else {
+            return self;
+        };
+        
+        let weak_ui = self.ui.as_weak();
+        let config = self.config.clone();
+        let results = self.current_results.clone();
+        let theme = self.theme.clone();
+        tokio::spawn(async move {
+            while let Some(names) = watch.changed().await {
+                let config = config.read().await.clone();
+                let current = &config.appearance.theme;
+                if !current.custom_name().is_some_and(|name| names.contains(name)) {
+                    continue;
+                }
+                info!("Reloading theme {:?}", current);
+                let palette = theme.lock().unwrap().resolve(current);
+                let rows = row_views(&results.read().await, &config.appearance);
+                let repainted = weak_ui.upgrade_in_event_loop(move |ui| {
+                    show_palette(&ui, &palette);
+                    show_rows(&ui, rows, &palette);
+                });
+                if repainted.is_err() {
+                    break;
+                }
+            }
+        });
+        self
+    }
+    
+    // Reopening the empty window soon after a copy offers to undo it
+    pub fn with_clipboard_undo(mut self, clipboard_undo: Arc<ClipboardUndo>) -> Self {
+        self.clipboard_offer().clipboard_undo = Some(clipboard_undo);
+        self
+    }
+    
+    // Opening the empty window offers to open, calculate or search what was copied
+    // (behavior.clipboard_suggestions)
+    pub fn with_clipboard_suggestions(mut self, suggestions: Arc<ClipboardSuggestions>) -> Self {
+        self.clipboard_offer().suggestions = Some(suggestions);
+        self
+    }
+    
+    fn clipboard_offer(&mut self) -> &mut ClipboardOffer {
+        let (config, results, theme) = (self.config.clone(), self.current_results.clone(), self.theme.clone());
+        self.clipboard_offer.get_or_insert_with(|| ClipboardOffer {
+            clipboard_undo: None,
+            suggestions: None,
+            config,
+            results,
+            theme,
+        })
+    }
+    
+    pub fn with_events(mut self, events: Arc<EventBus>) -> Self {
+        self.events = Some(events);
+        self
+    }
+    
+    pub fn with_safe_mode_badge(self) -> Self {
+        self.ui.set_badge(tr("window.safe_mode", "Safe mode").into());
+        self
+    }
+    
+    // Whether a result was executed during this run
+    pub fn executed(&self) -> bool {
+        self.executed.load(Ordering::SeqCst)
+    }
+    
     pub async fn run(&self) -> Result<()> {
         info!("Starting UI event loop (Slint run)...");
+        self.wire_input();
         self.ui.run().map_err(|e| UiError::EventError(e.to_string()))?;
         info!("UI event loop finished");
         Ok(())
     }
     
+    pub fn metrics(&self) -> Arc<LatencyMetrics> {
+        self.metrics.clone()
+    }
+    
+    pub fn handle(&self) -> WindowHandle {
+        WindowHandle {
+            ui: self.ui.as_weak(),
+            pending_paint: self.pending_paint.clone(),
+            clipboard_offer: self.clipboard_offer.clone(),
+            events: self.events.clone(),
+        }
+    }
+    
     pub fn show(&self) -> Result<()> {
+        self.show_requested_at(Instant::now())
+    }
+    
+    // `requested_at` is the hotkey press, so the recorded latency covers the whole path to first paint
+    pub fn show_requested_at(&self, requested_at: Instant) -> Result<()> {
         info!("Showing main window");
+        *self.pending_paint.lock().unwrap() = Some(requested_at);
+        
+        // Focus comes before the fade so typing works from the first frame
+        self.ui.invoke_focus_input();
+        self.start_fade();
 
         // Slint's ComponentHandle::show() is non-blocking and safe to call from the thread owning the handle.
         self.ui.show().map_err(|e| UiError::WindowError(e.to_string()))?;
+        publish(self.events.as_deref(), LauncherEvent::WindowShown);
+        
+        // Live results only tick while they can be seen
+        if let Some(ref live_refresh) = self.live_refresh {
+            let weak_ui = self.ui.as_weak();
+            let theme = self.theme.clone();
+            live_refresh.start(self.current_results.clone(), self.config.clone(), move |results, config| {
+                let rows = row_views(results, &config.appearance);
+                let palette = theme.lock().unwrap().palette.clone();
+                // A new model, same selection: refreshes never move the highlighted row
+                let _ = weak_ui.upgrade_in_event_loop(move |ui| {
+                    let selected = ui.get_selected();
+                    show_rows(&ui, rows, &palette);
+                    ui.set_selected(selected);
+                });
+            });
+        }
+        if let Some(ref offer) = self.clipboard_offer {
+            offer.show(&self.ui);
+        }
 
         let is_visible = self.is_visible.clone();
         tokio::spawn(async move {
@@ -98,17 +896,109 @@ impl MainWindow {
         Ok(())
     }
     
-    pub fn hide(&self) -> Result<()> {
-        info!("Hiding main window");
-
-        self.ui.hide().map_err(|e| UiError::WindowError(e.to_string()))?;
-
-        let is_visible = self.is_visible.clone();
-        tokio::spawn(async move {
-            *is_visible.write().await = false;
+    // Falls back to no fade if the config is being written at this moment
+    fn start_fade(&self) {
+        let duration = self.config.try_read()
+            .map(|config| fade_duration(&config.appearance))
+            .unwrap_or(Duration::ZERO);
+        
+        self.fade_timer.stop();
+        self.ui.set_content_opacity(fade_opacity(Duration::ZERO, duration));
+        if duration.is_zero() {
+            return;
+        }
+        
+        let weak_ui = self.ui.as_weak();
+        let timer = std::rc::Rc::downgrade(&self.fade_timer);
+        let started = Instant::now();
+        self.fade_timer.start(slint::TimerMode::Repeated, FADE_FRAME, move || {
+            let opacity = fade_opacity(started.elapsed(), duration);
+            if let Some(ui) = weak_ui.upgrade() {
+                ui.set_content_opacity(opacity);
+            }
+            if opacity >= 1.0 {
+                if let Some(timer) = timer.upgrade() {
+                    timer.stop();
+                }
+            }
+        });
+    }
+    
+    // Callbacks are connected once the builder methods have run, so they see the final setup
+    fn wire_input(&self) {
+        let input = self.input();
+        self.ui.on_edited(move |text| {
+            input.clear_stale_suggestion(&text);
+            let input = input.clone();
+            spawn_input(async move { input.update_search_results(&text).await });
+        });
+        
+        let input = self.input();
+        self.ui.on_accept_suggestion(move || {
+            let input = input.clone();
+            spawn_input(async move {
+                let Some(typed) = input.typed() else {
+                    return;
+                };
+                input.accept_suggestion(&typed).await;
+            });
+        });
+        
+        let input = self.input();
+        self.ui.on_complete_path(move || {
+            let input = input.clone();
+            spawn_input(async move {
+                let Some(typed) = input.typed() else {
+                    return;
+                };
+                input.complete_path(&typed).await;
+            });
+        });
+        
+        let input = self.input();
+        self.ui.on_execute(move |index| {
+            let input = input.clone();
+            spawn_input(async move {
+                if let Err(e) = input.execute_selected_result(index as usize).await {
+                    error!("{}", e);
+                }
+            });
+        });
+        
+        let input = self.input();
+        self.ui.on_execute_secondary(move |index, action_index| {
+            let input = input.clone();
+            spawn_input(async move {
+                if let Err(e) = input.execute_secondary_action(index as usize, action_index as usize).await {
+                    error!("{}", e);
+                }
+            });
         });
-
-        Ok(())
+    }
+    
+    fn input(&self) -> WindowInput {
+        WindowInput {
+            ui: self.ui.as_weak(),
+            search_engine: self.search_engine.clone(),
+            action_executor: self.action_executor.clone(),
+            config: self.config.clone(),
+            is_visible: self.is_visible.clone(),
+            current_results: self.current_results.clone(),
+            current_query: self.current_query.clone(),
+            searches: self.searches.clone(),
+            suggestion: self.suggestion.clone(),
+            confirmation: self.confirmation.clone(),
+            live_refresh: self.live_refresh.clone(),
+            fade_timer: self.fade_timer.clone(),
+            theme: self.theme.clone(),
+            events: self.events.clone(),
+            exit_after_execute: self.exit_after_execute,
+            executed: self.executed.clone(),
+        }
+    }
+    
+    pub fn hide(&self) -> Result<()> {
+        self.input().hide()
     }
     
     pub fn toggle_visibility(&self) -> Result<()> {
@@ -130,29 +1020,293 @@ impl MainWindow {
     }
     
     pub async fn update_search_results(&self, query: &str) {
+        self.input().update_search_results(query).await
+    }
+    
+    // Right-arrow/End: accept the ghost completion and search for it right away
+    pub async fn accept_suggestion(&self, typed: &str) -> Option<String> {
+        self.input().accept_suggestion(typed).await
+    }
+    
+    // Tab on a typed path: completes it like a shell would and lists what is inside
+    pub async fn complete_path(&self, typed: &str) -> Option<String> {
+        self.input().complete_path(typed).await
+    }
+    
+    pub async fn execute_secondary_action(&self, index: usize, action_index: usize) -> Result<()> {
+        self.input().execute_secondary_action(index, action_index).await
+    }
+    
+    pub async fn execute_selected_result(&self, index: usize) -> Result<()> {
+        self.input().execute_selected_result(index).await
+    }
+    
+    pub async fn get_window_config(&self) -> WindowConfig {
+        let config = self.config.read().await;
+        
+        WindowConfig {
+            theme: config.appearance.theme.clone(),
+            transparency: config.appearance.transparency,
+            position: config.appearance.position.clone(),
+            font_size: config.appearance.font_size,
+            visible_result_rows: config.appearance.visible_result_rows,
+        }
+    }
+    
+    pub async fn apply_theme(&self) -> Result<()> {
+        let window_config = self.get_window_config().await;
+        info!("Applying theme: {:?}", window_config.theme);
+        
+        let palette = self.theme.lock().unwrap().resolve(&window_config.theme);
+        show_palette(&self.ui, &palette);
+        Ok(())
+    }
+}
+
+// What the input callbacks work with: the window's shared state, cloned into each callback. It
+// only lives on the UI thread, where the callbacks' futures run through slint::spawn_local
+#[derive(Clone)]
+struct WindowInput {
+    ui: slint::Weak<LauncherWindow>,
+    search_engine: Arc<SearchEngine>,
+    action_executor: Arc<ActionExecutor>,
+    config: Arc<RwLock<Config>>,
+    is_visible: Arc<RwLock<bool>>,
+    current_results: Arc<RwLock<Vec<SearchResult>>>,
+    current_query: Arc<RwLock<String>>,
+    searches: Arc<AtomicU64>,
+    suggestion: Arc<std::sync::Mutex<Option<String>>>,
+    confirmation: Arc<std::sync::Mutex<ConfirmationGate>>,
+    live_refresh: Option<Arc<LiveRefresh>>,
+    fade_timer: std::rc::Rc<slint::Timer>,
+    theme: Arc<std::sync::Mutex<ThemeState>>,
+    events: Option<Arc<EventBus>>,
+    exit_after_execute: bool,
+    executed: Arc<std::sync::atomic::AtomicBool>,
+}
+
+fn spawn_input(future: impl std::future::Future<Output = ()> + 'static) {
+    if let Err(e) = slint::spawn_local(future) {
+        error!("Failed to handle input: {}", e);
+    }
+}
+
+impl WindowInput {
+    fn typed(&self) -> Option<String> {
+        self.ui.upgrade().map(|ui| ui.get_query().to_string())
+    }
+    
+    fn quit(&self) -> Result<()> {
+        slint::quit_event_loop().map_err(|e| UiError::EventError(e.to_string()))
+    }
+    
+    fn hide(&self) -> Result<()> {
+        info!("Hiding main window");
+        
+        // Dismissing the one-shot window ends the run
+        if self.exit_after_execute {
+            return self.quit();
+        }
+        let Some(ui) = self.ui.upgrade() else {
+            return Ok(());
+        };
+
+        ui.hide().map_err(|e| UiError::WindowError(e.to_string()))?;
+        publish(self.events.as_deref(), LauncherEvent::WindowHidden);
+        self.fade_timer.stop();
+        
+        if let Some(ref live_refresh) = self.live_refresh {
+            live_refresh.stop();
+        }
+
+        let is_visible = self.is_visible.clone();
+        tokio::spawn(async move {
+            *is_visible.write().await = false;
+        });
+
+        Ok(())
+    }
+    
+    async fn update_search_results(&self, query: &str) {
         info!("Updating search results for query: '{}'", query);
+        let search = self.searches.fetch_add(1, Ordering::SeqCst) + 1;
         
         let results = self.search_engine.search(query).await;
+        let hint = self.search_engine.expand_macro(query).await.map(|expansion| expansion_hint(&expansion));
+        let suggestion = self.search_engine.suggest_completion(query).await;
+        if self.searches.load(Ordering::SeqCst) != search {
+            return;
+        }
+        let Some(ui) = self.ui.upgrade() else {
+            return;
+        };
+        
+        ui.set_expansion_hint(hint.unwrap_or_default().into());
+        let rows = row_views(&results, &self.config.read().await.appearance);
         *self.current_results.write().await = results;
+        *self.current_query.write().await = query.to_string();
+        let palette = self.theme.lock().unwrap().palette.clone();
+        show_rows(&ui, rows, &palette);
+        ui.set_selected(0);
+        self.show_suggestion(&ui, suggestion);
         
-        // In a real implementation, this would update the Slint UI
         info!("Search results updated");
     }
     
-    pub async fn execute_selected_result(&self, index: usize) -> Result<()> {
+    fn show_suggestion(&self, ui: &LauncherWindow, suggestion: Option<String>) {
+        ui.set_ghost_text(suggestion.clone().unwrap_or_default().into());
+        *self.suggestion.lock().unwrap() = suggestion;
+    }
+    
+    // Hides a completion the new text no longer leads up to before the search for it is done
+    fn clear_stale_suggestion(&self, typed: &str) {
+        let mut suggestion = self.suggestion.lock().unwrap();
+        if suggestion.is_none() || accepted_completion(suggestion.clone(), typed).is_some() {
+            return;
+        }
+        *suggestion = None;
+        drop(suggestion);
+        if let Some(ui) = self.ui.upgrade() {
+            ui.set_ghost_text("".into());
+        }
+    }
+    
+    async fn accept_suggestion(&self, typed: &str) -> Option<String> {
+        let suggestion = accepted_completion(self.suggestion.lock().unwrap().take(), typed)?;
+        self.pivot_query(&suggestion).await;
+        Some(suggestion)
+    }
+    
+    async fn complete_path(&self, typed: &str) -> Option<String> {
+        let completed = self.search_engine.complete_typed_path(typed).await?;
+        self.pivot_query(&completed).await;
+        Some(completed)
+    }
+    
+    // Replaces the typed text with `query`, caret at the end so more can be typed, and searches it
+    async fn pivot_query(&self, query: &str) {
+        if let Some(ui) = self.ui.upgrade() {
+            ui.set_query(query.into());
+            ui.invoke_place_caret(query.len() as i32);
+        }
+        self.update_search_results(query).await;
+    }
+    
+    // Redraws the rows after the results changed in place
+    async fn show_results(&self) {
+        let rows = row_views(&self.current_results.read().await, &self.config.read().await.appearance);
+        let palette = self.theme.lock().unwrap().palette.clone();
+        if let Some(ui) = self.ui.upgrade() {
+            show_rows(&ui, rows, &palette);
+        }
+    }
+    
+    async fn execute_secondary_action(&self, index: usize, action_index: usize) -> Result<()> {
+        let results = self.current_results.read().await;
+        
+        let secondary = results.get(index)
+            .and_then(|result| result.secondary_actions.get(action_index))
+            .ok_or_else(|| UiError::EventError("Invalid secondary action index".to_string()))?;
+        
+        if secondary.destructive {
+            let secondary = secondary.clone();
+            drop(results);
+            self.request_confirmation(index, &secondary).await;
+            return Ok(());
+        }
+        
+        // Pivots such as "Search in this folder" and "Open with…" never reach the executor
+        if let Some(query) = query_pivot(&secondary.action) {
+            let query = query.to_string();
+            drop(results);
+            self.pivot_query(&query).await;
+            return Ok(());
+        }
+        
+        info!("Executing secondary action: {}", secondary.title);
+        self.action_executor.execute(&secondary.action).await
+            .map_err(|e| UiError::EventError(format!("Failed to execute action: {}", e)))?;
+        
+        let config = self.config.read().await;
+        if config.behavior.auto_hide {
+            self.hide()?;
+        }
+        
+        Ok(())
+    }
+    
+    // The result turns into a confirmation prompt; Enter on it within the window runs the action
+    async fn request_confirmation(&self, index: usize, secondary: &SecondaryAction) {
+        let window = {
+            let mut gate = self.confirmation.lock().unwrap();
+            gate.arm(secondary.action.clone(), std::time::Instant::now());
+            gate.window()
+        };
+        
+        let mut results = self.current_results.write().await;
+        let Some(original) = results.get(index).cloned() else {
+            return;
+        };
+        let prompt = confirmation_result(&original, secondary, window);
+        results[index] = prompt.clone();
+        drop(results);
+        self.show_results().await;
+        info!("Waiting for confirmation: {}", prompt.title);
+        
+        let weak_ui = self.ui.clone();
+        let (current_results, config, theme) = (self.current_results.clone(), self.config.clone(), self.theme.clone());
+        tokio::spawn(async move {
+            tokio::time::sleep(window).await;
+            let mut results = current_results.write().await;
+            if results.get(index).map(|result| result.id) != Some(prompt.id) {
+                return;
+            }
+            results[index] = original;
+            let rows = row_views(&results, &config.read().await.appearance);
+            drop(results);
+            let palette = theme.lock().unwrap().palette.clone();
+            let restored = weak_ui.upgrade_in_event_loop(move |ui| show_rows(&ui, rows, &palette));
+            if let Err(e) = restored {
+                error!("Failed to restore the result after its confirmation expired: {}", e);
+            }
+        });
+    }
+    
+    async fn execute_selected_result(&self, index: usize) -> Result<()> {
         let results = self.current_results.read().await;
         
         if let Some(result) = results.get(index) {
             info!("Executing selected result: {}", result.title);
             
-            if let Err(e) = result.action.execute().await {
+            // Handled here rather than by the executor: the window stays open with the new query
+            if let Some(query) = query_pivot(&result.action) {
+                let query = query.to_string();
+                drop(results);
+                self.pivot_query(&query).await;
+                return Ok(());
+            }
+            
+            if result.action.is_destructive()
+                && !self.confirmation.lock().unwrap().confirm(&result.action, std::time::Instant::now())
+            {
+                return Err(UiError::EventError("Confirmation expired; choose the action again".to_string()));
+            }
+            
+            if let Err(e) = self.action_executor.execute(&result.action).await {
                 error!("Failed to execute action: {}", e);
                 return Err(UiError::EventError(format!("Failed to execute action: {}", e)));
             }
             
             // Add to search history
-            // In a real implementation, this would get the current query from UI state
-            self.search_engine.add_to_history("", result);
+            let query = self.current_query.read().await.clone();
+            self.search_engine.add_to_history(&query, result).await;
+            self.search_engine.record_launch(result).await;
+            self.executed.store(true, Ordering::SeqCst);
+            
+            // History and usage are written above, so a one-shot run can end here
+            if self.exit_after_execute {
+                return self.quit();
+            }
             
             // Auto-hide if configured
             let config = self.config.read().await;
@@ -165,32 +1319,6 @@ impl MainWindow {
             Err(UiError::EventError("Invalid result index".to_string()))
         }
     }
-    
-    async fn should_exit(&self) -> bool {
-        // In a real implementation, this would check for quit signals
-        // For now, just return false to keep running
-        false
-    }
-    
-    pub async fn get_window_config(&self) -> WindowConfig {
-        let config = self.config.read().await;
-        
-        WindowConfig {
-            theme: config.appearance.theme.clone(),
-            transparency: config.appearance.transparency,
-            position: config.appearance.position.clone(),
-            font_size: config.appearance.font_size,
-            max_results: config.appearance.max_results,
-        }
-    }
-    
-    pub async fn apply_theme(&self) -> Result<()> {
-        let window_config = self.get_window_config().await;
-        info!("Applying theme: {:?}", window_config.theme);
-        
-        // In a real implementation, this would apply the theme to Slint components
-        Ok(())
-    }
 }
 
 #[derive(Debug, Clone)]
@@ -199,7 +1327,7 @@ pub struct WindowConfig {
     pub transparency: f32,
     pub position: falcommand_config::WindowPosition,
     pub font_size: u32,
-    pub max_results: usize,
+    pub visible_result_rows: usize,
 }
 
 // Placeholder for search input component
@@ -208,6 +1336,12 @@ pub struct SearchInput {
     current_query: Arc<RwLock<String>>,
 }
 
+impl Default for SearchInput {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl SearchInput {
     pub fn new() -> Self {
         Self {
@@ -235,6 +1369,12 @@ pub struct ResultList {
     selected_index: Arc<RwLock<usize>>,
 }
 
+impl Default for ResultList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ResultList {
     pub fn new() -> Self {
         Self {
@@ -243,9 +1383,16 @@ impl ResultList {
         }
     }
     
+    // The selection follows the selected result if it is still listed, otherwise it resets
     pub async fn update_results(&self, results: Vec<SearchResult>) {
-        *self.results.write().await = results;
-        *self.selected_index.write().await = 0; // Reset selection
+        let mut current = self.results.write().await;
+        let mut selected_index = self.selected_index.write().await;
+        
+        let selected_id = current.get(*selected_index).map(|result| result.id);
+        *selected_index = selected_id
+            .and_then(|id| results.iter().position(|result| result.id == id))
+            .unwrap_or(0);
+        *current = results;
     }
     
     pub async fn get_selected_index(&self) -> usize {
@@ -256,7 +1403,7 @@ impl ResultList {
         let results = self.results.read().await;
         let mut selected_index = self.selected_index.write().await;
         
-        if results.len() > 0 {
+        if !results.is_empty() {
             *selected_index = (*selected_index + 1) % results.len();
         }
     }
@@ -265,7 +1412,7 @@ impl ResultList {
         let results = self.results.read().await;
         let mut selected_index = self.selected_index.write().await;
         
-        if results.len() > 0 {
+        if !results.is_empty() {
             *selected_index = if *selected_index == 0 {
                 results.len() - 1
             } else {
@@ -287,7 +1434,85 @@ pub type Result<T> = std::result::Result<T, UiError>;
 // Note: In a real implementation, this module would also include:
 // 1. Slint UI definition files (.slint files)
 // 2. Proper Slint component integration
-// 3. Event handling for keyboard/mouse input
+// 3. Mouse input
 // 4. Window positioning and styling
-// 5. Theme application
-// 6. Real-time search result updates
\ No newline at end of file
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepting_only_adds_the_remainder() {
+        let suggestion = || Some("firefox".to_string());
+        assert_eq!(accepted_completion(suggestion(), "fi").as_deref(), Some("firefox"));
+        // Typed past the completion, or away from it: nothing to accept
+        assert_eq!(accepted_completion(suggestion(), "firefox"), None);
+        assert_eq!(accepted_completion(suggestion(), "fo"), None);
+        assert_eq!(accepted_completion(None, "fi"), None);
+    }
+
+    // A window input without a window, executing through an executor whose executions are recorded
+    async fn headless_input(dir: &std::path::Path, results: Vec<SearchResult>) -> (WindowInput, falcommand_core::EventSubscription) {
+        let resolver = falcommand_config::PathResolver::with_roots(dir.join("config"), dir.join("data"), None);
+        let clock: Arc<dyn falcommand_core::Clock> = Arc::new(falcommand_core::SystemClock);
+        let config = Arc::new(RwLock::new(Config::default()));
+        let index_manager = Arc::new(falcommand_core::IndexManager::new(config.clone()).await.unwrap());
+        let history = Arc::new(falcommand_core::SearchHistory::new(&resolver, clock.clone()).await.unwrap());
+        let usage = Arc::new(falcommand_core::UsageStore::new(&resolver, clock.clone()).await.unwrap());
+        let search_engine = Arc::new(SearchEngine::new(config.clone(), index_manager, history, usage).await.unwrap());
+        let scheduler = Arc::new(falcommand_core::ActionScheduler::new(&resolver, clock).await.unwrap());
+        let events = Arc::new(EventBus::default());
+        let executions = events.subscribe(falcommand_core::EventFilter::only([falcommand_core::EventKind::Execution]));
+        let platform = Arc::new(falcommand_plugin_testkit::RecordingPlatform::new());
+        let action_executor = Arc::new(ActionExecutor::new(platform, scheduler).with_events(events));
+
+        let input = WindowInput {
+            ui: slint::Weak::default(),
+            search_engine,
+            action_executor,
+            config,
+            is_visible: Arc::new(RwLock::new(true)),
+            current_results: Arc::new(RwLock::new(results)),
+            current_query: Arc::new(RwLock::new(String::new())),
+            searches: Arc::new(AtomicU64::new(0)),
+            suggestion: Arc::new(std::sync::Mutex::new(None)),
+            confirmation: Arc::new(std::sync::Mutex::new(ConfirmationGate::default())),
+            live_refresh: None,
+            fade_timer: std::rc::Rc::new(slint::Timer::default()),
+            theme: Arc::new(std::sync::Mutex::new(ThemeState::default())),
+            events: None,
+            exit_after_execute: false,
+            executed: Arc::new(std::sync::atomic::AtomicBool::new(false)),
+        };
+        (input, executions)
+    }
+
+    // SetQuery results and pivots retype the query; only other actions reach the executor
+    #[tokio::test]
+    async fn query_pivots_never_reach_the_executor() {
+        let dir = tempfile::tempdir().unwrap();
+        let report = SearchResult::new("report.txt", "/home/me/docs/report.txt")
+            .with_action(Action::CopyToClipboard("/home/me/docs/report.txt".to_string()))
+            .with_secondary_action("Search in this folder", Action::SetQuery("in:/home/me/docs ".to_string()));
+        let correction = SearchResult::new("Did you mean Firefox?", "").with_action(Action::SetQuery("Firefox".to_string()));
+        let (input, mut executions) = headless_input(dir.path(), vec![report, correction]).await;
+
+        input.execute_selected_result(1).await.unwrap();
+        input.execute_secondary_action(0, 0).await.unwrap();
+        input.execute_selected_result(0).await.unwrap();
+
+        // The copy is the first and only execution
+        let first = executions.next().await.unwrap();
+        assert_eq!(first, LauncherEvent::Executed { action: "copy".to_string(), succeeded: true });
+        assert!(input.executed.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn only_query_pivots_stay_in_the_window() {
+        let pivot = Action::SetQuery("in:/home/me/docs ".to_string());
+        assert_eq!(query_pivot(&pivot), Some("in:/home/me/docs "));
+        assert_eq!(query_pivot(&Action::OpenFile("/home/me/docs/report.txt".into())), None);
+        assert_eq!(query_pivot(&Action::TrashFile("/home/me/docs/report.txt".into())), None);
+        assert_eq!(query_pivot(&Action::CopyToClipboard("in:/home/me/docs ".to_string())), None);
+    }
+}
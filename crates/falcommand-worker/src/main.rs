@@ -0,0 +1,55 @@
+// Parses files the launcher doesn't trust to parse itself: archive listings, text previews, image
+// headers and file hashes. Jobs arrive on stdin and replies leave on stdout, one JSON frame per line; if a
+// file crashes this process, the launcher restarts it and skips that file
+use std::io::{BufReader, BufWriter};
+use log::{error, warn};
+
+use falcommand_core::{read_frame, run_job, write_frame, ExtractionJob, JobResponse};
+
+fn memory_limit_mb(args: &[String]) -> Option<u64> {
+    let position = args.iter().position(|arg| arg == "--memory-limit-mb")?;
+    args.get(position + 1)?.parse().ok()
+}
+
+// Allocations past the limit fail, which aborts this process rather than the launcher
+#[cfg(unix)]
+fn apply_memory_limit(limit_mb: u64) {
+    let bytes = (limit_mb * 1024 * 1024) as libc::rlim_t;
+    let limit = libc::rlimit { rlim_cur: bytes, rlim_max: bytes };
+    // SAFETY: setrlimit only reads the struct passed to it
+    if unsafe { libc::setrlimit(libc::RLIMIT_AS, &limit) } != 0 {
+        warn!("Failed to limit worker memory: {}", std::io::Error::last_os_error());
+    }
+}
+
+// The launcher's time limit still applies; memory is bounded only on Unix
+#[cfg(not(unix))]
+fn apply_memory_limit(_limit_mb: u64) {}
+
+fn main() {
+    env_logger::init();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(limit_mb) = memory_limit_mb(&args) {
+        apply_memory_limit(limit_mb);
+    }
+
+    let mut input = BufReader::new(std::io::stdin().lock());
+    let mut output = BufWriter::new(std::io::stdout().lock());
+    loop {
+        let job: ExtractionJob = match read_frame(&mut input) {
+            Ok(Some(job)) => job,
+            Ok(None) => break,
+            Err(e) => {
+                error!("Unreadable job, exiting: {}", e);
+                std::process::exit(2);
+            }
+        };
+
+        let response = JobResponse { id: job.id, result: run_job(&job.kind) };
+        if let Err(e) = write_frame(&mut output, &response) {
+            error!("Failed to reply to job {}: {}", job.id, e);
+            std::process::exit(2);
+        }
+    }
+}
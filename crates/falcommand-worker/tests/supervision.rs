@@ -0,0 +1,93 @@
+// The launcher's side of the worker protocol against the real worker binary. A wrapper script in front
+// of it aborts on files named "poison" and hangs on files named "sleepy", to crash the worker on demand
+#![cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use falcommand_core::{ExtractionError, ExtractionWorker, JobKind, JobOutput};
+
+const WORKER: &str = env!("CARGO_BIN_EXE_falcommand-worker");
+
+// Hands each job to a fresh worker, so a job that aborts takes the wrapper down with it.
+// Every start is logged to `starts`
+fn wrapper(dir: &Path) -> PathBuf {
+    let script = dir.join("worker.sh");
+    let content = format!(
+        "#!/bin/sh\n\
+         echo start >> '{starts}'\n\
+         while IFS= read -r line; do\n\
+         \x20 case \"$line\" in\n\
+         \x20   *poison*) kill -ABRT $$ ;;\n\
+         \x20   *sleepy*) sleep 30 ;;\n\
+         \x20 esac\n\
+         \x20 printf '%s\\n' \"$line\" | '{worker}' || exit 1\n\
+         done\n",
+        starts = dir.join("starts").display(),
+        worker = WORKER,
+    );
+    std::fs::write(&script, content).unwrap();
+    std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+    script
+}
+
+fn starts(dir: &Path) -> usize {
+    std::fs::read_to_string(dir.join("starts")).map_or(0, |starts| starts.lines().count())
+}
+
+fn preview(path: &Path) -> JobKind {
+    JobKind::TextPreview { path: path.to_path_buf(), max_bytes: 100 }
+}
+
+#[tokio::test]
+async fn the_real_worker_answers_jobs() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("notes.txt");
+    std::fs::write(&file, "hello worker").unwrap();
+
+    let worker = ExtractionWorker::with_program(Some(PathBuf::from(WORKER)));
+    assert_eq!(worker.run(preview(&file)).await.unwrap(), JobOutput::Text("hello worker".to_string()));
+    assert_eq!(worker.hash_file(&file, Some(5)).await.unwrap(), "aaf4c61ddcc5e8a2dabede0f3b482cd9aea9434d");
+    // A job that fails inside the worker is an ordinary error and leaves the file usable
+    let missing = dir.path().join("missing.txt");
+    assert!(matches!(worker.run(preview(&missing)).await, Err(ExtractionError::Failed(_))));
+    assert!(!worker.is_blacklisted(&missing).await);
+    worker.shutdown().await;
+}
+
+#[tokio::test]
+async fn a_file_that_crashes_the_worker_is_blacklisted_and_the_worker_restarts() {
+    let dir = tempfile::tempdir().unwrap();
+    let (good, poison) = (dir.path().join("good.txt"), dir.path().join("poison.txt"));
+    std::fs::write(&good, "fine").unwrap();
+    std::fs::write(&poison, "boom").unwrap();
+    let worker = ExtractionWorker::with_program(Some(wrapper(dir.path())));
+
+    assert_eq!(worker.run(preview(&good)).await.unwrap(), JobOutput::Text("fine".to_string()));
+    assert!(matches!(worker.run(preview(&poison)).await, Err(ExtractionError::WorkerCrashed(path)) if path == poison));
+    assert!(worker.is_blacklisted(&poison).await);
+    assert_eq!(starts(dir.path()), 1);
+
+    // The crashing file isn't tried again; the next good one gets a new worker
+    assert!(matches!(worker.run(preview(&poison)).await, Err(ExtractionError::Blacklisted(_))));
+    assert_eq!(starts(dir.path()), 1);
+    assert_eq!(worker.run(preview(&good)).await.unwrap(), JobOutput::Text("fine".to_string()));
+    assert_eq!(starts(dir.path()), 2);
+    worker.shutdown().await;
+}
+
+#[tokio::test]
+async fn a_file_that_hangs_the_worker_times_out_and_is_blacklisted() {
+    let dir = tempfile::tempdir().unwrap();
+    let (good, sleepy) = (dir.path().join("good.txt"), dir.path().join("sleepy.txt"));
+    std::fs::write(&good, "fine").unwrap();
+    std::fs::write(&sleepy, "zzz").unwrap();
+    let worker = ExtractionWorker::with_program(Some(wrapper(dir.path())))
+        .with_limits(ExtractionWorker::MEMORY_LIMIT_MB, Duration::from_millis(300));
+
+    assert!(matches!(worker.run(preview(&sleepy)).await, Err(ExtractionError::TimedOut(_))));
+    assert!(worker.is_blacklisted(&sleepy).await);
+    assert_eq!(worker.run(preview(&good)).await.unwrap(), JobOutput::Text("fine".to_string()));
+    assert_eq!(starts(dir.path()), 2);
+    worker.shutdown().await;
+}
@@ -0,0 +1,57 @@
+// "hello <name>" greets <name>; executing the result copies the greeting
+use async_trait::async_trait;
+
+use falcommand_config::{Action, Category, SearchResult};
+use falcommand_plugins::{Plugin, PluginContext, PluginError};
+
+#[derive(Debug)]
+pub struct GreeterPlugin {
+    context: PluginContext,
+}
+
+impl GreeterPlugin {
+    const PREFIX: &'static str = "hello ";
+
+    pub fn new(context: PluginContext) -> Self {
+        Self { context }
+    }
+}
+
+#[async_trait]
+impl Plugin for GreeterPlugin {
+    fn name(&self) -> &str {
+        "Greeter"
+    }
+
+    fn version(&self) -> &str {
+        "0.1.0"
+    }
+
+    fn description(&self) -> &str {
+        "Greets whoever you name"
+    }
+
+    fn can_handle(&self, query: &str) -> bool {
+        query.starts_with(Self::PREFIX)
+    }
+
+    async fn search(&self, query: &str) -> Result<Vec<SearchResult>, PluginError> {
+        let Some(name) = query.strip_prefix(Self::PREFIX).map(str::trim).filter(|name| !name.is_empty()) else {
+            return Ok(Vec::new());
+        };
+        let greeting = format!("Hello, {}!", name);
+        Ok(vec![
+            SearchResult::new(&greeting, "Copy the greeting")
+                .with_action(Action::CopyToClipboard(greeting.clone()))
+                .with_category(Category::Plugin(self.name().to_string()))
+                .with_score(0.8),
+        ])
+    }
+
+    async fn execute(&self, result: &SearchResult) -> Result<(), PluginError> {
+        if let Action::CopyToClipboard(ref text) = result.action {
+            self.context.copy_to_clipboard(text)?;
+        }
+        Ok(())
+    }
+}
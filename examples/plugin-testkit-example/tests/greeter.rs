@@ -0,0 +1,23 @@
+use falcommand_plugin_example::GreeterPlugin;
+use falcommand_plugin_testkit::{assert_execute_in_time, assert_search_in_time, PluginHarness};
+
+#[tokio::test]
+async fn greets_and_copies_the_greeting() {
+    let harness = PluginHarness::new();
+    let plugin = GreeterPlugin::new(harness.context());
+
+    let results = assert_search_in_time(&plugin, "hello world").await;
+    assert_eq!(results[0].title, "Hello, world!");
+    assert_execute_in_time(&plugin, &results[0]).await;
+    assert_eq!(harness.platform().clipboard().as_deref(), Some("Hello, world!"));
+}
+
+#[tokio::test]
+async fn other_queries_are_left_alone() {
+    let harness = PluginHarness::new();
+    let plugin = GreeterPlugin::new(harness.context());
+
+    let outcomes = harness.run_queries(&plugin, &["goodbye world", "hello "]).await;
+    assert!(!outcomes[0].handled);
+    assert!(outcomes[1].handled && outcomes[1].results.is_empty());
+}
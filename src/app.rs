@@ -1,12 +1,25 @@
 use std::sync::Arc;
+use std::path::PathBuf;
 use tokio::sync::RwLock;
-use log::{info, error};
+use log::{info, warn, error};
 
-use falcommand_config::{Config, ConfigError};
+use falcommand_config::{Config, ConfigError, PathResolver, ResultId};
 use falcommand_platform::PlatformProvider;
-use falcommand_core::{SearchEngine, IndexManager, SyncManager, IndexError, SearchError, SyncError};
+use falcommand_core::{
+    SearchEngine, IndexManager, SyncManager, ActionExecutor, ActionScheduler, ScheduledActionsSource,
+    SearchHistory, SystemClock, ProfileManager, ProfileSource, IndexError, SearchError, SyncError,
+    TrayStatusUpdater, SchedulerError, HistoryError, ConnectivityMonitor, UsageStore, UsageError, StatsSource, HistorySource,
+    HotkeyIntent, resolve_intent, ConfigStore, FrontAppMenuSource, SafeModeSource, StartupGuard,
+    InstanceChannel, InstanceMessage, JumpListUpdater, Heartbeat, UiWatchdog, FileMoveTracker, ExtractionWorker,
+    OpenWithSource, DoctorContext, DoctorSource, RowFields, plain_row, result_row, IncludePathNotice,
+    AppIndexRefresher, SettingsSource, CustomCommandSource, PowerPolicy, TrayMode, TrayFallbackNotice, QuitSource, QueryMacroSource, PageError,
+    PathCompletionSource, ClipboardUndo, ClipboardUndoSource, ClipboardSuggestions, FolderProvider, SoakCounters, SoakOptions, SoakRecord, SoakReport,
+    SoakSample, SoakSummary, SubsystemStats, SyncConflictSource, SyncEvent, TaskRegistry, TASK_SNAPSHOT_FILE, create_secret_store, process_rss_bytes,
+    start_task_snapshots, EventBus, WebClient,
+};
 use falcommand_plugins::{PluginSystem, PluginError};
-use falcommand_ui::MainWindow;
+use falcommand_ui::{MainWindow, WindowHandle};
+use crate::dev::{render_results, DevSession};
 
 #[derive(Debug, thiserror::Error)]
 pub enum AppError {
@@ -22,9 +35,21 @@ pub enum AppError {
     #[error("Sync error: {0}")]
     Sync(#[from] SyncError),
     
+    #[error("History error: {0}")]
+    History(#[from] HistoryError),
+    
+    #[error("Usage data error: {0}")]
+    Usage(#[from] UsageError),
+    
+    #[error("Scheduler error: {0}")]
+    Scheduler(#[from] SchedulerError),
+    
     #[error("Plugin system error: {0}")]
     Plugin(#[from] PluginError),
     
+    #[error("Search paging error: {0}")]
+    Page(#[from] PageError),
+    
     #[error("UI error: {0}")]
     Ui(String),
     
@@ -35,103 +60,837 @@ pub enum AppError {
     Other(#[from] anyhow::Error),
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RunMode {
+    // Tray, hotkeys and background work; runs until quit
+    #[default]
+    Resident,
+    // `--once`: show the window, execute one result and exit
+    Once,
+    // `falcommand soak`: everything but the window and desktop integration, under synthetic searches
+    Soak,
+}
+
+impl RunMode {
+    // Only --once reports through the exit code whether a result ran; other modes exit normally
+    pub fn exit_code(&self, outcome: RunOutcome) -> Option<i32> {
+        match self {
+            RunMode::Once => Some(outcome.exit_code()),
+            RunMode::Resident | RunMode::Soak => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexPolicy {
+    // Serve the cached index right away and rebuild in the background
+    CacheThenRebuild,
+    // Only rebuild when there is no usable cache
+    CacheOrRebuild,
+}
+
+// The parts of the app that run in a given mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Subsystems {
+    pub tray: bool,
+    pub hotkeys: bool,
+    pub scheduler: bool,
+    pub connectivity: bool,
+    pub auto_sync: bool,
+    pub progress_notifications: bool,
+    pub history_maintenance: bool,
+    pub plugins: bool,
+    // Results from outside the launcher: plugins and the frontmost app's menus
+    pub external_sources: bool,
+    // Accept results forwarded by later launches (`--execute-id`) and event subscriptions
+    pub single_instance: bool,
+    pub jump_list: bool,
+    // Warn about (and optionally recover from) a stalled UI event loop
+    pub watchdog: bool,
+    // Carry usage over to files renamed or moved within the indexed folders
+    pub file_moves: bool,
+    // Rescan applications when the install locations change
+    pub app_installs: bool,
+    // Write the tracked background tasks for `falcommand status`
+    pub task_snapshots: bool,
+    pub index: IndexPolicy,
+}
+
+impl Subsystems {
+    pub fn for_mode(mode: RunMode) -> Self {
+        match mode {
+            RunMode::Resident => Self {
+                tray: true,
+                hotkeys: true,
+                scheduler: true,
+                connectivity: true,
+                auto_sync: true,
+                progress_notifications: true,
+                history_maintenance: true,
+                plugins: true,
+                external_sources: true,
+                single_instance: true,
+                jump_list: true,
+                watchdog: true,
+                file_moves: true,
+                app_installs: true,
+                task_snapshots: true,
+                index: IndexPolicy::CacheThenRebuild,
+            },
+            RunMode::Once => Self {
+                tray: false,
+                hotkeys: false,
+                scheduler: false,
+                connectivity: false,
+                auto_sync: false,
+                progress_notifications: false,
+                history_maintenance: false,
+                plugins: true,
+                external_sources: true,
+                single_instance: false,
+                jump_list: false,
+                watchdog: false,
+                file_moves: false,
+                app_installs: false,
+                task_snapshots: false,
+                index: IndexPolicy::CacheOrRebuild,
+            },
+            // The resident background work, so leaks in it show up; the soak run samples the
+            // task registry itself rather than overwriting the resident launcher's snapshot
+            RunMode::Soak => Self {
+                tray: false,
+                hotkeys: false,
+                scheduler: true,
+                connectivity: true,
+                auto_sync: true,
+                progress_notifications: false,
+                history_maintenance: true,
+                plugins: true,
+                external_sources: true,
+                single_instance: false,
+                jump_list: false,
+                watchdog: false,
+                file_moves: true,
+                app_installs: true,
+                task_snapshots: false,
+                index: IndexPolicy::CacheThenRebuild,
+            },
+        }
+    }
+    
+    // Only what's needed to search and fix the config: no plugins, sync, background scans or scheduled actions
+    pub fn safe_mode(self) -> Self {
+        Self {
+            scheduler: false,
+            connectivity: false,
+            auto_sync: false,
+            progress_notifications: false,
+            file_moves: false,
+            app_installs: false,
+            plugins: false,
+            external_sources: false,
+            index: IndexPolicy::CacheOrRebuild,
+            ..self
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunOutcome {
+    Executed,
+    Dismissed,
+}
+
+impl RunOutcome {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            RunOutcome::Executed => 0,
+            RunOutcome::Dismissed => 1,
+        }
+    }
+}
+
 pub struct App {
+    mode: RunMode,
     config: Arc<RwLock<Config>>,
     platform_provider: Arc<dyn PlatformProvider>,
     search_engine: Arc<SearchEngine>,
     plugin_system: Arc<PluginSystem>,
     index_manager: Arc<IndexManager>,
     sync_manager: Arc<SyncManager>,
+    action_executor: Arc<ActionExecutor>,
+    app_refresher: Arc<AppIndexRefresher>,
+    // Settings changes, which the window follows live
+    config_store: Arc<ConfigStore>,
+    connectivity: Arc<ConnectivityMonitor>,
+    // Defers periodic background work on a low battery
+    power_policy: Arc<PowerPolicy>,
+    menu_source: Option<Arc<FrontAppMenuSource>>,
+    // Explains the missing tray on desktops without one, once
+    tray_notice: TrayFallbackNotice,
+    // Custom theme files, watched while the window is up
+    themes_dir: PathBuf,
+    // What the last copy replaced, offered back for a few seconds
+    clipboard_undo: Arc<ClipboardUndo>,
+    // What the clipboard holds, offered when the window opens
+    clipboard_suggestions: Arc<ClipboardSuggestions>,
+    task_snapshot_path: PathBuf,
+    // Index, sync, search, window and execution events for external tools
+    events: Arc<EventBus>,
+    startup_guard: Option<StartupGuard>,
+    instance: InstanceChannel,
+    // Result to run once started, from an `--execute-id` launch with no instance to forward to
+    pending_execution: Option<ResultId>,
+    // Set in safe mode: reduced subsystems, a badge in the window and the safe mode commands
+    safe_mode: Option<Arc<SafeModeSource>>,
+    heartbeat: Arc<Heartbeat>,
+    // Set by the watchdog when the window should be replaced once its event loop returns
+    restart_ui: Arc<std::sync::atomic::AtomicBool>,
+    // The current window for hotkeys, which outlive a restarted one
+    window_handle: Arc<std::sync::Mutex<Option<WindowHandle>>>,
     ui: Option<MainWindow>,
 }
 
 impl App {
+    // Results per page when `falcommand search` is given --offset without --limit
+    pub const DEFAULT_PAGE_SIZE: usize = falcommand_core::DEFAULT_PAGE_SIZE;
+    
     pub async fn new(
         config: Config,
-        platform_provider: Arc<dyn PlatformProvider>
+        resolver: PathResolver,
+        platform_provider: Arc<dyn PlatformProvider>,
+        mode: RunMode,
+        safe_mode: Option<SafeModeSource>,
     ) -> Result<Self> {
         info!("Initializing application...");
         
-        let config = Arc::new(RwLock::new(config));
+        // Safe mode runs on a reduced config; saving it over the user's file would lose their settings
+        let save_path = match safe_mode {
+            Some(_) => resolver.cache_file("safe-mode-config.json"),
+            None => resolver.active_config_file(),
+        };
+        let config_store = Arc::new(ConfigStore::new(config, save_path));
+        let config = config_store.shared();
+        if mode == RunMode::Resident {
+            Self::announce_include_path_problems(&config_store, &resolver, platform_provider.as_ref()).await;
+        }
         
         // Initialize core components
-        let index_manager = Arc::new(IndexManager::new(config.clone()).await?);
-        let plugin_system = Arc::new(PluginSystem::new(config.clone()).await?);
-        let sync_manager = Arc::new(SyncManager::new(config.clone()).await?);
+        let extractor = Arc::new(ExtractionWorker::new());
+        let index_manager = Arc::new(
+            IndexManager::new(config.clone()).await?
+                .with_cache_path(resolver.cache_file("index.json"))
+                .with_extractor(extractor.clone())
+        );
+        
+        // Published to `falcommand rpc subscribe` clients through the instance channel
+        let events = Arc::new(EventBus::default());
+        let clock: Arc<dyn falcommand_core::Clock> = Arc::new(SystemClock);
+        let history = Arc::new(SearchHistory::new(&resolver, clock.clone()).await?);
+        let usage = Arc::new(UsageStore::new(&resolver, clock.clone()).await?);
         
         let search_engine = Arc::new(
             SearchEngine::new(
                 config.clone(),
                 index_manager.clone(),
+                history,
+                usage,
             ).await?
+            .with_events(events.clone())
+        );
+        search_engine.apply_history_retention().await;
+        // `falcommand rpc search` pages through this engine's rankings
+        let instance = InstanceChannel::new(&resolver)
+            .with_events(events.clone())
+            .with_search(search_engine.clone());
+        let power_policy = Arc::new(PowerPolicy::new(platform_provider.clone(), config.clone()));
+        let mut sync_manager = SyncManager::new(config.clone(), &resolver).await?
+            .with_search_engine(search_engine.clone())
+            .with_config_store(config_store.clone())
+            .with_power_policy(power_policy.clone());
+        // Soak runs sync for real, into a folder of their own
+        if mode == RunMode::Soak {
+            sync_manager = sync_manager.with_cloud_provider(Arc::new(FolderProvider::new(resolver.cache_dir().join("soak-sync"))));
+        }
+        let sync_manager = Arc::new(sync_manager);
+        
+        let scheduler = Arc::new(ActionScheduler::new(&resolver, clock.clone()).await?);
+        let plugin_data_dir = resolver.shared_data_dir();
+        let plugin_icon_dir = resolver.icon_cache_dir().join("plugins");
+        let tray_notice = TrayFallbackNotice::new(resolver.cache_file("tray-fallback-notice.txt"));
+        let themes_dir = resolver.themes_dir();
+        let clipboard_undo = Arc::new(ClipboardUndo::new(clock.clone()));
+        let clipboard_suggestions = Arc::new(ClipboardSuggestions::new(platform_provider.clone(), config.clone()));
+        let task_snapshot_path = resolver.cache_file(TASK_SNAPSHOT_FILE);
+        let cache_dir = resolver.cache_dir();
+        let secret_store = create_secret_store(&resolver).await;
+        let doctor = DoctorContext::new(
+            config.read().await.clone(),
+            resolver.clone(),
+            platform_provider.clone(),
+            index_manager.clone(),
+            secret_store.clone(),
         );
+        let profile_manager = Arc::new(ProfileManager::new(
+            config_store.clone(),
+            search_engine.clone(),
+            clock,
+            resolver,
+        ));
+        
+        let connectivity = Arc::new(ConnectivityMonitor::new().with_power_policy(power_policy.clone()));
+        let web_client = Arc::new(WebClient::new());
+        let plugin_system = Arc::new(PluginSystem::new(config.clone()).await?
+            .with_platform_provider(platform_provider.clone())
+            .with_connectivity(connectivity.clone())
+            .with_http_client(web_client.clone())
+            .with_config_store(config_store.clone())
+            .with_data_dir(plugin_data_dir)
+            .with_secret_store(secret_store)
+            .with_index_manager(index_manager.clone(), extractor)
+            .with_icon_cache_dir(plugin_icon_dir));
+        // Quitting ends the window's event loop like closing it does; `run` then shuts down as usual
+        let window_handle: Arc<std::sync::Mutex<Option<WindowHandle>>> = Arc::new(std::sync::Mutex::new(None));
+        let quit_handle = window_handle.clone();
+        let quit = Arc::new(move || {
+            if let Some(ref handle) = *quit_handle.lock().unwrap() {
+                handle.close();
+            }
+        });
+        let mut action_executor = ActionExecutor::new(platform_provider.clone(), scheduler.clone())
+            .with_quit(quit)
+            .with_profile_manager(profile_manager.clone())
+            .with_plugin_handler(plugin_system.clone())
+            .with_index_manager(index_manager.clone())
+            .with_search_engine(search_engine.clone())
+            .with_config_store(config_store.clone())
+            .with_sync_manager(sync_manager.clone())
+            .with_clipboard_undo(clipboard_undo.clone())
+            .with_events(events.clone())
+            .with_cache_dir(cache_dir);
+        let app_refresher = Arc::new(
+            AppIndexRefresher::new(index_manager.clone(), platform_provider.clone())
+                .with_power_policy(power_policy.clone())
+        );
+        action_executor = action_executor.with_app_refresher(app_refresher.clone());
+        
+        // Menu commands of the frontmost app, only on macOS builds with `ax-integration`
+        let menu_access = falcommand_platform::menu_access();
+        if let Some(ref access) = menu_access {
+            action_executor = action_executor.with_menu_access(access.clone());
+        }
+        let action_executor = Arc::new(action_executor);
+        let menu_source = menu_access.map(|access| Arc::new(FrontAppMenuSource::new(access)));
+        search_engine.register_source(Arc::new(ScheduledActionsSource::new(scheduler))).await;
+        search_engine.register_source(Arc::new(ProfileSource::new(profile_manager))).await;
+        search_engine.register_source(Arc::new(StatsSource::new(search_engine.clone()))).await;
+        search_engine.register_source(Arc::new(HistorySource::new(search_engine.clone()))).await;
+        search_engine.register_source(Arc::new(OpenWithSource::new(config.clone(), index_manager.clone(), search_engine.clone()))).await;
+        search_engine.register_source(Arc::new(DoctorSource::new(doctor, config.clone()))).await;
+        search_engine.register_source(Arc::new(SettingsSource::new(config.clone()))).await;
+        search_engine.register_source(Arc::new(CustomCommandSource::new(config.clone()))).await;
+        search_engine.register_source(Arc::new(QueryMacroSource::new(config.clone()))).await;
+        search_engine.register_source(Arc::new(PathCompletionSource::new(config.clone()))).await;
+        search_engine.register_source(Arc::new(ClipboardUndoSource::new(clipboard_undo.clone()))).await;
+        search_engine.register_source(Arc::new(SyncConflictSource::new(sync_manager.clone()))).await;
         
         Ok(Self {
+            mode,
             config,
             platform_provider,
             search_engine,
             plugin_system,
             index_manager,
             sync_manager,
+            action_executor,
+            app_refresher,
+            config_store,
+            connectivity,
+            power_policy,
+            menu_source,
+            tray_notice,
+            themes_dir,
+            clipboard_undo,
+            clipboard_suggestions,
+            task_snapshot_path,
+            events,
+            startup_guard: None,
+            instance,
+            pending_execution: None,
+            safe_mode: safe_mode.map(Arc::new),
+            heartbeat: Arc::new(Heartbeat::new()),
+            restart_ui: Arc::new(std::sync::atomic::AtomicBool::new(false)),
+            window_handle,
             ui: None,
         })
     }
     
-    pub async fn run(&mut self) -> Result<()> {
-        info!("Starting application...");
-        
-        // Initialize index in background
-        let index_manager = self.index_manager.clone();
-        let platform_provider = self.platform_provider.clone();
-        tokio::spawn(async move {
-            if let Err(e) = index_manager.rebuild_index(platform_provider).await {
-                error!("Failed to build index: {}", e);
+    // Each problem is logged on every start, but a set of problems is only announced once
+    async fn announce_include_path_problems(config_store: &ConfigStore, resolver: &PathResolver, platform_provider: &dyn PlatformProvider) {
+        let report = config_store.include_path_report().await;
+        for finding in &report.findings {
+            warn!("Search path problem: {}", finding.message());
+        }
+        let notice = IncludePathNotice::new(resolver.cache_file("include-path-notice.txt"));
+        if let Some(summary) = notice.take_summary(&report) {
+            if let Err(e) = platform_provider.show_notification("FalCommand", &summary) {
+                error!("Failed to show search path notification: {}", e);
             }
-        });
+        }
+    }
+    
+    // Startup counts as finished once the window is up
+    pub fn with_startup_guard(mut self, guard: StartupGuard) -> Self {
+        self.startup_guard = Some(guard);
+        self
+    }
+    
+    pub fn with_pending_execution(mut self, id: Option<ResultId>) -> Self {
+        self.pending_execution = id;
+        self
+    }
+    
+    // Everything a mode runs besides the window and desktop integration
+    async fn start_subsystems(&self, subsystems: Subsystems) -> Result<()> {
+        self.start_index(subsystems.index).await;
+        if subsystems.file_moves {
+            let paths = self.index_manager.include_paths().await;
+            Arc::new(FileMoveTracker::new(self.index_manager.clone(), self.search_engine.clone())).start(paths);
+        }
+        if subsystems.app_installs {
+            self.app_refresher.clone().start();
+        }
+        if subsystems.connectivity {
+            self.connectivity.clone().start();
+        }
         
         // Initialize plugins
-        self.plugin_system.load_plugins().await?;
+        if subsystems.plugins {
+            self.plugin_system.load_plugins().await?;
+            if subsystems.progress_notifications {
+                self.plugin_system.start_progress_notifications();
+            }
+            self.search_engine.register_hint_provider(self.plugin_system.clone()).await;
+        }
+        if subsystems.external_sources {
+            self.search_engine.register_source(self.plugin_system.clone()).await;
+            if let Some(ref source) = self.menu_source {
+                self.search_engine.register_source(source.clone()).await;
+            }
+        }
         
-        // Initialize UI
-        let ui = MainWindow::new(
-            self.search_engine.clone(),
-            self.config.clone(),
-        ).await.map_err(|e| AppError::Ui(e.to_string()))?;
+        // Resume actions scheduled in previous sessions
+        if subsystems.scheduler {
+            self.action_executor.start_scheduler();
+        }
+        if subsystems.history_maintenance {
+            self.search_engine.start_history_maintenance(self.power_policy.clone());
+        }
+        if subsystems.auto_sync {
+            if let Err(e) = self.sync_manager.start_auto_sync().await {
+                error!("Failed to start auto-sync: {}", e);
+            }
+        }
+        if subsystems.task_snapshots {
+            start_task_snapshots(self.task_snapshot_path.clone());
+        }
+        Ok(())
+    }
+    
+    pub async fn run(&mut self) -> Result<RunOutcome> {
+        info!("Starting application ({:?})...", self.mode);
+        let mut subsystems = Subsystems::for_mode(self.mode);
+        if let Some(ref source) = self.safe_mode {
+            subsystems = subsystems.safe_mode();
+            self.search_engine.register_source(source.clone()).await;
+        }
+        self.start_subsystems(subsystems).await?;
         
+        // Initialize UI
+        let ui = self.build_ui().await?;
+        *self.window_handle.lock().unwrap() = Some(ui.handle());
         self.ui = Some(ui);
         
-        // Initialize system tray if enabled (after UI is created)
+        // Initialize system tray if enabled (after UI is created). Desktops that can't show one are
+        // detected up front; there the window keeps its taskbar entry and a Quit result takes the
+        // tray menu's place
         let config = self.config.read().await;
-        if config.appearance.enable_system_tray {
-            // Try to initialize system tray, but don't fail if it's not available
-            if let Err(e) = self.initialize_system_tray().await {
-                error!("Failed to initialize system tray: {}. Continuing without system tray.", e);
+        let wanted = subsystems.tray && config.appearance.enable_system_tray;
+        let hotkey = config.behavior.hotkey.clone();
+        drop(config);
+        let supported = if wanted {
+            let platform_provider = self.platform_provider.clone();
+            tokio::task::spawn_blocking(move || platform_provider.system_tray_available()).await.unwrap_or(true)
+        } else {
+            true
+        };
+        let tray_mode = TrayMode::select(wanted, supported);
+        match tray_mode {
+            TrayMode::Tray => {
+                // Try to initialize system tray, but don't fail if it's not available
+                if let Err(e) = self.initialize_system_tray().await {
+                    error!("Failed to initialize system tray: {}. Continuing without system tray.", e);
+                }
             }
+            TrayMode::Fallback => {
+                info!("No system tray on this desktop; {} shows the window", hotkey);
+                if let Some(message) = self.tray_notice.take_message(&hotkey) {
+                    if let Err(e) = self.platform_provider.show_notification("FalCommand", &message) {
+                        warn!("Failed to show the tray notice: {}", e);
+                    }
+                }
+            }
+            TrayMode::Off => {}
+        }
+        // Without a tray menu, a resident launcher can still be quit from its results
+        if subsystems.tray && tray_mode != TrayMode::Tray {
+            self.search_engine.register_source(Arc::new(QuitSource)).await;
         }
-        drop(config);
         
-        // Register global hotkey
-        if let Err(e) = self.register_global_hotkey().await {
-            error!("Failed to register global hotkey: {}. Continuing without global hotkey.", e);
+        // Register global hotkeys
+        if subsystems.hotkeys {
+            if let Err(e) = self.register_global_hotkeys().await {
+                error!("Failed to register global hotkey: {}. Continuing without global hotkey.", e);
+            }
         }
         
-        // Show window on startup unless configured to start in tray
+        // Show window on startup unless configured to start in tray; one-shot runs always show it
         let config = self.config.read().await;
-        if !config.appearance.start_in_tray {
+        if self.mode == RunMode::Once || !config.appearance.start_in_tray {
             if let Some(ref ui) = self.ui {
                 ui.show().map_err(|e| AppError::Ui(e.to_string()))?;
             }
         }
         drop(config);
         
-        // Start UI event loop
-        if let Some(ref ui) = self.ui {
-            ui.run().await.map_err(|e| AppError::Ui(e.to_string()))?;
+        if let Some(ref guard) = self.startup_guard {
+            if let Err(e) = guard.complete() {
+                warn!("Failed to record finished startup: {}", e);
+            }
+        }
+        
+        if subsystems.jump_list {
+            self.start_jump_list().await;
+        }
+        self.start_forwarded_execution(subsystems.single_instance).await;
+        if subsystems.single_instance {
+            self.events.forward_from(self.index_manager.clone(), self.sync_manager.clone());
+        }
+        
+        if subsystems.watchdog {
+            self.start_watchdog().await;
+        }
+        
+        // Start UI event loop; a window replaced by the watchdog gets a fresh one, indexes and plugins stay loaded
+        loop {
+            if let Some(ref ui) = self.ui {
+                ui.run().await.map_err(|e| AppError::Ui(e.to_string()))?;
+            }
+            if !self.restart_ui.swap(false, std::sync::atomic::Ordering::SeqCst) {
+                break;
+            }
+            
+            info!("Restarting the window");
+            self.ui = None;
+            let ui = self.build_ui().await?;
+            *self.window_handle.lock().unwrap() = Some(ui.handle());
+            self.ui = Some(ui);
+        }
+        
+        if subsystems.single_instance {
+            self.instance.close();
         }
         
+        self.plugin_system.shutdown().await;
+        
+        let executed = self.ui.as_ref().is_some_and(|ui| ui.executed());
+        Ok(if executed { RunOutcome::Executed } else { RunOutcome::Dismissed })
+    }
+    
+    async fn build_ui(&self) -> Result<MainWindow> {
+        let mut ui = MainWindow::new(
+            self.search_engine.clone(),
+            self.action_executor.clone(),
+            self.config.clone(),
+        ).await.map_err(|e| AppError::Ui(e.to_string()))?
+        .with_result_refresher(self.plugin_system.clone())
+        .with_heartbeat(self.heartbeat.clone())
+        .with_config_changes(self.config_store.subscribe())
+        .with_themes(self.themes_dir.clone(), self.platform_provider.get_system_theme())
+        .with_clipboard_undo(self.clipboard_undo.clone())
+        .with_clipboard_suggestions(self.clipboard_suggestions.clone())
+        .with_events(self.events.clone());
+        if self.mode == RunMode::Once {
+            ui = ui.with_exit_after_execute();
+        }
+        if self.safe_mode.is_some() {
+            ui = ui.with_safe_mode_badge();
+        }
+        
+        // Resolve the theme now so the first hotkey press only has to show the window
+        ui.apply_theme().await.map_err(|e| AppError::Ui(e.to_string()))?;
+        Ok(ui)
+    }
+    
+    async fn start_watchdog(&self) {
+        let Some(metrics) = self.ui.as_ref().map(|ui| ui.metrics()) else {
+            return;
+        };
+        let config = self.config.read().await.watchdog.clone();
+        
+        let restart_ui = self.restart_ui.clone();
+        let window_handle = self.window_handle.clone();
+        let restart = Arc::new(move || {
+            restart_ui.store(true, std::sync::atomic::Ordering::SeqCst);
+            if let Some(ref handle) = *window_handle.lock().unwrap() {
+                handle.close();
+            }
+        });
+        
+        Arc::new(
+            UiWatchdog::new(self.heartbeat.clone(), self.platform_provider.clone(), metrics, config)
+                .with_restart(restart)
+        ).start();
+    }
+    
+    // `falcommand dev`: the search stack with plugins, driven from stdin instead of the window
+    pub async fn run_dev(&self) -> Result<()> {
+        self.start_index(IndexPolicy::CacheOrRebuild).await;
+        self.plugin_system.load_plugins().await?;
+        self.search_engine.register_hint_provider(self.plugin_system.clone()).await;
+        self.search_engine.register_source(self.plugin_system.clone()).await;
+        
+        let session = DevSession::new(
+            self.search_engine.clone(),
+            self.plugin_system.clone(),
+            self.index_manager.clone(),
+            self.platform_provider.clone(),
+            self.config.clone(),
+        ).await;
+        session.run().await.map_err(|e| AppError::Other(e.into()))?;
+        
+        self.plugin_system.shutdown().await;
         Ok(())
     }
     
+    // `falcommand search`: one query's results as plain text, laid out like the window's rows
+    // `page` is (offset, limit); without it the results are cut at max_results as in the window
+    pub async fn run_search(&self, query: &str, page: Option<(usize, usize)>) -> Result<String> {
+        self.start_index(IndexPolicy::CacheOrRebuild).await;
+        self.plugin_system.load_plugins().await?;
+        self.search_engine.register_source(self.plugin_system.clone()).await;
+        
+        let (results, footer) = match page {
+            Some((offset, limit)) => {
+                let page = self.search_engine.search_page(query, offset, limit, None).await?;
+                let footer = match page.next_offset {
+                    Some(next_offset) => format!("{}; next page: --offset {}", page.describe(), next_offset),
+                    None => page.describe(),
+                };
+                (page.results, Some(footer))
+            }
+            None => (self.search_engine.search(query).await, None),
+        };
+        let fields = RowFields::from_appearance(&self.config.read().await.appearance);
+        self.plugin_system.shutdown().await;
+        
+        let mut output = String::new();
+        for result in &results {
+            output.push_str(&plain_row(&result_row(result, fields)));
+            output.push('\n');
+        }
+        if let Some(footer) = footer {
+            output.push_str(&footer);
+            output.push('\n');
+        }
+        Ok(output)
+    }
+    
+    // `falcommand search --explain-ranking`: the ranked results with the parts of each score and why
+    // each one matched, as the developer REPL shows them
+    pub async fn run_explain_ranking(&self, query: &str) -> Result<String> {
+        self.start_index(IndexPolicy::CacheOrRebuild).await;
+        self.plugin_system.load_plugins().await?;
+        self.search_engine.register_source(self.plugin_system.clone()).await;
+        
+        let ranking = self.config.read().await.ranking.clone();
+        let results = self.search_engine.search_with_breakdown(query, &ranking).await;
+        self.plugin_system.shutdown().await;
+        Ok(render_results(&results))
+    }
+    
+    // `falcommand soak`: the resident stack without the window, searched at a steady rate. Writes a
+    // sample a minute and the summary to the report as JSON lines
+    pub async fn run_soak(&self, options: &SoakOptions, corpus: Vec<String>) -> Result<SoakSummary> {
+        let mut report = SoakReport::create(&options.report).map_err(|e| AppError::Other(e.into()))?;
+        let started = std::time::Instant::now();
+        self.start_subsystems(Subsystems::for_mode(RunMode::Soak)).await?;
+        
+        // Sync outcomes from auto-sync and the syncs below alike
+        let sync_stats = Arc::new(std::sync::Mutex::new(SubsystemStats::default()));
+        let mut sync_events = self.sync_manager.subscribe();
+        let sync_counter = {
+            let sync_stats = sync_stats.clone();
+            tokio::spawn(async move {
+                loop {
+                    let failed = match sync_events.recv().await {
+                        Ok(SyncEvent::Completed) => false,
+                        Ok(SyncEvent::Failed(_)) => true,
+                        Ok(_) | Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                    };
+                    let mut stats = sync_stats.lock().unwrap();
+                    stats.attempts += 1;
+                    stats.failures += u64::from(failed);
+                }
+            })
+        };
+        
+        // One search at a time; a slow search delays the next rather than piling up
+        let counters = Arc::new(SoakCounters::default());
+        let searcher = {
+            let search_engine = self.search_engine.clone();
+            let counters = counters.clone();
+            let mut interval = tokio::time::interval(options.search_interval());
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            tokio::spawn(async move {
+                for query in corpus.iter().cycle() {
+                    interval.tick().await;
+                    let searched_at = std::time::Instant::now();
+                    match tokio::time::timeout(SoakOptions::SEARCH_TIMEOUT, search_engine.search(query)).await {
+                        Ok(_) => counters.record_search(searched_at.elapsed()),
+                        Err(_) => {
+                            warn!("Soak search '{}' took over {:?}", query, SoakOptions::SEARCH_TIMEOUT);
+                            counters.record_failed_search();
+                        }
+                    }
+                }
+            })
+        };
+        
+        let mut samples = Vec::new();
+        loop {
+            let remaining = options.duration.saturating_sub(started.elapsed());
+            tokio::time::sleep(remaining.min(SoakOptions::SAMPLE_INTERVAL)).await;
+            
+            // Auto-sync runs far less often than samples; an upload per sample keeps sync under load
+            if let Err(e) = self.sync_manager.sync_up().await {
+                warn!("Soak sync failed: {}", e);
+            }
+            let plugins = self.plugin_system.search_stats();
+            let subsystems = std::collections::BTreeMap::from([
+                ("search".to_string(), counters.searches()),
+                ("sync".to_string(), *sync_stats.lock().unwrap()),
+                ("plugins".to_string(), SubsystemStats { attempts: plugins.searches, failures: plugins.failures }),
+            ]);
+            let sample = SoakSample::new(
+                started.elapsed(),
+                process_rss_bytes(),
+                TaskRegistry::global().counts(),
+                &counters.take_latencies(),
+                subsystems,
+            );
+            info!(
+                "Soak {}s: {} searches, p95 {:?} ms, {} tasks running",
+                sample.elapsed_secs, sample.searches, sample.p95_ms, sample.tasks.alive,
+            );
+            report.write(&SoakRecord::Sample(sample.clone())).map_err(|e| AppError::Other(e.into()))?;
+            samples.push(sample);
+            
+            if started.elapsed() >= options.duration {
+                break;
+            }
+        }
+        searcher.abort();
+        sync_counter.abort();
+        self.plugin_system.shutdown().await;
+        
+        let summary = SoakSummary::evaluate(&samples, started.elapsed(), &options.thresholds);
+        report.write(&SoakRecord::Summary(summary.clone())).map_err(|e| AppError::Other(e.into()))?;
+        Ok(summary)
+    }
+    
+    async fn start_index(&self, policy: IndexPolicy) {
+        // A cache that drifted from disk is served while it is rebuilt
+        let verification = self.index_manager.load_cache().await;
+        let usable = verification.as_ref().is_some_and(|verification| !verification.needs_rebuild());
+        if policy == IndexPolicy::CacheOrRebuild && usable {
+            info!("Using cached index without rebuilding");
+            Self::seed_usage(&self.search_engine, &self.index_manager, self.platform_provider.as_ref()).await;
+            return;
+        }
+        
+        // Initialize index in background
+        let index_manager = self.index_manager.clone();
+        let platform_provider = self.platform_provider.clone();
+        let search_engine = self.search_engine.clone();
+        tokio::spawn(async move {
+            match index_manager.rebuild_index(platform_provider.clone()).await {
+                Ok(()) => Self::seed_usage(&search_engine, &index_manager, platform_provider.as_ref()).await,
+                Err(e) => error!("Failed to build index: {}", e),
+            }
+        });
+    }
+    
+    // On a fresh install, common apps get a head start in ranking once the app index is there
+    async fn seed_usage(search_engine: &SearchEngine, index_manager: &IndexManager, platform_provider: &dyn PlatformProvider) {
+        let apps = index_manager.applications().await;
+        let usage = search_engine.usage_store().await;
+        match usage.seed_common_apps(&apps, &platform_provider.common_app_names()).await {
+            Ok(0) => {}
+            Ok(count) => info!("Seeded usage for {} common apps", count),
+            Err(e) => warn!("Failed to seed usage: {}", e),
+        }
+    }
+    
+    // Windows only: recent results in the taskbar icon's jump list
+    async fn start_jump_list(&self) {
+        let Some(jump_list) = falcommand_platform::jump_list() else {
+            return;
+        };
+        let executable = match std::env::current_exe() {
+            Ok(executable) => executable,
+            Err(e) => {
+                warn!("Failed to locate the executable for the jump list: {}", e);
+                return;
+            }
+        };
+        
+        let usage = self.search_engine.usage_store().await;
+        Arc::new(JumpListUpdater::new(usage, jump_list, executable)).start();
+    }
+    
+    // Runs results forwarded by later launches, and the one this launch was started with
+    async fn start_forwarded_execution(&self, listen: bool) {
+        let (sender, mut messages) = tokio::sync::mpsc::unbounded_channel();
+        if let Some(id) = self.pending_execution {
+            let _ = sender.send(InstanceMessage::Execute(id));
+        }
+        
+        if listen {
+            if let Err(e) = self.instance.listen(sender).await {
+                warn!("Failed to listen for other launches: {}", e);
+            }
+        }
+        
+        let search_engine = self.search_engine.clone();
+        let action_executor = self.action_executor.clone();
+        tokio::spawn(async move {
+            while let Some(InstanceMessage::Execute(id)) = messages.recv().await {
+                let Some(result) = search_engine.find_by_id(id).await else {
+                    warn!("Result {} from another launch was not found", id);
+                    continue;
+                };
+                
+                info!("Executing forwarded result: {}", result.title);
+                match action_executor.execute(&result.action).await {
+                    Ok(()) => search_engine.record_launch(&result).await,
+                    Err(e) => error!("Failed to execute forwarded result {}: {}", id, e),
+                }
+            }
+        });
+    }
+    
     async fn initialize_system_tray(&self) -> Result<()> {
         info!("Initializing system tray...");
         
@@ -152,15 +911,27 @@ impl App {
             info!("Show requested from system tray (UI handle not captured in this build)");
         });
         
-        let quit_callback = Box::new(|| {
+        let window_handle = self.window_handle.clone();
+        let quit_callback = Box::new(move || {
             info!("Quit requested from system tray");
-            std::process::exit(0);
+            if let Some(ref handle) = *window_handle.lock().unwrap() {
+                handle.close();
+            }
         });
         
         self.platform_provider
             .update_system_tray_menu(show_callback, quit_callback)
             .map_err(|e| AppError::Platform(e.to_string()))?;
         
+        // Keep the tooltip in step with the hotkey, index and sync state
+        Arc::new(TrayStatusUpdater::new(
+            self.config.clone(),
+            self.platform_provider.clone(),
+            self.index_manager.clone(),
+            self.sync_manager.clone(),
+            icon_data,
+        ).with_background_tasks(self.plugin_system.clone())).start();
+        
         info!("System tray initialized successfully");
         Ok(())
     }
@@ -176,19 +947,116 @@ impl App {
         icon_data
     }
 
-    async fn register_global_hotkey(&self) -> Result<()> {
-        let config = self.config.read().await;
-        let hotkey = &config.behavior.hotkey;
+    // The primary (toggle) hotkey must register; other intents only warn when they can't
+    async fn register_global_hotkeys(&self) -> Result<()> {
+        let bindings = self.config.read().await.behavior.hotkey_bindings();
+        let trigger_prefixes = self.plugin_system.trigger_prefixes().await;
+        if self.window_handle.lock().unwrap().is_none() {
+            return Err(AppError::Ui("Window is not initialized".to_string()));
+        }
 
-        self.platform_provider
-            .register_global_hotkey(hotkey, Box::new(move || {
-                info!("Global hotkey triggered (toggle visibility not wired in this build)");
-            }))
-            .map_err(|e| AppError::Platform(e.to_string()))?;
-        
-        info!("Registered global hotkey: {}", hotkey);
+        for (intent, chord) in bindings {
+            let Some(action) = resolve_intent(&intent, &trigger_prefixes) else {
+                warn!("Hotkey {} targets '{}', which is not a loaded plugin with a prefix", chord, intent);
+                continue;
+            };
+
+            // Looked up on each press so the hotkey follows a window restarted by the watchdog
+            let window_handle = self.window_handle.clone();
+            let handle = move || window_handle.lock().unwrap().clone();
+            // The frontmost app is captured before the launcher takes focus
+            let menu_source = self.menu_source.clone();
+            let platform_provider = self.platform_provider.clone();
+            let search_engine = self.search_engine.clone();
+            let capture = move || {
+                if let Some(ref source) = menu_source {
+                    source.capture_foreground();
+                }
+                let app = platform_provider.get_foreground_window()
+                    .filter(|app| app.pid != std::process::id() as i32);
+                search_engine.set_foreground_app(app.map(|app| app.name));
+            };
+            let callback: Box<dyn Fn() + Send> = match action.clone() {
+                HotkeyIntent::Toggle => Box::new(move || {
+                    capture();
+                    if let Some(handle) = handle() {
+                        handle.toggle();
+                    }
+                }),
+                HotkeyIntent::Prefill(prefix) => Box::new(move || {
+                    capture();
+                    if let Some(handle) = handle() {
+                        handle.show_with_query(prefix.clone());
+                    }
+                }),
+            };
+
+            match self.platform_provider.register_global_hotkey(&chord, callback) {
+                Ok(()) => info!("Registered global hotkey {} for '{}'", chord, intent),
+                Err(e) if action == HotkeyIntent::Toggle => return Err(AppError::Platform(e.to_string())),
+                Err(e) => warn!("Failed to register hotkey {} for '{}': {}", chord, intent, e),
+            }
+        }
         Ok(())
     }
 }
 
-pub type Result<T> = std::result::Result<T, AppError>;
\ No newline at end of file
+pub type Result<T> = std::result::Result<T, AppError>;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn once_runs_only_what_a_single_launch_needs() {
+        let once = Subsystems::for_mode(RunMode::Once);
+        assert!(once.plugins && once.external_sources);
+        assert!(!once.tray && !once.hotkeys && !once.scheduler && !once.connectivity && !once.auto_sync);
+        assert!(!once.single_instance && !once.jump_list && !once.watchdog && !once.task_snapshots);
+        assert!(!once.file_moves && !once.app_installs && !once.history_maintenance);
+        assert_eq!(once.index, IndexPolicy::CacheOrRebuild);
+    }
+
+    #[test]
+    fn resident_runs_everything() {
+        let resident = Subsystems::for_mode(RunMode::Resident);
+        assert_eq!(resident, Subsystems::for_mode(RunMode::default()));
+        assert!(resident.tray && resident.hotkeys && resident.scheduler && resident.connectivity);
+        assert!(resident.auto_sync && resident.progress_notifications && resident.history_maintenance);
+        assert!(resident.single_instance && resident.jump_list && resident.watchdog && resident.task_snapshots);
+        assert!(resident.file_moves && resident.app_installs);
+        assert_eq!(resident.index, IndexPolicy::CacheThenRebuild);
+    }
+
+    #[test]
+    fn soak_keeps_the_background_work_but_no_desktop_integration() {
+        let soak = Subsystems::for_mode(RunMode::Soak);
+        assert!(soak.scheduler && soak.connectivity && soak.auto_sync && soak.file_moves && soak.app_installs);
+        assert!(!soak.tray && !soak.hotkeys && !soak.single_instance && !soak.jump_list && !soak.watchdog);
+        // The resident launcher's task snapshot is left alone
+        assert!(!soak.task_snapshots);
+    }
+
+    #[test]
+    fn only_once_exits_with_the_outcome() {
+        assert_eq!(RunMode::Once.exit_code(RunOutcome::Executed), Some(0));
+        assert_eq!(RunMode::Once.exit_code(RunOutcome::Dismissed), Some(1));
+        assert_eq!(RunMode::Resident.exit_code(RunOutcome::Dismissed), None);
+        assert_eq!(RunMode::Soak.exit_code(RunOutcome::Executed), None);
+    }
+
+    #[test]
+    fn safe_mode_drops_plugins_sync_and_background_work() {
+        let resident = Subsystems::for_mode(RunMode::Resident);
+        let safe = resident.safe_mode();
+        assert!(!safe.plugins && !safe.external_sources && !safe.auto_sync && !safe.connectivity);
+        assert!(!safe.scheduler && !safe.file_moves && !safe.app_installs && !safe.progress_notifications);
+        // The index is never rebuilt in the background
+        assert_eq!(safe.index, IndexPolicy::CacheOrRebuild);
+        // The launcher itself still works as usual
+        assert!(safe.tray && safe.hotkeys && safe.single_instance && safe.watchdog);
+
+        // Safe mode only ever takes subsystems away
+        let once = Subsystems::for_mode(RunMode::Once).safe_mode();
+        assert!(!once.plugins && !once.external_sources && !once.tray && !once.hotkeys);
+    }
+}
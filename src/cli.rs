@@ -0,0 +1,457 @@
+use std::path::PathBuf;
+use log::warn;
+
+use falcommand_config::{ResultId, Theme};
+use falcommand_core::{
+    parse_soak_duration, EventFilter, ExportFormat, PageRequest, SoakThresholds, DEFAULT_PAGE_SIZE, EXECUTE_ID_FLAG, SAFE_MODE_FLAG,
+};
+
+// Subcommands that run without starting the launcher
+#[derive(Debug, Clone, PartialEq)]
+pub enum CliCommand {
+    // falcommand stats export [--format csv|json] [--output <file>]
+    StatsExport {
+        format: ExportFormat,
+        output: Option<PathBuf>,
+    },
+    // falcommand config schema
+    ConfigSchema,
+    // falcommand config doc
+    ConfigDoc,
+    // falcommand dev [--profile <name>] [--portable]
+    Dev,
+    // falcommand index rebuild [--force]
+    IndexRebuild {
+        force: bool,
+    },
+    // falcommand index status
+    IndexStatus,
+    // falcommand status [--profile <name>] [--portable]: the index, the tray and whether background work runs
+    Status,
+    // falcommand doctor [--profile <name>] [--portable]
+    Doctor,
+    // falcommand search <query> [--offset <n>] [--limit <n>] [--explain-ranking] [--profile <name>] [--portable]
+    Search {
+        query: String,
+        // Either one pages through up to MAX_PAGED_RESULTS results instead of the usual max_results
+        offset: Option<usize>,
+        limit: Option<usize>,
+        // Print how each score came about and why each result matched instead of the plain rows
+        explain_ranking: bool,
+    },
+    // falcommand theme export [light|dark] [--output <file>] [--profile <name>] [--portable]
+    ThemeExport {
+        // Light or Dark; None is the base of the configured theme
+        theme: Option<Theme>,
+        output: Option<PathBuf>,
+    },
+    // falcommand soak [--duration 2h] [--qps 5] [--corpus <file>] [--report <file>]
+    //   [--max-rss-growth <MB/h>] [--max-error-rate <fraction>] [--profile <name>] [--portable]
+    Soak {
+        duration: std::time::Duration,
+        qps: f64,
+        // One query per line; None cycles through the built-in queries
+        corpus: Option<PathBuf>,
+        report: PathBuf,
+        thresholds: SoakThresholds,
+    },
+    // falcommand rpc subscribe [--events sync,index] [--profile <name>] [--portable]: the running
+    // launcher's events as JSON lines until it exits
+    RpcSubscribe {
+        filter: EventFilter,
+    },
+    // falcommand rpc search <query> [--offset <n>] [--limit <n>] [--generation <n>] [--profile <name>] [--portable]:
+    // one page of the running launcher's ranking as a JSON line
+    RpcSearch {
+        request: PageRequest,
+    },
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct CliArgs {
+    pub profile: Option<String>,
+    pub portable: bool,
+    // Show the window, execute one result and exit
+    pub once: bool,
+    // Default config, no plugins, sync or external sources
+    pub safe_mode: bool,
+    // Run this result in the running instance (jump list entries)
+    pub execute_id: Option<ResultId>,
+    pub command: Option<CliCommand>,
+}
+
+impl CliArgs {
+    pub fn parse<I>(args: I) -> Self
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut parsed = Self::default();
+        let mut args = args.into_iter().peekable();
+
+        if args.peek().map(String::as_str) == Some("stats") {
+            args.next();
+            return Self::parse_stats(args);
+        }
+        if args.peek().map(String::as_str) == Some("config") {
+            args.next();
+            return Self::parse_config(args);
+        }
+        if args.peek().map(String::as_str) == Some("index") {
+            args.next();
+            return Self::parse_index(args);
+        }
+        if args.peek().map(String::as_str) == Some("search") {
+            args.next();
+            return Self::parse_search(args);
+        }
+        if args.peek().map(String::as_str) == Some("theme") {
+            args.next();
+            return Self::parse_theme(args);
+        }
+        if args.peek().map(String::as_str) == Some("soak") {
+            args.next();
+            return Self::parse_soak(args);
+        }
+        if args.peek().map(String::as_str) == Some("rpc") {
+            args.next();
+            return Self::parse_rpc(args);
+        }
+
+        // The REPL, doctor and status take the same --profile/--portable flags as the launcher
+        if args.peek().map(String::as_str) == Some("dev") {
+            args.next();
+            parsed.command = Some(CliCommand::Dev);
+        } else if args.peek().map(String::as_str) == Some("doctor") {
+            args.next();
+            parsed.command = Some(CliCommand::Doctor);
+        } else if args.peek().map(String::as_str) == Some("status") {
+            args.next();
+            parsed.command = Some(CliCommand::Status);
+        }
+
+        while let Some(arg) = args.next() {
+            if arg == "--profile" {
+                match args.next() {
+                    Some(name) => parsed.profile = Some(name),
+                    None => warn!("--profile requires a profile name"),
+                }
+            } else if arg == "--portable" {
+                parsed.portable = true;
+            } else if arg == "--once" {
+                parsed.once = true;
+            } else if arg == SAFE_MODE_FLAG {
+                parsed.safe_mode = true;
+            } else if arg == EXECUTE_ID_FLAG {
+                match args.next().and_then(|id| id.parse().ok()) {
+                    Some(id) => parsed.execute_id = Some(id),
+                    None => warn!("{} requires a result id", EXECUTE_ID_FLAG),
+                }
+            } else if let Some(name) = arg.strip_prefix("--profile=") {
+                parsed.profile = Some(name.to_string());
+            } else {
+                warn!("Ignoring unknown argument: {}", arg);
+            }
+        }
+
+        parsed
+    }
+
+    fn parse_config<I>(mut args: I) -> Self
+    where
+        I: Iterator<Item = String>,
+    {
+        let command = match args.next().as_deref() {
+            Some("schema") => Some(CliCommand::ConfigSchema),
+            Some("doc") => Some(CliCommand::ConfigDoc),
+            _ => {
+                warn!("Usage: falcommand config schema|doc");
+                None
+            }
+        };
+        for arg in args {
+            warn!("Ignoring unknown argument: {}", arg);
+        }
+        Self { command, ..Self::default() }
+    }
+
+    fn parse_index<I>(mut args: I) -> Self
+    where
+        I: Iterator<Item = String>,
+    {
+        let mut parsed = Self::default();
+        let mut command = match args.next().as_deref() {
+            Some("rebuild") => Some(CliCommand::IndexRebuild { force: false }),
+            Some("status") => Some(CliCommand::IndexStatus),
+            _ => {
+                warn!("Usage: falcommand index rebuild [--force] | falcommand index status");
+                return parsed;
+            }
+        };
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                // Retry folders that are backing off after failed scans
+                "--force" => match command {
+                    Some(CliCommand::IndexRebuild { ref mut force }) => *force = true,
+                    _ => warn!("--force only applies to index rebuild"),
+                },
+                "--portable" => parsed.portable = true,
+                "--profile" => parsed.profile = args.next(),
+                _ => match arg.strip_prefix("--profile=") {
+                    Some(name) => parsed.profile = Some(name.to_string()),
+                    None => warn!("Ignoring unknown argument: {}", arg),
+                },
+            }
+        }
+
+        parsed.command = command;
+        parsed
+    }
+
+    // Words that aren't flags make up the query, so it needs no quoting
+    fn parse_search<I>(mut args: I) -> Self
+    where
+        I: Iterator<Item = String>,
+    {
+        let mut parsed = Self::default();
+        let mut words = Vec::new();
+        let (mut offset, mut limit) = (None, None);
+        let mut explain_ranking = false;
+        while let Some(arg) = args.next() {
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value.to_string())),
+                _ => (arg.clone(), None),
+            };
+
+            match flag.as_str() {
+                "--portable" => parsed.portable = true,
+                "--profile" => parsed.profile = inline.or_else(|| args.next()),
+                "--offset" => match inline.or_else(|| args.next()).and_then(|value| value.parse().ok()) {
+                    Some(value) => offset = Some(value),
+                    None => warn!("--offset requires a number"),
+                },
+                "--limit" => match inline.or_else(|| args.next()).and_then(|value| value.parse().ok()) {
+                    Some(value) if value > 0 => limit = Some(value),
+                    _ => warn!("--limit requires a number above 0"),
+                },
+                "--explain-ranking" => explain_ranking = true,
+                _ => words.push(arg),
+            }
+        }
+
+        if words.is_empty() {
+            warn!("Usage: falcommand search <query> [--offset <n>] [--limit <n>] [--explain-ranking]");
+            return parsed;
+        }
+        if explain_ranking && (offset.is_some() || limit.is_some()) {
+            warn!("--explain-ranking lists the first max_results results; --offset and --limit are ignored");
+        }
+        parsed.command = Some(CliCommand::Search { query: words.join(" "), offset, limit, explain_ranking });
+        parsed
+    }
+
+    fn parse_theme<I>(mut args: I) -> Self
+    where
+        I: Iterator<Item = String>,
+    {
+        let mut parsed = Self::default();
+        if args.next().as_deref() != Some("export") {
+            warn!("Usage: falcommand theme export [light|dark] [--output <file>]");
+            return parsed;
+        }
+
+        let mut theme = None;
+        let mut output = None;
+        while let Some(arg) = args.next() {
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value.to_string())),
+                _ => (arg.clone(), None),
+            };
+
+            match flag.as_str() {
+                "light" => theme = Some(Theme::Light),
+                "dark" => theme = Some(Theme::Dark),
+                "--output" => match inline.or_else(|| args.next()) {
+                    Some(path) => output = Some(PathBuf::from(path)),
+                    None => warn!("--output requires a file path"),
+                },
+                "--portable" => parsed.portable = true,
+                "--profile" => parsed.profile = inline.or_else(|| args.next()),
+                _ => warn!("Ignoring unknown argument: {}", arg),
+            }
+        }
+
+        parsed.command = Some(CliCommand::ThemeExport { theme, output });
+        parsed
+    }
+
+    fn parse_soak<I>(mut args: I) -> Self
+    where
+        I: Iterator<Item = String>,
+    {
+        let mut parsed = Self::default();
+        let mut duration = std::time::Duration::from_secs(2 * 3600);
+        let mut qps = 5.0;
+        let mut corpus = None;
+        let mut report = PathBuf::from("falcommand-soak.jsonl");
+        let mut thresholds = SoakThresholds::default();
+        while let Some(arg) = args.next() {
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value.to_string())),
+                _ => (arg.clone(), None),
+            };
+
+            match flag.as_str() {
+                "--duration" => match inline.or_else(|| args.next()).and_then(|value| parse_soak_duration(&value)) {
+                    Some(value) => duration = value,
+                    None => warn!("--duration requires a length such as 2h, 90m or 1h30m"),
+                },
+                "--qps" => match inline.or_else(|| args.next()).and_then(|value| value.parse::<f64>().ok()) {
+                    Some(value) if value > 0.0 && value.is_finite() => qps = value,
+                    _ => warn!("--qps requires a number above 0"),
+                },
+                "--corpus" => match inline.or_else(|| args.next()) {
+                    Some(path) => corpus = Some(PathBuf::from(path)),
+                    None => warn!("--corpus requires a file path"),
+                },
+                "--report" => match inline.or_else(|| args.next()) {
+                    Some(path) => report = PathBuf::from(path),
+                    None => warn!("--report requires a file path"),
+                },
+                "--max-rss-growth" => match inline.or_else(|| args.next()).and_then(|value| value.parse::<f64>().ok()) {
+                    Some(value) if value >= 0.0 => thresholds.max_rss_growth_mb_per_hour = value,
+                    _ => warn!("--max-rss-growth requires megabytes per hour"),
+                },
+                "--max-error-rate" => match inline.or_else(|| args.next()).and_then(|value| value.parse::<f64>().ok()) {
+                    Some(value) if (0.0..=1.0).contains(&value) => thresholds.max_error_rate = value,
+                    _ => warn!("--max-error-rate requires a fraction between 0 and 1"),
+                },
+                "--portable" => parsed.portable = true,
+                "--profile" => parsed.profile = inline.or_else(|| args.next()),
+                _ => warn!("Ignoring unknown argument: {}", arg),
+            }
+        }
+
+        parsed.command = Some(CliCommand::Soak { duration, qps, corpus, report, thresholds });
+        parsed
+    }
+
+    fn parse_rpc<I>(mut args: I) -> Self
+    where
+        I: Iterator<Item = String>,
+    {
+        let mut parsed = Self::default();
+        match args.next().as_deref() {
+            Some("subscribe") => {}
+            Some("search") => return Self::parse_rpc_search(args),
+            _ => {
+                warn!("Usage: falcommand rpc subscribe [--events index,sync,search,window,execution]");
+                warn!("       falcommand rpc search <query> [--offset <n>] [--limit <n>] [--generation <n>]");
+                return parsed;
+            }
+        }
+
+        let mut filter = EventFilter::default();
+        while let Some(arg) = args.next() {
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value.to_string())),
+                _ => (arg.clone(), None),
+            };
+
+            match flag.as_str() {
+                "--events" => match inline.or_else(|| args.next()).map(|list| EventFilter::parse(&list)) {
+                    Some(Ok(value)) => filter = value,
+                    Some(Err(e)) => {
+                        warn!("{}", e);
+                        return parsed;
+                    }
+                    None => warn!("--events requires a comma-separated list of event kinds"),
+                },
+                "--portable" => parsed.portable = true,
+                "--profile" => parsed.profile = inline.or_else(|| args.next()),
+                _ => warn!("Ignoring unknown argument: {}", arg),
+            }
+        }
+
+        parsed.command = Some(CliCommand::RpcSubscribe { filter });
+        parsed
+    }
+
+    fn parse_rpc_search<I>(mut args: I) -> Self
+    where
+        I: Iterator<Item = String>,
+    {
+        let mut parsed = Self::default();
+        let mut words = Vec::new();
+        let mut request = PageRequest::first(String::new(), DEFAULT_PAGE_SIZE);
+        while let Some(arg) = args.next() {
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value.to_string())),
+                _ => (arg.clone(), None),
+            };
+
+            match flag.as_str() {
+                "--portable" => parsed.portable = true,
+                "--profile" => parsed.profile = inline.or_else(|| args.next()),
+                "--offset" => match inline.or_else(|| args.next()).and_then(|value| value.parse().ok()) {
+                    Some(value) => request.offset = value,
+                    None => warn!("--offset requires a number"),
+                },
+                "--limit" => match inline.or_else(|| args.next()).and_then(|value| value.parse().ok()) {
+                    Some(value) if value > 0 => request.limit = value,
+                    _ => warn!("--limit requires a number above 0"),
+                },
+                "--generation" => match inline.or_else(|| args.next()).and_then(|value| value.parse().ok()) {
+                    Some(value) => request.generation = Some(value),
+                    None => warn!("--generation requires the number a previous page returned"),
+                },
+                _ => words.push(arg),
+            }
+        }
+
+        if words.is_empty() {
+            warn!("Usage: falcommand rpc search <query> [--offset <n>] [--limit <n>] [--generation <n>]");
+            return parsed;
+        }
+        request.query = words.join(" ");
+        parsed.command = Some(CliCommand::RpcSearch { request });
+        parsed
+    }
+
+    fn parse_stats<I>(mut args: I) -> Self
+    where
+        I: Iterator<Item = String>,
+    {
+        let mut parsed = Self::default();
+        if args.next().as_deref() != Some("export") {
+            warn!("Usage: falcommand stats export [--format csv|json] [--output <file>]");
+            return parsed;
+        }
+
+        let mut format = ExportFormat::Csv;
+        let mut output = None;
+        while let Some(arg) = args.next() {
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+                None => (arg.clone(), None),
+            };
+
+            match flag.as_str() {
+                "--format" => match inline.or_else(|| args.next()).as_deref().and_then(ExportFormat::parse) {
+                    Some(parsed_format) => format = parsed_format,
+                    None => warn!("--format must be csv or json"),
+                },
+                "--output" => match inline.or_else(|| args.next()) {
+                    Some(path) => output = Some(PathBuf::from(path)),
+                    None => warn!("--output requires a file path"),
+                },
+                "--portable" => parsed.portable = true,
+                "--profile" => parsed.profile = inline.or_else(|| args.next()),
+                _ => warn!("Ignoring unknown argument: {}", arg),
+            }
+        }
+
+        parsed.command = Some(CliCommand::StatsExport { format, output });
+        parsed
+    }
+}
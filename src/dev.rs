@@ -0,0 +1,391 @@
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::RwLock;
+use serde_json::Value;
+
+use falcommand_config::{Config, SearchResult};
+use falcommand_config::schema::{get_config_value, set_config_value};
+use falcommand_platform::PlatformProvider;
+use falcommand_core::{category_label, IndexManager, ScoreBreakdown, SearchEngine};
+use falcommand_plugins::PluginSystem;
+
+pub const DEV_HELP: &str = "\
+<query> | q <query>              run a query and show the ranked results
+set <path> <value>               change a setting for this session, e.g. set ranking.usage_weight 0.4
+get <path>                       show a setting
+reset                            undo every set
+reload                           reload plugins
+rebuild                          rebuild the index
+diff <query> | <query>           compare the results of two queries
+diff-set <path> <value> <query>  compare a query's results before and after a set
+help                             show this
+quit";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DevCommand {
+    Query(String),
+    Set { path: String, value: Value },
+    Get(String),
+    Reset,
+    ReloadPlugins,
+    Rebuild,
+    DiffQueries(String, String),
+    DiffSetting { path: String, value: Value, query: String },
+    Help,
+    Quit,
+}
+
+// Values are JSON where they parse as JSON, so `0.4` is a number and `firefox` a string
+fn parse_value(text: &str) -> Value {
+    serde_json::from_str(text).unwrap_or_else(|_| Value::String(text.to_string()))
+}
+
+// None for a blank line. A line that doesn't start with a command is a query
+pub fn parse_command(line: &str) -> Result<Option<DevCommand>, String> {
+    let line = line.trim();
+    if line.is_empty() {
+        return Ok(None);
+    }
+    let (word, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    let rest = rest.trim();
+
+    let command = match word {
+        "q" if !rest.is_empty() => DevCommand::Query(rest.to_string()),
+        "set" => {
+            let (path, value) = rest.split_once(char::is_whitespace)
+                .ok_or("Usage: set <path> <value>")?;
+            DevCommand::Set { path: path.to_string(), value: parse_value(value.trim()) }
+        }
+        "get" if !rest.is_empty() => DevCommand::Get(rest.to_string()),
+        "get" => return Err("Usage: get <path>".to_string()),
+        "reset" => DevCommand::Reset,
+        "reload" => DevCommand::ReloadPlugins,
+        "rebuild" => DevCommand::Rebuild,
+        "diff" => {
+            let (left, right) = rest.split_once('|').ok_or("Usage: diff <query> | <query>")?;
+            let (left, right) = (left.trim(), right.trim());
+            if left.is_empty() || right.is_empty() {
+                return Err("Usage: diff <query> | <query>".to_string());
+            }
+            DevCommand::DiffQueries(left.to_string(), right.to_string())
+        }
+        "diff-set" => {
+            let mut parts = rest.splitn(3, char::is_whitespace);
+            match (parts.next(), parts.next(), parts.next().map(str::trim)) {
+                (Some(path), Some(value), Some(query)) if !query.is_empty() => DevCommand::DiffSetting {
+                    path: path.to_string(),
+                    value: parse_value(value),
+                    query: query.to_string(),
+                },
+                _ => return Err("Usage: diff-set <path> <value> <query>".to_string()),
+            }
+        }
+        "help" => DevCommand::Help,
+        "quit" | "exit" => DevCommand::Quit,
+        _ => DevCommand::Query(line.to_string()),
+    };
+    Ok(Some(command))
+}
+
+fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+    let mut truncated: String = text.chars().take(width.saturating_sub(1)).collect();
+    truncated.push('…');
+    truncated
+}
+
+fn fuzzy_cell(fuzzy: Option<f64>) -> String {
+    fuzzy.map(|fuzzy| format!("{:.3}", fuzzy)).unwrap_or_else(|| "-".to_string())
+}
+
+fn reason_cell(result: &SearchResult) -> String {
+    result.match_reason.as_ref().map(|reason| reason.describe()).unwrap_or_else(|| "-".to_string())
+}
+
+// One row per result with the parts of its score and why it matched
+pub fn render_results(results: &[(SearchResult, ScoreBreakdown)]) -> String {
+    if results.is_empty() {
+        return "(no results)\n".to_string();
+    }
+
+    let mut table = format!(
+        "{:>3}  {:>6}  {:>6}  {:>6}  {:>6}  {:>6}  {:<12}  {:<20}  {}\n",
+        "#", "total", "source", "fuzzy", "select", "usage", "category", "matched", "title"
+    );
+    for (position, (result, breakdown)) in results.iter().enumerate() {
+        table.push_str(&format!(
+            "{:>3}  {:>6.3}  {:>6.3}  {:>6}  {:>6.3}  {:>6.3}  {:<12}  {:<20}  {}\n",
+            position + 1,
+            breakdown.total,
+            breakdown.source,
+            fuzzy_cell(breakdown.fuzzy),
+            breakdown.selection,
+            breakdown.usage,
+            truncate(&category_label(&result.category), 12),
+            truncate(&reason_cell(result), 20),
+            result.title,
+        ));
+    }
+    table
+}
+
+// How a result on the right moved relative to the left: "=", "^2" (up two), "v1" or "new"
+fn movement(left: &[(SearchResult, ScoreBreakdown)], result: &SearchResult, position: usize) -> String {
+    match left.iter().position(|(other, _)| other.id == result.id) {
+        Some(before) if before == position => "=".to_string(),
+        Some(before) if before > position => format!("^{}", before - position),
+        Some(before) => format!("v{}", position - before),
+        None => "new".to_string(),
+    }
+}
+
+// Both lists side by side, with how each result on the right moved and what dropped out
+pub fn render_diff(
+    left_label: &str,
+    left: &[(SearchResult, ScoreBreakdown)],
+    right_label: &str,
+    right: &[(SearchResult, ScoreBreakdown)],
+) -> String {
+    const WIDTH: usize = 36;
+
+    let cell = |entry: Option<&(SearchResult, ScoreBreakdown)>| match entry {
+        Some((result, breakdown)) => truncate(&format!("{:.3} {}", breakdown.total, result.title), WIDTH),
+        None => String::new(),
+    };
+
+    let mut diff = format!("{:>3}  {:<WIDTH$}  {:<WIDTH$}  {}\n", "#", truncate(left_label, WIDTH), truncate(right_label, WIDTH), "move");
+    for position in 0..left.len().max(right.len()) {
+        let moved = right.get(position)
+            .map(|(result, _)| movement(left, result, position))
+            .unwrap_or_default();
+        diff.push_str(&format!(
+            "{:>3}  {:<WIDTH$}  {:<WIDTH$}  {}\n",
+            position + 1,
+            cell(left.get(position)),
+            cell(right.get(position)),
+            moved,
+        ));
+    }
+
+    let dropped: Vec<&str> = left.iter()
+        .filter(|(result, _)| !right.iter().any(|(other, _)| other.id == result.id))
+        .map(|(result, _)| result.title.as_str())
+        .collect();
+    if !dropped.is_empty() {
+        diff.push_str(&format!("dropped: {}\n", dropped.join(", ")));
+    }
+    diff
+}
+
+// `falcommand dev`: queries with score breakdowns and ranking experiments over stdin/stdout.
+// Settings changed here are never saved
+pub struct DevSession {
+    search_engine: Arc<SearchEngine>,
+    plugin_system: Arc<PluginSystem>,
+    index_manager: Arc<IndexManager>,
+    platform_provider: Arc<dyn PlatformProvider>,
+    config: Arc<RwLock<Config>>,
+    original: Config,
+}
+
+impl DevSession {
+    pub async fn new(
+        search_engine: Arc<SearchEngine>,
+        plugin_system: Arc<PluginSystem>,
+        index_manager: Arc<IndexManager>,
+        platform_provider: Arc<dyn PlatformProvider>,
+        config: Arc<RwLock<Config>>,
+    ) -> Self {
+        let original = config.read().await.clone();
+        Self { search_engine, plugin_system, index_manager, platform_provider, config, original }
+    }
+
+    pub async fn run(&self) -> std::io::Result<()> {
+        let mut stdout = tokio::io::stdout();
+        let mut lines = BufReader::new(tokio::io::stdin()).lines();
+        stdout.write_all(b"FalCommand developer mode. Type 'help' for commands.\n").await?;
+
+        loop {
+            stdout.write_all(b"dev> ").await?;
+            stdout.flush().await?;
+            let Some(line) = lines.next_line().await? else {
+                break;
+            };
+
+            let output = match parse_command(&line) {
+                Ok(Some(DevCommand::Quit)) => break,
+                Ok(Some(command)) => self.execute(command).await,
+                Ok(None) => continue,
+                Err(usage) => usage,
+            };
+            stdout.write_all(output.as_bytes()).await?;
+            if !output.ends_with('\n') {
+                stdout.write_all(b"\n").await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn ranked(&self, query: &str, config: &Config) -> Vec<(SearchResult, ScoreBreakdown)> {
+        self.search_engine.search_with_breakdown(query, &config.ranking).await
+    }
+
+    async fn execute(&self, command: DevCommand) -> String {
+        match command {
+            DevCommand::Query(query) => {
+                let config = self.config.read().await.clone();
+                render_results(&self.ranked(&query, &config).await)
+            }
+            DevCommand::Set { path, value } => {
+                let mut config = self.config.write().await;
+                match set_config_value(&config, &path, value) {
+                    Ok(updated) => {
+                        *config = updated;
+                        format!("{} = {}", path, get_config_value(&config, &path).unwrap_or_default())
+                    }
+                    Err(e) => e,
+                }
+            }
+            DevCommand::Get(path) => match get_config_value(&*self.config.read().await, &path) {
+                Ok(value) => format!("{} = {}", path, value),
+                Err(e) => e,
+            },
+            DevCommand::Reset => {
+                *self.config.write().await = self.original.clone();
+                "Settings restored".to_string()
+            }
+            DevCommand::ReloadPlugins => match self.plugin_system.reload_plugins().await {
+                Ok(()) => "Plugins reloaded".to_string(),
+                Err(e) => format!("Failed to reload plugins: {}", e),
+            },
+            DevCommand::Rebuild => match self.index_manager.rebuild_index(self.platform_provider.clone()).await {
+                Ok(()) => {
+                    let stats = self.index_manager.get_index_stats().await;
+                    format!("Index rebuilt: {} apps, {} files", stats.app_count, stats.file_count)
+                }
+                Err(e) => format!("Failed to rebuild the index: {}", e),
+            },
+            DevCommand::DiffQueries(left, right) => {
+                let config = self.config.read().await.clone();
+                let (left_results, right_results) = tokio::join!(self.ranked(&left, &config), self.ranked(&right, &config));
+                render_diff(&left, &left_results, &right, &right_results)
+            }
+            DevCommand::DiffSetting { path, value, query } => {
+                let config = self.config.read().await.clone();
+                let changed = match set_config_value(&config, &path, value) {
+                    Ok(changed) => changed,
+                    Err(e) => return e,
+                };
+                let right_label = format!("{} = {}", path, get_config_value(&changed, &path).unwrap_or_default());
+
+                // Non-ranking settings are read from the shared config, so it holds the change for one search
+                *self.config.write().await = changed.clone();
+                let right_results = self.ranked(&query, &changed).await;
+                *self.config.write().await = config.clone();
+                let left_results = self.ranked(&query, &config).await;
+                render_diff("current", &left_results, &right_label, &right_results)
+            }
+            DevCommand::Help => DEV_HELP.to_string(),
+            DevCommand::Quit => String::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use falcommand_config::{Action, Category};
+
+    #[test]
+    fn lines_parse_into_commands() {
+        let parse = |line: &str| parse_command(line).unwrap();
+        assert_eq!(parse("   "), None);
+        assert_eq!(parse("firefox"), Some(DevCommand::Query("firefox".to_string())));
+        // `q` runs queries that start with a command word
+        assert_eq!(parse("q set alarm"), Some(DevCommand::Query("set alarm".to_string())));
+        assert_eq!(parse("q"), Some(DevCommand::Query("q".to_string())));
+        assert_eq!(parse(" reload "), Some(DevCommand::ReloadPlugins));
+        assert_eq!(parse("exit"), Some(DevCommand::Quit));
+        assert_eq!(parse("get ranking.fuzzy_weight"), Some(DevCommand::Get("ranking.fuzzy_weight".to_string())));
+
+        // Values are JSON when they parse as JSON and strings otherwise
+        assert_eq!(parse("set ranking.usage_weight 0.4"), Some(DevCommand::Set { path: "ranking.usage_weight".to_string(), value: json!(0.4) }));
+        assert_eq!(parse("set appearance.theme dark mode"), Some(DevCommand::Set { path: "appearance.theme".to_string(), value: json!("dark mode") }));
+        assert_eq!(parse("set search.exclude_patterns [\"*.tmp\"]"), Some(DevCommand::Set { path: "search.exclude_patterns".to_string(), value: json!(["*.tmp"]) }));
+
+        assert_eq!(parse("diff fire | fox "), Some(DevCommand::DiffQueries("fire".to_string(), "fox".to_string())));
+        assert_eq!(parse("diff-set ranking.usage_weight 0 fire fox"), Some(DevCommand::DiffSetting {
+            path: "ranking.usage_weight".to_string(),
+            value: json!(0),
+            query: "fire fox".to_string(),
+        }));
+    }
+
+    #[test]
+    fn incomplete_commands_explain_their_usage() {
+        for (line, usage) in [
+            ("set ranking.usage_weight", "Usage: set <path> <value>"),
+            ("get", "Usage: get <path>"),
+            ("diff fire", "Usage: diff <query> | <query>"),
+            ("diff fire |  ", "Usage: diff <query> | <query>"),
+            ("diff-set ranking.usage_weight 0.4", "Usage: diff-set <path> <value> <query>"),
+        ] {
+            assert_eq!(parse_command(line), Err(usage.to_string()), "{:?}", line);
+        }
+    }
+
+    fn ranked(entries: &[(&str, f64)]) -> Vec<(SearchResult, ScoreBreakdown)> {
+        entries.iter()
+            .map(|&(title, total)| {
+                let result = SearchResult::new(title, "")
+                    .with_action(Action::OpenFile(format!("/apps/{}", title).into()))
+                    .with_category(Category::Application);
+                (result, ScoreBreakdown { total, ..ScoreBreakdown::default() })
+            })
+            .collect()
+    }
+
+    // The cells of a rendered line, split at the two-space column gaps
+    fn cells(line: &str) -> Vec<&str> {
+        line.split("  ").map(str::trim).filter(|cell| !cell.is_empty()).collect()
+    }
+
+    #[test]
+    fn diffs_show_movement_and_dropped_results() {
+        let left = ranked(&[("Firefox", 0.9), ("Firewall", 0.7), ("Fish", 0.5)]);
+        let right = ranked(&[("Firewall", 0.8), ("Firefox", 0.75), ("Flameshot", 0.6), ("Files", 0.4)]);
+        let diff = render_diff("fire", &left, "fire (usage_weight 0.4)", &right);
+        let lines: Vec<&str> = diff.lines().collect();
+
+        assert_eq!(cells(lines[0]), ["#", "fire", "fire (usage_weight 0.4)", "move"]);
+        assert_eq!(cells(lines[1]), ["1", "0.900 Firefox", "0.800 Firewall", "^1"]);
+        assert_eq!(cells(lines[2]), ["2", "0.700 Firewall", "0.750 Firefox", "v1"]);
+        assert_eq!(cells(lines[3]), ["3", "0.500 Fish", "0.600 Flameshot", "new"]);
+        // The left column is blank where its list ran out
+        assert_eq!(cells(lines[4]), ["4", "0.400 Files", "new"]);
+        assert_eq!(lines[5], "dropped: Fish");
+        assert_eq!(lines.len(), 6);
+
+        // Columns line up however long the titles are
+        let right_column = 3 + 2 + 36 + 2;
+        assert!(lines[1..5].iter().all(|line| line.chars().nth(right_column - 1) == Some(' ')));
+        assert!(lines[4][right_column..].starts_with("0.400 Files"));
+    }
+
+    #[test]
+    fn identical_lists_diff_without_movement() {
+        let results = ranked(&[("Visual Studio Code - Insiders Edition (Preview)", 0.9), ("Vim", 0.5)]);
+        let diff = render_diff("v", &results, "v", &results);
+        let lines: Vec<&str> = diff.lines().collect();
+
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1..].iter().all(|line| line.ends_with('=')));
+        // Long titles are cut to the column width
+        assert_eq!(cells(lines[1])[1], "0.900 Visual Studio Code - Insiders…");
+        assert_eq!(cells(lines[1])[1].chars().count(), 36);
+        assert_eq!(render_results(&[]), "(no results)\n");
+    }
+}
@@ -1,11 +1,18 @@
-use log::{info, error};
-use tokio;
+use std::sync::Arc;
+use log::{info, warn, error};
 use anyhow::Result;
 
 // Import from separated crates
-use falcommand_config::Config;
-use falcommand_platform::{create_platform_provider};
-use crate::app::App;
+use falcommand_config::{Config, Palette, PathResolver, Theme};
+use falcommand_config::schema::{config_markdown, config_schema};
+use falcommand_platform::{create_platform_provider, PlatformProvider};
+use falcommand_core::{
+    CrashReporter, InstanceChannel, InstanceError, InstanceMessage, SafeModeSource, StartupGuard, SystemClock, UsageStore,
+    IndexManager, IndexStats, ExtractionWorker, DoctorContext, CheckStatus, PowerPolicy, TrayMode, export_usage_records, run_doctor,
+    SoakOptions, TaskSnapshot, DEFAULT_QUERY_CORPUS, TASK_SNAPSHOT_FILE, load_query_corpus, PageReply, WebClient, create_secret_store,
+};
+use crate::app::{App, RunMode};
+use crate::cli::{CliArgs, CliCommand};
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -13,26 +20,282 @@ async fn main() -> Result<()> {
     env_logger::init();
     info!("FalCommand starting...");
 
-    // 設定を読み込み
-    let config = match Config::load_default().await {
-        Ok(config) => config,
-        Err(e) => {
-            error!("Failed to load configuration: {}", e);
-            return Err(e.into());
+    // コマンドライン引数を解析
+    let args = CliArgs::parse(std::env::args().skip(1));
+
+    // 設定を読み込み（プロファイルを含む）
+    let resolver = PathResolver::detect(args.portable, args.profile)?;
+    if resolver.is_portable() {
+        info!("Running in portable mode from {:?}", resolver.config_dir().parent());
+    }
+
+    // サブコマンドはランチャーを起動せずに実行して終了する
+    if let Some(command) = args.command {
+        let (config, resolver) = Config::load_profile(resolver).await?;
+        return run_command(command, config, resolver).await;
+    }
+
+    // ジャンプリストからの起動は実行中のインスタンスに転送して終了する
+    if let Some(id) = args.execute_id {
+        match InstanceChannel::new(&resolver).forward(InstanceMessage::Execute(id)).await {
+            Ok(()) => {
+                info!("Forwarded result {} to the running instance", id);
+                return Ok(());
+            }
+            Err(InstanceError::NotRunning) => info!("No running instance; starting to execute result {}", id),
+            Err(e) => warn!("Failed to forward result {}: {}", id, e),
+        }
+    }
+
+    // 起動途中で続けてクラッシュしている場合は安全モードで起動する
+    let startup_guard = StartupGuard::new(&resolver);
+    let unfinished_startups = startup_guard.begin().unwrap_or_else(|e| {
+        error!("Failed to record startup: {}", e);
+        0
+    });
+    let auto_safe_mode = !args.safe_mode && StartupGuard::needs_safe_mode(unfinished_startups);
+    let safe_mode = args.safe_mode || auto_safe_mode;
+
+    let (config, resolver) = if safe_mode {
+        warn!("Starting in safe mode");
+        (Config::load_safe_mode(&resolver).await, resolver)
+    } else {
+        match Config::load_profile(resolver).await {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                error!("Failed to load configuration: {}", e);
+                return Err(e.into());
+            }
         }
     };
 
+    // 表示言語と数値・日付の書式を設定から決める
+    falcommand_config::i18n::init_language(config.appearance.language.as_deref());
+
+    // クラッシュレポーターを初期化
+    let crash_reporter = CrashReporter::new(env!("CARGO_PKG_VERSION"), &config, &resolver);
+    crash_reporter.install_panic_hook();
+
     // プラットフォーム固有のプロバイダーを初期化
     let platform_provider = create_platform_provider();
 
+    // 前回のクラッシュレポートを通知
+    report_previous_crashes(&crash_reporter, &config, platform_provider.as_ref());
+    if auto_safe_mode {
+        let message = format!("FalCommand crashed during its last {} startups and started in safe mode", unfinished_startups);
+        if let Err(e) = platform_provider.show_notification("FalCommand", &message) {
+            error!("Failed to show safe mode notification: {}", e);
+        }
+    }
+
     // アプリケーションを初期化
-    let mut app = App::new(config, platform_provider).await?;
+    let mode = if args.once { RunMode::Once } else { RunMode::Resident };
+    let safe_mode = safe_mode.then(|| SafeModeSource::new(resolver.config_file(), crash_reporter.latest_report()));
+    let mut app = App::new(config, resolver, platform_provider, mode, safe_mode).await?
+        .with_startup_guard(startup_guard)
+        .with_pending_execution(args.execute_id);
 
     // アプリケーションを実行
-    app.run().await?;
+    let outcome = app.run().await?;
 
     info!("FalCommand shutting down...");
+    // --once は実行したかどうかを終了コードで返す（実行 0、閉じた場合 1）
+    if let Some(code) = mode.exit_code(outcome) {
+        std::process::exit(code);
+    }
     Ok(())
 }
 
+async fn run_command(command: CliCommand, config: Config, resolver: PathResolver) -> Result<()> {
+    match command {
+        CliCommand::StatsExport { format, output } => {
+            let usage = UsageStore::new(&resolver, Arc::new(SystemClock)).await?;
+            let exported = export_usage_records(&usage.records().await, format)?;
+
+            match output {
+                Some(path) => {
+                    tokio::fs::write(&path, exported).await?;
+                    info!("Exported usage data to {}", path.display());
+                }
+                None => print!("{}", exported),
+            }
+        }
+        CliCommand::ThemeExport { theme, output } => {
+            // A starting point for a custom theme: the whole base palette, every key filled in
+            let theme = theme.unwrap_or(config.appearance.theme);
+            let system_dark = create_platform_provider().get_system_theme() != Theme::Light;
+            let exported = serde_json::to_string_pretty(&Palette::base(&theme, system_dark))?;
+
+            match output {
+                Some(path) => {
+                    tokio::fs::write(&path, format!("{}\n", exported)).await?;
+                    info!("Exported the palette to {}", path.display());
+                }
+                None => println!("{}", exported),
+            }
+        }
+        CliCommand::ConfigSchema => {
+            println!("{:#}", config_schema());
+        }
+        CliCommand::ConfigDoc => {
+            print!("{}", config_markdown(&config_schema()));
+        }
+        CliCommand::IndexRebuild { force } => {
+            let index_manager = open_index(config, &resolver).await?;
+            let platform_provider = create_platform_provider();
+            if force {
+                index_manager.force_rebuild_index(platform_provider).await?;
+            } else {
+                index_manager.rebuild_index(platform_provider).await?;
+            }
+            print!("{}", index_status(&index_manager.get_index_stats().await));
+        }
+        CliCommand::IndexStatus => {
+            let index_manager = open_index(config, &resolver).await?;
+            print!("{}", index_status(&index_manager.get_index_stats().await));
+        }
+        CliCommand::Status => {
+            let index_manager = open_index(config.clone(), &resolver).await?;
+            let platform_provider = create_platform_provider();
+            // What a resident launcher started now would get, not just what the setting asks for
+            let tray_mode = TrayMode::select(config.appearance.enable_system_tray, platform_provider.system_tray_available());
+            let hotkey = config.behavior.hotkey.clone();
+            let power_policy = PowerPolicy::new(platform_provider, Arc::new(tokio::sync::RwLock::new(config)));
+            print!("{}", index_status(&index_manager.get_index_stats().await));
+            println!("{}", tray_mode.describe(&hotkey));
+            println!("{}", power_policy.summary().await);
+            // Background tasks live in the resident launcher; this reads what it last wrote
+            match TaskSnapshot::read(&resolver.cache_file(TASK_SNAPSHOT_FILE)).await {
+                Some(snapshot) => print!("{}", snapshot.describe(chrono::Utc::now())),
+                None => println!("Background tasks: unknown, the launcher hasn't run yet"),
+            }
+        }
+        CliCommand::Doctor => {
+            let index_manager = Arc::new(open_index(config.clone(), &resolver).await?);
+            let secret_store = create_secret_store(&resolver).await;
+            let context = DoctorContext::new(config, resolver, create_platform_provider(), index_manager, secret_store);
+            let report = run_doctor(&context).await;
+            print!("{}", report.render());
+            if report.overall() == CheckStatus::Fail {
+                std::process::exit(1);
+            }
+        }
+        CliCommand::Search { query, offset, limit, explain_ranking } => {
+            falcommand_config::i18n::init_language(config.appearance.language.as_deref());
+            let app = App::new(config, resolver, create_platform_provider(), RunMode::Once, None).await?;
+            if explain_ranking {
+                print!("{}", app.run_explain_ranking(&query).await?);
+            } else {
+                let page = (offset.is_some() || limit.is_some())
+                    .then(|| (offset.unwrap_or(0), limit.unwrap_or(App::DEFAULT_PAGE_SIZE)));
+                print!("{}", app.run_search(&query, page).await?);
+            }
+        }
+        CliCommand::Soak { duration, qps, corpus, report, thresholds } => {
+            let corpus = match corpus {
+                Some(path) => load_query_corpus(&path)?,
+                None => DEFAULT_QUERY_CORPUS.iter().map(|query| query.to_string()).collect(),
+            };
+            // Sync is part of the load whatever the profile says; the change isn't saved
+            let mut config = config;
+            config.sync.enabled = true;
+            falcommand_config::i18n::init_language(config.appearance.language.as_deref());
+            let options = SoakOptions { duration, qps, thresholds, report };
+            let app = App::new(config, resolver, create_platform_provider(), RunMode::Soak, None).await?;
+            info!("Soaking for {:?} at {} searches per second, reporting to {}", duration, qps, options.report.display());
+            let summary = app.run_soak(&options, corpus).await?;
+
+            for violation in &summary.violations {
+                println!("FAIL: {}", violation);
+            }
+            if !summary.passed {
+                std::process::exit(1);
+            }
+            println!("Soak passed: {} samples over {}s", summary.samples, summary.duration_secs);
+        }
+        CliCommand::RpcSubscribe { filter } => {
+            let mut events = InstanceChannel::new(&resolver).subscribe(&filter).await?;
+            while let Some(line) = events.next_line().await? {
+                println!("{}", line);
+            }
+        }
+        CliCommand::RpcSearch { request } => {
+            let reply = InstanceChannel::new(&resolver).search(&request).await?;
+            println!("{}", serde_json::to_string(&reply)?);
+            // Clients tell a page from a refusal by the status field; scripts by the exit code
+            if !matches!(reply, PageReply::Page(_)) {
+                std::process::exit(1);
+            }
+        }
+        CliCommand::Dev => {
+            falcommand_config::i18n::init_language(config.appearance.language.as_deref());
+            let app = App::new(config, resolver, create_platform_provider(), RunMode::Once, None).await?;
+            app.run_dev().await?;
+        }
+    }
+    Ok(())
+}
+
+// The index as the launcher left it, including which folders are backing off
+async fn open_index(config: Config, resolver: &PathResolver) -> Result<IndexManager> {
+    let index_manager = IndexManager::new(Arc::new(tokio::sync::RwLock::new(config))).await?
+        .with_cache_path(resolver.cache_file("index.json"))
+        .with_extractor(Arc::new(ExtractionWorker::new()));
+    index_manager.load_cache().await;
+    Ok(index_manager)
+}
+
+fn index_status(stats: &IndexStats) -> String {
+    let mut status = format!("Applications: {}\nFiles: {}\n", stats.app_count, stats.file_count);
+    if stats.excluded_app_count > 0 {
+        status.push_str(&format!("Excluded applications: {}\n", stats.excluded_app_count));
+    }
+    match stats.last_rebuild {
+        Some(last_rebuild) => status.push_str(&format!("Last rebuild: {}\n", chrono::DateTime::<chrono::Local>::from(last_rebuild).format("%Y-%m-%d %H:%M"))),
+        None => status.push_str("Last rebuild: never\n"),
+    }
+    if let Some(ref verification) = stats.cache_verification {
+        status.push_str(&format!("Cache: {}\n", verification.describe()));
+    }
+    for warning in &stats.app_scan_warnings {
+        status.push_str(&format!("{}\n", warning));
+    }
+    for (path, retry_at) in &stats.backed_off_dirs {
+        let retry_at = chrono::DateTime::<chrono::Local>::from(*retry_at);
+        status.push_str(&format!("Skipped after failed scans: {} (retry after {})\n", path.display(), retry_at.format("%Y-%m-%d %H:%M")));
+    }
+    status
+}
+
+fn report_previous_crashes(reporter: &CrashReporter, config: &Config, platform_provider: &dyn PlatformProvider) {
+    let reports = reporter.pending_reports();
+    let Some(latest) = reports.last() else {
+        return;
+    };
+
+    let message = format!("FalCommand crashed last time. Report saved to {}", latest.display());
+    if let Err(e) = platform_provider.show_notification("FalCommand", &message) {
+        error!("Failed to show crash notification: {}", e);
+    }
+
+    // Uploaded in the background so a slow endpoint doesn't hold up startup
+    if let (true, Some(url)) = (config.crash_reporting.upload_enabled, config.crash_reporting.upload_url.clone()) {
+        let reporter = reporter.clone();
+        tokio::spawn(async move {
+            let client = WebClient::new();
+            for report in &reports {
+                if let Err(e) = reporter.upload_report(&client, &url, report).await {
+                    error!("Failed to upload crash report {:?}: {}", report, e);
+                }
+            }
+        });
+    }
+
+    if let Err(e) = reporter.mark_notified() {
+        error!("Failed to record crash notification: {}", e);
+    }
+}
+
 mod app;
+mod cli;
+mod dev;